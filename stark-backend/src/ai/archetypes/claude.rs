@@ -44,6 +44,7 @@ impl ModelArchetype for ClaudeArchetype {
         Some(AgentResponse {
             body: content.to_string(),
             tool_call: None,
+            thinking: None,
         })
     }
 
@@ -54,6 +54,7 @@ impl ModelArchetype for KimiArchetype {
         Some(AgentResponse {
             body: content.to_string(),
             tool_call: None,
+            thinking: None,
         })
     }
 
@@ -7,6 +7,7 @@
 //! - `body`: The text response to the user
 //! - `tool_call`: Optional tool invocation with name and parameters
 
+use super::minimax::{extract_think_blocks, strip_think_blocks};
 use super::{AgentResponse, ArchetypeId, ModelArchetype, TextToolCall};
 use crate::tools::ToolDefinition;
 use regex::Regex;
@@ -138,6 +139,7 @@ impl LlamaArchetype {
                         tool_name,
                         tool_params: params,
                     }),
+                    thinking: None,
                 })
             }
             Err(e) => {
@@ -159,6 +161,7 @@ impl LlamaArchetype {
                 Some(AgentResponse {
                     body: content_str.to_string(),
                     tool_call: None,
+                    thinking: None,
                 })
             }
             "function" => {
@@ -172,6 +175,7 @@ impl LlamaArchetype {
                         tool_name: name.to_string(),
                         tool_params: params.clone(),
                     }),
+                    thinking: None,
                 })
             }
             _ => None,
@@ -285,8 +289,14 @@ impl ModelArchetype for LlamaArchetype {
     }
 
     fn parse_response(&self, content: &str) -> Option<AgentResponse> {
+        // Reasoning models (deepseek-r1, qwq) put their thinking in <think> blocks
+        // ahead of the JSON; keep it apart from the answer
+        let thinking = extract_think_blocks(content);
+        let content = &strip_think_blocks(content);
+
         // Try to extract structured JSON response
-        if let Some(response) = self.extract_json(content) {
+        if let Some(mut response) = self.extract_json(content) {
+            response.thinking = thinking;
             return Some(response);
         }
 
@@ -297,6 +307,7 @@ impl ModelArchetype for LlamaArchetype {
         Some(AgentResponse {
             body: content.trim().to_string(),
             tool_call: None,
+            thinking,
         })
     }
 
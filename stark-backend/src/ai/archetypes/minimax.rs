@@ -40,6 +40,31 @@ pub fn strip_think_blocks(content: &str) -> String {
     result.trim().to_string()
 }
 
+/// Collect the inner text of all <think>...</think> blocks.
+/// Returns None when the content has no think blocks (or they are all empty).
+pub fn extract_think_blocks(content: &str) -> Option<String> {
+    let mut parts = Vec::new();
+    let mut remaining = content;
+
+    while let Some(start) = remaining.find("<think>") {
+        let inner = &remaining[start + "<think>".len()..];
+        match inner.find("</think>") {
+            Some(end) => {
+                parts.push(inner[..end].trim());
+                remaining = &inner[end + "</think>".len()..];
+            }
+            None => {
+                // Unclosed <think> tag — everything after it is reasoning
+                parts.push(inner.trim());
+                break;
+            }
+        }
+    }
+
+    let joined = parts.into_iter().filter(|p| !p.is_empty()).collect::<Vec<_>>().join("\n\n");
+    if joined.is_empty() { None } else { Some(joined) }
+}
+
 impl ModelArchetype for MiniMaxArchetype {
     fn id(&self) -> ArchetypeId {
         ArchetypeId::MiniMax
@@ -70,6 +95,7 @@ impl ModelArchetype for MiniMaxArchetype {
         Some(AgentResponse {
             body: if cleaned.is_empty() { content.to_string() } else { cleaned },
             tool_call: None,
+            thinking: extract_think_blocks(content),
         })
     }
 
@@ -99,4 +125,29 @@ mod tests {
     fn test_empty_think_block() {
         assert_eq!(strip_think_blocks("<think></think>Hello"), "Hello");
     }
+
+    #[test]
+    fn test_extract_think_blocks() {
+        assert_eq!(
+            extract_think_blocks("<think>plan A</think>Hi<think>plan B").as_deref(),
+            Some("plan A\n\nplan B")
+        );
+        assert_eq!(extract_think_blocks("<think></think>Hello"), None);
+        assert_eq!(extract_think_blocks("Hello"), None);
+    }
+
+    #[test]
+    fn test_parse_response_separates_thinking() {
+        let parsed = MiniMaxArchetype::new()
+            .parse_response("<think>check the balance first</think>Your balance is 5 USDC.")
+            .unwrap();
+        assert_eq!(parsed.body, "Your balance is 5 USDC.");
+        assert_eq!(parsed.thinking.as_deref(), Some("check the balance first"));
+
+        let parsed = crate::ai::archetypes::llama::LlamaArchetype::new()
+            .parse_response("<think>user wants {a greeting}</think>{\"body\": \"Hi!\", \"tool_call\": null}")
+            .unwrap();
+        assert_eq!(parsed.body, "Hi!");
+        assert_eq!(parsed.thinking.as_deref(), Some("user wants {a greeting}"));
+    }
 }
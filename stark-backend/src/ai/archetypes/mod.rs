@@ -75,6 +75,9 @@ pub struct TextToolCall {
 pub struct AgentResponse {
     pub body: String,
     pub tool_call: Option<TextToolCall>,
+    /// Model reasoning split out of the raw text (e.g. <think> blocks)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<String>,
 }
 
 /// Trait defining behavior for different model archetypes
@@ -110,6 +113,13 @@ pub trait ModelArchetype: Send + Sync {
         false
     }
 
+    /// Whether the provider behind this archetype honours a forced `tool_choice`.
+    /// When false, the dispatcher falls back to prompt coaxing.
+    /// Default: native tool-calling archetypes support it.
+    fn supports_tool_choice(&self) -> bool {
+        self.uses_native_tool_calling()
+    }
+
     /// Format the follow-up message after a tool execution
     fn format_tool_followup(&self, tool_name: &str, tool_result: &str, success: bool) -> String;
 }
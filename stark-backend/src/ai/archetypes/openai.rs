@@ -43,6 +43,7 @@ impl ModelArchetype for OpenAIArchetype {
         Some(AgentResponse {
             body: content.to_string(),
             tool_call: None,
+            thinking: None,
         })
     }
 
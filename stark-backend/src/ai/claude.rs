@@ -1,7 +1,9 @@
 use crate::ai::types::{
-    AiError, AiResponse, ClaudeContentBlock, ClaudeMessage as TypedClaudeMessage,
-    ClaudeMessageContent, ClaudeTool, ThinkingLevel, ToolCall, ToolResponse,
+    AiError, AiResponse, Attachment, ClaudeContentBlock, ClaudeMessage as TypedClaudeMessage,
+    ClaudeMessageContent, ClaudeTool, SamplingParams, ThinkingLevel, ToolCall, ToolResponse,
 };
+use crate::ai::streaming::TextStream;
+use crate::ai::RetryPolicy;
 use crate::ai::{Message, MessageRole};
 use crate::gateway::events::EventBroadcaster;
 use crate::gateway::protocol::GatewayEvent;
@@ -20,6 +22,14 @@ pub struct ClaudeClient {
     model: String,
     /// Thinking budget in tokens (0 = disabled)
     thinking_budget: AtomicU32,
+    /// Sampling parameters (temperature/top_p), adjustable per request
+    sampling: Arc<std::sync::RwLock<SamplingParams>>,
+    /// Tool forced via `tool_choice` (None = any tool)
+    forced_tool: Arc<std::sync::RwLock<Option<String>>>,
+    /// Images attached to the current user message
+    attachments: Arc<std::sync::RwLock<Vec<Attachment>>>,
+    /// Retry/backoff for transient HTTP errors (honors `Retry-After`)
+    retry_policy: RetryPolicy,
     /// Optional broadcaster for emitting retry events
     broadcaster: Option<Arc<EventBroadcaster>>,
     /// Channel ID for events
@@ -34,6 +44,10 @@ impl Clone for ClaudeClient {
             endpoint: self.endpoint.clone(),
             model: self.model.clone(),
             thinking_budget: AtomicU32::new(self.thinking_budget.load(Ordering::SeqCst)),
+            sampling: self.sampling.clone(),
+            forced_tool: self.forced_tool.clone(),
+            attachments: self.attachments.clone(),
+            retry_policy: self.retry_policy,
             broadcaster: self.broadcaster.clone(),
             channel_id: self.channel_id,
         }
@@ -57,6 +71,12 @@ struct ClaudeCompletionRequest {
     system: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     thinking: Option<ThinkingConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<bool>,
 }
 
 #[derive(Debug, Serialize)]
@@ -92,6 +112,10 @@ struct ClaudeToolRequest {
     tool_choice: Option<ToolChoice>,
     #[serde(skip_serializing_if = "Option::is_none")]
     thinking: Option<ThinkingConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -113,6 +137,8 @@ struct ClaudeResponseContent {
     name: Option<String>,
     #[serde(default)]
     input: Option<Value>,
+    #[serde(default)]
+    thinking: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -149,6 +175,10 @@ impl ClaudeClient {
                 .to_string(),
             model: model.unwrap_or("claude-sonnet-4-20250514").to_string(),
             thinking_budget: AtomicU32::new(0),
+            sampling: Default::default(),
+            forced_tool: Default::default(),
+            attachments: Default::default(),
+            retry_policy: RetryPolicy::default(),
             broadcaster: None,
             channel_id: None,
         })
@@ -182,6 +212,36 @@ impl ClaudeClient {
         log::info!("Claude thinking level set to {} (budget: {} tokens)", level, budget);
     }
 
+    /// Set sampling parameters for subsequent requests (Claude accepts temperature 0..=1)
+    pub fn set_sampling(&self, params: SamplingParams) {
+        if let Ok(mut sampling) = self.sampling.write() {
+            *sampling = params.clamped(1.0);
+        }
+    }
+
+    /// Force a specific tool on subsequent requests (None = any tool)
+    pub fn set_tool_choice(&self, tool_name: Option<&str>) {
+        if let Ok(mut forced) = self.forced_tool.write() {
+            *forced = tool_name.map(str::to_string);
+        }
+    }
+
+    /// Attach images to the latest user message of subsequent tool requests
+    pub fn set_attachments(&self, attachments: Vec<Attachment>) {
+        if let Ok(mut current) = self.attachments.write() {
+            *current = attachments;
+        }
+    }
+
+    /// Sampling params for a request. Extended thinking doesn't allow
+    /// custom sampling, so none are sent while it's enabled.
+    fn request_sampling(&self) -> SamplingParams {
+        if self.get_thinking_budget() > 0 {
+            return SamplingParams::default();
+        }
+        self.sampling.read().map(|s| *s).unwrap_or_default()
+    }
+
     /// Get the current thinking budget
     pub fn get_thinking_budget(&self) -> u32 {
         self.thinking_budget.load(Ordering::SeqCst)
@@ -200,6 +260,60 @@ impl ClaudeClient {
         }
     }
 
+    /// Stream text deltas using the Messages API `stream: true` mode
+    pub async fn generate_text_stream(&self, messages: Vec<Message>) -> Result<TextStream, String> {
+        let mut system_message = None;
+        let api_messages: Vec<SimpleClaudeMessage> = messages
+            .into_iter()
+            .filter_map(|m| {
+                if m.role == MessageRole::System {
+                    system_message = Some(m.content);
+                    None
+                } else {
+                    Some(SimpleClaudeMessage {
+                        role: m.role.to_string(),
+                        content: m.content,
+                    })
+                }
+            })
+            .collect();
+
+        let sampling = self.request_sampling();
+        let request = ClaudeCompletionRequest {
+            model: self.model.clone(),
+            messages: api_messages,
+            max_tokens: 4096,
+            system: system_message,
+            thinking: self.build_thinking_config(),
+            temperature: sampling.temperature,
+            top_p: sampling.top_p,
+            stream: Some(true),
+        };
+
+        let response = self
+            .client
+            .post(&self.endpoint)
+            .headers(self.auth_headers.clone())
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| format!("Claude API streaming request failed: {}", e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            if let Ok(error_response) = serde_json::from_str::<ClaudeErrorResponse>(&error_text) {
+                return Err(format!("Claude API error: {}", error_response.error.message));
+            }
+            return Err(format!(
+                "Claude API returned error status: {}, body: {}",
+                status, error_text
+            ));
+        }
+
+        Ok(TextStream::from_sse_response(response, None, claude_text_delta))
+    }
+
     pub async fn generate_text(&self, messages: Vec<Message>) -> Result<String, String> {
         // Extract system message if present
         let mut system_message = None;
@@ -224,41 +338,46 @@ impl ClaudeClient {
             .collect();
 
         let thinking = self.build_thinking_config();
+        let sampling = self.request_sampling();
         let request = ClaudeCompletionRequest {
             model: self.model.clone(),
             messages: api_messages,
             max_tokens: 4096,
             system: system_message,
             thinking,
+            temperature: sampling.temperature,
+            top_p: sampling.top_p,
+            stream: None,
         };
 
         log::debug!("Sending request to Claude API: {:?}", request);
 
-        // Retry configuration for transient errors
-        const MAX_RETRIES: u32 = 3;
-        const BASE_DELAY_MS: u64 = 2000;
+        // Retry transient errors per this client's policy
+        let policy = self.retry_policy;
+        let mut retry_after: Option<Duration> = None;
 
         let mut last_error: Option<String> = None;
         let mut response_data_opt: Option<ClaudeCompletionResponse> = None;
 
-        for attempt in 0..=MAX_RETRIES {
+        for attempt in 0..=policy.max_retries {
             if attempt > 0 {
-                let delay_ms = BASE_DELAY_MS * (1 << (attempt - 1));
-                let wait_secs = delay_ms / 1000;
+                let delay = policy.delay_for(attempt, retry_after.take());
+                let delay_ms = delay.as_millis();
+                let wait_secs = delay.as_secs();
                 log::warn!(
                     "[CLAUDE] Retry attempt {}/{} after {}ms delay",
                     attempt,
-                    MAX_RETRIES,
+                    policy.max_retries,
                     delay_ms
                 );
                 // Emit retry event to frontend
                 self.emit_retry_event(
                     attempt,
-                    MAX_RETRIES,
+                    policy.max_retries,
                     wait_secs,
                     last_error.as_deref().unwrap_or("Unknown error"),
                 );
-                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                tokio::time::sleep(delay).await;
             }
 
             let request_result = self
@@ -273,7 +392,7 @@ impl ClaudeClient {
                 Ok(r) => r,
                 Err(e) => {
                     last_error = Some(format!("Claude API request failed: {}", e));
-                    if attempt < MAX_RETRIES {
+                    if attempt < policy.max_retries {
                         log::warn!("[CLAUDE] Request failed (attempt {}): {}, will retry", attempt + 1, e);
                         continue;
                     }
@@ -286,6 +405,7 @@ impl ClaudeClient {
             let is_retryable = matches!(status_code, 429 | 502 | 503 | 504);
 
             if !status.is_success() {
+                retry_after = RetryPolicy::retry_after(status_code, response.headers());
                 let error_text = response.text().await.unwrap_or_default();
 
                 // Check if this is a transient 402 error (payment settlement network failure)
@@ -299,7 +419,7 @@ impl ClaudeClient {
                     error_text.contains("network error")
                 );
 
-                if (is_retryable || is_transient_402) && attempt < MAX_RETRIES {
+                if (is_retryable || is_transient_402) && attempt < policy.max_retries {
                     log::warn!(
                         "[CLAUDE] Received retryable status {} (attempt {}), will retry",
                         status,
@@ -374,6 +494,9 @@ impl ClaudeClient {
                 content: ClaudeMessageContent::Text(m.content),
             })
             .collect();
+        if let Ok(attachments) = self.attachments.read() {
+            attach_images(&mut api_messages, &attachments);
+        }
 
         // Add tool messages (assistant tool_use + user tool_result pairs)
         api_messages.extend(tool_messages);
@@ -389,7 +512,17 @@ impl ClaudeClient {
             .collect();
 
         let thinking = self.build_thinking_config();
+        let sampling = self.request_sampling();
         let has_tools = !claude_tools.is_empty();
+        // Force tool use when tools are available (a specific tool if one is forced)
+        let tool_choice = if has_tools {
+            match self.forced_tool.read().ok().and_then(|f| f.clone()) {
+                Some(name) if claude_tools.iter().any(|t| t.name == name) => Some(ToolChoice::Tool { name }),
+                _ => Some(ToolChoice::Any),
+            }
+        } else {
+            None
+        };
         let request = ClaudeToolRequest {
             model: self.model.clone(),
             messages: api_messages,
@@ -400,13 +533,10 @@ impl ClaudeClient {
             } else {
                 None
             },
-            // Force tool use when tools are available
-            tool_choice: if has_tools {
-                Some(ToolChoice::Any)
-            } else {
-                None
-            },
+            tool_choice,
             thinking,
+            temperature: sampling.temperature,
+            top_p: sampling.top_p,
         };
 
         log::debug!(
@@ -414,31 +544,32 @@ impl ClaudeClient {
             serde_json::to_string_pretty(&request).unwrap_or_default()
         );
 
-        // Retry configuration for transient errors
-        const MAX_RETRIES: u32 = 3;
-        const BASE_DELAY_MS: u64 = 2000;
+        // Retry transient errors per this client's policy
+        let policy = self.retry_policy;
+        let mut retry_after: Option<Duration> = None;
 
         let mut last_error: Option<(String, Option<u16>)> = None;
         let mut response_data_opt: Option<ClaudeCompletionResponse> = None;
 
-        for attempt in 0..=MAX_RETRIES {
+        for attempt in 0..=policy.max_retries {
             if attempt > 0 {
-                let delay_ms = BASE_DELAY_MS * (1 << (attempt - 1));
-                let wait_secs = delay_ms / 1000;
+                let delay = policy.delay_for(attempt, retry_after.take());
+                let delay_ms = delay.as_millis();
+                let wait_secs = delay.as_secs();
                 log::warn!(
                     "[CLAUDE] Tool request retry attempt {}/{} after {}ms delay",
                     attempt,
-                    MAX_RETRIES,
+                    policy.max_retries,
                     delay_ms
                 );
                 // Emit retry event to frontend
                 self.emit_retry_event(
                     attempt,
-                    MAX_RETRIES,
+                    policy.max_retries,
                     wait_secs,
                     last_error.as_ref().map(|(m, _)| m.as_str()).unwrap_or("Unknown error"),
                 );
-                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                tokio::time::sleep(delay).await;
             }
 
             let request_result = self
@@ -453,7 +584,7 @@ impl ClaudeClient {
                 Ok(r) => r,
                 Err(e) => {
                     last_error = Some((format!("Claude API request failed: {}", e), None));
-                    if attempt < MAX_RETRIES {
+                    if attempt < policy.max_retries {
                         log::warn!("[CLAUDE] Tool request failed (attempt {}): {}, will retry", attempt + 1, e);
                         continue;
                     }
@@ -470,6 +601,7 @@ impl ClaudeClient {
             let is_retryable = matches!(status_code, 429 | 502 | 503 | 504);
 
             if !status.is_success() {
+                retry_after = RetryPolicy::retry_after(status_code, response.headers());
                 let error_text = response.text().await.unwrap_or_default();
 
                 // Check if this is a transient 402 error (payment settlement network failure)
@@ -483,7 +615,7 @@ impl ClaudeClient {
                     error_text.contains("network error")
                 );
 
-                if (is_retryable || is_transient_402) && attempt < MAX_RETRIES {
+                if (is_retryable || is_transient_402) && attempt < policy.max_retries {
                     log::warn!(
                         "[CLAUDE] Tool request received retryable status {} (attempt {}), will retry",
                         status,
@@ -519,6 +651,7 @@ impl ClaudeClient {
 
         // Parse the response content
         let mut text_content = String::new();
+        let mut thinking = String::new();
         let mut tool_calls = Vec::new();
 
         for content in response_data.content {
@@ -539,6 +672,15 @@ impl ClaudeClient {
                         });
                     }
                 }
+                "thinking" => {
+                    // Extended thinking: kept separate from user-visible text
+                    if let Some(text) = content.thinking {
+                        if !thinking.is_empty() {
+                            thinking.push_str("\n\n");
+                        }
+                        thinking.push_str(&text);
+                    }
+                }
                 _ => {}
             }
         }
@@ -548,6 +690,7 @@ impl ClaudeClient {
             tool_calls,
             stop_reason: response_data.stop_reason,
             x402_payment: None, // Claude doesn't use x402
+            thinking: if thinking.is_empty() { None } else { Some(thinking) },
         })
     }
 
@@ -582,3 +725,73 @@ impl ClaudeClient {
         ]
     }
 }
+
+/// Text delta of a Messages API stream event (thinking deltas are skipped)
+fn claude_text_delta(event: &Value) -> Result<Option<String>, String> {
+    match event.get("type").and_then(|t| t.as_str()) {
+        Some("content_block_delta") if event.pointer("/delta/type").and_then(|t| t.as_str()) == Some("text_delta") => {
+            Ok(event.pointer("/delta/text").and_then(|t| t.as_str()).map(String::from))
+        }
+        Some("error") => Err(format!(
+            "Claude API error: {}",
+            event.pointer("/error/message").and_then(|m| m.as_str()).unwrap_or("unknown error")
+        )),
+        _ => Ok(None),
+    }
+}
+
+/// Turn the latest user message into `[image..., text]` content blocks
+fn attach_images(api_messages: &mut [TypedClaudeMessage], attachments: &[Attachment]) {
+    let images: Vec<ClaudeContentBlock> = attachments
+        .iter()
+        .filter(|a| a.is_image())
+        .map(ClaudeContentBlock::image)
+        .collect();
+    if images.is_empty() {
+        return;
+    }
+    let Some(message) = api_messages.iter_mut().rev().find(|m| m.role == "user") else {
+        return;
+    };
+    let mut blocks = images;
+    match std::mem::replace(&mut message.content, ClaudeMessageContent::Text(String::new())) {
+        ClaudeMessageContent::Text(text) => blocks.push(ClaudeContentBlock::text(text)),
+        ClaudeMessageContent::Blocks(existing) => blocks.extend(existing),
+    }
+    message.content = ClaudeMessageContent::Blocks(blocks);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attach_images_builds_image_blocks_on_latest_user_message() {
+        let mut api_messages = vec![
+            TypedClaudeMessage::user("earlier question"),
+            TypedClaudeMessage {
+                role: "assistant".to_string(),
+                content: ClaudeMessageContent::Text("earlier answer".to_string()),
+            },
+            TypedClaudeMessage::user("what does this chart show?"),
+        ];
+        let attachments = vec![
+            Attachment::base64("image/png", "iVBORw0KGgo="),
+            Attachment::url("image/jpeg", "https://cdn.example.com/chart.jpg"),
+            Attachment::url("application/pdf", "https://cdn.example.com/report.pdf"),
+        ];
+
+        attach_images(&mut api_messages, &attachments);
+
+        assert!(matches!(api_messages[0].content, ClaudeMessageContent::Text(_)));
+        let json = serde_json::to_value(&api_messages[2]).unwrap();
+        assert_eq!(
+            json["content"],
+            serde_json::json!([
+                {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBORw0KGgo="}},
+                {"type": "image", "source": {"type": "url", "url": "https://cdn.example.com/chart.jpg"}},
+                {"type": "text", "text": "what does this chart show?"}
+            ])
+        );
+    }
+}
@@ -0,0 +1,195 @@
+//! Tolerant parsing of tool-call arguments.
+//!
+//! Weaker models sometimes emit argument strings that are almost JSON:
+//! trailing commas, single-quoted strings, unquoted keys, Python literals or a
+//! markdown code fence around the object. Before giving up on a tool call we
+//! run a small repair pass over the text; only if that still doesn't produce a
+//! JSON object is the call turned into an error the model is asked to fix.
+
+use serde_json::{json, Value};
+
+use crate::telemetry;
+use crate::text::truncate_chars;
+
+/// Marker key placed in a tool call's arguments when they could not be parsed.
+/// The dispatcher turns such calls into a tool error instead of executing them.
+pub const INVALID_ARGUMENTS_KEY: &str = "__invalid_arguments";
+
+/// Parse the raw argument string of a native tool call.
+///
+/// Valid JSON is returned as-is and an empty string means no arguments. Otherwise
+/// the text is repaired (recorded as a `tool_args_repaired` annotation); if repair
+/// fails the result carries [`INVALID_ARGUMENTS_KEY`] with the raw text and error.
+pub fn parse_tool_arguments(tool_name: &str, raw: &str) -> Value {
+    if raw.trim().is_empty() {
+        return json!({});
+    }
+    let parse_error = match serde_json::from_str::<Value>(raw) {
+        Ok(value) => return value,
+        Err(e) => e.to_string(),
+    };
+
+    let repaired = repair_json(raw);
+    match serde_json::from_str::<Value>(&repaired) {
+        Ok(value) if value.is_object() => {
+            log::warn!(
+                "[TOOL_ARGS] Repaired malformed arguments for '{}': {}",
+                tool_name,
+                truncate_chars(raw, 200)
+            );
+            telemetry::emit_annotation("tool_args_repaired", json!({
+                "tool_name": tool_name,
+                "original": truncate_chars(raw, 500),
+                "parse_error": parse_error,
+            }));
+            value
+        }
+        _ => {
+            log::warn!(
+                "[TOOL_ARGS] Could not repair arguments for '{}' ({}): {}",
+                tool_name,
+                parse_error,
+                truncate_chars(raw, 200)
+            );
+            telemetry::emit_annotation("tool_args_unparseable", json!({
+                "tool_name": tool_name,
+                "original": truncate_chars(raw, 500),
+                "parse_error": parse_error,
+            }));
+            json!({ INVALID_ARGUMENTS_KEY: { "raw": raw, "error": parse_error } })
+        }
+    }
+}
+
+/// Rewrite common near-JSON mistakes into JSON. The output is not guaranteed
+/// to be valid; callers still parse it.
+pub fn repair_json(raw: &str) -> String {
+    let chars: Vec<char> = strip_code_fence(raw.trim()).chars().collect();
+    let mut out = String::with_capacity(chars.len() + 16);
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '"' => {
+                // Copy double-quoted strings verbatim, honouring escapes
+                out.push('"');
+                i += 1;
+                while i < chars.len() {
+                    let ch = chars[i];
+                    out.push(ch);
+                    i += 1;
+                    if ch == '\\' && i < chars.len() {
+                        out.push(chars[i]);
+                        i += 1;
+                    } else if ch == '"' {
+                        break;
+                    }
+                }
+            }
+            '\'' => {
+                // Single-quoted string -> double-quoted
+                out.push('"');
+                i += 1;
+                while i < chars.len() {
+                    let ch = chars[i];
+                    i += 1;
+                    if ch == '\\' && i < chars.len() {
+                        let next = chars[i];
+                        i += 1;
+                        if next != '\'' {
+                            out.push('\\');
+                        }
+                        out.push(next);
+                    } else if ch == '\'' {
+                        break;
+                    } else if ch == '"' {
+                        out.push_str("\\\"");
+                    } else {
+                        out.push(ch);
+                    }
+                }
+                out.push('"');
+            }
+            ',' => {
+                // Drop trailing commas before a closing bracket
+                let next = chars[i + 1..].iter().find(|ch| !ch.is_whitespace());
+                if !matches!(next, Some('}') | Some(']')) {
+                    out.push(',');
+                }
+                i += 1;
+            }
+            c if c.is_alphabetic() || c == '_' || c == '$' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '$') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                let next = chars[i..].iter().find(|ch| !ch.is_whitespace());
+                if next == Some(&':') {
+                    // Unquoted object key
+                    out.push('"');
+                    out.push_str(&word);
+                    out.push('"');
+                } else {
+                    out.push_str(match word.as_str() {
+                        "True" => "true",
+                        "False" => "false",
+                        "None" => "null",
+                        other => other,
+                    });
+                }
+            }
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Remove a surrounding ```json ... ``` fence, if present
+fn strip_code_fence(s: &str) -> &str {
+    let Some(rest) = s.strip_prefix("```") else {
+        return s;
+    };
+    let rest = rest.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+    rest.strip_suffix("```").unwrap_or(rest).trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repairs_common_malformations() {
+        let cases = [
+            (r#"{"token": "ETH", "amount": 1,}"#, json!({"token": "ETH", "amount": 1})),
+            (r#"{"tasks": ["a", "b",],}"#, json!({"tasks": ["a", "b"]})),
+            (r#"{'query': 'price of ETH'}"#, json!({"query": "price of ETH"})),
+            (r#"{'message': 'say "hi" and it\'s done'}"#, json!({"message": "say \"hi\" and it's done"})),
+            (r#"{network: "base", limit: 5}"#, json!({"network": "base", "limit": 5})),
+            (r#"{"dry_run": True, "memo": None, "all": False}"#, json!({"dry_run": true, "memo": null, "all": false})),
+            ("```json\n{\"city\": \"Paris\",}\n```", json!({"city": "Paris"})),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(parse_tool_arguments("test_tool", raw), expected, "input: {}", raw);
+        }
+    }
+
+    #[test]
+    fn test_valid_and_empty_arguments_unchanged() {
+        let raw = r#"{"text": "commas, inside, strings,]", "n": 1e3}"#;
+        assert_eq!(parse_tool_arguments("t", raw), serde_json::from_str::<Value>(raw).unwrap());
+        assert_eq!(parse_tool_arguments("t", "   "), json!({}));
+    }
+
+    #[test]
+    fn test_unrepairable_arguments_are_marked_invalid() {
+        let args = parse_tool_arguments("t", r#"{"amount": 1 "token"}"#);
+        let invalid = args.get(INVALID_ARGUMENTS_KEY).expect("marked invalid");
+        assert_eq!(invalid["raw"], r#"{"amount": 1 "token"}"#);
+        assert!(invalid["error"].as_str().is_some());
+    }
+}
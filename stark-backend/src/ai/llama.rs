@@ -1,4 +1,5 @@
-use crate::ai::types::{AiResponse, ToolCall};
+use crate::ai::types::{AiResponse, SamplingParams, ToolCall};
+use crate::ai::RetryPolicy;
 use crate::ai::Message;
 use crate::gateway::events::EventBroadcaster;
 use crate::gateway::protocol::GatewayEvent;
@@ -17,6 +18,10 @@ pub struct LlamaClient {
     auth_headers: header::HeaderMap,
     endpoint: String,
     model: String,
+    /// Sampling parameters (temperature/top_p), adjustable per request
+    sampling: Arc<std::sync::RwLock<SamplingParams>>,
+    /// Retry/backoff for transient HTTP errors; a local server wants quick fixed retries
+    retry_policy: RetryPolicy,
     /// Optional broadcaster for emitting retry events
     broadcaster: Option<Arc<EventBroadcaster>>,
     /// Channel ID for events
@@ -30,6 +35,17 @@ struct OllamaChatRequest {
     stream: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<OllamaTool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<OllamaOptions>,
+}
+
+/// Ollama model options (sampling parameters go here, not top-level)
+#[derive(Debug, Serialize)]
+struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -80,6 +96,9 @@ struct OllamaResponseMessage {
     content: String,
     #[serde(default)]
     tool_calls: Option<Vec<OllamaToolCall>>,
+    /// Reasoning output from thinking-capable models
+    #[serde(default)]
+    thinking: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -102,11 +121,31 @@ impl LlamaClient {
                 .unwrap_or("http://localhost:11434/api/chat")
                 .to_string(),
             model: model.unwrap_or("llama3.3").to_string(),
+            sampling: Default::default(),
+            retry_policy: RetryPolicy::fixed(3, Duration::from_millis(500)),
             broadcaster: None,
             channel_id: None,
         })
     }
 
+    /// Set sampling parameters for subsequent requests (sent as Ollama `options`)
+    pub fn set_sampling(&self, params: SamplingParams) {
+        if let Ok(mut sampling) = self.sampling.write() {
+            *sampling = params.clamped(2.0);
+        }
+    }
+
+    fn request_options(&self) -> Option<OllamaOptions> {
+        let sampling = self.sampling.read().map(|s| *s).unwrap_or_default();
+        if sampling == SamplingParams::default() {
+            return None;
+        }
+        Some(OllamaOptions {
+            temperature: sampling.temperature,
+            top_p: sampling.top_p,
+        })
+    }
+
     /// Set the broadcaster for emitting retry events
     pub fn with_broadcaster(mut self, broadcaster: Arc<EventBroadcaster>, channel_id: i64) -> Self {
         self.broadcaster = Some(broadcaster);
@@ -143,35 +182,37 @@ impl LlamaClient {
             messages: api_messages,
             stream: false,
             tools: None,
+            options: self.request_options(),
         };
 
         log::debug!("Sending request to Ollama API: {:?}", request);
 
-        // Retry configuration for transient errors
-        const MAX_RETRIES: u32 = 3;
-        const BASE_DELAY_MS: u64 = 2000;
+        // Retry transient errors per this client's policy
+        let policy = self.retry_policy;
+        let mut retry_after: Option<Duration> = None;
 
         let mut last_error: Option<String> = None;
         let mut response_data_opt: Option<OllamaChatResponse> = None;
 
-        for attempt in 0..=MAX_RETRIES {
+        for attempt in 0..=policy.max_retries {
             if attempt > 0 {
-                let delay_ms = BASE_DELAY_MS * (1 << (attempt - 1));
-                let wait_secs = delay_ms / 1000;
+                let delay = policy.delay_for(attempt, retry_after.take());
+                let delay_ms = delay.as_millis();
+                let wait_secs = delay.as_secs();
                 log::warn!(
                     "[OLLAMA] Retry attempt {}/{} after {}ms delay",
                     attempt,
-                    MAX_RETRIES,
+                    policy.max_retries,
                     delay_ms
                 );
                 // Emit retry event to frontend
                 self.emit_retry_event(
                     attempt,
-                    MAX_RETRIES,
+                    policy.max_retries,
                     wait_secs,
                     last_error.as_deref().unwrap_or("Unknown error"),
                 );
-                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                tokio::time::sleep(delay).await;
             }
 
             let request_result = self
@@ -187,7 +228,7 @@ impl LlamaClient {
                 Ok(r) => r,
                 Err(e) => {
                     last_error = Some(format!("Ollama API request failed: {}", e));
-                    if attempt < MAX_RETRIES {
+                    if attempt < policy.max_retries {
                         log::warn!("[OLLAMA] Request failed (attempt {}): {}, will retry", attempt + 1, e);
                         continue;
                     }
@@ -200,6 +241,7 @@ impl LlamaClient {
             let is_retryable = matches!(status_code, 429 | 502 | 503 | 504);
 
             if !status.is_success() {
+                retry_after = RetryPolicy::retry_after(status_code, response.headers());
                 let error_text = response.text().await.unwrap_or_default();
 
                 // Check if this is a transient 402 error (payment settlement network failure)
@@ -213,7 +255,7 @@ impl LlamaClient {
                     error_text.contains("network error")
                 );
 
-                if (is_retryable || is_transient_402) && attempt < MAX_RETRIES {
+                if (is_retryable || is_transient_402) && attempt < policy.max_retries {
                     log::warn!(
                         "[OLLAMA] Received retryable status {} (attempt {}), will retry",
                         status,
@@ -293,6 +335,7 @@ impl LlamaClient {
             } else {
                 Some(ollama_tools)
             },
+            options: self.request_options(),
         };
 
         log::debug!(
@@ -300,31 +343,32 @@ impl LlamaClient {
             serde_json::to_string_pretty(&request).unwrap_or_default()
         );
 
-        // Retry configuration for transient errors
-        const MAX_RETRIES: u32 = 3;
-        const BASE_DELAY_MS: u64 = 2000;
+        // Retry transient errors per this client's policy
+        let policy = self.retry_policy;
+        let mut retry_after: Option<Duration> = None;
 
         let mut last_error: Option<String> = None;
         let mut response_data_opt: Option<OllamaChatResponse> = None;
 
-        for attempt in 0..=MAX_RETRIES {
+        for attempt in 0..=policy.max_retries {
             if attempt > 0 {
-                let delay_ms = BASE_DELAY_MS * (1 << (attempt - 1));
-                let wait_secs = delay_ms / 1000;
+                let delay = policy.delay_for(attempt, retry_after.take());
+                let delay_ms = delay.as_millis();
+                let wait_secs = delay.as_secs();
                 log::warn!(
                     "[OLLAMA] Tool request retry attempt {}/{} after {}ms delay",
                     attempt,
-                    MAX_RETRIES,
+                    policy.max_retries,
                     delay_ms
                 );
                 // Emit retry event to frontend
                 self.emit_retry_event(
                     attempt,
-                    MAX_RETRIES,
+                    policy.max_retries,
                     wait_secs,
                     last_error.as_deref().unwrap_or("Unknown error"),
                 );
-                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                tokio::time::sleep(delay).await;
             }
 
             let request_result = self
@@ -340,7 +384,7 @@ impl LlamaClient {
                 Ok(r) => r,
                 Err(e) => {
                     last_error = Some(format!("Ollama API request failed: {}", e));
-                    if attempt < MAX_RETRIES {
+                    if attempt < policy.max_retries {
                         log::warn!("[OLLAMA] Tool request failed (attempt {}): {}, will retry", attempt + 1, e);
                         continue;
                     }
@@ -353,6 +397,7 @@ impl LlamaClient {
             let is_retryable = matches!(status_code, 429 | 502 | 503 | 504);
 
             if !status.is_success() {
+                retry_after = RetryPolicy::retry_after(status_code, response.headers());
                 let error_text = response.text().await.unwrap_or_default();
 
                 // Check if this is a transient 402 error (payment settlement network failure)
@@ -366,7 +411,7 @@ impl LlamaClient {
                     error_text.contains("network error")
                 );
 
-                if (is_retryable || is_transient_402) && attempt < MAX_RETRIES {
+                if (is_retryable || is_transient_402) && attempt < policy.max_retries {
                     log::warn!(
                         "[OLLAMA] Tool request received retryable status {} (attempt {}), will retry",
                         status,
@@ -416,12 +461,15 @@ impl LlamaClient {
             response_data.done_reason
         };
 
-        Ok(AiResponse {
+        let mut ai_response = AiResponse {
             content: response_data.message.content,
             tool_calls,
             stop_reason,
             x402_payment: None, // Llama doesn't use x402 directly (handled by OpenAI-compatible wrapper)
-        })
+            thinking: response_data.message.thinking.filter(|t| !t.is_empty()),
+        };
+        ai_response.separate_inline_thinking();
+        Ok(ai_response)
     }
 
     /// Build tool result messages for continuing conversation after tool execution
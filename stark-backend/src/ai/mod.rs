@@ -1,18 +1,21 @@
 pub mod archetypes;
 pub mod claude;
+pub mod json_repair;
 pub mod llama;
 pub mod multi_agent;
 pub mod openai;
+pub mod retry;
 pub mod streaming;
 pub mod types;
 
 pub use claude::ClaudeClient;
 pub use llama::{LlamaClient, LlamaMessage};
 pub use openai::OpenAIClient;
+pub use retry::RetryPolicy;
 pub use archetypes::{ArchetypeId, ArchetypeRegistry, ModelArchetype};
 pub use types::{
-    AiError, AiResponse, ClaudeMessage as TypedClaudeMessage, ThinkingLevel, ToolCall,
-    ToolHistoryEntry, ToolResponse,
+    AiError, AiResponse, Attachment, ClaudeMessage as TypedClaudeMessage,
+    SamplingParams, ThinkingLevel, ToolCall, ToolHistoryEntry, ToolResponse,
 };
 
 use crate::gateway::events::EventBroadcaster;
@@ -20,6 +23,8 @@ use crate::gateway::protocol::GatewayEvent;
 use crate::models::AgentSettings;
 use crate::tools::ToolDefinition;
 use crate::x402::X402PaymentInfo;
+use futures_util::StreamExt;
+use streaming::TextStream;
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
@@ -58,6 +63,12 @@ pub struct TraceEntry {
     pub input_tool_history: Vec<ToolHistoryEntry>,
     /// INPUT: available tool definitions
     pub input_tools: Vec<String>, // just tool names to keep it readable
+    /// INPUT: tool the request forced via `tool_choice`, if any
+    pub input_tool_choice: Option<String>,
+    /// INPUT: sampling parameters set on the client for this request
+    pub input_sampling: SamplingParams,
+    /// INPUT: images attached to the latest user message
+    pub input_attachments: Vec<Attachment>,
     /// OUTPUT: the AI's response
     pub output_response: Option<AiResponse>,
     /// OUTPUT: error if the AI call failed
@@ -70,6 +81,9 @@ pub struct TraceEntry {
 pub struct MockAiClient {
     responses: Arc<Mutex<VecDeque<Result<AiResponse, AiError>>>>,
     trace: Arc<Mutex<Vec<TraceEntry>>>,
+    tool_choice: Arc<Mutex<Option<String>>>,
+    sampling: Arc<Mutex<SamplingParams>>,
+    attachments: Arc<Mutex<Vec<Attachment>>>,
 }
 
 impl MockAiClient {
@@ -78,6 +92,9 @@ impl MockAiClient {
         MockAiClient {
             responses: Arc::new(Mutex::new(VecDeque::from(responses))),
             trace: Arc::new(Mutex::new(Vec::new())),
+            tool_choice: Arc::new(Mutex::new(None)),
+            sampling: Arc::new(Mutex::new(SamplingParams::default())),
+            attachments: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
@@ -102,6 +119,9 @@ impl MockAiClient {
             input_messages: messages,
             input_tool_history: tool_history,
             input_tools: tools.iter().map(|t| t.name.clone()).collect(),
+            input_tool_choice: self.tool_choice.lock().unwrap().clone(),
+            input_sampling: *self.sampling.lock().unwrap(),
+            input_attachments: self.attachments.lock().unwrap().clone(),
             output_response: result.as_ref().ok().cloned(),
             output_error: result.as_ref().err().map(|e| e.message.clone()),
         };
@@ -154,6 +174,8 @@ impl AiClient {
         // Use settings.model if available, fall back to archetype default
         let model = settings.model.as_deref().unwrap_or_else(|| archetype.default_model());
 
+        Self::check_x402_breaker(settings)?;
+
         // Determine API key: x402 endpoints don't need one, others use secret_key
         let api_key = if is_x402_endpoint(&settings.endpoint) {
             ""  // x402 endpoints use crypto signatures, no API key needed
@@ -184,9 +206,42 @@ impl AiClient {
 
     /// Create an AI client from agent settings with WalletProvider for x402
     /// This works with both Standard mode (LocalWallet) and Flash mode (Privy)
+    ///
+    /// If the primary model's client can't be built (e.g. its x402 circuit
+    /// breaker is open), the configured fallback models are tried in order.
     pub fn from_settings_with_wallet_provider(
         settings: &AgentSettings,
         wallet_provider: Option<std::sync::Arc<dyn crate::wallet::WalletProvider>>,
+    ) -> Result<Self, String> {
+        Self::from_settings_chain(settings, wallet_provider, 0).map(|(client, _)| client)
+    }
+
+    /// Create an AI client from the first buildable entry of
+    /// `settings.model_chain()` at or after `start`.
+    ///
+    /// Returns the client together with its index in the chain.
+    pub fn from_settings_chain(
+        settings: &AgentSettings,
+        wallet_provider: Option<std::sync::Arc<dyn crate::wallet::WalletProvider>>,
+        start: usize,
+    ) -> Result<(Self, usize), String> {
+        let chain = settings.model_chain();
+        let mut first_error = None;
+        for (idx, entry) in chain.iter().enumerate().skip(start) {
+            match Self::build_with_wallet_provider(entry, wallet_provider.clone()) {
+                Ok(client) => return Ok((client, idx)),
+                Err(e) => {
+                    log::warn!("[AI] Could not create client for model '{}': {}", entry.model_label(), e);
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        Err(first_error.unwrap_or_else(|| "No models left in the fallback chain".to_string()))
+    }
+
+    fn build_with_wallet_provider(
+        settings: &AgentSettings,
+        wallet_provider: Option<std::sync::Arc<dyn crate::wallet::WalletProvider>>,
     ) -> Result<Self, String> {
         use crate::x402::is_x402_endpoint;
 
@@ -198,6 +253,8 @@ impl AiClient {
         // Use settings.model if available, fall back to archetype default
         let model = settings.model.as_deref().unwrap_or_else(|| archetype.default_model());
 
+        Self::check_x402_breaker(settings)?;
+
         // Determine API key: x402 endpoints don't need one, others use secret_key
         let api_key = if is_x402_endpoint(&settings.endpoint) {
             ""  // x402 endpoints use crypto signatures, no API key needed
@@ -226,6 +283,15 @@ impl AiClient {
         Ok(AiClient::OpenAI(client))
     }
 
+    /// Don't build a client for an x402 endpoint whose circuit breaker won't
+    /// let a request through; the breaker itself is checked per request
+    fn check_x402_breaker(settings: &AgentSettings) -> Result<(), String> {
+        if crate::x402::is_x402_endpoint(&settings.endpoint) {
+            crate::x402::circuit_breaker().is_available(&settings.endpoint)?;
+        }
+        Ok(())
+    }
+
     /// Get the archetype ID from agent settings
     pub fn infer_archetype(settings: &AgentSettings) -> ArchetypeId {
         ArchetypeId::from_str(&settings.model_archetype).unwrap_or(ArchetypeId::Kimi)
@@ -256,14 +322,7 @@ impl AiClient {
                 let (content, payment) = client.generate_text_with_payment_info(messages).await?;
                 // Emit x402 payment event if payment was made
                 if let Some(ref payment_info) = payment {
-                    broadcaster.broadcast(GatewayEvent::x402_payment(
-                        channel_id,
-                        &payment_info.amount,
-                        &payment_info.amount_formatted,
-                        &payment_info.asset,
-                        &payment_info.pay_to,
-                        payment_info.resource.as_deref(),
-                    ));
+                    Self::broadcast_x402_payment(broadcaster, channel_id, payment_info);
                 }
                 Ok((content, payment))
             }
@@ -276,6 +335,83 @@ impl AiClient {
         }
     }
 
+    /// Stream generated text as incremental deltas. OpenAI and Claude stream over
+    /// SSE; Llama (and the mock) buffer the whole response into a single delta.
+    pub async fn generate_text_stream(&self, messages: Vec<Message>) -> Result<TextStream, String> {
+        match self {
+            AiClient::OpenAI(client) => client.generate_text_stream(messages).await,
+            AiClient::Claude(client) => client.generate_text_stream(messages).await,
+            AiClient::Llama(client) => Ok(TextStream::from_text(client.generate_text(messages).await?, None)),
+            AiClient::Mock(client) => client.next_response()
+                .map(|r| TextStream::from_text(r.content, None))
+                .map_err(|e| e.message),
+        }
+    }
+
+    /// Streaming variant of `generate_text_with_events`: every delta is broadcast as
+    /// `agent.token_delta` so clients can render the reply progressively. The x402
+    /// payment (if any) is emitted and returned once the stream ends. If the stream
+    /// can't be started or breaks off, falls back to the buffered request and its
+    /// retries, unless an x402 payment was already made for it: that request is
+    /// not repeated, so it is never paid for twice.
+    pub async fn generate_text_streamed_with_events(
+        &self,
+        messages: Vec<Message>,
+        broadcaster: &Arc<EventBroadcaster>,
+        channel_id: i64,
+    ) -> Result<(String, Option<X402PaymentInfo>), String> {
+        let mut stream = match self.generate_text_stream(messages.clone()).await {
+            Ok(stream) => stream,
+            // Nothing was paid: a rejected paid request fails through the stream instead
+            Err(e) => {
+                log::warn!("[AI] Streaming unavailable ({}), falling back to a buffered request", e);
+                return self.generate_text_with_events(messages, broadcaster, channel_id).await;
+            }
+        };
+
+        let mut content = String::new();
+        let mut failure = None;
+        while let Some(delta) = stream.next().await {
+            match delta {
+                Ok(delta) => {
+                    broadcaster.broadcast(GatewayEvent::agent_token_delta(channel_id, &delta));
+                    content.push_str(&delta);
+                }
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+
+        let payment = stream.into_payment();
+        if let Some(ref payment_info) = payment {
+            Self::broadcast_x402_payment(broadcaster, channel_id, payment_info);
+        }
+        if let Some(e) = failure {
+            if payment.is_some() {
+                return Err(format!("{} (x402 payment already made, not retried)", e));
+            }
+            log::warn!("[AI] Stream failed ({}), retrying as a buffered request", e);
+            return self.generate_text_with_events(messages, broadcaster, channel_id).await;
+        }
+        if content.contains("<think>") {
+            content = archetypes::minimax::strip_think_blocks(&content);
+        }
+        Ok((content, payment))
+    }
+
+    fn broadcast_x402_payment(broadcaster: &EventBroadcaster, channel_id: i64, payment_info: &X402PaymentInfo) {
+        broadcaster.broadcast(GatewayEvent::x402_payment(
+            channel_id,
+            &payment_info.amount,
+            &payment_info.amount_formatted,
+            &payment_info.asset,
+            &payment_info.pay_to,
+            payment_info.resource.as_deref(),
+        ));
+    }
+
     /// Generate response with tool support (Claude, OpenAI, and Llama 3.1+)
     pub async fn generate_with_tools(
         &self,
@@ -321,6 +457,63 @@ impl AiClient {
         matches!(self, AiClient::Claude(_))
     }
 
+    /// Address of the wallet paying for x402 requests (OpenAI-compatible x402 endpoints only)
+    pub fn x402_wallet_address(&self) -> Option<String> {
+        match self {
+            AiClient::OpenAI(client) => client.x402_wallet_address(),
+            _ => None,
+        }
+    }
+
+    /// Set sampling parameters (temperature/top_p) for subsequent requests.
+    /// Each provider clamps them to its own valid range.
+    pub fn set_sampling(&self, params: SamplingParams) {
+        match self {
+            AiClient::Claude(client) => client.set_sampling(params),
+            AiClient::OpenAI(client) => client.set_sampling(params),
+            AiClient::Llama(client) => client.set_sampling(params),
+            AiClient::Mock(client) => *client.sampling.lock().unwrap() = params,
+        }
+    }
+
+    /// Whether the provider can force a specific tool call via `tool_choice`
+    pub fn supports_tool_choice(&self) -> bool {
+        matches!(self, AiClient::Claude(_) | AiClient::OpenAI(_) | AiClient::Mock(_))
+    }
+
+    /// Force the named tool on subsequent requests (None restores the default
+    /// "any tool" choice). Ignored by providers without `tool_choice` support.
+    pub fn set_tool_choice(&self, tool_name: Option<&str>) {
+        match self {
+            AiClient::Claude(client) => client.set_tool_choice(tool_name),
+            AiClient::OpenAI(client) => client.set_tool_choice(tool_name),
+            AiClient::Llama(_) => {}
+            AiClient::Mock(client) => {
+                *client.tool_choice.lock().unwrap() = tool_name.map(str::to_string);
+            }
+        }
+    }
+
+    /// Whether the provider/model accepts image attachments
+    pub fn supports_vision(&self) -> bool {
+        match self {
+            AiClient::Claude(_) | AiClient::Mock(_) => true,
+            AiClient::OpenAI(client) => client.supports_vision(),
+            AiClient::Llama(_) => false,
+        }
+    }
+
+    /// Attach images to the latest user message of subsequent tool requests.
+    /// Ignored by providers without vision support.
+    pub fn set_attachments(&self, attachments: Vec<Attachment>) {
+        match self {
+            AiClient::Claude(client) => client.set_attachments(attachments),
+            AiClient::OpenAI(client) => client.set_attachments(attachments),
+            AiClient::Llama(_) => {}
+            AiClient::Mock(client) => *client.attachments.lock().unwrap() = attachments,
+        }
+    }
+
     /// Set the thinking level for Claude models
     pub fn set_thinking_level(&self, level: ThinkingLevel) {
         if let AiClient::Claude(client) = self {
@@ -20,6 +20,6 @@ pub mod subagent_manager;
 pub mod tools;
 pub mod types;
 
-pub use orchestrator::{Orchestrator, ProcessResult};
+pub use orchestrator::{Orchestrator, PlanReply, ProcessResult};
 pub use subagent_manager::SubAgentManager;
 pub use types::{AgentContext, AgentMode, SubAgentContext, SubAgentStatus};
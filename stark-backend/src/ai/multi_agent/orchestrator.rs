@@ -8,6 +8,9 @@ use serde_json::Value;
 /// Maximum iterations before forcing completion
 const MAX_ITERATIONS: u32 = 100;
 
+/// Maximum characters of a pending user question carried into the next turn
+const MAX_PENDING_QUESTION_LEN: usize = 1000;
+
 /// The orchestrator manages agent context and tool processing
 pub struct Orchestrator {
     context: AgentContext,
@@ -28,8 +31,28 @@ impl Orchestrator {
         }
     }
 
-    /// Create from existing context (for resuming)
-    pub fn from_context(context: AgentContext) -> Self {
+    /// Create from existing context (for resuming).
+    ///
+    /// A persisted context may reference a subtype key that has since been
+    /// renamed or removed from the registry. Resuming with such a key would
+    /// build an empty toolset and stall the loop, so the subtype is cleared
+    /// instead, forcing the agent to reselect via `set_agent_subtype`.
+    pub fn from_context(mut context: AgentContext) -> Self {
+        if let Some(ref key) = context.subtype {
+            if !key.is_empty()
+                && types::subtype_registry_loaded()
+                && types::get_subtype_config(key).is_none()
+            {
+                log::warn!(
+                    "[ORCHESTRATOR] Persisted subtype '{}' no longer exists in the registry, clearing it to force reselection",
+                    key
+                );
+                context.subtype = None;
+            }
+        }
+        if context.waiting_for_user_context.is_some() {
+            log::info!("[ORCHESTRATOR] Resuming after a question to the user; re-anchoring it in the first prompt");
+        }
         Self { context }
     }
 
@@ -93,6 +116,19 @@ impl Orchestrator {
         self.context.active_skill = None;
     }
 
+    /// Clear a skill left active by a previous turn when resuming a session.
+    ///
+    /// Skills only last for the turn that invoked them. The skill is remembered in
+    /// `cleared_skill` so the prompt can tell the model its instructions and
+    /// skill-only tools no longer apply; build the toolset after calling this so
+    /// its `requires_tools` are no longer force-included.
+    pub fn clear_stale_skill_on_resume(&mut self) {
+        if self.context.active_skill.is_some() {
+            self.context.cleared_skill = self.context.active_skill.clone();
+            self.clear_active_skill();
+        }
+    }
+
     /// Check if the agent should have called tools but didn't
     /// Returns (error_message, warning_count) if tool calls were required but skipped
     pub fn check_tool_call_required(&mut self) -> Option<(String, u32)> {
@@ -168,12 +204,14 @@ impl Orchestrator {
     }
 
     /// Get the planner prompt using a resource manager for versioned prompt resolution.
+    /// `resource_version` selects a specific version (`None` = the active one).
     pub fn get_planner_prompt_with_resource_manager(
         &self,
         skills_text: &str,
         resource_manager: &crate::telemetry::ResourceManager,
+        resource_version: Option<&str>,
     ) -> String {
-        resource_manager.resolve_prompt("system_prompt.task_planner")
+        resource_manager.resolve_prompt_in("system_prompt.task_planner", resource_version)
             .replace("{original_request}", &self.context.original_request)
             .replace("{available_skills}", skills_text)
             .replace("{available_subtypes}", &Self::generate_subtypes_table())
@@ -184,19 +222,22 @@ impl Orchestrator {
         &self,
         resource_manager: &crate::telemetry::ResourceManager,
     ) -> String {
-        self.get_system_prompt_with_resource_manager_and_channel(resource_manager, None)
+        self.get_system_prompt_with_resource_version(resource_manager, None, None)
     }
 
-    /// Get the system prompt with channel type context (for conditional prompt sections).
-    pub fn get_system_prompt_with_resource_manager_and_channel(
+    /// Get the system prompt with channel type context (for conditional prompt sections),
+    /// from a specific resource version (`None` = the active one), e.g. a channel's pin.
+    pub fn get_system_prompt_with_resource_version(
         &self,
         resource_manager: &crate::telemetry::ResourceManager,
+        resource_version: Option<&str>,
         channel_type: Option<&str>,
     ) -> String {
         if self.context.mode == AgentMode::TaskPlanner && !self.context.planner_completed {
             return self.get_planner_prompt_with_resource_manager(
                 "No skills available.",
                 resource_manager,
+                resource_version,
             );
         }
 
@@ -207,7 +248,7 @@ impl Orchestrator {
         } else {
             "system_prompt.assistant_director"
         };
-        let base_prompt = resource_manager.resolve_prompt(prompt_key);
+        let base_prompt = resource_manager.resolve_prompt_in(prompt_key, resource_version);
         self.build_system_prompt_with_channel(&base_prompt, channel_type)
     }
 
@@ -451,13 +492,27 @@ impl Orchestrator {
         if let Some(ref skill) = self.context.active_skill {
             summary.push_str(&format!("### Active Skill: `{}`\n\n", skill.name));
             summary.push_str("Skill instructions are at the top of this prompt. Follow them.\n\n");
+        } else if let Some(ref skill) = self.context.cleared_skill {
+            summary.push_str("### Previous Skill Cleared\n\n");
+            summary.push_str(&format!(
+                "The skill `{}` from an earlier turn is no longer active and its instructions no longer apply.",
+                skill.name
+            ));
+            if !skill.requires_tools.is_empty() {
+                let tools: Vec<String> = skill.requires_tools.iter().map(|t| format!("`{}`", t)).collect();
+                summary.push_str(&format!(
+                    " Tools it brought in ({}) may no longer be available, even if earlier messages used them.",
+                    tools.join(", ")
+                ));
+            }
+            summary.push_str(" Only call tools from your current tool list, and call `use_skill` again if this request needs the skill.\n\n");
         }
 
         // Add scratchpad if not empty (truncated)
         if !self.context.scratchpad.is_empty() {
             summary.push_str("### Scratchpad\n\n");
-            if self.context.scratchpad.len() > MAX_SCRATCHPAD_LEN {
-                summary.push_str(&self.context.scratchpad[..MAX_SCRATCHPAD_LEN]);
+            if self.context.scratchpad.chars().count() > MAX_SCRATCHPAD_LEN {
+                summary.push_str(&crate::text::truncate_chars(&self.context.scratchpad, MAX_SCRATCHPAD_LEN));
                 summary.push_str("\n_(truncated)_\n\n");
             } else {
                 summary.push_str(&self.context.scratchpad);
@@ -465,10 +520,11 @@ impl Orchestrator {
             }
         }
 
-        // Add waiting for user context (if any) - this shows what tools were called before asking user
+        // Add waiting for user context (if any) - the question the user is answering
+        // and what tools were called before asking
         if let Some(ref waiting_context) = self.context.waiting_for_user_context {
-            summary.push_str("### Actions Completed Before User Question\n\n");
-            summary.push_str("**IMPORTANT**: The following actions were ALREADY completed in a previous turn. Do NOT repeat them.\n\n");
+            summary.push_str("### Resuming After User Question\n\n");
+            summary.push_str("**IMPORTANT**: Actions listed here were ALREADY completed in a previous turn. Do NOT repeat them, and do not ask the same question again.\n\n");
             summary.push_str(waiting_context);
             summary.push_str("\n\n");
         }
@@ -476,6 +532,28 @@ impl Orchestrator {
         summary
     }
 
+    /// Record the question the turn ended on (and the actions completed before it)
+    /// so the next turn's first prompt re-anchors on it when the user replies
+    pub fn set_pending_user_question(&mut self, question: &str, completed_actions: &[String]) {
+        let mut note = String::new();
+        if !question.trim().is_empty() {
+            note.push_str(&format!(
+                "You previously asked the user: \"{}\"\nTheir reply follows; continue from it.",
+                crate::text::truncate_chars(question.trim(), MAX_PENDING_QUESTION_LEN)
+            ));
+        }
+        if !completed_actions.is_empty() {
+            if !note.is_empty() {
+                note.push_str("\n\n");
+            }
+            note.push_str(&format!(
+                "Before asking the user, I already completed these actions:\n{}",
+                completed_actions.join("\n")
+            ));
+        }
+        self.context.waiting_for_user_context = (!note.is_empty()).then_some(note);
+    }
+
     /// Clear the waiting_for_user_context after it's been consumed
     pub fn clear_waiting_for_user_context(&mut self) {
         self.context.waiting_for_user_context = None;
@@ -523,6 +601,47 @@ impl Orchestrator {
         self.context.planner_completed = true;
     }
 
+    /// Whether plan approval mode is enabled for this session
+    pub fn plan_approval_enabled(&self) -> bool {
+        self.context.plan_approval
+    }
+
+    /// Whether a defined plan is waiting for the user's approval
+    pub fn awaiting_plan_approval(&self) -> bool {
+        self.context.awaiting_plan_approval
+    }
+
+    /// Pause before executing the task queue. Returns the plan to show the user.
+    pub fn request_plan_approval(&mut self) -> String {
+        self.context.awaiting_plan_approval = true;
+        let tasks = self
+            .context
+            .task_queue
+            .tasks
+            .iter()
+            .enumerate()
+            .map(|(i, t)| format!("{}. {}", i + 1, t.description))
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!(
+            "📋 Proposed plan:\n{}\n\nReply /approve to run it, /reject to discard it, or describe what to change.",
+            tasks
+        )
+    }
+
+    /// The user approved the plan; execution can start with the first task
+    pub fn approve_plan(&mut self) {
+        self.context.awaiting_plan_approval = false;
+    }
+
+    /// Drop the pending plan and go back to the planner (rejection or revision)
+    pub fn discard_plan(&mut self) {
+        self.context.awaiting_plan_approval = false;
+        self.context.task_queue = types::TaskQueue::default();
+        self.context.mode = AgentMode::TaskPlanner;
+        self.context.planner_completed = false;
+    }
+
     /// Pop the next task from the queue
     pub fn pop_next_task(&mut self) -> Option<&super::types::PlannerTask> {
         self.context.task_queue.pop_next()
@@ -575,6 +694,33 @@ impl Orchestrator {
     }
 }
 
+/// How the user answered a plan awaiting approval
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanReply {
+    /// Execute the plan as proposed
+    Approve,
+    /// Discard the plan without executing anything
+    Reject,
+    /// Anything else is treated as feedback for a revised plan
+    Revise,
+}
+
+impl PlanReply {
+    pub fn parse(text: &str) -> Self {
+        let normalized = text
+            .trim()
+            .trim_end_matches(['.', '!'])
+            .to_lowercase();
+        match normalized.as_str() {
+            "/approve" | "approve" | "approved" | "yes" | "y" | "ok" | "go" | "go ahead" | "lgtm" => {
+                PlanReply::Approve
+            }
+            "/reject" | "reject" | "no" | "n" | "cancel" | "stop" => PlanReply::Reject,
+            _ => PlanReply::Revise,
+        }
+    }
+}
+
 /// Result of processing a tool call
 #[derive(Debug)]
 pub enum ProcessResult {
@@ -587,3 +733,66 @@ pub enum ProcessResult {
     /// Error occurred
     Error(String),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_context_clears_unknown_subtype() {
+        types::load_subtype_registry(types::load_test_subtypes());
+
+        let context = AgentContext {
+            original_request: "check my balance".to_string(),
+            mode: AgentMode::Assistant,
+            planner_completed: true,
+            subtype: Some("renamed_subtype_that_does_not_exist".to_string()),
+            ..Default::default()
+        };
+        let orch = Orchestrator::from_context(context);
+        assert!(orch.current_subtype().is_none());
+        assert_eq!(orch.current_subtype_key(), "");
+        // Toolbox selection prompt is back so the agent reselects a subtype
+        assert!(orch.get_system_prompt().contains("set_agent_subtype"));
+    }
+
+    #[test]
+    fn test_plan_reply_parse() {
+        assert_eq!(PlanReply::parse("/approve"), PlanReply::Approve);
+        assert_eq!(PlanReply::parse("  Yes! "), PlanReply::Approve);
+        assert_eq!(PlanReply::parse("/reject"), PlanReply::Reject);
+        assert_eq!(PlanReply::parse("no."), PlanReply::Reject);
+        assert_eq!(PlanReply::parse("skip step 2 and use USDC instead"), PlanReply::Revise);
+    }
+
+    #[test]
+    fn test_from_context_keeps_known_subtype() {
+        types::load_subtype_registry(types::load_test_subtypes());
+
+        let known = types::default_subtype_key();
+        assert!(!known.is_empty());
+        let context = AgentContext {
+            subtype: Some(known.clone()),
+            ..Default::default()
+        };
+        let orch = Orchestrator::from_context(context);
+        assert_eq!(orch.current_subtype_key(), known);
+    }
+
+    #[test]
+    fn test_context_summary_truncates_multibyte_scratchpad() {
+        // One ASCII byte shifts every 3-byte char off the 1000-byte boundary
+        let scratchpad = format!("a{}", "日".repeat(1500));
+        let context = AgentContext {
+            original_request: "summarize".to_string(),
+            scratchpad,
+            ..Default::default()
+        };
+        let orch = Orchestrator::from_context(context);
+
+        let summary = orch.format_context_summary();
+        assert!(summary.contains("_(truncated)_"));
+        assert!(summary.contains(&format!("a{}", "日".repeat(990))));
+        assert!(!summary.contains(&"日".repeat(1000)));
+    }
+}
@@ -6,8 +6,9 @@
 //! - Isolated session creation for sub-agents
 //! - Real-time event broadcasting for sub-agent lifecycle
 
+use crate::text::truncate_chars;
 use crate::ai::archetypes::minimax::strip_think_blocks;
-use crate::ai::multi_agent::types::{SubAgentConfig, SubAgentContext, SubAgentStatus};
+use crate::ai::multi_agent::types::{SubAgentConfig, SubAgentContext, SubAgentResult, SubAgentStatus};
 use crate::ai::{AiClient, Message, MessageRole, ToolHistoryEntry};
 use crate::db::Database;
 use crate::gateway::events::EventBroadcaster;
@@ -21,7 +22,7 @@ use serde_json::json;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::sync::OnceLock;
-use tokio::sync::{oneshot, Semaphore};
+use tokio::sync::{oneshot, Notify, Semaphore};
 use tokio::time::{timeout, Duration};
 
 /// Counter for generating unique sub-agent IDs
@@ -56,6 +57,8 @@ pub struct SubAgentManager {
     active_agents: Arc<DashMap<String, SubAgentHandle>>,
     /// Last activity timestamp per subagent (updated after each tool call result)
     last_activity: Arc<DashMap<String, chrono::DateTime<chrono::Utc>>>,
+    /// Woken whenever a sub-agent reaches a terminal state (see `await_result`)
+    completion_notify: Arc<Notify>,
     /// Wallet provider for x402 payments and transaction signing
     wallet_provider: Option<Arc<dyn crate::wallet::WalletProvider>>,
     /// Skill registry for sub-agent tool context
@@ -98,6 +101,7 @@ impl SubAgentManager {
             channel_semaphores: DashMap::new(),
             active_agents: Arc::new(DashMap::new()),
             last_activity: Arc::new(DashMap::new()),
+            completion_notify: Arc::new(Notify::new()),
             config,
             wallet_provider,
             skill_registry: OnceLock::new(),
@@ -147,12 +151,52 @@ impl SubAgentManager {
             .clone()
     }
 
+    /// Maximum sub-agent nesting depth
+    pub fn max_spawn_depth(&self) -> u32 {
+        self.config.max_spawn_depth
+    }
+
+    /// Check whether a sub-agent at `depth` (0 = spawned by the main agent) may be created
+    pub fn check_spawn_depth(&self, depth: u32) -> Result<(), String> {
+        if depth >= self.config.max_spawn_depth {
+            return Err(format!(
+                "Maximum sub-agent depth ({}) reached — sub-agents at this level cannot spawn further sub-agents. \
+                 Do the work directly with your own tools instead of delegating.",
+                self.config.max_spawn_depth
+            ));
+        }
+        Ok(())
+    }
+
     /// Spawn a new sub-agent
     ///
     /// Returns the sub-agent ID immediately. The sub-agent will execute in the background.
     pub async fn spawn(&self, mut context: SubAgentContext) -> Result<String, String> {
         let subagent_id = context.id.clone();
 
+        // Refuse runaway delegation chains before anything is persisted
+        if let Err(e) = self.check_spawn_depth(context.depth) {
+            log::warn!(
+                "[SUBAGENT] Refusing to spawn '{}' at depth {} (parent: {:?}): max depth is {}",
+                subagent_id,
+                context.depth,
+                context.parent_subagent_id,
+                self.config.max_spawn_depth
+            );
+            crate::telemetry::emit_annotation("subagent_depth_exceeded", json!({
+                "subagent_id": subagent_id,
+                "parent_subagent_id": context.parent_subagent_id,
+                "depth": context.depth,
+                "max_depth": self.config.max_spawn_depth,
+            }));
+            return Err(e);
+        }
+        crate::telemetry::emit_annotation("subagent_spawn_depth", json!({
+            "subagent_id": subagent_id,
+            "parent_subagent_id": context.parent_subagent_id,
+            "depth": context.depth,
+        }));
+
         // Validate timeout
         if context.timeout_secs > self.config.max_timeout_secs {
             context.timeout_secs = self.config.max_timeout_secs;
@@ -208,6 +252,7 @@ impl SubAgentManager {
         let disk_quota = self.disk_quota.get().cloned();
         let active_agents = self.active_agents.clone();
         let last_activity = self.last_activity.clone();
+        let completion_notify = self.completion_notify.clone();
         let subagent_id_for_cleanup = subagent_id.clone();
 
         // Spawn the execution task
@@ -315,6 +360,9 @@ impl SubAgentManager {
                 );
             }
             last_activity.remove(&subagent_id_for_cleanup);
+
+            // Wake anyone blocked in await_result now that the final state is persisted
+            completion_notify.notify_waiters();
         });
 
         Ok(subagent_id)
@@ -381,6 +429,13 @@ impl SubAgentManager {
             Err(e) => return Err(format!("Failed to create AI client: {}", e)),
         };
 
+        let subtype_sampling = context
+            .agent_subtype
+            .as_deref()
+            .map(crate::ai::multi_agent::types::sampling_for_subtype)
+            .unwrap_or_default();
+        client.set_sampling(effective_settings.sampling().with_overrides(subtype_sampling));
+
         // Build the task prompt
         let mut task_prompt = context.task.clone();
         if let Some(ref additional_context) = context.context {
@@ -469,13 +524,9 @@ impl SubAgentManager {
             tool_context = tool_context.with_disk_quota(dq);
         }
 
-        // Load API keys from database so sub-agent tools can call external services
+        // Let sub-agent tools fetch API keys from the database when they call external services
         if !parent_channel_safe_mode {
-            if let Ok(keys) = db.list_api_keys() {
-                for key in keys {
-                    tool_context = tool_context.with_api_key(&key.service_name, key.api_key.clone());
-                }
-            }
+            tool_context = tool_context.with_lazy_api_keys();
         }
 
         // SECURITY: Pass safe_mode flag to tool context so memory tools sandbox to safemode/
@@ -487,9 +538,16 @@ impl SubAgentManager {
         }
 
         // Get tool configuration — enforce safe mode and read_only restrictions
-        let mut tool_config = db
-            .get_effective_tool_config(Some(context.parent_channel_id))
-            .unwrap_or_default();
+        let mut tool_config = match db.get_effective_tool_config(Some(context.parent_channel_id)) {
+            Ok(config) => config,
+            Err(e) => {
+                log::error!(
+                    "[SUBAGENT] Failed to load tool config for channel {}: {} — using restricted fallback",
+                    context.parent_channel_id, e
+                );
+                crate::tools::ToolConfig::load_failure_fallback()
+            }
+        };
 
         // SECURITY: If parent channel is in safe mode, override to safe mode config.
         // Defense-in-depth — the subagent tool shouldn't be callable in safe mode,
@@ -613,7 +671,7 @@ impl SubAgentManager {
                 // Broadcast tool_call event
                 let params_preview = {
                     let s = tool_call.arguments.to_string();
-                    if s.len() > 500 { format!("{}...", &s[..500]) } else { s }
+                    truncate_chars(&s, 500)
                 };
                 broadcaster.broadcast(GatewayEvent::subagent_tool_call(
                     context.parent_channel_id,
@@ -645,11 +703,7 @@ impl SubAgentManager {
 
                 // Broadcast tool_result event (strip <think> blocks from preview)
                 let cleaned_content = strip_think_blocks(&result.content);
-                let content_preview = if cleaned_content.len() > 500 {
-                    format!("{}...", &cleaned_content[..500])
-                } else {
-                    cleaned_content
-                };
+                let content_preview = truncate_chars(&cleaned_content, 500);
                 broadcaster.broadcast(GatewayEvent::subagent_tool_result(
                     context.parent_channel_id,
                     &context.id,
@@ -930,6 +984,69 @@ impl SubAgentManager {
         Ok(agents)
     }
 
+    /// Wait for a sub-agent to reach a terminal state and collect its result.
+    ///
+    /// Returns immediately if the sub-agent has already finished. Errors if the
+    /// ID is unknown or the sub-agent is still running once `wait` elapses; the
+    /// sub-agent itself keeps running in that case.
+    pub async fn await_result(&self, subagent_id: &str, wait: Duration) -> Result<SubAgentResult, String> {
+        let deadline = tokio::time::Instant::now() + wait;
+        loop {
+            // Register for the wakeup before checking, so a completion that lands
+            // between the check and the await isn't missed
+            let notified = self.completion_notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            let context = self
+                .get_status(subagent_id)?
+                .ok_or_else(|| format!("Sub-agent '{}' not found", subagent_id))?;
+            if context.status.is_terminal() {
+                return Ok(self.collect_result(context));
+            }
+
+            tokio::select! {
+                _ = &mut notified => {}
+                _ = tokio::time::sleep_until(deadline) => {
+                    return Err(format!(
+                        "Sub-agent '{}' is still {} after waiting {}s",
+                        subagent_id,
+                        context.status,
+                        wait.as_secs()
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Build the result summary for a finished sub-agent
+    fn collect_result(&self, context: SubAgentContext) -> SubAgentResult {
+        let tool_calls = context
+            .session_id
+            .and_then(|session_id| self.db.get_session_messages(session_id).ok())
+            .map(|messages| messages.iter().filter(|m| m.role == DbMessageRole::ToolCall).count())
+            .unwrap_or(0);
+
+        SubAgentResult {
+            success: context.status == SubAgentStatus::Completed,
+            subagent_id: context.id,
+            label: context.label,
+            status: context.status,
+            response: context.result,
+            error: context.error,
+            tool_calls,
+        }
+    }
+
+    /// Snapshot of the sub-agents currently running in this process
+    pub fn list_active(&self) -> Vec<SubAgentContext> {
+        let ids: Vec<String> = self.active_agents.iter().map(|entry| entry.key().clone()).collect();
+        ids.iter()
+            .filter_map(|id| self.get_status(id).ok().flatten())
+            .filter(|context| !context.status.is_terminal())
+            .collect()
+    }
+
     /// Cancel a running sub-agent
     pub fn cancel(&self, subagent_id: &str) -> Result<bool, String> {
         if let Some((_, handle)) = self.active_agents.remove(subagent_id) {
@@ -1039,7 +1156,7 @@ impl GatewayEvent {
                 "channel_id": channel_id,
                 "subagent_id": subagent_id,
                 "label": label,
-                "task": if task.len() > 200 { format!("{}...", &task[..200]) } else { task.to_string() },
+                "task": truncate_chars(task, 200),
                 "parent_subagent_id": parent_subagent_id,
                 "depth": depth,
                 "session_id": session_id,
@@ -1065,7 +1182,7 @@ impl GatewayEvent {
                 "channel_id": channel_id,
                 "subagent_id": subagent_id,
                 "label": label,
-                "result": if result.len() > 500 { format!("{}...", &result[..500]) } else { result.to_string() },
+                "result": truncate_chars(result, 500),
                 "parent_subagent_id": parent_subagent_id,
                 "depth": depth,
                 "session_id": session_id,
@@ -1164,3 +1281,53 @@ impl GatewayEvent {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_manager() -> (SubAgentManager, i64) {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let session = db
+            .get_or_create_chat_session("subagent", 1, "subagent:1:worker", SessionScope::Dm, None)
+            .unwrap();
+        let manager = SubAgentManager::new(
+            db,
+            Arc::new(EventBroadcaster::new()),
+            Arc::new(ToolRegistry::new()),
+        );
+        (manager, session.id)
+    }
+
+    #[tokio::test]
+    async fn test_await_result_wakes_on_completion() {
+        let (manager, session_id) = test_manager();
+        let mut context = SubAgentContext::new("worker-1".to_string(), session_id, 1, "worker".to_string(), "Do it".to_string(), 60);
+        context.mark_running(session_id);
+        manager.save_subagent(&context).unwrap();
+        for _ in 0..2 {
+            manager.db.add_session_message(session_id, DbMessageRole::ToolCall, "🔧 web_fetch", None, None, None, None).unwrap();
+        }
+        assert!(manager.await_result("worker-1", Duration::from_millis(20)).await.unwrap_err().contains("still running"));
+
+        // Finish the agent the way the spawn task does: persist, then notify
+        let db = manager.db.clone();
+        let notify = manager.completion_notify.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            context.mark_completed("All done".to_string());
+            SubAgentManager::save_subagent_direct(&db, &context).unwrap();
+            notify.notify_waiters();
+        });
+
+        let started = std::time::Instant::now();
+        let result = manager.await_result("worker-1", Duration::from_secs(30)).await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5), "woken by the notification, not the timeout");
+        assert!(result.success);
+        assert_eq!(result.status, SubAgentStatus::Completed);
+        assert_eq!(result.response.as_deref(), Some("All done"));
+        assert_eq!(result.tool_calls, 2);
+
+        assert!(manager.await_result("missing", Duration::from_millis(10)).await.unwrap_err().contains("not found"));
+    }
+}
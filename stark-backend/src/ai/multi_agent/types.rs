@@ -2,6 +2,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::ai::SamplingParams;
+use crate::tools::rpc_config::Network;
 use crate::tools::types::ToolGroup;
 
 // =====================================================
@@ -37,12 +39,45 @@ pub struct AgentSubtypeConfig {
     /// Alternative names that resolve to this subtype key (e.g. "crypto" → "finance")
     #[serde(default)]
     pub aliases: Vec<String>,
+    /// Maximum number of tasks `define_tasks` may queue (default 20)
+    #[serde(default = "default_max_tasks")]
+    pub max_tasks: u32,
+    /// Sampling temperature override (None = use agent settings)
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// Nucleus sampling override (None = use agent settings)
+    #[serde(default)]
+    pub top_p: Option<f32>,
 }
 
 fn default_max_iterations() -> u32 {
     90
 }
 
+/// Default cap on task queue size for subtypes that don't set `max_tasks`
+pub const DEFAULT_MAX_TASKS: u32 = 20;
+
+fn default_max_tasks() -> u32 {
+    DEFAULT_MAX_TASKS
+}
+
+/// Task queue cap for a subtype, falling back to `DEFAULT_MAX_TASKS`
+/// when the subtype is unknown or has no cap set.
+pub fn max_tasks_for_subtype(key: &str) -> usize {
+    get_subtype_config(key)
+        .map(|c| c.max_tasks)
+        .filter(|&n| n > 0)
+        .unwrap_or(DEFAULT_MAX_TASKS) as usize
+}
+
+/// Sampling overrides configured for a subtype (empty when the subtype is
+/// unknown or sets none).
+pub fn sampling_for_subtype(key: &str) -> SamplingParams {
+    get_subtype_config(key)
+        .map(|c| SamplingParams::new(c.temperature, c.top_p))
+        .unwrap_or_default()
+}
+
 /// Global registry of agent subtype configs, loaded at startup from DB.
 static SUBTYPE_REGISTRY: std::sync::OnceLock<parking_lot::RwLock<Vec<AgentSubtypeConfig>>> =
     std::sync::OnceLock::new();
@@ -359,6 +394,7 @@ impl TaskQueue {
         "task_fully_completed",
         "define_tasks",
         "set_agent_subtype",
+        "list_subtypes",
         "add_task",
         "ask_user",
         "spawn_subagent",
@@ -677,6 +713,11 @@ pub struct AgentContext {
     #[serde(default)]
     pub active_skill: Option<ActiveSkill>,
 
+    /// Skill left active by a previous turn and cleared when this turn resumed.
+    /// Only used to tell the model the skill is gone; never persisted.
+    #[serde(default)]
+    pub cleared_skill: Option<ActiveSkill>,
+
     /// Total actual tool calls made (excludes orchestrator tools)
     #[serde(default)]
     pub actual_tool_calls: u32,
@@ -699,10 +740,29 @@ pub struct AgentContext {
     #[serde(default)]
     pub planner_completed: bool,
 
+    /// Plan approval mode: pause after `define_tasks` until the user approves the plan
+    #[serde(default)]
+    pub plan_approval: bool,
+
+    /// A defined plan is waiting for the user's approval before execution starts
+    #[serde(default)]
+    pub awaiting_plan_approval: bool,
+
+    /// This context was saved by a mid-loop checkpoint rather than at the end of a
+    /// turn. If it is still set on load the loop never finished (crash), so the
+    /// task queue is restored and execution resumes from the checkpoint.
+    #[serde(default)]
+    pub loop_checkpoint: bool,
+
+    /// A tool result this turn contained a high-risk prompt injection; sensitive
+    /// tools are blocked until the user confirms in a new message
+    #[serde(default)]
+    pub untrusted_content_flagged: bool,
+
     /// Currently selected network from UI (e.g., "base", "polygon", "mainnet")
     /// Used as default for web3 operations unless user explicitly specifies otherwise
-    #[serde(default)]
-    pub selected_network: Option<String>,
+    #[serde(default, deserialize_with = "crate::tools::rpc_config::deserialize_network_lenient")]
+    pub selected_network: Option<Network>,
 }
 
 /// Active skill context that persists across turns
@@ -952,6 +1012,23 @@ impl SubAgentContext {
     }
 }
 
+/// Outcome of a finished sub-agent, as collected by `SubAgentManager::await_result`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubAgentResult {
+    pub subagent_id: String,
+    pub label: String,
+    /// Terminal status (completed, failed, timed_out or cancelled)
+    pub status: SubAgentStatus,
+    /// True only if the sub-agent completed normally
+    pub success: bool,
+    /// Final response text (set when completed)
+    pub response: Option<String>,
+    /// Error message (set when failed, timed out or cancelled)
+    pub error: Option<String>,
+    /// Number of tool calls the sub-agent made
+    pub tool_calls: usize,
+}
+
 /// Configuration for the sub-agent system
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SubAgentConfig {
@@ -963,6 +1040,9 @@ pub struct SubAgentConfig {
     pub default_timeout_secs: u64,
     /// Maximum timeout allowed (cannot exceed this)
     pub max_timeout_secs: u64,
+    /// Maximum nesting of sub-agents: a sub-agent at depth `d` (0 = spawned by the
+    /// main agent) may only spawn children while `d + 1 < max_spawn_depth`
+    pub max_spawn_depth: u32,
 }
 
 impl Default for SubAgentConfig {
@@ -972,6 +1052,7 @@ impl Default for SubAgentConfig {
             max_total_concurrent: 10,
             default_timeout_secs: 300,
             max_timeout_secs: 3600,
+            max_spawn_depth: crate::config::subagent_max_depth(),
         }
     }
 }
@@ -1,5 +1,8 @@
-use crate::ai::streaming::{StreamEvent, StreamSender};
-use crate::ai::types::{AiError, AiResponse, ToolCall};
+use crate::text::truncate_chars;
+use crate::ai::json_repair::parse_tool_arguments;
+use crate::ai::streaming::{StreamEvent, StreamSender, TextStream};
+use crate::ai::types::{AiError, AiResponse, Attachment, AttachmentSource, SamplingParams, ToolCall};
+use crate::ai::RetryPolicy;
 use crate::ai::Message;
 use crate::gateway::events::EventBroadcaster;
 use crate::gateway::protocol::GatewayEvent;
@@ -13,6 +16,9 @@ use serde_json::{json, Value};
 use std::sync::Arc;
 use std::time::Duration;
 
+/// OpenAI-compatible APIs accept temperatures in 0..=2
+const OPENAI_MAX_TEMPERATURE: f32 = 2.0;
+
 #[derive(Clone)]
 pub struct OpenAIClient {
     client: Client,
@@ -20,7 +26,15 @@ pub struct OpenAIClient {
     endpoint: String,
     model: Option<String>,
     max_tokens: u32,
+    /// Sampling parameters (temperature/top_p), adjustable per request
+    sampling: Arc<std::sync::RwLock<SamplingParams>>,
+    /// Tool forced via `tool_choice` (None = any tool)
+    forced_tool: Arc<std::sync::RwLock<Option<String>>>,
+    /// Images attached to the current user message
+    attachments: Arc<std::sync::RwLock<Vec<Attachment>>>,
     x402_client: Option<Arc<X402Client>>,
+    /// Retry/backoff for transient HTTP errors (honors `Retry-After`)
+    retry_policy: RetryPolicy,
     /// Optional broadcaster for emitting retry events
     broadcaster: Option<Arc<EventBroadcaster>>,
     /// Channel ID for events (set when broadcasting)
@@ -34,9 +48,13 @@ struct OpenAICompletionRequest {
     messages: Vec<OpenAIMessage>,
     max_tokens: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<OpenAITool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    tool_choice: Option<String>,
+    tool_choice: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     stream: Option<bool>,
 }
@@ -87,13 +105,50 @@ struct OpenAIStreamUsage {
 pub struct OpenAIMessage {
     pub role: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub content: Option<String>,
+    pub content: Option<OpenAIContent>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<OpenAIToolCall>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_call_id: Option<String>,
 }
 
+/// Message content: plain text, or multimodal parts when images are attached
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum OpenAIContent {
+    Text(String),
+    Parts(Vec<OpenAIContentPart>),
+}
+
+impl From<String> for OpenAIContent {
+    fn from(text: String) -> Self {
+        OpenAIContent::Text(text)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OpenAIContentPart {
+    Text { text: String },
+    ImageUrl { image_url: OpenAIImageUrl },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OpenAIImageUrl {
+    /// Remote URL or a `data:<mime>;base64,...` URI
+    pub url: String,
+}
+
+impl OpenAIContentPart {
+    fn image(attachment: &Attachment) -> Self {
+        let url = match &attachment.source {
+            AttachmentSource::Url { url } => url.clone(),
+            AttachmentSource::Base64 { data } => format!("data:{};base64,{}", attachment.mime_type, data),
+        };
+        OpenAIContentPart::ImageUrl { image_url: OpenAIImageUrl { url } }
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 struct OpenAITool {
     #[serde(rename = "type")]
@@ -137,6 +192,9 @@ struct OpenAIChoice {
 struct OpenAIResponseMessage {
     content: Option<String>,
     tool_calls: Option<Vec<OpenAIToolCall>>,
+    /// Reasoning text from OpenAI-compatible reasoning models (DeepSeek, Kimi, etc.)
+    #[serde(default)]
+    reasoning_content: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -230,7 +288,11 @@ impl OpenAIClient {
             endpoint: endpoint_url,
             model: effective_model,
             max_tokens: max_tokens.unwrap_or(40096),
+            sampling: Default::default(),
+            forced_tool: Default::default(),
+            attachments: Default::default(),
             x402_client,
+            retry_policy: RetryPolicy::default(),
             broadcaster: None,
             channel_id: None,
         })
@@ -306,12 +368,68 @@ impl OpenAIClient {
             endpoint: endpoint_url,
             model: model_name,
             max_tokens: max_tokens.unwrap_or(40000),
+            sampling: Default::default(),
+            forced_tool: Default::default(),
+            attachments: Default::default(),
             x402_client,
+            retry_policy: RetryPolicy::default(),
             broadcaster: None,
             channel_id: None,
         })
     }
 
+    /// Set sampling parameters for subsequent requests (clamped to OpenAI's ranges)
+    pub fn set_sampling(&self, params: SamplingParams) {
+        if let Ok(mut sampling) = self.sampling.write() {
+            *sampling = params.clamped(OPENAI_MAX_TEMPERATURE);
+        }
+    }
+
+    fn sampling(&self) -> SamplingParams {
+        self.sampling.read().map(|s| *s).unwrap_or_default()
+    }
+
+    /// Force a specific tool on subsequent requests (None = any tool)
+    pub fn set_tool_choice(&self, tool_name: Option<&str>) {
+        if let Ok(mut forced) = self.forced_tool.write() {
+            *forced = tool_name.map(str::to_string);
+        }
+    }
+
+    /// Attach images to the latest user message of subsequent tool requests
+    pub fn set_attachments(&self, attachments: Vec<Attachment>) {
+        if let Ok(mut current) = self.attachments.write() {
+            *current = attachments;
+        }
+    }
+
+    /// Whether the configured model accepts image input
+    pub fn supports_vision(&self) -> bool {
+        self.model.as_deref().is_some_and(model_supports_vision)
+    }
+
+    /// `tool_choice` for a request: the forced tool if it's in the tool list,
+    /// otherwise "required" whenever tools are offered.
+    fn tool_choice(&self, tools: &[ToolDefinition]) -> Option<Value> {
+        if tools.is_empty() {
+            return None;
+        }
+        let forced = self.forced_tool.read().ok().and_then(|f| f.clone());
+        match forced {
+            Some(name) if tools.iter().any(|t| t.name == name) => Some(json!({
+                "type": "function",
+                "function": { "name": name }
+            })),
+            _ => Some(json!("required")),
+        }
+    }
+
+    /// Override how transient HTTP errors are retried
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
     /// Set the broadcaster for emitting retry events
     pub fn with_broadcaster(mut self, broadcaster: Arc<EventBroadcaster>, channel_id: i64) -> Self {
         self.broadcaster = Some(broadcaster);
@@ -339,6 +457,11 @@ impl OpenAIClient {
         Ok(response.content)
     }
 
+    /// Address of the wallet paying for x402 requests, if x402 is enabled
+    pub fn x402_wallet_address(&self) -> Option<String> {
+        self.x402_client.as_ref().map(|c| c.wallet_address())
+    }
+
     /// Generate text and return payment info if x402 payment was made
     pub async fn generate_text_with_payment_info(&self, messages: Vec<Message>) -> Result<(String, Option<X402PaymentInfo>), String> {
         let response = self.generate_with_tools_internal(messages, vec![], vec![]).await
@@ -346,6 +469,81 @@ impl OpenAIClient {
         Ok((response.content, response.x402_payment))
     }
 
+    /// Stream text deltas using the SSE `stream: true` mode.
+    /// x402 endpoints are paid up front; the payment travels with the stream.
+    pub async fn generate_text_stream(&self, messages: Vec<Message>) -> Result<TextStream, String> {
+        let sampling = self.sampling();
+        let request = OpenAICompletionRequest {
+            model: self.model.clone(),
+            messages: messages
+                .into_iter()
+                .map(|m| OpenAIMessage {
+                    role: m.role.to_string(),
+                    content: Some(m.content.into()),
+                    tool_calls: None,
+                    tool_call_id: None,
+                })
+                .collect(),
+            max_tokens: self.max_tokens,
+            temperature: sampling.temperature,
+            top_p: sampling.top_p,
+            tools: None,
+            tool_choice: None,
+            stream: Some(true),
+        };
+
+        log::info!(
+            "[OPENAI] Streaming text request to {} (x402: {})",
+            self.endpoint,
+            self.x402_client.is_some()
+        );
+
+        let (response, payment) = if let Some(ref x402) = self.x402_client {
+            crate::x402::circuit_breaker().check(&self.endpoint)?;
+            let x402_response = match x402.post_with_payment(&self.endpoint, &request).await {
+                Ok(response) => response,
+                Err(e) => {
+                    self.record_breaker_outcome(Err(None));
+                    return Err(format!("x402 request failed: {}", e));
+                }
+            };
+            let status = x402_response.response.status();
+            self.record_breaker_outcome(if status.is_success() { Ok(()) } else { Err(Some(status.as_u16())) });
+            (x402_response.response, x402_response.payment)
+        } else {
+            let response = self
+                .client
+                .post(&self.endpoint)
+                .headers(self.auth_headers.clone())
+                .json(&request)
+                .send()
+                .await
+                .map_err(|e| format!("OpenAI API streaming request failed: {}", e))?;
+            (response, None)
+        };
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            let error = match serde_json::from_str::<OpenAIErrorResponse>(&error_text) {
+                Ok(error_response) => format!("OpenAI API error: {}", error_response.error.message),
+                Err(_) => format!(
+                    "OpenAI API returned error status: {}, body: {}",
+                    status,
+                    truncate_chars(&error_text, 200)
+                ),
+            };
+            // Already paid: fail through the stream so the payment isn't lost
+            // and the caller knows not to retry
+            return match payment {
+                Some(_) => Ok(TextStream::from_error(error, payment)),
+                None => Err(error),
+            };
+        }
+
+        Ok(TextStream::from_sse_response(response, payment, openai_text_delta))
+    }
+
     pub async fn generate_with_tools(
         &self,
         messages: Vec<Message>,
@@ -355,23 +553,70 @@ impl OpenAIClient {
         self.generate_with_tools_internal(messages, tool_history, tools).await
     }
 
+    /// Send a completion request. For x402 endpoints the request goes through the
+    /// shared circuit breaker: refused while it's open, and its outcome recorded.
     async fn generate_with_tools_internal(
         &self,
         messages: Vec<Message>,
         tool_history: Vec<OpenAIMessage>,
         tools: Vec<ToolDefinition>,
+    ) -> Result<AiResponse, AiError> {
+        if self.x402_client.is_none() {
+            return self.send_completion(messages, tool_history, tools).await;
+        }
+
+        crate::x402::circuit_breaker().check(&self.endpoint).map_err(AiError::new)?;
+
+        let result = self.send_completion(messages, tool_history, tools).await;
+        self.record_breaker_outcome(result.as_ref().map(|_| ()).map_err(|e| e.status_code));
+        result
+    }
+
+    /// Record an x402 request's outcome (`Err` carries the failure's status code)
+    /// with the circuit breaker, emitting any state change
+    fn record_breaker_outcome(&self, outcome: Result<(), Option<u16>>) {
+        let breaker = crate::x402::circuit_breaker();
+        let transition = match outcome {
+            Ok(()) => breaker.record_success(&self.endpoint),
+            Err(status_code) if crate::x402::counts_as_failure(status_code) => breaker.record_failure(&self.endpoint),
+            Err(_) => {
+                breaker.record_inconclusive(&self.endpoint);
+                None
+            }
+        };
+        if let Some(status) = transition {
+            self.emit_breaker_event(&status);
+        }
+    }
+
+    /// Emit a circuit breaker state change if broadcaster is configured
+    fn emit_breaker_event(&self, status: &crate::x402::BreakerStatus) {
+        if let (Some(broadcaster), Some(channel_id)) = (&self.broadcaster, self.channel_id) {
+            broadcaster.broadcast(GatewayEvent::x402_circuit_breaker(channel_id, status));
+        }
+    }
+
+    async fn send_completion(
+        &self,
+        messages: Vec<Message>,
+        tool_history: Vec<OpenAIMessage>,
+        tools: Vec<ToolDefinition>,
     ) -> Result<AiResponse, AiError> {
         // Convert messages to OpenAI format
         let mut api_messages: Vec<OpenAIMessage> = messages
             .into_iter()
             .map(|m| OpenAIMessage {
                 role: m.role.to_string(),
-                content: Some(m.content),
+                content: Some(m.content.into()),
                 tool_calls: None,
                 tool_call_id: None,
             })
             .collect();
 
+        if let Ok(attachments) = self.attachments.read() {
+            attach_images(&mut api_messages, &attachments);
+        }
+
         // Add tool history messages (previous tool calls and results)
         api_messages.extend(tool_history);
 
@@ -415,12 +660,15 @@ impl OpenAIClient {
             )
         };
 
+        let sampling = self.sampling();
         let request = OpenAICompletionRequest {
             model: self.model.clone(),
             messages: api_messages,
             max_tokens: self.max_tokens,
+            temperature: sampling.temperature,
+            top_p: sampling.top_p,
             tools: openai_tools.clone(),
-            tool_choice: if tools.is_empty() { None } else { Some("required".to_string()) },
+            tool_choice: self.tool_choice(&tools),
             stream: None,
         };
 
@@ -437,33 +685,33 @@ impl OpenAIClient {
             serde_json::to_string_pretty(&request).unwrap_or_default()
         );
 
-        // Retry configuration for transient errors
-        const MAX_RETRIES: u32 = 3;
-        const BASE_DELAY_MS: u64 = 2000; // 2 seconds base delay
+        // Retry transient errors per this client's policy
+        let policy = self.retry_policy;
+        let mut retry_after: Option<Duration> = None;
 
         let mut last_error: Option<(String, Option<u16>)> = None;
         let mut x402_payment: Option<X402PaymentInfo> = None;
         let mut response_text: Option<String> = None;
 
-        for attempt in 0..=MAX_RETRIES {
+        for attempt in 0..=policy.max_retries {
             if attempt > 0 {
-                // Exponential backoff: 2s, 4s, 8s
-                let delay_ms = BASE_DELAY_MS * (1 << (attempt - 1));
-                let wait_secs = delay_ms / 1000;
+                let delay = policy.delay_for(attempt, retry_after.take());
+                let delay_ms = delay.as_millis();
+                let wait_secs = delay.as_secs();
                 log::warn!(
                     "[OPENAI] Retry attempt {}/{} after {}ms delay",
                     attempt,
-                    MAX_RETRIES,
+                    policy.max_retries,
                     delay_ms
                 );
                 // Emit retry event to frontend
                 self.emit_retry_event(
                     attempt,
-                    MAX_RETRIES,
+                    policy.max_retries,
                     wait_secs,
                     last_error.as_ref().map(|(m, _)| m.as_str()).unwrap_or("Unknown error"),
                 );
-                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                tokio::time::sleep(delay).await;
             }
 
             // Use x402 client if available, otherwise use regular client
@@ -490,7 +738,7 @@ impl OpenAIClient {
                 Err(e) => {
                     // Network errors are retryable
                     last_error = Some((e.clone(), None));
-                    if attempt < MAX_RETRIES {
+                    if attempt < policy.max_retries {
                         log::warn!("[OPENAI] Request failed (attempt {}): {}, will retry", attempt + 1, e);
                         continue;
                     }
@@ -505,6 +753,7 @@ impl OpenAIClient {
             let is_retryable = matches!(status_code, 429 | 502 | 503 | 504);
 
             if !status.is_success() {
+                retry_after = RetryPolicy::retry_after(status_code, response.headers());
                 let error_text = response.text().await.unwrap_or_default();
 
                 // Check if this is a transient 402 error (payment settlement network failure)
@@ -519,12 +768,12 @@ impl OpenAIClient {
                     error_text.contains("network error")
                 );
 
-                if (is_retryable || is_transient_402) && attempt < MAX_RETRIES {
+                if (is_retryable || is_transient_402) && attempt < policy.max_retries {
                     log::warn!(
                         "[OPENAI] Received retryable status {} (attempt {}), will retry: {}",
                         status,
                         attempt + 1,
-                        if error_text.len() > 200 { &error_text[..200] } else { &error_text }
+                        truncate_chars(&error_text, 200)
                     );
                     last_error = Some((format!("HTTP {}: {}", status, error_text), Some(status_code)));
                     continue;
@@ -549,11 +798,7 @@ impl OpenAIClient {
                         }
                     } else {
                         // Truncate long text errors
-                        let truncated = if error_text.len() > 200 {
-                            format!("{}...", &error_text[..200])
-                        } else {
-                            error_text.clone()
-                        };
+                        let truncated = truncate_chars(&error_text, 200);
                         format!("OpenAI API returned error status: {}, body: {}", status, truncated)
                     }
                 };
@@ -609,8 +854,7 @@ impl OpenAIClient {
                 calls
                     .iter()
                     .filter_map(|tc| {
-                        let args: Value = serde_json::from_str(&tc.function.arguments)
-                            .unwrap_or(json!({}));
+                        let args = parse_tool_arguments(&tc.function.name, &tc.function.arguments);
                         Some(ToolCall {
                             id: tc.id.clone(),
                             name: tc.function.name.clone(),
@@ -623,7 +867,7 @@ impl OpenAIClient {
 
         let is_tool_use = finish_reason.as_deref() == Some("tool_calls") || !tool_calls.is_empty();
 
-        Ok(AiResponse {
+        let mut ai_response = AiResponse {
             content,
             tool_calls,
             stop_reason: if is_tool_use {
@@ -632,7 +876,10 @@ impl OpenAIClient {
                 Some("end_turn".to_string())
             },
             x402_payment,
-        })
+            thinking: choice.message.reasoning_content.clone().filter(|r| !r.is_empty()),
+        };
+        ai_response.separate_inline_thinking();
+        Ok(ai_response)
     }
 
     /// Build tool result messages for continuing after tool execution
@@ -657,7 +904,7 @@ impl OpenAIClient {
 
         messages.push(OpenAIMessage {
             role: "assistant".to_string(),
-            content: Some("\n".to_string()), // Must be non-empty: Kimi rejects "", MiniMax/litellm rejects omitted field
+            content: Some("\n".to_string().into()), // Must be non-empty: Kimi rejects "", MiniMax/litellm rejects omitted field
             tool_calls: Some(openai_tool_calls),
             tool_call_id: None,
         });
@@ -666,7 +913,7 @@ impl OpenAIClient {
         for response in tool_responses {
             messages.push(OpenAIMessage {
                 role: "tool".to_string(),
-                content: Some(response.content.clone()),
+                content: Some(response.content.clone().into()),
                 tool_calls: None,
                 tool_call_id: Some(response.tool_call_id.clone()),
             });
@@ -691,7 +938,7 @@ impl OpenAIClient {
             .into_iter()
             .map(|m| OpenAIMessage {
                 role: m.role.to_string(),
-                content: Some(m.content),
+                content: Some(m.content.into()),
                 tool_calls: None,
                 tool_call_id: None,
             })
@@ -740,12 +987,15 @@ impl OpenAIClient {
             )
         };
 
+        let sampling = self.sampling();
         let request = OpenAICompletionRequest {
             model: self.model.clone(),
             messages: api_messages,
             max_tokens: self.max_tokens,
+            temperature: sampling.temperature,
+            top_p: sampling.top_p,
             tools: openai_tools.clone(),
-            tool_choice: if tools.is_empty() { None } else { Some("required".to_string()) },
+            tool_choice: self.tool_choice(&tools),
             stream: Some(true),
         };
 
@@ -756,32 +1006,33 @@ impl OpenAIClient {
             openai_tools.as_ref().map(|t| t.len()).unwrap_or(0),
         );
 
-        // Retry configuration for transient errors
-        const MAX_RETRIES: u32 = 3;
-        const BASE_DELAY_MS: u64 = 2000;
+        // Retry transient errors per this client's policy
+        let policy = self.retry_policy;
+        let mut retry_after: Option<Duration> = None;
 
         let mut last_error: Option<String> = None;
         let mut response_opt: Option<reqwest::Response> = None;
 
         // Note: x402 streaming not yet supported, fall back to regular client
-        for attempt in 0..=MAX_RETRIES {
+        for attempt in 0..=policy.max_retries {
             if attempt > 0 {
-                let delay_ms = BASE_DELAY_MS * (1 << (attempt - 1));
-                let wait_secs = delay_ms / 1000;
+                let delay = policy.delay_for(attempt, retry_after.take());
+                let delay_ms = delay.as_millis();
+                let wait_secs = delay.as_secs();
                 log::warn!(
                     "[OPENAI] Streaming retry attempt {}/{} after {}ms delay",
                     attempt,
-                    MAX_RETRIES,
+                    policy.max_retries,
                     delay_ms
                 );
                 // Emit retry event to frontend
                 self.emit_retry_event(
                     attempt,
-                    MAX_RETRIES,
+                    policy.max_retries,
                     wait_secs,
                     last_error.as_deref().unwrap_or("Unknown error"),
                 );
-                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                tokio::time::sleep(delay).await;
             }
 
             let request_result = self.client
@@ -795,12 +1046,12 @@ impl OpenAIClient {
                 Ok(r) => r,
                 Err(e) => {
                     last_error = Some(format!("OpenAI API streaming request failed: {}", e));
-                    if attempt < MAX_RETRIES {
+                    if attempt < policy.max_retries {
                         log::warn!("[OPENAI] Streaming request failed (attempt {}): {}, will retry", attempt + 1, e);
                         continue;
                     }
                     let _ = stream_sender.send(StreamEvent::Error {
-                        message: format!("Request failed after {} retries: {}", MAX_RETRIES, e),
+                        message: format!("Request failed after {} retries: {}", policy.max_retries, e),
                         code: None,
                     }).await;
                     return Err(last_error.unwrap());
@@ -812,6 +1063,7 @@ impl OpenAIClient {
             let is_retryable = matches!(status_code, 429 | 502 | 503 | 504);
 
             if !status.is_success() {
+                retry_after = RetryPolicy::retry_after(status_code, response.headers());
                 let error_text = response.text().await.unwrap_or_default();
 
                 // Check if this is a transient 402 error (payment settlement network failure)
@@ -825,7 +1077,7 @@ impl OpenAIClient {
                     error_text.contains("network error")
                 );
 
-                if (is_retryable || is_transient_402) && attempt < MAX_RETRIES {
+                if (is_retryable || is_transient_402) && attempt < policy.max_retries {
                     log::warn!(
                         "[OPENAI] Streaming received retryable status {} (attempt {}), will retry",
                         status,
@@ -947,7 +1199,7 @@ impl OpenAIClient {
         // Convert partial tool calls to complete ones
         for (idx, (id, name, args)) in partial_tool_calls {
             if !id.is_empty() && !name.is_empty() {
-                let arguments: Value = serde_json::from_str(&args).unwrap_or(json!({}));
+                let arguments = parse_tool_arguments(&name, &args);
 
                 let _ = stream_sender.send(StreamEvent::ToolCallComplete {
                     id: id.clone(),
@@ -977,7 +1229,7 @@ impl OpenAIClient {
 
         let is_tool_use = finish_reason.as_deref() == Some("tool_calls") || !tool_calls.is_empty();
 
-        Ok(AiResponse {
+        let mut ai_response = AiResponse {
             content,
             tool_calls,
             stop_reason: if is_tool_use {
@@ -986,6 +1238,192 @@ impl OpenAIClient {
                 Some("end_turn".to_string())
             },
             x402_payment: None, // Streaming doesn't support x402 yet
-        })
+            thinking: None,
+        };
+        ai_response.separate_inline_thinking();
+        Ok(ai_response)
+    }
+}
+
+/// Models known to accept image input (OpenAI-compatible naming)
+fn model_supports_vision(model: &str) -> bool {
+    let model = model.to_lowercase();
+    ["gpt-4o", "gpt-4.1", "gpt-4-turbo", "gpt-5", "o1", "o3", "o4", "vision", "-vl"]
+        .iter()
+        .any(|marker| model.contains(marker))
+}
+
+/// Turn the latest user message into `[image_url..., text]` content parts
+fn attach_images(api_messages: &mut [OpenAIMessage], attachments: &[Attachment]) {
+    let images: Vec<OpenAIContentPart> = attachments
+        .iter()
+        .filter(|a| a.is_image())
+        .map(OpenAIContentPart::image)
+        .collect();
+    if images.is_empty() {
+        return;
+    }
+    let Some(message) = api_messages.iter_mut().rev().find(|m| m.role == "user") else {
+        return;
+    };
+    let mut parts = images;
+    match message.content.take() {
+        Some(OpenAIContent::Text(text)) => parts.push(OpenAIContentPart::Text { text }),
+        Some(OpenAIContent::Parts(existing)) => parts.extend(existing),
+        None => {}
+    }
+    message.content = Some(OpenAIContent::Parts(parts));
+}
+
+/// Text delta of an OpenAI chat completion chunk
+fn openai_text_delta(event: &Value) -> Result<Option<String>, String> {
+    if let Some(message) = event.get("error").and_then(|e| e.get("message")).and_then(|m| m.as_str()) {
+        return Err(format!("OpenAI API error: {}", message));
+    }
+    Ok(event
+        .pointer("/choices/0/delta/content")
+        .and_then(|c| c.as_str())
+        .map(String::from))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::MessageRole;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Serve one chat completion and return the JSON body the client sent
+    async fn capture_request_body(listener: tokio::net::TcpListener) -> Value {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let body = read_request_body(&mut socket).await;
+
+        let reply = r#"{"choices":[{"message":{"role":"assistant","content":"ok"},"finish_reason":"stop"}]}"#;
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            reply.len(),
+            reply
+        );
+        socket.write_all(response.as_bytes()).await.unwrap();
+        body
+    }
+
+    async fn read_request_body(socket: &mut tokio::net::TcpStream) -> Value {
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+        let body_start = loop {
+            let n = socket.read(&mut chunk).await.unwrap();
+            buf.extend_from_slice(&chunk[..n]);
+            if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+        };
+        let headers = String::from_utf8_lossy(&buf[..body_start]).to_lowercase();
+        let content_length: usize = headers
+            .lines()
+            .find_map(|l| l.strip_prefix("content-length:"))
+            .map(|v| v.trim().parse().unwrap())
+            .unwrap_or(0);
+        while buf.len() < body_start + content_length {
+            let n = socket.read(&mut chunk).await.unwrap();
+            buf.extend_from_slice(&chunk[..n]);
+        }
+        serde_json::from_slice(&buf[body_start..body_start + content_length]).unwrap()
+    }
+
+    async fn sent_sampling(params: SamplingParams) -> Value {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/v1/chat/completions", listener.local_addr().unwrap());
+        let server = tokio::spawn(capture_request_body(listener));
+
+        let client = OpenAIClient::new("test-key", Some(&endpoint), Some("test-model")).unwrap();
+        client.set_sampling(params);
+        let content = client.generate_text(vec![Message {
+            role: MessageRole::User,
+            content: "hi".to_string(),
+        }]).await.unwrap();
+        assert_eq!(content, "ok");
+        server.await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sampling_reaches_request_payload() {
+        let settings = SamplingParams::new(Some(0.7), Some(0.9));
+        let body = sent_sampling(settings).await;
+        assert!((body["temperature"].as_f64().unwrap() - 0.7).abs() < 1e-6);
+        assert!((body["top_p"].as_f64().unwrap() - 0.9).abs() < 1e-6);
+
+        // Subtype override wins over the agent settings value
+        let body = sent_sampling(settings.with_overrides(SamplingParams::new(Some(0.1), None))).await;
+        assert!((body["temperature"].as_f64().unwrap() - 0.1).abs() < 1e-6);
+        assert!((body["top_p"].as_f64().unwrap() - 0.9).abs() < 1e-6);
+
+        // Out-of-range values are clamped; unset values are omitted
+        let body = sent_sampling(SamplingParams::new(Some(5.0), None)).await;
+        assert_eq!(body["temperature"].as_f64().unwrap(), 2.0);
+        assert!(body.get("top_p").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_attachments_sent_as_image_parts_for_vision_models() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/v1/chat/completions", listener.local_addr().unwrap());
+        let server = tokio::spawn(capture_request_body(listener));
+
+        let client = OpenAIClient::new("test-key", Some(&endpoint), Some("gpt-4o-mini")).unwrap();
+        assert!(client.supports_vision());
+        client.set_attachments(vec![Attachment::base64("image/png", "iVBORw0KGgo=")]);
+        client
+            .generate_with_tools(
+                vec![Message { role: MessageRole::User, content: "what is this?".to_string() }],
+                vec![],
+                vec![],
+            )
+            .await
+            .unwrap();
+
+        let body = server.await.unwrap();
+        assert_eq!(
+            body["messages"][0]["content"],
+            json!([
+                {"type": "image_url", "image_url": {"url": "data:image/png;base64,iVBORw0KGgo="}},
+                {"type": "text", "text": "what is this?"}
+            ])
+        );
+        assert!(!OpenAIClient::new("k", None, Some("kimi-k2")).unwrap().supports_vision());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_honors_retry_after_then_succeeds() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/v1/chat/completions", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            read_request_body(&mut socket).await;
+            let body = r#"{"error":{"message":"rate limited"}}"#;
+            let response = format!(
+                "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 2\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            drop(socket);
+            capture_request_body(listener).await
+        });
+
+        // A tiny base delay shows the wait comes from the server's Retry-After
+        let client = OpenAIClient::new("test-key", Some(&endpoint), Some("test-model"))
+            .unwrap()
+            .with_retry_policy(RetryPolicy::fixed(1, Duration::from_millis(10)));
+        let started = std::time::Instant::now();
+        let content = client.generate_text(vec![Message {
+            role: MessageRole::User,
+            content: "hi".to_string(),
+        }]).await.unwrap();
+        let elapsed = started.elapsed();
+
+        assert_eq!(content, "ok");
+        assert!(elapsed >= Duration::from_secs(2), "waited only {:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(5), "waited {:?}", elapsed);
+        server.await.unwrap();
     }
 }
@@ -0,0 +1,117 @@
+//! Per-provider retry/backoff policy for transient HTTP failures
+//!
+//! Each AI client retries 429/502/503/504 (and transient 402s) internally before
+//! surfacing an error to the dispatcher. Hosted APIs back off exponentially and
+//! honor `Retry-After`; a self-hosted endpoint prefers quick fixed retries.
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::time::Duration;
+
+/// How a client retries transient failures
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (0 = never retry)
+    pub max_retries: u32,
+    /// Delay before the first retry
+    pub base_delay: Duration,
+    /// Double the delay on each retry instead of keeping it fixed
+    pub exponential: bool,
+    /// Upper bound for any single wait, including a server's `Retry-After`
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Exponential backoff from `base_delay` (2s, 4s, 8s, ...)
+    pub fn exponential(max_retries: u32, base_delay: Duration) -> Self {
+        Self {
+            max_retries,
+            base_delay,
+            exponential: true,
+            max_delay: Duration::from_secs(60),
+        }
+    }
+
+    /// The same short delay before every retry
+    #[allow(dead_code)] // Used by the Llama client, which nothing constructs yet
+    pub fn fixed(max_retries: u32, delay: Duration) -> Self {
+        Self {
+            max_retries,
+            base_delay: delay,
+            exponential: false,
+            max_delay: Duration::from_secs(60),
+        }
+    }
+
+    /// Delay before retry number `attempt` (1-based). A server-provided
+    /// `Retry-After` takes precedence over the computed backoff.
+    pub fn delay_for(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        let delay = match retry_after {
+            Some(wait) => wait,
+            None if self.exponential => {
+                let factor = 1u32 << attempt.saturating_sub(1).min(16);
+                self.base_delay.saturating_mul(factor)
+            }
+            None => self.base_delay,
+        };
+        delay.min(self.max_delay)
+    }
+
+    /// The `Retry-After` wait requested by a 429/503 response, if any
+    pub fn retry_after(status_code: u16, headers: &HeaderMap) -> Option<Duration> {
+        if !matches!(status_code, 429 | 503) {
+            return None;
+        }
+        parse_retry_after(headers.get(RETRY_AFTER)?.to_str().ok()?)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::exponential(3, Duration::from_secs(2))
+    }
+}
+
+/// Parse a `Retry-After` value: either delay-seconds or an HTTP date
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_delay_for_backoff_and_retry_after() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay_for(1, None), Duration::from_secs(2));
+        assert_eq!(policy.delay_for(3, None), Duration::from_secs(8));
+        assert_eq!(policy.delay_for(1, Some(Duration::from_secs(5))), Duration::from_secs(5));
+        assert_eq!(policy.delay_for(1, Some(Duration::from_secs(600))), Duration::from_secs(60));
+
+        let fixed = RetryPolicy::fixed(3, Duration::from_millis(500));
+        assert_eq!(fixed.delay_for(3, None), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_retry_after_header_parsing() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("2"));
+        assert_eq!(RetryPolicy::retry_after(429, &headers), Some(Duration::from_secs(2)));
+        assert_eq!(RetryPolicy::retry_after(503, &headers), Some(Duration::from_secs(2)));
+        assert_eq!(RetryPolicy::retry_after(502, &headers), None);
+
+        // A date in the past means "retry now"
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"));
+        assert_eq!(RetryPolicy::retry_after(429, &headers), Some(Duration::ZERO));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("soon"));
+        assert_eq!(RetryPolicy::retry_after(429, &headers), None);
+    }
+}
@@ -3,10 +3,15 @@
 //! This module provides types for streaming AI responses in real-time,
 //! allowing incremental updates of both content and tool calls.
 
+use futures_util::{Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 use tokio::sync::mpsc;
 
+use crate::x402::X402PaymentInfo;
+
 /// Events emitted during streaming response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -193,6 +198,117 @@ impl StreamAccumulator {
     }
 }
 
+/// Splits a server-sent-events body into `data:` payloads.
+/// Bytes are buffered until a full line arrives, so payloads (and multi-byte
+/// characters) split across network chunks are reassembled.
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+}
+
+impl SseDecoder {
+    /// Feed a chunk; returns the `data:` payloads of every line it completed.
+    /// The `[DONE]` terminator is dropped.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+        let mut payloads = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(data) = line.trim().strip_prefix("data:") {
+                let data = data.trim();
+                if !data.is_empty() && data != "[DONE]" {
+                    payloads.push(data.to_string());
+                }
+            }
+        }
+        payloads
+    }
+}
+
+/// Incremental text deltas from `AiClient::generate_text_stream`.
+///
+/// Yields each chunk of generated text as it arrives. Any x402 payment made for
+/// the request is available from [`TextStream::into_payment`] once the stream ends.
+pub struct TextStream {
+    receiver: mpsc::Receiver<Result<String, String>>,
+    payment: Option<X402PaymentInfo>,
+}
+
+impl TextStream {
+    /// A stream that yields `text` as a single delta, for providers without streaming
+    pub fn from_text(text: String, payment: Option<X402PaymentInfo>) -> Self {
+        let (tx, receiver) = mpsc::channel(1);
+        if !text.is_empty() {
+            let _ = tx.try_send(Ok(text));
+        }
+        Self { receiver, payment }
+    }
+
+    /// A stream that fails with `message` straight away, e.g. a paid x402 request
+    /// the provider then rejected; the payment still travels with it
+    pub fn from_error(message: String, payment: Option<X402PaymentInfo>) -> Self {
+        let (tx, receiver) = mpsc::channel(1);
+        let _ = tx.try_send(Err(message));
+        Self { receiver, payment }
+    }
+
+    /// Read an SSE response body in a background task. `extract` turns each
+    /// `data:` payload into a text delta (`Ok(None)` for events without text).
+    pub fn from_sse_response(
+        response: reqwest::Response,
+        payment: Option<X402PaymentInfo>,
+        extract: fn(&Value) -> Result<Option<String>, String>,
+    ) -> Self {
+        let (tx, receiver) = mpsc::channel(64);
+        tokio::spawn(async move {
+            let mut body = response.bytes_stream();
+            let mut decoder = SseDecoder::default();
+            while let Some(chunk) = body.next().await {
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        let _ = tx.send(Err(format!("Stream read error: {}", e))).await;
+                        return;
+                    }
+                };
+                for payload in decoder.push(&chunk) {
+                    let Ok(event) = serde_json::from_str::<Value>(&payload) else {
+                        continue;
+                    };
+                    match extract(&event) {
+                        Ok(Some(delta)) if !delta.is_empty() => {
+                            if tx.send(Ok(delta)).await.is_err() {
+                                // Consumer dropped the stream
+                                return;
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            let _ = tx.send(Err(e)).await;
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+        Self { receiver, payment }
+    }
+
+    /// The x402 payment made for this request, if any
+    pub fn into_payment(self) -> Option<X402PaymentInfo> {
+        self.payment
+    }
+}
+
+impl Stream for TextStream {
+    type Item = Result<String, String>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
 /// Configuration for streaming behavior
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StreamConfig {
@@ -281,4 +397,41 @@ mod tests {
         assert!(acc.tool_calls[0].complete);
         assert_eq!(acc.tool_calls[0].name, "get_weather");
     }
+
+    #[test]
+    fn test_sse_decoder_reassembles_split_lines() {
+        let mut decoder = SseDecoder::default();
+        let body = "event: x\ndata: {\"text\": \"caf\u{e9}\"}\n\ndata: [DONE]\n".as_bytes();
+        // Split inside the multi-byte character
+        let split = body.iter().position(|b| *b == 0xc3).unwrap() + 1;
+        assert!(decoder.push(&body[..split]).is_empty());
+        assert_eq!(decoder.push(&body[split..]), vec!["{\"text\": \"caf\u{e9}\"}".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_text_stream_from_text_yields_single_delta() {
+        let mut stream = TextStream::from_text("whole answer".to_string(), None);
+        assert_eq!(stream.next().await, Some(Ok("whole answer".to_string())));
+        assert_eq!(stream.next().await, None);
+        assert!(stream.into_payment().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_text_stream_from_error_keeps_payment() {
+        let payment = X402PaymentInfo {
+            amount: "1000".to_string(),
+            amount_formatted: "0.001".to_string(),
+            asset: "USDC".to_string(),
+            pay_to: "0xpayee".to_string(),
+            resource: None,
+            tx_hash: None,
+            status: crate::x402::PaymentStatus::Confirmed,
+            timestamp: chrono::Utc::now(),
+            payer: None,
+        };
+        let mut stream = TextStream::from_error("upstream 502".to_string(), Some(payment));
+        assert_eq!(stream.next().await, Some(Err("upstream 502".to_string())));
+        assert_eq!(stream.next().await, None);
+        assert_eq!(stream.into_payment().unwrap().amount, "1000");
+    }
 }
@@ -141,6 +141,70 @@ impl std::fmt::Display for ThinkingLevel {
     }
 }
 
+/// Image attached to a user message, sent to vision-capable models
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+    /// MIME type, e.g. "image/png"
+    pub mime_type: String,
+    pub source: AttachmentSource,
+}
+
+/// Where an attachment's bytes come from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AttachmentSource {
+    /// Publicly fetchable URL
+    Url { url: String },
+    /// Base64-encoded bytes (no `data:` prefix)
+    Base64 { data: String },
+}
+
+impl Attachment {
+    pub fn url(mime_type: impl Into<String>, url: impl Into<String>) -> Self {
+        Self { mime_type: mime_type.into(), source: AttachmentSource::Url { url: url.into() } }
+    }
+
+    pub fn base64(mime_type: impl Into<String>, data: impl Into<String>) -> Self {
+        Self { mime_type: mime_type.into(), source: AttachmentSource::Base64 { data: data.into() } }
+    }
+
+    /// Only images are forwarded to models
+    pub fn is_image(&self) -> bool {
+        self.mime_type.starts_with("image/")
+    }
+}
+
+/// Sampling parameters sent with each completion request.
+/// `None` leaves the provider's default in place.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct SamplingParams {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+}
+
+impl SamplingParams {
+    pub fn new(temperature: Option<f32>, top_p: Option<f32>) -> Self {
+        Self { temperature, top_p }
+    }
+
+    /// Layer `overrides` on top of these params (e.g. a subtype over agent settings)
+    pub fn with_overrides(self, overrides: SamplingParams) -> Self {
+        Self {
+            temperature: overrides.temperature.or(self.temperature),
+            top_p: overrides.top_p.or(self.top_p),
+        }
+    }
+
+    /// Clamp to a provider's valid range: temperature to `0..=max_temperature`,
+    /// top_p to `0..=1`. Non-finite values are dropped.
+    pub fn clamped(self, max_temperature: f32) -> Self {
+        Self {
+            temperature: self.temperature.filter(|t| t.is_finite()).map(|t| t.clamp(0.0, max_temperature)),
+            top_p: self.top_p.filter(|p| p.is_finite()).map(|p| p.clamp(0.0, 1.0)),
+        }
+    }
+}
+
 /// Represents a tool call made by the AI
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolCall {
@@ -198,6 +262,49 @@ impl ToolHistoryEntry {
             tool_responses,
         }
     }
+
+    /// Whether every call has a response and every response answers a call in this entry
+    pub fn is_consistent(&self) -> bool {
+        self.tool_responses
+            .iter()
+            .all(|r| self.tool_calls.iter().any(|c| c.id == r.tool_call_id))
+            && self
+                .tool_calls
+                .iter()
+                .all(|c| self.tool_responses.iter().any(|r| r.tool_call_id == c.id))
+    }
+
+    /// Synthetic entries injected by the loop itself (overflow recovery, API error feedback)
+    fn is_system_feedback(&self) -> bool {
+        self.tool_calls.iter().any(|c| c.name == "system_feedback")
+    }
+}
+
+/// Trim tool history to the most recent `max_entries` entries, dropping any entry
+/// whose calls and responses don't pair up. Returns the number of entries removed.
+pub fn trim_tool_history(tool_history: &mut Vec<ToolHistoryEntry>, max_entries: usize) -> usize {
+    let before = tool_history.len();
+    tool_history.retain(ToolHistoryEntry::is_consistent);
+    if tool_history.len() > max_entries {
+        tool_history.drain(0..tool_history.len() - max_entries);
+    }
+    before - tool_history.len()
+}
+
+/// Realign tool history after the session was compacted underneath the loop.
+///
+/// Compaction keeps only the most recent `keep_tool_pairs` tool call/result pairs
+/// verbatim, so anything older now refers to turns that exist only in the summary.
+/// System feedback entries describe pre-compaction state and are dropped too.
+/// Returns the number of entries removed.
+pub fn align_tool_history_after_compaction(
+    tool_history: &mut Vec<ToolHistoryEntry>,
+    keep_tool_pairs: usize,
+) -> usize {
+    let before = tool_history.len();
+    tool_history.retain(|entry| !entry.is_system_feedback());
+    let removed = before - tool_history.len();
+    removed + trim_tool_history(tool_history, keep_tool_pairs)
 }
 
 /// Handle context overflow by clearing tool history and creating a recovery entry.
@@ -282,6 +389,12 @@ pub struct AiResponse {
     /// x402 payment info if a payment was made for this request
     #[serde(skip_serializing_if = "Option::is_none")]
     pub x402_payment: Option<X402PaymentInfo>,
+    /// Model reasoning (Claude thinking blocks, OpenAI-compatible reasoning_content
+    /// or <think> tags). Kept out of `content` so it never reaches the answer or
+    /// session storage; broadcast separately as `agent.thinking` and recorded in
+    /// telemetry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<String>,
 }
 
 impl AiResponse {
@@ -291,6 +404,7 @@ impl AiResponse {
             tool_calls: vec![],
             stop_reason: Some("end_turn".to_string()),
             x402_payment: None,
+            thinking: None,
         }
     }
 
@@ -300,6 +414,7 @@ impl AiResponse {
             tool_calls,
             stop_reason: Some("tool_use".to_string()),
             x402_payment: None,
+            thinking: None,
         }
     }
 
@@ -309,6 +424,30 @@ impl AiResponse {
         self
     }
 
+    /// Attach model reasoning to the response
+    pub fn with_thinking(mut self, thinking: impl Into<String>) -> Self {
+        self.thinking = Some(thinking.into());
+        self
+    }
+
+    /// Move any inline <think>...</think> blocks out of `content` into `thinking`.
+    /// Existing thinking (e.g. from native provider fields) is kept first.
+    pub fn separate_inline_thinking(&mut self) {
+        use crate::ai::archetypes::minimax::{extract_think_blocks, strip_think_blocks};
+
+        if !self.content.contains("<think>") {
+            return;
+        }
+        let inline = extract_think_blocks(&self.content);
+        self.content = strip_think_blocks(&self.content);
+        if let Some(inline) = inline {
+            self.thinking = Some(match self.thinking.take() {
+                Some(existing) if !existing.is_empty() => format!("{}\n\n{}", existing, inline),
+                _ => inline,
+            });
+        }
+    }
+
     /// Check if the response contains tool calls
     pub fn has_tool_calls(&self) -> bool {
         !self.tool_calls.is_empty()
@@ -347,6 +486,16 @@ pub enum ClaudeContentBlock {
         #[serde(skip_serializing_if = "Option::is_none")]
         is_error: Option<bool>,
     },
+    #[serde(rename = "image")]
+    Image { source: ClaudeImageSource },
+}
+
+/// Image source of a Claude `image` content block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClaudeImageSource {
+    Base64 { media_type: String, data: String },
+    Url { url: String },
 }
 
 impl ClaudeContentBlock {
@@ -354,6 +503,17 @@ impl ClaudeContentBlock {
         ClaudeContentBlock::Text { text: text.into() }
     }
 
+    pub fn image(attachment: &Attachment) -> Self {
+        let source = match &attachment.source {
+            AttachmentSource::Base64 { data } => ClaudeImageSource::Base64 {
+                media_type: attachment.mime_type.clone(),
+                data: data.clone(),
+            },
+            AttachmentSource::Url { url } => ClaudeImageSource::Url { url: url.clone() },
+        };
+        ClaudeContentBlock::Image { source }
+    }
+
     pub fn tool_result(tool_use_id: String, content: String, is_error: bool) -> Self {
         ClaudeContentBlock::ToolResult {
             tool_use_id,
@@ -442,4 +602,51 @@ mod tests {
         let error = ToolResponse::error("call_456".to_string(), "Failed".to_string());
         assert!(error.is_error);
     }
+
+    fn history_entry(id: &str, name: &str) -> ToolHistoryEntry {
+        ToolHistoryEntry::new(
+            vec![ToolCall {
+                id: id.to_string(),
+                name: name.to_string(),
+                arguments: serde_json::json!({}),
+            }],
+            vec![ToolResponse::success(id.to_string(), format!("{} done", name))],
+        )
+    }
+
+    #[test]
+    fn test_trim_tool_history_drops_unpaired_entries() {
+        let mut orphan = history_entry("call_2", "read_file");
+        orphan.tool_responses[0].tool_call_id = "call_gone".to_string();
+        let mut history = vec![
+            history_entry("call_1", "list_files"),
+            orphan,
+            history_entry("call_3", "grep"),
+            history_entry("call_4", "read_file"),
+        ];
+
+        let removed = trim_tool_history(&mut history, 2);
+
+        assert_eq!(removed, 2);
+        let ids: Vec<&str> = history.iter().map(|e| e.tool_calls[0].id.as_str()).collect();
+        assert_eq!(ids, vec!["call_3", "call_4"]);
+    }
+
+    #[test]
+    fn test_align_after_compaction_leaves_no_dangling_references() {
+        let mut history = vec![history_entry("call_1", "list_files"), history_entry("call_2", "grep")];
+        // Overflow recovery summarizes entries by id, which compaction then removes
+        let recovery = handle_context_overflow(&mut history, "3");
+        history.push(recovery);
+        history.push(history_entry("call_4", "read_file"));
+        history.push(history_entry("call_5", "edit_file"));
+
+        let removed = align_tool_history_after_compaction(&mut history, 1);
+
+        assert_eq!(removed, 2);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].tool_calls[0].id, "call_5");
+        assert!(history.iter().all(ToolHistoryEntry::is_consistent));
+        assert!(history.iter().all(|e| !e.is_system_feedback()));
+    }
 }
@@ -308,6 +308,8 @@ pub struct SkillEntry {
     /// requires_api_keys serialized as JSON string
     #[serde(default)]
     pub requires_api_keys: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_override: Option<crate::skills::SkillModelOverride>,
     pub scripts: Vec<SkillScriptEntry>,
 }
 
@@ -395,6 +397,11 @@ pub struct AgentSubtypeEntry {
     pub max_iterations: Option<u32>,
     pub skip_task_planner: Option<bool>,
     pub aliases_json: String,
+    pub max_tasks: Option<u32>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub top_p: Option<f32>,
 }
 
 /// Special role entry in backup (enriched safe mode)
@@ -661,6 +668,7 @@ pub async fn collect_backup_data(
                 subagent_type: skill.subagent_type,
                 requires_api_keys: serde_json::to_string(&skill.requires_api_keys)
                     .unwrap_or_default(),
+                model_override: skill.model_override,
                 scripts,
             });
         }
@@ -732,6 +740,9 @@ pub async fn collect_backup_data(
                 max_iterations: Some(s.max_iterations),
                 skip_task_planner: Some(s.skip_task_planner),
                 aliases_json: serde_json::to_string(&s.aliases).unwrap_or_else(|_| "[]".to_string()),
+                max_tasks: Some(s.max_tasks),
+                temperature: s.temperature,
+                top_p: s.top_p,
             })
             .collect();
     }
@@ -0,0 +1,217 @@
+//! Message features each channel type supports.
+//!
+//! Channel implementations declare what they can deliver (interactive prompts,
+//! images, markdown, message length) and register it with the
+//! `ChannelCapabilityRegistry`. The dispatcher reads the registry to gate tools
+//! and to shape the final response, instead of special-casing channel types.
+
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// What a channel can deliver to its users
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelCapabilities {
+    /// The user can answer follow-up questions (`ask_user`) in the same session
+    pub supports_interactive: bool,
+    /// Images can be sent and received
+    pub supports_images: bool,
+    /// Longest response the channel can deliver; longer ones are truncated.
+    /// `None` when the channel splits long responses itself.
+    pub max_message_len: Option<usize>,
+    /// Markdown is rendered; otherwise it is stripped from the final response
+    pub supports_markdown: bool,
+}
+
+impl Default for ChannelCapabilities {
+    /// Full-featured channel (web UI, API clients)
+    fn default() -> Self {
+        Self {
+            supports_interactive: true,
+            supports_images: true,
+            max_message_len: None,
+            supports_markdown: true,
+        }
+    }
+}
+
+impl ChannelCapabilities {
+    /// Tools that can't work on this channel
+    pub fn denied_tools(&self) -> Vec<&'static str> {
+        let mut denied = Vec::new();
+        if !self.supports_interactive {
+            denied.push("ask_user");
+        }
+        denied
+    }
+
+    /// Shape a final response for delivery: strip markdown the channel can't
+    /// render and truncate to its length limit.
+    pub fn format_response(&self, response: &str) -> String {
+        let mut text = if self.supports_markdown {
+            response.to_string()
+        } else {
+            strip_markdown(response)
+        };
+        match self.max_message_len {
+            Some(max_len) if text.chars().count() > max_len => {
+                let keep = max_len.saturating_sub(1);
+                text = text.chars().take(keep).collect::<String>().trim_end().to_string();
+                text.push('…');
+            }
+            _ => {}
+        }
+        text
+    }
+}
+
+/// Capabilities per channel type. Channel types nobody registered get the
+/// full-featured defaults.
+pub struct ChannelCapabilityRegistry {
+    by_type: DashMap<String, ChannelCapabilities>,
+}
+
+impl ChannelCapabilityRegistry {
+    /// Empty registry: every channel type gets the defaults
+    pub fn new() -> Self {
+        Self { by_type: DashMap::new() }
+    }
+
+    /// Registry with the built-in channel implementations registered
+    pub fn with_builtin_channels() -> Self {
+        let registry = Self::new();
+        registry.register("telegram", super::telegram::capabilities());
+        registry.register("slack", super::slack::capabilities());
+        registry.register("discord", super::discord::capabilities());
+        registry.register("twitter", super::twitter::capabilities());
+        registry
+    }
+
+    /// Declare (or replace) the capabilities of a channel type
+    pub fn register(&self, channel_type: &str, capabilities: ChannelCapabilities) {
+        self.by_type.insert(channel_type.to_string(), capabilities);
+    }
+
+    /// Capabilities of a channel type
+    pub fn get(&self, channel_type: &str) -> ChannelCapabilities {
+        self.by_type.get(channel_type).map(|c| *c).unwrap_or_default()
+    }
+}
+
+impl Default for ChannelCapabilityRegistry {
+    fn default() -> Self {
+        Self::with_builtin_channels()
+    }
+}
+
+/// A fenced code block (content in group 1) or an inline code span (group 2)
+static MD_CODE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?ms)^```[^\n]*\n(.*?)(?:^```[ \t]*$\n?|\z)|`([^`\n]+)`").unwrap());
+static MD_LINK: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[([^\]]+)\]\(([^)]+)\)").unwrap());
+static MD_HEADING: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^#{1,6}\s+").unwrap());
+/// Emphasis whose content doesn't start or end with whitespace, so `2 * 3 * 4` is left alone
+static MD_EMPHASIS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"\*\*([^\s*](?:[^*\n]*[^\s*])?)\*\*|__([^\s_](?:[^_\n]*[^\s_])?)__|~~([^\s~](?:[^~\n]*[^\s~])?)~~",
+        r"|\*([^\s*](?:[^*\n]*[^\s*])?)\*|_([^\s_](?:[^_\n]*[^\s_])?)_",
+    ))
+    .unwrap()
+});
+
+/// Reduce markdown to plain text: links become "text (url)", and headings and
+/// emphasis markers are dropped. Code blocks and inline code lose only their
+/// fences and ticks; their content is kept verbatim.
+fn strip_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for code in MD_CODE.captures_iter(text) {
+        let whole = code.get(0).unwrap();
+        out.push_str(&strip_prose(&text[last..whole.start()]));
+        out.push_str(code.get(1).or_else(|| code.get(2)).map_or("", |m| m.as_str()));
+        last = whole.end();
+    }
+    out.push_str(&strip_prose(&text[last..]));
+    out.trim().to_string()
+}
+
+fn strip_prose(text: &str) -> String {
+    let text = MD_LINK.replace_all(text, "$1 ($2)");
+    let text = MD_HEADING.replace_all(&text, "");
+    MD_EMPHASIS
+        .replace_all(&text, |caps: &regex::Captures| {
+            let whole = caps.get(0).unwrap();
+            let inner = (1..=5).find_map(|i| caps.get(i)).map_or("", |m| m.as_str());
+            let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+            let before = text[..whole.start()].chars().next_back();
+            let after = text[whole.end()..].chars().next();
+            // Markers inside a word (snake_case, a*b*c) and dunder names (__init__) aren't emphasis
+            let dunder = whole.as_str().starts_with("__") && inner.chars().all(|c| c.is_alphanumeric() || c == '_');
+            if is_word(before) || is_word(after) || dunder {
+                whole.as_str().to_string()
+            } else {
+                inner.to_string()
+            }
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unregistered_channel_gets_full_featured_defaults() {
+        let registry = ChannelCapabilityRegistry::with_builtin_channels();
+        let web = registry.get("web");
+        assert_eq!(web, ChannelCapabilities::default());
+        assert!(web.denied_tools().is_empty());
+        assert_eq!(web.format_response("**hi**"), "**hi**");
+    }
+
+    #[test]
+    fn test_twitter_blocks_ask_user_and_strips_markdown() {
+        let registry = ChannelCapabilityRegistry::with_builtin_channels();
+        let twitter = registry.get("twitter");
+        assert_eq!(twitter.denied_tools(), vec!["ask_user"]);
+        assert_eq!(
+            twitter.format_response("## Result\n**Swapped** 1 ETH, see [tx](https://basescan.org/tx/0x1) `ok`"),
+            "Result\nSwapped 1 ETH, see tx (https://basescan.org/tx/0x1) ok"
+        );
+    }
+
+    #[test]
+    fn test_strip_markdown_keeps_code_and_non_emphasis_markers() {
+        assert_eq!(
+            strip_markdown("Run this:\n```python\ndef __init__(self):\n    x = 2 * 3 * 4\n```\nThen **done**."),
+            "Run this:\ndef __init__(self):\n    x = 2 * 3 * 4\nThen done."
+        );
+        assert_eq!(strip_markdown("`my_var_name` is *set*"), "my_var_name is set");
+        assert_eq!(strip_markdown("2 * 3 * 4 = 24"), "2 * 3 * 4 = 24");
+        assert_eq!(strip_markdown("Override __init__ and __repr__"), "Override __init__ and __repr__");
+        assert_eq!(strip_markdown("set max_retry_count to 3"), "set max_retry_count to 3");
+        assert_eq!(strip_markdown("a*b*c and _really_ ~~old~~"), "a*b*c and really old");
+    }
+
+    #[test]
+    fn test_telegram_keeps_markdown() {
+        let registry = ChannelCapabilityRegistry::with_builtin_channels();
+        assert_eq!(registry.get("telegram").format_response("**hi** `x`"), "**hi** `x`");
+    }
+
+    #[test]
+    fn test_registered_capabilities_replace_defaults() {
+        let registry = ChannelCapabilityRegistry::new();
+        registry.register(
+            "sms",
+            ChannelCapabilities {
+                supports_interactive: true,
+                supports_images: false,
+                max_message_len: Some(10),
+                supports_markdown: false,
+            },
+        );
+        let sms = registry.get("sms");
+        assert_eq!(sms.format_response("hello there world"), "hello the…");
+        assert_eq!(sms.format_response("short"), "short");
+    }
+}
@@ -0,0 +1,264 @@
+//! Posting to a channel's platform chat.
+//!
+//! Listener replies and proactive messages (scheduled messages, operator
+//! injections, deferred cron results, poll follow-ups) share one outbound
+//! queue per chat, so they are delivered in order and under one rate limit.
+//! Each platform has a single send implementation here; proactive senders
+//! build it from the channel's bot token, listeners from their client.
+
+use dashmap::DashMap;
+use serde_json::{json, Value};
+use std::sync::{Arc, OnceLock};
+
+use crate::channels::outbound::{OutboundMessage, OutboundQueue, OutboundQueues, SendFn};
+use crate::channels::util::{channel_bot_token, split_message};
+use crate::db::Database;
+use crate::telemetry::TelemetryStore;
+
+/// Outbound queues of every running channel, keyed by channel ID
+static CHANNEL_QUEUES: OnceLock<DashMap<i64, Arc<OutboundQueues>>> = OnceLock::new();
+
+fn channel_queue_map() -> &'static DashMap<i64, Arc<OutboundQueues>> {
+    CHANNEL_QUEUES.get_or_init(DashMap::new)
+}
+
+/// Channel types messages can be posted to outside a listener
+pub fn supports_channel_type(channel_type: &str) -> bool {
+    matches!(channel_type, "discord" | "telegram" | "slack")
+}
+
+/// Longest single message a platform accepts; longer text is split
+pub fn max_message_len(channel_type: &str) -> usize {
+    match channel_type {
+        "discord" => 2000,
+        "telegram" => 4096,
+        _ => 4000,
+    }
+}
+
+/// Check that `chat_id` looks like a chat on the channel's platform:
+/// a Discord channel snowflake, a Telegram chat ID or @username, or a Slack
+/// conversation ID.
+pub fn validate_chat_id(channel_type: &str, chat_id: &str) -> Result<(), String> {
+    let valid = match channel_type {
+        "discord" => chat_id.parse::<i64>().map(|id| id > 0).unwrap_or(false),
+        "telegram" => match chat_id.strip_prefix('@') {
+            Some(name) => (5..=32).contains(&name.len()) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+            None => chat_id.parse::<i64>().map(|id| id != 0).unwrap_or(false),
+        },
+        "slack" => {
+            chat_id.len() >= 9
+                && matches!(chat_id.chars().next(), Some('C' | 'G' | 'D'))
+                && chat_id.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        }
+        other => return Err(format!("Cannot post to {} channels", other)),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("'{}' is not a valid {} chat ID", chat_id, channel_type))
+    }
+}
+
+/// The outbound queues shared by everything that posts to `channel_id`
+pub fn channel_queues(channel_id: i64, channel_type: &str, telemetry: Arc<TelemetryStore>) -> Arc<OutboundQueues> {
+    channel_queue_map()
+        .entry(channel_id)
+        .or_insert_with(|| Arc::new(OutboundQueues::for_channel_type(channel_type).with_telemetry(telemetry)))
+        .clone()
+}
+
+/// Drop a channel's queues, e.g. when its listener (re)starts with a new token
+pub fn reset_channel_queues(channel_id: i64) {
+    channel_queue_map().remove(&channel_id);
+}
+
+/// Send function for a Discord channel
+pub fn discord_send_fn(http: Arc<serenity::http::Http>, channel: serenity::all::ChannelId) -> SendFn {
+    use serenity::all::{CreateMessage, MessageId};
+    Arc::new(move |message: OutboundMessage| {
+        let http = http.clone();
+        Box::pin(async move {
+            let mut builder = CreateMessage::new().content(message.text);
+            let reply_to = message.reply_to.as_deref().and_then(|id| id.parse::<u64>().ok()).filter(|id| *id != 0);
+            if let Some(reply_to) = reply_to {
+                builder = builder.reference_message((channel, MessageId::new(reply_to)));
+            }
+            channel
+                .send_message(&http, builder)
+                .await
+                .map(|sent| Some(sent.id.to_string()))
+                .map_err(|e| e.to_string())
+        })
+    })
+}
+
+/// Send function for a Telegram chat (chat ID or public @username)
+pub fn telegram_send_fn(bot: teloxide::Bot, chat: impl Into<teloxide::types::Recipient>) -> SendFn {
+    use teloxide::payloads::SendMessageSetters;
+    use teloxide::requests::Requester;
+    use teloxide::types::MessageId;
+    let chat = chat.into();
+    Arc::new(move |message: OutboundMessage| {
+        let bot = bot.clone();
+        let chat = chat.clone();
+        Box::pin(async move {
+            let mut request = bot.send_message(chat, message.text);
+            if let Some(reply_to) = message.reply_to.as_deref().and_then(|id| id.parse::<i32>().ok()) {
+                request = request.reply_to_message_id(MessageId(reply_to));
+            }
+            request
+                .await
+                .map(|sent| Some(sent.id.0.to_string()))
+                .map_err(|e| e.to_string())
+        })
+    })
+}
+
+/// Send function for a Slack conversation. Replies are posted in the thread.
+pub fn slack_send_fn(bot_token: String, channel: String) -> SendFn {
+    Arc::new(move |message: OutboundMessage| {
+        let bot_token = bot_token.clone();
+        let channel = channel.clone();
+        Box::pin(async move {
+            let mut body = json!({ "channel": channel, "text": message.text });
+            if let Some(thread_ts) = message.reply_to {
+                body["thread_ts"] = json!(thread_ts);
+            }
+            let response: Value = crate::http::shared_client()
+                .post("https://slack.com/api/chat.postMessage")
+                .header("Authorization", format!("Bearer {}", bot_token))
+                .json(&body)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to send Slack message: {}", e))?
+                .json()
+                .await
+                .map_err(|e| format!("Invalid Slack response: {}", e))?;
+            // Slack returns 200 even on errors, check the response body
+            if !response.get("ok").and_then(|v| v.as_bool()).unwrap_or(false) {
+                let error = response.get("error").and_then(|v| v.as_str()).unwrap_or("unknown error");
+                return Err(format!("Slack API error: {}", error));
+            }
+            Ok(response["ts"].as_str().map(String::from))
+        })
+    })
+}
+
+/// The outbound queue for a chat, creating its sender from the channel's bot token
+pub fn chat_queue(db: &Arc<Database>, channel_id: i64, channel_type: &str, chat_id: &str) -> Result<OutboundQueue, String> {
+    validate_chat_id(channel_type, chat_id)?;
+    let setting_key = match channel_type {
+        "discord" => "discord_bot_token",
+        "telegram" => "telegram_bot_token",
+        _ => "slack_bot_token",
+    };
+    let bot_token = channel_bot_token(db, channel_id, setting_key)
+        .ok_or_else(|| format!("No bot token configured for {} channel {}", channel_type, channel_id))?;
+
+    let queues = channel_queues(channel_id, channel_type, Arc::new(TelemetryStore::new(db.clone())));
+    Ok(queues.queue(chat_id, || match (channel_type, chat_id.parse::<i64>()) {
+        ("discord", Ok(id)) => discord_send_fn(
+            Arc::new(serenity::http::Http::new(&bot_token)),
+            serenity::all::ChannelId::new(id as u64),
+        ),
+        ("telegram", Ok(id)) => telegram_send_fn(teloxide::Bot::new(&bot_token), teloxide::types::ChatId(id)),
+        ("telegram", Err(_)) => telegram_send_fn(
+            teloxide::Bot::new(&bot_token),
+            teloxide::types::Recipient::ChannelUsername(chat_id.to_string()),
+        ),
+        _ => slack_send_fn(bot_token.clone(), chat_id.to_string()),
+    }))
+}
+
+/// What reached the platform when posting a text
+#[derive(Debug, Default)]
+pub struct Delivery {
+    /// Parts that were delivered, in order
+    pub sent_parts: Vec<String>,
+    /// Platform message IDs of the delivered parts
+    pub message_ids: Vec<String>,
+    /// Why delivery stopped early, if it did
+    pub error: Option<String>,
+}
+
+impl Delivery {
+    /// The delivered text (parts joined the way they were split)
+    pub fn sent_text(&self) -> String {
+        self.sent_parts.join("\n")
+    }
+
+    /// Fail unless every part was delivered, noting how much was
+    pub fn into_result(self) -> Result<Self, String> {
+        match &self.error {
+            None => Ok(self),
+            Some(e) if self.sent_parts.is_empty() => Err(e.clone()),
+            Some(e) => Err(format!("Delivered only the first {} part(s): {}", self.sent_parts.len(), e)),
+        }
+    }
+}
+
+/// Post `text` to a chat through its outbound queue, split to the platform's
+/// message limit. Stops at the first part that fails.
+pub async fn send_text(db: &Arc<Database>, channel_id: i64, channel_type: &str, chat_id: &str, text: &str) -> Delivery {
+    let queue = match chat_queue(db, channel_id, channel_type, chat_id) {
+        Ok(queue) => queue,
+        Err(e) => return Delivery { error: Some(e), ..Delivery::default() },
+    };
+    send_parts(&queue, split_message(text, max_message_len(channel_type))).await
+}
+
+async fn send_parts(queue: &OutboundQueue, parts: Vec<String>) -> Delivery {
+    let mut delivery = Delivery::default();
+    for part in parts {
+        match queue.send_and_wait(part.as_str()).await {
+            Ok(id) => {
+                delivery.message_ids.extend(id);
+                delivery.sent_parts.push(part);
+            }
+            Err(e) => {
+                delivery.error = Some(e);
+                break;
+            }
+        }
+    }
+    delivery
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_chat_id() {
+        assert!(validate_chat_id("discord", "1234567890123456789").is_ok());
+        assert!(validate_chat_id("discord", "general").is_err());
+        assert!(validate_chat_id("telegram", "-1001234567890").is_ok());
+        assert!(validate_chat_id("telegram", "@stark_news").is_ok());
+        assert!(validate_chat_id("telegram", "../sendMessage").is_err());
+        assert!(validate_chat_id("slack", "C0123456789").is_ok());
+        assert!(validate_chat_id("slack", "c0123456789").is_err());
+        assert!(validate_chat_id("web", "anything").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_partial_delivery_reports_what_was_sent() {
+        let send: SendFn = Arc::new(|message: OutboundMessage| {
+            Box::pin(async move {
+                if message.text.starts_with("fail") {
+                    Err("rate limited".to_string())
+                } else {
+                    Ok(Some(format!("id-{}", message.text)))
+                }
+            })
+        });
+        let queue = OutboundQueue::spawn("test".to_string(), None, 4, None, send);
+
+        let parts = vec!["one".to_string(), "two".to_string(), "fail".to_string(), "four".to_string()];
+        let delivery = send_parts(&queue, parts).await;
+        assert_eq!(delivery.sent_text(), "one\ntwo");
+        assert_eq!(delivery.message_ids, vec!["id-one", "id-two"]);
+        let error = delivery.into_result().unwrap_err();
+        assert!(error.contains("first 2 part(s)") && error.contains("rate limited"), "{}", error);
+    }
+}
@@ -0,0 +1,217 @@
+//! Channel digests.
+//!
+//! Summarizes recent activity in a channel across all of its sessions for
+//! community managers: top topics, notable requests and errors. Sessions that
+//! have been compacted contribute their stored compaction summary instead of
+//! raw messages, which keeps the prompt (and the cost) small. Other sessions
+//! contribute their latest user/assistant messages.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::ai::{AiClient, Message, MessageRole};
+use crate::db::Database;
+use crate::models::MessageRole as SessionRole;
+use crate::text::truncate_chars;
+
+/// Most sessions included in one digest
+pub const MAX_DIGEST_SESSIONS: i32 = 30;
+/// Messages taken from a session that has no summary
+const MESSAGES_PER_SESSION: i32 = 12;
+/// Longest message excerpt included in the prompt
+const MAX_MESSAGE_CHARS: usize = 300;
+
+const DIGEST_SYSTEM_PROMPT: &str = "You write activity digests for the community managers of a chat channel. \
+You are given recent sessions from the channel, each either as a summary or as an excerpt of its latest messages. \
+Write a concise digest with three short sections: Top topics, Notable requests, and Errors or problems. \
+Only use what is in the sessions; say \"None\" for an empty section.";
+
+/// What one session contributes to a digest
+#[derive(Debug, Clone)]
+struct SessionActivity {
+    session_id: i64,
+    chat_id: String,
+    last_activity_at: DateTime<Utc>,
+    source: ActivitySource,
+}
+
+#[derive(Debug, Clone)]
+enum ActivitySource {
+    Summary(String),
+    Messages(Vec<String>),
+}
+
+/// A channel digest
+#[derive(Debug, Clone, Serialize)]
+pub struct ChannelDigest {
+    pub channel_id: i64,
+    pub since: DateTime<Utc>,
+    pub session_count: usize,
+    /// Sessions that contributed their stored summary rather than raw messages
+    pub summaries_used: usize,
+    pub digest: String,
+}
+
+/// Summarize a channel's activity since `since` with the given AI client.
+pub async fn summarize_channel(
+    db: &Database,
+    client: &AiClient,
+    channel_id: i64,
+    since: DateTime<Utc>,
+) -> Result<ChannelDigest, String> {
+    let activity = collect_activity(db, channel_id, &since)?;
+    let summaries_used = activity
+        .iter()
+        .filter(|a| matches!(a.source, ActivitySource::Summary(_)))
+        .count();
+
+    let digest = if activity.is_empty() {
+        "No activity in this channel for the selected period.".to_string()
+    } else {
+        let messages = vec![
+            Message {
+                role: MessageRole::System,
+                content: DIGEST_SYSTEM_PROMPT.to_string(),
+            },
+            Message {
+                role: MessageRole::User,
+                content: build_prompt(&activity, &since),
+            },
+        ];
+        client
+            .generate_text(messages)
+            .await
+            .map_err(|e| format!("Failed to generate channel digest: {}", e))?
+    };
+
+    log::info!(
+        "[DIGEST] Summarized channel {} ({} sessions, {} from stored summaries)",
+        channel_id,
+        activity.len(),
+        summaries_used
+    );
+    Ok(ChannelDigest {
+        channel_id,
+        since,
+        session_count: activity.len(),
+        summaries_used,
+        digest,
+    })
+}
+
+fn collect_activity(db: &Database, channel_id: i64, since: &DateTime<Utc>) -> Result<Vec<SessionActivity>, String> {
+    let sessions = db
+        .list_channel_sessions_since(channel_id, since, MAX_DIGEST_SESSIONS)
+        .map_err(|e| format!("Failed to list channel sessions: {}", e))?;
+
+    let mut activity = Vec::with_capacity(sessions.len());
+    for session in sessions {
+        let summary = db
+            .get_session_compaction_summary(session.id)
+            .ok()
+            .flatten()
+            .filter(|s| !s.trim().is_empty());
+        let source = match summary {
+            Some(summary) => ActivitySource::Summary(summary),
+            None => {
+                let lines: Vec<String> = db
+                    .get_recent_session_messages(session.id, MESSAGES_PER_SESSION)
+                    .map_err(|e| format!("Failed to read session {} messages: {}", session.id, e))?
+                    .into_iter()
+                    .filter(|m| matches!(m.role, SessionRole::User | SessionRole::Assistant))
+                    .map(|m| {
+                        let speaker = match m.role {
+                            SessionRole::Assistant => "Assistant".to_string(),
+                            _ => m.user_name.unwrap_or_else(|| "User".to_string()),
+                        };
+                        format!("{}: {}", speaker, truncate_chars(&m.content, MAX_MESSAGE_CHARS))
+                    })
+                    .collect();
+                if lines.is_empty() {
+                    continue;
+                }
+                ActivitySource::Messages(lines)
+            }
+        };
+        activity.push(SessionActivity {
+            session_id: session.id,
+            chat_id: session.platform_chat_id,
+            last_activity_at: session.last_activity_at,
+            source,
+        });
+    }
+    Ok(activity)
+}
+
+fn build_prompt(activity: &[SessionActivity], since: &DateTime<Utc>) -> String {
+    let mut prompt = format!(
+        "Channel activity since {} ({} sessions):\n",
+        since.format("%Y-%m-%d %H:%M UTC"),
+        activity.len()
+    );
+    for session in activity {
+        prompt.push_str(&format!(
+            "\n## Session {} (chat {}, last active {})\n",
+            session.session_id,
+            session.chat_id,
+            session.last_activity_at.format("%Y-%m-%d %H:%M UTC")
+        ));
+        match &session.source {
+            ActivitySource::Summary(summary) => {
+                prompt.push_str("Summary: ");
+                prompt.push_str(summary.trim());
+                prompt.push('\n');
+            }
+            ActivitySource::Messages(lines) => {
+                for line in lines {
+                    prompt.push_str(line);
+                    prompt.push('\n');
+                }
+            }
+        }
+    }
+    prompt.push_str("\nWrite the digest.");
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::{AiResponse, MockAiClient};
+    use crate::models::SessionScope;
+    use chrono::Duration;
+
+    #[tokio::test]
+    async fn test_digest_uses_stored_summaries_from_multiple_sessions() {
+        let db = Database::new(":memory:").unwrap();
+        let channel = db.create_channel("discord", "community", "fake-token", None).unwrap();
+        let other = db.create_channel("discord", "ops", "fake-token", None).unwrap();
+
+        let alice = db.get_or_create_chat_session("discord", channel.id, "dm-alice", SessionScope::Dm, None).unwrap();
+        let bob = db.get_or_create_chat_session("discord", channel.id, "dm-bob", SessionScope::Dm, None).unwrap();
+        let carol = db.get_or_create_chat_session("discord", channel.id, "dm-carol", SessionScope::Dm, None).unwrap();
+        let elsewhere = db.get_or_create_chat_session("discord", other.id, "dm-dave", SessionScope::Dm, None).unwrap();
+        db.set_session_compaction_summary(alice.id, "Alice asked how to bridge USDC to Base.").unwrap();
+        db.set_session_compaction_summary(bob.id, "Bob reported that swaps keep failing with slippage errors.").unwrap();
+        db.add_session_message(carol.id, SessionRole::User, "Can you add a daily price alert?", Some("u-carol"), Some("carol"), None, None)
+            .unwrap();
+        db.set_session_compaction_summary(elsewhere.id, "Unrelated ops chatter.").unwrap();
+
+        let client = AiClient::Mock(MockAiClient::new(vec![Ok(AiResponse::text("Digest text".to_string()))]));
+        let since = Utc::now() - Duration::hours(24);
+        let digest = summarize_channel(&db, &client, channel.id, since).await.unwrap();
+        assert_eq!(digest.digest, "Digest text");
+        assert_eq!(digest.session_count, 3);
+        assert_eq!(digest.summaries_used, 2);
+
+        let prompt = build_prompt(&collect_activity(&db, channel.id, &since).unwrap(), &since);
+        assert!(prompt.contains("Summary: Alice asked how to bridge USDC to Base."));
+        assert!(prompt.contains("Summary: Bob reported that swaps keep failing"));
+        assert!(prompt.contains("carol: Can you add a daily price alert?"));
+        assert!(!prompt.contains("Unrelated ops chatter"));
+
+        // Nothing since a future cutoff: no AI call needed
+        let idle = summarize_channel(&db, &client, channel.id, Utc::now() + Duration::hours(1)).await.unwrap();
+        assert_eq!(idle.session_count, 0);
+    }
+}
@@ -1,4 +1,9 @@
+use crate::text::truncate_chars;
+use crate::channels::capabilities::ChannelCapabilities;
 use crate::channels::dispatcher::MessageDispatcher;
+use crate::channels::delivery;
+use crate::channels::outbound::{OutboundQueue, OutboundQueues};
+use crate::channels::polls;
 use crate::channels::safe_mode_rate_limiter::SafeModeChannelRateLimiter;
 use crate::channels::types::{ChannelType, NormalizedMessage};
 use crate::channels::util;
@@ -8,8 +13,8 @@ use crate::gateway::events::EventBroadcaster;
 use crate::gateway::protocol::GatewayEvent;
 use crate::models::{Channel, ToolOutputVerbosity};
 use serenity::all::{
-    Client, Context, EditMessage, EventHandler, GatewayIntents, GetMessages, Message, MessageId,
-    Ready, UserId,
+    Client, Context, CreateInteractionResponse, CreateInteractionResponseMessage, EditMessage,
+    EventHandler, GatewayIntents, GetMessages, Interaction, Message, MessageId, Ready, UserId,
 };
 use std::sync::Arc;
 use tokio::sync::oneshot;
@@ -27,11 +32,7 @@ fn format_tool_call_for_discord(
             let params_str = serde_json::to_string_pretty(parameters)
                 .unwrap_or_else(|_| parameters.to_string());
             // Truncate params if too long for Discord
-            let params_display = if params_str.len() > 800 {
-                format!("{}...", &params_str[..800])
-            } else {
-                params_str
-            };
+            let params_display = truncate_chars(&params_str, 800);
             Some(format!("🔧 **Tool Call:** `{}`\n```json\n{}\n```", tool_name, params_display))
         }
     }
@@ -56,11 +57,7 @@ fn format_tool_result_for_discord(
         }
         ToolOutputVerbosity::Full => {
             // Truncate content if too long
-            let content_display = if content.len() > 1200 {
-                format!("{}...", &content[..1200])
-            } else {
-                content.to_string()
-            };
+            let content_display = truncate_chars(content, 1200);
             Some(format!(
                 "{} **Tool Result:** `{}` ({} ms)\n```\n{}\n```",
                 status, tool_name, duration_ms, content_display
@@ -69,6 +66,22 @@ fn format_tool_result_for_discord(
     }
 }
 
+/// Image attachments of a Discord message, passed to the model by URL
+fn image_attachments(msg: &Message) -> Vec<crate::ai::Attachment> {
+    msg.attachments
+        .iter()
+        .filter_map(|a| {
+            let mime = a.content_type.as_deref()?.split(';').next()?.trim();
+            mime.starts_with("image/").then(|| crate::ai::Attachment::url(mime, a.url.clone()))
+        })
+        .collect()
+}
+
+/// Parse a Discord message ID returned by the outbound queue
+fn parse_message_id(id: &str) -> Option<MessageId> {
+    id.parse::<u64>().ok().filter(|id| *id != 0).map(MessageId::new)
+}
+
 /// Format an agent mode change for Discord display
 fn format_mode_change_for_discord(mode: &str, label: &str, reason: Option<&str>) -> String {
     let emoji = match mode {
@@ -94,6 +107,9 @@ struct DiscordHandler {
     /// Cached bot user ID, set once from the Ready event to avoid
     /// calling get_current_user() (a Discord API call) on every message.
     bot_user_id: Arc<tokio::sync::OnceCell<UserId>>,
+    /// Ordered, rate-limited send queues keyed by Discord channel ID,
+    /// shared with proactive senders (see `channels::delivery`)
+    outbound: Arc<OutboundQueues>,
 }
 
 #[serenity::async_trait]
@@ -149,12 +165,13 @@ impl EventHandler for DiscordHandler {
             Ok(result) => {
                 // If module handled it with a direct response, send it and return
                 if let Some(response) = result.response {
-                    let chunks = util::split_message(&response, 2000);
-                    for chunk in chunks {
-                        if let Err(e) = msg.channel_id.say(&ctx.http, &chunk).await {
-                            log::error!("Discord: Failed to send hooks response: {}", e);
+                    let queue = self.outbound_queue(&ctx, msg.channel_id);
+                    for chunk in util::split_message(&response, 2000) {
+                        if let Err(e) = queue.send(chunk).await {
+                            log::error!("Discord: Failed to queue hooks response: {}", e);
                         }
                     }
+                    queue.flush().await;
                     return;
                 }
 
@@ -167,7 +184,8 @@ impl EventHandler for DiscordHandler {
                     if forward.force_safe_mode {
                         if let Err(rate_limit_msg) = self.safe_mode_rate_limiter.check_and_record_query(&user_id, "discord") {
                             log::info!("Discord: Rate limiting user {} - {}", user_id, rate_limit_msg);
-                            if let Err(e) = msg.channel_id.say(&ctx.http, format!("⏳ {}", rate_limit_msg)).await {
+                            let queue = self.outbound_queue(&ctx, msg.channel_id);
+                            if let Err(e) = queue.send_and_wait(format!("⏳ {}", rate_limit_msg)).await {
                                 log::error!("Discord: Failed to send rate limit message to user {}: {}", user_id, e);
                             }
                             return;
@@ -179,7 +197,7 @@ impl EventHandler for DiscordHandler {
                         if forward.force_safe_mode { "Safe mode query" } else { "Admin command" },
                         user_name,
                         user_id,
-                        if forward.text.len() > 50 { format!("{}...", forward.text.chars().take(50).collect::<String>()) } else { forward.text.clone() }
+                        truncate_chars(&forward.text, 50)
                     );
 
                     // Fetch recent channel context (last 6 messages before this one)
@@ -189,11 +207,7 @@ impl EventHandler for DiscordHandler {
                         // If this is a reply, include what it's replying to
                         if let Some(ref replied) = msg.referenced_message {
                             let reply_author = &replied.author.name;
-                            let reply_content = if replied.content.len() > 300 {
-                                format!("{}...", &replied.content[..300])
-                            } else {
-                                replied.content.clone()
-                            };
+                            let reply_content = truncate_chars(&replied.content, 300);
                             ctx_str.push_str(&format!(
                                 "[REPLYING TO @{}:]\n{}\n\n",
                                 reply_author, reply_content
@@ -214,11 +228,7 @@ impl EventHandler for DiscordHandler {
                                 for m in msgs {
                                     let who = &m.author.name;
                                     let tag = if m.author.bot { " [you]" } else { "" };
-                                    let preview = if m.content.len() > 300 {
-                                        format!("{}...", &m.content[..300])
-                                    } else {
-                                        m.content.clone()
-                                    };
+                                    let preview = truncate_chars(&m.content, 300);
                                     ctx_str.push_str(&format!("@{}{}: {}\n", who, tag, preview));
                                 }
                                 ctx_str.push('\n');
@@ -244,23 +254,29 @@ impl EventHandler for DiscordHandler {
                         )
                     };
 
-                    // Get channel name for context
-                    let channel_name = msg.channel_id.to_channel(&ctx.http).await.ok().and_then(|ch| {
-                        ch.guild().map(|gc| gc.name().to_string())
-                    });
+                    // Get channel name for context. A thread is its own channel, so
+                    // chat_id is the thread ID and the session follows the thread.
+                    let guild_channel = msg.channel_id.to_channel(&ctx.http).await.ok().and_then(|ch| ch.guild());
+                    let channel_name = guild_channel.as_ref().map(|gc| gc.name().to_string());
+                    let is_thread = guild_channel.as_ref().is_some_and(|gc| gc.thread_metadata.is_some());
 
                     let normalized = NormalizedMessage {
                         channel_id: self.channel_id,
                         channel_type: ChannelType::Discord.to_string(),
                         chat_id: msg.channel_id.to_string(),
                         chat_name: channel_name,
+                        is_thread,
                         user_id,
                         user_name: user_name.clone(),
                         text: text_with_hint,
+                        attachments: image_attachments(&msg),
                         message_id: Some(msg.id.to_string()),
                         session_mode: None,
                         selected_network: None,
                         force_safe_mode: forward.force_safe_mode,
+                        agent_settings_override: None,
+                        ephemeral: false,
+                        dry_run: false,
                     };
 
                     self.dispatch_and_respond(&ctx, &msg, normalized, &user_name).await;
@@ -283,7 +299,8 @@ impl EventHandler for DiscordHandler {
             Err(e) => {
                 log::error!("Discord hooks error: {}", e);
                 // Security: Do NOT fall through - this would bypass admin checks
-                let _ = msg.channel_id.say(&ctx.http, "Sorry, I encountered an error processing your message.").await;
+                let queue = self.outbound_queue(&ctx, msg.channel_id);
+                let _ = queue.send_and_wait("Sorry, I encountered an error processing your message.").await;
                 return;
             }
         }
@@ -294,9 +311,47 @@ impl EventHandler for DiscordHandler {
         log::info!("Discord: Bot connected as {} (id={})", ready.user.name, ready.user.id);
         let _ = self.bot_user_id.set(ready.user.id);
     }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        // Only poll buttons (create_poll tool) are handled here
+        let Interaction::Component(component) = interaction else {
+            return;
+        };
+        let Some((poll_id, option_index)) = polls::parse_vote_payload(&component.data.custom_id) else {
+            return;
+        };
+
+        let reply = match self.db.record_poll_vote(
+            poll_id,
+            &component.user.id.to_string(),
+            Some(&component.user.name),
+            option_index,
+        ) {
+            Ok(true) => "✅ Vote recorded",
+            Ok(false) => "This poll is closed",
+            Err(e) => {
+                log::error!("Discord: Failed to record vote for poll {}: {}", poll_id, e);
+                "Failed to record your vote"
+            }
+        };
+        let response = CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new().content(reply).ephemeral(true),
+        );
+        if let Err(e) = component.create_response(&ctx.http, response).await {
+            log::warn!("Discord: Failed to acknowledge poll vote: {}", e);
+        }
+    }
 }
 
 impl DiscordHandler {
+    /// Get the outbound queue for a Discord channel
+    fn outbound_queue(&self, ctx: &Context, discord_channel_id: serenity::all::ChannelId) -> OutboundQueue {
+        let http = ctx.http.clone();
+        self.outbound.queue(&discord_channel_id.to_string(), move || {
+            delivery::discord_send_fn(http, discord_channel_id)
+        })
+    }
+
     /// Dispatch a message to the AI and send the response
     async fn dispatch_and_respond(
         &self,
@@ -311,9 +366,11 @@ impl DiscordHandler {
         let (client_id, mut event_rx) = self.broadcaster.subscribe();
         log::info!("Discord: Subscribed to events as client {}", client_id);
 
-        // Clone context and channel info for the event forwarder task
+        // Clone context and channel info for the event forwarder task.
+        // Status messages are sent through the channel's outbound queue; edits go direct.
         let http = ctx.http.clone();
         let discord_channel_id = msg.channel_id;
+        let status_queue = self.outbound_queue(ctx, discord_channel_id);
         let channel_id_for_events = self.channel_id;
         // Convert Discord channel ID to string for event filtering
         let chat_id_for_events = discord_channel_id.to_string();
@@ -325,10 +382,10 @@ impl DiscordHandler {
             let mut status_message_id: Option<MessageId> = None;
 
             // Send an immediate "thinking" message so users see feedback right away
-            match discord_channel_id.say(&http, "💭 **Thinking...**").await {
-                Ok(msg) => {
-                    status_message_id = Some(msg.id);
-                    log::debug!("Discord: Created initial thinking message {}", msg.id);
+            match status_queue.send_and_wait("💭 **Thinking...**").await {
+                Ok(id) => {
+                    status_message_id = id.as_deref().and_then(parse_message_id);
+                    log::debug!("Discord: Created initial thinking message {:?}", status_message_id);
                 }
                 Err(e) => {
                     log::error!("Discord: Failed to send thinking message: {}", e);
@@ -450,11 +507,7 @@ impl DiscordHandler {
 
                 if let Some(text) = message_text {
                     // Only use the first chunk if message is too long (status updates should be brief)
-                    let display_text = if text.len() > 2000 {
-                        format!("{}...", &text[..1997])
-                    } else {
-                        text
-                    };
+                    let display_text = truncate_chars(&text, 2000);
 
                     match status_message_id {
                         Some(msg_id) => {
@@ -468,9 +521,9 @@ impl DiscordHandler {
                                 // Try to delete the old message
                                 let _ = discord_channel_id.delete_message(&http, msg_id).await;
                                 // Send a new message
-                                match discord_channel_id.say(&http, &display_text).await {
-                                    Ok(new_msg) => {
-                                        status_message_id = Some(new_msg.id);
+                                match status_queue.send_and_wait(display_text.as_str()).await {
+                                    Ok(id) => {
+                                        status_message_id = id.as_deref().and_then(parse_message_id);
                                     }
                                     Err(e) => {
                                         log::error!("Discord: Failed to send new status message: {}", e);
@@ -481,10 +534,10 @@ impl DiscordHandler {
                         }
                         None => {
                             // First message - create it and store the ID
-                            match discord_channel_id.say(&http, &display_text).await {
-                                Ok(msg) => {
-                                    status_message_id = Some(msg.id);
-                                    log::debug!("Discord: Created status message {}", msg.id);
+                            match status_queue.send_and_wait(display_text.as_str()).await {
+                                Ok(id) => {
+                                    status_message_id = id.as_deref().and_then(parse_message_id);
+                                    log::debug!("Discord: Created status message {:?}", status_message_id);
                                 }
                                 Err(e) => {
                                     log::error!("Discord: Failed to send initial status message: {}", e);
@@ -543,20 +596,35 @@ impl DiscordHandler {
             let response = &result.response;
             let chunks = util::split_message(response, 2000);
 
+            // Queue chunks so they arrive in order under Discord's per-channel limit
+            let queue = self.outbound_queue(ctx, msg.channel_id);
             for chunk in chunks {
-                if let Err(e) = msg.channel_id.say(&ctx.http, &chunk).await {
-                    log::error!("Failed to send Discord message: {}", e);
+                if let Err(e) = queue.send(chunk).await {
+                    log::error!("Failed to queue Discord message: {}", e);
                 }
             }
+            queue.flush().await;
         } else if let Some(error) = result.error {
             let error_msg = format!("Sorry, I encountered an error: {}", error);
-            let _ = msg.channel_id.say(&ctx.http, &error_msg).await;
+            let queue = self.outbound_queue(ctx, msg.channel_id);
+            let _ = queue.send(error_msg).await;
+            queue.flush().await;
         } else if result.response.is_empty() {
             log::debug!("Discord: Empty final response for user {}", user_name);
         }
     }
 }
 
+/// Discord renders markdown; long replies are split into 2000-char messages by the listener
+pub fn capabilities() -> ChannelCapabilities {
+    ChannelCapabilities {
+        supports_interactive: true,
+        supports_images: true,
+        max_message_len: None,
+        supports_markdown: true,
+    }
+}
+
 /// Start a Discord bot listener
 pub async fn start_discord_listener(
     channel: Channel,
@@ -578,6 +646,14 @@ pub async fn start_discord_listener(
         | GatewayIntents::DIRECT_MESSAGES
         | GatewayIntents::MESSAGE_CONTENT;
 
+    // Queues from a previous run may hold a client with an old token
+    delivery::reset_channel_queues(channel_id);
+    let outbound = delivery::channel_queues(
+        channel_id,
+        ChannelType::Discord.as_str(),
+        dispatcher.telemetry_store().clone(),
+    );
+
     let handler = DiscordHandler {
         channel_id,
         dispatcher,
@@ -585,6 +661,7 @@ pub async fn start_discord_listener(
         db,
         safe_mode_rate_limiter,
         bot_user_id: Arc::new(tokio::sync::OnceCell::new()),
+        outbound,
     };
 
     // Create client
@@ -33,6 +33,26 @@ impl MessageDispatcher {
         ));
     }
 
+    /// Broadcast an `agent_warning`, collapsing repeats of the same warning type
+    /// on a channel within the cooldown. The payload carries `seen_count`: how
+    /// many times the warning occurred since the previous broadcast.
+    pub(super) fn broadcast_agent_warning(&self, channel_id: i64, warning_type: &str, message: &str, attempt: u32) {
+        match self.warning_throttle.record(channel_id, warning_type) {
+            Some(seen_count) => {
+                let mut event = GatewayEvent::agent_warning(channel_id, warning_type, message, attempt);
+                event.data["seen_count"] = serde_json::json!(seen_count);
+                if seen_count > 1 {
+                    event.data["message"] = serde_json::json!(format!("{} (seen {} times)", message, seen_count));
+                }
+                self.broadcaster.broadcast(event);
+            }
+            None => log::debug!(
+                "[AGENT_WARNING] Suppressed repeated '{}' warning on channel {}: {}",
+                warning_type, channel_id, message
+            ),
+        }
+    }
+
     /// Populate the current attempt's stats (tool_calls, llm_calls) from collected spans.
     pub(super) fn populate_attempt_stats(rollout: &mut Rollout, collector: &SpanCollector) {
         let spans = collector.snapshot();
@@ -1,3 +1,5 @@
+use crate::ai::multi_agent::types::TaskStatus;
+use crate::ai::multi_agent::Orchestrator;
 use crate::ai::{AiClient, AiResponse, Message, ThinkingLevel, ToolHistoryEntry};
 use crate::tools::ToolDefinition;
 use crate::channels::types::{DispatchResult, NormalizedMessage};
@@ -101,6 +103,260 @@ impl MessageDispatcher {
         None
     }
 
+    /// Handle "/plan-approval on|off": when on, the agent shows its task plan and
+    /// waits for /approve before executing it. Stored in the session's agent context.
+    pub(super) async fn handle_plan_approval_command(&self, message: &NormalizedMessage) -> Option<DispatchResult> {
+        let text = message.text.trim().to_lowercase();
+        let arg = text.strip_prefix("/plan-approval")?;
+        if !arg.is_empty() && !arg.starts_with(char::is_whitespace) {
+            return None;
+        }
+
+        let channel_type = message.channel_type.to_lowercase();
+        let response = if (channel_type == "discord" || channel_type == "telegram") && !message.is_thread {
+            "Plan approval mode isn't available here: each message starts a fresh session.".to_string()
+        } else {
+            let scope = if message.chat_id != message.user_id {
+                SessionScope::Group
+            } else {
+                SessionScope::Dm
+            };
+            let session = match self.get_or_create_message_session(message, scope) {
+                Ok(s) => s,
+                Err(e) => return Some(DispatchResult::error(format!("Session error: {}", e))),
+            };
+            let mut context = match self.db.get_agent_context(session.id) {
+                Ok(Some(ctx)) => ctx,
+                _ => Orchestrator::new(String::new()).context().clone(),
+            };
+
+            match arg.trim() {
+                "on" | "off" => {
+                    context.plan_approval = arg.trim() == "on";
+                    if !context.plan_approval {
+                        context.awaiting_plan_approval = false;
+                    }
+                    if let Err(e) = self.db.save_agent_context(session.id, &context) {
+                        return Some(DispatchResult::error(format!("Failed to save session setting: {}", e)));
+                    }
+                    log::info!(
+                        "Plan approval {} for session {} on channel {}",
+                        arg.trim(), session.id, message.channel_id
+                    );
+                    if context.plan_approval {
+                        "Plan approval is **on**. I'll show my plan and wait for /approve before acting.".to_string()
+                    } else {
+                        "Plan approval is **off**. Plans run as soon as they are defined.".to_string()
+                    }
+                }
+                "" => format!(
+                    "Plan approval is **{}**. Use `/plan-approval on` or `/plan-approval off`.",
+                    if context.plan_approval { "on" } else { "off" }
+                ),
+                other => format!("Invalid option '{}'. Use `/plan-approval on` or `/plan-approval off`.", other),
+            }
+        };
+
+        self.broadcaster.broadcast(GatewayEvent::agent_response(
+            message.channel_id,
+            &message.user_name,
+            &response,
+        ));
+        Some(DispatchResult::success(response))
+    }
+
+    /// Handle "/pin" and "/unpin": pinned messages are kept verbatim by compaction.
+    ///   /pin            pin your previous message in this session
+    ///   /pin <id>       pin a message by id
+    ///   /pin list       list pinned messages
+    ///   /unpin <id>     unpin a message
+    pub(super) fn handle_pin_command(&self, message: &NormalizedMessage) -> Option<DispatchResult> {
+        let text = message.text.trim().to_lowercase();
+        let (pin, arg) = match text.strip_prefix("/unpin") {
+            Some(arg) => (false, arg),
+            None => (true, text.strip_prefix("/pin")?),
+        };
+        if !arg.is_empty() && !arg.starts_with(char::is_whitespace) {
+            return None;
+        }
+
+        let channel_type = message.channel_type.to_lowercase();
+        let response = if (channel_type == "discord" || channel_type == "telegram") && !message.is_thread {
+            "Pinning isn't available here: each message starts a fresh session.".to_string()
+        } else {
+            let scope = if message.chat_id != message.user_id {
+                SessionScope::Group
+            } else {
+                SessionScope::Dm
+            };
+            let session = match self.get_or_create_message_session(message, scope) {
+                Ok(s) => s,
+                Err(e) => return Some(DispatchResult::error(format!("Session error: {}", e))),
+            };
+
+            match (pin, arg.trim()) {
+                (true, "list") => match self.db.get_pinned_messages(session.id) {
+                    Ok(pinned) if pinned.is_empty() => "No messages are pinned in this session.".to_string(),
+                    Ok(pinned) => format!(
+                        "Pinned messages (kept verbatim through compaction):\n{}",
+                        pinned
+                            .iter()
+                            .map(|m| format!("[{}] {}", m.id, crate::text::truncate_chars(&m.content, 80)))
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    ),
+                    Err(e) => return Some(DispatchResult::error(format!("Failed to read pinned messages: {}", e))),
+                },
+                (true, "") => {
+                    // The sender's latest message in the session (this command isn't stored)
+                    let previous = self.db.get_recent_session_messages(session.id, 50)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .rev()
+                        .find(|m| {
+                            m.role == crate::models::session_message::MessageRole::User
+                                && m.user_id.as_deref() == Some(message.user_id.as_str())
+                        });
+                    match previous {
+                        Some(m) => match self.db.pin_message(session.id, m.id) {
+                            Ok(_) => format!("Pinned your previous message [{}]. Compaction will keep it verbatim.", m.id),
+                            Err(e) => return Some(DispatchResult::error(format!("Failed to pin message: {}", e))),
+                        },
+                        None => "You have no earlier message in this session to pin.".to_string(),
+                    }
+                }
+                (_, id) => match id.parse::<i64>() {
+                    Ok(id) => match self.db.set_message_pinned(session.id, id, pin) {
+                        Ok(true) if pin => format!("Pinned message [{}]. Compaction will keep it verbatim.", id),
+                        Ok(true) => format!("Unpinned message [{}].", id),
+                        Ok(false) => format!("This session has no message [{}].", id),
+                        Err(e) => return Some(DispatchResult::error(format!("Failed to update message: {}", e))),
+                    },
+                    Err(_) if pin => "Usage: `/pin`, `/pin <id>`, `/pin list` or `/unpin <id>`.".to_string(),
+                    Err(_) => "Usage: `/unpin <id>` (see `/pin list` for ids).".to_string(),
+                },
+            }
+        };
+
+        log::info!("[DISPATCH] {} on channel {}", response, message.channel_id);
+        self.broadcaster.broadcast(GatewayEvent::agent_response(
+            message.channel_id,
+            &message.user_name,
+            &response,
+        ));
+        Some(DispatchResult::success(response))
+    }
+
+    /// Handle "/cancel-task N": drop the Nth task (1-based) of the running plan
+    /// without stopping the session. The running loop applies the deletion on its
+    /// next iteration and broadcasts the updated queue.
+    pub(super) fn handle_cancel_task_command(&self, message: &NormalizedMessage) -> Option<DispatchResult> {
+        let text = message.text.trim().to_lowercase();
+        let arg = text.strip_prefix("/cancel-task")?;
+        if !arg.is_empty() && !arg.starts_with(char::is_whitespace) {
+            return None;
+        }
+
+        let tasks = self.execution_tracker.get_planner_tasks(message.channel_id);
+        let result = match arg.trim().parse::<usize>() {
+            _ if tasks.is_empty() => Err("There is no task queue to cancel from right now.".to_string()),
+            Ok(n) if (1..=tasks.len()).contains(&n) => {
+                let task = &tasks[n - 1];
+                if task.status == TaskStatus::Completed {
+                    Err(format!("Task {} is already completed.", n))
+                } else {
+                    self.execution_tracker.queue_task_deletion(message.channel_id, task.id);
+                    Ok(format!("Cancelled task {}: {}", n, task.description))
+                }
+            }
+            Ok(n) => Err(format!(
+                "Task {} doesn't exist: the queue has {} task{}.",
+                n,
+                tasks.len(),
+                if tasks.len() == 1 { "" } else { "s" }
+            )),
+            Err(_) => Err("Usage: `/cancel-task N`, where N is the task's position in the queue.".to_string()),
+        };
+
+        Some(match result {
+            Ok(response) => {
+                log::info!("[DISPATCH] {} on channel {}", response, message.channel_id);
+                self.broadcaster.broadcast(GatewayEvent::agent_response(
+                    message.channel_id,
+                    &message.user_name,
+                    &response,
+                ));
+                DispatchResult::success(response)
+            }
+            Err(error) => {
+                self.broadcaster.broadcast(GatewayEvent::agent_error(message.channel_id, &error));
+                DispatchResult::error(error)
+            }
+        })
+    }
+
+    /// Handle "/profile": manage the sender's public profile, the only facts about
+    /// them other users can see (via the lookup_user tool).
+    ///   /profile                 list shared facts
+    ///   /profile share <fact>    share a fact
+    ///   /profile unshare <id>    stop sharing a fact
+    pub(super) async fn handle_profile_command(&self, message: &NormalizedMessage) -> Option<DispatchResult> {
+        let text = message.text.trim();
+        let arg = text.strip_prefix("/profile")?;
+        if !arg.is_empty() && !arg.starts_with(char::is_whitespace) {
+            return None;
+        }
+
+        let identity = match self.db.get_or_create_identity(
+            &message.channel_type,
+            &message.user_id,
+            Some(&message.user_name),
+        ) {
+            Ok(identity) => identity,
+            Err(e) => return Some(DispatchResult::error(format!("Identity error: {}", e))),
+        };
+        let identity_id = identity.identity_id.as_str();
+
+        let arg = arg.trim();
+        let (subcommand, rest) = arg.split_once(char::is_whitespace).unwrap_or((arg, ""));
+        let rest = rest.trim();
+        let response = match subcommand.to_lowercase().as_str() {
+            "" | "list" => match self.db.get_public_profile_facts(identity_id) {
+                Ok(facts) if facts.is_empty() => "Your public profile is empty. Other users can't see anything \
+                    about you. Use `/profile share <fact>` to share something."
+                    .to_string(),
+                Ok(facts) => format!(
+                    "Your public profile (visible to other users):\n{}\n\nUse `/profile unshare <id>` to remove a fact.",
+                    facts.iter().map(|(id, fact)| format!("[{}] {}", id, fact)).collect::<Vec<_>>().join("\n")
+                ),
+                Err(e) => return Some(DispatchResult::error(format!("Failed to read profile: {}", e))),
+            },
+            "share" if !rest.is_empty() => match self.db.add_public_profile_fact(identity_id, rest) {
+                Ok(id) => {
+                    log::info!("[PROFILE] Identity {} shared public fact {}", identity_id, id);
+                    format!("Shared [{}] \"{}\" on your public profile.", id, rest)
+                }
+                Err(e) => return Some(DispatchResult::error(format!("Failed to update profile: {}", e))),
+            },
+            "unshare" => match rest.parse::<i64>() {
+                Ok(id) => match self.db.unshare_profile_fact(identity_id, id) {
+                    Ok(true) => format!("Fact [{}] is no longer shared.", id),
+                    Ok(false) => format!("Your public profile has no fact [{}].", id),
+                    Err(e) => return Some(DispatchResult::error(format!("Failed to update profile: {}", e))),
+                },
+                Err(_) => "Usage: `/profile unshare <id>` (see `/profile` for ids).".to_string(),
+            },
+            _ => "Usage: `/profile`, `/profile share <fact>` or `/profile unshare <id>`.".to_string(),
+        };
+
+        self.broadcaster.broadcast(GatewayEvent::agent_response(
+            message.channel_id,
+            &message.user_name,
+            &response,
+        ));
+        Some(DispatchResult::success(response))
+    }
+
     /// Call AI with progress notifications for long-running requests
     /// Broadcasts "still waiting" events every 30 seconds and handles timeout errors gracefully
     /// Also emits granular thinking phase tasks for better UI visibility
@@ -320,13 +576,7 @@ impl MessageDispatcher {
         };
 
         // Get the current session
-        match self.db.get_or_create_chat_session(
-            &message.channel_type,
-            message.channel_id,
-            &message.chat_id,
-            scope,
-            None,
-        ) {
+        match self.get_or_create_message_session(message, scope) {
             Ok(session) => {
                 // Get identity for memory storage
                 let identity_id = self.db.get_or_create_identity(
@@ -374,6 +624,7 @@ impl MessageDispatcher {
                 // Reset the session
                 match self.db.reset_chat_session(session.id) {
                     Ok(_) => {
+                        self.tool_registry.clear_session_cache(session.id);
                         let response = "Session reset. Let's start fresh!".to_string();
                         self.broadcaster.broadcast(GatewayEvent::agent_response(
                             message.channel_id,
@@ -0,0 +1,118 @@
+//! Side-by-side comparison runs.
+//!
+//! Operators tuning prompts/models replay one user message under different
+//! agent settings. Each run goes through the normal dispatch pipeline as an
+//! ephemeral message in a throwaway session on a dedicated channel, so it
+//! never touches a real session or writes to memory. Runs are forced into
+//! safe mode: replaying a message twice must not repeat transfers, swaps or
+//! other side effects. The session is deleted once its stats have been
+//! collected.
+
+use crate::channels::types::NormalizedMessage;
+use crate::context::estimate_tokens;
+use crate::models::AgentSettings;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use super::MessageDispatcher;
+
+/// Channel ID used for comparison runs (keeps them apart from web chat execution tracking)
+pub const COMPARE_CHANNEL_ID: i64 = -2;
+/// Channel type used for comparison runs
+pub const COMPARE_CHANNEL_TYPE: &str = "compare";
+/// Platform user/chat ID used for comparison runs
+const COMPARE_USER_ID: &str = "compare-operator";
+
+/// Outcome of one comparison run
+#[derive(Debug, Clone, Serialize)]
+pub struct ComparisonRun {
+    pub label: String,
+    pub endpoint: String,
+    pub model: Option<String>,
+    pub response: String,
+    pub error: Option<String>,
+    /// Orchestrator iterations used by the run
+    pub iterations: u32,
+    /// Tool calls executed by the run
+    pub tool_calls: u32,
+    /// Estimated tokens in the run's session context at the end of the run
+    pub context_tokens: i32,
+    /// Estimated tokens in the final response
+    pub response_tokens: i32,
+    /// x402 spend during the run, per asset
+    pub x402_cost: BTreeMap<String, f64>,
+    pub duration_ms: u64,
+}
+
+impl MessageDispatcher {
+    /// Run `text` through the dispatch pipeline with `settings` in a throwaway session.
+    pub async fn run_comparison(&self, label: &str, text: &str, settings: AgentSettings) -> ComparisonRun {
+        // Comparison runs share one channel and session key: serialize them so
+        // sessions and x402 payments are attributed to the right run
+        let _lane_guard = self.session_lanes.acquire(COMPARE_CHANNEL_TYPE).await;
+
+        // Clear a session left behind by an interrupted run
+        self.delete_comparison_session();
+
+        let payments_before = self.db.latest_x402_payment_id().unwrap_or(0);
+        let started = Instant::now();
+
+        let message = NormalizedMessage {
+            channel_id: COMPARE_CHANNEL_ID,
+            channel_type: COMPARE_CHANNEL_TYPE.to_string(),
+            chat_id: COMPARE_USER_ID.to_string(),
+            chat_name: None,
+            is_thread: false,
+            user_id: COMPARE_USER_ID.to_string(),
+            user_name: COMPARE_USER_ID.to_string(),
+            text: text.to_string(),
+            attachments: Vec::new(),
+            message_id: None,
+            session_mode: None,
+            selected_network: None,
+            force_safe_mode: true,
+            agent_settings_override: Some(settings.clone()),
+            ephemeral: true,
+            dry_run: false,
+        };
+        let result = self.dispatch_safe(message).await;
+
+        let session = self.db
+            .get_latest_session_for_channel(COMPARE_CHANNEL_TYPE, COMPARE_CHANNEL_ID)
+            .ok()
+            .flatten();
+        let context = session
+            .as_ref()
+            .and_then(|s| self.db.get_agent_context(s.id).ok().flatten());
+        let x402_cost = self.db
+            .sum_x402_payments_since(COMPARE_CHANNEL_ID, payments_before)
+            .map(|rows| rows.into_iter().collect())
+            .unwrap_or_default();
+
+        let run = ComparisonRun {
+            label: label.to_string(),
+            endpoint: settings.endpoint,
+            model: settings.model,
+            response_tokens: estimate_tokens(&result.response),
+            response: result.response,
+            error: result.error,
+            iterations: context.as_ref().map(|c| c.total_iterations).unwrap_or(0),
+            tool_calls: context.as_ref().map(|c| c.actual_tool_calls).unwrap_or(0),
+            context_tokens: session.as_ref().map(|s| s.context_tokens).unwrap_or(0),
+            x402_cost,
+            duration_ms: started.elapsed().as_millis() as u64,
+        };
+
+        self.delete_comparison_session();
+        run
+    }
+
+    fn delete_comparison_session(&self) {
+        if let Ok(Some(session)) = self.db.get_latest_session_for_channel(COMPARE_CHANNEL_TYPE, COMPARE_CHANNEL_ID) {
+            if let Err(e) = self.db.delete_chat_session(session.id) {
+                log::warn!("[COMPARE] Failed to delete comparison session {}: {}", session.id, e);
+            }
+        }
+    }
+}
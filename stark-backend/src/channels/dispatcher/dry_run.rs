@@ -0,0 +1,124 @@
+//! Planning dry runs ("/plan <request>").
+//!
+//! For iterating on planner prompts: the request goes through the normal
+//! dispatch pipeline, but only the task-planning tools run for real. Every
+//! other tool call is answered with a synthetic success echoing its
+//! arguments, so nothing is executed and no x402 funds are spent. Messages
+//! for `say_to_user` are held back and returned with the result instead of
+//! reaching the channel. The run stops once `define_tasks` has produced a
+//! queue and returns it serialized. It uses a throwaway session, deleted
+//! afterwards, so the real conversation and memory are untouched.
+
+use crate::ai::multi_agent::Orchestrator;
+use crate::channels::types::{DispatchResult, NormalizedMessage};
+use crate::db::Database;
+use crate::gateway::protocol::GatewayEvent;
+use serde_json::json;
+
+use super::MessageDispatcher;
+
+/// Tools that only shape the plan or agent state; these still run in a dry run
+const DRY_RUN_LIVE_TOOLS: &[&str] = &[
+    "define_tasks",
+    "set_agent_subtype",
+    "list_subtypes",
+    "add_task",
+    "task_fully_completed",
+];
+
+/// Whether a tool is executed for real during a dry run
+pub(super) fn runs_in_dry_run(tool_name: &str) -> bool {
+    DRY_RUN_LIVE_TOOLS.contains(&tool_name)
+}
+
+/// Synthetic result for a tool call skipped by a dry run
+pub(super) fn dry_run_tool_result(tool_name: &str, args_pretty: &str) -> String {
+    format!(
+        "[dry run] `{}` was not executed. It would have been called with:\n```json\n{}\n```\n\
+         Treat the call as successful and continue.",
+        tool_name, args_pretty
+    )
+}
+
+/// Final response of a dry run: the planned task queue, serialized, followed by
+/// anything the agent tried to tell the user along the way
+pub(super) fn dry_run_plan_report(orchestrator: &Orchestrator, said: &str) -> String {
+    let context = orchestrator.context();
+    let plan = json!({
+        "subtype": orchestrator.current_subtype_key(),
+        "task_queue": context.task_queue,
+    });
+    let mut report = format!(
+        "**Dry run:** planned {} task(s). No tools were executed.\n```json\n{}\n```",
+        context.task_queue.tasks.len(),
+        serde_json::to_string_pretty(&plan).unwrap_or_default()
+    );
+    if !said.is_empty() {
+        report.push_str("\n\nThe agent would have told the user:\n");
+        report.push_str(said);
+    }
+    report
+}
+
+/// Final response of a dry run that ended without a plan, only a reply
+pub(super) fn dry_run_reply_report(said: &str) -> String {
+    format!(
+        "**Dry run:** no tasks were planned. The agent would have told the user:\n{}",
+        said
+    )
+}
+
+impl MessageDispatcher {
+    /// Handle "/plan <request>": plan the request in a dry run and return the task queue
+    pub(super) async fn handle_plan_command(&self, message: &NormalizedMessage) -> Option<DispatchResult> {
+        let text = message.text.trim();
+        let rest = text.get(..5).filter(|p| p.eq_ignore_ascii_case("/plan")).map(|_| &text[5..])?;
+        if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let request = rest.trim();
+        if request.is_empty() {
+            let response = "Usage: `/plan <request>` plans the request without running any tools.".to_string();
+            self.broadcaster.broadcast(GatewayEvent::agent_response(
+                message.channel_id,
+                &message.user_name,
+                &response,
+            ));
+            return Some(DispatchResult::success(response));
+        }
+
+        log::info!(
+            "[DRY_RUN] Planning dry run for channel {}: {}",
+            message.channel_id,
+            crate::text::truncate_chars(request, 100)
+        );
+
+        let session_key = Database::dry_run_session_key(
+            &message.channel_type,
+            message.channel_id,
+            &message.chat_id,
+        );
+        // Clear a session left behind by an interrupted run
+        self.delete_dry_run_session(&session_key);
+
+        let dry_run = NormalizedMessage {
+            text: request.to_string(),
+            message_id: None,
+            ephemeral: true,
+            dry_run: true,
+            ..message.clone()
+        };
+        let result = self.run_message(dry_run).await;
+
+        self.delete_dry_run_session(&session_key);
+        Some(result)
+    }
+
+    fn delete_dry_run_session(&self, session_key: &str) {
+        if let Ok(Some(session)) = self.db.get_chat_session_by_key(session_key) {
+            if let Err(e) = self.db.delete_chat_session(session.id) {
+                log::warn!("[DRY_RUN] Failed to delete dry-run session {}: {}", session.id, e);
+            }
+        }
+    }
+}
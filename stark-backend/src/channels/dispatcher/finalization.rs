@@ -1,10 +1,14 @@
+use crate::text::truncate_chars;
 use crate::ai::multi_agent::Orchestrator;
 use crate::channels::types::NormalizedMessage;
 use crate::models::session_message::MessageRole as DbMessageRole;
-use crate::models::CompletionStatus;
+use crate::models::{CompletionStatus, FailureCategory, SessionFailureReason};
 use crate::telemetry::Watchdog;
 use std::sync::Arc;
+use std::time::Duration;
 
+use super::dry_run;
+use super::x402_budget::SessionSpend;
 use super::MessageDispatcher;
 
 /// Result of attempting to advance to the next task in the queue
@@ -37,15 +41,28 @@ impl MessageDispatcher {
             let identity_id = if is_safe_mode { Some("safemode") } else { None };
             let entry = format!(
                 "\n### Session completed\n**User:** {}\n**Response:** {}\n",
-                user_input.chars().take(500).collect::<String>(),
-                bot_response.chars().take(1000).collect::<String>(),
+                truncate_chars(user_input, 500),
+                truncate_chars(bot_response, 1000),
             );
-            if let Err(e) = store.append_daily_log(&entry, identity_id) {
-                log::error!("[SESSION_MEMORY] Failed to append daily log: {}", e);
+            match store.append_daily_log_deduped(&entry, identity_id, self.memory_config.dedup_threshold) {
+                Ok(false) => log::debug!("[SESSION_MEMORY] Skipped near-duplicate session entry"),
+                Ok(true) => {}
+                Err(e) => log::error!("[SESSION_MEMORY] Failed to append daily log: {}", e),
             }
         }
     }
 
+    /// Persist a mid-loop checkpoint of the orchestrator context. The saved copy
+    /// is flagged `loop_checkpoint` so that, if the process dies before the loop
+    /// finalizes, the next message restores the task queue and resumes from here.
+    pub(super) fn checkpoint_agent_context(&self, session_id: i64, orchestrator: &Orchestrator) {
+        let mut context = orchestrator.context().clone();
+        context.loop_checkpoint = true;
+        if let Err(e) = self.db.save_agent_context(session_id, &context) {
+            log::warn!("[MULTI_AGENT] Failed to checkpoint context for session {}: {}", session_id, e);
+        }
+    }
+
     /// Try to advance to the next task in the queue.
     /// If a next task exists, marks it as in_progress and broadcasts updates.
     /// If no tasks remain, marks the session as complete in the database and broadcasts completion.
@@ -70,6 +87,8 @@ impl MessageDispatcher {
                 &next_task.description,
             );
             self.broadcast_task_queue_update(channel_id, session_id, orchestrator);
+            // Completed tasks survive a crash from here on
+            self.checkpoint_agent_context(session_id, orchestrator);
             TaskAdvanceResult::NextTaskStarted
         } else if orchestrator.task_queue_is_empty() || orchestrator.all_tasks_complete() {
             // Queue is empty or all tasks completed - end the session
@@ -91,6 +110,8 @@ impl MessageDispatcher {
     /// Finalization logic shared by both native and text tool loop paths:
     /// clearing active skill, saving orchestrator context, updating completion status,
     /// saving cancellation/max-iteration summaries, building final return value.
+    /// `time_limit_reached` is set when the loop stopped on the session wall-clock limit,
+    /// `budget_exceeded` when it stopped on the session's x402 budget.
     #[allow(clippy::too_many_arguments)]
     pub(super) fn finalize_tool_loop(
         &self,
@@ -108,6 +129,8 @@ impl MessageDispatcher {
         user_question_content: &str,
         max_tool_iterations: usize,
         iterations: usize,
+        time_limit_reached: Option<Duration>,
+        budget_exceeded: Option<SessionSpend>,
         watchdog: &Arc<Watchdog>,
     ) -> Result<(String, bool), String> {
         // Returns (response_text, already_delivered_via_say_to_user)
@@ -139,6 +162,8 @@ impl MessageDispatcher {
             self.broadcast_session_complete(original_message.channel_id, session_id);
             if memory_suppressed {
                 log::info!("[ORCHESTRATED_LOOP] Skipping session memory — memory-excluded tool was called");
+            } else if original_message.ephemeral {
+                log::info!("[ORCHESTRATED_LOOP] Skipping session memory — ephemeral run");
             } else {
                 // Prefer say_to_user content for memory, fall back to task_fully_completed summary
                 let memory_content = if !last_say_to_user_content.is_empty() {
@@ -186,18 +211,61 @@ impl MessageDispatcher {
 
         // Build final return: (response, already_delivered_via_say_to_user)
         if waiting_for_user_response {
-            // Save the tool call log to the orchestrator context
-            if !tool_call_log.is_empty() {
-                let context_summary = format!(
-                    "Before asking the user, I already completed these actions:\n{}",
-                    tool_call_log.join("\n")
-                );
-                orchestrator.context_mut().waiting_for_user_context = Some(context_summary);
+            // Save the pending question and the tool call log so the reply's turn
+            // resumes from here. A plan awaiting approval has its own resume path.
+            if !orchestrator.awaiting_plan_approval() {
+                orchestrator.set_pending_user_question(user_question_content, tool_call_log);
                 if let Err(e) = self.db.save_agent_context(session_id, orchestrator.context()) {
                     log::warn!("[MULTI_AGENT] Failed to save context with user_context: {}", e);
                 }
             }
             Ok((user_question_content.to_string(), false))
+        } else if let Some(limit) = time_limit_reached {
+            // Stopped on the session wall-clock limit — end gracefully with what was done
+            log::info!("[ORCHESTRATED_LOOP] Marking session {} as Failed (time limit)", session_id);
+            let _ = self.db.mark_session_failed(
+                session_id,
+                &SessionFailureReason::new(
+                    FailureCategory::TimeLimit,
+                    format!("Session time limit reached ({}s)", limit.as_secs()),
+                ),
+            );
+            self.broadcast_session_complete(original_message.channel_id, session_id);
+            let work = if tool_call_log.is_empty() {
+                "No tool work was completed.".to_string()
+            } else {
+                format!("Work completed before the limit:\n{}", tool_call_log.join("\n"))
+            };
+            Ok((
+                format!(
+                    "⏱️ Session time limit reached ({}s). Stopping here.\n{}",
+                    limit.as_secs(),
+                    work
+                ),
+                false,
+            ))
+        } else if let Some(spend) = budget_exceeded {
+            // Stopped before a paid call would overrun the x402 budget
+            let message = format!(
+                "x402 budget reached: spent {:.6} of {:.6} USDC in this session",
+                spend.spent,
+                spend.max.unwrap_or_default()
+            );
+            log::info!("[ORCHESTRATED_LOOP] Marking session {} as Failed (x402 budget)", session_id);
+            let _ = self.db.mark_session_failed(
+                session_id,
+                &SessionFailureReason::new(FailureCategory::Budget, message.clone()),
+            );
+            self.broadcast_session_complete(original_message.channel_id, session_id);
+            let work = if tool_call_log.is_empty() {
+                "No tool work was completed.".to_string()
+            } else {
+                format!("Work completed before the budget ran out:\n{}", tool_call_log.join("\n"))
+            };
+            Ok((format!("💸 {}. Stopping here.\n{}", message, work), false))
+        } else if !last_say_to_user_content.is_empty() && original_message.dry_run {
+            // A dry run held say_to_user back, so nothing was broadcast yet
+            Ok((dry_run::dry_run_reply_report(last_say_to_user_content), false))
         } else if !last_say_to_user_content.is_empty() {
             // say_to_user content IS the final result — already broadcast via tool.result event.
             // dispatch() will store it as assistant message but should NOT re-broadcast.
@@ -207,7 +275,13 @@ impl MessageDispatcher {
             Ok((final_summary.to_string(), false))
         } else if tool_call_log.is_empty() {
             // Mark session as Failed — hit max iterations with no work done
-            let _ = self.db.update_session_completion_status(session_id, CompletionStatus::Failed);
+            let _ = self.db.mark_session_failed(
+                session_id,
+                &SessionFailureReason::new(
+                    FailureCategory::MaxIterations,
+                    format!("Tool loop hit max iterations ({})", max_tool_iterations),
+                ),
+            );
             self.broadcast_session_complete(original_message.channel_id, session_id);
             Err(format!(
                 "Tool loop hit max iterations ({}) without completion",
@@ -215,7 +289,13 @@ impl MessageDispatcher {
             ))
         } else {
             // Max iterations with work done — mark as Failed (didn't complete normally)
-            let _ = self.db.update_session_completion_status(session_id, CompletionStatus::Failed);
+            let _ = self.db.mark_session_failed(
+                session_id,
+                &SessionFailureReason::new(
+                    FailureCategory::MaxIterations,
+                    format!("Tool loop hit max iterations ({})", max_tool_iterations),
+                ),
+            );
             self.broadcast_session_complete(original_message.channel_id, session_id);
             let summary = format!(
                 "[Session hit max iterations. Work completed before limit:]\n{}",
@@ -1,22 +1,26 @@
 mod system_prompt;
 
+use crate::text::truncate_chars;
 use crate::ai::{
-    multi_agent::{types::{self as agent_types, AgentMode}, Orchestrator, SubAgentManager},
+    multi_agent::{types::{self as agent_types, AgentMode}, Orchestrator, PlanReply, SubAgentManager},
     AiClient, ArchetypeId, ArchetypeRegistry, Message, MessageRole, ModelArchetype,
-    ThinkingLevel,
+    SamplingParams,
 };
 use crate::channels::types::{DispatchResult, NormalizedMessage};
 use crate::config::MemoryConfig;
-use crate::context::{self, estimate_tokens, ContextManager};
+use crate::context::ContextManager;
 use crate::db::Database;
 use crate::execution::{ExecutionTracker, SessionLaneManager};
 use crate::gateway::events::EventBroadcaster;
 use crate::gateway::protocol::GatewayEvent;
 use crate::models::session_message::MessageRole as DbMessageRole;
-use crate::models::{AgentSettings, CompletionStatus, SessionScope, SpecialRoleGrants, DEFAULT_MAX_TOOL_ITERATIONS};
+use crate::models::{
+    AgentSettings, CompletionStatus, FailureCategory, SessionFailureReason, SessionScope, SpecialRoleGrants,
+    DEFAULT_MAX_TOOL_ITERATIONS,
+};
 use crate::qmd_memory::MemoryStore;
 use crate::telemetry::{
-    self, Rollout, RolloutConfig, RolloutManager, SpanCollector, SpanType,
+    self, FailureReason, RolloutConfig, RolloutManager, SpanType,
     RewardEmitter, TelemetryStore, Watchdog, WatchdogConfig, ResourceManager,
 };
 use crate::tools::{ToolConfig, ToolContext, ToolDefinition, ToolExecution, ToolRegistry};
@@ -26,15 +30,60 @@ use std::sync::Arc;
 use std::time::Duration;
 mod broadcasting;
 mod commands;
+mod compare;
+mod dry_run;
 mod finalization;
 mod skills;
 mod tool_loop;
 mod tool_processing;
+mod x402_budget;
+
+use tool_loop::LoopDetection;
 
 /// Fallback maximum tool iterations (used when db lookup fails)
 /// Actual value is configurable via bot settings
 pub(super) const FALLBACK_MAX_TOOL_ITERATIONS: usize = DEFAULT_MAX_TOOL_ITERATIONS as usize;
 
+/// How long a dispatched platform message id is remembered; a redelivery of the
+/// same message within this window is ignored
+pub const PROCESSED_MESSAGE_TTL_HOURS: i64 = 24;
+
+/// A platform message claimed for dispatch. The claim is released again unless
+/// the dispatch succeeds, so a redelivery of a message whose dispatch failed or
+/// panicked is processed instead of ignored.
+struct ProcessedMessageClaim<'a> {
+    db: &'a Database,
+    channel_type: String,
+    channel_id: i64,
+    chat_id: String,
+    message_id: String,
+    keep: bool,
+}
+
+impl<'a> ProcessedMessageClaim<'a> {
+    fn new(db: &'a Database, message: &NormalizedMessage, message_id: &str) -> Self {
+        Self {
+            db,
+            channel_type: message.channel_type.clone(),
+            channel_id: message.channel_id,
+            chat_id: message.chat_id.clone(),
+            message_id: message_id.to_string(),
+            keep: false,
+        }
+    }
+}
+
+impl Drop for ProcessedMessageClaim<'_> {
+    fn drop(&mut self) {
+        if self.keep {
+            return;
+        }
+        if let Err(e) = self.db.release_processed_message(&self.channel_type, self.channel_id, &self.chat_id, &self.message_id) {
+            log::warn!("[DISPATCH] Failed to release claim on message {}: {}", self.message_id, e);
+        }
+    }
+}
+
 /// Dispatcher routes messages to the AI and returns responses
 pub struct MessageDispatcher {
     db: Arc<Database>,
@@ -43,10 +92,10 @@ pub struct MessageDispatcher {
     execution_tracker: Arc<ExecutionTracker>,
     /// Async write-behind buffer for tool call/result session messages
     session_writer: crate::channels::session_writer::SessionMessageWriter,
-    /// Wallet provider for x402 payments and transaction signing
-    /// Encapsulates both Standard mode (EnvWalletProvider with raw private key)
-    /// and Flash mode (FlashWalletProvider with Privy proxy)
-    wallet_provider: Option<Arc<dyn crate::wallet::WalletProvider>>,
+    /// Wallets for x402 payments and transaction signing, resolved per channel/identity.
+    /// The default wallet encapsulates both Standard mode (EnvWalletProvider with raw
+    /// private key) and Flash mode (FlashWalletProvider with Privy proxy)
+    wallet_registry: Arc<crate::wallet::WalletRegistry>,
     context_manager: ContextManager,
     archetype_registry: ArchetypeRegistry,
     /// Memory configuration (simplified - no longer using memory markers)
@@ -75,9 +124,22 @@ pub struct MessageDispatcher {
     watchdog_config: WatchdogConfig,
     /// Session lane manager for serializing requests per channel/session
     session_lanes: Arc<SessionLaneManager>,
+    /// Per-identity message rate limit (configured in bot settings)
+    identity_rate_limiter: crate::channels::identity_rate_limiter::IdentityRateLimiter,
+    /// Cooldown for repeated agent_warning broadcasts per (channel, warning type)
+    warning_throttle: crate::channels::util::WarningThrottle,
+    /// Cached static system prompt sections, keyed by channel/subtype/tool config
+    system_prompt_cache: system_prompt::SystemPromptCache,
+    /// Message features per channel type (tool gating, response formatting)
+    channel_capabilities: Arc<crate::channels::ChannelCapabilityRegistry>,
+    /// Session reward scoring handed to each rollout's config
+    reward_function: Arc<dyn crate::telemetry::RewardFunction>,
     /// Mock AI client for integration tests (bypasses real AI API)
     #[cfg(test)]
     mock_ai_client: Option<crate::ai::MockAiClient>,
+    /// Mock AI client standing in for skill `model_override` clients in tests
+    #[cfg(test)]
+    skill_mock_ai_client: Option<crate::ai::MockAiClient>,
 }
 
 impl MessageDispatcher {
@@ -153,7 +215,8 @@ impl MessageDispatcher {
 
         // Create context manager and link memory store to it
         let mut context_manager = ContextManager::new(db.clone())
-            .with_memory_config(memory_config.clone());
+            .with_memory_config(memory_config.clone())
+            .with_keep_recent_tool_pairs(crate::config::compaction_keep_tool_pairs());
         if let Some(ref store) = memory_store {
             context_manager = context_manager.with_memory_store(store.clone());
             log::info!("[DISPATCHER] Memory store linked to context manager");
@@ -173,7 +236,7 @@ impl MessageDispatcher {
             tool_registry,
             execution_tracker,
             session_writer,
-            wallet_provider,
+            wallet_registry: Arc::new(crate::wallet::WalletRegistry::new(wallet_provider)),
             context_manager,
             archetype_registry: ArchetypeRegistry::new(),
             memory_config,
@@ -189,8 +252,15 @@ impl MessageDispatcher {
             resource_manager,
             watchdog_config: WatchdogConfig::default(),
             session_lanes: SessionLaneManager::new(),
+            identity_rate_limiter: crate::channels::identity_rate_limiter::IdentityRateLimiter::new(),
+            warning_throttle: crate::channels::util::WarningThrottle::new(crate::config::agent_warning_cooldown()),
+            system_prompt_cache: system_prompt::SystemPromptCache::new(crate::config::system_prompt_cache_ttl()),
+            channel_capabilities: Arc::new(crate::channels::ChannelCapabilityRegistry::with_builtin_channels()),
+            reward_function: Arc::new(crate::telemetry::DefaultRewardFunction),
             #[cfg(test)]
             mock_ai_client: None,
+            #[cfg(test)]
+            skill_mock_ai_client: None,
         }
     }
 
@@ -226,6 +296,24 @@ impl MessageDispatcher {
         self
     }
 
+    /// Share a wallet registry (e.g. with the API layer that manages channel wallets)
+    pub fn with_wallet_registry(mut self, wallet_registry: Arc<crate::wallet::WalletRegistry>) -> Self {
+        self.wallet_registry = wallet_registry;
+        self
+    }
+
+    /// Share a channel capability registry (e.g. the ChannelManager's)
+    pub fn with_channel_capabilities(mut self, capabilities: Arc<crate::channels::ChannelCapabilityRegistry>) -> Self {
+        self.channel_capabilities = capabilities;
+        self
+    }
+
+    /// Score session rewards with a custom function (set on every rollout's config)
+    pub fn with_reward_function(mut self, reward_function: Arc<dyn crate::telemetry::RewardFunction>) -> Self {
+        self.reward_function = reward_function;
+        self
+    }
+
     /// Set a mock AI client for integration tests (bypasses real AI API)
     #[cfg(test)]
     pub fn with_mock_ai_client(mut self, client: crate::ai::MockAiClient) -> Self {
@@ -233,6 +321,13 @@ impl MessageDispatcher {
         self
     }
 
+    /// Set the mock AI client used while a skill with a `model_override` is active
+    #[cfg(test)]
+    pub fn with_skill_mock_ai_client(mut self, client: crate::ai::MockAiClient) -> Self {
+        self.skill_mock_ai_client = Some(client);
+        self
+    }
+
     #[cfg(test)]
     pub fn get_mock_trace(&self) -> Vec<crate::ai::TraceEntry> {
         self.mock_ai_client.as_ref().map(|m| m.get_trace()).unwrap_or_default()
@@ -252,7 +347,8 @@ impl MessageDispatcher {
 
         // Create context manager and link memory store to it
         let mut context_manager = ContextManager::new(db.clone())
-            .with_memory_config(memory_config.clone());
+            .with_memory_config(memory_config.clone())
+            .with_keep_recent_tool_pairs(crate::config::compaction_keep_tool_pairs());
         if let Some(ref store) = memory_store {
             context_manager = context_manager.with_memory_store(store.clone());
         }
@@ -269,7 +365,7 @@ impl MessageDispatcher {
             tool_registry: Arc::new(ToolRegistry::new()),
             execution_tracker,
             session_writer,
-            wallet_provider: None,
+            wallet_registry: Arc::new(crate::wallet::WalletRegistry::new(None)),
             context_manager,
             archetype_registry: ArchetypeRegistry::new(),
             memory_config,
@@ -285,8 +381,15 @@ impl MessageDispatcher {
             resource_manager,
             watchdog_config: WatchdogConfig::default(),
             session_lanes: SessionLaneManager::new(),
+            identity_rate_limiter: crate::channels::identity_rate_limiter::IdentityRateLimiter::new(),
+            warning_throttle: crate::channels::util::WarningThrottle::new(crate::config::agent_warning_cooldown()),
+            system_prompt_cache: system_prompt::SystemPromptCache::new(crate::config::system_prompt_cache_ttl()),
+            channel_capabilities: Arc::new(crate::channels::ChannelCapabilityRegistry::with_builtin_channels()),
+            reward_function: Arc::new(crate::telemetry::DefaultRewardFunction),
             #[cfg(test)]
             mock_ai_client: None,
+            #[cfg(test)]
+            skill_mock_ai_client: None,
         }
     }
 
@@ -300,6 +403,71 @@ impl MessageDispatcher {
         self.subagent_manager.clone()
     }
 
+    /// Get the WalletRegistry
+    pub fn wallet_registry(&self) -> &Arc<crate::wallet::WalletRegistry> {
+        &self.wallet_registry
+    }
+
+    /// Create the AI client for the first usable model in `settings`' fallback
+    /// chain at or after `start`, returning it with its index in the chain.
+    fn create_ai_client(
+        &self,
+        settings: &AgentSettings,
+        wallet_provider: Option<Arc<dyn crate::wallet::WalletProvider>>,
+        channel_id: i64,
+        start: usize,
+    ) -> Result<(AiClient, usize), String> {
+        #[cfg(test)]
+        if let Some(ref mock) = self.mock_ai_client {
+            return Ok((AiClient::Mock(mock.clone()), start));
+        }
+        AiClient::from_settings_chain(settings, wallet_provider, start)
+            .map(|(client, idx)| (client.with_broadcaster(Arc::clone(&self.broadcaster), channel_id), idx))
+    }
+
+    /// Sampling parameters for an AI call: the active agent settings (or the
+    /// message's settings override), with the current subtype's temperature/top_p
+    /// taking precedence when set.
+    pub(crate) fn effective_sampling(&self, settings_override: Option<&AgentSettings>, subtype_key: &str) -> SamplingParams {
+        let base = match settings_override {
+            Some(settings) => settings.sampling(),
+            None => self
+                .db
+                .get_active_agent_settings()
+                .ok()
+                .flatten()
+                .map(|s| s.sampling())
+                .unwrap_or_default(),
+        };
+        base.with_overrides(agent_types::sampling_for_subtype(subtype_key))
+    }
+
+    /// Resolve the wallet for a channel/identity, loading the channel's wallet from
+    /// its `wallet_key_env` setting on first use. Falls back to the default wallet.
+    pub fn resolve_wallet_provider(
+        &self,
+        channel_id: i64,
+        identity_id: &str,
+    ) -> Option<Arc<dyn crate::wallet::WalletProvider>> {
+        if !self.wallet_registry.has_channel_wallet(channel_id) {
+            let env_var = self
+                .db
+                .get_channel_setting(channel_id, "wallet_key_env")
+                .ok()
+                .flatten()
+                .filter(|v| !v.trim().is_empty());
+            if let Some(env_var) = env_var {
+                if let Err(e) = self.wallet_registry.load_channel_wallet_from_env(channel_id, env_var.trim()) {
+                    log::warn!(
+                        "[DISPATCH] Channel {} wallet not loaded, using default: {}",
+                        channel_id, e
+                    );
+                }
+            }
+        }
+        self.wallet_registry.resolve(channel_id, Some(identity_id))
+    }
+
     /// Get the TelemetryStore
     pub fn telemetry_store(&self) -> &Arc<TelemetryStore> {
         &self.telemetry_store
@@ -310,6 +478,88 @@ impl MessageDispatcher {
         &self.resource_manager
     }
 
+    /// Get the RolloutManager
+    pub fn rollout_manager(&self) -> &Arc<RolloutManager> {
+        &self.rollout_manager
+    }
+
+    /// Get or create the session a (non-gateway) message belongs to. Safe-mode
+    /// messages in a thread get a session of their own, so untrusted users never
+    /// share history or the safe-mode flag with the thread's other users; so do
+    /// planning dry runs.
+    pub(super) fn get_or_create_message_session(
+        &self,
+        message: &NormalizedMessage,
+        scope: SessionScope,
+    ) -> rusqlite::Result<crate::models::ChatSession> {
+        if message.dry_run {
+            self.db.get_or_create_dry_run_chat_session(
+                &message.channel_type,
+                message.channel_id,
+                &message.chat_id,
+                scope,
+            )
+        } else if message.is_thread && message.force_safe_mode {
+            self.db.get_or_create_safe_mode_chat_session(
+                &message.channel_type,
+                message.channel_id,
+                &message.chat_id,
+                scope,
+                None,
+            )
+        } else {
+            self.db.get_or_create_chat_session(
+                &message.channel_type,
+                message.channel_id,
+                &message.chat_id,
+                scope,
+                None,
+            )
+        }
+    }
+
+    /// Apply the per-identity rate limit from bot settings. Scheduler messages and
+    /// users holding a special role are exempt. Returns the rejection when throttled.
+    fn check_identity_rate_limit(&self, message: &NormalizedMessage) -> Option<DispatchResult> {
+        let settings = self.db.get_bot_settings().ok()?;
+        if settings.rate_limit_per_minute <= 0 || message.user_id == "system" {
+            return None;
+        }
+        let has_special_role = self.db
+            .get_special_role_grants(&message.channel_type, &message.user_id)
+            .map(|grants| grants.role_name.is_some())
+            .unwrap_or(false);
+        if has_special_role {
+            return None;
+        }
+        let identity = self.db
+            .get_or_create_identity(&message.channel_type, &message.user_id, Some(&message.user_name))
+            .ok()?;
+
+        let retry_after = self.identity_rate_limiter
+            .check(
+                &identity.identity_id,
+                settings.rate_limit_per_minute as u32,
+                settings.rate_limit_burst.max(1) as u32,
+            )
+            .err()?;
+        let retry_after_secs = retry_after.as_secs().max(1);
+        log::info!(
+            "[DISPATCH] Rate limited {} ({}) on channel {}, retry in {}s",
+            message.user_name, identity.identity_id, message.channel_id, retry_after_secs
+        );
+        self.broadcaster.broadcast(GatewayEvent::agent_rate_limited(
+            message.channel_id,
+            &message.user_name,
+            retry_after_secs,
+        ));
+        Some(DispatchResult::error(format!(
+            "You're sending messages a little too quickly. Please wait {} second{} and try again.",
+            retry_after_secs,
+            if retry_after_secs == 1 { "" } else { "s" }
+        )))
+    }
+
     /// Panic-safe dispatch wrapper.
     ///
     /// Catches any panic inside `dispatch()` and returns a `DispatchResult::error`
@@ -352,23 +602,119 @@ impl MessageDispatcher {
             &message.text,
         ));
 
+        // Task cancellation targets the run in progress, so it must not wait for its lane
+        if let Some(response) = self.handle_cancel_task_command(&message) {
+            return response;
+        }
+
         // Acquire session lane to serialize requests for the same channel/chat.
         // This prevents concurrent dispatches from racing on session creation,
         // context building, and tool execution for the same conversation.
         let lane_key = format!("{}:{}:{}", message.channel_type, message.channel_id, message.chat_id);
         let _lane_guard = self.session_lanes.acquire(&lane_key).await;
 
+        // Skip platform redeliveries (webhook retries, reconnects) of a message already dispatched
+        let mut claim = None;
+        if let Some(message_id) = message.message_id.as_deref() {
+            let expired_before = Utc::now() - chrono::Duration::hours(PROCESSED_MESSAGE_TTL_HOURS);
+            match self.db.claim_processed_message(
+                &message.channel_type,
+                message.channel_id,
+                &message.chat_id,
+                message_id,
+                expired_before,
+            ) {
+                Ok(true) => claim = Some(ProcessedMessageClaim::new(&self.db, &message, message_id)),
+                Ok(false) => {
+                    log::info!(
+                        "[DISPATCH] Ignoring redelivered message {} on {} channel {}",
+                        message_id, message.channel_type, message.channel_id
+                    );
+                    return DispatchResult::success(String::new());
+                }
+                // Never drop a message because the idempotency check failed
+                Err(e) => log::warn!("[DISPATCH] Failed to record message {}: {}", message_id, e),
+            }
+        }
+
+        let result = self.dispatch_in_lane(message).await;
+        if let Some(mut claim) = claim {
+            claim.keep = result.error.is_none();
+        }
+        result
+    }
+
+    /// Dispatch a message once its session lane is held and it is known not to be a redelivery
+    async fn dispatch_in_lane(&self, message: NormalizedMessage) -> DispatchResult {
         // Check for reset commands
         let text_lower = message.text.trim().to_lowercase();
         if text_lower == "/new" || text_lower == "/reset" {
             return self.handle_reset_command(&message).await;
         }
 
+        // Check for plan approval toggle (session-level setting)
+        if let Some(response) = self.handle_plan_approval_command(&message).await {
+            return response;
+        }
+
+        // Check for message pinning ("/pin", "/unpin")
+        if let Some(response) = self.handle_pin_command(&message) {
+            return response;
+        }
+
+        // Check for public profile management ("/profile ...")
+        if let Some(response) = self.handle_profile_command(&message).await {
+            return response;
+        }
+
         // Check for thinking directives (session-level setting)
         if let Some(thinking_response) = self.handle_thinking_directive(&message).await {
             return thinking_response;
         }
 
+        // Throttle per identity before anything that runs the AI loop
+        if let Some(response) = self.check_identity_rate_limit(&message) {
+            return response;
+        }
+
+        // Take a system-wide rollout slot; when all are busy, queue (bounded, with timeout)
+        let _rollout_slot = match self.rollout_manager.try_acquire_slot() {
+            Some(slot) => slot,
+            None => {
+                let concurrency = self.rollout_manager.concurrency();
+                log::info!(
+                    "[DISPATCH] All {} rollout slots busy ({} queued), queuing message for channel {}",
+                    concurrency.max_concurrent, concurrency.queued, message.channel_id
+                );
+                self.broadcaster.broadcast(GatewayEvent::rollout_queued(
+                    message.channel_id,
+                    &message.chat_id,
+                    concurrency.running,
+                    concurrency.queued + 1,
+                    concurrency.max_concurrent,
+                ));
+                match self.rollout_manager.wait_for_slot().await {
+                    Ok(slot) => slot,
+                    Err(e) => {
+                        log::warn!("[DISPATCH] Rejected message for channel {}: {}", message.channel_id, e);
+                        return DispatchResult::error(e);
+                    }
+                }
+            }
+        };
+
+        // Check for a planning dry run ("/plan <request>"); throttled and holding
+        // a rollout slot like any other run
+        if let Some(response) = self.handle_plan_command(&message).await {
+            return response;
+        }
+
+        self.run_message(message).await
+    }
+
+    /// Run the AI loop for a message once it holds a rollout slot
+    async fn run_message(&self, message: NormalizedMessage) -> DispatchResult {
+
         // Parse inline thinking directive and extract clean message
         let (thinking_level, clean_text) = commands::parse_inline_thinking(&message.text);
 
@@ -383,7 +729,11 @@ impl MessageDispatcher {
 
         // Initialize telemetry rollout for this dispatch
         // We use session_id=0 initially; it will be updated once the session is resolved
-        let rollout_config = RolloutConfig::default();
+        let rollout_config = RolloutConfig {
+            snapshot_tool_history: crate::config::rollout_snapshot_tool_history(),
+            reward_function: Arc::clone(&self.reward_function),
+            ..RolloutConfig::default()
+        };
         let (mut rollout, span_collector) = self.rollout_manager.start_rollout(
             0, // will be updated once we have the session
             message.channel_id,
@@ -391,10 +741,27 @@ impl MessageDispatcher {
         );
         let span_collector = Arc::new(span_collector);
 
-        // Set up the watchdog for timeout enforcement
-        let reward_emitter = Arc::new(RewardEmitter::new(Arc::clone(&span_collector)));
+        // Set up the watchdog for timeout enforcement (tool timeouts can be tuned in bot settings)
+        let reward_emitter = Arc::new(
+            RewardEmitter::new(Arc::clone(&span_collector))
+                .with_reward_function(Arc::clone(&rollout.config.reward_function)),
+        );
+        let mut watchdog_config = match self.db.get_bot_settings().ok().and_then(|s| s.tool_timeouts) {
+            Some(timeouts) => self.watchdog_config.clone().with_tool_timeouts(&timeouts),
+            None => self.watchdog_config.clone(),
+        };
+        // Per-channel session time limit overrides the bot-wide default
+        if let Some(secs) = self.db
+            .get_channel_setting(message.channel_id, "session_time_limit_secs")
+            .ok()
+            .flatten()
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .filter(|&n| n > 0)
+        {
+            watchdog_config = watchdog_config.with_session_timeout(std::time::Duration::from_secs(secs));
+        }
         let watchdog = Watchdog::new(
-            self.watchdog_config.clone(),
+            watchdog_config,
             Arc::clone(&span_collector),
             Arc::clone(&reward_emitter),
         );
@@ -420,8 +787,9 @@ impl MessageDispatcher {
         rollout_span.succeed();
         span_collector.record(rollout_span);
 
-        // Track the resource version used
-        rollout.resources_id = self.resource_manager.active_version_id();
+        // Track the resource version used (the channel's pin, else the global active one)
+        let resources_id = self.resource_manager.effective_version_id(message.channel_id);
+        self.rollout_manager.set_resources(&mut rollout, resources_id);
 
         // Get or create identity for the user
         let identity = match self.db.get_or_create_identity(
@@ -461,6 +829,9 @@ impl MessageDispatcher {
                 }
                 _ => SessionScope::Dm, // fallback
             }
+        } else if message.is_thread {
+            // A thread is one shared conversation
+            SessionScope::Group
         } else {
             // Original logic for non-cron messages
             if message.chat_id != message.user_id {
@@ -473,32 +844,55 @@ impl MessageDispatcher {
         // For gateway channels (Discord, Telegram), create a fresh session for each message
         // to prevent context from growing too large. Previous conversation context is
         // preserved by including the last 10 messages in the system prompt.
+        // Thread messages instead keep one session per thread, like any other group chat.
         let channel_type_lower = message.channel_type.to_lowercase();
-        let is_gateway_channel = channel_type_lower == "discord" || channel_type_lower == "telegram";
+        let is_gateway_channel = !message.is_thread
+            && !message.dry_run
+            && (channel_type_lower == "discord" || channel_type_lower == "telegram");
+
+        // A message arriving within the grace window continues the previous session,
+        // so a quick burst of messages stays one conversation
+        let gateway_grace_secs = self.db
+            .get_channel_setting(message.channel_id, "gateway_session_grace_secs")
+            .ok()
+            .flatten()
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .unwrap_or_else(crate::config::gateway_session_grace_secs);
+        let mut reused_gateway_session = None;
 
         // Collect previous session messages for gateway channels (max 10)
         let previous_gateway_messages: Vec<crate::models::SessionMessage> = if is_gateway_channel {
             const MAX_PREVIOUS_MESSAGES: i32 = 10;
 
             // Get the current active session (if any) and its messages
-            if let Ok(Some(prev_session)) = self.db.get_latest_session_for_channel(
+            if let Ok(Some(prev_session)) = self.db.get_latest_gateway_session(
                 &message.channel_type,
                 message.channel_id,
             ) {
-                let messages = self.db.get_recent_session_messages(prev_session.id, MAX_PREVIOUS_MESSAGES)
-                    .unwrap_or_default();
-
-                // Deactivate the old session
-                if let Err(e) = self.db.deactivate_session(prev_session.id) {
-                    log::warn!("[DISPATCH] Failed to deactivate previous session {}: {}", prev_session.id, e);
-                } else {
+                let idle_secs = (chrono::Utc::now() - prev_session.last_activity_at).num_seconds();
+                if gateway_grace_secs > 0 && idle_secs < gateway_grace_secs as i64 {
                     log::info!(
-                        "[DISPATCH] Deactivated previous {} session {} with {} messages for context",
-                        message.channel_type, prev_session.id, messages.len()
+                        "[DISPATCH] Reusing {} session {} (last activity {}s ago, grace {}s)",
+                        message.channel_type, prev_session.id, idle_secs, gateway_grace_secs
                     );
-                }
+                    reused_gateway_session = Some(prev_session);
+                    vec![]
+                } else {
+                    let messages = self.db.get_recent_session_messages(prev_session.id, MAX_PREVIOUS_MESSAGES)
+                        .unwrap_or_default();
+
+                    // Deactivate the old session
+                    if let Err(e) = self.db.deactivate_session(prev_session.id) {
+                        log::warn!("[DISPATCH] Failed to deactivate previous session {}: {}", prev_session.id, e);
+                    } else {
+                        log::info!(
+                            "[DISPATCH] Deactivated previous {} session {} with {} messages for context",
+                            message.channel_type, prev_session.id, messages.len()
+                        );
+                    }
 
-                messages
+                    messages
+                }
             } else {
                 vec![]
             }
@@ -507,7 +901,13 @@ impl MessageDispatcher {
         };
 
         // Get or create chat session
-        let session = if is_gateway_channel {
+        let gateway_session_reused = reused_gateway_session.is_some();
+        let session = if let Some(s) = reused_gateway_session {
+            if let Err(e) = self.db.touch_chat_session(s.id) {
+                log::warn!("[DISPATCH] Failed to update activity for session {}: {}", s.id, e);
+            }
+            s
+        } else if is_gateway_channel {
             // Always create a fresh session for gateway channels
             match self.db.create_gateway_session(
                 &message.channel_type,
@@ -539,13 +939,7 @@ impl MessageDispatcher {
             }
         } else {
             // Standard session handling for other channels
-            match self.db.get_or_create_chat_session(
-                &message.channel_type,
-                message.channel_id,
-                &message.chat_id,
-                scope,
-                None,
-            ) {
+            match self.get_or_create_message_session(&message, scope) {
                 Ok(s) => s,
                 Err(e) => {
                     let error_msg = format!("Session error: {}", e);
@@ -565,7 +959,7 @@ impl MessageDispatcher {
         };
 
         // Now that session is resolved, update rollout and span collector with real session_id
-        rollout.session_id = session.id;
+        self.rollout_manager.set_session(&mut rollout, session.id);
         span_collector.set_session(session.id);
 
         // Reset session state when a new message comes in on a previously-completed session
@@ -590,11 +984,19 @@ impl MessageDispatcher {
             }
         }
 
+        // Resumed sessions: rebuild context_tokens from stored messages so any missed
+        // incremental update doesn't skew compaction decisions
+        if !is_gateway_channel || gateway_session_reused {
+            if let Err(e) = self.context_manager.refresh_context_tokens(session.id) {
+                log::warn!("[DISPATCH] Failed to recompute context tokens for session {}: {}", session.id, e);
+            }
+        }
+
         // Use clean text (with inline thinking directive removed) for storage
         let message_text = clean_text.as_deref().unwrap_or(&message.text);
 
         // Estimate tokens for the user message
-        let user_tokens = estimate_tokens(message_text);
+        let user_tokens = self.context_manager.estimate_tokens(session.id, message_text);
 
         // Store user message in session with token count
         if let Err(e) = self.db.add_session_message(
@@ -612,8 +1014,18 @@ impl MessageDispatcher {
             self.context_manager.update_context_tokens(session.id, user_tokens);
         }
 
-        // Get active agent settings from database, falling back to kimi defaults
-        let settings = match self.db.get_active_agent_settings() {
+        // Remember the user's message language for locale-aware responses
+        if !message.ephemeral {
+            self.remember_detected_language(message.channel_id, &identity.identity_id, message_text);
+        }
+
+        // Get active agent settings from database (unless overridden for this message),
+        // falling back to kimi defaults
+        let active_settings = match message.agent_settings_override {
+            Some(ref settings) => Ok(Some(settings.clone())),
+            None => self.db.get_active_agent_settings(),
+        };
+        let settings = match active_settings {
             Ok(Some(settings)) => settings,
             Ok(None) => {
                 log::info!("No agent configured, using default kimi settings");
@@ -628,7 +1040,10 @@ impl MessageDispatcher {
                     &format!("[Error] {}", error), None, None, None, None,
                 );
                 // Mark session as Failed so it doesn't stay stuck as Active
-                let _ = self.db.update_session_completion_status(session.id, CompletionStatus::Failed);
+                let _ = self.db.mark_session_failed(
+                    session.id,
+                    &SessionFailureReason::new(FailureCategory::Internal, &error),
+                );
                 self.broadcast_session_complete(message.channel_id, session.id);
                 self.execution_tracker.complete_execution(message.channel_id);
                 self.rollout_manager.fail_attempt(&mut rollout, &error, &span_collector);
@@ -640,7 +1055,7 @@ impl MessageDispatcher {
         };
 
         // Infer archetype from settings
-        let archetype_id = AiClient::infer_archetype(&settings);
+        let mut archetype_id = AiClient::infer_archetype(&settings);
         log::info!(
             "Using endpoint {} for message dispatch (archetype={}, max_response={}, max_context={})",
             settings.endpoint,
@@ -649,40 +1064,18 @@ impl MessageDispatcher {
             settings.max_context_tokens
         );
 
-        // Sync session's max_context_tokens with agent settings for dynamic compaction
+        // Sync session's max_context_tokens and token estimator with agent settings for dynamic compaction
         self.context_manager.sync_max_context_tokens(session.id, settings.max_context_tokens);
+        self.context_manager.sync_token_estimator(session.id, &settings.model_archetype, settings.model.as_deref());
 
-        // Create AI client — use mock in tests if configured, otherwise create from settings
-        #[cfg(test)]
-        let client = if let Some(ref mock) = self.mock_ai_client {
-            AiClient::Mock(mock.clone())
-        } else {
-            match AiClient::from_settings_with_wallet_provider(&settings, self.wallet_provider.clone()) {
-                Ok(c) => c.with_broadcaster(Arc::clone(&self.broadcaster), message.channel_id),
-                Err(e) => {
-                    let error = format!("Failed to create AI client: {}", e);
-                    log::error!("{}", error);
-                    // Store error as assistant message so it's visible in the session
-                    let _ = self.db.add_session_message(
-                        session.id, DbMessageRole::Assistant,
-                        &format!("[Error] {}", error), None, None, None, None,
-                    );
-                    // Mark session as Failed so it doesn't stay stuck as Active
-                    let _ = self.db.update_session_completion_status(session.id, CompletionStatus::Failed);
-                    self.broadcast_session_complete(message.channel_id, session.id);
-                    self.broadcaster.broadcast(GatewayEvent::agent_error(message.channel_id, &error));
-                    self.execution_tracker.complete_execution(message.channel_id);
-                    self.rollout_manager.fail_attempt(&mut rollout, &error, &span_collector);
-                    self.telemetry_store.persist_spans(&span_collector);
-                    heartbeat_handle.abort();
-                    telemetry::clear_active_collector();
-                    return DispatchResult::error(error);
-                }
-            }
-        };
-        #[cfg(not(test))]
-        let client = match AiClient::from_settings_with_wallet_provider(&settings, self.wallet_provider.clone()) {
-            Ok(c) => c.with_broadcaster(Arc::clone(&self.broadcaster), message.channel_id),
+        // Resolve which wallet pays for this channel/identity (channel → identity → default)
+        let wallet_provider = self.resolve_wallet_provider(message.channel_id, &identity.identity_id);
+
+        // Create AI client — use mock in tests if configured, otherwise create from settings.
+        // Models whose client can't be built are skipped in favour of the next fallback.
+        let model_chain = settings.model_chain();
+        let (mut client, mut model_idx) = match self.create_ai_client(&settings, wallet_provider.clone(), message.channel_id, 0) {
+            Ok(created) => created,
             Err(e) => {
                 let error = format!("Failed to create AI client: {}", e);
                 log::error!("{}", error);
@@ -692,7 +1085,10 @@ impl MessageDispatcher {
                     &format!("[Error] {}", error), None, None, None, None,
                 );
                 // Mark session as Failed so it doesn't stay stuck as Active
-                let _ = self.db.update_session_completion_status(session.id, CompletionStatus::Failed);
+                let _ = self.db.mark_session_failed(
+                    session.id,
+                    &SessionFailureReason::new(FailureCategory::ClientCreation, &error),
+                );
                 self.broadcast_session_complete(message.channel_id, session.id);
                 self.broadcaster.broadcast(GatewayEvent::agent_error(
                     message.channel_id,
@@ -706,13 +1102,39 @@ impl MessageDispatcher {
                 return DispatchResult::error(error);
             }
         };
+        if model_idx > 0 {
+            log::warn!(
+                "[DISPATCH] Primary model unavailable, starting on fallback '{}'",
+                model_chain[model_idx].model_label()
+            );
+            archetype_id = AiClient::infer_archetype(&model_chain[model_idx]);
+        }
+        self.rollout_manager.set_attempt_model(&mut rollout, &model_chain[model_idx].model_label());
+
+        if let Some(payer) = client.x402_wallet_address() {
+            log::info!("[DISPATCH] x402 payments for channel {} use wallet {}", message.channel_id, payer);
+        }
 
         // Add thinking event before AI generation
         self.execution_tracker.add_thinking(message.channel_id, "Processing request...");
 
         // Get tool configuration for this channel (needed for system prompt)
-        let mut tool_config = self.db.get_effective_tool_config(Some(message.channel_id))
-            .unwrap_or_default();
+        let mut tool_config = match self.db.get_effective_tool_config(Some(message.channel_id)) {
+            Ok(config) => config,
+            Err(e) => {
+                log::error!(
+                    "[DISPATCH] Failed to load tool config for channel {}: {} — using restricted fallback",
+                    message.channel_id, e
+                );
+                self.broadcast_agent_warning(
+                    message.channel_id,
+                    "tool_config_unavailable",
+                    "Tool configuration could not be loaded; only messaging tools are available until it is fixed.",
+                    0,
+                );
+                ToolConfig::load_failure_fallback()
+            }
+        };
 
         // Check channel safe_mode OR message-level force_safe_mode
         let channel_safe_mode = self.db.get_channel(message.channel_id)
@@ -809,9 +1231,12 @@ impl MessageDispatcher {
             }
         }
 
-        // Twitter has no interactive session — ask_user can never work, so block it.
-        if message.channel_type == "twitter" {
-            tool_config.deny_list.push("ask_user".to_string());
+        // Block tools the channel can't support (e.g. ask_user without an interactive session)
+        let capabilities = self.channel_capabilities.get(&message.channel_type);
+        for tool in capabilities.denied_tools() {
+            if !tool_config.deny_list.iter().any(|t| t == tool) {
+                tool_config.deny_list.push(tool.to_string());
+            }
         }
 
         // Debug: Log tool configuration
@@ -823,17 +1248,37 @@ impl MessageDispatcher {
         );
 
         // Build context from memories, tools, skills, and session history
-        let system_prompt = self.build_system_prompt(&message, &identity.identity_id, &tool_config, is_safe_mode, special_role_grants.as_ref());
+        let subtype_key = self.db.get_agent_context(session.id)
+            .ok()
+            .flatten()
+            .and_then(|ctx| ctx.subtype)
+            .unwrap_or_default();
+        let system_prompt = self.build_system_prompt(
+            &message,
+            &identity.identity_id,
+            &subtype_key,
+            &tool_config,
+            is_safe_mode,
+            special_role_grants.as_ref(),
+        );
 
         // Debug: Log full system prompt
         log::debug!("[DISPATCH] System prompt:\n{}", system_prompt);
 
         // Build context with cross-session memory integration
         let memory_identity: Option<&str> = if is_safe_mode { Some("safemode") } else { Some(&identity.identity_id) };
+        // Per-channel memory refresh interval overrides the global default
+        let memory_refresh_turns = self.db
+            .get_channel_setting(message.channel_id, "memory_refresh_turns")
+            .ok()
+            .flatten()
+            .and_then(|v| v.trim().parse::<u32>().ok())
+            .filter(|&n| n > 0);
         let (history, context_summary) = self.context_manager.build_context_with_memories(
             session.id,
             memory_identity,
             20,
+            memory_refresh_turns,
         );
 
         // Build messages for the AI
@@ -863,11 +1308,7 @@ impl MessageDispatcher {
                     DbMessageRole::ToolResult => "Tool Result",
                 };
                 // Truncate very long messages to keep context manageable
-                let content = if msg.content.len() > 500 {
-                    format!("{}...", &msg.content[..500])
-                } else {
-                    msg.content.clone()
-                };
+                let content = truncate_chars(&msg.content, 500);
                 context_text.push_str(&format!("**{}**: {}\n\n", role_label, content));
             }
             messages.push(Message {
@@ -880,19 +1321,53 @@ impl MessageDispatcher {
             );
         }
 
-        // Scan user input for key terms (ETH addresses, token symbols) for context bank
-        let context_bank_items = crate::tools::scan_input(message_text);
+        // Scan user input for key terms (ETH addresses, token symbols) for context bank,
+        // capped so a message listing many of them can't inflate the prompt
+        let scanned_items = crate::tools::scan_input(message_text);
+        let scanned_count = scanned_items.len();
+        let mut context_bank_items = crate::tools::cap_items(scanned_items, crate::tools::MAX_CONTEXT_BANK_ITEMS);
+        if context_bank_items.len() < scanned_count {
+            log::info!(
+                "[DISPATCH] Context bank trimmed from {} to {} items",
+                scanned_count,
+                context_bank_items.len()
+            );
+        }
+        // Remember addresses/symbols for this identity and bring back a few from earlier
+        // sessions after the fresh ones (safe-mode input is untrusted, so it is neither
+        // stored nor re-loaded)
+        if !is_safe_mode && crate::config::persist_context_bank() {
+            for item in context_bank_items.iter().filter(|i| crate::tools::is_persistable(i)) {
+                if let Err(e) = self.db.upsert_context_bank_item(&identity.identity_id, item) {
+                    log::warn!("[DISPATCH] Failed to store context bank item: {}", e);
+                }
+            }
+            match self.db.get_context_bank_items(&identity.identity_id) {
+                Ok(stored) => {
+                    context_bank_items = crate::tools::merge_items(
+                        context_bank_items,
+                        stored,
+                        crate::tools::MAX_RECALLED_CONTEXT_BANK_ITEMS,
+                    )
+                }
+                Err(e) => log::warn!("[DISPATCH] Failed to load stored context bank: {}", e),
+            }
+        }
         if !context_bank_items.is_empty() {
             // Create a temporary context bank for formatting
             let temp_bank = crate::tools::ContextBank::new();
             temp_bank.add_all(context_bank_items.clone());
             if let Some(context_bank_text) = temp_bank.format_for_agent() {
+                let content = format!(
+                    "## Context Bank\nThe following key terms were detected in the user's input (this message or earlier conversations): {}",
+                    context_bank_text
+                );
+                // The injected block counts toward the session's context like any message
+                self.context_manager
+                    .update_context_tokens(session.id, self.context_manager.estimate_tokens(session.id, &content));
                 messages.push(Message {
                     role: MessageRole::System,
-                    content: format!(
-                        "## Context Bank\nThe following key terms were detected in the user's input: {}",
-                        context_bank_text
-                    ),
+                    content,
                 });
             }
         }
@@ -917,6 +1392,15 @@ impl MessageDispatcher {
             });
         }
 
+        // Tool results aren't replayed above; optionally give the model a condensed
+        // note of what already ran so it doesn't redundantly re-call tools
+        if let Some(note) = self.context_manager.recent_tool_results_note(&history) {
+            messages.push(Message {
+                role: MessageRole::System,
+                content: note,
+            });
+        }
+
         // Add current user message (use clean text without thinking directive)
         messages.push(Message {
             role: MessageRole::User,
@@ -933,6 +1417,7 @@ impl MessageDispatcher {
                 client.set_thinking_level(level);
             }
         }
+        apply_attachments(&client, &message);
 
         // Check if the client supports tools and tools are configured
         let use_tools = client.supports_tools() && !self.tool_registry.is_empty();
@@ -957,7 +1442,9 @@ impl MessageDispatcher {
             .with_workspace(workspace_dir.clone())
             .with_broadcaster(self.broadcaster.clone())
             .with_database(self.db.clone())
-            .with_selected_network(message.selected_network.clone());
+            .with_selected_network(message.selected_network.clone())
+            .with_cancellation_token(self.execution_tracker.get_cancellation_token(message.channel_id))
+            .with_watchdog_config(Arc::new(watchdog.config().clone()));
 
         // Log selected network if present
         if let Some(ref network) = message.selected_network {
@@ -983,9 +1470,13 @@ impl MessageDispatcher {
         }
 
         // Add WalletProvider for x402 payments (Flash mode)
-        if let Some(ref wallet_provider) = self.wallet_provider {
+        if let Some(ref wallet_provider) = wallet_provider {
             tool_context = tool_context.with_wallet_provider(wallet_provider.clone());
-            log::debug!("[DISPATCH] WalletProvider attached to tool context ({})", wallet_provider.mode_name());
+            log::debug!(
+                "[DISPATCH] WalletProvider attached to tool context ({}, {})",
+                wallet_provider.mode_name(),
+                wallet_provider.get_address()
+            );
         }
 
         // Add MemoryStore for QMD memory tools (memory_search, memory_read)
@@ -1028,19 +1519,13 @@ impl MessageDispatcher {
         // Ensure workspace directory exists
         let _ = std::fs::create_dir_all(&workspace_dir);
 
-        // Load API keys from database into ToolContext (per-session, no global env mutation)
-        // In safe mode, skip loading API keys (discord/telegram/slack tokens come from channel settings)
+        // API keys are fetched from the database when a tool first asks for one
+        // (per-session, no global env mutation). In safe mode no keys are reachable
+        // (discord/telegram/slack tokens come from channel settings)
         if !is_safe_mode {
-            if let Ok(keys) = self.db.list_api_keys() {
-                log::debug!("[DISPATCH] Loading {} API keys from database into ToolContext", keys.len());
-                for key in keys {
-                    let preview = if key.api_key.len() > 8 { &key.api_key[..8] } else { &key.api_key };
-                    log::debug!("[DISPATCH]   Loading key: {} (len={}, prefix={}...)", key.service_name, key.api_key.len(), preview);
-                    tool_context = tool_context.with_api_key(&key.service_name, key.api_key.clone());
-                }
-            }
+            tool_context = tool_context.with_lazy_api_keys();
         } else {
-            log::debug!("[DISPATCH] Safe mode enabled — skipping API key loading");
+            log::debug!("[DISPATCH] Safe mode enabled — API keys unavailable");
         }
 
         // Load bot config from bot_settings for git commits etc.
@@ -1103,24 +1588,12 @@ impl MessageDispatcher {
                     &watchdog,
                 ).await
             } else {
-                // Simple generation without tools - with x402 event emission
-                match client.generate_text_with_events(messages.clone(), &self.broadcaster, message.channel_id).await {
+                // Simple generation without tools - streamed, with x402 event emission
+                match client.generate_text_streamed_with_events(messages.clone(), &self.broadcaster, message.channel_id).await {
                     Ok((content, payment)) => {
                         // Save x402 payment if one was made
                         if let Some(ref payment_info) = payment {
-                            if let Err(e) = self.db.record_x402_payment(
-                                Some(message.channel_id),
-                                None,
-                                payment_info.resource.as_deref(),
-                                &payment_info.amount,
-                                &payment_info.amount_formatted,
-                                &payment_info.asset,
-                                &payment_info.pay_to,
-                                payment_info.tx_hash.as_deref(),
-                                &payment_info.status.to_string(),
-                            ) {
-                                log::error!("[DISPATCH] Failed to record x402 payment: {}", e);
-                            }
+                            self.record_x402_payment(message.channel_id, session.id, None, payment_info);
                         }
                         Ok((content, false))
                     }
@@ -1141,6 +1614,53 @@ impl MessageDispatcher {
                     let error_msg = error_str.to_string();
                     // Populate attempt stats before failing
                     Self::populate_attempt_stats(&mut rollout, &span_collector);
+
+                    // A provider error the retry policy gives up on moves down the fallback chain
+                    let reason = FailureReason::classify(&error_msg);
+                    if reason.is_provider_error()
+                        && !rollout.config.should_retry(&reason, rollout.attempt_count())
+                        && model_idx + 1 < model_chain.len()
+                    {
+                        match self.create_ai_client(&settings, wallet_provider.clone(), message.channel_id, model_idx + 1) {
+                            Ok((next_client, next_idx)) => {
+                                let from_model = model_chain[model_idx].model_label();
+                                let to_model = model_chain[next_idx].model_label();
+                                log::warn!(
+                                    "[DISPATCH] Model '{}' failed, falling back to '{}': {}",
+                                    from_model, to_model, error_msg
+                                );
+                                self.rollout_manager.fall_back_attempt(
+                                    &mut rollout,
+                                    &error_msg,
+                                    &span_collector,
+                                    &to_model,
+                                );
+                                self.broadcaster.broadcast(GatewayEvent::ai_model_fallback(
+                                    message.channel_id,
+                                    &from_model,
+                                    &to_model,
+                                    &error_msg,
+                                ));
+                                self.broadcaster.broadcast(GatewayEvent::rollout_status_change(
+                                    message.channel_id, &rollout.rollout_id, "retrying", rollout.attempt_count(),
+                                ));
+                                client = next_client;
+                                model_idx = next_idx;
+                                archetype_id = AiClient::infer_archetype(&model_chain[model_idx]);
+                                if let Some(level) = thinking_level {
+                                    if client.supports_thinking() {
+                                        client.set_thinking_level(level);
+                                    }
+                                }
+                                apply_attachments(&client, &message);
+                                continue; // retry on the fallback model
+                            }
+                            Err(e) => {
+                                log::warn!("[DISPATCH] No usable fallback model: {}", e);
+                            }
+                        }
+                    }
+
                     let should_retry = self.rollout_manager.fail_attempt(
                         &mut rollout,
                         &error_msg,
@@ -1188,8 +1708,15 @@ impl MessageDispatcher {
 
         match final_response {
             Ok((response, delivered_via_say_to_user)) => {
+                // Tidy provider-specific whitespace/bullets so the stored copy replays cleanly
+                let response = if crate::config::normalize_assistant_output() {
+                    crate::text::normalize_assistant_text(&response)
+                } else {
+                    response
+                };
+
                 // Estimate tokens for the response
-                let response_tokens = estimate_tokens(&response);
+                let response_tokens = self.context_manager.estimate_tokens(session.id, &response);
 
                 // Store AI response in session with token count
                 // Skip storing empty responses (nothing useful to persist)
@@ -1209,8 +1736,9 @@ impl MessageDispatcher {
                     // Update context tokens
                     self.context_manager.update_context_tokens(session.id, response_tokens);
 
-                    // Check if incremental compaction is needed (earlier trigger, smaller batches)
-                    if self.context_manager.needs_incremental_compaction(session.id) {
+                    // Check if incremental compaction is needed (earlier trigger, smaller batches).
+                    // Ephemeral runs never compact: compaction flushes to long-term memory.
+                    if !message.ephemeral && self.context_manager.needs_incremental_compaction(session.id, session.scope) {
                         log::info!("[COMPACTION] Context threshold reached for session {}, triggering incremental compaction", session.id);
                         // Broadcast compaction event to UI
                         self.broadcaster.broadcast(GatewayEvent::context_compacting(
@@ -1219,13 +1747,24 @@ impl MessageDispatcher {
                             "incremental",
                             "Context threshold reached",
                         ));
-                        if let Err(e) = self.context_manager.compact_incremental(
+                        let incremental = self.context_manager.compact_incremental(
                             session.id,
+                            session.scope,
                             &client,
                             memory_identity,
-                        ).await {
-                            log::error!("[COMPACTION] Incremental compaction failed: {}", e);
-                            // Fall back to full compaction if incremental fails
+                        ).await;
+                        let reason = match incremental {
+                            Ok(compacted) if compacted > 0 => None,
+                            // Everything compactable is a kept tool pair: incremental
+                            // compaction can't make progress
+                            Ok(_) => Some("Nothing to compact incrementally, falling back to full compaction"),
+                            Err(e) => {
+                                log::error!("[COMPACTION] Incremental compaction failed: {}", e);
+                                Some("Incremental failed, falling back to full compaction")
+                            }
+                        };
+                        // Fall back to full compaction if incremental fails or stalls
+                        if let Some(reason) = reason {
                             if self.context_manager.needs_compaction(session.id) {
                                 log::info!("[COMPACTION] Falling back to full compaction");
                                 // Broadcast fallback compaction event
@@ -1233,10 +1772,11 @@ impl MessageDispatcher {
                                     message.channel_id,
                                     session.id,
                                     "full",
-                                    "Incremental failed, falling back to full compaction",
+                                    reason,
                                 ));
                                 if let Err(e) = self.context_manager.compact_session(
                                     session.id,
+                                    session.scope,
                                     &client,
                                     memory_identity,
                                 ).await {
@@ -1244,7 +1784,7 @@ impl MessageDispatcher {
                                 }
                             }
                         }
-                    } else if self.context_manager.needs_compaction(session.id) {
+                    } else if !message.ephemeral && self.context_manager.needs_compaction(session.id) {
                         // Hard limit reached - do full compaction
                         log::info!("[COMPACTION] Hard context limit reached for session {}, triggering full compaction", session.id);
                         // Broadcast compaction event to UI
@@ -1256,6 +1796,7 @@ impl MessageDispatcher {
                         ));
                         if let Err(e) = self.context_manager.compact_session(
                             session.id,
+                            session.scope,
                             &client,
                             memory_identity,
                         ).await {
@@ -1319,17 +1860,18 @@ impl MessageDispatcher {
                     _ => {} // Already finalized (Complete/Failed/Cancelled) or DB error
                 }
 
-                DispatchResult::success(response)
+                DispatchResult::success(capabilities.format_response(&response))
             }
             Err(e) => {
                 let mut error = format!("AI generation error ({}): {}", archetype_id, e);
                 log::error!("{}", error);
 
-                // If this is an x402 endpoint failure, check if it's due to insufficient USDC
-                if crate::x402::is_x402_endpoint(&settings.endpoint) {
-                    if let Some(ref wp) = self.wallet_provider {
+                // If this is an x402 endpoint failure, check if it's due to insufficient USDC.
+                // The check is cached and time-bounded; on timeout the generic error stands.
+                if crate::x402::is_x402_endpoint(&model_chain[model_idx].endpoint) {
+                    if let Some(ref wp) = wallet_provider {
                         let wallet_addr = wp.get_address();
-                        match crate::x402::check_usdc_balance(&wallet_addr).await {
+                        match crate::x402::check_usdc_balance_cached(&wallet_addr).await {
                             Ok(balance) => {
                                 // 10000 raw units = 0.01 USDC (6 decimals)
                                 if balance < ethers::types::U256::from(10000u64) {
@@ -1362,8 +1904,15 @@ impl MessageDispatcher {
                     log::error!("Failed to store error message in session: {}", db_err);
                 }
 
-                // Mark session as Failed so it doesn't stay stuck as Active with spinner
-                if let Err(status_err) = self.db.update_session_completion_status(session.id, CompletionStatus::Failed) {
+                // Mark session as Failed so it doesn't stay stuck as Active with spinner.
+                // A more specific reason recorded by the tool loop (max iterations,
+                // time limit) is kept.
+                let category = match FailureReason::classify(&error) {
+                    FailureReason::ContextOverflow => FailureCategory::ContextOverflow,
+                    _ => FailureCategory::AiError,
+                };
+                let reason = SessionFailureReason::new(category, &error);
+                if let Err(status_err) = self.db.mark_session_failed(session.id, &reason) {
                     log::error!("[DISPATCH] Failed to mark session {} as Failed: {}", session.id, status_err);
                 }
                 self.broadcast_session_complete(message.channel_id, session.id);
@@ -1415,14 +1964,29 @@ impl MessageDispatcher {
                     session_id,
                     context.mode_iterations
                 );
+                if context.loop_checkpoint {
+                    log::info!(
+                        "[MULTI_AGENT] Session {} resuming from mid-loop checkpoint ({} tasks)",
+                        session_id,
+                        context.task_queue.tasks.len()
+                    );
+                }
                 let mut orch = Orchestrator::from_context(context);
+                // The checkpoint has been picked up; the next save is a regular one
+                orch.context_mut().loop_checkpoint = false;
                 // Clear active skill at the start of each new message to prevent stale skills
                 // from being used. Skills should only be active for the turn they were invoked.
-                orch.clear_active_skill();
+                // The toolset is built below, after this, so the skill's requires_tools drop out
+                // and the prompt notes the reset.
+                orch.clear_stale_skill_on_resume();
                 // Reset per-turn counters so they don't carry over from previous messages.
                 // mode_iterations/actual_tool_calls/no_tool_warnings are per-turn state,
                 // not cumulative session state.
                 orch.reset_turn_counters();
+                // Contexts created by session commands (e.g. /plan-approval) have no request yet
+                if orch.context().original_request.is_empty() {
+                    orch.context_mut().original_request = original_message.text.clone();
+                }
                 orch
             }
             Ok(None) => {
@@ -1448,6 +2012,35 @@ impl MessageDispatcher {
             log::info!("[MULTI_AGENT] Selected network set to: {}", network);
         }
 
+        // Plan approval mode: this message answers a plan that is waiting for approval
+        if orchestrator.awaiting_plan_approval() {
+            match PlanReply::parse(&original_message.text) {
+                PlanReply::Approve => {
+                    log::info!("[MULTI_AGENT] Plan approved for session {}, starting execution", session_id);
+                    orchestrator.approve_plan();
+                    self.advance_to_next_task_or_complete(
+                        original_message.channel_id,
+                        session_id,
+                        &mut orchestrator,
+                    );
+                }
+                PlanReply::Reject => {
+                    log::info!("[MULTI_AGENT] Plan rejected for session {}", session_id);
+                    orchestrator.discard_plan();
+                    if let Err(e) = self.db.save_agent_context(session_id, orchestrator.context()) {
+                        log::warn!("[MULTI_AGENT] Failed to save context for session {}: {}", session_id, e);
+                    }
+                    self.broadcast_task_queue_update(original_message.channel_id, session_id, &orchestrator);
+                    return Ok(("Plan discarded. Nothing was executed.".to_string(), false));
+                }
+                PlanReply::Revise => {
+                    // Back to the planner; the user's feedback is in the conversation
+                    log::info!("[MULTI_AGENT] Plan revision requested for session {}", session_id);
+                    orchestrator.discard_plan();
+                }
+            }
+        }
+
         // Config-driven TaskPlanner skip: subtypes with skip_task_planner=true go straight
         // to Assistant mode (e.g. Director delegates planning to specialized agents).
         if orchestrator.current_mode() == AgentMode::TaskPlanner
@@ -1483,7 +2076,7 @@ impl MessageDispatcher {
             "[MULTI_AGENT] Started in {} mode ({} subtype) for request: {}",
             initial_mode,
             agent_types::subtype_label(&subtype_key),
-            original_message.text.chars().take(50).collect::<String>()
+            truncate_chars(&original_message.text, 50)
         );
 
         // Broadcast initial subtype
@@ -1513,22 +2106,10 @@ impl MessageDispatcher {
 
         if tools.is_empty() {
             log::warn!("[TOOL_LOOP] No tools available, falling back to text-only generation");
-            let (content, payment) = client.generate_text_with_events(messages, &self.broadcaster, original_message.channel_id).await?;
+            let (content, payment) = client.generate_text_streamed_with_events(messages, &self.broadcaster, original_message.channel_id).await?;
             // Save x402 payment if one was made
             if let Some(ref payment_info) = payment {
-                if let Err(e) = self.db.record_x402_payment(
-                    Some(original_message.channel_id),
-                    None,
-                    payment_info.resource.as_deref(),
-                    &payment_info.amount,
-                    &payment_info.amount_formatted,
-                    &payment_info.asset,
-                    &payment_info.pay_to,
-                    payment_info.tx_hash.as_deref(),
-                    &payment_info.status.to_string(),
-                ) {
-                    log::error!("[TOOL_LOOP] Failed to record x402 payment: {}", e);
-                }
+                self.record_x402_payment(original_message.channel_id, session_id, None, payment_info);
             }
             return Ok((content, false));
         }
@@ -1543,21 +2124,40 @@ impl MessageDispatcher {
             archetype.uses_native_tool_calling()
         );
 
+        // Loop detection thresholds (defaults apply when the settings are unset)
+        let loop_detection = self.db.get_bot_settings()
+            .map(|s| LoopDetection::from_settings(&s))
+            .unwrap_or_default();
+
         // Branch based on archetype type
         if archetype.uses_native_tool_calling() {
             self.generate_with_native_tools_orchestrated(
                 client, messages, tools, tool_config, tool_context,
-                original_message, archetype, &mut orchestrator, session_id, is_safe_mode, watchdog
+                original_message, archetype, &mut orchestrator, session_id, is_safe_mode, watchdog,
+                loop_detection,
             ).await
         } else {
             self.generate_with_text_tools_orchestrated(
                 client, messages, tools, tool_config, tool_context,
-                original_message, archetype, &mut orchestrator, session_id, is_safe_mode, watchdog
+                original_message, archetype, &mut orchestrator, session_id, is_safe_mode, watchdog,
+                loop_detection,
             ).await
         }
     }
 }
 
+/// Hand the message's images to the client, or drop them when the model can't see images
+fn apply_attachments(client: &AiClient, message: &NormalizedMessage) {
+    if client.supports_vision() {
+        client.set_attachments(message.attachments.clone());
+    } else if !message.attachments.is_empty() {
+        log::info!(
+            "[DISPATCH] Model has no vision support, ignoring {} attachment(s)",
+            message.attachments.len()
+        );
+    }
+}
+
 #[cfg(test)]
 #[path = "../dispatcher_tests.rs"]
 mod dispatcher_tests;
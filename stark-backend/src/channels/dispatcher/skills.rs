@@ -1,9 +1,21 @@
 use crate::ai::multi_agent::{types as agent_types, Orchestrator};
+use crate::ai::AiClient;
+use crate::channels::types::NormalizedMessage;
 use crate::gateway::protocol::GatewayEvent;
-use crate::tools::{ToolConfig, ToolDefinition};
+use crate::models::AgentSettings;
+use crate::telemetry::{SpanType, Watchdog};
+use crate::tools::{ToolConfig, ToolContext, ToolDefinition};
+use std::sync::Arc;
 
 use super::MessageDispatcher;
 
+/// Client built for the active skill's `model_override`
+pub(super) struct SkillModelClient {
+    skill_name: String,
+    /// None when the skill has no usable override
+    client: Option<AiClient>,
+}
+
 impl MessageDispatcher {
     /// Auto-set the orchestrator's subtype if the skill specifies one.
     /// Returns the new subtype key if it changed, so the caller can use it for tool refresh.
@@ -32,6 +44,132 @@ impl MessageDispatcher {
         None
     }
 
+    /// Agent settings for a skill's `model_override`, layered over `base`.
+    /// Returns `Ok(None)` when the skill has no override. Skills can only move to an
+    /// endpoint that is already configured (saved agent settings or an endpoint
+    /// preset); it borrows the API key saved for that endpoint, and is rejected if
+    /// there is none (x402 endpoints pay per call and need no key).
+    pub(super) fn skill_model_settings(
+        &self,
+        skill: &crate::skills::types::DbSkill,
+        base: &AgentSettings,
+    ) -> Result<Option<AgentSettings>, String> {
+        let model_override = match skill.model_override {
+            Some(ref o) if !o.is_empty() => o,
+            _ => return Ok(None),
+        };
+
+        let mut settings = model_override.to_settings_override().apply(base);
+        if settings.endpoint != base.endpoint {
+            let saved = self
+                .db
+                .get_agent_settings_by_endpoint(&settings.endpoint)
+                .map_err(|e| format!("Database error: {}", e))?;
+            let is_preset = crate::ai_endpoint_config::list_ai_endpoints()
+                .iter()
+                .any(|(_, preset)| preset.endpoint == settings.endpoint);
+            if saved.is_none() && !is_preset {
+                return Err(format!(
+                    "endpoint {} is not configured in agent settings",
+                    settings.endpoint
+                ));
+            }
+            settings.secret_key = saved.and_then(|saved| saved.secret_key);
+            if settings.secret_key.is_none() && !crate::x402::is_x402_endpoint(&settings.endpoint) {
+                return Err(format!(
+                    "no API key is configured for endpoint {}",
+                    settings.endpoint
+                ));
+            }
+        }
+        Ok(Some(settings))
+    }
+
+    /// Keep the tool loop's model in step with the active skill: when a skill with a
+    /// `model_override` becomes active, build a client for it; when the skill is
+    /// deactivated (or another one activated), drop it. Returns the client the next
+    /// generation should use.
+    pub(super) fn sync_skill_model_client<'a>(
+        &self,
+        current: &'a mut Option<SkillModelClient>,
+        default_client: &'a AiClient,
+        orchestrator: &Orchestrator,
+        tool_context: &ToolContext,
+        original_message: &NormalizedMessage,
+        watchdog: &Watchdog,
+    ) -> &'a AiClient {
+        let active_skill = orchestrator.context().active_skill.as_ref().map(|s| s.name.as_str());
+        if current.as_ref().map(|c| c.skill_name.as_str()) != active_skill {
+            if let Some(SkillModelClient { skill_name, client: Some(_) }) = current.take() {
+                log::info!("[SKILL] Skill '{}' no longer active, reverting to the agent's model", skill_name);
+            }
+            *current = active_skill.map(|name| SkillModelClient {
+                skill_name: name.to_string(),
+                client: self.build_skill_model_client(name, tool_context, original_message, watchdog),
+            });
+        }
+        current.as_ref().and_then(|c| c.client.as_ref()).unwrap_or(default_client)
+    }
+
+    fn build_skill_model_client(
+        &self,
+        skill_name: &str,
+        tool_context: &ToolContext,
+        original_message: &NormalizedMessage,
+        watchdog: &Watchdog,
+    ) -> Option<AiClient> {
+        let skill = self.db.get_enabled_skill_by_name(skill_name).ok().flatten()?;
+        let base = match original_message.agent_settings_override {
+            Some(ref settings) => settings.clone(),
+            None => self.db.get_active_agent_settings().ok().flatten().unwrap_or_default(),
+        };
+        let settings = match self.skill_model_settings(&skill, &base) {
+            Ok(Some(settings)) => settings,
+            Ok(None) => return None,
+            Err(e) => {
+                log::warn!("[SKILL] Ignoring model_override of skill '{}': {}", skill_name, e);
+                return None;
+            }
+        };
+
+        #[cfg(test)]
+        let client = match self.skill_mock_ai_client {
+            Some(ref mock) => Ok(AiClient::Mock(mock.clone())),
+            None => AiClient::from_settings_with_wallet_provider(&settings, tool_context.wallet_provider.clone()),
+        };
+        #[cfg(not(test))]
+        let client = AiClient::from_settings_with_wallet_provider(&settings, tool_context.wallet_provider.clone());
+        let client = match client {
+            Ok(c) => c.with_broadcaster(Arc::clone(&self.broadcaster), original_message.channel_id),
+            Err(e) => {
+                log::warn!("[SKILL] Failed to create client for skill '{}' model_override: {}", skill_name, e);
+                return None;
+            }
+        };
+
+        log::info!(
+            "[SKILL] Skill '{}' switches model to {} ({}, archetype={})",
+            skill_name,
+            settings.model.as_deref().unwrap_or("default"),
+            settings.endpoint,
+            settings.model_archetype
+        );
+        let mut span = watchdog.collector().start_span(SpanType::Annotation, "skill_model_override");
+        span.attributes = serde_json::json!({
+            "annotation_key": "skill_model_override",
+            "annotation_value": {
+                "skill": skill_name,
+                "endpoint": settings.endpoint,
+                "model_archetype": settings.model_archetype,
+                "model": settings.model,
+            },
+        });
+        span.succeed();
+        watchdog.collector().record(span);
+
+        Some(client)
+    }
+
     /// Returns the list of skills available for the given context.
     ///
     /// Filtering layers:
@@ -242,6 +380,11 @@ impl MessageDispatcher {
     ///    `requires_tools` explicitly includes it (keeps it out of Assistant mode).
     ///
     /// Note: Safe mode filtering is handled upstream by `ToolConfig`, not here.
+    ///
+    /// **Ordering guarantee:** the returned list is sorted by `ToolGroup` (declaration
+    /// order) then tool name, so identical inputs always produce an identically-ordered
+    /// list regardless of registry `HashMap` iteration order. The same list is what gets
+    /// sent to the model and broadcast via `broadcast_toolset_update`.
     pub(super) fn build_tool_list(
         &self,
         tool_config: &ToolConfig,
@@ -321,6 +464,7 @@ impl MessageDispatcher {
             }
         }
 
+        tools.sort_by(|a, b| a.group.cmp(&b.group).then_with(|| a.name.cmp(&b.name)));
         tools
     }
 }
@@ -1,9 +1,104 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::channels::language;
 use crate::channels::types::NormalizedMessage;
 use crate::models::SpecialRoleGrants;
 use crate::tools::ToolConfig;
 
 use super::MessageDispatcher;
 
+/// Upper bound on cached base prompts before the cache is flushed
+const MAX_CACHED_PROMPTS: usize = 256;
+
+/// Tools that rewrite a source of the base prompt (soul, API keys, agent identity);
+/// the cache is invalidated when one of them succeeds
+pub(super) const PROMPT_SOURCE_TOOLS: &[&str] = &[
+    "modify_soul",
+    "install_api_key",
+    "import_identity",
+    "register_new_identity",
+    "identity_post_register",
+];
+
+/// Cache of the static part of the system prompt (safe-mode rules, special role,
+/// soul, guidelines, identity, API keys), keyed by a hash of the inputs that shape it.
+/// Entries expire after the configured TTL; a zero TTL disables caching.
+pub(crate) struct SystemPromptCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<u64, (Instant, String)>>,
+    hits: AtomicU64,
+}
+
+impl SystemPromptCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+        }
+    }
+
+    /// Hash of everything the base prompt depends on
+    pub fn key(
+        channel_id: i64,
+        subtype_key: &str,
+        tool_config: &ToolConfig,
+        is_safe_mode: bool,
+        special_role_grants: Option<&SpecialRoleGrants>,
+    ) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        channel_id.hash(&mut hasher);
+        subtype_key.hash(&mut hasher);
+        is_safe_mode.hash(&mut hasher);
+        serde_json::to_string(tool_config).unwrap_or_default().hash(&mut hasher);
+        serde_json::to_string(&special_role_grants).unwrap_or_default().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn get(&self, key: u64) -> Option<String> {
+        if self.ttl.is_zero() {
+            return None;
+        }
+        let entries = self.entries.lock().unwrap();
+        let (built_at, prompt) = entries.get(&key)?;
+        if built_at.elapsed() >= self.ttl {
+            return None;
+        }
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(prompt.clone())
+    }
+
+    fn insert(&self, key: u64, prompt: String) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_CACHED_PROMPTS {
+            let ttl = self.ttl;
+            entries.retain(|_, (built_at, _)| built_at.elapsed() < ttl);
+            if entries.len() >= MAX_CACHED_PROMPTS {
+                entries.clear();
+            }
+        }
+        entries.insert(key, (Instant::now(), prompt));
+    }
+
+    /// Drop all cached prompts (tool config, special roles or API keys changed)
+    pub fn invalidate(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Number of dispatches served from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+}
+
 impl MessageDispatcher {
     /// Load SOUL.md content if it exists
     fn load_soul() -> Option<String> {
@@ -45,12 +140,187 @@ impl MessageDispatcher {
         None
     }
 
+    /// Resolve the language the agent should respond in.
+    ///
+    /// The channel's `preferred_language` setting wins; otherwise the identity's
+    /// detected language preference (persisted by `remember_detected_language`) is used.
+    pub(crate) fn resolve_response_language(&self, channel_id: i64, identity_id: &str) -> Option<String> {
+        if let Ok(Some(value)) = self.db.get_channel_setting(channel_id, "preferred_language") {
+            if !value.trim().is_empty() {
+                return Some(language::language_display_name(&value));
+            }
+        }
+        match self.db.get_identity_preference(identity_id, language::LANGUAGE_PREFERENCE_CATEGORY) {
+            Ok(pref) => pref,
+            Err(e) => {
+                log::warn!("[LANGUAGE] Failed to load language preference for {}: {}", identity_id, e);
+                None
+            }
+        }
+    }
+
+    /// Detect the language of a user message and persist it as a preference memory
+    /// for the identity. Skipped when the channel pins a preferred language.
+    pub(crate) fn remember_detected_language(&self, channel_id: i64, identity_id: &str, text: &str) {
+        let channel_pinned = self.db.get_channel_setting(channel_id, "preferred_language")
+            .ok()
+            .flatten()
+            .map(|v| !v.trim().is_empty())
+            .unwrap_or(false);
+        if channel_pinned {
+            return;
+        }
+        let Some(detected) = language::detect_language(text) else {
+            return;
+        };
+        let current = self.db
+            .get_identity_preference(identity_id, language::LANGUAGE_PREFERENCE_CATEGORY)
+            .ok()
+            .flatten();
+        if current.as_deref() != Some(detected) {
+            log::info!("[LANGUAGE] Detected {} for identity {}, saving preference", detected, identity_id);
+            if let Err(e) = self.db.set_identity_preference(
+                identity_id,
+                language::LANGUAGE_PREFERENCE_CATEGORY,
+                detected,
+            ) {
+                log::warn!("[LANGUAGE] Failed to save language preference: {}", e);
+            }
+        }
+    }
+
+    /// Drop cached base system prompts so the next dispatch rebuilds them
+    pub fn invalidate_system_prompt_cache(&self) {
+        self.system_prompt_cache.invalidate();
+    }
+
     /// Build the base system prompt with context from memories and user info
     /// Note: Tool-related instructions are added by the archetype's enhance_system_prompt
+    ///
+    /// The static part is served from `system_prompt_cache` when the channel, subtype,
+    /// tool config, safe mode and special-role grants are unchanged; memory, language
+    /// and request details are always appended fresh.
     pub(crate) fn build_system_prompt(
         &self,
         message: &NormalizedMessage,
         identity_id: &str,
+        subtype_key: &str,
+        tool_config: &ToolConfig,
+        is_safe_mode: bool,
+        special_role_grants: Option<&SpecialRoleGrants>,
+    ) -> String {
+        let cache_key = SystemPromptCache::key(
+            message.channel_id,
+            subtype_key,
+            tool_config,
+            is_safe_mode,
+            special_role_grants,
+        );
+        let mut prompt = match self.system_prompt_cache.get(cache_key) {
+            Some(base) => {
+                log::debug!(
+                    "[SYSTEM_PROMPT] Reusing cached base prompt for channel {} ({} cache hits)",
+                    message.channel_id,
+                    self.system_prompt_cache.hits()
+                );
+                base
+            }
+            None => {
+                let base = self.build_base_system_prompt(tool_config, is_safe_mode, special_role_grants);
+                self.system_prompt_cache.insert(cache_key, base.clone());
+                base
+            }
+        };
+
+        // QMD Memory System: Read from markdown files
+        // In safe mode, use segregated "safemode" memory (memory/safemode/MEMORY.md)
+        // to prevent leaking sensitive data from admin sessions to external users.
+        // No daily log or global memory in safe mode — only curated long-term memory.
+        if let Some(ref memory_store) = self.memory_store {
+            if is_safe_mode {
+                // Safe mode: only inject curated safemode memory
+                if let Ok(safe_memory) = memory_store.get_long_term(Some("safemode")) {
+                    if !safe_memory.is_empty() {
+                        prompt.push_str("## Memory\n");
+                        let content = if safe_memory.len() > 2000 {
+                            format!("...\n{}", &safe_memory[safe_memory.len() - 2000..])
+                        } else {
+                            safe_memory
+                        };
+                        prompt.push_str(&content);
+                        prompt.push_str("\n\n");
+                    }
+                }
+            } else {
+                // Standard mode: full memory access
+                // Add long-term memory (MEMORY.md)
+                if let Ok(long_term) = memory_store.get_long_term(Some(identity_id)) {
+                    if !long_term.is_empty() {
+                        prompt.push_str("## Long-Term Memory\n");
+                        // Truncate if too long (keep last 2000 chars for recency)
+                        let content = if long_term.len() > 2000 {
+                            format!("...\n{}", &long_term[long_term.len() - 2000..])
+                        } else {
+                            long_term
+                        };
+                        prompt.push_str(&content);
+                        prompt.push_str("\n\n");
+                    }
+                }
+
+                // Add today's activity (daily log)
+                if let Ok(daily_log) = memory_store.get_daily_log(Some(identity_id)) {
+                    if !daily_log.is_empty() {
+                        prompt.push_str("## Today's Activity\n");
+                        // Truncate if too long
+                        let content = if daily_log.len() > 1000 {
+                            format!("...\n{}", &daily_log[daily_log.len() - 1000..])
+                        } else {
+                            daily_log
+                        };
+                        prompt.push_str(&content);
+                        prompt.push_str("\n\n");
+                    }
+                }
+
+                // Also check global (non-identity) memories
+                if let Ok(global_long_term) = memory_store.get_long_term(None) {
+                    if !global_long_term.is_empty() {
+                        prompt.push_str("## Global Memory\n");
+                        let content = if global_long_term.len() > 1500 {
+                            format!("...\n{}", &global_long_term[global_long_term.len() - 1500..])
+                        } else {
+                            global_long_term
+                        };
+                        prompt.push_str(&content);
+                        prompt.push_str("\n\n");
+                    }
+                }
+            }
+        }
+
+        // Response language directive (channel setting or detected identity preference)
+        if let Some(lang) = self.resolve_response_language(message.channel_id, identity_id) {
+            prompt.push_str(&language::language_directive(&lang));
+        }
+
+        // Add context
+        let channel_info = match (&message.chat_name, message.channel_type.as_str()) {
+            (Some(name), _) => format!("{} (#{}, id:{})", message.channel_type, name, message.chat_id),
+            _ => message.channel_type.clone(),
+        };
+        prompt.push_str(&format!(
+            "## Current Request\nUser: {} | Channel: {}\n",
+            message.user_name, channel_info
+        ));
+
+        prompt
+    }
+
+    /// Static part of the system prompt: safe-mode rules, special role, soul,
+    /// guidelines, agent identity and configured API keys
+    fn build_base_system_prompt(
+        &self,
         tool_config: &ToolConfig,
         is_safe_mode: bool,
         special_role_grants: Option<&SpecialRoleGrants>,
@@ -165,80 +435,13 @@ impl MessageDispatcher {
             ));
         }
 
-        // QMD Memory System: Read from markdown files
-        // In safe mode, use segregated "safemode" memory (memory/safemode/MEMORY.md)
-        // to prevent leaking sensitive data from admin sessions to external users.
-        // No daily log or global memory in safe mode — only curated long-term memory.
-        if let Some(ref memory_store) = self.memory_store {
-            if is_safe_mode {
-                // Safe mode: only inject curated safemode memory
-                if let Ok(safe_memory) = memory_store.get_long_term(Some("safemode")) {
-                    if !safe_memory.is_empty() {
-                        prompt.push_str("## Memory\n");
-                        let content = if safe_memory.len() > 2000 {
-                            format!("...\n{}", &safe_memory[safe_memory.len() - 2000..])
-                        } else {
-                            safe_memory
-                        };
-                        prompt.push_str(&content);
-                        prompt.push_str("\n\n");
-                    }
-                }
-            } else {
-                // Standard mode: full memory access
-                // Add long-term memory (MEMORY.md)
-                if let Ok(long_term) = memory_store.get_long_term(Some(identity_id)) {
-                    if !long_term.is_empty() {
-                        prompt.push_str("## Long-Term Memory\n");
-                        // Truncate if too long (keep last 2000 chars for recency)
-                        let content = if long_term.len() > 2000 {
-                            format!("...\n{}", &long_term[long_term.len() - 2000..])
-                        } else {
-                            long_term
-                        };
-                        prompt.push_str(&content);
-                        prompt.push_str("\n\n");
-                    }
-                }
-
-                // Add today's activity (daily log)
-                if let Ok(daily_log) = memory_store.get_daily_log(Some(identity_id)) {
-                    if !daily_log.is_empty() {
-                        prompt.push_str("## Today's Activity\n");
-                        // Truncate if too long
-                        let content = if daily_log.len() > 1000 {
-                            format!("...\n{}", &daily_log[daily_log.len() - 1000..])
-                        } else {
-                            daily_log
-                        };
-                        prompt.push_str(&content);
-                        prompt.push_str("\n\n");
-                    }
-                }
-
-                // Also check global (non-identity) memories
-                if let Ok(global_long_term) = memory_store.get_long_term(None) {
-                    if !global_long_term.is_empty() {
-                        prompt.push_str("## Global Memory\n");
-                        let content = if global_long_term.len() > 1500 {
-                            format!("...\n{}", &global_long_term[global_long_term.len() - 1500..])
-                        } else {
-                            global_long_term
-                        };
-                        prompt.push_str(&content);
-                        prompt.push_str("\n\n");
-                    }
-                }
-            }
-        }
-
         // Add available API keys (so the agent knows what credentials are configured)
-        if let Ok(keys) = self.db.list_api_keys() {
-            if !keys.is_empty() {
+        if let Ok(names) = self.db.list_api_key_names() {
+            if !names.is_empty() {
                 prompt.push_str("## Available API Keys\n");
                 prompt.push_str("The following API keys are configured and available as environment variables when using the exec tool:\n");
-                for key in &keys {
-                    prompt.push_str(&format!("- ${}\n", key.service_name));
+                for name in &names {
+                    prompt.push_str(&format!("- ${}\n", name));
                 }
                 prompt.push('\n');
             }
@@ -247,16 +450,6 @@ impl MessageDispatcher {
         // Memory tool instructions
         prompt.push_str("## Memory\nUse `memory_search` to find relevant memories. Use `memory_read` to read specific memory files.\n\n");
 
-        // Add context
-        let channel_info = match (&message.chat_name, message.channel_type.as_str()) {
-            (Some(name), _) => format!("{} (#{}, id:{})", message.channel_type, name, message.chat_id),
-            _ => message.channel_type.clone(),
-        };
-        prompt.push_str(&format!(
-            "## Current Request\nUser: {} | Channel: {}\n",
-            message.user_name, channel_info
-        ));
-
         prompt
     }
 }
@@ -5,15 +5,49 @@ use crate::ai::{
 use crate::channels::types::NormalizedMessage;
 use crate::gateway::protocol::GatewayEvent;
 use crate::models::session_message::MessageRole as DbMessageRole;
-use crate::models::TaskType;
-use crate::telemetry::Watchdog;
+use crate::models::{BotSettings, TaskType};
+use crate::telemetry::{self, Watchdog};
+use crate::text::truncate_chars;
 use crate::tools::{ToolConfig, ToolContext, ToolDefinition};
 use std::sync::Arc;
 
+use super::dry_run;
 use super::finalization::TaskAdvanceResult;
 use super::tool_processing::BatchState;
+use super::x402_budget::SessionSpend;
 use super::{MessageDispatcher, FALLBACK_MAX_TOOL_ITERATIONS};
 
+/// Loop detection thresholds, tunable through bot settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct LoopDetection {
+    /// Identical calls (same mode, tool and arguments) treated as a loop
+    pub max_repeated_calls: usize,
+    /// Recent call signatures kept for comparison
+    pub signature_history: usize,
+}
+
+impl LoopDetection {
+    pub(super) fn from_settings(settings: &BotSettings) -> Self {
+        Self {
+            // A threshold of 1 would flag every call
+            max_repeated_calls: settings.loop_max_repeated_calls.max(2) as usize,
+            signature_history: settings.loop_signature_history.max(1) as usize,
+        }
+    }
+
+    /// Signature used to compare calls. The orchestrator mode is part of it so the
+    /// same call made in different task phases isn't counted as a repeat.
+    pub(super) fn signature(mode: AgentMode, tool_name: &str, arguments: &serde_json::Value) -> String {
+        format!("{}|{}:{}", mode, tool_name, arguments)
+    }
+}
+
+impl Default for LoopDetection {
+    fn default() -> Self {
+        Self::from_settings(&BotSettings::default())
+    }
+}
+
 impl MessageDispatcher {
     /// Generate response using native API tool calling with multi-agent orchestration
     pub(super) async fn generate_with_native_tools_orchestrated(
@@ -29,18 +63,22 @@ impl MessageDispatcher {
         session_id: i64,
         is_safe_mode: bool,
         watchdog: &Arc<Watchdog>,
+        loop_detection: LoopDetection,
     ) -> Result<(String, bool), String> {
         // Get max tool iterations from bot settings
         let max_tool_iterations = self.db.get_bot_settings()
             .map(|s| s.max_tool_iterations as usize)
             .unwrap_or(FALLBACK_MAX_TOOL_ITERATIONS);
 
+        // Prompts come from the channel's pinned resource version, if any
+        let resource_version = self.resource_manager.effective_version_id(original_message.channel_id);
+
         // Build conversation with orchestrator's system prompt prepended
         let mut conversation = messages.clone();
         if let Some(system_msg) = conversation.first_mut() {
             if system_msg.role == MessageRole::System {
                 // Prepend orchestrator context to the existing system prompt
-                let orchestrator_prompt = orchestrator.get_system_prompt_with_resource_manager_and_channel(&self.resource_manager, Some(&original_message.channel_type));
+                let orchestrator_prompt = orchestrator.get_system_prompt_with_resource_version(&self.resource_manager, resource_version.as_deref(), Some(&original_message.channel_type));
                 system_msg.content = format!(
                     "{}\n\n---\n\n{}",
                     orchestrator_prompt,
@@ -73,10 +111,12 @@ impl MessageDispatcher {
             conversation.extend(non_system);
         }
 
-        // Clear waiting_for_user_context now that it's been consumed into the prompt
-        orchestrator.clear_waiting_for_user_context();
+        // Base prompt the system message is rebuilt from on every iteration
+        let base_system_prompt = base_system_prompt(&messages, archetype);
 
         let mut tool_history: Vec<ToolHistoryEntry> = Vec::new();
+        // Compaction generation the tool history was built against
+        let mut compaction_generation = self.context_manager.compaction_generation(session_id);
         let mut iterations = 0;
         let mut tool_call_log: Vec<String> = Vec::new();
         let mut orchestrator_complete = false;
@@ -85,12 +125,13 @@ impl MessageDispatcher {
         let mut waiting_for_user_response = false;
         let mut user_question_content = String::new();
         let mut was_cancelled = false;
+        let mut time_limit_reached: Option<std::time::Duration> = None;
+        let mut budget_exceeded: Option<SessionSpend> = None;
+        let mut skill_model_client: Option<super::skills::SkillModelClient> = None;
         let mut last_say_to_user_content = String::new();
 
         // Loop detection: track recent tool call signatures to detect repetitive behavior
         let mut recent_call_signatures: Vec<String> = Vec::new();
-        const MAX_REPEATED_CALLS: usize = 3; // Break loop after 3 identical consecutive calls
-        const SIGNATURE_HISTORY_SIZE: usize = 20; // Track last 20 call signatures
 
         // say_to_user loop prevention: don't allow say_to_user to be called twice in a row
         let mut previous_iteration_had_say_to_user = false;
@@ -101,14 +142,24 @@ impl MessageDispatcher {
         let mut no_tool_pending_retries: u32 = 0;
         const MAX_NO_TOOL_PENDING_RETRIES: u32 = 3;
 
+        let checkpoint_interval = crate::config::context_checkpoint_interval();
+
         loop {
             iterations += 1;
+            // Progress for the watchdog heartbeat
+            let task_queue = &orchestrator.context().task_queue;
+            watchdog.progress().set_iteration(iterations, max_tool_iterations);
+            watchdog.progress().set_tasks(task_queue.completed_count(), task_queue.tasks.len());
             log::info!(
                 "[ORCHESTRATED_LOOP] Iteration {} in {} mode",
                 iterations,
                 orchestrator.current_mode()
             );
 
+            if checkpoint_interval > 0 && iterations % checkpoint_interval == 0 {
+                self.checkpoint_agent_context(session_id, orchestrator);
+            }
+
             // === DETERMINE TOOLS FOR CURRENT MODE ===
             // In TaskPlanner mode (first iteration), use only define_tasks tool
             let current_tools = if orchestrator.current_mode() == AgentMode::TaskPlanner && !orchestrator.context().planner_completed {
@@ -209,6 +260,19 @@ impl MessageDispatcher {
                 break;
             }
 
+            // Stop gracefully once the session's wall-clock budget is spent
+            if let Some(limit) = watchdog.session_time_limit_exceeded(orchestrator.current_subtype_key()) {
+                log::warn!("[ORCHESTRATED_LOOP] Session time limit ({}s) reached, stopping loop", limit.as_secs());
+                time_limit_reached = Some(limit);
+                break;
+            }
+
+            // Stop before the next paid AI call would overrun this request's x402 budget
+            if let Some(spend) = self.check_x402_budget(original_message.channel_id, session_id) {
+                budget_exceeded = Some(spend);
+                break;
+            }
+
             // === TASK PLANNER MODE (first iteration, planner not yet completed) ===
             // If planner just completed (define_tasks was called), pop first task and continue
             if orchestrator.context().planner_completed && orchestrator.context().task_queue.current_task().is_none() {
@@ -252,11 +316,11 @@ impl MessageDispatcher {
                     // Update system prompt for new mode with current task
                     if let Some(system_msg) = conversation.first_mut() {
                         if system_msg.role == MessageRole::System {
-                            let orchestrator_prompt = orchestrator.get_system_prompt_with_resource_manager_and_channel(&self.resource_manager, Some(&original_message.channel_type));
+                            let orchestrator_prompt = orchestrator.get_system_prompt_with_resource_version(&self.resource_manager, resource_version.as_deref(), Some(&original_message.channel_type));
                             system_msg.content = format!(
                                 "{}\n\n---\n\n{}",
                                 orchestrator_prompt,
-                                archetype.enhance_system_prompt(&messages[0].content, &tools)
+                                archetype.enhance_system_prompt(&base_system_prompt, &tools)
                             );
                         }
                     }
@@ -319,11 +383,11 @@ impl MessageDispatcher {
                 // Update system prompt for new mode
                 if let Some(system_msg) = conversation.first_mut() {
                     if system_msg.role == MessageRole::System {
-                        let orchestrator_prompt = orchestrator.get_system_prompt_with_resource_manager_and_channel(&self.resource_manager, Some(&original_message.channel_type));
+                        let orchestrator_prompt = orchestrator.get_system_prompt_with_resource_version(&self.resource_manager, resource_version.as_deref(), Some(&original_message.channel_type));
                         system_msg.content = format!(
                             "{}\n\n---\n\n{}",
                             orchestrator_prompt,
-                            archetype.enhance_system_prompt(&messages[0].content, &tools)
+                            archetype.enhance_system_prompt(&base_system_prompt, &tools)
                         );
                     }
                 }
@@ -333,14 +397,16 @@ impl MessageDispatcher {
             // mode changes, and any context updates from the orchestrator.
             if let Some(system_msg) = conversation.first_mut() {
                 if system_msg.role == MessageRole::System {
-                    let orchestrator_prompt = orchestrator.get_system_prompt_with_resource_manager_and_channel(&self.resource_manager, Some(&original_message.channel_type));
+                    let orchestrator_prompt = orchestrator.get_system_prompt_with_resource_version(&self.resource_manager, resource_version.as_deref(), Some(&original_message.channel_type));
                     system_msg.content = format!(
                         "{}\n\n---\n\n{}",
                         orchestrator_prompt,
-                        archetype.enhance_system_prompt(&messages[0].content, &current_tools)
+                        archetype.enhance_system_prompt(&base_system_prompt, &current_tools)
                     );
                 }
             }
+            // The pending user question only anchors the first iteration's prompt
+            orchestrator.clear_waiting_for_user_context();
 
             // Log available tools for this iteration
             log::debug!(
@@ -350,6 +416,46 @@ impl MessageDispatcher {
                 current_tools.iter().map(|t| t.name.as_str()).collect::<Vec<_>>().join(", ")
             );
 
+            // A skill with a model_override runs on its own model while active
+            let client = self.sync_skill_model_client(
+                &mut skill_model_client,
+                client,
+                orchestrator,
+                tool_context,
+                original_message,
+                watchdog,
+            );
+
+            client.set_sampling(self.effective_sampling(
+                original_message.agent_settings_override.as_ref(),
+                orchestrator.current_subtype_key(),
+            ));
+
+            // Planner mode must call define_tasks: force it where the provider supports
+            // tool_choice instead of relying on the skipped-tool retry below
+            let forced_tool = (orchestrator.current_mode() == AgentMode::TaskPlanner
+                && !orchestrator.context().planner_completed
+                && archetype.supports_tool_choice()
+                && client.supports_tool_choice())
+                .then_some("define_tasks");
+            client.set_tool_choice(forced_tool);
+
+            // If the session was compacted since the history was built, older entries
+            // refer to turns that now only exist in the summary. Keep what compaction
+            // kept verbatim (at least the latest entry, which the model is acting on).
+            let current_generation = self.context_manager.compaction_generation(session_id);
+            if current_generation != compaction_generation {
+                let removed = crate::ai::types::align_tool_history_after_compaction(
+                    &mut tool_history,
+                    crate::config::compaction_keep_tool_pairs().max(1),
+                );
+                log::info!(
+                    "[ORCHESTRATED_LOOP] Session {} compacted (generation {} -> {}), dropped {} tool history entries",
+                    session_id, compaction_generation, current_generation, removed
+                );
+                compaction_generation = current_generation;
+            }
+
             // Generate with native tool support and progress notifications
             let mut ai_response = match self.generate_with_progress(
                 &client,
@@ -408,10 +514,19 @@ impl MessageDispatcher {
                     }
                     // Save context before returning error
                     let _ = self.db.save_agent_context(session_id, orchestrator.context());
+                    watchdog.collector().record_tool_history(tool_history);
                     return Err(error_str);
                 }
             };
 
+            // Keep reasoning out of the answer and session storage; it is shown
+            // separately as agent.thinking and recorded in telemetry.
+            ai_response.separate_inline_thinking();
+            if let Some(ref thinking) = ai_response.thinking {
+                log::debug!("[ORCHESTRATED_LOOP] Model thinking ({} chars)", thinking.len());
+                self.surface_thinking(original_message.channel_id, session_id, iterations, thinking);
+            }
+
             // Strip model-specific artifacts (e.g. MiniMax <think> blocks)
             ai_response.content = archetype.clean_content(&ai_response.content);
 
@@ -431,17 +546,7 @@ impl MessageDispatcher {
                     &payment_info.pay_to,
                     payment_info.resource.as_deref(),
                 ));
-                let _ = self.db.record_x402_payment(
-                    Some(original_message.channel_id),
-                    None,
-                    payment_info.resource.as_deref(),
-                    &payment_info.amount,
-                    &payment_info.amount_formatted,
-                    &payment_info.asset,
-                    &payment_info.pay_to,
-                    payment_info.tx_hash.as_deref(),
-                    &payment_info.status.to_string(),
-                );
+                self.record_x402_payment(original_message.channel_id, session_id, None, payment_info);
             }
 
             // If no tool calls, check if this is allowed
@@ -454,7 +559,7 @@ impl MessageDispatcher {
                     );
 
                     // Broadcast warning to UI so user has visibility
-                    self.broadcaster.broadcast(GatewayEvent::agent_warning(
+                    self.broadcast_agent_warning(
                         original_message.channel_id,
                         "no_tool_calls",
                         &format!(
@@ -462,7 +567,7 @@ impl MessageDispatcher {
                             attempt
                         ),
                         attempt,
-                    ));
+                    );
 
                     // Add a system message telling the agent to call tools
                     conversation.push(Message {
@@ -549,6 +654,10 @@ impl MessageDispatcher {
 
                 // say_to_user content takes priority — it IS the final result (already broadcast)
                 if !last_say_to_user_content.is_empty() {
+                    if original_message.dry_run {
+                        // Held back by the dry run, so nothing was broadcast yet
+                        return Ok((dry_run::dry_run_reply_report(&last_say_to_user_content), false));
+                    }
                     log::info!("[ORCHESTRATED_LOOP] Returning say_to_user content as final result ({} chars)", last_say_to_user_content.len());
                     return Ok((last_say_to_user_content.clone(), true));
                 }
@@ -571,13 +680,16 @@ impl MessageDispatcher {
             let mut tool_responses = Vec::new();
 
             // Loop detection: check for repetitive tool calls
+            let mode = orchestrator.current_mode();
             let current_signatures: Vec<String> = ai_response.tool_calls.iter()
-                .map(|c| format!("{}:{}", c.name, c.arguments.to_string()))
+                .map(|c| LoopDetection::signature(mode, &c.name, &c.arguments))
                 .collect();
 
             // Check if all current calls were recently made (loop detection)
             let repeated_count = current_signatures.iter()
-                .filter(|sig| recent_call_signatures.iter().filter(|s| s == sig).count() >= MAX_REPEATED_CALLS - 1)
+                .filter(|sig| {
+                    recent_call_signatures.iter().filter(|s| s == sig).count() >= loop_detection.max_repeated_calls - 1
+                })
                 .count();
 
             if repeated_count > 0 && repeated_count == current_signatures.len() {
@@ -586,16 +698,27 @@ impl MessageDispatcher {
                     repeated_count
                 );
 
+                let repeated_calls: Vec<String> = ai_response.tool_calls.iter()
+                    .map(|c| format!("{}:{}", c.name, c.arguments))
+                    .collect();
+
                 // Emit loop detection reward signal via RewardEmitter
-                watchdog.reward_emitter().loop_detected(&current_signatures, iterations as u32);
+                watchdog.reward_emitter().loop_detected(&repeated_calls, iterations as u32);
+
+                self.broadcast_agent_warning(
+                    original_message.channel_id,
+                    "loop_detected",
+                    &format!("Agent repeated {} identical tool call(s); asking it to change approach", repeated_count),
+                    iterations as u32,
+                );
 
                 // Create a feedback entry to guide the AI
                 let loop_warning = format!(
                     "⚠️ LOOP DETECTED: You've called the same tool(s) {} times with identical arguments. \
                     The repeated calls are: {}. \
                     Please try a DIFFERENT approach or tool, or explain what you're trying to accomplish.",
-                    MAX_REPEATED_CALLS,
-                    current_signatures.join(", ")
+                    loop_detection.max_repeated_calls,
+                    repeated_calls.join(", ")
                 );
 
                 // Add as a tool response to guide the AI
@@ -616,8 +739,9 @@ impl MessageDispatcher {
                 if iterations > max_tool_iterations / 2 {
                     log::error!(
                         "[LOOP_DETECTION] Loop persists after warning, breaking out. Last attempt: {}",
-                        current_signatures.join(", ")
+                        repeated_calls.join(", ")
                     );
+                    watchdog.collector().record_tool_history(tool_history);
                     return Err("Sorry, I wasn't able to complete this request. Please try again.".to_string());
                 }
                 continue;
@@ -628,8 +752,8 @@ impl MessageDispatcher {
                 recent_call_signatures.push(sig.clone());
             }
             // Keep only recent signatures
-            if recent_call_signatures.len() > SIGNATURE_HISTORY_SIZE {
-                recent_call_signatures.drain(0..recent_call_signatures.len() - SIGNATURE_HISTORY_SIZE);
+            if recent_call_signatures.len() > loop_detection.signature_history {
+                recent_call_signatures.drain(0..recent_call_signatures.len() - loop_detection.signature_history);
             }
 
             // say_to_user consecutive call detection: if say_to_user is the ONLY tool called
@@ -709,10 +833,7 @@ impl MessageDispatcher {
                 ai_response.tool_calls,
                 tool_responses,
             ));
-            if tool_history.len() > MAX_TOOL_HISTORY {
-                // Remove oldest entries, keeping the most recent
-                tool_history.drain(0..tool_history.len() - MAX_TOOL_HISTORY);
-            }
+            crate::ai::types::trim_tool_history(&mut tool_history, MAX_TOOL_HISTORY);
 
             // If orchestrator is complete, break the loop
             if orchestrator_complete {
@@ -730,6 +851,9 @@ impl MessageDispatcher {
             previous_iteration_had_say_to_user = only_say_to_user;
         }
 
+        // Kept for the rollout in case this attempt ends up failing
+        watchdog.collector().record_tool_history(tool_history);
+
         self.finalize_tool_loop(
             original_message,
             session_id,
@@ -745,6 +869,8 @@ impl MessageDispatcher {
             &user_question_content,
             max_tool_iterations,
             iterations,
+            time_limit_reached,
+            budget_exceeded,
             watchdog,
         )
     }
@@ -763,6 +889,7 @@ impl MessageDispatcher {
         session_id: i64,
         is_safe_mode: bool,
         watchdog: &Arc<Watchdog>,
+        loop_detection: LoopDetection,
     ) -> Result<(String, bool), String> {
         // Get max tool iterations from bot settings
         let max_tool_iterations = self.db.get_bot_settings()
@@ -771,11 +898,14 @@ impl MessageDispatcher {
 
         // Note: define_tasks stripping is handled by build_tool_list() at the call site
 
+        // Prompts come from the channel's pinned resource version, if any
+        let resource_version = self.resource_manager.effective_version_id(original_message.channel_id);
+
         // Build conversation with orchestrator's system prompt
         let mut conversation = messages.clone();
         if let Some(system_msg) = conversation.first_mut() {
             if system_msg.role == MessageRole::System {
-                let orchestrator_prompt = orchestrator.get_system_prompt_with_resource_manager_and_channel(&self.resource_manager, Some(&original_message.channel_type));
+                let orchestrator_prompt = orchestrator.get_system_prompt_with_resource_version(&self.resource_manager, resource_version.as_deref(), Some(&original_message.channel_type));
                 system_msg.content = format!(
                     "{}\n\n---\n\n{}",
                     orchestrator_prompt,
@@ -808,6 +938,9 @@ impl MessageDispatcher {
             conversation.extend(non_system);
         }
 
+        // Base prompt the system message is rebuilt from on every iteration
+        let base_system_prompt = base_system_prompt(&messages, archetype);
+
         // Clear waiting_for_user_context now that it's been consumed into the prompt
         orchestrator.clear_waiting_for_user_context();
 
@@ -819,12 +952,13 @@ impl MessageDispatcher {
         let mut waiting_for_user_response = false;
         let mut user_question_content = String::new();
         let mut was_cancelled = false;
+        let mut time_limit_reached: Option<std::time::Duration> = None;
+        let mut budget_exceeded: Option<SessionSpend> = None;
+        let mut skill_model_client: Option<super::skills::SkillModelClient> = None;
         let mut last_say_to_user_content = String::new();
 
         // Loop detection: track recent tool call signatures to detect repetitive behavior
         let mut recent_call_signatures: Vec<String> = Vec::new();
-        const MAX_REPEATED_CALLS: usize = 3; // Break loop after 3 identical consecutive calls
-        const SIGNATURE_HISTORY_SIZE: usize = 20; // Track last 20 call signatures
 
         // say_to_user loop prevention: don't allow say_to_user to be called twice in a row
         let mut previous_iteration_had_say_to_user = false;
@@ -833,14 +967,24 @@ impl MessageDispatcher {
         let mut no_tool_pending_retries: u32 = 0;
         const MAX_NO_TOOL_PENDING_RETRIES: u32 = 3;
 
+        let checkpoint_interval = crate::config::context_checkpoint_interval();
+
         loop {
             iterations += 1;
+            // Progress for the watchdog heartbeat
+            let task_queue = &orchestrator.context().task_queue;
+            watchdog.progress().set_iteration(iterations, max_tool_iterations);
+            watchdog.progress().set_tasks(task_queue.completed_count(), task_queue.tasks.len());
             log::info!(
                 "[TEXT_ORCHESTRATED] Iteration {} in {} mode",
                 iterations,
                 orchestrator.current_mode()
             );
 
+            if checkpoint_interval > 0 && iterations % checkpoint_interval == 0 {
+                self.checkpoint_agent_context(session_id, orchestrator);
+            }
+
             // Check if execution was cancelled (e.g., user sent /new or stop button)
             if self.execution_tracker.is_cancelled(original_message.channel_id) {
                 log::info!("[TEXT_ORCHESTRATED] Execution cancelled by user, stopping loop");
@@ -853,6 +997,19 @@ impl MessageDispatcher {
                 break;
             }
 
+            // Stop gracefully once the session's wall-clock budget is spent
+            if let Some(limit) = watchdog.session_time_limit_exceeded(orchestrator.current_subtype_key()) {
+                log::warn!("[TEXT_ORCHESTRATED] Session time limit ({}s) reached, stopping loop", limit.as_secs());
+                time_limit_reached = Some(limit);
+                break;
+            }
+
+            // Stop before the next paid AI call would overrun this request's x402 budget
+            if let Some(spend) = self.check_x402_budget(original_message.channel_id, session_id) {
+                budget_exceeded = Some(spend);
+                break;
+            }
+
             // Check for forced mode transition
             if let Some(transition) = orchestrator.check_forced_transition() {
                 self.broadcaster.broadcast(GatewayEvent::agent_mode_change(
@@ -878,11 +1035,11 @@ impl MessageDispatcher {
                 // Update system prompt
                 if let Some(system_msg) = conversation.first_mut() {
                     if system_msg.role == MessageRole::System {
-                        let orchestrator_prompt = orchestrator.get_system_prompt_with_resource_manager_and_channel(&self.resource_manager, Some(&original_message.channel_type));
+                        let orchestrator_prompt = orchestrator.get_system_prompt_with_resource_version(&self.resource_manager, resource_version.as_deref(), Some(&original_message.channel_type));
                         system_msg.content = format!(
                             "{}\n\n---\n\n{}",
                             orchestrator_prompt,
-                            archetype.enhance_system_prompt(&messages[0].content, &tools)
+                            archetype.enhance_system_prompt(&base_system_prompt, &tools)
                         );
                     }
                 }
@@ -891,11 +1048,11 @@ impl MessageDispatcher {
             // Update system prompt every iteration so the AI sees the current task
             if let Some(system_msg) = conversation.first_mut() {
                 if system_msg.role == MessageRole::System {
-                    let orchestrator_prompt = orchestrator.get_system_prompt_with_resource_manager_and_channel(&self.resource_manager, Some(&original_message.channel_type));
+                    let orchestrator_prompt = orchestrator.get_system_prompt_with_resource_version(&self.resource_manager, resource_version.as_deref(), Some(&original_message.channel_type));
                     system_msg.content = format!(
                         "{}\n\n---\n\n{}",
                         orchestrator_prompt,
-                        archetype.enhance_system_prompt(&messages[0].content, &tools)
+                        archetype.enhance_system_prompt(&base_system_prompt, &tools)
                     );
                 }
             }
@@ -908,6 +1065,21 @@ impl MessageDispatcher {
                 tools.iter().map(|t| &t.name).collect::<Vec<_>>()
             );
 
+            // A skill with a model_override runs on its own model while active
+            let client = self.sync_skill_model_client(
+                &mut skill_model_client,
+                client,
+                orchestrator,
+                tool_context,
+                original_message,
+                watchdog,
+            );
+
+            client.set_sampling(self.effective_sampling(
+                original_message.agent_settings_override.as_ref(),
+                orchestrator.current_subtype_key(),
+            ));
+
             let (ai_content, payment) = match client.generate_text_with_events(
                 conversation.clone(),
                 &self.broadcaster,
@@ -940,31 +1112,29 @@ impl MessageDispatcher {
             };
 
             if let Some(ref payment_info) = payment {
-                let _ = self.db.record_x402_payment(
-                    Some(original_message.channel_id),
-                    None,
-                    payment_info.resource.as_deref(),
-                    &payment_info.amount,
-                    &payment_info.amount_formatted,
-                    &payment_info.asset,
-                    &payment_info.pay_to,
-                    payment_info.tx_hash.as_deref(),
-                    &payment_info.status.to_string(),
-                );
+                self.record_x402_payment(original_message.channel_id, session_id, None, payment_info);
             }
 
             let parsed = archetype.parse_response(&ai_content);
+            if let Some(thinking) = parsed.as_ref().and_then(|r| r.thinking.as_deref()) {
+                log::debug!("[TEXT_ORCHESTRATED] Model thinking ({} chars)", thinking.len());
+                self.surface_thinking(original_message.channel_id, session_id, iterations, thinking);
+            }
 
             match parsed {
                 Some(agent_response) => {
                     if let Some(tool_call) = agent_response.tool_call {
                         // Loop detection: check for repetitive tool calls
-                        let call_signature = format!("{}:{}", tool_call.tool_name, tool_call.tool_params.to_string());
+                        let call_signature = LoopDetection::signature(
+                            orchestrator.current_mode(),
+                            &tool_call.tool_name,
+                            &tool_call.tool_params,
+                        );
                         let repeated_count = recent_call_signatures.iter()
                             .filter(|s| *s == &call_signature)
                             .count();
 
-                        if repeated_count >= MAX_REPEATED_CALLS - 1 {
+                        if repeated_count >= loop_detection.max_repeated_calls - 1 {
                             log::warn!(
                                 "[TEXT_LOOP_DETECTION] Detected repeated tool call '{}', breaking loop",
                                 tool_call.tool_name
@@ -972,7 +1142,14 @@ impl MessageDispatcher {
 
                             // Emit loop detection reward signal via RewardEmitter
                             watchdog.reward_emitter().loop_detected(
-                                &[call_signature.clone()],
+                                &[format!("{}:{}", tool_call.tool_name, tool_call.tool_params)],
+                                iterations as u32,
+                            );
+
+                            self.broadcast_agent_warning(
+                                original_message.channel_id,
+                                "loop_detected",
+                                &format!("Agent repeated `{}` with identical arguments; asking it to change approach", tool_call.tool_name),
                                 iterations as u32,
                             );
 
@@ -981,7 +1158,7 @@ impl MessageDispatcher {
                                 "⚠️ LOOP DETECTED: You've called `{}` {} times with identical arguments. \
                                 Please try a DIFFERENT approach or tool.",
                                 tool_call.tool_name,
-                                MAX_REPEATED_CALLS
+                                loop_detection.max_repeated_calls
                             );
                             conversation.push(Message {
                                 role: MessageRole::User,
@@ -1001,8 +1178,8 @@ impl MessageDispatcher {
 
                         // Track signature for future loop detection
                         recent_call_signatures.push(call_signature);
-                        if recent_call_signatures.len() > SIGNATURE_HISTORY_SIZE {
-                            recent_call_signatures.drain(0..recent_call_signatures.len() - SIGNATURE_HISTORY_SIZE);
+                        if recent_call_signatures.len() > loop_detection.signature_history {
+                            recent_call_signatures.drain(0..recent_call_signatures.len() - loop_detection.signature_history);
                         }
 
                         // say_to_user consecutive call detection: if say_to_user is the ONLY tool called
@@ -1099,7 +1276,7 @@ impl MessageDispatcher {
                             );
 
                             // Broadcast warning to UI so user has visibility
-                            self.broadcaster.broadcast(GatewayEvent::agent_warning(
+                            self.broadcast_agent_warning(
                                 original_message.channel_id,
                                 "no_tool_calls",
                                 &format!(
@@ -1107,7 +1284,7 @@ impl MessageDispatcher {
                                     attempt
                                 ),
                                 attempt,
-                            ));
+                            );
 
                             // Add messages to force tool calling
                             conversation.push(Message {
@@ -1191,7 +1368,7 @@ impl MessageDispatcher {
                     self.broadcaster.broadcast(GatewayEvent::agent_thinking(
                         original_message.channel_id,
                         Some(session_id),
-                        &format!("Parse failed, raw AI response:\n{}", &ai_content[..ai_content.len().min(500)]),
+                        &format!("Parse failed, raw AI response:\n{}", truncate_chars(&ai_content, 500)),
                     ));
 
                     final_response = ai_content;
@@ -1215,7 +1392,37 @@ impl MessageDispatcher {
             &user_question_content,
             max_tool_iterations,
             iterations,
+            time_limit_reached,
+            budget_exceeded,
             watchdog,
         )
     }
+
+    /// Broadcast model reasoning as `agent.thinking` (apart from the answer)
+    /// and record it as an `llm_thinking` telemetry annotation.
+    fn surface_thinking(&self, channel_id: i64, session_id: i64, iteration: usize, thinking: &str) {
+        self.broadcaster.broadcast(GatewayEvent::agent_thinking(channel_id, Some(session_id), thinking));
+        telemetry::emit_annotation("llm_thinking", serde_json::json!({
+            "iteration": iteration,
+            "chars": thinking.len(),
+            "thinking": thinking,
+        }));
+    }
+}
+
+/// The system prompt the orchestrated loops rebuild from each iteration.
+///
+/// For archetypes that require a single system message, the extra system
+/// messages (compaction summary, context bank, recent tool results, ...) are
+/// merged into it; otherwise the per-iteration rebuild would drop them.
+fn base_system_prompt(messages: &[Message], archetype: &dyn ModelArchetype) -> String {
+    let mut system_messages = messages.iter().filter(|m| m.role == MessageRole::System);
+    let mut base = system_messages.next().map(|m| m.content.clone()).unwrap_or_default();
+    if archetype.requires_single_system_message() {
+        for msg in system_messages {
+            base.push_str("\n\n---\n\n");
+            base.push_str(&msg.content);
+        }
+    }
+    base
 }
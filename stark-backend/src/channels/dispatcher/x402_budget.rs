@@ -0,0 +1,158 @@
+//! Per-session x402 spend tracking.
+//!
+//! Every x402 payment made for a session is recorded against it, including
+//! the ones tools like `web_fetch` and `erc8128_fetch` make on their own, and
+//! the session's running total is broadcast so the UI can show spend live.
+//! When `max_session_usdc` is set in bot settings, the tool loop checks that
+//! total before each paid AI or tool call and halts once the next call would
+//! go over. The total covers every message in the session, so the cap can't
+//! be sidestepped by spreading paid calls across messages; once it is hit,
+//! further paid calls need a new session or a higher budget.
+
+use crate::gateway::protocol::GatewayEvent;
+use crate::tools::ToolResult;
+use crate::x402::X402PaymentInfo;
+
+use super::MessageDispatcher;
+
+/// Slack for float rounding when comparing spend against the budget
+const BUDGET_EPSILON: f64 = 1e-9;
+
+/// Tools that pay x402 invoices themselves when a fetch returns 402
+const PAYING_TOOLS: &[&str] = &["web_fetch", "erc8128_fetch"];
+
+/// A session's x402 spend measured against its budget
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct SessionSpend {
+    /// Total paid so far in the session (USDC)
+    pub(super) spent: f64,
+    /// Largest single payment so far, used as the price estimate for the next call
+    pub(super) largest_payment: f64,
+    /// Budget from bot settings (None = no budget)
+    pub(super) max: Option<f64>,
+}
+
+impl SessionSpend {
+    /// Whether the next paid call would push the session over its budget.
+    /// Prices aren't known before a call is made, so the most expensive
+    /// payment seen so far stands in for it.
+    pub(super) fn next_call_exceeds_budget(&self) -> bool {
+        match self.max {
+            Some(max) => self.spent + self.largest_payment > max + BUDGET_EPSILON,
+            None => false,
+        }
+    }
+}
+
+impl MessageDispatcher {
+    /// Record an x402 payment against the session and broadcast the new running total
+    pub(super) fn record_x402_payment(
+        &self,
+        channel_id: i64,
+        session_id: i64,
+        tool_name: Option<&str>,
+        payment: &X402PaymentInfo,
+    ) {
+        if let Err(e) = self.db.record_x402_payment(
+            Some(channel_id),
+            Some(session_id),
+            tool_name,
+            payment.resource.as_deref(),
+            &payment.amount,
+            &payment.amount_formatted,
+            &payment.asset,
+            &payment.pay_to,
+            payment.payer.as_deref(),
+            payment.tx_hash.as_deref(),
+            &payment.status.to_string(),
+        ) {
+            log::error!("[X402] Failed to record x402 payment: {}", e);
+            return;
+        }
+
+        let spend = self.session_spend(session_id);
+        self.broadcaster.broadcast(GatewayEvent::x402_session_spend(
+            channel_id,
+            session_id,
+            spend.spent,
+            spend.max,
+        ));
+    }
+
+    /// Record the x402 payment a tool reports in its result metadata, if any
+    pub(super) fn record_tool_x402_payment(&self, channel_id: i64, session_id: i64, tool_name: &str, result: &ToolResult) {
+        let payment = result
+            .metadata
+            .as_ref()
+            .and_then(|m| m.get("x402_payment"))
+            .filter(|p| !p.is_null())
+            .and_then(|p| serde_json::from_value::<X402PaymentInfo>(p.clone()).ok());
+        if let Some(payment) = payment {
+            self.record_x402_payment(channel_id, session_id, Some(tool_name), &payment);
+        }
+    }
+
+    /// Current x402 spend for a session and the configured budget
+    pub(super) fn session_spend(&self, session_id: i64) -> SessionSpend {
+        let (spent, largest_payment) = self.db.x402_session_spend(session_id).unwrap_or((0.0, 0.0));
+        let max = self.db.get_bot_settings()
+            .ok()
+            .map(|s| s.max_session_usdc)
+            .filter(|max| *max > 0.0);
+        SessionSpend { spent, largest_payment, max }
+    }
+
+    /// Check the budget before a paid call. Returns the spend (and
+    /// broadcasts `x402.budget_exceeded`) when the loop should halt.
+    pub(super) fn check_x402_budget(&self, channel_id: i64, session_id: i64) -> Option<SessionSpend> {
+        let spend = self.session_spend(session_id);
+        if !spend.next_call_exceeds_budget() {
+            return None;
+        }
+        let max = spend.max.unwrap_or_default();
+        log::warn!(
+            "[X402] Session {} spent {:.6} USDC of its {:.6} USDC budget, halting before the next paid call",
+            session_id, spend.spent, max
+        );
+        self.broadcaster.broadcast(GatewayEvent::x402_budget_exceeded(
+            channel_id,
+            session_id,
+            spend.spent,
+            max,
+        ));
+        Some(spend)
+    }
+
+    /// Refuse a tool that may pay for what it fetches once the budget is spent
+    pub(super) fn x402_tool_budget_block(&self, tool_name: &str, channel_id: i64, session_id: i64) -> Option<ToolResult> {
+        if !PAYING_TOOLS.contains(&tool_name) {
+            return None;
+        }
+        let spend = self.check_x402_budget(channel_id, session_id)?;
+        Some(ToolResult::error(format!(
+            "x402 budget reached: spent {:.6} of {:.6} USDC in this session, so '{}' can't make another paid call",
+            spend.spent,
+            spend.max.unwrap_or_default(),
+            tool_name
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_call_exceeds_budget() {
+        let spend = |spent, largest_payment, max| SessionSpend { spent, largest_payment, max };
+
+        // No budget, or nothing paid yet
+        assert!(!spend(5.0, 1.0, None).next_call_exceeds_budget());
+        assert!(!spend(0.0, 0.0, Some(0.01)).next_call_exceeds_budget());
+        // One more 0.01 call lands exactly on the budget
+        assert!(!spend(0.02, 0.01, Some(0.03)).next_call_exceeds_budget());
+        // ...but not past it
+        assert!(spend(0.03, 0.01, Some(0.03)).next_call_exceeds_budget());
+        assert!(spend(0.025, 0.01, Some(0.03)).next_call_exceeds_budget());
+    }
+}
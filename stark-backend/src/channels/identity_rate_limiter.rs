@@ -0,0 +1,118 @@
+//! Per-identity message rate limiter
+//!
+//! A token bucket per identity: each holds up to `burst` tokens and refills at
+//! `per_minute / 60` tokens per second, based on wall-clock time. Every inbound
+//! message costs one token; an empty bucket means the message is rejected
+//! before it reaches the AI loop. Limits come from Bot Settings on every
+//! check, so changes apply immediately.
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use std::time::Duration;
+
+/// Buckets kept before idle (fully refilled) ones are pruned
+const MAX_TRACKED_IDENTITIES: usize = 10_000;
+
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: DateTime<Utc>,
+}
+
+/// Token-bucket limiter keyed by identity ID
+#[derive(Debug, Default)]
+pub struct IdentityRateLimiter {
+    buckets: DashMap<String, TokenBucket>,
+}
+
+impl IdentityRateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take a token for `identity_id`. Returns how long until the next token
+    /// is available when the bucket is empty. `per_minute == 0` disables limiting.
+    pub fn check(&self, identity_id: &str, per_minute: u32, burst: u32) -> Result<(), Duration> {
+        self.check_at(identity_id, per_minute, burst, Utc::now())
+    }
+
+    fn check_at(&self, identity_id: &str, per_minute: u32, burst: u32, now: DateTime<Utc>) -> Result<(), Duration> {
+        if per_minute == 0 {
+            return Ok(());
+        }
+        let capacity = burst.max(1) as f64;
+        let per_sec = per_minute as f64 / 60.0;
+
+        if self.buckets.len() >= MAX_TRACKED_IDENTITIES {
+            self.prune(capacity, per_sec, now);
+        }
+
+        let mut bucket = self
+            .buckets
+            .entry(identity_id.to_string())
+            .or_insert(TokenBucket { tokens: capacity, last_refill: now });
+        refill(&mut bucket, capacity, per_sec, now);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_sec))
+        }
+    }
+
+    /// Drop buckets that have refilled completely (they behave like new ones)
+    fn prune(&self, capacity: f64, per_sec: f64, now: DateTime<Utc>) {
+        self.buckets.retain(|_, bucket| {
+            refill(bucket, capacity, per_sec, now);
+            bucket.tokens < capacity
+        });
+    }
+}
+
+fn refill(bucket: &mut TokenBucket, capacity: f64, per_sec: f64, now: DateTime<Utc>) {
+    // Clock adjustments backwards never add tokens
+    let elapsed = (now - bucket.last_refill).num_milliseconds().max(0) as f64 / 1000.0;
+    bucket.tokens = (bucket.tokens + elapsed * per_sec).min(capacity);
+    bucket.last_refill = now;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_then_refill() {
+        let limiter = IdentityRateLimiter::new();
+        let start = Utc::now();
+
+        // 6/min = one token every 10s, burst of 3
+        for _ in 0..3 {
+            assert!(limiter.check_at("alice", 6, 3, start).is_ok());
+        }
+        let wait = limiter.check_at("alice", 6, 3, start).unwrap_err();
+        assert_eq!(wait.as_secs(), 10);
+
+        // Other identities have their own bucket
+        assert!(limiter.check_at("bob", 6, 3, start).is_ok());
+
+        // Half a token is not enough; a full one is
+        assert!(limiter.check_at("alice", 6, 3, start + chrono::Duration::seconds(5)).is_err());
+        assert!(limiter.check_at("alice", 6, 3, start + chrono::Duration::seconds(10)).is_ok());
+
+        // A long pause refills only up to the burst size
+        let later = start + chrono::Duration::hours(1);
+        for _ in 0..3 {
+            assert!(limiter.check_at("alice", 6, 3, later).is_ok());
+        }
+        assert!(limiter.check_at("alice", 6, 3, later).is_err());
+    }
+
+    #[test]
+    fn test_zero_rate_disables_limit() {
+        let limiter = IdentityRateLimiter::new();
+        for _ in 0..100 {
+            assert!(limiter.check("alice", 0, 1).is_ok());
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Operator message injection.
+//!
+//! Lets an operator post a message into a session as if the assistant had
+//! written it (support handoff, corrections). The message is delivered to the
+//! session's chat through the channel's outbound queue, stored as an assistant
+//! `SessionMessage`, and broadcast to gateway clients. The AI is not invoked.
+
+use std::sync::Arc;
+
+use crate::channels::delivery;
+use crate::db::Database;
+use crate::gateway::events::EventBroadcaster;
+use crate::gateway::protocol::GatewayEvent;
+use crate::models::{ChatSession, MessageRole, SessionMessage};
+
+/// Result of an injection
+#[derive(Debug)]
+pub struct InjectedMessage {
+    /// The stored assistant message: what actually reached the chat
+    pub message: SessionMessage,
+    /// Set when only part of the text could be delivered
+    pub delivery_error: Option<String>,
+}
+
+/// Deliver, store and broadcast an assistant message for `session`.
+///
+/// Discord, Telegram and Slack sessions are sent to their platform chat first.
+/// If nothing could be delivered nothing is stored; if delivery stopped part
+/// way, only the delivered part is stored and the error is returned with it.
+/// Other channel types (web, gateway) only receive the gateway broadcast.
+pub async fn inject_assistant_message(
+    db: &Arc<Database>,
+    broadcaster: &EventBroadcaster,
+    session: &ChatSession,
+    text: &str,
+) -> Result<InjectedMessage, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Message text is required".to_string());
+    }
+
+    let (stored_text, platform_message_id, delivery_error) = if delivery::supports_channel_type(&session.channel_type) {
+        let delivery = delivery::send_text(
+            db,
+            session.channel_id,
+            &session.channel_type,
+            &session.platform_chat_id,
+            text,
+        )
+        .await;
+        if delivery.sent_parts.is_empty() {
+            return Err(delivery.error.unwrap_or_else(|| "Nothing was delivered".to_string()));
+        }
+        let first_id = delivery.message_ids.first().cloned();
+        let error = delivery.error.clone();
+        (delivery.sent_text(), first_id, error)
+    } else {
+        (text.to_string(), None, None)
+    };
+
+    let message = db
+        .add_session_message(
+            session.id,
+            MessageRole::Assistant,
+            &stored_text,
+            None,
+            None,
+            platform_message_id.as_deref(),
+            None,
+        )
+        .map_err(|e| format!("Failed to store message: {}", e))?;
+
+    broadcaster.broadcast(GatewayEvent::agent_response(
+        session.channel_id,
+        &session.platform_chat_id,
+        &stored_text,
+    ));
+
+    match &delivery_error {
+        None => log::info!(
+            "[INJECT] Operator message injected into session {} ({} chat {})",
+            session.id,
+            session.channel_type,
+            session.platform_chat_id
+        ),
+        Some(e) => log::warn!(
+            "[INJECT] Operator message only partly delivered to session {} ({} chat {}): {}",
+            session.id,
+            session.channel_type,
+            session.platform_chat_id,
+            e
+        ),
+    }
+    Ok(InjectedMessage { message, delivery_error })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SessionScope;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    #[tokio::test]
+    async fn test_inject_stores_message_and_broadcasts() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let broadcaster = EventBroadcaster::new();
+        let (client_id, mut event_rx) = broadcaster.subscribe();
+        let session = db
+            .get_or_create_chat_session("web", 0, "web-user", SessionScope::Dm, None)
+            .unwrap();
+
+        let injected = inject_assistant_message(&db, &broadcaster, &session, "  A human will follow up shortly.  ")
+            .await
+            .unwrap();
+        assert!(injected.delivery_error.is_none());
+        let message = injected.message;
+        assert_eq!(message.role, MessageRole::Assistant);
+        assert_eq!(message.content, "A human will follow up shortly.");
+
+        let stored = db.get_session_messages(session.id).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].role, MessageRole::Assistant);
+
+        let event = timeout(Duration::from_secs(1), event_rx.recv())
+            .await
+            .expect("broadcast received")
+            .expect("channel open");
+        assert_eq!(event.event, "agent.response");
+        assert_eq!(event.data["to"], "web-user");
+        assert_eq!(event.data["text"], "A human will follow up shortly.");
+
+        assert!(inject_assistant_message(&db, &broadcaster, &session, "   ").await.is_err());
+
+        // A platform chat that cannot be delivered to stores nothing
+        let discord = db
+            .get_or_create_chat_session("discord", 5, "not-a-channel", SessionScope::Group, None)
+            .unwrap();
+        assert!(inject_assistant_message(&db, &broadcaster, &discord, "hello").await.is_err());
+        assert!(db.get_session_messages(discord.id).unwrap().is_empty());
+        broadcaster.unsubscribe(&client_id);
+    }
+}
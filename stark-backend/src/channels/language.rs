@@ -0,0 +1,169 @@
+//! Response language resolution for channels.
+//!
+//! A channel can pin a preferred response language via the `preferred_language`
+//! channel setting. When none is configured, the user's message language is
+//! detected heuristically and remembered per identity as a `preference` memory,
+//! so the agent keeps replying in the user's language across sessions.
+
+/// Memory category used to persist an identity's detected language.
+pub const LANGUAGE_PREFERENCE_CATEGORY: &str = "language";
+
+/// Minimum number of alphabetic characters before detection is attempted.
+/// Short messages ("ok", "gm", emoji) carry too little signal.
+const MIN_DETECTION_CHARS: usize = 12;
+
+/// Minimum stopword hits required to classify a Latin-script message.
+const MIN_STOPWORD_HITS: usize = 2;
+
+/// Common function words per Latin-script language.
+const LATIN_STOPWORDS: &[(&str, &[&str])] = &[
+    ("English", &["the", "and", "is", "are", "you", "what", "how", "with", "this", "that", "my", "please", "can"]),
+    ("Spanish", &["el", "la", "los", "las", "que", "es", "por", "para", "con", "una", "cómo", "qué", "mi", "está", "hola", "gracias"]),
+    ("French", &["le", "les", "des", "est", "et", "je", "vous", "pour", "avec", "une", "mon", "c'est", "bonjour", "merci", "pas"]),
+    ("German", &["der", "die", "das", "und", "ist", "ich", "nicht", "mit", "ein", "eine", "wie", "mein", "bitte", "danke"]),
+    ("Portuguese", &["o", "os", "não", "é", "você", "com", "uma", "para", "meu", "obrigado", "olá", "como", "isso"]),
+    ("Italian", &["il", "gli", "che", "è", "non", "sono", "per", "con", "una", "mio", "ciao", "grazie", "come"]),
+];
+
+/// Map an ISO 639-1 code (or a language name) to a display name used in prompts.
+/// Unknown values are returned trimmed as-is so operators can use any label.
+pub fn language_display_name(value: &str) -> String {
+    let trimmed = value.trim();
+    let name = match trimmed.to_lowercase().as_str() {
+        "en" => "English",
+        "es" => "Spanish",
+        "fr" => "French",
+        "de" => "German",
+        "pt" => "Portuguese",
+        "it" => "Italian",
+        "ru" => "Russian",
+        "uk" => "Ukrainian",
+        "zh" => "Chinese",
+        "ja" => "Japanese",
+        "ko" => "Korean",
+        "ar" => "Arabic",
+        "he" => "Hebrew",
+        "hi" => "Hindi",
+        "el" => "Greek",
+        "th" => "Thai",
+        "tr" => "Turkish",
+        _ => return trimmed.to_string(),
+    };
+    name.to_string()
+}
+
+/// Detect the language of a user message.
+///
+/// Non-Latin scripts are identified by Unicode ranges; Latin-script text is
+/// scored against small stopword lists. Returns `None` when the message is
+/// too short or ambiguous.
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let letters: Vec<char> = text.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.len() < MIN_DETECTION_CHARS {
+        return None;
+    }
+
+    // Script-based detection: count characters per script
+    let mut counts: [(&'static str, usize); 9] = [
+        ("Japanese", 0),
+        ("Korean", 0),
+        ("Chinese", 0),
+        ("Russian", 0),
+        ("Arabic", 0),
+        ("Hebrew", 0),
+        ("Hindi", 0),
+        ("Greek", 0),
+        ("Thai", 0),
+    ];
+    for c in &letters {
+        let idx = match *c as u32 {
+            0x3040..=0x30FF => Some(0),                   // Hiragana / Katakana
+            0xAC00..=0xD7AF | 0x1100..=0x11FF => Some(1), // Hangul
+            0x4E00..=0x9FFF => Some(2),                   // CJK ideographs
+            0x0400..=0x04FF => Some(3),                   // Cyrillic
+            0x0600..=0x06FF => Some(4),                   // Arabic
+            0x0590..=0x05FF => Some(5),                   // Hebrew
+            0x0900..=0x097F => Some(6),                   // Devanagari
+            0x0370..=0x03FF => Some(7),                   // Greek
+            0x0E00..=0x0E7F => Some(8),                   // Thai
+            _ => None,
+        };
+        if let Some(i) = idx {
+            counts[i].1 += 1;
+        }
+    }
+    // Any kana means Japanese even when kanji dominate
+    if counts[0].1 > 0 {
+        return Some("Japanese");
+    }
+    if let Some((lang, n)) = counts.iter().max_by_key(|(_, n)| *n) {
+        if *n * 2 >= letters.len() {
+            return Some(lang);
+        }
+    }
+
+    // Latin-script detection via stopword hits
+    let lower = text.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !(c.is_alphanumeric() || c == '\''))
+        .filter(|w| !w.is_empty())
+        .collect();
+    let mut best: Option<(&'static str, usize)> = None;
+    let mut tie = false;
+    for (lang, stopwords) in LATIN_STOPWORDS {
+        let hits = words.iter().filter(|w| stopwords.contains(w)).count();
+        match best {
+            Some((_, best_hits)) if hits == best_hits => tie = true,
+            Some((_, best_hits)) if hits < best_hits => {}
+            _ => {
+                best = Some((lang, hits));
+                tie = false;
+            }
+        }
+    }
+    match best {
+        Some((lang, hits)) if hits >= MIN_STOPWORD_HITS && !tie => Some(lang),
+        _ => None,
+    }
+}
+
+/// Build the system prompt directive for a resolved response language.
+pub fn language_directive(language: &str) -> String {
+    format!(
+        "## Response Language\nRespond in {} unless the user writes in another language, \
+         in which case reply in the user's language.\n\n",
+        language
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_latin_languages() {
+        assert_eq!(detect_language("Hola, ¿cómo está el precio de la moneda hoy?"), Some("Spanish"));
+        assert_eq!(detect_language("Bonjour, je voudrais vérifier mon solde pour le wallet"), Some("French"));
+        assert_eq!(detect_language("Hello, what is the balance of my wallet and how do I swap?"), Some("English"));
+        assert_eq!(detect_language("Hallo, wie ist der Preis und ich möchte das wissen"), Some("German"));
+    }
+
+    #[test]
+    fn test_detect_non_latin_scripts() {
+        assert_eq!(detect_language("Привет, какой сейчас курс эфира?"), Some("Russian"));
+        assert_eq!(detect_language("こんにちは、今日のイーサリアムの価格は？"), Some("Japanese"));
+        assert_eq!(detect_language("안녕하세요 오늘 이더리움 가격은 얼마인가요"), Some("Korean"));
+    }
+
+    #[test]
+    fn test_detect_short_or_ambiguous() {
+        assert_eq!(detect_language("gm"), None);
+        assert_eq!(detect_language("0x1234567890abcdef 🚀🚀"), None);
+    }
+
+    #[test]
+    fn test_language_display_name() {
+        assert_eq!(language_display_name("es"), "Spanish");
+        assert_eq!(language_display_name(" Klingon "), "Klingon");
+    }
+}
@@ -1,13 +1,25 @@
+pub mod capabilities;
+pub mod delivery;
+pub mod digest;
 pub mod discord;
 pub mod dispatcher;
+pub mod identity_rate_limiter;
+pub mod inject;
+pub mod language;
+pub mod outbound;
+pub mod polls;
+pub mod quiet_hours;
 pub mod safe_mode_rate_limiter;
+pub mod scheduled_messages;
 pub mod session_writer;
 pub mod slack;
 pub mod telegram;
 pub mod twitter;
 pub mod types;
 pub mod util;
+pub mod webhook;
 
+pub use capabilities::{ChannelCapabilities, ChannelCapabilityRegistry};
 pub use dispatcher::MessageDispatcher;
 pub use safe_mode_rate_limiter::{SafeModeChannelRateLimiter, SafeModeQueryResult};
 pub use types::{ChannelHandle, ChannelType, NormalizedMessage};
@@ -35,6 +47,8 @@ pub struct ChannelManager {
     wallet_provider: Option<Arc<dyn crate::wallet::WalletProvider>>,
     tx_queue: Option<Arc<TxQueueManager>>,
     skill_registry: Option<Arc<crate::skills::SkillRegistry>>,
+    /// Message features per channel type, shared with every dispatcher
+    capabilities: Arc<ChannelCapabilityRegistry>,
 }
 
 impl ChannelManager {
@@ -49,6 +63,7 @@ impl ChannelManager {
             wallet_provider: None,
             tx_queue: None,
             skill_registry: None,
+            capabilities: Arc::new(ChannelCapabilityRegistry::with_builtin_channels()),
         }
     }
 
@@ -79,6 +94,7 @@ impl ChannelManager {
             wallet_provider,
             tx_queue: None,
             skill_registry: None,
+            capabilities: Arc::new(ChannelCapabilityRegistry::with_builtin_channels()),
         }
     }
 
@@ -94,6 +110,18 @@ impl ChannelManager {
         self
     }
 
+    /// Declare the message features of a channel type. Dispatchers gate tools and
+    /// shape responses from these, so new channel types register here instead of
+    /// being special-cased in the dispatcher.
+    pub fn register_capabilities(&self, channel_type: &str, capabilities: ChannelCapabilities) {
+        self.capabilities.register(channel_type, capabilities);
+    }
+
+    /// Shared capability registry (e.g. for the web dispatcher)
+    pub fn capabilities(&self) -> Arc<ChannelCapabilityRegistry> {
+        self.capabilities.clone()
+    }
+
     /// Check if a channel is currently running
     pub fn is_running(&self, channel_id: i64) -> bool {
         self.running_channels.contains_key(&channel_id)
@@ -122,7 +150,7 @@ impl ChannelManager {
                 "discord" => "discord_bot_token",
                 "telegram" => "telegram_bot_token",
                 "slack" => "slack_bot_token",
-                _ => "", // Twitter and ExternalChannel don't use bot_token
+                _ => "", // Twitter, ExternalChannel and Webhook don't use bot_token
             };
             if !setting_key.is_empty() {
                 if let Ok(Some(token)) = self.db.get_channel_setting(channel_id, setting_key) {
@@ -159,12 +187,12 @@ impl ChannelManager {
             if let Some(ref tx_queue) = self.tx_queue {
                 disp = disp.with_tx_queue(tx_queue.clone());
             }
-            Arc::new(disp)
+            Arc::new(disp.with_channel_capabilities(self.capabilities.clone()))
         } else {
-            Arc::new(MessageDispatcher::new_without_tools(
-                self.db.clone(),
-                self.broadcaster.clone(),
-            ))
+            Arc::new(
+                MessageDispatcher::new_without_tools(self.db.clone(), self.broadcaster.clone())
+                    .with_channel_capabilities(self.capabilities.clone()),
+            )
         };
 
         // Store handle
@@ -283,6 +311,11 @@ impl ChannelManager {
                 // Channel being in running_channels is sufficient.
                 log::info!("External channel '{}' started (no listener)", channel_name);
             }
+            types::ChannelType::Webhook => {
+                // Inbound messages arrive via POST /api/channels/{id}/webhook;
+                // responses are delivered from there.
+                log::info!("Webhook channel '{}' started (no listener)", channel_name);
+            }
         }
 
         log::info!(
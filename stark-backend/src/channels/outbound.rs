@@ -0,0 +1,368 @@
+//! Per-chat outbound send queues.
+//!
+//! Responses can be produced faster than a platform accepts them. Each chat
+//! gets a FIFO queue drained by a single worker, so messages are delivered in
+//! order and paced under the platform's documented rate limit. `send` waits
+//! when the queue is full, pushing backpressure onto the producer instead of
+//! dropping messages. Failed sends are recorded as `ChannelSend` spans.
+//! Queues idle for `OUTBOUND_IDLE_TIMEOUT` are dropped so their workers exit.
+
+use dashmap::DashMap;
+use futures_util::future::BoxFuture;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+
+use crate::telemetry::{SpanCollector, SpanType, TelemetryStore};
+
+/// Default number of messages a chat queue holds before `send` blocks
+pub const DEFAULT_OUTBOUND_CAPACITY: usize = 64;
+/// How long a chat queue may sit empty before it is dropped
+pub const OUTBOUND_IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Sliding-window send limit: at most `max_messages` per `per`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutboundRateLimit {
+    pub max_messages: usize,
+    pub per: Duration,
+}
+
+impl OutboundRateLimit {
+    pub fn new(max_messages: usize, per: Duration) -> Self {
+        Self { max_messages: max_messages.max(1), per }
+    }
+
+    /// Documented per-chat limits for rate-limited platforms.
+    /// Discord: 5 messages / 5s per channel. Telegram: ~1 message/s per chat.
+    /// Slack: ~1 message/s per channel.
+    pub fn for_channel_type(channel_type: &str) -> Option<Self> {
+        match channel_type {
+            "discord" => Some(Self::new(5, Duration::from_secs(5))),
+            "telegram" | "slack" => Some(Self::new(1, Duration::from_secs(1))),
+            _ => None,
+        }
+    }
+}
+
+/// One message to deliver, optionally as a reply to a platform message
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutboundMessage {
+    pub text: String,
+    pub reply_to: Option<String>,
+}
+
+impl OutboundMessage {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self { text: text.into(), reply_to: None }
+    }
+
+    pub fn reply_to(mut self, message_id: impl Into<String>) -> Self {
+        self.reply_to = Some(message_id.into());
+        self
+    }
+}
+
+impl From<String> for OutboundMessage {
+    fn from(text: String) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<&str> for OutboundMessage {
+    fn from(text: &str) -> Self {
+        Self::new(text)
+    }
+}
+
+/// Result of delivering one message: the platform message ID, if it returned one
+pub type SendResult = Result<Option<String>, String>;
+
+/// Delivers one message to the platform
+pub type SendFn = Arc<dyn Fn(OutboundMessage) -> BoxFuture<'static, SendResult> + Send + Sync>;
+
+enum Outbound {
+    Message(OutboundMessage, Option<oneshot::Sender<SendResult>>),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Activity of a queue, used to find idle queues
+struct QueueActivity {
+    /// Messages queued but not yet attempted
+    pending: AtomicUsize,
+    last_active: Mutex<Instant>,
+}
+
+impl QueueActivity {
+    fn touch(&self) {
+        *self.last_active.lock() = Instant::now();
+    }
+}
+
+/// Ordered, rate-limited send queue for one chat
+#[derive(Clone)]
+pub struct OutboundQueue {
+    tx: mpsc::Sender<Outbound>,
+    activity: Arc<QueueActivity>,
+}
+
+impl OutboundQueue {
+    /// Spawn the worker for a chat. `label` identifies the chat in logs and telemetry.
+    pub fn spawn(
+        label: String,
+        rate_limit: Option<OutboundRateLimit>,
+        capacity: usize,
+        telemetry: Option<Arc<TelemetryStore>>,
+        send: SendFn,
+    ) -> Self {
+        let (tx, rx) = mpsc::channel(capacity.max(1));
+        let activity = Arc::new(QueueActivity {
+            pending: AtomicUsize::new(0),
+            last_active: Mutex::new(Instant::now()),
+        });
+        tokio::spawn(run_worker(label, rate_limit, telemetry, send, rx, activity.clone()));
+        Self { tx, activity }
+    }
+
+    /// Queue a message. Waits while the queue is full.
+    /// Delivery failures are logged and recorded, not returned.
+    pub async fn send(&self, message: impl Into<OutboundMessage>) -> Result<(), String> {
+        self.enqueue(Outbound::Message(message.into(), None)).await
+    }
+
+    /// Queue a message and wait until it has been delivered (in order, after
+    /// everything queued before it). Returns the platform message ID.
+    pub async fn send_and_wait(&self, message: impl Into<OutboundMessage>) -> SendResult {
+        let (done_tx, done_rx) = oneshot::channel();
+        self.enqueue(Outbound::Message(message.into(), Some(done_tx))).await?;
+        done_rx.await.map_err(|_| "Outbound queue closed".to_string())?
+    }
+
+    async fn enqueue(&self, item: Outbound) -> Result<(), String> {
+        self.activity.pending.fetch_add(1, Ordering::SeqCst);
+        self.activity.touch();
+        self.tx.send(item).await.map_err(|_| {
+            self.activity.pending.fetch_sub(1, Ordering::SeqCst);
+            "Outbound queue closed".to_string()
+        })
+    }
+
+    /// Whether nothing is queued and nothing was sent for at least `idle_for`
+    fn is_idle(&self, idle_for: Duration) -> bool {
+        self.activity.pending.load(Ordering::SeqCst) == 0
+            && self.activity.last_active.lock().elapsed() >= idle_for
+    }
+
+    /// Wait until every message queued before this call has been attempted
+    pub async fn flush(&self) {
+        let (done_tx, done_rx) = oneshot::channel();
+        if self.tx.send(Outbound::Flush(done_tx)).await.is_ok() {
+            let _ = done_rx.await;
+        }
+    }
+}
+
+async fn run_worker(
+    label: String,
+    rate_limit: Option<OutboundRateLimit>,
+    telemetry: Option<Arc<TelemetryStore>>,
+    send: SendFn,
+    mut rx: mpsc::Receiver<Outbound>,
+    activity: Arc<QueueActivity>,
+) {
+    let mut sent_at: VecDeque<Instant> = VecDeque::new();
+    while let Some(item) = rx.recv().await {
+        let (message, done) = match item {
+            Outbound::Message(message, done) => (message, done),
+            Outbound::Flush(done) => {
+                let _ = done.send(());
+                continue;
+            }
+        };
+
+        if let Some(limit) = rate_limit {
+            while let Some(&oldest) = sent_at.front() {
+                if oldest.elapsed() >= limit.per {
+                    sent_at.pop_front();
+                } else {
+                    break;
+                }
+            }
+            if sent_at.len() >= limit.max_messages {
+                if let Some(&oldest) = sent_at.front() {
+                    tokio::time::sleep_until(oldest + limit.per).await;
+                }
+                sent_at.pop_front();
+            }
+            sent_at.push_back(Instant::now());
+        }
+
+        let text_chars = message.text.chars().count();
+        let result = send(message).await;
+        if let Err(ref e) = result {
+            log::error!("[OUTBOUND] Failed to deliver message to {}: {}", label, e);
+            if let Some(ref store) = telemetry {
+                record_send_failure(store, &label, text_chars, e);
+            }
+        }
+        activity.touch();
+        activity.pending.fetch_sub(1, Ordering::SeqCst);
+        if let Some(done) = done {
+            let _ = done.send(result);
+        }
+    }
+}
+
+/// Persist a failed `ChannelSend` span so delivery failures show up in telemetry
+fn record_send_failure(store: &TelemetryStore, label: &str, text_chars: usize, error: &str) {
+    let collector = SpanCollector::new(format!("outbound:{}", label), 0);
+    let mut span = collector.start_span(SpanType::ChannelSend, label).with_attributes(serde_json::json!({
+        "chat": label,
+        "message_chars": text_chars,
+    }));
+    span.fail(error.to_string());
+    collector.record(span);
+    store.persist_spans(&collector);
+}
+
+/// Lazily-created send queues keyed by chat
+pub struct OutboundQueues {
+    rate_limit: Option<OutboundRateLimit>,
+    capacity: usize,
+    idle_timeout: Duration,
+    telemetry: Option<Arc<TelemetryStore>>,
+    queues: DashMap<String, OutboundQueue>,
+}
+
+impl OutboundQueues {
+    /// Queues for a channel type, paced by its platform rate limit
+    pub fn for_channel_type(channel_type: &str) -> Self {
+        Self {
+            rate_limit: OutboundRateLimit::for_channel_type(channel_type),
+            capacity: DEFAULT_OUTBOUND_CAPACITY,
+            idle_timeout: OUTBOUND_IDLE_TIMEOUT,
+            telemetry: None,
+            queues: DashMap::new(),
+        }
+    }
+
+    pub fn with_telemetry(mut self, telemetry: Arc<TelemetryStore>) -> Self {
+        self.telemetry = Some(telemetry);
+        self
+    }
+
+    /// Get the queue for `chat_key`, spawning it with `make_send` on first use.
+    /// Idle queues of other chats are dropped first.
+    pub fn queue(&self, chat_key: &str, make_send: impl FnOnce() -> SendFn) -> OutboundQueue {
+        self.reap_idle();
+        self.queues
+            .entry(chat_key.to_string())
+            .or_insert_with(|| {
+                OutboundQueue::spawn(
+                    chat_key.to_string(),
+                    self.rate_limit,
+                    self.capacity,
+                    self.telemetry.clone(),
+                    make_send(),
+                )
+            })
+            .clone()
+    }
+
+    /// Drop queues with nothing pending that have been idle past the timeout.
+    /// A dropped queue's worker exits once the last clone of it is gone.
+    fn reap_idle(&self) {
+        let idle_timeout = self.idle_timeout;
+        self.queues.retain(|_, queue| !queue.is_idle(idle_timeout));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_burst_delivered_in_order_under_rate_limit() {
+        let delivered: Arc<Mutex<Vec<(String, Instant)>>> = Arc::new(Mutex::new(Vec::new()));
+        let sink = delivered.clone();
+        let send: SendFn = Arc::new(move |message: OutboundMessage| {
+            let sink = sink.clone();
+            Box::pin(async move {
+                // Simulated platform latency varies per message
+                let delay = if message.text.ends_with('0') { 20 } else { 2 };
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                sink.lock().unwrap().push((message.text, Instant::now()));
+                Ok(None)
+            })
+        });
+
+        let limit = OutboundRateLimit::new(2, Duration::from_millis(200));
+        let queue = OutboundQueue::spawn("test-chat".to_string(), Some(limit), 4, None, send);
+
+        let start = Instant::now();
+        for i in 0..7 {
+            queue.send(format!("msg {}", i)).await.unwrap();
+        }
+        queue.flush().await;
+
+        let delivered = delivered.lock().unwrap();
+        let texts: Vec<&str> = delivered.iter().map(|(t, _)| t.as_str()).collect();
+        assert_eq!(texts, vec!["msg 0", "msg 1", "msg 2", "msg 3", "msg 4", "msg 5", "msg 6"]);
+
+        // No more than 2 sends may start in any 200ms window: 7 messages need >= 3 windows
+        assert!(start.elapsed() >= Duration::from_millis(600));
+        for pair in delivered.windows(3) {
+            assert!(
+                pair[2].1 - pair[0].1 >= Duration::from_millis(180),
+                "three sends landed within one rate-limit window"
+            );
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_send_and_wait_returns_message_id_and_idle_queues_are_dropped() {
+        let send_calls = Arc::new(AtomicUsize::new(0));
+        let make_send = || -> SendFn {
+            let send_calls = send_calls.clone();
+            Arc::new(move |message: OutboundMessage| {
+                send_calls.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async move {
+                    match message.reply_to {
+                        Some(id) => Ok(Some(format!("reply-to-{}", id))),
+                        None => Err("no reply target".to_string()),
+                    }
+                })
+            })
+        };
+        let queues = OutboundQueues::for_channel_type("telegram");
+
+        let queue = queues.queue("chat-a", make_send);
+        let sent = queue.send_and_wait(OutboundMessage::new("hi").reply_to("42")).await;
+        assert_eq!(sent, Ok(Some("reply-to-42".to_string())));
+        assert!(queue.send_and_wait("no reply").await.is_err());
+        drop(queue);
+
+        // Still in use: a second lookup reuses the same queue
+        queues.queue("chat-a", make_send);
+        assert_eq!(queues.queues.len(), 1);
+
+        tokio::time::advance(OUTBOUND_IDLE_TIMEOUT).await;
+        queues.queue("chat-b", make_send);
+        assert!(!queues.queues.contains_key("chat-a"), "idle queue should be dropped");
+        assert!(queues.queues.contains_key("chat-b"));
+        assert_eq!(send_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_platform_rate_limits() {
+        assert_eq!(
+            OutboundRateLimit::for_channel_type("discord"),
+            Some(OutboundRateLimit::new(5, Duration::from_secs(5)))
+        );
+        assert!(OutboundRateLimit::for_channel_type("web").is_none());
+    }
+}
@@ -0,0 +1,259 @@
+//! Interactive polls posted by the `create_poll` tool.
+//!
+//! Discord and Telegram render each option as a button whose payload is
+//! `poll:<poll_id>:<option_index>`; the channel listeners record a vote when
+//! one is pressed. Channels without interactive components (Slack) get a
+//! numbered list instead, and votes are tallied from emoji reactions when the
+//! window closes. The scheduler closes due polls and reports the results back
+//! to the originating chat session as a follow-up message.
+
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::db::tables::polls::{Poll, PollTally};
+use crate::channels::util::channel_bot_token;
+use crate::db::Database;
+use crate::text::truncate_chars;
+
+/// Options rendered as buttons; votes recorded as they are pressed
+pub const POLL_MODE_BUTTONS: &str = "buttons";
+/// Options rendered as a numbered list; votes tallied from reactions at close
+pub const POLL_MODE_REACTIONS: &str = "reactions";
+
+/// Maximum options per poll (bounded by the numbered reaction emoji)
+pub const MAX_POLL_OPTIONS: usize = 10;
+
+const VOTE_PAYLOAD_PREFIX: &str = "poll:";
+
+/// Slack emoji names used to vote for options 1..=10
+const NUMBER_EMOJI: [&str; MAX_POLL_OPTIONS] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "keycap_ten",
+];
+
+/// How polls are rendered on a channel type, or None if polls are unsupported
+pub fn poll_mode_for_channel(channel_type: &str) -> Option<&'static str> {
+    match channel_type {
+        "discord" | "telegram" => Some(POLL_MODE_BUTTONS),
+        "slack" => Some(POLL_MODE_REACTIONS),
+        _ => None,
+    }
+}
+
+/// Button payload identifying a poll option
+pub fn vote_payload(poll_id: &str, option_index: usize) -> String {
+    format!("{}{}:{}", VOTE_PAYLOAD_PREFIX, poll_id, option_index)
+}
+
+/// Parse a button payload into (poll_id, option_index)
+pub fn parse_vote_payload(payload: &str) -> Option<(&str, usize)> {
+    let rest = payload.strip_prefix(VOTE_PAYLOAD_PREFIX)?;
+    let (poll_id, index) = rest.rsplit_once(':')?;
+    if poll_id.is_empty() {
+        return None;
+    }
+    Some((poll_id, index.parse().ok()?))
+}
+
+/// Discord message components: action rows of up to 5 buttons
+pub fn discord_components(poll: &Poll) -> Value {
+    let buttons: Vec<Value> = poll
+        .options
+        .iter()
+        .enumerate()
+        .map(|(i, option)| {
+            json!({
+                "type": 2,
+                "style": 1,
+                "label": truncate_chars(option, 80),
+                "custom_id": vote_payload(&poll.poll_id, i),
+            })
+        })
+        .collect();
+    let rows: Vec<Value> = buttons
+        .chunks(5)
+        .map(|chunk| json!({ "type": 1, "components": chunk }))
+        .collect();
+    Value::Array(rows)
+}
+
+/// Telegram inline keyboard: one button per row
+pub fn telegram_inline_keyboard(poll: &Poll) -> Value {
+    let rows: Vec<Value> = poll
+        .options
+        .iter()
+        .enumerate()
+        .map(|(i, option)| json!([{ "text": option, "callback_data": vote_payload(&poll.poll_id, i) }]))
+        .collect();
+    json!({ "inline_keyboard": rows })
+}
+
+/// Message text for a poll. Reaction polls list the emoji to vote with.
+pub fn poll_text(poll: &Poll) -> String {
+    let mut text = format!("📊 {}", poll.question);
+    for (i, option) in poll.options.iter().enumerate() {
+        if poll.mode == POLL_MODE_REACTIONS {
+            text.push_str(&format!("\n:{}: {}", NUMBER_EMOJI[i], option));
+        } else {
+            text.push_str(&format!("\n{}. {}", i + 1, option));
+        }
+    }
+    if poll.mode == POLL_MODE_REACTIONS {
+        text.push_str("\n\nReact with the matching number to vote.");
+    }
+    text
+}
+
+/// Follow-up message delivered to the agent when a poll closes
+pub fn poll_results_message(tally: &PollTally) -> String {
+    format!("[Poll Results] The poll {} has closed.\n\n{}", tally.poll_id, tally.summary())
+}
+
+/// Fetch reaction votes for a Slack poll message as (user_id, option_index).
+/// A user reacting with several numbers keeps the first one.
+async fn fetch_slack_reaction_votes(
+    client: &reqwest::Client,
+    bot_token: &str,
+    channel: &str,
+    ts: &str,
+) -> Result<Vec<(String, usize)>, String> {
+    let response = client
+        .get("https://slack.com/api/reactions.get")
+        .header("Authorization", format!("Bearer {}", bot_token))
+        .query(&[("channel", channel), ("timestamp", ts), ("full", "true")])
+        .send()
+        .await
+        .map_err(|e| format!("Slack request failed: {}", e))?;
+    let body: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid Slack response: {}", e))?;
+    if !body.get("ok").and_then(|v| v.as_bool()).unwrap_or(false) {
+        let error = body.get("error").and_then(|v| v.as_str()).unwrap_or("unknown error");
+        return Err(format!("Slack API error: {}", error));
+    }
+
+    let mut votes: Vec<(String, usize)> = Vec::new();
+    let reactions = body["message"]["reactions"].as_array().cloned().unwrap_or_default();
+    for reaction in reactions {
+        let name = reaction.get("name").and_then(|v| v.as_str()).unwrap_or("");
+        let Some(index) = NUMBER_EMOJI.iter().position(|e| *e == name) else {
+            continue;
+        };
+        for user_id in reaction["users"].as_array().into_iter().flatten().filter_map(|u| u.as_str()) {
+            if !votes.iter().any(|(u, _)| u == user_id) {
+                votes.push((user_id.to_string(), index));
+            }
+        }
+    }
+    Ok(votes)
+}
+
+/// Close a due poll and return its final tally.
+/// Reaction polls have their reactions recorded as votes first.
+/// Returns None if another caller already closed the poll.
+pub async fn close_poll(db: &Arc<Database>, poll: &Poll) -> Result<Option<PollTally>, String> {
+    if poll.mode == POLL_MODE_REACTIONS {
+        match (&poll.platform_message_id, channel_bot_token(db, poll.channel_id, "slack_bot_token")) {
+            (Some(ts), Some(token)) => {
+                let client = crate::http::shared_client();
+                match fetch_slack_reaction_votes(client, &token, &poll.chat_id, ts).await {
+                    Ok(votes) => {
+                        for (user_id, index) in votes {
+                            let _ = db.upsert_poll_vote(poll, &user_id, None, index);
+                        }
+                    }
+                    Err(e) => log::warn!("[POLL] Failed to tally reactions for poll {}: {}", poll.poll_id, e),
+                }
+            }
+            _ => log::warn!("[POLL] Cannot tally reactions for poll {}: missing message or token", poll.poll_id),
+        }
+    }
+
+    if !db.close_poll(&poll.poll_id).map_err(|e| e.to_string())? {
+        return Ok(None);
+    }
+    db.tally_poll(&poll.poll_id).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::tables::polls::POLL_STATUS_OPEN;
+    use chrono::{Duration, Utc};
+
+    fn open_poll(poll_id: &str, closes_in: Duration) -> Poll {
+        Poll {
+            poll_id: poll_id.to_string(),
+            channel_id: 1,
+            channel_type: "discord".to_string(),
+            chat_id: "1122334455667788".to_string(),
+            session_id: None,
+            question: "Next meetup?".to_string(),
+            options: vec!["Friday".to_string(), "Saturday".to_string(), "Sunday".to_string()],
+            mode: POLL_MODE_BUTTONS.to_string(),
+            platform_message_id: None,
+            status: POLL_STATUS_OPEN.to_string(),
+            closes_at: Utc::now() + closes_in,
+            created_at: Utc::now().to_rfc3339(),
+        }
+    }
+
+    #[test]
+    fn test_vote_payload_roundtrip() {
+        let payload = vote_payload("abc123", 2);
+        assert_eq!(parse_vote_payload(&payload), Some(("abc123", 2)));
+        assert_eq!(parse_vote_payload("poll::1"), None);
+        assert_eq!(parse_vote_payload("other:abc:1"), None);
+    }
+
+    #[test]
+    fn test_create_poll_and_tally_votes() {
+        let db = Database::new(":memory:").unwrap();
+        db.create_poll(&open_poll("p1", Duration::minutes(10))).unwrap();
+
+        let stored = db.get_poll("p1").unwrap().expect("poll stored");
+        assert_eq!(stored.options.len(), 3);
+        assert_eq!(stored.status, POLL_STATUS_OPEN);
+
+        assert!(db.record_poll_vote("p1", "alice", Some("Alice"), 0).unwrap());
+        assert!(db.record_poll_vote("p1", "bob", None, 1).unwrap());
+        assert!(db.record_poll_vote("p1", "carol", None, 1).unwrap());
+        // Voting again changes the voter's choice instead of adding a vote
+        assert!(db.record_poll_vote("p1", "alice", Some("Alice"), 1).unwrap());
+        assert!(!db.record_poll_vote("p1", "dave", None, 7).unwrap());
+
+        let tally = db.tally_poll("p1").unwrap().unwrap();
+        let votes: Vec<i64> = tally.options.iter().map(|o| o.votes).collect();
+        assert_eq!(votes, vec![0, 3, 0]);
+        assert_eq!(tally.total_votes, 3);
+        assert!(poll_results_message(&tally).contains("Saturday — 3 (100%)"));
+    }
+
+    #[tokio::test]
+    async fn test_due_poll_closes_once() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        db.create_poll(&open_poll("p2", Duration::seconds(-1))).unwrap();
+        assert!(!db.record_poll_vote("p2", "late", None, 0).unwrap(), "votes after the window are rejected");
+
+        let due = db.list_due_polls(Utc::now()).unwrap();
+        assert_eq!(due.len(), 1);
+        let tally = close_poll(&db, &due[0]).await.unwrap().expect("first close reports");
+        assert_eq!(tally.total_votes, 0);
+        assert!(close_poll(&db, &due[0]).await.unwrap().is_none());
+        assert!(db.list_due_polls(Utc::now()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_due_polls_compare_parsed_timestamps() {
+        let db = Database::new(":memory:").unwrap();
+        db.create_poll(&open_poll("p3", Duration::minutes(10))).unwrap();
+        // 12:00 at +05:00 is 07:00 UTC, which sorts after 07:30 UTC as text
+        db.conn()
+            .execute("UPDATE polls SET closes_at = '2026-01-01T12:00:00+05:00' WHERE poll_id = 'p3'", [])
+            .unwrap();
+
+        let at = |s: &str| chrono::DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        assert!(db.list_due_polls(at("2026-01-01T06:59:00Z")).unwrap().is_empty());
+        assert_eq!(db.list_due_polls(at("2026-01-01T07:30:00Z")).unwrap().len(), 1);
+    }
+}
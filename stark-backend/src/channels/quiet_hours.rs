@@ -0,0 +1,299 @@
+//! Per-channel quiet hours.
+//!
+//! A channel's `quiet_hours` setting names a daily window in a time zone
+//! (e.g. `22:00-07:00 America/New_York`) in which the bot must not post
+//! proactively. Cron deliveries produced inside the window are stored as
+//! deferred messages and sent by the scheduler once the window ends. Replies
+//! to user messages never pass through here and are unaffected.
+
+use chrono::{DateTime, Duration, NaiveTime, Timelike, Utc};
+use jiff::civil::Time;
+use jiff::tz::{Offset, TimeZone};
+use jiff::Timestamp;
+use std::future::Future;
+
+use crate::db::tables::deferred_messages::DeferredMessage;
+use crate::db::Database;
+
+/// Send attempts before a deferred message is dropped
+pub const MAX_DEFERRED_SEND_ATTEMPTS: i64 = 5;
+/// Wait before retrying a deferred message that failed to send
+const DEFERRED_RETRY_MINUTES: i64 = 5;
+
+/// A daily quiet window in a time zone
+#[derive(Debug, Clone)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub zone: TimeZone,
+}
+
+impl QuietHours {
+    /// Parse `HH:MM-HH:MM` with an optional IANA time zone (`Europe/Berlin`)
+    /// or fixed UTC offset (`+02:00`, `-0500`, `UTC`).
+    /// The window may wrap midnight (`22:00-07:00`).
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut parts = value.split_whitespace();
+        let window = parts.next().ok_or_else(|| "Quiet hours are empty".to_string())?;
+        let (start, end) = window
+            .split_once('-')
+            .ok_or_else(|| format!("Quiet hours '{}' must look like HH:MM-HH:MM", window))?;
+        let parse_time = |s: &str| {
+            NaiveTime::parse_from_str(s, "%H:%M").map_err(|_| format!("Invalid quiet hours time '{}'", s))
+        };
+        let zone = match parts.next() {
+            None => TimeZone::UTC,
+            Some(tz) => parse_zone(tz)?,
+        };
+        if parts.next().is_some() {
+            return Err(format!("Unexpected text after quiet hours '{}'", value));
+        }
+        Ok(Self { start: parse_time(start)?, end: parse_time(end)?, zone })
+    }
+
+    /// Whether `now` falls inside the window. Equal start and end means no window.
+    pub fn contains(&self, now: DateTime<Utc>) -> bool {
+        let local = to_timestamp(now).to_zoned(self.zone.clone());
+        let time = NaiveTime::from_hms_opt(local.hour() as u32, local.minute() as u32, local.second() as u32)
+            .unwrap_or_default();
+        if self.start <= self.end {
+            time >= self.start && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// The next time the window ends after `now`. A wall-clock end skipped by
+    /// a DST change resolves to the first instant after the gap.
+    pub fn ends_at(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let now_ts = to_timestamp(now);
+        let local = now_ts.to_zoned(self.zone.clone());
+        let end_time = Time::constant(self.end.hour() as i8, self.end.minute() as i8, 0, 0);
+        let mut date = local.date();
+        for _ in 0..3 {
+            if let Ok(end) = date.to_datetime(end_time).to_zoned(self.zone.clone()) {
+                if end.timestamp() > now_ts {
+                    return DateTime::from_timestamp(end.timestamp().as_second(), 0).unwrap_or(now);
+                }
+            }
+            date = match date.tomorrow() {
+                Ok(next) => next,
+                Err(_) => break,
+            };
+        }
+        now
+    }
+
+    /// The channel's configured quiet hours, if any. Invalid values are logged and ignored.
+    pub fn for_channel(db: &Database, channel_id: i64) -> Option<Self> {
+        let value = db.get_channel_setting(channel_id, "quiet_hours").ok().flatten()?;
+        if value.trim().is_empty() {
+            return None;
+        }
+        match Self::parse(&value) {
+            Ok(quiet) => Some(quiet),
+            Err(e) => {
+                log::warn!("[QUIET_HOURS] Ignoring quiet hours for channel {}: {}", channel_id, e);
+                None
+            }
+        }
+    }
+}
+
+fn to_timestamp(at: DateTime<Utc>) -> Timestamp {
+    Timestamp::from_second(at.timestamp()).unwrap_or(Timestamp::UNIX_EPOCH)
+}
+
+/// An IANA zone name, `UTC`, or a fixed offset like `+02:00`
+fn parse_zone(tz: &str) -> Result<TimeZone, String> {
+    if tz.eq_ignore_ascii_case("utc") || tz.eq_ignore_ascii_case("z") {
+        return Ok(TimeZone::UTC);
+    }
+    if tz.starts_with('+') || tz.starts_with('-') {
+        return parse_offset(tz).map(TimeZone::fixed);
+    }
+    TimeZone::get(tz).map_err(|_| format!("Unknown quiet hours time zone '{}' (use e.g. Europe/Berlin or +02:00)", tz))
+}
+
+fn parse_offset(tz: &str) -> Result<Offset, String> {
+    let invalid = || format!("Invalid quiet hours UTC offset '{}' (use e.g. +02:00)", tz);
+    let (sign, rest) = match tz.chars().next() {
+        Some('+') => (1, &tz[1..]),
+        Some('-') => (-1, &tz[1..]),
+        _ => return Err(invalid()),
+    };
+    let digits: String = rest.chars().filter(|c| *c != ':').collect();
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let hours: i32 = digits[..2].parse().map_err(|_| invalid())?;
+    let minutes: i32 = digits[2..].parse().map_err(|_| invalid())?;
+    Offset::from_seconds(sign * (hours * 3600 + minutes * 60)).map_err(|_| invalid())
+}
+
+/// Queue `text` for after the channel's quiet hours if `now` is inside them.
+/// Returns true when the message was deferred and must not be sent now.
+pub fn defer_if_quiet(
+    db: &Database,
+    channel_id: i64,
+    chat_id: Option<&str>,
+    source: &str,
+    text: &str,
+    now: DateTime<Utc>,
+) -> Result<bool, String> {
+    let Some(quiet) = QuietHours::for_channel(db, channel_id) else {
+        return Ok(false);
+    };
+    if !quiet.contains(now) {
+        return Ok(false);
+    }
+    let deliver_after = quiet.ends_at(now);
+    db.defer_message(channel_id, chat_id, source, text, deliver_after)
+        .map_err(|e| format!("Failed to defer message: {}", e))?;
+    log::info!(
+        "[QUIET_HOURS] Channel {} is in quiet hours, deferred {} until {}",
+        channel_id,
+        source,
+        deliver_after.to_rfc3339()
+    );
+    Ok(true)
+}
+
+/// Send the deferred messages due at `now` with `send`. Each message is
+/// removed only once it has been sent; failed sends are retried a few times
+/// before the message is dropped. Returns the number of messages sent.
+pub async fn flush_deferred_messages<F, Fut>(db: &Database, now: DateTime<Utc>, mut send: F) -> Result<usize, String>
+where
+    F: FnMut(DeferredMessage) -> Fut,
+    Fut: Future<Output = Result<(), String>>,
+{
+    let due = db
+        .list_due_deferred_messages(now)
+        .map_err(|e| format!("Failed to list deferred messages: {}", e))?;
+
+    let mut sent = 0;
+    for message in due {
+        let (id, channel_id, attempts) = (message.id, message.channel_id, message.attempts);
+        log::info!("[QUIET_HOURS] Quiet hours over for channel {}, sending deferred {}", channel_id, message.source);
+        let outcome = match send(message).await {
+            Ok(()) => {
+                sent += 1;
+                db.delete_deferred_message(id)
+            }
+            Err(e) if attempts + 1 >= MAX_DEFERRED_SEND_ATTEMPTS => {
+                log::error!("[QUIET_HOURS] Giving up on deferred message {} for channel {}: {}", id, channel_id, e);
+                db.delete_deferred_message(id)
+            }
+            Err(e) => {
+                log::warn!("[QUIET_HOURS] Failed to send deferred message {} for channel {}, will retry: {}", id, channel_id, e);
+                db.retry_deferred_message(id, now + Duration::minutes(DEFERRED_RETRY_MINUTES))
+            }
+        };
+        if let Err(e) = outcome {
+            log::error!("[QUIET_HOURS] Failed to update deferred message {}: {}", id, e);
+        }
+    }
+    Ok(sent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_parse_and_overnight_window() {
+        let quiet = QuietHours::parse("22:00-07:00 -05:00").unwrap();
+        // 03:30 UTC is 22:30 in UTC-5
+        assert!(quiet.contains(utc("2026-03-10T03:30:00Z")));
+        assert!(quiet.contains(utc("2026-03-10T11:59:00Z")));
+        assert!(!quiet.contains(utc("2026-03-10T12:00:00Z")));
+        assert_eq!(quiet.ends_at(utc("2026-03-10T03:30:00Z")), utc("2026-03-10T12:00:00Z"));
+
+        assert!(QuietHours::parse("09:00-17:00").unwrap().contains(utc("2026-03-10T09:00:00Z")));
+        assert!(QuietHours::parse("9pm-7am").is_err());
+        assert!(QuietHours::parse("22:00-07:00 Mars/Olympus_Mons").is_err());
+    }
+
+    #[test]
+    fn test_iana_zone_follows_daylight_saving() {
+        let quiet = QuietHours::parse("22:00-07:00 America/New_York").unwrap();
+        // Winter: New York is UTC-5
+        assert!(quiet.contains(utc("2026-01-15T03:30:00Z")));
+        assert_eq!(quiet.ends_at(utc("2026-01-15T03:30:00Z")), utc("2026-01-15T12:00:00Z"));
+        // After the March switch New York is UTC-4, so the window ends an hour earlier in UTC
+        assert!(quiet.contains(utc("2026-03-10T10:30:00Z")));
+        assert!(!quiet.contains(utc("2026-03-10T11:30:00Z")));
+        assert_eq!(quiet.ends_at(utc("2026-03-10T03:30:00Z")), utc("2026-03-10T11:00:00Z"));
+    }
+
+    #[tokio::test]
+    async fn test_cron_delivery_during_quiet_hours_is_deferred_until_window_ends() {
+        let db = Database::new(":memory:").unwrap();
+        let quiet = db.create_channel("discord", "community", "fake-token", None).unwrap();
+        let loud = db.create_channel("discord", "ops", "fake-token", None).unwrap();
+        db.set_channel_setting(quiet.id, "quiet_hours", "22:00-07:00 +00:00").unwrap();
+
+        let night = utc("2026-03-10T23:15:00Z");
+        assert!(defer_if_quiet(&db, quiet.id, Some("general"), "cron:daily-report", "Daily report", night).unwrap());
+        // Other channels and daytime deliveries go out immediately
+        assert!(!defer_if_quiet(&db, loud.id, None, "cron:daily-report", "Daily report", night).unwrap());
+        let noon = utc("2026-03-10T12:00:00Z");
+        assert!(!defer_if_quiet(&db, quiet.id, None, "cron:daily-report", "Daily report", noon).unwrap());
+
+        let delivered: Arc<Mutex<Vec<DeferredMessage>>> = Arc::new(Mutex::new(Vec::new()));
+        let record = |message: DeferredMessage| {
+            let delivered = Arc::clone(&delivered);
+            async move {
+                delivered.lock().unwrap().push(message);
+                Ok(())
+            }
+        };
+
+        assert_eq!(flush_deferred_messages(&db, utc("2026-03-11T06:59:00Z"), record).await.unwrap(), 0);
+        assert_eq!(flush_deferred_messages(&db, utc("2026-03-11T07:00:00Z"), record).await.unwrap(), 1);
+        {
+            let due = delivered.lock().unwrap();
+            assert_eq!(due.len(), 1);
+            assert_eq!(due[0].channel_id, quiet.id);
+            assert_eq!(due[0].chat_id.as_deref(), Some("general"));
+            assert_eq!(due[0].text, "Daily report");
+        }
+        // Flushed messages are not sent twice
+        assert_eq!(flush_deferred_messages(&db, utc("2026-03-11T08:00:00Z"), record).await.unwrap(), 0);
+        assert_eq!(delivered.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_deferred_send_is_kept_and_retried() {
+        let db = Database::new(":memory:").unwrap();
+        let at = utc("2026-03-11T07:00:00Z");
+        db.defer_message(1, Some("42"), "cron:a", "first", at).unwrap();
+        db.defer_message(1, Some("42"), "cron:b", "second", at).unwrap();
+
+        // The first send fails: the rest are still attempted and the failure is kept
+        let sent = flush_deferred_messages(&db, at, |message| async move {
+            if message.text == "first" { Err("platform down".to_string()) } else { Ok(()) }
+        })
+        .await
+        .unwrap();
+        assert_eq!(sent, 1);
+        let remaining = db.list_due_deferred_messages(at + Duration::hours(1)).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].text, "first");
+        assert_eq!(remaining[0].attempts, 1);
+        assert!(db.list_due_deferred_messages(at).unwrap().is_empty(), "retry waits before the next attempt");
+
+        // Dropped after the last attempt
+        for hour in 1..MAX_DEFERRED_SEND_ATTEMPTS {
+            flush_deferred_messages(&db, at + Duration::hours(hour), |_| async { Err("still down".to_string()) })
+                .await
+                .unwrap();
+        }
+        assert!(db.list_due_deferred_messages(at + Duration::days(1)).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+//! Delayed messages scheduled by the `schedule_message` tool.
+//!
+//! A lighter alternative to a cron job for "remind the channel in 2 hours":
+//! the message is stored as pending with its send time, and the scheduler
+//! posts due messages to the chat through the channel's outbound queue (no
+//! agent run).
+//! Each owner (identity, or platform user when there is none) may only have
+//! a limited number of messages pending at once.
+
+use chrono::{DateTime, Duration, Utc};
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::channels::delivery;
+use crate::db::tables::scheduled_messages::ScheduledMessage;
+use crate::db::Database;
+
+/// Most messages one owner may have pending
+pub const MAX_PENDING_PER_IDENTITY: i64 = 10;
+/// Furthest ahead a message may be scheduled
+pub const MAX_SCHEDULE_DAYS: i64 = 30;
+/// Longest message accepted
+pub const MAX_SCHEDULED_MESSAGE_CHARS: usize = 4000;
+
+/// Validate and store a scheduled message. Returns its id.
+#[allow(clippy::too_many_arguments)]
+pub fn schedule_message(
+    db: &Database,
+    channel_id: i64,
+    channel_type: &str,
+    chat_id: &str,
+    owner: &str,
+    text: &str,
+    send_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Result<i64, String> {
+    if !delivery::supports_channel_type(channel_type) {
+        return Err(format!(
+            "Scheduled messages are not supported on {} channels. Supported: discord, telegram, slack",
+            channel_type
+        ));
+    }
+    delivery::validate_chat_id(channel_type, chat_id)?;
+    if text.trim().is_empty() {
+        return Err("The message is empty".to_string());
+    }
+    if text.chars().count() > MAX_SCHEDULED_MESSAGE_CHARS {
+        return Err(format!("The message is longer than {} characters", MAX_SCHEDULED_MESSAGE_CHARS));
+    }
+    if send_at > now + Duration::days(MAX_SCHEDULE_DAYS) {
+        return Err(format!("Messages can be scheduled at most {} days ahead", MAX_SCHEDULE_DAYS));
+    }
+
+    let pending = db
+        .count_pending_scheduled_messages(owner)
+        .map_err(|e| format!("Failed to count scheduled messages: {}", e))?;
+    if pending >= MAX_PENDING_PER_IDENTITY {
+        return Err(format!(
+            "You already have {} scheduled messages waiting (limit {}). Wait for one to be sent first.",
+            pending, MAX_PENDING_PER_IDENTITY
+        ));
+    }
+
+    // A time in the past is sent on the next scheduler tick
+    let send_at = send_at.max(now);
+    db.create_scheduled_message(channel_id, channel_type, chat_id, owner, text, send_at)
+        .map_err(|e| format!("Failed to store scheduled message: {}", e))
+}
+
+/// Deliver every message due at `now` with `send`, recording each outcome.
+/// Returns the number of messages sent successfully.
+pub async fn deliver_due_messages<F, Fut>(db: &Database, now: DateTime<Utc>, mut send: F) -> Result<usize, String>
+where
+    F: FnMut(ScheduledMessage) -> Fut,
+    Fut: Future<Output = Result<(), String>>,
+{
+    let due = db
+        .list_due_scheduled_messages(now)
+        .map_err(|e| format!("Failed to list scheduled messages: {}", e))?;
+
+    let mut sent = 0;
+    for message in due {
+        let id = message.id;
+        let channel_id = message.channel_id;
+        let error = send(message).await.err();
+        match &error {
+            None => {
+                sent += 1;
+                log::info!("[SCHEDULED_MESSAGE] Sent message {} to channel {}", id, channel_id);
+            }
+            Some(e) => log::error!("[SCHEDULED_MESSAGE] Failed to send message {} to channel {}: {}", id, channel_id, e),
+        }
+        if let Err(e) = db.finish_scheduled_message(id, error.as_deref()) {
+            log::error!("[SCHEDULED_MESSAGE] Failed to record outcome of message {}: {}", id, e);
+        }
+    }
+    Ok(sent)
+}
+
+/// Post a scheduled message to its chat through the channel's outbound queue.
+/// A message delivered only in part is reported as failed and not retried.
+pub async fn send_via_channel(db: &Arc<Database>, message: &ScheduledMessage) -> Result<(), String> {
+    delivery::send_text(db, message.channel_id, &message.channel_type, &message.chat_id, &message.text)
+        .await
+        .into_result()
+        .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::tables::scheduled_messages::{SCHEDULED_STATUS_PENDING, SCHEDULED_STATUS_SENT};
+    use std::sync::Mutex;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_message_persists_and_is_sent_at_its_time() {
+        let path = std::env::temp_dir().join(format!("stark-scheduled-{}.db", uuid::Uuid::new_v4()));
+        let path_str = path.to_str().unwrap().to_string();
+        let now = utc("2026-03-10T12:00:00Z");
+        let send_at = utc("2026-03-10T14:00:00Z");
+
+        let id = {
+            let db = Database::new(&path_str).unwrap();
+            schedule_message(&db, 7, "discord", "1122334455667788", "identity-1", "Standup in 5 minutes!", send_at, now).unwrap()
+        };
+
+        // Reopening the database (a restart) keeps the pending message
+        let db = Database::new(&path_str).unwrap();
+        let stored = db.get_scheduled_message(id).unwrap().expect("message persisted");
+        assert_eq!(stored.status, SCHEDULED_STATUS_PENDING);
+
+        let delivered: Arc<Mutex<Vec<(i64, String, String)>>> = Arc::new(Mutex::new(Vec::new()));
+        let record = |message: ScheduledMessage| {
+            let delivered = Arc::clone(&delivered);
+            async move {
+                delivered.lock().unwrap().push((message.channel_id, message.chat_id, message.text));
+                Ok(())
+            }
+        };
+
+        // Not sent before its time
+        let early = deliver_due_messages(&db, send_at - Duration::minutes(1), record).await.unwrap();
+        assert_eq!(early, 0);
+        assert!(delivered.lock().unwrap().is_empty());
+
+        // Sent once at its time, then never again
+        assert_eq!(deliver_due_messages(&db, send_at, record).await.unwrap(), 1);
+        assert_eq!(deliver_due_messages(&db, send_at + Duration::hours(1), record).await.unwrap(), 0);
+        assert_eq!(
+            *delivered.lock().unwrap(),
+            vec![(7, "1122334455667788".to_string(), "Standup in 5 minutes!".to_string())]
+        );
+        assert_eq!(db.get_scheduled_message(id).unwrap().unwrap().status, SCHEDULED_STATUS_SENT);
+
+        drop(db);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_pending_messages_are_limited_per_owner() {
+        let db = Database::new(":memory:").unwrap();
+        let now = Utc::now();
+        let later = now + Duration::hours(1);
+        for _ in 0..MAX_PENDING_PER_IDENTITY {
+            schedule_message(&db, 1, "telegram", "42", "identity-1", "ping", later, now).unwrap();
+        }
+        assert!(schedule_message(&db, 1, "telegram", "42", "identity-1", "ping", later, now).is_err());
+        // Other owners are unaffected
+        assert!(schedule_message(&db, 1, "telegram", "42", "identity-2", "ping", later, now).is_ok());
+
+        assert!(schedule_message(&db, 1, "telegram", "42", "identity-2", "ping", now + Duration::days(31), now).is_err());
+        assert!(schedule_message(&db, 1, "web", "42", "identity-2", "ping", later, now).is_err());
+        assert!(schedule_message(&db, 1, "discord", "general", "identity-2", "ping", later, now).is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use crate::text::truncate_chars;
+use crate::channels::capabilities::ChannelCapabilities;
 use crate::channels::dispatcher::MessageDispatcher;
 use crate::channels::safe_mode_rate_limiter::SafeModeChannelRateLimiter;
 use crate::channels::types::{ChannelType, NormalizedMessage};
@@ -137,11 +139,7 @@ async fn fetch_chat_context(
             .text
             .as_deref()
             .unwrap_or("");
-        let preview = if text.len() > 300 {
-            format!("{}...", &text[..300])
-        } else {
-            text.to_string()
-        };
+        let preview = truncate_chars(text, 300);
         ctx.push_str(&format!("@{}{}: {}\n", who, tag, preview));
     }
 
@@ -406,11 +404,7 @@ async fn process_slack_message(
         "Slack: Message from {} ({}): {}",
         user_name,
         user_id,
-        if clean_text.len() > 50 {
-            format!("{}...", clean_text.chars().take(50).collect::<String>())
-        } else {
-            clean_text.clone()
-        }
+        truncate_chars(&clean_text, 50)
     );
 
     // Reply thread_ts: if already in a thread, use that; otherwise start a new thread under the user's message
@@ -494,13 +488,18 @@ async fn process_slack_message(
         channel_type: ChannelType::Slack.to_string(),
         chat_id: slack_channel.to_string(),
         chat_name: None,
+        is_thread: false,
         user_id: user_id.clone(),
         user_name: user_name.clone(),
         text: message_text,
+        attachments: Vec::new(),
         message_id: Some(message_ts.to_string()),
         session_mode: None,
         selected_network: None,
         force_safe_mode,
+        agent_settings_override: None,
+        ephemeral: false,
+        dry_run: false,
     };
 
     // Subscribe to events for real-time tool call forwarding
@@ -589,11 +588,7 @@ async fn process_slack_message(
                     continue;
                 }
 
-                let display_text = if text.len() > 4000 {
-                    format!("{}...", &text[..3997])
-                } else {
-                    text
-                };
+                let display_text = truncate_chars(&text, 4000);
 
                 match &status_ts {
                     Some(ts) => {
@@ -805,11 +800,7 @@ fn handle_push_event(
                     "Slack: AppMention from {} in {}: {}",
                     user_id,
                     slack_channel,
-                    if text.len() > 50 {
-                        format!("{}...", &text[..50])
-                    } else {
-                        text.clone()
-                    }
+                    truncate_chars(&text, 50)
                 );
 
                 tokio::spawn(process_slack_message(
@@ -876,11 +867,7 @@ fn handle_push_event(
                     "Slack: DM from {} in {}: {}",
                     sender_id,
                     slack_channel,
-                    if text.len() > 50 {
-                        format!("{}...", &text[..50])
-                    } else {
-                        text.clone()
-                    }
+                    truncate_chars(&text, 50)
                 );
 
                 tokio::spawn(process_slack_message(
@@ -907,6 +894,16 @@ fn handle_push_event(
 // Public entry point
 // ---------------------------------------------------------------------------
 
+/// Slack renders its own markdown flavour; long replies are split by the listener
+pub fn capabilities() -> ChannelCapabilities {
+    ChannelCapabilities {
+        supports_interactive: true,
+        supports_images: true,
+        max_message_len: None,
+        supports_markdown: true,
+    }
+}
+
 /// Start a Slack bot listener using Socket Mode with full AI dispatch
 pub async fn start_slack_listener(
     channel: Channel,
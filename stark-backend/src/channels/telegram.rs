@@ -1,4 +1,9 @@
+use crate::text::truncate_chars;
+use crate::channels::capabilities::ChannelCapabilities;
 use crate::channels::dispatcher::MessageDispatcher;
+use crate::channels::delivery;
+use crate::channels::outbound::{OutboundMessage, OutboundQueue, OutboundQueues};
+use crate::channels::polls;
 use crate::channels::types::{ChannelType, NormalizedMessage};
 use crate::channels::util;
 use crate::db::Database;
@@ -11,7 +16,7 @@ use rand::seq::SliceRandom;
 use std::sync::Arc;
 use teloxide::prelude::*;
 use teloxide::requests::Requester;
-use teloxide::types::MessageId;
+use teloxide::types::{CallbackQuery, MessageId};
 use tokio::sync::oneshot;
 
 /// Format a tool call event for Telegram display based on verbosity
@@ -26,11 +31,7 @@ fn format_tool_call_for_telegram(
         ToolOutputVerbosity::Full => {
             let params_str = serde_json::to_string_pretty(parameters)
                 .unwrap_or_else(|_| parameters.to_string());
-            let params_display = if params_str.len() > 500 {
-                format!("{}...", &params_str[..500])
-            } else {
-                params_str
-            };
+            let params_display = truncate_chars(&params_str, 500);
             Some(format!("🔧 Tool Call: {}\n{}", tool_name, params_display))
         }
     }
@@ -54,11 +55,7 @@ fn format_tool_result_for_telegram(
             ))
         }
         ToolOutputVerbosity::Full => {
-            let content_display = if content.len() > 1000 {
-                format!("{}...", &content[..1000])
-            } else {
-                content.to_string()
-            };
+            let content_display = truncate_chars(content, 1000);
             Some(format!(
                 "{} Tool Result: {} ({} ms)\n{}",
                 status, tool_name, duration_ms, content_display
@@ -230,6 +227,28 @@ async fn handle_shortcircuit_command(
     }
 }
 
+/// Get the outbound queue for a Telegram chat
+fn outbound_queue(outbound: &OutboundQueues, bot: &Bot, chat_id: ChatId) -> OutboundQueue {
+    let bot = bot.clone();
+    outbound.queue(&chat_id.to_string(), move || delivery::telegram_send_fn(bot, chat_id))
+}
+
+/// Parse a Telegram message ID returned by the outbound queue
+fn parse_message_id(id: &str) -> Option<MessageId> {
+    id.parse::<i32>().ok().map(MessageId)
+}
+
+/// Telegram replies are split into 4096-char messages by the listener. Markdown
+/// is passed through as written, so code and identifiers reach the user intact.
+pub fn capabilities() -> ChannelCapabilities {
+    ChannelCapabilities {
+        supports_interactive: true,
+        supports_images: true,
+        max_message_len: None,
+        supports_markdown: true,
+    }
+}
+
 /// Start a Telegram bot listener
 pub async fn start_telegram_listener(
     channel: Channel,
@@ -300,15 +319,24 @@ pub async fn start_telegram_listener(
     let broadcaster_for_handler = broadcaster.clone();
     let bot_username_for_handler = bot_username.clone();
     let db_for_handler = db.clone();
+    // Ordered, rate-limited send queues keyed by chat ID, shared with proactive
+    // senders. Queues from a previous run may hold a bot with an old token.
+    delivery::reset_channel_queues(channel_id);
+    let outbound = delivery::channel_queues(
+        channel_id,
+        ChannelType::Telegram.as_str(),
+        dispatcher.telemetry_store().clone(),
+    );
 
     // Create message handler
-    let handler = Update::filter_message().endpoint(
+    let message_handler = Update::filter_message().endpoint(
         move |bot: Bot, msg: teloxide::types::Message, dispatcher: Arc<MessageDispatcher>, db: Arc<Database>| {
             let channel_id = channel_id;
             let broadcaster = broadcaster_for_handler.clone();
             let admin_user_id = admin_user_id.clone();
             let bot_username = bot_username_for_handler.clone();
             let bot_user_id = bot_user_id;
+            let outbound = outbound.clone();
             async move {
                 log::info!("Telegram: Received update from chat {}", msg.chat.id);
 
@@ -377,20 +405,14 @@ pub async fn start_telegram_listener(
                         "Telegram: Message from {} ({}): {}",
                         user_name,
                         user_id,
-                        if clean_text.len() > 50 {
-                            format!("{}...", clean_text.chars().take(50).collect::<String>())
-                        } else {
-                            clean_text.clone()
-                        }
+                        truncate_chars(&clean_text, 50)
                     );
 
                     // Check for shortcircuit commands (register, status, help, love)
                     if let Some(response) = handle_shortcircuit_command(&clean_text, &user_id, &user_name, &db).await {
-                        if let Err(e) = bot
-                            .send_message(msg.chat.id, &response)
-                            .reply_to_message_id(msg.id)
-                            .await
-                        {
+                        let queue = outbound_queue(&outbound, &bot, msg.chat.id);
+                        let reply = OutboundMessage::new(response).reply_to(msg.id.0.to_string());
+                        if let Err(e) = queue.send_and_wait(reply).await {
                             log::error!("Telegram: Failed to send shortcircuit response: {}", e);
                         }
                         return Ok(());
@@ -430,11 +452,7 @@ pub async fn start_telegram_listener(
                                         let who = m.user_name.as_deref()
                                             .unwrap_or(m.user_id.as_deref().unwrap_or("unknown"));
                                         let tag = if m.is_bot_response { " [you]" } else { "" };
-                                        let preview = if m.content.len() > 300 {
-                                            format!("{}...", &m.content[..300])
-                                        } else {
-                                            m.content.clone()
-                                        };
+                                        let preview = truncate_chars(&m.content, 300);
                                         ctx.push_str(&format!("@{}{}: {}\n", who, tag, preview));
                                     }
                                     ctx.push_str(&format!("\n[MESSAGE DIRECTED TO YOU:]\n{}", clean_text));
@@ -452,22 +470,29 @@ pub async fn start_telegram_listener(
                         channel_type: ChannelType::Telegram.to_string(),
                         chat_id: msg.chat.id.to_string(),
                         chat_name: msg.chat.title().map(|t| t.to_string()),
+                        is_thread: false,
                         user_id,
                         user_name: user_name.clone(),
                         text: message_text,
+                        attachments: Vec::new(),
                         message_id: Some(msg.id.to_string()),
                         session_mode: None,
                         selected_network: None,
                         force_safe_mode,
+                        agent_settings_override: None,
+                        ephemeral: false,
+                        dry_run: false,
                     };
 
                     // Subscribe to events for real-time tool call forwarding
                     let (client_id, mut event_rx) = broadcaster.subscribe();
                     log::info!("Telegram: Subscribed to events as client {}", client_id);
 
-                    // Clone for event forwarder task
+                    // Clone for event forwarder task. Status messages are sent
+                    // through the chat's outbound queue; edits and deletes go direct.
                     let bot_for_events = bot.clone();
                     let telegram_chat_id = msg.chat.id;
+                    let status_queue = outbound_queue(&outbound, &bot, telegram_chat_id);
                     let channel_id_for_events = channel_id;
                     let chat_id_str_for_events = telegram_chat_id.to_string();
 
@@ -479,16 +504,13 @@ pub async fn start_telegram_listener(
                         let mut throttler = util::StatusThrottler::default_for_gateway();
 
                         // Send an immediate "thinking" message so users see feedback right away
-                        match bot_for_events
-                            .send_message(telegram_chat_id, "💭 Thinking...")
-                            .await
-                        {
-                            Ok(sent_msg) => {
-                                status_message_id = Some(sent_msg.id);
+                        match status_queue.send_and_wait("💭 Thinking...").await {
+                            Ok(id) => {
+                                status_message_id = id.as_deref().and_then(parse_message_id);
                                 throttler.record_success();
                                 log::debug!(
                                     "Telegram: Created initial thinking message {:?}",
-                                    sent_msg.id
+                                    status_message_id
                                 );
                             }
                             Err(e) => {
@@ -628,11 +650,7 @@ pub async fn start_telegram_listener(
                                     continue;
                                 }
 
-                                let display_text = if text.len() > 4096 {
-                                    format!("{}...", &text[..4093])
-                                } else {
-                                    text
-                                };
+                                let display_text = truncate_chars(&text, 4096);
 
                                 match status_message_id {
                                     Some(msg_id) => {
@@ -658,12 +676,12 @@ pub async fn start_telegram_listener(
                                                     let _ = bot_for_events
                                                         .delete_message(telegram_chat_id, msg_id)
                                                         .await;
-                                                    match bot_for_events
-                                                        .send_message(telegram_chat_id, &display_text)
+                                                    match status_queue
+                                                        .send_and_wait(display_text.as_str())
                                                         .await
                                                     {
-                                                        Ok(new_msg) => {
-                                                            status_message_id = Some(new_msg.id);
+                                                        Ok(id) => {
+                                                            status_message_id = id.as_deref().and_then(parse_message_id);
                                                             throttler.record_success();
                                                         }
                                                         Err(e2) => {
@@ -681,16 +699,16 @@ pub async fn start_telegram_listener(
                                     }
                                     None => {
                                         // First status message — create it
-                                        match bot_for_events
-                                            .send_message(telegram_chat_id, &display_text)
+                                        match status_queue
+                                            .send_and_wait(display_text.as_str())
                                             .await
                                         {
-                                            Ok(sent_msg) => {
-                                                status_message_id = Some(sent_msg.id);
+                                            Ok(id) => {
+                                                status_message_id = id.as_deref().and_then(parse_message_id);
                                                 throttler.record_success();
                                                 log::debug!(
                                                     "Telegram: Created status message {:?}",
-                                                    sent_msg.id
+                                                    status_message_id
                                                 );
                                             }
                                             Err(e) => {
@@ -767,23 +785,22 @@ pub async fn start_telegram_listener(
                             true,
                         );
 
+                        // Queue chunks so they arrive in order under Telegram's per-chat limit
+                        let queue = outbound_queue(&outbound, &bot, msg.chat.id);
                         let chunks = util::split_message(&result.response, 4096);
                         for chunk in chunks {
-                            if let Err(e) = bot
-                                .send_message(msg.chat.id, &chunk)
-                                .reply_to_message_id(msg.id)
-                                .await
-                            {
-                                log::error!("Failed to send Telegram message: {}", e);
+                            let reply = OutboundMessage::new(chunk).reply_to(msg.id.0.to_string());
+                            if let Err(e) = queue.send(reply).await {
+                                log::error!("Failed to queue Telegram message: {}", e);
                             }
                         }
+                        queue.flush().await;
                     } else if let Some(error) = result.error {
                         let error_msg =
                             format!("Sorry, I encountered an error: {}", error);
-                        let _ = bot
-                            .send_message(msg.chat.id, &error_msg)
-                            .reply_to_message_id(msg.id)
-                            .await;
+                        let queue = outbound_queue(&outbound, &bot, msg.chat.id);
+                        let _ = queue.send(OutboundMessage::new(error_msg).reply_to(msg.id.0.to_string())).await;
+                        queue.flush().await;
                     } else if result.response.is_empty() {
                         log::debug!("Telegram: Empty final response for user {}", user_name);
                     }
@@ -794,6 +811,15 @@ pub async fn start_telegram_listener(
         },
     );
 
+    // Poll button presses (create_poll tool)
+    let callback_handler = Update::filter_callback_query().endpoint(
+        |bot: Bot, query: CallbackQuery, db: Arc<Database>| async move {
+            handle_poll_callback(&bot, &query, &db).await;
+            Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
+        },
+    );
+    let handler = dptree::entry().branch(message_handler).branch(callback_handler);
+
     // Create dispatcher
     let mut tg_dispatcher = Dispatcher::builder(bot, handler)
         .dependencies(dptree::deps![dispatcher, db_for_handler])
@@ -819,3 +845,22 @@ pub async fn start_telegram_listener(
 
     Ok(())
 }
+
+/// Record a vote from a poll button press (create_poll tool)
+async fn handle_poll_callback(bot: &Bot, query: &CallbackQuery, db: &Database) {
+    let Some((poll_id, option_index)) = query.data.as_deref().and_then(polls::parse_vote_payload) else {
+        return;
+    };
+    let voter_name = query.from.username.clone().unwrap_or_else(|| query.from.first_name.clone());
+    let reply = match db.record_poll_vote(poll_id, &query.from.id.to_string(), Some(&voter_name), option_index) {
+        Ok(true) => "✅ Vote recorded",
+        Ok(false) => "This poll is closed",
+        Err(e) => {
+            log::error!("Telegram: Failed to record vote for poll {}: {}", poll_id, e);
+            "Failed to record your vote"
+        }
+    };
+    if let Err(e) = bot.answer_callback_query(query.id.clone()).text(reply).await {
+        log::warn!("Telegram: Failed to answer poll callback: {}", e);
+    }
+}
@@ -3,6 +3,8 @@
 //! Polls the Twitter API v2 mentions endpoint to detect and respond to @mentions.
 //! Uses OAuth 1.0a for authentication and respects rate limits.
 
+use crate::text::truncate_chars;
+use crate::channels::capabilities::ChannelCapabilities;
 use crate::channels::dispatcher::MessageDispatcher;
 use crate::channels::types::{ChannelType, NormalizedMessage};
 use crate::controllers::api_keys::ApiKeyId;
@@ -302,6 +304,17 @@ struct PostedTweet {
     text: String,
 }
 
+/// Twitter replies are one-shot plain-text posts (threaded when long): there is
+/// no interactive session for follow-up questions
+pub fn capabilities() -> ChannelCapabilities {
+    ChannelCapabilities {
+        supports_interactive: false,
+        supports_images: false,
+        max_message_len: None,
+        supports_markdown: false,
+    }
+}
+
 /// Start the Twitter mention listener
 pub async fn start_twitter_listener(
     channel: Channel,
@@ -536,11 +549,7 @@ pub async fn start_twitter_listener(
                                 log::info!(
                                     "Twitter: Processing mention from @{}: {}",
                                     author_username,
-                                    if mention.text.len() > 50 {
-                                        format!("{}...", mention.text.chars().take(50).collect::<String>())
-                                    } else {
-                                        mention.text.clone()
-                                    }
+                                    truncate_chars(&mention.text, 50)
                                 );
 
                                 // Determine safe mode: admin gets standard mode, everyone else gets safe mode.
@@ -867,7 +876,7 @@ async fn fetch_thread_context(
         log::warn!(
             "Twitter: Thread context API error ({}): {}",
             status,
-            if body.len() > 200 { &body[..200] } else { &body }
+            truncate_chars(&body, 200)
         );
         return None;
     }
@@ -1018,13 +1027,18 @@ async fn process_mention(
         // This prevents non-admin mentions from poisoning an admin session's safe mode flag.
         chat_id: tweet.id.clone(),
         chat_name: None,
+        is_thread: false,
         user_id: tweet.author_id.clone(),
         user_name: author_username.to_string(),
         text: text_with_hint,
+        attachments: Vec::new(),
         message_id: Some(tweet.id.clone()),
         session_mode: None,
         selected_network: None,
         force_safe_mode,
+        agent_settings_override: None,
+        ephemeral: false,
+        dry_run: false,
     };
 
     // Subscribe to events to capture say_to_user messages.
@@ -1079,7 +1093,8 @@ async fn process_mention(
     if !captured.is_empty() {
         let last = captured.last().unwrap().clone();
         log::info!("Twitter: Using say_to_user message ({} chars, {} total captured)", last.len(), captured.len());
-        Some(last)
+        // The tweet is the say_to_user text, not the dispatch result, so shape it here
+        Some(capabilities().format_response(&last))
     } else if let Some(error) = result.error {
         // Never tweet internal errors (loop detection, AI failures, etc.)
         log::warn!(
@@ -1243,11 +1258,7 @@ async fn post_single_tweet(
             log::info!(
                 "Twitter: Posted tweet {} - {}",
                 tweet.id,
-                if tweet.text.len() > 50 {
-                    format!("{}...", &tweet.text[..50])
-                } else {
-                    tweet.text.clone()
-                }
+                truncate_chars(&tweet.text, 50)
             );
             tweet.id
         })
@@ -1,3 +1,6 @@
+use crate::ai::Attachment;
+use crate::models::AgentSettings;
+use crate::tools::rpc_config::Network;
 use serde::{Deserialize, Serialize};
 
 /// Supported channel types
@@ -9,6 +12,7 @@ pub enum ChannelType {
     Discord,
     Twitter,
     ExternalChannel,
+    Webhook,
 }
 
 impl ChannelType {
@@ -20,6 +24,7 @@ impl ChannelType {
             Self::Discord => "discord",
             Self::Twitter => "twitter",
             Self::ExternalChannel => "external_channel",
+            Self::Webhook => "webhook",
         }
     }
 
@@ -31,13 +36,14 @@ impl ChannelType {
             "discord" => Some(Self::Discord),
             "twitter" => Some(Self::Twitter),
             "external_channel" => Some(Self::ExternalChannel),
+            "webhook" => Some(Self::Webhook),
             _ => None,
         }
     }
 
     /// All supported channel types
     pub fn all() -> &'static [ChannelType] {
-        &[Self::Telegram, Self::Slack, Self::Discord, Self::Twitter, Self::ExternalChannel]
+        &[Self::Telegram, Self::Slack, Self::Discord, Self::Twitter, Self::ExternalChannel, Self::Webhook]
     }
 
     /// Display name for UI
@@ -48,6 +54,7 @@ impl ChannelType {
             Self::Discord => "Discord",
             Self::Twitter => "Twitter",
             Self::ExternalChannel => "External Channel",
+            Self::Webhook => "Webhook",
         }
     }
 }
@@ -70,12 +77,19 @@ pub struct NormalizedMessage {
     /// Human-readable name for the chat/channel (e.g., Discord channel name)
     #[serde(default)]
     pub chat_name: Option<String>,
+    /// `chat_id` is a thread (e.g. a Discord thread): the session follows the
+    /// thread instead of starting fresh for every message
+    #[serde(default)]
+    pub is_thread: bool,
     /// Platform-specific user ID
     pub user_id: String,
     /// Display name of the user
     pub user_name: String,
     /// Message text content
     pub text: String,
+    /// Images sent with the message (empty for channels without image support)
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
     /// Platform-specific message ID (for replies)
     pub message_id: Option<String>,
     /// Session mode for cron jobs: "main" (shared with web) or "isolated" (separate session)
@@ -84,10 +98,21 @@ pub struct NormalizedMessage {
     /// Currently selected network from UI (e.g., "base", "polygon", "mainnet")
     /// Used as default for web3 operations unless user explicitly specifies otherwise
     #[serde(default)]
-    pub selected_network: Option<String>,
+    pub selected_network: Option<Network>,
     /// Force safe mode for this message (e.g., non-admin Discord queries)
     #[serde(default)]
     pub force_safe_mode: bool,
+    /// Agent settings to use instead of the active ones (e.g. config comparison runs).
+    /// Set internally only — never accepted from clients.
+    #[serde(skip)]
+    pub agent_settings_override: Option<AgentSettings>,
+    /// Throwaway run: skips memory writes, language preference updates and compaction
+    #[serde(skip)]
+    pub ephemeral: bool,
+    /// Planning dry run (/plan): only task-planning tools run, every other tool
+    /// call is answered with its arguments instead of being executed
+    #[serde(skip)]
+    pub dry_run: bool,
 }
 
 /// Handle to a running channel listener
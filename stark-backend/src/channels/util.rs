@@ -19,8 +19,13 @@ pub fn split_message(text: &str, max_len: usize) -> Vec<String> {
             if line.len() > max_len {
                 let mut remaining = line;
                 while remaining.len() > max_len {
-                    chunks.push(remaining[..max_len].to_string());
-                    remaining = &remaining[max_len..];
+                    // Cut on a char boundary so multi-byte text can't panic
+                    let mut cut = remaining.floor_char_boundary(max_len);
+                    if cut == 0 {
+                        cut = remaining.chars().next().map_or(remaining.len(), char::len_utf8);
+                    }
+                    chunks.push(remaining[..cut].to_string());
+                    remaining = &remaining[cut..];
                 }
                 if !remaining.is_empty() {
                     current = remaining.to_string();
@@ -43,6 +48,23 @@ pub fn split_message(text: &str, max_len: usize) -> Vec<String> {
     chunks
 }
 
+/// Bot token for a channel: channel setting first, then the legacy bot_token field
+pub fn channel_bot_token(
+    db: &crate::db::Database,
+    channel_id: i64,
+    setting_key: &str,
+) -> Option<String> {
+    let setting = db.get_channel_setting(channel_id, setting_key).ok().flatten();
+    if let Some(token) = setting.filter(|t| !t.is_empty()) {
+        return Some(token);
+    }
+    db.get_channel(channel_id)
+        .ok()
+        .flatten()
+        .map(|c| c.bot_token)
+        .filter(|t| !t.is_empty())
+}
+
 /// Parse "Retry after Xs" from a platform API error string.
 /// Returns the number of seconds to wait, or None if not a rate-limit error.
 pub fn parse_retry_after(err: &str) -> Option<u64> {
@@ -135,6 +157,52 @@ impl StatusThrottler {
     }
 }
 
+/// Per-(channel, warning type) cooldown for `agent_warning` broadcasts.
+///
+/// A pathological loop can raise the same warning every iteration; only the
+/// first occurrence per interval reaches the UI, carrying how many times the
+/// warning was seen since the previous broadcast.
+pub struct WarningThrottle {
+    interval: std::time::Duration,
+    /// (channel_id, warning_type) -> (last broadcast, occurrences since then)
+    state: std::sync::Mutex<std::collections::HashMap<(i64, String), (std::time::Instant, u32)>>,
+}
+
+impl WarningThrottle {
+    pub fn new(interval: std::time::Duration) -> Self {
+        Self {
+            interval,
+            state: std::sync::Mutex::new(std::collections::HashMap::new()),
+        }
+    }
+
+    /// Record an occurrence. Returns the seen count if it should be broadcast now,
+    /// or None if it falls within the cooldown of the previous broadcast.
+    pub fn record(&self, channel_id: i64, warning_type: &str) -> Option<u32> {
+        self.record_at(channel_id, warning_type, std::time::Instant::now())
+    }
+
+    fn record_at(&self, channel_id: i64, warning_type: &str, now: std::time::Instant) -> Option<u32> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match state.get_mut(&(channel_id, warning_type.to_string())) {
+            Some((last_sent, seen)) => {
+                *seen += 1;
+                if now.duration_since(*last_sent) < self.interval {
+                    return None;
+                }
+                let count = *seen;
+                *last_sent = now;
+                *seen = 0;
+                Some(count)
+            }
+            None => {
+                state.insert((channel_id, warning_type.to_string()), (now, 0));
+                Some(1)
+            }
+        }
+    }
+}
+
 /// Check whether a broadcast event belongs to a specific channel + chat session.
 ///
 /// Matches on `channel_id` and optionally `chat_id` inside `event.data`:
@@ -155,3 +223,34 @@ pub fn event_matches_session(
         _ => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_warning_throttle_collapses_repeats_within_interval() {
+        let throttle = WarningThrottle::new(Duration::from_secs(10));
+        let start = Instant::now();
+
+        assert_eq!(throttle.record_at(1, "no_tool_calls", start), Some(1));
+        assert_eq!(throttle.record_at(1, "no_tool_calls", start + Duration::from_secs(1)), None);
+        assert_eq!(throttle.record_at(1, "no_tool_calls", start + Duration::from_secs(2)), None);
+        // Different warning type and different channel are throttled independently
+        assert_eq!(throttle.record_at(1, "loop_detected", start + Duration::from_secs(2)), Some(1));
+        assert_eq!(throttle.record_at(2, "no_tool_calls", start + Duration::from_secs(2)), Some(1));
+
+        // After the interval the next occurrence is broadcast with the accumulated count
+        assert_eq!(throttle.record_at(1, "no_tool_calls", start + Duration::from_secs(11)), Some(3));
+        assert_eq!(throttle.record_at(1, "no_tool_calls", start + Duration::from_secs(12)), None);
+    }
+
+    #[test]
+    fn test_split_message_hard_split_respects_char_boundaries() {
+        let line = format!("a{}", "日".repeat(10));
+        let chunks = split_message(&line, 5);
+        assert!(chunks.iter().all(|c| c.len() <= 5));
+        assert_eq!(chunks.concat(), line);
+    }
+}
@@ -0,0 +1,425 @@
+//! Webhook channel: delivers agent responses to an HTTP endpoint.
+//!
+//! Inbound messages arrive at `POST /api/channels/{id}/webhook`. Every message
+//! the agent sends while handling one (each `say_to_user` as it happens, then
+//! the final reply) is POSTed to the channel's `webhook_url` as
+//! `{channel_id, user, text, session_id}`. When `webhook_secret` is set,
+//! outbound requests carry the Unix time in `X-Stark-Timestamp` and an
+//! HMAC-SHA256 of `<timestamp>.<body>` in `X-Stark-Signature: sha256=<hex>`;
+//! inbound requests must be signed the same way, be no older than
+//! `MAX_SIGNATURE_AGE_SECS`, and not reuse a signature already accepted.
+
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::channels::util::event_matches_session;
+use crate::channels::{MessageDispatcher, NormalizedMessage};
+use crate::db::Database;
+use crate::gateway::events::EventBroadcaster;
+use crate::gateway::protocol::GatewayEvent;
+use crate::models::chat_session::SessionScope;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Channel type string stored in the database
+pub const CHANNEL_TYPE: &str = "webhook";
+
+/// Header carrying the `sha256=<hex>` request signature (both directions)
+pub const SIGNATURE_HEADER: &str = "X-Stark-Signature";
+
+/// Header carrying the Unix time (seconds) the request was signed at
+pub const TIMESTAMP_HEADER: &str = "X-Stark-Timestamp";
+
+/// Signed requests older (or further in the future) than this are rejected
+pub const MAX_SIGNATURE_AGE_SECS: i64 = 300;
+
+/// Delivery attempts per payload (5xx and connection errors are retried)
+const MAX_DELIVERY_ATTEMPTS: u32 = 4;
+
+/// Wait before the first retry; doubles on each further retry
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+static SEEN_SIGNATURES: Lazy<ReplayGuard> = Lazy::new(ReplayGuard::default);
+
+/// JSON body POSTed to the webhook URL for each agent message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    pub channel_id: i64,
+    pub user: String,
+    pub text: String,
+    pub session_id: Option<i64>,
+}
+
+/// Outbound side of a webhook channel
+pub struct WebhookChannel {
+    channel_id: i64,
+    url: String,
+    secret: Option<String>,
+    client: reqwest::Client,
+    initial_backoff: Duration,
+}
+
+impl WebhookChannel {
+    pub fn new(channel_id: i64, url: String, secret: Option<String>) -> Self {
+        Self {
+            channel_id,
+            url,
+            secret: secret.filter(|s| !s.is_empty()),
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(15))
+                .build()
+                .unwrap_or_default(),
+            initial_backoff: INITIAL_BACKOFF,
+        }
+    }
+
+    /// Build from the channel's `webhook_url` / `webhook_secret` settings
+    pub fn from_settings(db: &Database, channel_id: i64) -> Result<Self, String> {
+        let url = db
+            .get_channel_setting(channel_id, "webhook_url")
+            .map_err(|e| format!("Failed to read webhook settings: {}", e))?
+            .filter(|u| !u.trim().is_empty())
+            .ok_or_else(|| "Webhook channel has no webhook_url configured".to_string())?;
+        let secret = db.get_channel_setting(channel_id, "webhook_secret").ok().flatten();
+        Ok(Self::new(channel_id, url.trim().to_string(), secret))
+    }
+
+    #[cfg(test)]
+    fn with_initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// POST one payload, retrying 5xx responses and connection errors with backoff
+    pub async fn deliver(&self, payload: &WebhookPayload) -> Result<(), String> {
+        let body = serde_json::to_vec(payload).map_err(|e| format!("Failed to encode payload: {}", e))?;
+        let mut backoff = self.initial_backoff;
+
+        for attempt in 1..=MAX_DELIVERY_ATTEMPTS {
+            let mut request = self
+                .client
+                .post(&self.url)
+                .header("Content-Type", "application/json")
+                .body(body.clone());
+            if let Some(ref secret) = self.secret {
+                let timestamp = chrono::Utc::now().timestamp();
+                request = request
+                    .header(TIMESTAMP_HEADER, timestamp.to_string())
+                    .header(SIGNATURE_HEADER, sign(secret, timestamp, &body));
+            }
+
+            let error = match request.send().await {
+                Ok(resp) if resp.status().is_success() => return Ok(()),
+                Ok(resp) if resp.status().is_server_error() => format!("HTTP {}", resp.status()),
+                Ok(resp) => return Err(format!("Webhook rejected delivery: HTTP {}", resp.status())),
+                Err(e) => e.to_string(),
+            };
+
+            if attempt == MAX_DELIVERY_ATTEMPTS {
+                return Err(format!("Webhook delivery failed after {} attempts: {}", attempt, error));
+            }
+            log::warn!(
+                "[WEBHOOK] Delivery to channel {} failed (attempt {}/{}): {}, retrying in {:?}",
+                self.channel_id, attempt, MAX_DELIVERY_ATTEMPTS, error, backoff
+            );
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+        unreachable!("loop returns on the last attempt")
+    }
+}
+
+fn mac(secret: &str, timestamp: i64, body: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// `sha256=<hex>` HMAC of `<timestamp>.<body>` under `secret`
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    format!("sha256={}", hex::encode(mac(secret, timestamp, body).finalize().into_bytes()))
+}
+
+/// Check the signature and timestamp headers of a request against `body`
+/// (constant time). Requests signed more than `MAX_SIGNATURE_AGE_SECS` away
+/// from `now` are rejected so a captured request can't be replayed later.
+pub fn verify_signature(
+    secret: &str,
+    body: &[u8],
+    signature: Option<&str>,
+    timestamp: Option<&str>,
+    now: i64,
+) -> bool {
+    let Some(timestamp) = timestamp.and_then(|t| t.trim().parse::<i64>().ok()) else {
+        return false;
+    };
+    if (now - timestamp).abs() > MAX_SIGNATURE_AGE_SECS {
+        return false;
+    }
+    let Some(sig) = signature.and_then(|h| h.trim().strip_prefix("sha256=")) else {
+        return false;
+    };
+    let Ok(expected) = hex::decode(sig) else {
+        return false;
+    };
+    mac(secret, timestamp, body).verify_slice(&expected).is_ok()
+}
+
+/// Signatures of accepted inbound requests, remembered until their timestamp
+/// falls out of the `MAX_SIGNATURE_AGE_SECS` window (after which
+/// `verify_signature` rejects them anyway)
+#[derive(Default)]
+pub struct ReplayGuard {
+    /// Signature → Unix time after which it can be forgotten
+    seen: Mutex<HashMap<String, i64>>,
+}
+
+impl ReplayGuard {
+    /// Record `signature` (signed at `timestamp`); false if it was already seen
+    pub fn check_and_record(&self, signature: &str, timestamp: i64, now: i64) -> bool {
+        let mut seen = self.seen.lock().unwrap();
+        seen.retain(|_, expires_at| *expires_at >= now);
+        let key = signature.trim().to_lowercase();
+        if seen.contains_key(&key) {
+            return false;
+        }
+        seen.insert(key, timestamp + MAX_SIGNATURE_AGE_SECS);
+        true
+    }
+}
+
+/// Reject an already-verified inbound request whose signature was used before
+pub fn check_replay(signature: Option<&str>, timestamp: Option<&str>, now: i64) -> bool {
+    let (Some(signature), Some(timestamp)) = (signature, timestamp.and_then(|t| t.trim().parse::<i64>().ok())) else {
+        return false;
+    };
+    SEEN_SIGNATURES.check_and_record(signature, timestamp, now)
+}
+
+/// Text of a `say_to_user` sent mid-run in this channel and chat, if `event` is one
+fn say_to_user_text(event: &GatewayEvent, channel_id: i64, chat_id: &str) -> Option<String> {
+    if event.event != "tool.result" || !event_matches_session(&event.data, channel_id, chat_id) {
+        return None;
+    }
+    let data = &event.data;
+    let is_say = data.get("tool_name").and_then(|v| v.as_str()) == Some("say_to_user");
+    let success = data.get("success").and_then(|v| v.as_bool()).unwrap_or(false);
+    let content = data.get("content").and_then(|v| v.as_str()).unwrap_or("");
+    (is_say && success && !content.trim().is_empty()).then(|| content.to_string())
+}
+
+/// Deliver one agent message, surfacing failures as `agent.error` events
+async fn deliver_message(
+    db: &Database,
+    broadcaster: &EventBroadcaster,
+    webhook: &WebhookChannel,
+    chat_id: &str,
+    user: &str,
+    text: String,
+) {
+    let channel_id = webhook.channel_id;
+    let session_id = db
+        .get_or_create_chat_session(CHANNEL_TYPE, channel_id, chat_id, SessionScope::Api, None)
+        .ok()
+        .map(|s| s.id);
+
+    let payload = WebhookPayload { channel_id, user: user.to_string(), text, session_id };
+    if let Err(e) = webhook.deliver(&payload).await {
+        log::error!("[WEBHOOK] Channel {}: {}", channel_id, e);
+        broadcaster.broadcast(GatewayEvent::agent_error(channel_id, &e));
+    }
+}
+
+/// Dispatch an inbound webhook message and deliver what the agent says: each
+/// `say_to_user` as it is sent, then the final reply unless a `say_to_user`
+/// already carried it. Delivery failures are surfaced as `agent.error` events
+/// on the channel.
+pub async fn handle_inbound(
+    db: Arc<Database>,
+    dispatcher: Arc<MessageDispatcher>,
+    broadcaster: Arc<EventBroadcaster>,
+    webhook: WebhookChannel,
+    message: NormalizedMessage,
+) {
+    let channel_id = message.channel_id;
+    let chat_id = message.chat_id.clone();
+    let user = message.user_name.clone();
+    let webhook = Arc::new(webhook);
+
+    // Tool results carry the chat_id, so concurrent conversations on the
+    // channel only ever see their own say_to_user messages
+    let (client_id, mut event_rx) = broadcaster.subscribe();
+    let forwarder = tokio::spawn({
+        let (db, broadcaster, webhook) = (db.clone(), broadcaster.clone(), webhook.clone());
+        let (chat_id, user) = (chat_id.clone(), user.clone());
+        async move {
+            let mut delivered = Vec::new();
+            while let Some(event) = event_rx.recv().await {
+                if let Some(text) = say_to_user_text(&event, channel_id, &chat_id) {
+                    deliver_message(&db, &broadcaster, &webhook, &chat_id, &user, text.clone()).await;
+                    delivered.push(text);
+                }
+            }
+            delivered
+        }
+    });
+
+    let result = dispatcher.dispatch_safe(message).await;
+
+    // Unsubscribing closes the stream once the events already queued are
+    // forwarded, so the final reply always goes out after them
+    broadcaster.unsubscribe(&client_id);
+    let delivered = forwarder.await.unwrap_or_else(|e| {
+        log::warn!("[WEBHOOK] Channel {}: say_to_user forwarder failed: {}", channel_id, e);
+        Vec::new()
+    });
+
+    let text = match result.error {
+        Some(error) => format!("Sorry, I encountered an error: {}", error),
+        None if result.response.trim().is_empty() => return,
+        // A final say_to_user is also the dispatch result; don't send it twice
+        None if delivered.iter().any(|t| t.trim() == result.response.trim()) => return,
+        None => result.response,
+    };
+    deliver_message(&db, &broadcaster, &webhook, &chat_id, &user, text).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Serve `statuses` in order (one connection each), returning the bodies and
+    /// signature/timestamp headers received
+    async fn serve(listener: tokio::net::TcpListener, statuses: Vec<u16>, hits: Arc<AtomicUsize>) -> Vec<(String, Option<String>, Option<String>)> {
+        let mut received = Vec::new();
+        for status in statuses {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 4096];
+            let body_start = loop {
+                let n = socket.read(&mut chunk).await.unwrap();
+                buf.extend_from_slice(&chunk[..n]);
+                if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break pos + 4;
+                }
+            };
+            let headers = String::from_utf8_lossy(&buf[..body_start]).to_lowercase();
+            let header = |name: &str| {
+                headers.lines().find_map(|l| l.strip_prefix(name)).map(|v| v.trim().to_string())
+            };
+            let content_length: usize = header("content-length:").map(|v| v.parse().unwrap()).unwrap_or(0);
+            while buf.len() < body_start + content_length {
+                let n = socket.read(&mut chunk).await.unwrap();
+                buf.extend_from_slice(&chunk[..n]);
+            }
+            let body = String::from_utf8_lossy(&buf[body_start..body_start + content_length]).to_string();
+            received.push((body, header("x-stark-signature:"), header("x-stark-timestamp:")));
+            hits.fetch_add(1, Ordering::SeqCst);
+
+            let response = format!("HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+            socket.write_all(response.as_bytes()).await.unwrap();
+        }
+        received
+    }
+
+    fn payload() -> WebhookPayload {
+        WebhookPayload {
+            channel_id: 7,
+            user: "ops-bot".to_string(),
+            text: "Deploy finished.".to_string(),
+            session_id: Some(3),
+        }
+    }
+
+    #[test]
+    fn test_signature_roundtrip() {
+        let body = br#"{"text":"hi"}"#;
+        let now = 1_700_000_000;
+        let ts = now.to_string();
+        let sig = sign("s3cret", now, body);
+        assert!(sig.starts_with("sha256="));
+        assert!(verify_signature("s3cret", body, Some(&sig), Some(&ts), now));
+        assert!(!verify_signature("other", body, Some(&sig), Some(&ts), now));
+        assert!(!verify_signature("s3cret", br#"{"text":"hi!"}"#, Some(&sig), Some(&ts), now));
+        assert!(!verify_signature("s3cret", body, Some("sha256=zz"), Some(&ts), now));
+        assert!(!verify_signature("s3cret", body, None, Some(&ts), now));
+        // The timestamp is part of what's signed and is required
+        assert!(!verify_signature("s3cret", body, Some(&sig), Some(&(now + 1).to_string()), now));
+        assert!(!verify_signature("s3cret", body, Some(&sig), None, now));
+    }
+
+    #[test]
+    fn test_stale_signature_is_rejected() {
+        let body = br#"{"text":"hi"}"#;
+        let signed_at = 1_700_000_000;
+        let sig = sign("s3cret", signed_at, body);
+        let ts = signed_at.to_string();
+        assert!(verify_signature("s3cret", body, Some(&sig), Some(&ts), signed_at + MAX_SIGNATURE_AGE_SECS));
+        assert!(!verify_signature("s3cret", body, Some(&sig), Some(&ts), signed_at + MAX_SIGNATURE_AGE_SECS + 1));
+        assert!(!verify_signature("s3cret", body, Some(&sig), Some(&ts), signed_at - MAX_SIGNATURE_AGE_SECS - 1));
+    }
+
+    #[tokio::test]
+    async fn test_deliver_retries_server_errors_and_signs_body() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let server = tokio::spawn(serve(listener, vec![503, 500, 200], hits.clone()));
+
+        let webhook = WebhookChannel::new(7, url, Some("s3cret".to_string()))
+            .with_initial_backoff(Duration::from_millis(10));
+        webhook.deliver(&payload()).await.unwrap();
+
+        let received = server.await.unwrap();
+        assert_eq!(received.len(), 3);
+        let (body, sig, ts) = &received[2];
+        assert_eq!(serde_json::from_str::<WebhookPayload>(body).unwrap(), payload());
+        let now = chrono::Utc::now().timestamp();
+        assert!(verify_signature("s3cret", body.as_bytes(), sig.as_deref(), ts.as_deref(), now));
+    }
+
+    #[tokio::test]
+    async fn test_deliver_does_not_retry_client_errors() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let _server = tokio::spawn(serve(listener, vec![404, 200], hits.clone()));
+
+        let webhook = WebhookChannel::new(7, url, None).with_initial_backoff(Duration::from_millis(10));
+        let err = webhook.deliver(&payload()).await.unwrap_err();
+        assert!(err.contains("404"), "got: {}", err);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_replay_guard_rejects_reused_signature_until_it_expires() {
+        let guard = ReplayGuard::default();
+        let signed_at = 1_700_000_000;
+        assert!(guard.check_and_record("sha256=abc", signed_at, signed_at + 1));
+        assert!(!guard.check_and_record("sha256=ABC", signed_at, signed_at + 2));
+        assert!(guard.check_and_record("sha256=def", signed_at, signed_at + 2));
+        // Once the timestamp is past the signature window the entry is dropped
+        assert!(guard.check_and_record("sha256=abc", signed_at, signed_at + MAX_SIGNATURE_AGE_SECS + 1));
+    }
+
+    #[test]
+    fn test_say_to_user_text_matches_channel_and_chat() {
+        let say = |channel_id, chat_id, tool, success| {
+            GatewayEvent::tool_result(channel_id, Some(chat_id), tool, success, 5, "Halfway there.", false)
+        };
+        assert_eq!(say_to_user_text(&say(7, "ops", "say_to_user", true), 7, "ops").as_deref(), Some("Halfway there."));
+        assert_eq!(say_to_user_text(&say(7, "other", "say_to_user", true), 7, "ops"), None);
+        assert_eq!(say_to_user_text(&say(8, "ops", "say_to_user", true), 7, "ops"), None);
+        assert_eq!(say_to_user_text(&say(7, "ops", "say_to_user", false), 7, "ops"), None);
+        assert_eq!(say_to_user_text(&say(7, "ops", "web_fetch", true), 7, "ops"), None);
+    }
+}
@@ -1,11 +1,16 @@
 use ethers::core::k256::ecdsa::SigningKey;
 use ethers::signers::{LocalWallet, Signer};
+use crate::models::SessionScope;
+use std::collections::HashMap;
 use std::env;
 use std::path::{Path, PathBuf};
 
 /// Environment variable names - single source of truth
 pub mod env_vars {
     pub const LOGIN_ADMIN_PUBLIC_ADDRESS: &str = "LOGIN_ADMIN_PUBLIC_ADDRESS";
+    pub const LOGIN_ALLOWED_ADDRESSES: &str = "LOGIN_ALLOWED_ADDRESSES";
+    // Host (and port) sign-in messages must name, e.g. "bot.example.com"
+    pub const LOGIN_SIWE_DOMAIN: &str = "LOGIN_SIWE_DOMAIN";
     pub const BURNER_WALLET_PRIVATE_KEY: &str = "BURNER_WALLET_BOT_PRIVATE_KEY";
     pub const PORT: &str = "PORT";
     pub const DATABASE_URL: &str = "DATABASE_URL";
@@ -22,6 +27,38 @@ pub mod env_vars {
     pub const MEMORY_ENABLE_PRE_COMPACTION_FLUSH: &str = "STARK_MEMORY_ENABLE_PRE_COMPACTION_FLUSH";
     pub const MEMORY_ENABLE_CROSS_SESSION: &str = "STARK_MEMORY_ENABLE_CROSS_SESSION";
     pub const MEMORY_CROSS_SESSION_LIMIT: &str = "STARK_MEMORY_CROSS_SESSION_LIMIT";
+    pub const CONTEXT_INCLUDE_TOOL_RESULTS: &str = "STARK_CONTEXT_INCLUDE_TOOL_RESULTS";
+    pub const CONTEXT_TOOL_RESULTS_MAX_TOKENS: &str = "STARK_CONTEXT_TOOL_RESULTS_MAX_TOKENS";
+    pub const MEMORY_REFRESH_TURNS: &str = "STARK_MEMORY_REFRESH_TURNS";
+    pub const MEMORY_DEDUP_THRESHOLD: &str = "STARK_MEMORY_DEDUP_THRESHOLD";
+    // Messages kept after compaction per session scope, e.g. "group=20,cron=5"
+    pub const CONTEXT_KEEP_RECENT_BY_SCOPE: &str = "STARK_CONTEXT_KEEP_RECENT_BY_SCOPE";
+    // Minimum seconds between identical agent_warning broadcasts per channel
+    pub const AGENT_WARNING_COOLDOWN_SECS: &str = "STARK_AGENT_WARNING_COOLDOWN_SECS";
+    // Seconds a cached base system prompt stays valid (0 disables the cache)
+    pub const SYSTEM_PROMPT_CACHE_SECS: &str = "STARK_SYSTEM_PROMPT_CACHE_SECS";
+    // Set to "off" to disable prompt-injection screening of tool results
+    pub const INJECTION_GUARD: &str = "STARK_INJECTION_GUARD";
+    // System-wide rollout concurrency limit and its wait queue
+    pub const MAX_CONCURRENT_ROLLOUTS: &str = "STARK_MAX_CONCURRENT_ROLLOUTS";
+    pub const ROLLOUT_QUEUE_MAX: &str = "STARK_ROLLOUT_QUEUE_MAX";
+    pub const ROLLOUT_QUEUE_TIMEOUT_SECS: &str = "STARK_ROLLOUT_QUEUE_TIMEOUT_SECS";
+    // Set to "on" to save the tool history of failed rollout attempts for replay
+    pub const ROLLOUT_SNAPSHOT_TOOL_HISTORY: &str = "STARK_ROLLOUT_SNAPSHOT_TOOL_HISTORY";
+    // Maximum nesting depth of sub-agents spawning sub-agents
+    pub const SUBAGENT_MAX_DEPTH: &str = "STARK_SUBAGENT_MAX_DEPTH";
+    // Tool-loop iterations between mid-loop agent context checkpoints (0 disables)
+    pub const CONTEXT_CHECKPOINT_ITERATIONS: &str = "STARK_CONTEXT_CHECKPOINT_ITERATIONS";
+    // Recent tool call/result pairs kept verbatim through incremental compaction
+    pub const COMPACTION_KEEP_TOOL_PAIRS: &str = "STARK_COMPACTION_KEEP_TOOL_PAIRS";
+    // Set to "off" to store and broadcast assistant responses exactly as generated
+    pub const NORMALIZE_ASSISTANT_OUTPUT: &str = "STARK_NORMALIZE_ASSISTANT_OUTPUT";
+    // Set to "on" to also log every persisted span as one JSON line (target `telemetry_json`)
+    pub const TELEMETRY_JSON_LOGS: &str = "STARK_TELEMETRY_JSON_LOGS";
+    // Seconds a Discord/Telegram session keeps absorbing new messages before the next one starts fresh
+    pub const GATEWAY_SESSION_GRACE_SECS: &str = "STARK_GATEWAY_SESSION_GRACE_SECS";
+    // Set to "off" to forget context-bank addresses/symbols when a session ends
+    pub const PERSIST_CONTEXT_BANK: &str = "STARK_PERSIST_CONTEXT_BANK";
 }
 
 /// Default values
@@ -34,6 +71,15 @@ pub mod defaults {
     pub const SOUL_DIR: &str = "soul";
     pub const MEMORY_DIR: &str = "memory";
     pub const DISK_QUOTA_MB: u64 = 1024;
+    pub const AGENT_WARNING_COOLDOWN_SECS: u64 = 30;
+    pub const SYSTEM_PROMPT_CACHE_SECS: u64 = 300;
+    pub const MAX_CONCURRENT_ROLLOUTS: usize = 16;
+    pub const ROLLOUT_QUEUE_MAX: usize = 64;
+    pub const ROLLOUT_QUEUE_TIMEOUT_SECS: u64 = 120;
+    pub const SUBAGENT_MAX_DEPTH: u32 = 3;
+    pub const CONTEXT_CHECKPOINT_ITERATIONS: usize = 5;
+    pub const COMPACTION_KEEP_TOOL_PAIRS: usize = 0;
+    pub const GATEWAY_SESSION_GRACE_SECS: u64 = 0;
 }
 
 /// Returns the absolute path to the stark-backend directory.
@@ -113,6 +159,12 @@ fn derive_address_from_private_key(private_key: &str) -> Result<String, String>
 #[derive(Clone)]
 pub struct Config {
     pub login_admin_public_address: Option<String>,
+    /// Additional wallet addresses allowed to sign in (comma-separated env var).
+    /// Addresses can also be allowlisted at runtime via the database.
+    pub login_allowed_addresses: Vec<String>,
+    /// Canonical domain (host[:port]) that sign-in messages must be bound to.
+    /// Sign-in via /api/auth/verify is refused while this is unset.
+    pub login_siwe_domain: Option<String>,
     pub burner_wallet_private_key: Option<String>,
     pub port: u16,
     pub database_url: String,
@@ -133,8 +185,24 @@ impl Config {
                 })
             });
 
+        let login_allowed_addresses = env::var(env_vars::LOGIN_ALLOWED_ADDRESSES)
+            .map(|v| {
+                v.split(',')
+                    .map(|a| a.trim().to_lowercase())
+                    .filter(|a| !a.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let login_siwe_domain = env::var(env_vars::LOGIN_SIWE_DOMAIN)
+            .ok()
+            .map(|d| d.trim().to_lowercase())
+            .filter(|d| !d.is_empty());
+
         Self {
             login_admin_public_address,
+            login_allowed_addresses,
+            login_siwe_domain,
             burner_wallet_private_key,
             port: env::var(env_vars::PORT)
                 .unwrap_or_else(|_| defaults::PORT.to_string())
@@ -159,6 +227,16 @@ pub struct MemoryConfig {
     pub enable_cross_session_memory: bool,
     /// Maximum number of cross-session memories to include
     pub cross_session_memory_limit: i32,
+    /// Include a condensed note of recent successful tool results in the AI context
+    pub include_tool_results_note: bool,
+    /// Token budget for the recent tool results note
+    pub tool_results_note_max_tokens: i32,
+    /// Turns between cross-session memory rebuilds; the block is reused in between (1 = every turn)
+    pub memory_refresh_turns: u32,
+    /// Similarity (0-1) at which a daily log entry counts as a near-duplicate of a recent one and is skipped (0 = off)
+    pub dedup_threshold: f64,
+    /// Messages kept after compaction per session scope; scopes not listed use the context manager's default
+    pub keep_recent_by_scope: HashMap<SessionScope, i32>,
 }
 
 impl Default for MemoryConfig {
@@ -169,6 +247,11 @@ impl Default for MemoryConfig {
             enable_pre_compaction_flush: true,
             enable_cross_session_memory: true,
             cross_session_memory_limit: 5,
+            include_tool_results_note: false,
+            tool_results_note_max_tokens: 400,
+            memory_refresh_turns: 1,
+            dedup_threshold: 0.85,
+            keep_recent_by_scope: HashMap::new(),
         }
     }
 }
@@ -191,6 +274,25 @@ impl MemoryConfig {
                 .unwrap_or_else(|_| "5".to_string())
                 .parse()
                 .unwrap_or(5),
+            include_tool_results_note: env::var(env_vars::CONTEXT_INCLUDE_TOOL_RESULTS)
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            tool_results_note_max_tokens: env::var(env_vars::CONTEXT_TOOL_RESULTS_MAX_TOKENS)
+                .unwrap_or_else(|_| "400".to_string())
+                .parse()
+                .unwrap_or(400),
+            memory_refresh_turns: env::var(env_vars::MEMORY_REFRESH_TURNS)
+                .ok()
+                .and_then(|v| v.parse::<u32>().ok())
+                .unwrap_or(1)
+                .max(1),
+            dedup_threshold: env::var(env_vars::MEMORY_DEDUP_THRESHOLD)
+                .ok()
+                .and_then(|v| v.parse::<f64>().ok())
+                .unwrap_or(0.85),
+            keep_recent_by_scope: env::var(env_vars::CONTEXT_KEEP_RECENT_BY_SCOPE)
+                .map(|v| parse_keep_recent_by_scope(&v))
+                .unwrap_or_default(),
         }
     }
 
@@ -200,11 +302,144 @@ impl MemoryConfig {
     }
 }
 
+/// Parse "scope=count" pairs separated by commas (e.g. "group=20,cron=5").
+/// Unknown scopes and malformed entries are skipped.
+fn parse_keep_recent_by_scope(value: &str) -> HashMap<SessionScope, i32> {
+    value
+        .split(',')
+        .filter_map(|entry| {
+            let (scope, count) = entry.split_once('=')?;
+            let scope = SessionScope::from_str(scope.trim());
+            let count = count.trim().parse::<i32>().ok();
+            if scope.is_none() || count.is_none() {
+                log::warn!("Ignoring invalid {} entry '{}'", env_vars::CONTEXT_KEEP_RECENT_BY_SCOPE, entry);
+            }
+            Some((scope?, count?))
+        })
+        .collect()
+}
+
 /// Get the memory configuration
 pub fn memory_config() -> MemoryConfig {
     MemoryConfig::from_env()
 }
 
+/// Cooldown between identical `agent_warning` broadcasts on a channel
+pub fn agent_warning_cooldown() -> std::time::Duration {
+    let secs = env::var(env_vars::AGENT_WARNING_COOLDOWN_SECS)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(defaults::AGENT_WARNING_COOLDOWN_SECS);
+    std::time::Duration::from_secs(secs)
+}
+
+/// How long a cached base system prompt is reused. Zero disables caching.
+pub fn system_prompt_cache_ttl() -> std::time::Duration {
+    let secs = env::var(env_vars::SYSTEM_PROMPT_CACHE_SECS)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(defaults::SYSTEM_PROMPT_CACHE_SECS);
+    std::time::Duration::from_secs(secs)
+}
+
+/// Whether tool results are screened for prompt injection (on unless set to off/false/0)
+pub fn injection_guard_enabled() -> bool {
+    env::var(env_vars::INJECTION_GUARD)
+        .map(|v| !matches!(v.trim().to_lowercase().as_str(), "off" | "false" | "0"))
+        .unwrap_or(true)
+}
+
+/// Maximum rollouts (dispatches) running at once across all channels
+pub fn max_concurrent_rollouts() -> usize {
+    env::var(env_vars::MAX_CONCURRENT_ROLLOUTS)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&n: &usize| n > 0)
+        .unwrap_or(defaults::MAX_CONCURRENT_ROLLOUTS)
+}
+
+/// Maximum messages waiting for a rollout slot before new ones are rejected
+pub fn rollout_queue_max() -> usize {
+    env::var(env_vars::ROLLOUT_QUEUE_MAX)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(defaults::ROLLOUT_QUEUE_MAX)
+}
+
+/// How long a queued message waits for a rollout slot before giving up
+pub fn rollout_queue_timeout() -> std::time::Duration {
+    let secs = env::var(env_vars::ROLLOUT_QUEUE_TIMEOUT_SECS)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(defaults::ROLLOUT_QUEUE_TIMEOUT_SECS);
+    std::time::Duration::from_secs(secs)
+}
+
+/// Whether failed rollout attempts save their tool history (off by default)
+pub fn rollout_snapshot_tool_history() -> bool {
+    env::var(env_vars::ROLLOUT_SNAPSHOT_TOOL_HISTORY)
+        .map(|v| matches!(v.trim().to_lowercase().as_str(), "on" | "true" | "1"))
+        .unwrap_or(false)
+}
+
+/// Whether persisted spans are also logged as JSON lines for log shippers (off by default)
+pub fn telemetry_json_logs() -> bool {
+    env::var(env_vars::TELEMETRY_JSON_LOGS)
+        .map(|v| matches!(v.trim().to_lowercase().as_str(), "on" | "true" | "1"))
+        .unwrap_or(false)
+}
+
+/// Grace window after a gateway session's last activity during which new messages
+/// reuse it (0 = every message starts a fresh session)
+pub fn gateway_session_grace_secs() -> u64 {
+    env::var(env_vars::GATEWAY_SESSION_GRACE_SECS)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(defaults::GATEWAY_SESSION_GRACE_SECS)
+}
+
+/// Maximum sub-agent nesting depth (levels of sub-agents below the main agent)
+pub fn subagent_max_depth() -> u32 {
+    env::var(env_vars::SUBAGENT_MAX_DEPTH)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(defaults::SUBAGENT_MAX_DEPTH)
+}
+
+/// Tool-loop iterations between agent context checkpoints. Contexts are also
+/// checkpointed whenever a task completes; 0 disables the periodic checkpoint.
+pub fn context_checkpoint_interval() -> usize {
+    env::var(env_vars::CONTEXT_CHECKPOINT_ITERATIONS)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(defaults::CONTEXT_CHECKPOINT_ITERATIONS)
+}
+
+/// Tool call/result pairs incremental compaction keeps verbatim from each
+/// compacted segment (0 folds them into the summary like other messages).
+pub fn compaction_keep_tool_pairs() -> usize {
+    env::var(env_vars::COMPACTION_KEEP_TOOL_PAIRS)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(defaults::COMPACTION_KEEP_TOOL_PAIRS)
+}
+
+/// Whether assistant responses get whitespace/bullet normalization before they
+/// are stored and broadcast (on unless set to off/false/0)
+pub fn normalize_assistant_output() -> bool {
+    env::var(env_vars::NORMALIZE_ASSISTANT_OUTPUT)
+        .map(|v| !matches!(v.trim().to_lowercase().as_str(), "off" | "false" | "0"))
+        .unwrap_or(true)
+}
+
+/// Whether detected context-bank items are stored per identity and re-loaded
+/// in later sessions (off by default)
+pub fn persist_context_bank() -> bool {
+    env::var(env_vars::PERSIST_CONTEXT_BANK)
+        .map(|v| matches!(v.trim().to_lowercase().as_str(), "on" | "true" | "1"))
+        .unwrap_or(false)
+}
+
 /// Get the path to SOUL.md in the soul directory
 pub fn soul_document_path() -> PathBuf {
     PathBuf::from(soul_dir()).join("SOUL.md")
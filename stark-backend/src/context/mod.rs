@@ -14,11 +14,15 @@ pub mod tokenizer;
 use crate::ai::{AiClient, Message, MessageRole};
 use crate::config::MemoryConfig;
 use crate::db::Database;
-use crate::models::SessionMessage;
+use crate::models::{SessionMessage, SessionScope};
 use crate::models::session_message::MessageRole as DbMessageRole;
 use crate::qmd_memory::MemoryStore;
+use crate::text::truncate_chars;
 use chrono::Utc;
-use std::sync::Arc;
+use dashmap::DashMap;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 pub use tokenizer::TokenEstimator;
 
 /// Default context window size (Claude 3.5 Sonnet)
@@ -33,6 +37,14 @@ pub const MIN_KEEP_RECENT_MESSAGES: i32 = 5;
 /// Default number of messages to keep after compaction
 pub const DEFAULT_KEEP_RECENT_MESSAGES: i32 = 10;
 
+/// Share of the post-compaction budget that the summary and the kept messages
+/// may occupy together (the rest stays free for new turns)
+const COMPACTION_SUMMARY_BUDGET_SHARE: f64 = 0.25;
+
+/// Bounds for the target length of a full compaction summary, in tokens
+const MIN_COMPACTION_SUMMARY_TOKENS: i32 = 150;
+const MAX_COMPACTION_SUMMARY_TOKENS: i32 = 3_000;
+
 /// Configuration for sliding window (incremental) compaction
 #[derive(Debug, Clone)]
 pub struct SlidingWindowConfig {
@@ -64,15 +76,107 @@ pub fn estimate_tokens(text: &str) -> i32 {
     TokenEstimator::ContentAware.estimate_text(text)
 }
 
-/// Estimate total tokens for a list of messages
-/// Uses content-aware estimation with role overhead
-pub fn estimate_messages_tokens(messages: &[SessionMessage]) -> i32 {
-    let estimator = TokenEstimator::ContentAware;
+/// Estimate total tokens for a list of messages with the given estimator
+/// (including role overhead)
+pub fn estimate_messages_tokens(estimator: TokenEstimator, messages: &[SessionMessage]) -> i32 {
     messages.iter()
         .map(|m| estimator.estimate_message(&m.content, &m.role))
         .sum()
 }
 
+/// Max characters of a tool's output kept in its one-line outcome
+const TOOL_OUTCOME_MAX_CHARS: usize = 120;
+
+/// Tools whose results are conversation flow rather than reusable data
+const TOOL_NOTE_SKIPPED_TOOLS: &[&str] = &["say_to_user", "task_fully_completed", "ask_user"];
+
+/// Condense successful tool results in `messages` into a "tool name: outcome" note.
+///
+/// Tool results are stored as `**Result:** <tool>\n<output>`; failed results
+/// (`**Error:**`) are skipped. The most recent results are kept first until
+/// `max_tokens` is reached, then listed oldest → newest.
+pub fn condense_tool_results(messages: &[SessionMessage], max_tokens: i32) -> Option<String> {
+    let header = "## Recent Tool Results\n\
+                  These tools already ran in this conversation. Reuse their outcomes \
+                  instead of calling them again unless fresh data is needed:\n";
+    let mut budget = max_tokens - estimate_tokens(header);
+    let mut lines: Vec<String> = Vec::new();
+
+    for msg in messages.iter().rev().filter(|m| m.role == DbMessageRole::ToolResult) {
+        let Some(rest) = msg.content.strip_prefix("**Result:** ") else { continue };
+        let (tool_name, output) = rest.split_once('\n').unwrap_or((rest, ""));
+        if TOOL_NOTE_SKIPPED_TOOLS.contains(&tool_name) {
+            continue;
+        }
+        let first_line = output.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or("(no output)");
+        let outcome = truncate_chars(first_line, TOOL_OUTCOME_MAX_CHARS);
+        let line = format!("- {}: {}", tool_name, outcome);
+        let cost = estimate_tokens(&line);
+        if cost > budget {
+            break;
+        }
+        budget -= cost;
+        lines.push(line);
+    }
+
+    if lines.is_empty() {
+        return None;
+    }
+    lines.reverse();
+    Some(format!("{}{}", header, lines.join("\n")))
+}
+
+/// Upper bound on cached memory blocks; the cache is cleared when exceeded
+const MAX_CACHED_MEMORY_BLOCKS: usize = 1024;
+
+/// Upper bound on sessions whose token estimator is remembered
+const MAX_TRACKED_ESTIMATORS: usize = 1024;
+
+/// A session resumed after this long without a dispatch has its `context_tokens`
+/// rebuilt from the stored messages
+const CONTEXT_TOKENS_RECHECK_AFTER: Duration = Duration::from_secs(30 * 60);
+
+struct CachedMemoryBlock {
+    block: Option<String>,
+    /// Turns served (including the rebuild) since the block was built
+    turns_served: u32,
+}
+
+/// Cross-session memory blocks cached per (session, identity) so memory
+/// search runs only every `refresh_turns` turns. Trades freshness for cost.
+#[derive(Default)]
+pub struct MemoryBlockCache {
+    entries: Mutex<HashMap<(i64, Option<String>), CachedMemoryBlock>>,
+}
+
+impl MemoryBlockCache {
+    /// Return the cached block, calling `build` when there is none yet or it
+    /// has been served for `refresh_turns` turns. `refresh_turns` of 1 rebuilds every turn.
+    pub fn get_or_build(
+        &self,
+        session_id: i64,
+        identity_id: Option<&str>,
+        refresh_turns: u32,
+        build: impl FnOnce() -> Option<String>,
+    ) -> Option<String> {
+        let key = (session_id, identity_id.map(String::from));
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(cached) = entries.get_mut(&key) {
+            if cached.turns_served < refresh_turns.max(1) {
+                cached.turns_served += 1;
+                return cached.block.clone();
+            }
+        }
+
+        let block = build();
+        if entries.len() >= MAX_CACHED_MEMORY_BLOCKS {
+            entries.clear();
+        }
+        entries.insert(key, CachedMemoryBlock { block: block.clone(), turns_served: 1 });
+        block
+    }
+}
+
 /// Context manager for handling session context and compaction
 pub struct ContextManager {
     db: Arc<Database>,
@@ -80,7 +184,8 @@ pub struct ContextManager {
     max_context_tokens: i32,
     /// Tokens to reserve for system prompt and output
     reserve_tokens: i32,
-    /// Number of recent messages to keep after compaction
+    /// Number of recent messages to keep after compaction (scopes without an
+    /// entry in `MemoryConfig::keep_recent_by_scope`)
     keep_recent_messages: i32,
     /// Memory configuration
     memory_config: MemoryConfig,
@@ -88,6 +193,19 @@ pub struct ContextManager {
     memory_store: Option<Arc<MemoryStore>>,
     /// Configuration for sliding window compaction
     sliding_window_config: SlidingWindowConfig,
+    /// Memory blocks reused between rebuilds (see `MemoryConfig::memory_refresh_turns`)
+    memory_cache: MemoryBlockCache,
+    /// Token estimator per session, matching the model its last dispatch used
+    /// (see `sync_token_estimator`). Kept per session so concurrent dispatches
+    /// on different models don't count each other's tokens.
+    session_estimators: DashMap<i64, TokenEstimator>,
+    /// Estimator given explicitly; model syncs then leave every session on it
+    pinned_estimator: Option<TokenEstimator>,
+    /// When each session's `context_tokens` was last rebuilt or kept current
+    /// (see `refresh_context_tokens`)
+    context_tokens_checked: DashMap<i64, Instant>,
+    /// Most recent tool call/result pairs kept verbatim by incremental compaction
+    keep_recent_tool_pairs: usize,
 }
 
 impl ContextManager {
@@ -100,6 +218,11 @@ impl ContextManager {
             memory_config: MemoryConfig::from_env(),
             memory_store: None,
             sliding_window_config: SlidingWindowConfig::default(),
+            memory_cache: MemoryBlockCache::default(),
+            session_estimators: DashMap::new(),
+            pinned_estimator: None,
+            context_tokens_checked: DashMap::new(),
+            keep_recent_tool_pairs: 0,
         }
     }
 
@@ -118,6 +241,35 @@ impl ContextManager {
         self
     }
 
+    /// Number of recent messages a full compaction keeps for a session of `scope`.
+    /// Never below `MIN_KEEP_RECENT_MESSAGES`, whatever is configured.
+    pub fn keep_recent_messages_for(&self, scope: SessionScope) -> i32 {
+        self.memory_config
+            .keep_recent_by_scope
+            .get(&scope)
+            .copied()
+            .unwrap_or(self.keep_recent_messages)
+            .max(MIN_KEEP_RECENT_MESSAGES)
+    }
+
+    /// Number of recent messages incremental compaction never removes for a session
+    /// of `scope`: the sliding window's floor, raised to the scope's configured
+    /// keep count if it has one
+    pub fn incremental_keep_for(&self, scope: SessionScope) -> i32 {
+        let floor = self.sliding_window_config.min_keep_messages;
+        match self.memory_config.keep_recent_by_scope.get(&scope) {
+            Some(keep) => floor.max((*keep).max(MIN_KEEP_RECENT_MESSAGES)),
+            None => floor,
+        }
+    }
+
+    /// Keep the `count` most recent tool call/result pairs of each compacted
+    /// segment verbatim instead of folding them into the summary
+    pub fn with_keep_recent_tool_pairs(mut self, count: usize) -> Self {
+        self.keep_recent_tool_pairs = count;
+        self
+    }
+
     pub fn with_memory_config(mut self, config: MemoryConfig) -> Self {
         self.memory_config = config;
         self
@@ -133,6 +285,52 @@ impl ContextManager {
         self
     }
 
+    /// Always count tokens with `estimator`, whatever model is configured
+    pub fn with_token_estimator(mut self, estimator: TokenEstimator) -> Self {
+        self.pinned_estimator = Some(estimator);
+        self
+    }
+
+    /// Count a session's tokens with the estimator matching the model it is
+    /// dispatched to, so compaction thresholds are checked against that model's tokenizer
+    pub fn sync_token_estimator(&self, session_id: i64, archetype: &str, model: Option<&str>) {
+        if self.pinned_estimator.is_some() {
+            return;
+        }
+        let estimator = TokenEstimator::for_model(archetype, model);
+        if self.session_estimators.len() >= MAX_TRACKED_ESTIMATORS && !self.session_estimators.contains_key(&session_id) {
+            // Sessions are re-synced on every dispatch, so forgetting idle ones is harmless
+            self.session_estimators.clear();
+        }
+        if self.session_estimators.insert(session_id, estimator) != Some(estimator) {
+            // Stored counts were made with another estimator; rebuild them next dispatch
+            self.context_tokens_checked.remove(&session_id);
+            log::debug!("[CONTEXT] Session {} token estimator for model {:?}: {:?}", session_id, model, estimator);
+        }
+    }
+
+    /// Estimator used for a session's token counts
+    pub fn token_estimator(&self, session_id: i64) -> TokenEstimator {
+        if let Some(estimator) = self.pinned_estimator {
+            return estimator;
+        }
+        self.session_estimators.get(&session_id).map(|e| *e).unwrap_or_default()
+    }
+
+    /// Estimate tokens for text added to a session
+    pub fn estimate_tokens(&self, session_id: i64, text: &str) -> i32 {
+        self.token_estimator(session_id).estimate_text(text)
+    }
+
+    /// Condensed note of recent successful tool results from `history`, if
+    /// enabled via `MemoryConfig::include_tool_results_note`
+    pub fn recent_tool_results_note(&self, history: &[SessionMessage]) -> Option<String> {
+        if !self.memory_config.include_tool_results_note {
+            return None;
+        }
+        condense_tool_results(history, self.memory_config.tool_results_note_max_tokens)
+    }
+
     /// Sync session's max_context_tokens with agent settings
     /// This ensures compaction triggers at the right threshold for the configured endpoint
     pub fn sync_max_context_tokens(&self, session_id: i64, agent_max_tokens: i32) {
@@ -150,17 +348,26 @@ impl ContextManager {
         }
     }
 
-    /// Check if compaction is needed for a session (original all-at-once threshold)
+    /// Check if compaction is needed for a session (original all-at-once threshold).
+    /// Pinned messages survive compaction but still take up the context window,
+    /// so they always count toward the total.
     pub fn needs_compaction(&self, session_id: i64) -> bool {
         if let Ok(session) = self.db.get_chat_session(session_id) {
             if let Some(session) = session {
                 let threshold = session.max_context_tokens - self.reserve_tokens;
-                return session.context_tokens > threshold;
+                let context_tokens = session.context_tokens.max(self.pinned_tokens(session_id));
+                return context_tokens > threshold;
             }
         }
         false
     }
 
+    /// Estimated tokens held by a session's pinned messages
+    pub fn pinned_tokens(&self, session_id: i64) -> i32 {
+        let pinned = self.db.get_pinned_messages(session_id).unwrap_or_default();
+        estimate_messages_tokens(self.token_estimator(session_id), &pinned)
+    }
+
     /// Get available context budget (after reserving tokens)
     pub fn get_context_budget(&self, session_id: i64) -> i32 {
         if let Ok(Some(session)) = self.db.get_chat_session(session_id) {
@@ -183,16 +390,28 @@ impl ContextManager {
         self.db.get_session_compaction_summary(session_id).ok().flatten()
     }
 
+    /// Number of compactions the session has gone through. Anything built from
+    /// the conversation before the generation changed may reference summarized turns.
+    pub fn compaction_generation(&self, session_id: i64) -> i32 {
+        self.db.get_compaction_generation(session_id).unwrap_or(0)
+    }
+
     /// Check if incremental (sliding window) compaction should occur
-    /// Triggers earlier than full compaction to do smaller, less disruptive compactions
-    pub fn needs_incremental_compaction(&self, session_id: i64) -> bool {
+    /// Triggers earlier than full compaction to do smaller, less disruptive compactions.
+    /// Not needed while every message is within the scope's kept window, since
+    /// there would be nothing to compact.
+    pub fn needs_incremental_compaction(&self, session_id: i64, scope: SessionScope) -> bool {
         if let Ok(Some(session)) = self.db.get_chat_session(session_id) {
             // Trigger at (max - reserve - buffer) instead of (max - reserve)
             // e.g., at 85k instead of 80k for 100k context with 20k reserve and 15k buffer
             let threshold = session.max_context_tokens
                 - self.reserve_tokens
                 - self.sliding_window_config.compaction_buffer;
-            return session.context_tokens > threshold;
+            if session.context_tokens <= threshold {
+                return false;
+            }
+            let message_count = self.db.count_session_messages(session_id).unwrap_or(0);
+            return message_count > self.incremental_keep_for(scope) as i64;
         }
         false
     }
@@ -202,21 +421,30 @@ impl ContextManager {
     pub async fn compact_incremental(
         &self,
         session_id: i64,
+        scope: SessionScope,
         client: &AiClient,
         identity_id: Option<&str>,
     ) -> Result<i32, String> {
         // Calculate how many messages to compact to free target tokens
-        let messages_to_compact = self.calculate_messages_to_compact(session_id)?;
+        let messages_to_compact = self.calculate_messages_to_compact(session_id, scope)?;
 
         if messages_to_compact.is_empty() {
             log::info!("[INCREMENTAL_COMPACT] No messages to compact for session {}", session_id);
             return Ok(0);
         }
 
+        // Recent tool call/result pairs stay verbatim; only the rest is summarized
+        let (messages_to_compact, preserved_pairs) =
+            split_recent_tool_pairs(messages_to_compact, self.keep_recent_tool_pairs);
+        if messages_to_compact.is_empty() {
+            log::info!("[INCREMENTAL_COMPACT] Only preserved tool pairs left to compact for session {}", session_id);
+            return Ok(0);
+        }
+
         let message_count = messages_to_compact.len() as i32;
         log::info!(
-            "[INCREMENTAL_COMPACT] Compacting {} oldest messages for session {} (incremental)",
-            message_count, session_id
+            "[INCREMENTAL_COMPACT] Compacting {} oldest messages for session {} (incremental, {} tool pairs kept)",
+            message_count, session_id, preserved_pairs
         );
 
         // Phase 1: Pre-compaction memory flush (writes to markdown files)
@@ -239,7 +467,7 @@ impl ContextManager {
         }
 
         // Generate a shorter summary for incremental compaction
-        let summary = self.generate_incremental_summary(client, &messages_to_compact).await?;
+        let summary = self.generate_incremental_summary(client, &messages_to_compact, preserved_pairs).await?;
 
         log::info!(
             "[INCREMENTAL_COMPACT] Generated summary ({} chars) for {} messages",
@@ -254,9 +482,14 @@ impl ContextManager {
             log::warn!("[INCREMENTAL_COMPACT] Failed to store compaction summary: {}", e);
         }
 
-        // Delete only the oldest N messages
-        let deleted = self.db.delete_oldest_messages(session_id, message_count)
-            .map_err(|e| format!("Failed to delete oldest messages: {}", e))?;
+        // Delete only the oldest N messages (skipping the preserved tool pairs)
+        let deleted = if preserved_pairs > 0 {
+            let ids: Vec<i64> = messages_to_compact.iter().map(|m| m.id).collect();
+            self.db.delete_session_messages_by_ids(session_id, &ids)
+        } else {
+            self.db.delete_oldest_messages(session_id, message_count)
+        }
+        .map_err(|e| format!("Failed to delete oldest messages: {}", e))?;
 
         log::info!("[INCREMENTAL_COMPACT] Deleted {} oldest messages for session {}", deleted, session_id);
 
@@ -267,7 +500,9 @@ impl ContextManager {
 
         // Recalculate and update context tokens
         let remaining = self.db.get_session_messages(session_id).unwrap_or_default();
-        let new_token_count = estimate_messages_tokens(&remaining) + estimate_tokens(&chained_summary);
+        let estimator = self.token_estimator(session_id);
+        let new_token_count =
+            estimate_messages_tokens(estimator, &remaining) + estimator.estimate_text(&chained_summary);
         self.db.update_session_context_tokens(session_id, new_token_count)
             .map_err(|e| format!("Failed to update context tokens: {}", e))?;
 
@@ -275,39 +510,40 @@ impl ContextManager {
     }
 
     /// Calculate which messages to compact to free target tokens
-    fn calculate_messages_to_compact(&self, session_id: i64) -> Result<Vec<SessionMessage>, String> {
+    fn calculate_messages_to_compact(&self, session_id: i64, scope: SessionScope) -> Result<Vec<SessionMessage>, String> {
         let all_messages = self.db.get_session_messages(session_id)
             .map_err(|e| format!("Failed to get session messages: {}", e))?;
 
-        if all_messages.len() as i32 <= self.sliding_window_config.min_keep_messages {
+        let min_keep = self.incremental_keep_for(scope) as usize;
+        if all_messages.len() <= min_keep {
             return Ok(vec![]);
         }
 
         let target_tokens = self.sliding_window_config.target_free_tokens;
         let max_messages = self.sliding_window_config.max_compact_per_cycle;
-        let min_keep = self.sliding_window_config.min_keep_messages as usize;
+
+        // Only messages outside the kept window are candidates; pinned ones are skipped
+        let max_compactable = all_messages.len().saturating_sub(min_keep);
+        let candidates = all_messages.into_iter().take(max_compactable).filter(|m| !m.pinned);
 
         // Calculate how many messages to compact
+        let estimator = self.token_estimator(session_id);
         let mut token_sum = 0i32;
-        let mut count = 0usize;
-        let max_compactable = all_messages.len().saturating_sub(min_keep);
+        let mut to_compact = Vec::new();
 
-        for msg in &all_messages {
-            if count >= max_compactable {
-                break;
-            }
-            if count >= max_messages as usize {
+        for msg in candidates {
+            if to_compact.len() >= max_messages as usize {
                 break;
             }
             if token_sum >= target_tokens {
                 break;
             }
 
-            token_sum += estimate_tokens(&msg.content);
-            count += 1;
+            token_sum += estimator.estimate_text(&msg.content);
+            to_compact.push(msg);
         }
 
-        Ok(all_messages.into_iter().take(count).collect())
+        Ok(to_compact)
     }
 
     /// Generate a shorter summary for incremental compaction
@@ -315,29 +551,9 @@ impl ContextManager {
         &self,
         client: &AiClient,
         messages: &[SessionMessage],
+        preserved_tool_pairs: usize,
     ) -> Result<String, String> {
-        let conversation_text = messages.iter()
-            .map(|m| {
-                let role = match m.role {
-                    DbMessageRole::User => "User",
-                    DbMessageRole::Assistant => "Assistant",
-                    DbMessageRole::System => "System",
-                    DbMessageRole::ToolCall => "Tool Call",
-                    DbMessageRole::ToolResult => "Tool Result",
-                };
-                format!("{}: {}", role, m.content)
-            })
-            .collect::<Vec<_>>()
-            .join("\n\n");
-
-        // Shorter prompt for incremental summaries - target ~200 words
-        let summary_prompt = format!(
-            "Summarize this conversation segment concisely (under 200 words). \
-            Focus on: decisions made, facts learned, tasks started or completed. \
-            Be factual and specific.\n\n\
-            Conversation:\n{}\n\nSummary:",
-            conversation_text
-        );
+        let summary_prompt = incremental_summary_prompt(messages, preserved_tool_pairs);
 
         let summary_messages = vec![
             Message {
@@ -515,11 +731,14 @@ impl ContextManager {
     pub async fn compact_session(
         &self,
         session_id: i64,
+        scope: SessionScope,
         client: &AiClient,
         identity_id: Option<&str>,
     ) -> Result<i32, String> {
+        let keep_recent = self.keep_recent_messages_for(scope);
+
         // Get messages to compact (all except recent ones)
-        let messages_to_compact = self.db.get_messages_for_compaction(session_id, self.keep_recent_messages)
+        let messages_to_compact = self.db.get_messages_for_compaction(session_id, keep_recent)
             .map_err(|e| format!("Failed to get messages for compaction: {}", e))?;
 
         if messages_to_compact.is_empty() {
@@ -549,6 +768,14 @@ impl ContextManager {
             }
         }
 
+        // Size the summary to the budget left once the old messages are gone
+        let compacted_tokens = estimate_messages_tokens(self.token_estimator(session_id), &messages_to_compact);
+        let target_tokens = compaction_summary_target_tokens(
+            self.get_context_budget(session_id) + compacted_tokens,
+            keep_recent,
+        );
+        let target_words = tokens_to_words(target_tokens);
+
         // Build the conversation text for summarization
         let conversation_text = messages_to_compact.iter()
             .map(|m| {
@@ -564,13 +791,22 @@ impl ContextManager {
             .collect::<Vec<_>>()
             .join("\n\n");
 
+        // Pinned messages stay in the conversation as-is; the summary shouldn't repeat them
+        let has_pinned = self.db.get_pinned_messages(session_id).map(|p| !p.is_empty()).unwrap_or(false);
+        let pinned_note = if has_pinned {
+            "Some messages are pinned and retained verbatim outside this summary, \
+            so don't restate their content. "
+        } else {
+            ""
+        };
+
         // Generate summary using AI
         let summary_prompt = format!(
             "Summarize the following conversation history concisely. \
             Focus on: key topics discussed, important decisions made, user preferences learned, \
-            and any tasks or commitments. Keep it factual and under 500 words.\n\n\
+            and any tasks or commitments. {}Keep it factual and under {} words.\n\n\
             Conversation:\n{}\n\nSummary:",
-            conversation_text
+            pinned_note, target_words, conversation_text
         );
 
         let summary_messages = vec![
@@ -584,9 +820,38 @@ impl ContextManager {
             },
         ];
 
-        let summary = client.generate_text(summary_messages).await
+        let mut summary = client.generate_text(summary_messages).await
             .map_err(|e| format!("Failed to generate compaction summary: {}", e))?;
 
+        // Models overshoot word limits; condense an oversized summary once
+        let summary_tokens = self.estimate_tokens(session_id, &summary);
+        if summary_tokens > target_tokens {
+            log::info!(
+                "[COMPACTION] Summary for session {} is {} tokens (target {}), condensing",
+                session_id, summary_tokens, target_tokens
+            );
+            let condense_messages = vec![
+                Message {
+                    role: MessageRole::System,
+                    content: "You are a helpful assistant that summarizes conversations accurately and concisely.".to_string(),
+                },
+                Message {
+                    role: MessageRole::User,
+                    content: format!(
+                        "Condense this conversation summary to under {} words. Keep decisions, \
+                        user preferences and open tasks; drop everything else.\n\n\
+                        Summary:\n{}\n\nCondensed summary:",
+                        target_words, summary
+                    ),
+                },
+            ];
+            match client.generate_text(condense_messages).await {
+                Ok(condensed) if !condensed.trim().is_empty() => summary = condensed,
+                Ok(_) => log::warn!("[COMPACTION] Condensed summary was empty, keeping the original"),
+                Err(e) => log::warn!("[COMPACTION] Failed to condense summary (keeping the original): {}", e),
+            }
+        }
+
         log::info!("[COMPACTION] Generated summary ({} chars) for session {}", summary.len(), session_id);
 
         // Write the compaction summary to the daily log as a session summary
@@ -604,20 +869,68 @@ impl ContextManager {
         }
 
         // Delete the compacted messages
-        let deleted = self.db.delete_compacted_messages(session_id, self.keep_recent_messages)
+        let deleted = self.db.delete_compacted_messages(session_id, keep_recent)
             .map_err(|e| format!("Failed to delete compacted messages: {}", e))?;
 
         log::info!("[COMPACTION] Deleted {} old messages for session {}", deleted, session_id);
 
+        // Increment compaction generation
+        if let Err(e) = self.db.increment_compaction_generation(session_id) {
+            log::warn!("[COMPACTION] Failed to increment compaction generation: {}", e);
+        }
+
         // Recalculate and update context tokens
-        let remaining = self.db.get_session_messages(session_id).unwrap_or_default();
-        let new_token_count = estimate_messages_tokens(&remaining) + estimate_tokens(&summary);
-        self.db.update_session_context_tokens(session_id, new_token_count)
-            .map_err(|e| format!("Failed to update context tokens: {}", e))?;
+        self.recompute_context_tokens(session_id)?;
 
         Ok(message_count)
     }
 
+    /// Rebuild `context_tokens` from the stored messages plus the compaction summary.
+    /// Corrects drift from missed incremental updates (e.g. a crash between a message
+    /// write and its token update). Returns the recomputed count.
+    pub fn recompute_context_tokens(&self, session_id: i64) -> Result<i32, String> {
+        let messages = self.db.get_session_messages(session_id)
+            .map_err(|e| format!("Failed to load session messages: {}", e))?;
+        let summary_tokens = self.db.get_session_compaction_summary(session_id)
+            .ok()
+            .flatten()
+            .map(|s| self.estimate_tokens(session_id, &s))
+            .unwrap_or(0);
+        let total = estimate_messages_tokens(self.token_estimator(session_id), &messages) + summary_tokens;
+
+        if let Ok(Some(session)) = self.db.get_chat_session(session_id) {
+            if session.context_tokens != total {
+                log::info!(
+                    "[CONTEXT] Session {} context_tokens drifted ({} -> {}), correcting",
+                    session_id, session.context_tokens, total
+                );
+            }
+        }
+
+        self.db.update_session_context_tokens(session_id, total)
+            .map_err(|e| format!("Failed to update context tokens: {}", e))?;
+        if self.context_tokens_checked.len() >= MAX_TRACKED_ESTIMATORS && !self.context_tokens_checked.contains_key(&session_id) {
+            self.context_tokens_checked.clear();
+        }
+        self.context_tokens_checked.insert(session_id, Instant::now());
+        Ok(total)
+    }
+
+    /// Rebuild `context_tokens` for a session being resumed: the first dispatch
+    /// since startup, one after `CONTEXT_TOKENS_RECHECK_AFTER` of inactivity, or
+    /// one after its token estimator changed. Sessions dispatched recently are
+    /// kept current incrementally and are left alone. Returns the recomputed
+    /// count, or `None` when no rebuild was needed.
+    pub fn refresh_context_tokens(&self, session_id: i64) -> Result<Option<i32>, String> {
+        if let Some(mut checked) = self.context_tokens_checked.get_mut(&session_id) {
+            if checked.elapsed() < CONTEXT_TOKENS_RECHECK_AFTER {
+                *checked = Instant::now();
+                return Ok(None);
+            }
+        }
+        self.recompute_context_tokens(session_id).map(Some)
+    }
+
     /// Update context tokens after adding a message
     pub fn update_context_tokens(&self, session_id: i64, message_tokens: i32) {
         if let Ok(Some(session)) = self.db.get_chat_session(session_id) {
@@ -696,18 +1009,24 @@ impl ContextManager {
 
     /// Build context with optional memory retrieval
     /// Returns (messages, combined_context_summary)
-    /// The combined_context includes both compaction summary and cross-session memories
+    /// The combined_context includes both compaction summary and cross-session memories.
+    /// The memory portion is rebuilt every `refresh_turns` turns (None = `MemoryConfig`
+    /// default) and reused from cache in between.
     pub fn build_context_with_memories(
         &self,
         session_id: i64,
         identity_id: Option<&str>,
         limit: i32,
+        refresh_turns: Option<u32>,
     ) -> (Vec<SessionMessage>, Option<String>) {
         let messages = self.build_context(session_id, limit);
         let compaction_summary = self.get_compaction_summary(session_id);
 
         // Retrieve cross-session memories if enabled
-        let memory_context = self.retrieve_relevant_memories(identity_id, &messages);
+        let refresh_turns = refresh_turns.unwrap_or(self.memory_config.memory_refresh_turns);
+        let memory_context = self.memory_cache.get_or_build(session_id, identity_id, refresh_turns, || {
+            self.retrieve_relevant_memories(identity_id, &messages)
+        });
 
         // Combine summaries
         let combined = match (compaction_summary, memory_context) {
@@ -801,6 +1120,82 @@ pub async fn save_session_memory(
     Ok(())
 }
 
+/// Target length of a full compaction summary for a post-compaction `budget`:
+/// the summary gets the same share as each of the `keep_recent` messages kept
+/// beside it, within fixed bounds.
+fn compaction_summary_target_tokens(budget: i32, keep_recent: i32) -> i32 {
+    let share = budget.max(0) as f64 * COMPACTION_SUMMARY_BUDGET_SHARE / (keep_recent.max(0) + 1) as f64;
+    (share as i32).clamp(MIN_COMPACTION_SUMMARY_TOKENS, MAX_COMPACTION_SUMMARY_TOKENS)
+}
+
+/// Approximate English word count for a token count (~0.75 words per token)
+fn tokens_to_words(tokens: i32) -> i32 {
+    tokens * 3 / 4
+}
+
+/// Split the messages picked for compaction into the ones to summarize and
+/// the number of tool call/result pairs kept. The `keep_pairs` most recent
+/// pairs (a ToolCall directly followed by its ToolResult) are dropped from
+/// the returned list so they survive compaction verbatim.
+fn split_recent_tool_pairs(messages: Vec<SessionMessage>, keep_pairs: usize) -> (Vec<SessionMessage>, usize) {
+    if keep_pairs == 0 {
+        return (messages, 0);
+    }
+
+    let pair_starts: Vec<usize> = messages
+        .windows(2)
+        .enumerate()
+        .filter(|(_, w)| w[0].role == DbMessageRole::ToolCall && w[1].role == DbMessageRole::ToolResult)
+        .map(|(i, _)| i)
+        .collect();
+    let kept: Vec<usize> = pair_starts.iter().rev().take(keep_pairs).copied().collect();
+
+    let to_compact = messages
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| !kept.iter().any(|&start| *i == start || *i == start + 1))
+        .map(|(_, m)| m)
+        .collect();
+    (to_compact, kept.len())
+}
+
+/// Prompt for an incremental summary. When tool pairs were kept verbatim the
+/// model is told so it doesn't restate them.
+fn incremental_summary_prompt(messages: &[SessionMessage], preserved_tool_pairs: usize) -> String {
+    let conversation_text = messages.iter()
+        .map(|m| {
+            let role = match m.role {
+                DbMessageRole::User => "User",
+                DbMessageRole::Assistant => "Assistant",
+                DbMessageRole::System => "System",
+                DbMessageRole::ToolCall => "Tool Call",
+                DbMessageRole::ToolResult => "Tool Result",
+            };
+            format!("{}: {}", role, m.content)
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let preserved_note = if preserved_tool_pairs > 0 {
+        format!(
+            "The {} most recent tool call/result pair(s) from this segment are kept verbatim \
+            after this summary, so don't restate their details.\n\n",
+            preserved_tool_pairs
+        )
+    } else {
+        String::new()
+    };
+
+    // Shorter prompt for incremental summaries - target ~200 words
+    format!(
+        "Summarize this conversation segment concisely (under 200 words). \
+        Focus on: decisions made, facts learned, tasks started or completed. \
+        Be factual and specific.\n\n\
+        {}Conversation:\n{}\n\nSummary:",
+        preserved_note, conversation_text
+    )
+}
+
 /// Truncate a summary to approximately max_words, breaking at word boundaries
 fn truncate_summary(summary: &str, max_words: usize) -> String {
     let words: Vec<&str> = summary.split_whitespace().collect();
@@ -840,7 +1235,7 @@ fn parse_title_summary(response: &str) -> (String, String) {
         title = format!("Session {}", Utc::now().format("%Y-%m-%d %H:%M"));
     }
     if summary.is_empty() {
-        summary = response.chars().take(500).collect();
+        summary = truncate_chars(response, 500);
     }
 
     (title, summary)
@@ -862,6 +1257,355 @@ mod tests {
         assert!(tokens >= 10 && tokens <= 50);
     }
 
+    #[test]
+    fn test_recompute_context_tokens_repairs_drift() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let session = db
+            .get_or_create_chat_session("web", 1, "chat-1", crate::models::SessionScope::Dm, None)
+            .unwrap();
+        db.add_session_message(session.id, DbMessageRole::User, "What is the ETH price today?", None, None, None, None)
+            .unwrap();
+        db.add_session_message(session.id, DbMessageRole::Assistant, "ETH is trading around $3,000.", None, None, None, None)
+            .unwrap();
+        db.set_session_compaction_summary(session.id, "User asked about token prices earlier.")
+            .unwrap();
+
+        let manager = ContextManager::new(db.clone());
+        let expected = manager.recompute_context_tokens(session.id).unwrap();
+        assert!(expected > 0);
+
+        // Corrupt the stored count, then rebuild it
+        db.update_session_context_tokens(session.id, 999_999).unwrap();
+        assert_eq!(manager.recompute_context_tokens(session.id).unwrap(), expected);
+        assert_eq!(db.get_chat_session(session.id).unwrap().unwrap().context_tokens, expected);
+    }
+
+    #[test]
+    fn test_refresh_context_tokens_only_on_resume() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let session = db
+            .get_or_create_chat_session("web", 1, "chat-1", crate::models::SessionScope::Dm, None)
+            .unwrap();
+        db.add_session_message(session.id, DbMessageRole::User, "What is the ETH price today?", None, None, None, None)
+            .unwrap();
+
+        // First dispatch since startup rebuilds the count
+        let manager = ContextManager::new(db.clone());
+        db.update_session_context_tokens(session.id, 999_999).unwrap();
+        let expected = manager.refresh_context_tokens(session.id).unwrap().expect("rebuilt on first dispatch");
+        assert_ne!(expected, 999_999);
+
+        // Active sessions are kept current incrementally
+        assert_eq!(manager.refresh_context_tokens(session.id).unwrap(), None);
+
+        // A new estimator invalidates the stored count
+        manager.sync_token_estimator(session.id, "openai", Some("gpt-4o"));
+        assert!(manager.refresh_context_tokens(session.id).unwrap().is_some());
+        manager.sync_token_estimator(session.id, "openai", Some("gpt-4o"));
+        assert_eq!(manager.refresh_context_tokens(session.id).unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_compaction_keeps_recent_tool_pairs() {
+        use crate::ai::{AiResponse, MockAiClient};
+
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let session = db
+            .get_or_create_chat_session("web", 1, "chat-1", crate::models::SessionScope::Dm, None)
+            .unwrap();
+        for (role, content) in [
+            (DbMessageRole::User, "Check my balances"),
+            (DbMessageRole::ToolCall, "🔧 token_balance {\"token\":\"USDC\"}"),
+            (DbMessageRole::ToolResult, "USDC: 120.5"),
+            (DbMessageRole::Assistant, "You have 120.5 USDC."),
+            (DbMessageRole::ToolCall, "🔧 token_balance {\"token\":\"ETH\"}"),
+            (DbMessageRole::ToolResult, "ETH: 0.42"),
+            (DbMessageRole::Assistant, "And 0.42 ETH."),
+            (DbMessageRole::User, "Thanks"),
+            (DbMessageRole::Assistant, "Anytime!"),
+        ] {
+            db.add_session_message(session.id, role, content, None, None, None, None).unwrap();
+        }
+
+        let manager = ContextManager::new(db.clone())
+            .with_memory_config(MemoryConfig {
+                enable_pre_compaction_flush: false,
+                ..MemoryConfig::default()
+            })
+            .with_sliding_window_config(SlidingWindowConfig {
+                min_keep_messages: 2,
+                ..SlidingWindowConfig::default()
+            })
+            .with_keep_recent_tool_pairs(1);
+        let client = AiClient::Mock(MockAiClient::new(vec![Ok(AiResponse::text(
+            "User checked balances.".to_string(),
+        ))]));
+
+        // 7 oldest messages picked, the ETH pair survives
+        let compacted = manager.compact_incremental(session.id, session.scope, &client, None).await.unwrap();
+        assert_eq!(compacted, 5);
+
+        let remaining: Vec<String> = db
+            .get_session_messages(session.id)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.content)
+            .collect();
+        assert_eq!(
+            remaining,
+            vec!["🔧 token_balance {\"token\":\"ETH\"}", "ETH: 0.42", "Thanks", "Anytime!"]
+        );
+        assert_eq!(
+            db.get_session_compaction_summary(session.id).unwrap().as_deref(),
+            Some("User checked balances.")
+        );
+    }
+
+    #[test]
+    fn test_compaction_summary_target_scales_with_budget() {
+        // 100k model with 20k reserved, 10 messages kept
+        assert_eq!(compaction_summary_target_tokens(80_000, 10), 1_818);
+        assert_eq!(tokens_to_words(1_818), 1_363);
+        // Large models hit the cap, tiny ones the floor
+        assert_eq!(compaction_summary_target_tokens(180_000, 10), MAX_COMPACTION_SUMMARY_TOKENS);
+        assert_eq!(compaction_summary_target_tokens(4_000, 10), MIN_COMPACTION_SUMMARY_TOKENS);
+        assert_eq!(compaction_summary_target_tokens(-500, 10), MIN_COMPACTION_SUMMARY_TOKENS);
+    }
+
+    #[tokio::test]
+    async fn test_compaction_condenses_oversized_summary_once() {
+        use crate::ai::{AiResponse, MockAiClient};
+
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let session = db
+            .get_or_create_chat_session("web", 1, "chat-1", crate::models::SessionScope::Dm, None)
+            .unwrap();
+        for i in 0..8 {
+            db.add_session_message(session.id, DbMessageRole::User, &format!("Message {}", i), None, None, None, None)
+                .unwrap();
+        }
+
+        // Reserve nearly everything so the summary target bottoms out
+        let manager = ContextManager::new(db.clone())
+            .with_reserve_tokens(1_000_000)
+            .with_keep_recent(5)
+            .with_memory_config(MemoryConfig {
+                enable_pre_compaction_flush: false,
+                ..MemoryConfig::default()
+            });
+        let client = AiClient::Mock(MockAiClient::new(vec![
+            Ok(AiResponse::text("The user sent numbered messages. ".repeat(100))),
+            Ok(AiResponse::text("User sent numbered messages.".to_string())),
+        ]));
+
+        assert_eq!(manager.compact_session(session.id, SessionScope::Dm, &client, None).await.unwrap(), 3);
+        assert_eq!(
+            db.get_session_compaction_summary(session.id).unwrap().as_deref(),
+            Some("User sent numbered messages.")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compaction_keeps_pinned_messages() {
+        use crate::ai::{AiResponse, MockAiClient};
+
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let session = db
+            .get_or_create_chat_session("web", 1, "chat-1", crate::models::SessionScope::Dm, None)
+            .unwrap();
+        let spec = db
+            .add_session_message(session.id, DbMessageRole::User, &"Spec: every endpoint needs auth. ".repeat(50), None, None, None, None)
+            .unwrap();
+        for i in 1..8 {
+            db.add_session_message(session.id, DbMessageRole::User, &format!("Message {}", i), None, None, None, None)
+                .unwrap();
+        }
+        assert!(db.pin_message(session.id, spec.id).unwrap());
+
+        let compactable: Vec<String> = db
+            .get_messages_for_compaction(session.id, 5)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.content)
+            .collect();
+        assert_eq!(compactable, vec!["Message 1", "Message 2"]);
+
+        let manager = ContextManager::new(db.clone())
+            .with_keep_recent(5)
+            .with_memory_config(MemoryConfig {
+                enable_pre_compaction_flush: false,
+                ..MemoryConfig::default()
+            });
+        let client = AiClient::Mock(MockAiClient::new(vec![Ok(AiResponse::text(
+            "User sent numbered messages.".to_string(),
+        ))]));
+        assert_eq!(manager.compact_session(session.id, SessionScope::Dm, &client, None).await.unwrap(), 2);
+
+        let remaining = db.get_session_messages(session.id).unwrap();
+        assert_eq!(remaining.len(), 6);
+        assert_eq!(remaining[0].id, spec.id);
+        assert!(remaining[0].pinned);
+
+        // The pinned spec alone fills the window, even if the stored count lags behind
+        db.update_session_context_tokens(session.id, 0).unwrap();
+        let max_context = db.get_chat_session(session.id).unwrap().unwrap().max_context_tokens;
+        let pinned_tokens = manager.pinned_tokens(session.id);
+        let manager = manager.with_reserve_tokens(max_context - pinned_tokens + 1);
+        assert!(manager.needs_compaction(session.id));
+
+        assert!(db.unpin_message(session.id, spec.id).unwrap());
+        assert_eq!(db.get_messages_for_compaction(session.id, 5).unwrap()[0].id, spec.id);
+    }
+
+    #[test]
+    fn test_split_recent_tool_pairs() {
+        let message = |id: i64, role: DbMessageRole| SessionMessage {
+            id,
+            session_id: 1,
+            role,
+            content: format!("message {}", id),
+            user_id: None,
+            user_name: None,
+            platform_message_id: None,
+            tokens_used: None,
+            created_at: Utc::now(),
+            pinned: false,
+        };
+        let messages = vec![
+            message(1, DbMessageRole::ToolCall),
+            message(2, DbMessageRole::ToolResult),
+            message(3, DbMessageRole::Assistant),
+            message(4, DbMessageRole::ToolCall),
+            message(5, DbMessageRole::ToolResult),
+            // Call without its result in this segment: not a pair
+            message(6, DbMessageRole::ToolCall),
+        ];
+        let ids = |msgs: &[SessionMessage]| msgs.iter().map(|m| m.id).collect::<Vec<_>>();
+
+        let (rest, kept) = split_recent_tool_pairs(messages.clone(), 0);
+        assert_eq!((ids(&rest), kept), (vec![1, 2, 3, 4, 5, 6], 0));
+
+        let (rest, kept) = split_recent_tool_pairs(messages.clone(), 1);
+        assert_eq!((ids(&rest), kept), (vec![1, 2, 3, 6], 1));
+
+        let (rest, kept) = split_recent_tool_pairs(messages, 5);
+        assert_eq!((ids(&rest), kept), (vec![3, 6], 2));
+
+        let prompt = incremental_summary_prompt(&rest, kept);
+        assert!(prompt.contains("The 2 most recent tool call/result pair(s)"));
+        assert!(!incremental_summary_prompt(&rest, 0).contains("kept verbatim"));
+    }
+
+    #[test]
+    fn test_context_tokens_follow_model_estimator() {
+        use tokenizer::BpeEncoding;
+
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let session = db
+            .get_or_create_chat_session("web", 1, "chat-1", crate::models::SessionScope::Dm, None)
+            .unwrap();
+        let cjk = "今天天气很好，我们一起去公园散步吧。明天可能会下雨，记得带伞。";
+        db.add_session_message(session.id, DbMessageRole::User, cjk, None, None, None, None).unwrap();
+
+        let manager = ContextManager::new(db.clone());
+        assert_eq!(manager.token_estimator(session.id), TokenEstimator::ContentAware);
+        let heuristic = manager.recompute_context_tokens(session.id).unwrap();
+
+        manager.sync_token_estimator(session.id, "openai", Some("gpt-4o"));
+        assert_eq!(manager.token_estimator(session.id), TokenEstimator::Bpe(BpeEncoding::O200k));
+        let bpe = manager.recompute_context_tokens(session.id).unwrap();
+        assert!(bpe > heuristic, "BPE count {} should exceed the heuristic {} for CJK", bpe, heuristic);
+
+        // Another session dispatched to a different model keeps its own estimator
+        manager.sync_token_estimator(session.id + 1, "kimi", Some("kimi-k2"));
+        assert_eq!(manager.token_estimator(session.id), TokenEstimator::Bpe(BpeEncoding::O200k));
+        assert_eq!(manager.recompute_context_tokens(session.id).unwrap(), bpe);
+
+        manager.sync_token_estimator(session.id, "kimi", Some("kimi-k2"));
+        assert_eq!(manager.recompute_context_tokens(session.id).unwrap(), heuristic);
+
+        // An explicit estimator is kept whatever model is configured
+        let pinned = ContextManager::new(db.clone()).with_token_estimator(TokenEstimator::Heuristic);
+        pinned.sync_token_estimator(session.id, "openai", Some("gpt-4o"));
+        assert_eq!(pinned.token_estimator(session.id), TokenEstimator::Heuristic);
+    }
+
+    #[test]
+    fn test_memory_block_reused_within_refresh_window() {
+        let cache = MemoryBlockCache::default();
+        let mut builds = 0;
+        fn turn(cache: &MemoryBlockCache, builds: &mut i32) -> Option<String> {
+            cache.get_or_build(7, Some("alice"), 3, || {
+                *builds += 1;
+                Some(format!("- memory v{}", builds))
+            })
+        }
+
+        // Built on the first turn, reused for the next two, rebuilt on the fourth
+        assert_eq!(turn(&cache, &mut builds).as_deref(), Some("- memory v1"));
+        assert_eq!(turn(&cache, &mut builds).as_deref(), Some("- memory v1"));
+        assert_eq!(turn(&cache, &mut builds).as_deref(), Some("- memory v1"));
+        assert_eq!(builds, 1);
+        assert_eq!(turn(&cache, &mut builds).as_deref(), Some("- memory v2"));
+        assert_eq!(builds, 2);
+
+        // Other identities in the same session get their own block
+        cache.get_or_build(7, Some("safemode"), 3, || {
+            builds += 1;
+            None
+        });
+        assert_eq!(builds, 3);
+
+        // A refresh interval of 1 rebuilds every turn
+        for _ in 0..2 {
+            cache.get_or_build(8, None, 1, || {
+                builds += 1;
+                None
+            });
+        }
+        assert_eq!(builds, 5);
+    }
+
+    #[tokio::test]
+    async fn test_keep_recent_messages_per_scope() {
+        use crate::ai::{AiResponse, MockAiClient};
+
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let manager = ContextManager::new(db.clone())
+            .with_keep_recent(6)
+            .with_memory_config(MemoryConfig {
+                enable_pre_compaction_flush: false,
+                keep_recent_by_scope: HashMap::from([(SessionScope::Group, 8), (SessionScope::Cron, 2)]),
+                ..MemoryConfig::default()
+            });
+        assert_eq!(manager.keep_recent_messages_for(SessionScope::Group), 8);
+        assert_eq!(manager.keep_recent_messages_for(SessionScope::Dm), 6);
+        // The floor applies to configured scopes too
+        assert_eq!(manager.keep_recent_messages_for(SessionScope::Cron), MIN_KEEP_RECENT_MESSAGES);
+
+        let session = db
+            .get_or_create_chat_session("discord", 1, "group-1", SessionScope::Group, None)
+            .unwrap();
+        for i in 0..10 {
+            db.add_session_message(session.id, DbMessageRole::User, &format!("Message {}", i), None, None, None, None)
+                .unwrap();
+        }
+        let client = AiClient::Mock(MockAiClient::new(vec![Ok(AiResponse::text(
+            "User sent numbered messages.".to_string(),
+        ))]));
+        assert_eq!(manager.compact_session(session.id, session.scope, &client, None).await.unwrap(), 2);
+        assert_eq!(db.get_session_messages(session.id).unwrap().len(), 8);
+
+        // Incremental compaction keeps the scope's window too
+        assert_eq!(manager.incremental_keep_for(SessionScope::Group), 8);
+        assert_eq!(manager.incremental_keep_for(SessionScope::Dm), SlidingWindowConfig::default().min_keep_messages);
+        db.update_session_context_tokens(session.id, session.max_context_tokens).unwrap();
+        assert!(!manager.needs_incremental_compaction(session.id, SessionScope::Group));
+        assert!(manager.needs_incremental_compaction(session.id, SessionScope::Dm));
+        let client = AiClient::Mock(MockAiClient::new(vec![]));
+        assert_eq!(manager.compact_incremental(session.id, session.scope, &client, None).await.unwrap(), 0);
+    }
+
     #[test]
     fn test_parse_title_summary() {
         let response = "TITLE: Discussion about Rust programming\nSUMMARY: User asked about ownership and borrowing in Rust.";
@@ -2,9 +2,32 @@
 //!
 //! Provides more accurate token estimation than simple character counting
 //! by considering content type (JSON, code, prose) and message role.
+//! For known OpenAI and Claude model families a real BPE tokenizer
+//! (tiktoken) is used instead, which matters most for code-heavy and CJK
+//! conversations where character ratios are far off.
+
+use tiktoken_rs::CoreBPE;
 
 use crate::models::session_message::MessageRole;
 
+/// BPE encodings available for token counting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BpeEncoding {
+    /// GPT-4 / GPT-3.5 encoding, also a close approximation for Claude
+    Cl100k,
+    /// GPT-4o, GPT-4.1, GPT-5 and o-series encoding
+    O200k,
+}
+
+impl BpeEncoding {
+    fn bpe(&self) -> &'static CoreBPE {
+        match self {
+            BpeEncoding::Cl100k => tiktoken_rs::cl100k_base_singleton(),
+            BpeEncoding::O200k => tiktoken_rs::o200k_base_singleton(),
+        }
+    }
+}
+
 /// Token estimator strategy
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TokenEstimator {
@@ -12,6 +35,8 @@ pub enum TokenEstimator {
     Heuristic,
     /// Content-aware estimation based on text type
     ContentAware,
+    /// Exact count with a BPE tokenizer
+    Bpe(BpeEncoding),
 }
 
 impl Default for TokenEstimator {
@@ -21,11 +46,30 @@ impl Default for TokenEstimator {
 }
 
 impl TokenEstimator {
+    /// Pick the estimator for a model. Known OpenAI and Claude families get
+    /// their BPE encoding (matched on the model name, then the archetype);
+    /// anything else uses the content-aware heuristic.
+    pub fn for_model(archetype: &str, model: Option<&str>) -> Self {
+        let model = model.unwrap_or("").to_lowercase();
+        let is_o_series = ["o1", "o3", "o4"].iter().any(|p| model == *p || model.starts_with(&format!("{}-", p)));
+        if model.starts_with("gpt-4o") || model.starts_with("gpt-4.1") || model.starts_with("gpt-5") || is_o_series {
+            return TokenEstimator::Bpe(BpeEncoding::O200k);
+        }
+        if model.starts_with("gpt-4") || model.starts_with("gpt-3.5") || model.contains("claude") {
+            return TokenEstimator::Bpe(BpeEncoding::Cl100k);
+        }
+        if model.is_empty() && matches!(archetype, "claude" | "openai") {
+            return TokenEstimator::Bpe(BpeEncoding::Cl100k);
+        }
+        TokenEstimator::ContentAware
+    }
+
     /// Estimate tokens for a message with role context
     pub fn estimate_message(&self, content: &str, role: &MessageRole) -> i32 {
         match self {
             TokenEstimator::Heuristic => heuristic_estimate(content),
             TokenEstimator::ContentAware => content_aware_estimate(content, role),
+            TokenEstimator::Bpe(encoding) => bpe_estimate(*encoding, content) + role_overhead(role),
         }
     }
 
@@ -34,10 +78,19 @@ impl TokenEstimator {
         match self {
             TokenEstimator::Heuristic => heuristic_estimate(text),
             TokenEstimator::ContentAware => content_aware_text_estimate(text),
+            TokenEstimator::Bpe(encoding) => bpe_estimate(*encoding, text),
         }
     }
 }
 
+/// Exact token count with a BPE encoding
+fn bpe_estimate(encoding: BpeEncoding, text: &str) -> i32 {
+    if text.is_empty() {
+        return 0;
+    }
+    encoding.bpe().encode_ordinary(text).len() as i32
+}
+
 /// Simple heuristic: ~3.5 characters per token for English text
 fn heuristic_estimate(text: &str) -> i32 {
     let chars = text.chars().count();
@@ -65,16 +118,16 @@ fn content_aware_text_estimate(text: &str) -> i32 {
 
 /// Content-aware estimation with role overhead
 fn content_aware_estimate(text: &str, role: &MessageRole) -> i32 {
-    let base = content_aware_text_estimate(text);
+    content_aware_text_estimate(text) + role_overhead(role)
+}
 
-    // Role overhead (message framing tokens)
-    let overhead = match role {
+/// Role overhead (message framing tokens)
+fn role_overhead(role: &MessageRole) -> i32 {
+    match role {
         MessageRole::ToolCall | MessageRole::ToolResult => 8,  // More structured
         MessageRole::System => 6,   // System messages have role prefix
         MessageRole::User | MessageRole::Assistant => 4,  // Basic role prefix
-    };
-
-    base + overhead
+    }
 }
 
 /// Check if text appears to be JSON content
@@ -193,4 +246,33 @@ mod tests {
         assert_eq!(user_estimate, base + 4);
         assert_eq!(tool_estimate, base + 8);
     }
+
+    #[test]
+    fn test_estimator_for_model() {
+        assert_eq!(TokenEstimator::for_model("openai", Some("gpt-4o-mini")), TokenEstimator::Bpe(BpeEncoding::O200k));
+        assert_eq!(TokenEstimator::for_model("openai", Some("o3-mini")), TokenEstimator::Bpe(BpeEncoding::O200k));
+        assert_eq!(TokenEstimator::for_model("openai", Some("gpt-4-turbo")), TokenEstimator::Bpe(BpeEncoding::Cl100k));
+        assert_eq!(
+            TokenEstimator::for_model("kimi", Some("claude-sonnet-4-5")),
+            TokenEstimator::Bpe(BpeEncoding::Cl100k)
+        );
+        assert_eq!(TokenEstimator::for_model("claude", None), TokenEstimator::Bpe(BpeEncoding::Cl100k));
+        assert_eq!(TokenEstimator::for_model("kimi", Some("kimi-k2")), TokenEstimator::ContentAware);
+        assert_eq!(TokenEstimator::for_model("llama", None), TokenEstimator::ContentAware);
+    }
+
+    #[test]
+    fn test_bpe_estimate_counts_cjk_text() {
+        let bpe = TokenEstimator::Bpe(BpeEncoding::Cl100k);
+        assert_eq!(bpe.estimate_text(""), 0);
+        assert_eq!(bpe.estimate_text("hello world"), 2);
+
+        // CJK text is far denser than 3.5 chars per token
+        let cjk = "今天天气很好，我们一起去公园散步吧。明天可能会下雨。";
+        assert!(bpe.estimate_text(cjk) > TokenEstimator::ContentAware.estimate_text(cjk) * 2);
+        assert_eq!(
+            bpe.estimate_message(cjk, &MessageRole::ToolResult),
+            bpe.estimate_text(cjk) + 8
+        );
+    }
 }
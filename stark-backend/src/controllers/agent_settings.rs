@@ -1,7 +1,7 @@
 use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use crate::ai::ArchetypeId;
 use crate::keystore_client::{KEYSTORE_CLIENT, DEFAULT_KEYSTORE_URL};
-use crate::models::{AgentSettings, AgentSettingsResponse, UpdateAgentSettingsRequest, UpdateBotSettingsRequest};
+use crate::models::{AgentSettings, AgentSettingsResponse, FallbackModel, UpdateAgentSettingsRequest, UpdateBotSettingsRequest};
 use crate::ai_endpoint_config;
 use crate::tools::rpc_config;
 use crate::AppState;
@@ -155,6 +155,22 @@ pub async fn update_agent_settings(
         }));
     }
 
+    // Validate fallback chain
+    if let Some(ref fallbacks) = request.fallback_models {
+        for fallback in fallbacks {
+            if fallback.endpoint.is_empty() {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": "Fallback model endpoint URL is required"
+                }));
+            }
+            if ArchetypeId::from_str(&fallback.provider).is_none() {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": format!("Invalid fallback provider: {}. Must be kimi, llama, claude, openai, or minimax.", fallback.provider)
+                }));
+            }
+        }
+    }
+
     // Save settings
     log::info!(
         "Saving agent settings: endpoint={}, archetype={}, max_response_tokens={}, max_context_tokens={}, has_secret_key={}",
@@ -166,8 +182,26 @@ pub async fn update_agent_settings(
     );
 
     match state.db.save_agent_settings(&request.endpoint, &request.model_archetype, request.model.as_deref(), request.max_response_tokens, request.max_context_tokens, request.secret_key.as_deref()) {
-        Ok(settings) => {
+        Ok(mut settings) => {
             log::info!("Updated agent settings to use {} endpoint with {} archetype", request.endpoint, request.model_archetype);
+            if let Err(e) = state.db.update_agent_settings_sampling(settings.id, request.temperature, request.top_p) {
+                log::error!("Failed to save sampling settings: {}", e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": format!("Database error: {}", e)
+                }));
+            }
+            settings.temperature = request.temperature;
+            settings.top_p = request.top_p;
+            if let Some(mut fallbacks) = request.fallback_models {
+                FallbackModel::keep_stored_keys(&mut fallbacks, &settings.fallback_models);
+                if let Err(e) = state.db.update_agent_settings_fallbacks(settings.id, &fallbacks) {
+                    log::error!("Failed to save fallback models: {}", e);
+                    return HttpResponse::InternalServerError().json(serde_json::json!({
+                        "error": format!("Database error: {}", e)
+                    }));
+                }
+                settings.fallback_models = fallbacks;
+            }
             let response: AgentSettingsResponse = settings.into();
             HttpResponse::Ok().json(response)
         }
@@ -266,6 +300,12 @@ pub async fn update_bot_settings(
         request.theme_accent.as_deref(),
         request.proxy_url.as_deref(),
         request.kanban_auto_execute,
+        request.loop_max_repeated_calls,
+        request.loop_signature_history,
+        request.tool_timeouts.as_ref(),
+        request.rate_limit_per_minute,
+        request.rate_limit_burst,
+        request.max_session_usdc,
     ) {
         Ok(settings) => {
             log::info!(
@@ -112,6 +112,12 @@ struct CreateSubtypeRequest {
     skip_task_planner: bool,
     #[serde(default)]
     aliases: Vec<String>,
+    #[serde(default)]
+    max_tasks: Option<u32>,
+    #[serde(default)]
+    temperature: Option<f32>,
+    #[serde(default)]
+    top_p: Option<f32>,
 }
 
 fn default_true() -> bool {
@@ -165,6 +171,9 @@ async fn create_subtype(
         max_iterations: body.max_iterations.unwrap_or(90),
         skip_task_planner: body.skip_task_planner,
         aliases: body.aliases.clone(),
+        max_tasks: body.max_tasks.unwrap_or(crate::ai::multi_agent::types::DEFAULT_MAX_TASKS),
+        temperature: body.temperature,
+        top_p: body.top_p,
     };
 
     match data.db.upsert_agent_subtype(&config) {
@@ -207,6 +216,12 @@ struct UpdateSubtypeRequest {
     skip_task_planner: Option<bool>,
     #[serde(default)]
     aliases: Option<Vec<String>>,
+    #[serde(default)]
+    max_tasks: Option<u32>,
+    #[serde(default)]
+    temperature: Option<f32>,
+    #[serde(default)]
+    top_p: Option<f32>,
 }
 
 /// Update an existing agent subtype.
@@ -252,6 +267,9 @@ async fn update_subtype(
         max_iterations: body.max_iterations.unwrap_or(existing.max_iterations),
         skip_task_planner: body.skip_task_planner.unwrap_or(existing.skip_task_planner),
         aliases: body.aliases.clone().unwrap_or(existing.aliases),
+        max_tasks: body.max_tasks.unwrap_or(existing.max_tasks),
+        temperature: body.temperature.or(existing.temperature),
+        top_p: body.top_p.or(existing.top_p),
     };
 
     match data.db.upsert_agent_subtype(&updated) {
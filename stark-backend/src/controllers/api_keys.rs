@@ -622,11 +622,15 @@ async fn upsert_api_key(
 
     // Store the key (key_name is the service_name in the database)
     match state.db.upsert_api_key(&body.key_name, &body.api_key) {
-        Ok(key) => HttpResponse::Ok().json(ApiKeyOperationResponse {
-            success: true,
-            key: Some(key.to_response()),
-            error: None,
-        }),
+        Ok(key) => {
+            // The key list is part of the cached system prompt
+            state.dispatcher.invalidate_system_prompt_cache();
+            HttpResponse::Ok().json(ApiKeyOperationResponse {
+                success: true,
+                key: Some(key.to_response()),
+                error: None,
+            })
+        }
         Err(e) => {
             log::error!("Failed to save API key: {}", e);
             HttpResponse::InternalServerError().json(ApiKeyOperationResponse {
@@ -650,6 +654,7 @@ async fn delete_api_key(
     match state.db.delete_api_key(&body.key_name) {
         Ok(deleted) => {
             if deleted {
+                state.dispatcher.invalidate_system_prompt_cache();
                 HttpResponse::Ok().json(ApiKeyOperationResponse {
                     success: true,
                     key: None,
@@ -1301,6 +1306,12 @@ async fn restore_from_cloud(state: web::Data<AppState>, req: HttpRequest) -> imp
             settings.theme_accent.as_deref(),
             None, // Don't restore proxy_url - it's infrastructure config
             None, // Don't restore kanban_auto_execute - keep current setting
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         ) {
             log::warn!("Failed to restore bot settings: {}", e);
         }
@@ -1592,6 +1603,7 @@ async fn restore_from_cloud(state: web::Data<AppState>, req: HttpRequest) -> imp
             tags: skill_entry.tags.clone(),
             subagent_type: skill_entry.subagent_type.clone(),
             requires_api_keys,
+            model_override: skill_entry.model_override.clone(),
             created_at: now.clone(),
             updated_at: now.clone(),
         };
@@ -1780,6 +1792,9 @@ async fn restore_from_cloud(state: web::Data<AppState>, req: HttpRequest) -> imp
         log::info!("Auto-started {} channels after restore", auto_started_channels);
     }
 
+    // Restored keys, soul and identity are part of the cached system prompt
+    state.dispatcher.invalidate_system_prompt_cache();
+
     // Record retrieval in local state
     if let Some(wallet_address) = get_wallet_address(&private_key) {
         let _ = state.db.record_keystore_retrieval(&wallet_address);
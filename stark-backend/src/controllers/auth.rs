@@ -4,10 +4,25 @@ use ethers::core::types::Signature;
 use ethers::utils::hash_message;
 use serde::{Deserialize, Serialize};
 
+use crate::config::Config;
+use crate::db::Database;
+use crate::models::Session;
 use crate::AppState;
 
 const SERVICE_NAME: &str = "StarkBot";
 
+/// How long an issued sign-in nonce stays valid
+const NONCE_TTL_MINUTES: i64 = 10;
+
+/// Most unused sign-in nonces one client IP may hold at once
+const MAX_NONCES_PER_CLIENT: usize = 20;
+
+/// Chains a sign-in message may name (Ethereum mainnet, Base)
+const SIWE_CHAIN_IDS: &[u64] = &[1, crate::x402::BASE_CHAIN_ID];
+
+/// Tolerated clock skew for a sign-in message's timestamps
+const SIWE_CLOCK_SKEW_SECS: i64 = 300;
+
 #[derive(Deserialize)]
 pub struct GenerateChallengeRequest {
     public_address: String,
@@ -40,6 +55,29 @@ pub struct LoginResponse {
     error: Option<String>,
 }
 
+#[derive(Serialize)]
+pub struct NonceResponse {
+    success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nonce: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct VerifyRequest {
+    /// The EIP-4361 sign-in message that was signed (must contain the issued nonce)
+    message: String,
+    signature: String,
+}
+
+#[derive(Deserialize)]
+pub struct AllowlistRequest {
+    public_address: String,
+    #[serde(default)]
+    label: Option<String>,
+}
+
 #[derive(Deserialize)]
 pub struct LogoutRequest {
     token: String,
@@ -60,6 +98,11 @@ pub fn config(cfg: &mut web::ServiceConfig) {
         web::scope("/api/auth")
             .route("/generate_challenge", web::post().to(generate_challenge))
             .route("/validate_auth", web::post().to(validate_auth))
+            .route("/nonce", web::post().to(issue_nonce))
+            .route("/verify", web::post().to(verify))
+            .route("/allowlist", web::get().to(list_allowlist))
+            .route("/allowlist", web::post().to(add_to_allowlist))
+            .route("/allowlist/{address}", web::delete().to(remove_from_allowlist))
             .route("/logout", web::post().to(logout))
             .route("/validate", web::get().to(validate)),
     );
@@ -76,6 +119,204 @@ fn generate_challenge_text(public_address: &str, unix_timestamp: i64) -> String
     )
 }
 
+/// Reasons a wallet sign-in can be refused
+#[derive(Debug, PartialEq)]
+enum SignInError {
+    BadRequest(String),
+    Unauthorized(String),
+    NotConfigured,
+    DomainNotConfigured,
+    Internal(String),
+}
+
+impl SignInError {
+    fn into_response(self) -> HttpResponse {
+        let (mut builder, error) = match self {
+            SignInError::BadRequest(e) => (HttpResponse::BadRequest(), e),
+            SignInError::Unauthorized(e) => (HttpResponse::Unauthorized(), e),
+            SignInError::NotConfigured => (
+                HttpResponse::ServiceUnavailable(),
+                "Login not configured. Set LOGIN_ADMIN_PUBLIC_ADDRESS or BURNER_WALLET_BOT_PRIVATE_KEY environment variable.".to_string(),
+            ),
+            SignInError::DomainNotConfigured => (
+                HttpResponse::ServiceUnavailable(),
+                "Sign-in domain not configured. Set the LOGIN_SIWE_DOMAIN environment variable.".to_string(),
+            ),
+            SignInError::Internal(e) => (HttpResponse::InternalServerError(), e),
+        };
+        builder.json(LoginResponse {
+            success: false,
+            token: None,
+            expires_at: None,
+            error: Some(error),
+        })
+    }
+}
+
+fn is_valid_address(address: &str) -> bool {
+    address.starts_with("0x") && address.len() == 42
+}
+
+/// Check that `public_address` (lowercased) may sign in: the configured admin,
+/// an address from LOGIN_ALLOWED_ADDRESSES, or one allowlisted in the database.
+fn check_address_allowed(db: &Database, config: &Config, public_address: &str) -> Result<(), SignInError> {
+    let admin = config.login_admin_public_address.as_deref().map(|a| a.to_lowercase());
+    if admin.as_deref() == Some(public_address)
+        || config.login_allowed_addresses.iter().any(|a| a == public_address)
+    {
+        return Ok(());
+    }
+
+    match db.is_address_allowlisted(public_address) {
+        Ok(true) => Ok(()),
+        Ok(false) => {
+            let nothing_configured = admin.is_none()
+                && config.login_allowed_addresses.is_empty()
+                && db.list_auth_allowlist().map(|l| l.is_empty()).unwrap_or(true);
+            if nothing_configured {
+                Err(SignInError::NotConfigured)
+            } else {
+                Err(SignInError::Unauthorized("Unauthorized wallet address".to_string()))
+            }
+        }
+        Err(e) => {
+            log::error!("Failed to check auth allowlist: {}", e);
+            Err(SignInError::Internal("Database error".to_string()))
+        }
+    }
+}
+
+/// The fields of an EIP-4361 sign-in message that are checked on verify
+#[derive(Debug)]
+struct SiweMessage {
+    domain: String,
+    address: String,
+    uri: String,
+    chain_id: u64,
+    nonce: String,
+    issued_at: chrono::DateTime<Utc>,
+    expiration_time: Option<chrono::DateTime<Utc>>,
+    not_before: Option<chrono::DateTime<Utc>>,
+}
+
+/// Parse an EIP-4361 message. The first line names the requesting domain,
+/// the second is the address; the rest are `Key: value` fields.
+fn parse_siwe_message(message: &str) -> Option<SiweMessage> {
+    let mut lines = message.lines();
+    let domain = lines
+        .next()?
+        .trim()
+        .strip_suffix(" wants you to sign in with your Ethereum account:")?
+        .to_lowercase();
+    let address = lines.next()?.trim().to_lowercase();
+
+    let field = |name: &str| {
+        message
+            .lines()
+            .find_map(|l| l.trim().strip_prefix(name)?.strip_prefix(':'))
+            .map(|v| v.trim().to_string())
+    };
+    let timestamp = |name: &str| -> Option<Option<chrono::DateTime<Utc>>> {
+        match field(name) {
+            Some(v) => chrono::DateTime::parse_from_rfc3339(&v).ok().map(|t| Some(t.with_timezone(&Utc))),
+            None => Some(None),
+        }
+    };
+
+    let nonce = field("Nonce")?;
+    if domain.is_empty() || !is_valid_address(&address) || nonce.is_empty() {
+        return None;
+    }
+    Some(SiweMessage {
+        domain,
+        address,
+        uri: field("URI")?,
+        chain_id: field("Chain ID")?.parse().ok()?,
+        nonce,
+        issued_at: timestamp("Issued At")??,
+        expiration_time: timestamp("Expiration Time")?,
+        not_before: timestamp("Not Before")?,
+    })
+}
+
+/// Check that a sign-in message was made for this server (`expected_domain` is
+/// the configured LOGIN_SIWE_DOMAIN), on a supported chain, and is within its
+/// validity window
+fn check_siwe_fields(siwe: &SiweMessage, expected_domain: &str) -> Result<(), SignInError> {
+    let unauthorized = |e: &str| Err(SignInError::Unauthorized(e.to_string()));
+    let expected_domain = expected_domain.to_lowercase();
+    if siwe.domain != expected_domain {
+        return unauthorized("Sign-in message is for another domain");
+    }
+    let uri_authority = url::Url::parse(&siwe.uri).ok().and_then(|uri| {
+        let host = uri.host_str()?.to_lowercase();
+        Some(match uri.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host,
+        })
+    });
+    if uri_authority.as_deref() != Some(expected_domain.as_str()) {
+        return unauthorized("Sign-in message URI does not match the domain");
+    }
+    if !SIWE_CHAIN_IDS.contains(&siwe.chain_id) {
+        return unauthorized("Unsupported chain ID");
+    }
+
+    let now = Utc::now();
+    let skew = chrono::Duration::seconds(SIWE_CLOCK_SKEW_SECS);
+    if siwe.issued_at > now + skew || siwe.issued_at < now - chrono::Duration::minutes(NONCE_TTL_MINUTES) - skew {
+        return unauthorized("Sign-in message issue time is out of range");
+    }
+    if siwe.expiration_time.is_some_and(|t| t <= now) {
+        return unauthorized("Sign-in message has expired");
+    }
+    if siwe.not_before.is_some_and(|t| t > now + skew) {
+        return unauthorized("Sign-in message is not valid yet");
+    }
+    Ok(())
+}
+
+/// Verify a signed sign-in message and create a session for its address.
+/// The message must be bound to the configured LOGIN_SIWE_DOMAIN; the request's
+/// Host and X-Forwarded-Host headers are client-controlled and never trusted.
+fn verify_sign_in(
+    db: &Database,
+    config: &Config,
+    message: &str,
+    signature: &str,
+) -> Result<Session, SignInError> {
+    let expected_domain = config
+        .login_siwe_domain
+        .as_deref()
+        .ok_or(SignInError::DomainNotConfigured)?;
+    let siwe = parse_siwe_message(message)
+        .ok_or_else(|| SignInError::BadRequest("Malformed sign-in message".to_string()))?;
+    let public_address = siwe.address.clone();
+
+    // Consume the nonce before any other check so a message can only be tried once
+    match db.consume_auth_nonce(&siwe.nonce, chrono::Duration::minutes(NONCE_TTL_MINUTES)) {
+        Ok(true) => {}
+        Ok(false) => return Err(SignInError::Unauthorized("Unknown or expired nonce".to_string())),
+        Err(e) => {
+            log::error!("Failed to consume auth nonce: {}", e);
+            return Err(SignInError::Internal("Database error".to_string()));
+        }
+    }
+
+    check_siwe_fields(&siwe, expected_domain)?;
+
+    if recover_address(message, signature).as_deref() != Some(public_address.as_str()) {
+        return Err(SignInError::Unauthorized("Invalid signature".to_string()));
+    }
+
+    check_address_allowed(db, config, &public_address)?;
+
+    db.create_session_for_address(Some(&public_address)).map_err(|e| {
+        log::error!("Failed to create session: {}", e);
+        SignInError::Internal("Failed to create session".to_string())
+    })
+}
+
 fn recover_address(msg: &str, signature: &str) -> Option<String> {
     let sig_bytes = hex::decode(signature.strip_prefix("0x").unwrap_or(signature)).ok()?;
     let sig = Signature::try_from(sig_bytes.as_slice()).ok()?;
@@ -139,27 +380,9 @@ async fn validate_auth(
         });
     }
 
-    // Check that login is configured
-    let admin_address = match &state.config.login_admin_public_address {
-        Some(addr) => addr.to_lowercase(),
-        None => {
-            return HttpResponse::ServiceUnavailable().json(LoginResponse {
-                success: false,
-                token: None,
-                expires_at: None,
-                error: Some("Login not configured. Set LOGIN_ADMIN_PUBLIC_ADDRESS or BURNER_WALLET_BOT_PRIVATE_KEY environment variable.".to_string()),
-            });
-        }
-    };
-
-    // Check that this address is the admin address
-    if public_address != admin_address {
-        return HttpResponse::Unauthorized().json(LoginResponse {
-            success: false,
-            token: None,
-            expires_at: None,
-            error: Some("Unauthorized wallet address".to_string()),
-        });
+    // Check that this address is allowed to sign in
+    if let Err(e) = check_address_allowed(&state.db, &state.config, &public_address) {
+        return e.into_response();
     }
 
     // Verify the challenge exists and matches
@@ -218,6 +441,151 @@ async fn validate_auth(
     }
 }
 
+async fn issue_nonce(state: web::Data<AppState>, req: HttpRequest) -> impl Responder {
+    // The socket address, not X-Forwarded-For: a header the caller controls
+    // would let them pick a fresh bucket for every request.
+    let client_ip = req.peer_addr().map(|addr| addr.ip().to_string()).unwrap_or_default();
+    match state.db.create_auth_nonce(
+        &client_ip,
+        chrono::Duration::minutes(NONCE_TTL_MINUTES),
+        MAX_NONCES_PER_CLIENT,
+    ) {
+        Ok(Some(nonce)) => HttpResponse::Ok().json(NonceResponse {
+            success: true,
+            nonce: Some(nonce),
+            error: None,
+        }),
+        Ok(None) => {
+            log::warn!("Refusing to issue a sign-in nonce: {} already holds {}", client_ip, MAX_NONCES_PER_CLIENT);
+            HttpResponse::TooManyRequests().json(NonceResponse {
+                success: false,
+                nonce: None,
+                error: Some("Too many pending sign-ins, try again later".to_string()),
+            })
+        }
+        Err(e) => {
+            log::error!("Failed to create auth nonce: {}", e);
+            HttpResponse::InternalServerError().json(NonceResponse {
+                success: false,
+                nonce: None,
+                error: Some("Database error".to_string()),
+            })
+        }
+    }
+}
+
+async fn verify(state: web::Data<AppState>, body: web::Json<VerifyRequest>) -> impl Responder {
+    match verify_sign_in(&state.db, &state.config, &body.message, &body.signature) {
+        Ok(session) => HttpResponse::Ok().json(LoginResponse {
+            success: true,
+            token: Some(session.token),
+            expires_at: Some(session.expires_at.timestamp()),
+            error: None,
+        }),
+        Err(e) => e.into_response(),
+    }
+}
+
+fn bearer_token(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .map(|s| s.trim_start_matches("Bearer ").to_string())
+}
+
+fn require_session(db: &Database, req: &HttpRequest) -> Result<(), HttpResponse> {
+    match bearer_token(req).map(|t| db.validate_session(&t)) {
+        Some(Ok(Some(_))) => Ok(()),
+        Some(Err(e)) => {
+            log::error!("Session validation error: {}", e);
+            Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            })))
+        }
+        _ => Err(HttpResponse::Unauthorized().json(serde_json::json!({
+            "error": "Invalid or expired session"
+        }))),
+    }
+}
+
+/// Require a session signed in with the admin wallet (LOGIN_ADMIN_PUBLIC_ADDRESS).
+/// For endpoints that change access or expose every user's conversations.
+pub(crate) fn require_admin(db: &Database, config: &Config, req: &HttpRequest) -> Result<(), HttpResponse> {
+    require_session(db, req)?;
+    let admin = config.login_admin_public_address.as_deref().map(|a| a.to_lowercase());
+    let address = bearer_token(req)
+        .and_then(|t| db.get_session_address(&t).ok().flatten())
+        .map(|a| a.to_lowercase());
+    match (admin, address) {
+        (Some(admin), Some(address)) if admin == address => Ok(()),
+        _ => Err(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Only the admin wallet can do this"
+        }))),
+    }
+}
+
+async fn list_allowlist(state: web::Data<AppState>, req: HttpRequest) -> impl Responder {
+    if let Err(resp) = require_session(&state.db, &req) {
+        return resp;
+    }
+    match state.db.list_auth_allowlist() {
+        Ok(entries) => {
+            let entries: Vec<_> = entries
+                .into_iter()
+                .map(|(address, label)| serde_json::json!({ "public_address": address, "label": label }))
+                .collect();
+            HttpResponse::Ok().json(serde_json::json!({
+                "allowlist": entries,
+                "config_addresses": state.config.login_allowed_addresses,
+            }))
+        }
+        Err(e) => {
+            log::error!("Failed to list auth allowlist: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({ "error": "Database error" }))
+        }
+    }
+}
+
+async fn add_to_allowlist(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    body: web::Json<AllowlistRequest>,
+) -> impl Responder {
+    if let Err(resp) = require_admin(&state.db, &state.config, &req) {
+        return resp;
+    }
+    let public_address = body.public_address.trim().to_lowercase();
+    if !is_valid_address(&public_address) {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": "Invalid public address" }));
+    }
+    match state.db.add_auth_allowlist_address(&public_address, body.label.as_deref()) {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({ "success": true })),
+        Err(e) => {
+            log::error!("Failed to add allowlist address: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({ "error": "Database error" }))
+        }
+    }
+}
+
+async fn remove_from_allowlist(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> impl Responder {
+    if let Err(resp) = require_admin(&state.db, &state.config, &req) {
+        return resp;
+    }
+    let public_address = path.into_inner().trim().to_lowercase();
+    match state.db.remove_auth_allowlist_address(&public_address) {
+        Ok(true) => HttpResponse::Ok().json(serde_json::json!({ "success": true })),
+        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({ "error": "Address is not on the allowlist" })),
+        Err(e) => {
+            log::error!("Failed to remove allowlist address: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({ "error": "Database error" }))
+        }
+    }
+}
+
 async fn logout(state: web::Data<AppState>, body: web::Json<LogoutRequest>) -> impl Responder {
     match state.db.delete_session(&body.token) {
         Ok(_) => HttpResponse::Ok().json(LogoutResponse { success: true }),
@@ -350,3 +718,215 @@ async fn flash_login(
         .append_header(("Location", redirect_url))
         .finish()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::signers::{LocalWallet, Signer};
+
+    fn test_config(admin: Option<&str>) -> Config {
+        Config {
+            login_admin_public_address: admin.map(|a| a.to_string()),
+            login_allowed_addresses: Vec::new(),
+            login_siwe_domain: Some(DOMAIN.to_string()),
+            burner_wallet_private_key: None,
+            port: 0,
+            database_url: ":memory:".to_string(),
+        }
+    }
+
+    const DOMAIN: &str = "localhost:8080";
+
+    fn siwe_message(address: &str, nonce: &str) -> String {
+        siwe_message_with(address, nonce, DOMAIN, 1, "")
+    }
+
+    fn siwe_message_with(address: &str, nonce: &str, domain: &str, chain_id: u64, extra: &str) -> String {
+        format!(
+            "{} wants you to sign in with your Ethereum account:\n{}\n\nSign in to StarkBot\n\n\
+             URI: http://{}/auth\nVersion: 1\nChain ID: {}\nNonce: {}\nIssued At: {}{}",
+            domain,
+            address,
+            domain,
+            chain_id,
+            nonce,
+            Utc::now().to_rfc3339(),
+            extra
+        )
+    }
+
+    fn issue_nonce(db: &Database) -> String {
+        db.create_auth_nonce("127.0.0.1", chrono::Duration::minutes(NONCE_TTL_MINUTES), MAX_NONCES_PER_CLIENT)
+            .unwrap()
+            .unwrap()
+    }
+
+    fn sign(wallet: &LocalWallet, message: &str) -> String {
+        let sig = wallet.sign_hash(hash_message(message)).unwrap();
+        format!("0x{}", sig)
+    }
+
+    #[test]
+    fn test_verify_allowlisted_signature_grants_session() {
+        let db = Database::new(":memory:").unwrap();
+        let wallet: LocalWallet =
+            "0000000000000000000000000000000000000000000000000000000000000001".parse().unwrap();
+        let address = format!("{:?}", wallet.address());
+        db.add_auth_allowlist_address(&address, Some("ops")).unwrap();
+
+        let nonce = issue_nonce(&db);
+        let message = siwe_message(&address, &nonce);
+        let session = verify_sign_in(&db, &test_config(None), &message, &sign(&wallet, &message))
+            .expect("allowlisted wallet should sign in");
+        assert!(db.validate_session(&session.token).unwrap().is_some());
+
+        // The nonce is single-use
+        let replay = verify_sign_in(&db, &test_config(None), &message, &sign(&wallet, &message));
+        assert!(matches!(replay, Err(SignInError::Unauthorized(_))));
+    }
+
+    #[test]
+    fn test_verify_rejects_unauthorized_address() {
+        let db = Database::new(":memory:").unwrap();
+        let admin: LocalWallet =
+            "0000000000000000000000000000000000000000000000000000000000000001".parse().unwrap();
+        let stranger: LocalWallet =
+            "0000000000000000000000000000000000000000000000000000000000000002".parse().unwrap();
+        let config = test_config(Some(&format!("{:?}", admin.address())));
+
+        let nonce = issue_nonce(&db);
+        let message = siwe_message(&format!("{:?}", stranger.address()), &nonce);
+        let result = verify_sign_in(&db, &config, &message, &sign(&stranger, &message));
+        assert_eq!(
+            result.err(),
+            Some(SignInError::Unauthorized("Unauthorized wallet address".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_verify_checks_domain_chain_and_validity_window() {
+        let db = Database::new(":memory:").unwrap();
+        let wallet: LocalWallet =
+            "0000000000000000000000000000000000000000000000000000000000000001".parse().unwrap();
+        let address = format!("{:?}", wallet.address());
+        let config = test_config(Some(&address));
+        let attempt = |message: String| {
+            verify_sign_in(&db, &config, &message, &sign(&wallet, &message)).err()
+        };
+        let rejected = |e: &str| Some(SignInError::Unauthorized(e.to_string()));
+
+        let phishing = siwe_message_with(&address, &issue_nonce(&db), "evil.example", 1, "");
+        assert_eq!(attempt(phishing), rejected("Sign-in message is for another domain"));
+
+        let wrong_uri = siwe_message(&address, &issue_nonce(&db)).replace("URI: http://localhost:8080", "URI: https://evil.example");
+        assert_eq!(attempt(wrong_uri), rejected("Sign-in message URI does not match the domain"));
+
+        let other_chain = siwe_message_with(&address, &issue_nonce(&db), DOMAIN, 137, "");
+        assert_eq!(attempt(other_chain), rejected("Unsupported chain ID"));
+
+        let expired = siwe_message_with(&address, &issue_nonce(&db), DOMAIN, 1, "\nExpiration Time: 2020-01-01T00:00:00Z");
+        assert_eq!(attempt(expired), rejected("Sign-in message has expired"));
+
+        let base = siwe_message_with(&address, &issue_nonce(&db), DOMAIN, crate::x402::BASE_CHAIN_ID, "");
+        assert_eq!(attempt(base), None);
+    }
+
+    #[test]
+    fn test_verify_ignores_spoofed_forwarded_host() {
+        let db = Database::new(":memory:").unwrap();
+        let wallet: LocalWallet =
+            "0000000000000000000000000000000000000000000000000000000000000001".parse().unwrap();
+        let address = format!("{:?}", wallet.address());
+        let config = test_config(Some(&address));
+
+        // A signature phished on evil.example, replayed with a matching X-Forwarded-Host
+        let req = actix_web::test::TestRequest::default()
+            .insert_header(("X-Forwarded-Host", "evil.example"))
+            .to_http_request();
+        assert_eq!(req.connection_info().host(), "evil.example");
+        let phished = siwe_message_with(&address, &issue_nonce(&db), "evil.example", 1, "");
+        let result = verify_sign_in(&db, &config, &phished, &sign(&wallet, &phished));
+        assert_eq!(
+            result.err(),
+            Some(SignInError::Unauthorized("Sign-in message is for another domain".to_string()))
+        );
+
+        // Without a configured domain, sign-in is refused outright
+        let unconfigured = Config { login_siwe_domain: None, ..test_config(Some(&address)) };
+        let message = siwe_message(&address, &issue_nonce(&db));
+        let result = verify_sign_in(&db, &unconfigured, &message, &sign(&wallet, &message));
+        assert_eq!(result.err(), Some(SignInError::DomainNotConfigured));
+    }
+
+    #[test]
+    fn test_outstanding_nonces_are_capped_per_client() {
+        let db = Database::new(":memory:").unwrap();
+        let ttl = chrono::Duration::minutes(NONCE_TTL_MINUTES);
+        let mut issued = Vec::new();
+        for _ in 0..MAX_NONCES_PER_CLIENT {
+            issued.push(db.create_auth_nonce("10.0.0.1", ttl, MAX_NONCES_PER_CLIENT).unwrap().unwrap());
+        }
+        assert!(db.create_auth_nonce("10.0.0.1", ttl, MAX_NONCES_PER_CLIENT).unwrap().is_none());
+        // Other clients are unaffected
+        assert!(db.create_auth_nonce("10.0.0.2", ttl, MAX_NONCES_PER_CLIENT).unwrap().is_some());
+
+        // Using a nonce frees its slot
+        assert!(db.consume_auth_nonce(&issued[0], ttl).unwrap());
+        assert!(db.create_auth_nonce("10.0.0.1", ttl, MAX_NONCES_PER_CLIENT).unwrap().is_some());
+        assert!(db.create_auth_nonce("10.0.0.1", ttl, MAX_NONCES_PER_CLIENT).unwrap().is_none());
+
+        // Once the outstanding nonces age past the TTL they stop counting
+        let stale = (Utc::now() - ttl - chrono::Duration::minutes(1)).to_rfc3339();
+        db.conn()
+            .execute("UPDATE auth_nonces SET created_at = ?1 WHERE client_ip = '10.0.0.1'", [&stale])
+            .unwrap();
+        assert!(db.create_auth_nonce("10.0.0.1", ttl, MAX_NONCES_PER_CLIENT).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_nonce_is_consumed_once_and_not_after_expiry() {
+        let db = Database::new(":memory:").unwrap();
+        let ttl = chrono::Duration::minutes(NONCE_TTL_MINUTES);
+        let nonce = db.create_auth_nonce("10.0.0.1", ttl, MAX_NONCES_PER_CLIENT).unwrap().unwrap();
+        assert!(db.consume_auth_nonce(&nonce, ttl).unwrap());
+        assert!(!db.consume_auth_nonce(&nonce, ttl).unwrap());
+
+        let stale = db.create_auth_nonce("10.0.0.1", ttl, MAX_NONCES_PER_CLIENT).unwrap().unwrap();
+        let issued_at = (Utc::now() - ttl - chrono::Duration::minutes(1)).to_rfc3339();
+        db.conn()
+            .execute("UPDATE auth_nonces SET created_at = ?1 WHERE nonce = ?2", [&issued_at, &stale])
+            .unwrap();
+        assert!(!db.consume_auth_nonce(&stale, ttl).unwrap());
+    }
+
+    #[test]
+    fn test_require_admin_rejects_other_sessions() {
+        let db = Database::new(":memory:").unwrap();
+        let config = test_config(Some("0xAdmin"));
+        let request = |token: Option<&str>| {
+            let mut req = actix_web::test::TestRequest::default();
+            if let Some(token) = token {
+                req = req.insert_header(("Authorization", format!("Bearer {}", token)));
+            }
+            req.to_http_request()
+        };
+        let status = |result: Result<(), HttpResponse>| result.err().map(|resp| resp.status().as_u16());
+
+        assert_eq!(status(require_admin(&db, &config, &request(None))), Some(401));
+
+        let member = db.create_session_for_address(Some("0xmember")).unwrap();
+        assert_eq!(status(require_admin(&db, &config, &request(Some(&member.token)))), Some(403));
+
+        let admin = db.create_session_for_address(Some("0xadmin")).unwrap();
+        assert_eq!(status(require_admin(&db, &config, &request(Some(&admin.token)))), None);
+    }
+
+    #[test]
+    fn test_session_address_is_recorded() {
+        let db = Database::new(":memory:").unwrap();
+        let session = db.create_session_for_address(Some("0xabc")).unwrap();
+        assert_eq!(db.get_session_address(&session.token).unwrap().as_deref(), Some("0xabc"));
+        let anonymous = db.create_session().unwrap();
+        assert_eq!(db.get_session_address(&anonymous.token).unwrap(), None);
+    }
+}
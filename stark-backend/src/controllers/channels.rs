@@ -1,6 +1,7 @@
 use actix_web::{web, HttpRequest, HttpResponse, Responder};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
+use crate::controllers::auth::require_admin;
 use crate::models::{
     get_settings_for_channel_type, ChannelResponse, ChannelSettingsResponse,
     ChannelSettingsSchemaResponse, ChannelType, CreateChannelRequest, CreateSafeModeChannelRequest,
@@ -54,7 +55,11 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route("/{id}/start", web::post().to(start_channel))
             .route("/{id}/stop", web::post().to(stop_channel))
             .route("/{id}/settings", web::get().to(get_channel_settings))
-            .route("/{id}/settings", web::put().to(update_channel_settings)),
+            .route("/{id}/settings", web::put().to(update_channel_settings))
+            .route("/{id}/resource-version", web::get().to(get_resource_version_pin))
+            .route("/{id}/resource-version", web::put().to(pin_resource_version))
+            .route("/{id}/summarize", web::post().to(summarize_channel))
+            .route("/{id}/webhook", web::post().to(receive_webhook)),
     );
 }
 
@@ -183,7 +188,7 @@ async fn create_channel(
         return HttpResponse::BadRequest().json(ChannelOperationResponse {
             success: false,
             channel: None,
-            error: Some("Invalid channel type. Valid options: telegram, slack, discord, twitter, external_channel".to_string()),
+            error: Some("Invalid channel type. Valid options: telegram, slack, discord, twitter, external_channel, webhook".to_string()),
         });
     }
 
@@ -402,6 +407,7 @@ async fn update_channel(
         app_token_update,
     ) {
         Ok(Some(channel)) => {
+            state.dispatcher.wallet_registry().remove_channel_wallet(channel.id);
             let channel_manager = state.gateway.channel_manager();
             let running = channel_manager.is_running(channel.id);
             let response = ChannelResponse::from(channel).with_running(running);
@@ -448,6 +454,7 @@ async fn delete_channel(
     match state.db.delete_channel(id) {
         Ok(deleted) => {
             if deleted {
+                state.dispatcher.wallet_registry().remove_channel_wallet(id);
                 HttpResponse::Ok().json(ChannelOperationResponse {
                     success: true,
                     channel: None,
@@ -700,6 +707,9 @@ async fn update_channel_settings(
 
     match state.db.update_channel_settings(id, &settings_tuples) {
         Ok(()) => {
+            // The channel's wallet is loaded lazily from `wallet_key_env`; drop
+            // the cached one so the next dispatch picks up the new setting
+            state.dispatcher.wallet_registry().remove_channel_wallet(id);
             // Return updated settings
             match state.db.get_channel_settings(id) {
                 Ok(settings) => HttpResponse::Ok().json(ChannelSettingsResponse {
@@ -724,3 +734,301 @@ async fn update_channel_settings(
         }
     }
 }
+
+/// Request body for pinning a channel to a resource (prompt) version
+#[derive(Deserialize)]
+pub struct PinResourceVersionRequest {
+    /// Version to pin; `null` clears the pin so the global active version is used
+    pub version_id: Option<String>,
+}
+
+fn resource_version_pin_json(state: &web::Data<AppState>, channel_id: i64) -> serde_json::Value {
+    serde_json::json!({
+        "success": true,
+        "pinned_version_id": state.resource_manager.pinned_version_id(channel_id),
+        "effective_version_id": state.resource_manager.effective_version_id(channel_id),
+    })
+}
+
+/// Get the resource version a channel is pinned to, and the one it effectively uses
+async fn get_resource_version_pin(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<i64>,
+) -> impl Responder {
+    if let Err(resp) = validate_session_from_request(&state, &req) {
+        return resp;
+    }
+
+    let id = path.into_inner();
+    match state.db.get_channel(id) {
+        Ok(Some(_)) => HttpResponse::Ok().json(resource_version_pin_json(&state, id)),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "Channel not found"
+        })),
+        Err(e) => {
+            log::error!("Failed to get channel: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": "Failed to retrieve channel"
+            }))
+        }
+    }
+}
+
+/// Pin a channel to a resource version (or clear the pin)
+async fn pin_resource_version(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<i64>,
+    body: web::Json<PinResourceVersionRequest>,
+) -> impl Responder {
+    if let Err(resp) = validate_session_from_request(&state, &req) {
+        return resp;
+    }
+
+    let id = path.into_inner();
+    match state.db.get_channel(id) {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "Channel not found"
+            }));
+        }
+        Err(e) => {
+            log::error!("Failed to get channel: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": "Failed to retrieve channel"
+            }));
+        }
+    }
+
+    let version_id = body.version_id.as_deref().map(str::trim).filter(|v| !v.is_empty());
+    match state.resource_manager.pin_channel(id, version_id) {
+        Ok(()) => {
+            log::info!("Channel {} resource version pin set to {:?}", id, version_id);
+            HttpResponse::Ok().json(resource_version_pin_json(&state, id))
+        }
+        Err(e) => HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": e
+        })),
+    }
+}
+
+/// Request body for a channel digest
+#[derive(Deserialize)]
+pub struct SummarizeChannelRequest {
+    /// RFC 3339 start of the period to summarize (default: the last 24 hours)
+    pub since: Option<String>,
+}
+
+/// Summarize recent activity in a channel across its sessions
+async fn summarize_channel(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<i64>,
+    body: Option<web::Json<SummarizeChannelRequest>>,
+) -> impl Responder {
+    // A digest reads every user's sessions in the channel
+    if let Err(resp) = require_admin(&state.db, &state.config, &req) {
+        return resp;
+    }
+
+    let id = path.into_inner();
+    match state.db.get_channel(id) {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "Channel not found"
+            }));
+        }
+        Err(e) => {
+            log::error!("Failed to get channel: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": "Failed to retrieve channel"
+            }));
+        }
+    }
+
+    let since = match body.and_then(|b| b.into_inner().since) {
+        Some(since) => match chrono::DateTime::parse_from_rfc3339(&since) {
+            Ok(dt) => dt.with_timezone(&chrono::Utc),
+            Err(e) => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "success": false,
+                    "error": format!("Invalid 'since' timestamp: {}", e)
+                }));
+            }
+        },
+        None => chrono::Utc::now() - chrono::Duration::hours(24),
+    };
+
+    let client = match state.db.get_active_agent_settings() {
+        Ok(Some(settings)) => match crate::ai::AiClient::from_settings(&settings) {
+            Ok(client) => client,
+            Err(e) => {
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to create AI client: {}", e)
+                }));
+            }
+        },
+        _ => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": "No AI provider configured"
+            }));
+        }
+    };
+
+    match crate::channels::digest::summarize_channel(&state.db, &client, id, since).await {
+        Ok(digest) => HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "digest": digest
+        })),
+        Err(e) => {
+            log::error!("Failed to summarize channel {}: {}", id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": e
+            }))
+        }
+    }
+}
+
+/// Inbound message for a webhook channel
+#[derive(Deserialize)]
+pub struct WebhookInboundRequest {
+    pub text: String,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub user_id: Option<String>,
+    /// Conversation key; messages with the same chat_id share a session
+    #[serde(default)]
+    pub chat_id: Option<String>,
+}
+
+/// POST /api/channels/{id}/webhook — authenticated by the channel's HMAC
+/// signing secret rather than a web session. Each signed request is accepted
+/// once. The agent's messages are delivered to the channel's webhook URL as
+/// they are sent.
+async fn receive_webhook(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<i64>,
+    body: web::Bytes,
+) -> impl Responder {
+    use crate::channels::webhook::{self, WebhookChannel};
+
+    let id = path.into_inner();
+    match state.db.get_channel(id) {
+        Ok(Some(channel)) if channel.channel_type == webhook::CHANNEL_TYPE => {}
+        Ok(_) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "Webhook channel not found"
+            }));
+        }
+        Err(e) => {
+            log::error!("Failed to get channel: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": "Failed to retrieve channel"
+            }));
+        }
+    }
+
+    let secret = match state.db.get_channel_setting(id, "webhook_secret").ok().flatten() {
+        Some(secret) if !secret.is_empty() => secret,
+        _ => {
+            return HttpResponse::Forbidden().json(serde_json::json!({
+                "success": false,
+                "error": "Webhook channel has no signing secret configured"
+            }));
+        }
+    };
+    let header = |name: &str| req.headers().get(name).and_then(|h| h.to_str().ok());
+    let now = chrono::Utc::now().timestamp();
+    if !webhook::verify_signature(&secret, &body, header(webhook::SIGNATURE_HEADER), header(webhook::TIMESTAMP_HEADER), now) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Invalid, stale or missing signature"
+        }));
+    }
+    if !webhook::check_replay(header(webhook::SIGNATURE_HEADER), header(webhook::TIMESTAMP_HEADER), now) {
+        return HttpResponse::Conflict().json(serde_json::json!({
+            "success": false,
+            "error": "Signature already used"
+        }));
+    }
+
+    if !state.gateway.channel_manager().is_running(id) {
+        return HttpResponse::Forbidden().json(serde_json::json!({
+            "success": false,
+            "error": "Webhook channel is not running"
+        }));
+    }
+
+    let request: WebhookInboundRequest = match serde_json::from_slice(&body) {
+        Ok(r) => r,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": format!("Invalid request body: {}", e)
+            }));
+        }
+    };
+    if request.text.trim().is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "text cannot be empty"
+        }));
+    }
+
+    let outbound = match WebhookChannel::from_settings(&state.db, id) {
+        Ok(w) => w,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": e
+            }));
+        }
+    };
+
+    let user_id = request.user_id.unwrap_or_else(|| "webhook-user".to_string());
+    let message = crate::channels::NormalizedMessage {
+        channel_id: id,
+        channel_type: webhook::CHANNEL_TYPE.to_string(),
+        chat_id: request.chat_id.unwrap_or_else(|| user_id.clone()),
+        chat_name: None,
+        is_thread: false,
+        user_name: request.user.unwrap_or_else(|| user_id.clone()),
+        user_id,
+        text: request.text,
+        attachments: Vec::new(),
+        message_id: None,
+        session_mode: None,
+        selected_network: None,
+        force_safe_mode: false,
+        agent_settings_override: None,
+        ephemeral: false,
+        dry_run: false,
+    };
+
+    tokio::spawn(webhook::handle_inbound(
+        state.db.clone(),
+        state.dispatcher.clone(),
+        state.broadcaster.clone(),
+        outbound,
+        message,
+    ));
+
+    HttpResponse::Accepted().json(serde_json::json!({ "success": true }))
+}
@@ -1,8 +1,10 @@
 use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 
+use crate::text::truncate_chars;
 use crate::channels::NormalizedMessage;
 use crate::models::SessionScope;
+use crate::tools::rpc_config::Network;
 use crate::AppState;
 
 /// Web channel ID - a reserved ID for web-based chat
@@ -198,6 +200,19 @@ async fn chat(
         }
     };
 
+    // Parse the selected network once here so everything downstream is typed
+    let selected_network = match body.network.as_deref().map(str::parse::<Network>).transpose() {
+        Ok(network) => network,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(ChatResponse {
+                success: false,
+                message: None,
+                error: Some(e),
+                session_id: None,
+            });
+        }
+    };
+
     // Generate a user ID for the web session
     // Use the provided user_id, or derive from the session token
     let user_id = body.user_id.clone()
@@ -210,13 +225,18 @@ async fn chat(
         channel_type: WEB_CHANNEL_TYPE.to_string(),
         chat_id: user_id.clone(),  // For web, chat_id == user_id (always DM-like)
         chat_name: None,
+        is_thread: false,
         user_id: user_id.clone(),
         user_name: format!("web-user-{}", &user_id[..8.min(user_id.len())]),
         text: user_message,
+        attachments: Vec::new(),
         message_id: None,
         session_mode: None,
-        selected_network: body.network.clone(),
+        selected_network,
         force_safe_mode: false,
+        agent_settings_override: None,
+        ephemeral: false,
+        dry_run: false,
     };
 
     // Dispatch through the unified pipeline
@@ -410,11 +430,7 @@ async fn list_subagents(
                 .map(|ctx| SubagentInfo {
                     id: ctx.id,
                     label: ctx.label,
-                    task: if ctx.task.len() > 100 {
-                        format!("{}...", &ctx.task[..97])
-                    } else {
-                        ctx.task
-                    },
+                    task: truncate_chars(&ctx.task, 100),
                     status: format!("{:?}", ctx.status),
                     started_at: ctx.started_at.to_rfc3339(),
                     session_id: ctx.session_id,
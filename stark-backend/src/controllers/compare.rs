@@ -0,0 +1,132 @@
+//! Config comparison endpoint: replay one message under two agent configs.
+
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+
+use crate::ai::ArchetypeId;
+use crate::models::{AgentSettings, AgentSettingsOverride};
+use crate::AppState;
+
+/// Validate session token from request
+fn validate_session(
+    state: &web::Data<AppState>,
+    req: &HttpRequest,
+) -> Result<(), HttpResponse> {
+    let token = req
+        .headers()
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .map(|s| s.trim_start_matches("Bearer ").to_string());
+
+    let token = match token {
+        Some(t) => t,
+        None => {
+            return Err(HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "No authorization token provided"
+            })));
+        }
+    };
+
+    match state.db.validate_session(&token) {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(HttpResponse::Unauthorized().json(serde_json::json!({
+            "error": "Invalid or expired session"
+        }))),
+        Err(e) => {
+            log::error!("Session validation error: {}", e);
+            Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            })))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompareRequest {
+    pub message: String,
+    /// Overrides for the first run, layered over the active agent settings
+    #[serde(default)]
+    pub a: AgentSettingsOverride,
+    /// Overrides for the second run, layered over the active agent settings
+    #[serde(default)]
+    pub b: AgentSettingsOverride,
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/api/compare").route(web::post().to(compare)));
+}
+
+/// Build the settings for one run. A saved config's secret key is reused when
+/// the run targets a saved endpoint without supplying its own key.
+fn resolve_settings(
+    state: &web::Data<AppState>,
+    base: &AgentSettings,
+    overrides: &AgentSettingsOverride,
+) -> Result<AgentSettings, String> {
+    if let Some(ref archetype) = overrides.model_archetype {
+        if ArchetypeId::from_str(archetype).is_none() {
+            return Err(format!("Invalid archetype: {}", archetype));
+        }
+    }
+    let mut settings = overrides.apply(base);
+    if settings.endpoint.is_empty() {
+        return Err("Endpoint URL is required".to_string());
+    }
+    if settings.secret_key.is_none() {
+        if let Ok(Some(saved)) = state.db.get_agent_settings_by_endpoint(&settings.endpoint) {
+            settings.secret_key = saved.secret_key;
+        }
+    }
+    Ok(settings)
+}
+
+/// POST /api/compare — run a message through two configs in throwaway sessions
+async fn compare(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    body: web::Json<CompareRequest>,
+) -> impl Responder {
+    if let Err(resp) = validate_session(&state, &req) {
+        return resp;
+    }
+    let request = body.into_inner();
+    if request.message.trim().is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Message is required"
+        }));
+    }
+
+    let base = match state.db.get_active_agent_settings() {
+        Ok(settings) => settings.unwrap_or_default(),
+        Err(e) => {
+            log::error!("Failed to get agent settings: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            }));
+        }
+    };
+
+    let (settings_a, settings_b) = match (
+        resolve_settings(&state, &base, &request.a),
+        resolve_settings(&state, &base, &request.b),
+    ) {
+        (Ok(a), Ok(b)) => (a, b),
+        (Err(e), _) | (_, Err(e)) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({ "error": e }));
+        }
+    };
+
+    log::info!(
+        "[COMPARE] Comparing {} ({:?}) vs {} ({:?})",
+        settings_a.endpoint, settings_a.model, settings_b.endpoint, settings_b.model
+    );
+
+    let run_a = state.dispatcher.run_comparison("a", &request.message, settings_a).await;
+    let run_b = state.dispatcher.run_comparison("b", &request.message, settings_b).await;
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "a": run_a,
+        "b": run_b,
+    }))
+}
@@ -2,7 +2,7 @@ use actix_web::{web, HttpRequest, HttpResponse};
 use std::sync::Arc;
 
 use crate::models::{
-    CreateCronJobRequest, CronJobResponse, HeartbeatConfigResponse,
+    CreateCronJobRequest, CronJob, CronJobResponse, HeartbeatConfigResponse,
     UpdateCronJobRequest, UpdateHeartbeatConfigRequest,
 };
 use crate::scheduler::Scheduler;
@@ -160,7 +160,9 @@ async fn create_job(
         body.thinking_level.as_deref(),
         body.timeout_seconds,
         body.delete_after_run,
-    ) {
+    )
+    .and_then(|job| apply_retry_policy(&state, job, body.max_retries, body.retry_delay_seconds))
+    {
         Ok(job) => HttpResponse::Created().json(CronJobResponse {
             success: true,
             job: Some(job),
@@ -176,6 +178,19 @@ async fn create_job(
     }
 }
 
+/// Store the auto-retry fields of a create/update request, if any were given
+fn apply_retry_policy(
+    state: &AppState,
+    job: CronJob,
+    max_retries: Option<i32>,
+    retry_delay_seconds: Option<i32>,
+) -> rusqlite::Result<CronJob> {
+    if max_retries.is_none() && retry_delay_seconds.is_none() {
+        return Ok(job);
+    }
+    state.db.set_cron_job_retry_policy(job.id, max_retries, retry_delay_seconds)
+}
+
 /// Get a cron job by ID
 async fn get_job(state: web::Data<AppState>, req: HttpRequest, path: web::Path<i64>) -> HttpResponse {
     if let Err(resp) = validate_session_from_request(&state, &req) {
@@ -256,7 +271,9 @@ async fn update_job(
         body.timeout_seconds,
         body.delete_after_run,
         body.status.as_deref(),
-    ) {
+    )
+    .and_then(|job| apply_retry_policy(&state, job, body.max_retries, body.retry_delay_seconds))
+    {
         Ok(job) => HttpResponse::Ok().json(CronJobResponse {
             success: true,
             job: Some(job),
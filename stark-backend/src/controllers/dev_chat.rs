@@ -66,13 +66,18 @@ async fn dev_chat(
         channel_type: DEV_CHANNEL_TYPE.to_string(),
         chat_id: "dev-test".to_string(),
         chat_name: None,
+        is_thread: false,
         user_id: "dev-user".to_string(),
         user_name: "dev-user".to_string(),
         text: body.message.clone(),
+        attachments: Vec::new(),
         message_id: None,
         session_mode: None,
         selected_network: None,
         force_safe_mode: false,
+        agent_settings_override: None,
+        ephemeral: false,
+        dry_run: false,
     };
 
     let result = state.dispatcher.dispatch_safe(normalized).await;
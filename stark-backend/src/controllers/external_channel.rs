@@ -315,13 +315,18 @@ async fn gateway_chat(
         channel_type: CHANNEL_TYPE.to_string(),
         chat_id: chat_id.clone(),
         chat_name: None,
+        is_thread: false,
         user_id: "gateway-user".to_string(),
         user_name,
         text: body.message.clone(),
+        attachments: Vec::new(),
         message_id: None,
         session_mode: None,
         selected_network: None,
         force_safe_mode: safe_mode,
+        agent_settings_override: None,
+        ephemeral: false,
+        dry_run: false,
     };
 
     let result = state.dispatcher.dispatch_safe(normalized).await;
@@ -413,13 +418,18 @@ async fn gateway_chat_stream(
             channel_type: CHANNEL_TYPE.to_string(),
             chat_id,
             chat_name: None,
+            is_thread: false,
             user_id: "gateway-user".to_string(),
             user_name,
             text: msg_text,
+            attachments: Vec::new(),
             message_id: None,
             session_mode: None,
             selected_network: None,
             force_safe_mode: safe_mode,
+            agent_settings_override: None,
+            ephemeral: false,
+            dry_run: false,
         };
         let _ = dispatcher.dispatch_safe(normalized).await;
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
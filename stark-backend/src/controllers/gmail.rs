@@ -6,6 +6,7 @@ use actix_web::{web, HttpRequest, HttpResponse};
 use base64::Engine;
 use std::sync::Arc;
 
+use crate::text::truncate_chars;
 use crate::channels::types::{DispatchResult, NormalizedMessage};
 use crate::channels::MessageDispatcher;
 use crate::db::Database;
@@ -213,8 +214,8 @@ async fn dispatch_email(
         {}",
         email.from,
         email.subject,
-        if email.body.len() > 4000 {
-            format!("{}...\n\n[Body truncated]", &email.body[..4000])
+        if email.body.chars().count() > 4000 {
+            format!("{}\n\n[Body truncated]", truncate_chars(&email.body, 4000))
         } else {
             email.body.clone()
         }
@@ -226,13 +227,18 @@ async fn dispatch_email(
         channel_type: "gmail".to_string(),
         chat_id: email.thread_id.clone(),
         chat_name: None,
+        is_thread: false,
         user_id: email.from.clone(),
         user_name: extract_name_from_email(&email.from),
         text: message_content,
+        attachments: Vec::new(),
         message_id: Some(email.message_id.clone()),
         session_mode: None,
         selected_network: None,
         force_safe_mode: false,
+        agent_settings_override: None,
+        ephemeral: false,
+        dry_run: false,
     };
 
     // Broadcast event
@@ -11,10 +11,11 @@ pub fn config_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(web::resource("/api/health/config").route(web::get().to(get_config_status)));
 }
 
-async fn health_check() -> impl Responder {
+async fn health_check(state: web::Data<AppState>) -> impl Responder {
     HttpResponse::Ok().json(serde_json::json!({
         "status": "ok",
-        "version": VERSION
+        "version": VERSION,
+        "rollouts": state.dispatcher.rollout_manager().concurrency()
     }))
 }
 
@@ -34,7 +35,7 @@ async fn get_config_status(state: web::Data<AppState>) -> impl Responder {
     let guest_dashboard = state.db.get_bot_settings().map(|s| s.guest_dashboard_enabled).unwrap_or(false);
 
     HttpResponse::Ok().json(serde_json::json!({
-        "login_configured": state.config.login_admin_public_address.is_some(),
+        "login_configured": state.config.login_admin_public_address.is_some() || !state.config.login_allowed_addresses.is_empty(),
         "burner_wallet_configured": crate::config::burner_wallet_private_key().is_some(),
         "wallet_configured": state.wallet_provider.is_some(),
         "guest_dashboard_enabled": guest_dashboard,
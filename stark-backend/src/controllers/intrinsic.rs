@@ -260,6 +260,8 @@ async fn write_intrinsic(
     }
 
     log::info!("Updated intrinsic file: {}", intrinsic.name);
+    // The soul and guidelines are part of the cached system prompt
+    data.dispatcher.invalidate_system_prompt_cache();
 
     HttpResponse::Ok().json(WriteIntrinsicResponse {
         success: true,
@@ -322,6 +324,7 @@ async fn delete_intrinsic(
     }
 
     log::info!("Deleted intrinsic file: {}", name);
+    data.dispatcher.invalidate_system_prompt_cache();
 
     HttpResponse::Ok().json(WriteIntrinsicResponse {
         success: true,
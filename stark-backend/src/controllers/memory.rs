@@ -1,12 +1,14 @@
 //! Memory controller - REST API for QMD markdown-based memory system
 //!
-//! Provides endpoints for browsing, searching, and viewing memory files.
+//! Provides endpoints for browsing, searching, and viewing memory files, and
+//! for moving memories (markdown files plus structured rows) between deployments.
 
 use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
-use crate::qmd_memory::file_ops;
+use crate::db::tables::memories::Memory;
+use crate::qmd_memory::{file_ops, MemoryFileExport};
 use crate::AppState;
 
 /// Validate session token from request
@@ -138,6 +140,45 @@ struct AppendResponse {
     error: Option<String>,
 }
 
+/// Current memory bundle format version
+const MEMORY_BUNDLE_VERSION: u32 = 1;
+
+/// Everything needed to move memories between deployments: the markdown
+/// files and the structured memory rows
+#[derive(Debug, Serialize, Deserialize)]
+struct MemoryBundle {
+    version: u32,
+    exported_at: String,
+    /// Identity the bundle was exported for (None = all memories)
+    identity_id: Option<String>,
+    files: Vec<MemoryFileExport>,
+    memories: Vec<Memory>,
+}
+
+#[derive(Debug, Serialize)]
+struct ImportResponse {
+    success: bool,
+    files_written: usize,
+    files_skipped: usize,
+    memories_inserted: usize,
+    memories_skipped: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl ImportResponse {
+    fn error(message: String) -> Self {
+        Self {
+            success: false,
+            files_written: 0,
+            files_skipped: 0,
+            memories_inserted: 0,
+            memories_skipped: 0,
+            error: Some(message),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct MemoryInfoResponse {
     success: bool,
@@ -176,6 +217,11 @@ struct LongTermQuery {
     identity_id: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct ExportQuery {
+    identity_id: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct AppendBody {
     content: String,
@@ -745,6 +791,139 @@ async fn reindex(data: web::Data<AppState>, req: HttpRequest) -> impl Responder
     }
 }
 
+/// GET /api/memory/export - Export memory files and structured memories as one bundle
+async fn export_memories(
+    data: web::Data<AppState>,
+    req: HttpRequest,
+    query: web::Query<ExportQuery>,
+) -> impl Responder {
+    if let Err(resp) = validate_session_from_request(&data, &req) {
+        return resp;
+    }
+
+    let identity_id = query.identity_id.as_deref();
+    let files = match data.dispatcher.memory_store() {
+        Some(store) => match store.export_files(identity_id) {
+            Ok(files) => files,
+            Err(e) => {
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": format!("Failed to read memory files: {}", e)
+                }));
+            }
+        },
+        None => Vec::new(),
+    };
+    let memories = match data.db.export_memories(identity_id) {
+        Ok(memories) => memories,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to read memories: {}", e)
+            }));
+        }
+    };
+
+    HttpResponse::Ok().json(MemoryBundle {
+        version: MEMORY_BUNDLE_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        identity_id: query.identity_id.clone(),
+        files,
+        memories,
+    })
+}
+
+/// Check a bundle before anything is written
+fn validate_bundle(bundle: &MemoryBundle) -> Result<(), String> {
+    if bundle.version > MEMORY_BUNDLE_VERSION {
+        return Err(format!("Unsupported bundle version {}", bundle.version));
+    }
+    for (i, memory) in bundle.memories.iter().enumerate() {
+        if memory.memory_type.trim().is_empty() || memory.content.trim().is_empty() {
+            return Err(format!("Memory {} has an empty type or content", i));
+        }
+        for ts in [&memory.created_at, &memory.updated_at] {
+            if chrono::DateTime::parse_from_rfc3339(ts).is_err() {
+                return Err(format!("Memory {} has an invalid timestamp: {}", i, ts));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// POST /api/memory/import - Import a bundle from /api/memory/export, skipping duplicates
+async fn import_memories(
+    data: web::Data<AppState>,
+    req: HttpRequest,
+    body: web::Json<MemoryBundle>,
+) -> impl Responder {
+    if let Err(resp) = validate_session_from_request(&data, &req) {
+        return resp;
+    }
+
+    let bundle = body.into_inner();
+    if let Err(e) = validate_bundle(&bundle) {
+        return HttpResponse::BadRequest().json(ImportResponse::error(e));
+    }
+
+    // Reject the bundle before changing anything; the rows then go in one
+    // transaction, and only after it commits are the files written
+    let store = if bundle.files.is_empty() {
+        None
+    } else {
+        let Some(store) = data.dispatcher.memory_store() else {
+            return HttpResponse::ServiceUnavailable()
+                .json(ImportResponse::error("Memory system not initialized".to_string()));
+        };
+        match store.check_import_files(&bundle.files) {
+            Ok(()) => Some(store),
+            Err(e) if e.kind() == std::io::ErrorKind::InvalidInput => {
+                return HttpResponse::BadRequest().json(ImportResponse::error(e.to_string()));
+            }
+            Err(e) => {
+                return HttpResponse::InternalServerError()
+                    .json(ImportResponse::error(format!("Failed to write memory files: {}", e)));
+            }
+        }
+    };
+
+    let (memories_inserted, memories_skipped) = match data.db.import_memories(&bundle.memories) {
+        Ok(counts) => counts,
+        Err(e) => {
+            return HttpResponse::InternalServerError()
+                .json(ImportResponse::error(format!("Failed to import memories: {}", e)));
+        }
+    };
+
+    let (files_written, files_skipped) = match store.map(|store| store.import_files(&bundle.files)) {
+        None => (0, 0),
+        Some(Ok(counts)) => counts,
+        Some(Err(e)) => {
+            // The rows are committed: report the partial import
+            log::error!("[MEMORY] Imported {} memories but failed to write memory files: {}", memories_inserted, e);
+            return HttpResponse::InternalServerError().json(ImportResponse {
+                memories_inserted,
+                memories_skipped,
+                ..ImportResponse::error(format!(
+                    "Imported {} memories, but failed to write memory files: {}",
+                    memories_inserted, e
+                ))
+            });
+        }
+    };
+
+    log::info!(
+        "[MEMORY] Imported bundle: {} files written ({} skipped), {} memories inserted ({} skipped)",
+        files_written, files_skipped, memories_inserted, memories_skipped
+    );
+    HttpResponse::Ok().json(ImportResponse {
+        success: true,
+        files_written,
+        files_skipped,
+        memories_inserted,
+        memories_skipped,
+        error: None,
+    })
+}
+
 /// GET /api/memory/info - Get memory system info
 async fn memory_info(data: web::Data<AppState>, req: HttpRequest) -> impl Responder {
     if let Err(resp) = validate_session_from_request(&data, &req) {
@@ -783,6 +962,78 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route("/long-term", web::post().to(append_long_term))
             .route("/stats", web::get().to(get_stats))
             .route("/reindex", web::post().to(reindex))
+            .route("/export", web::get().to(export_memories))
+            .route("/import", web::post().to(import_memories))
             .route("/info", web::get().to(memory_info)),
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    fn memory(content: &str) -> Memory {
+        Memory {
+            memory_type: "preference".to_string(),
+            content: content.to_string(),
+            category: Some("style".to_string()),
+            tags: None,
+            importance: 8,
+            identity_id: Some("alice".to_string()),
+            source_channel_type: None,
+            log_date: None,
+            entity_type: None,
+            entity_name: None,
+            confidence: Some(0.9),
+            source_type: None,
+            is_public: false,
+            valid_from: None,
+            valid_until: None,
+            temporal_type: None,
+            expires_at: None,
+            created_at: "2026-01-02T03:04:05+00:00".to_string(),
+            updated_at: "2026-02-03T04:05:06+00:00".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_memory_import_round_trip_preserves_fields_and_skips_duplicates() {
+        let source = Database::new(":memory:").unwrap();
+        let rows = vec![memory("Prefers short answers"), memory("Uses dark mode")];
+        assert_eq!(source.import_memories(&rows).unwrap(), (2, 0));
+
+        let exported = source.export_memories(Some("alice")).unwrap();
+        assert_eq!(exported.len(), 2);
+        assert!(source.export_memories(Some("bob")).unwrap().is_empty());
+
+        let target = Database::new(":memory:").unwrap();
+        assert_eq!(target.import_memories(&exported).unwrap(), (2, 0));
+        let mut imported = target.export_memories(None).unwrap();
+        imported.sort_by(|a, b| a.content.cmp(&b.content));
+        assert_eq!(imported, rows);
+
+        // Re-importing the same bundle (or whitespace variants) adds nothing
+        let mut again = exported.clone();
+        again[0].content = format!("  {}  ", again[0].content);
+        assert_eq!(target.import_memories(&again).unwrap(), (0, 2));
+    }
+
+    #[test]
+    fn test_validate_bundle_rejects_bad_rows() {
+        let mut bundle = MemoryBundle {
+            version: MEMORY_BUNDLE_VERSION,
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            identity_id: None,
+            files: Vec::new(),
+            memories: vec![memory("ok")],
+        };
+        assert!(validate_bundle(&bundle).is_ok());
+
+        bundle.memories[0].created_at = "yesterday".to_string();
+        assert!(validate_bundle(&bundle).is_err());
+
+        bundle.memories[0] = memory("   ");
+        assert!(validate_bundle(&bundle).is_err());
+    }
+}
@@ -5,6 +5,7 @@ pub mod auth;
 pub mod broadcasted_transactions;
 pub mod channels;
 pub mod chat;
+pub mod compare;
 pub mod cron;
 pub mod dashboard;
 pub mod dev_chat;
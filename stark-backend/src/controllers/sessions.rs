@@ -1,6 +1,7 @@
 use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use serde::Deserialize;
 
+use crate::text::truncate_chars;
 use crate::models::{
     ChatSessionResponse, CompletionStatus, GetOrCreateSessionRequest, SessionScope,
     SessionTranscriptResponse, UpdateResetPolicyRequest,
@@ -65,11 +66,7 @@ async fn list_sessions(
                     if is_web {
                         if let Ok(Some(first_msg)) = data.db.get_first_user_message(session_id) {
                             // Truncate to 100 chars for the list view
-                            response.initial_query = Some(if first_msg.len() > 100 {
-                                format!("{}...", &first_msg[..100])
-                            } else {
-                                first_msg
-                            });
+                            response.initial_query = Some(truncate_chars(&first_msg, 100));
                         }
                     }
                     response
@@ -138,6 +135,9 @@ async fn get_session(
             if let Ok(count) = data.db.count_session_messages(response.id) {
                 response.message_count = Some(count);
             }
+            if response.completion_status == CompletionStatus::Failed {
+                response.failure_reason = data.db.get_session_failure_reason(response.id).ok().flatten();
+            }
             HttpResponse::Ok().json(response)
         }
         Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
@@ -476,6 +476,68 @@ async fn resume_session(
     }
 }
 
+#[derive(Deserialize)]
+struct InjectMessageRequest {
+    text: String,
+}
+
+/// Inject a message into a session as the assistant (operator handoff).
+/// Delivered to the session's channel without invoking the AI.
+async fn inject_message(
+    data: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<i64>,
+    body: web::Json<InjectMessageRequest>,
+) -> impl Responder {
+    if let Err(resp) = validate_session_from_request(&data, &req) {
+        return resp;
+    }
+    let session_id = path.into_inner();
+
+    let session = match data.db.get_chat_session(session_id) {
+        Ok(Some(s)) => s,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Session not found"
+            }));
+        }
+        Err(e) => {
+            log::error!("Failed to get session for inject: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            }));
+        }
+    };
+
+    if body.text.trim().is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Message text is required"
+        }));
+    }
+
+    match crate::channels::inject::inject_assistant_message(&data.db, &data.broadcaster, &session, &body.text).await {
+        Ok(injected) => match injected.delivery_error {
+            None => HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "message": injected.message
+            })),
+            Some(e) => {
+                log::error!("Message injected into session {} was only partly delivered: {}", session_id, e);
+                HttpResponse::BadGateway().json(serde_json::json!({
+                    "error": format!("Only part of the message was delivered: {}", e),
+                    "message": injected.message
+                }))
+            }
+        },
+        Err(e) => {
+            log::error!("Failed to inject message into session {}: {}", session_id, e);
+            HttpResponse::BadGateway().json(serde_json::json!({
+                "error": e
+            }))
+        }
+    }
+}
+
 /// Get session transcript (message history)
 #[derive(Deserialize)]
 struct TranscriptQuery {
@@ -517,6 +579,77 @@ async fn get_transcript(
     }
 }
 
+/// Pin a message so compaction keeps it verbatim
+async fn pin_message(
+    data: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<(i64, i64)>,
+) -> impl Responder {
+    set_message_pinned(data, req, path.into_inner(), true)
+}
+
+/// Unpin a message so it can be compacted again
+async fn unpin_message(
+    data: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<(i64, i64)>,
+) -> impl Responder {
+    set_message_pinned(data, req, path.into_inner(), false)
+}
+
+fn set_message_pinned(
+    data: web::Data<AppState>,
+    req: HttpRequest,
+    (session_id, message_id): (i64, i64),
+    pinned: bool,
+) -> HttpResponse {
+    if let Err(resp) = validate_session_from_request(&data, &req) {
+        return resp;
+    }
+
+    match data.db.set_message_pinned(session_id, message_id, pinned) {
+        Ok(true) => HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "message_id": message_id,
+            "pinned": pinned
+        })),
+        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Message not found in session"
+        })),
+        Err(e) => {
+            log::error!("Failed to update pin on message {} in session {}: {}", message_id, session_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            }))
+        }
+    }
+}
+
+/// Replay a session's persisted telemetry as a rollout → attempt → span tree
+async fn get_trace(
+    data: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<i64>,
+) -> impl Responder {
+    if let Err(resp) = validate_session_from_request(&data, &req) {
+        return resp;
+    }
+    let session_id = path.into_inner();
+
+    match data.db.get_chat_session(session_id) {
+        Ok(Some(_)) => HttpResponse::Ok().json(data.telemetry_store.get_session_trace(session_id)),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Session not found"
+        })),
+        Err(e) => {
+            log::error!("Failed to get session trace: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            }))
+        }
+    }
+}
+
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api/sessions")
@@ -529,6 +662,10 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route("/{id}/stop", web::post().to(stop_session))
             .route("/{id}/resume", web::post().to(resume_session))
             .route("/{id}/policy", web::put().to(update_reset_policy))
-            .route("/{id}/transcript", web::get().to(get_transcript)),
+            .route("/{id}/inject", web::post().to(inject_message))
+            .route("/{id}/transcript", web::get().to(get_transcript))
+            .route("/{id}/messages/{message_id}/pin", web::post().to(pin_message))
+            .route("/{id}/messages/{message_id}/pin", web::delete().to(unpin_message))
+            .route("/{id}/trace", web::get().to(get_trace)),
     );
 }
@@ -490,6 +490,7 @@ async fn update_skill(
         tags: existing.metadata.tags.clone(),
         subagent_type: existing.metadata.subagent_type.clone(),
         requires_api_keys: existing.metadata.requires_api_keys.clone(),
+        model_override: existing.metadata.model_override.clone(),
         created_at: now.clone(),
         updated_at: now,
     };
@@ -163,6 +163,7 @@ async fn create_role(
 
     match data.db.upsert_special_role(&role) {
         Ok(_) => {
+            data.dispatcher.invalidate_system_prompt_cache();
             // Re-fetch to get timestamps
             match data.db.get_special_role(&role.name) {
                 Ok(Some(created)) => HttpResponse::Created().json(created),
@@ -253,6 +254,7 @@ async fn update_role(
 
     match data.db.upsert_special_role(&updated) {
         Ok(_) => {
+            data.dispatcher.invalidate_system_prompt_cache();
             match data.db.get_special_role(&updated.name) {
                 Ok(Some(refreshed)) => HttpResponse::Ok().json(refreshed),
                 _ => HttpResponse::Ok().json(updated),
@@ -278,10 +280,13 @@ async fn delete_role(
 
     let name = path.into_inner();
     match data.db.delete_special_role(&name) {
-        Ok(true) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "message": format!("Special role '{}' deleted", name)
-        })),
+        Ok(true) => {
+            data.dispatcher.invalidate_system_prompt_cache();
+            HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "message": format!("Special role '{}' deleted", name)
+            }))
+        }
         Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
             "error": format!("Special role '{}' not found", name)
         })),
@@ -360,11 +360,14 @@ async fn update_global_config(
     }
 
     match state.db.save_tool_config(&config) {
-        Ok(_) => HttpResponse::Ok().json(ConfigResponse {
-            success: true,
-            config: Some(config.into()),
-            error: None,
-        }),
+        Ok(_) => {
+            state.dispatcher.invalidate_system_prompt_cache();
+            HttpResponse::Ok().json(ConfigResponse {
+                success: true,
+                config: Some(config.into()),
+                error: None,
+            })
+        }
         Err(e) => {
             log::error!("Failed to save tool config: {}", e);
             HttpResponse::InternalServerError().json(ConfigResponse {
@@ -454,11 +457,14 @@ async fn update_channel_config(
     }
 
     match state.db.save_tool_config(&config) {
-        Ok(_) => HttpResponse::Ok().json(ConfigResponse {
-            success: true,
-            config: Some(config.into()),
-            error: None,
-        }),
+        Ok(_) => {
+            state.dispatcher.invalidate_system_prompt_cache();
+            HttpResponse::Ok().json(ConfigResponse {
+                success: true,
+                config: Some(config.into()),
+                error: None,
+            })
+        }
         Err(e) => {
             log::error!("Failed to save channel tool config: {}", e);
             HttpResponse::InternalServerError().json(ConfigResponse {
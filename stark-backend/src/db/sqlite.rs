@@ -119,6 +119,32 @@ impl Database {
             [],
         )?;
 
+        // Auth nonces for sign-in-with-Ethereum (issued before the address is known)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS auth_nonces (
+                nonce TEXT PRIMARY KEY,
+                client_ip TEXT NOT NULL DEFAULT '',
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // Migration: Add client_ip column to auth_nonces if it doesn't exist
+        let _ = conn.execute(
+            "ALTER TABLE auth_nonces ADD COLUMN client_ip TEXT NOT NULL DEFAULT ''",
+            [],
+        );
+
+        // Wallet addresses allowed to sign in (in addition to the configured admin)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS auth_allowlist (
+                public_address TEXT PRIMARY KEY,
+                label TEXT,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
         // External API keys table
         conn.execute(
             "CREATE TABLE IF NOT EXISTS external_api_keys (
@@ -270,6 +296,13 @@ impl Database {
             conn.execute("ALTER TABLE agent_settings ADD COLUMN model TEXT", [])?;
         }
 
+        // Migration: Add sampling parameters (NULL = provider default)
+        let _ = conn.execute("ALTER TABLE agent_settings ADD COLUMN temperature REAL", []);
+        let _ = conn.execute("ALTER TABLE agent_settings ADD COLUMN top_p REAL", []);
+
+        // Migration: Add ordered fallback model chain (JSON array, NULL = none)
+        let _ = conn.execute("ALTER TABLE agent_settings ADD COLUMN fallback_models TEXT", []);
+
         // Migration: Add web3_tx_requires_confirmation column to bot_settings if it doesn't exist
         let has_web3_tx_confirmation: bool = conn
             .query_row(
@@ -439,6 +472,20 @@ impl Database {
             conn.execute("ALTER TABLE bot_settings ADD COLUMN kanban_auto_execute INTEGER NOT NULL DEFAULT 1", [])?;
         }
 
+        // Migration: Add loop detection thresholds to bot_settings (NULL = built-in defaults)
+        let _ = conn.execute("ALTER TABLE bot_settings ADD COLUMN loop_max_repeated_calls INTEGER", []);
+        let _ = conn.execute("ALTER TABLE bot_settings ADD COLUMN loop_signature_history INTEGER", []);
+
+        // Migration: Add tool timeout overrides (JSON) to bot_settings (NULL = built-in timeouts)
+        let _ = conn.execute("ALTER TABLE bot_settings ADD COLUMN tool_timeouts TEXT", []);
+
+        // Migration: Add per-identity message rate limit to bot_settings (NULL = built-in defaults)
+        let _ = conn.execute("ALTER TABLE bot_settings ADD COLUMN rate_limit_per_minute INTEGER", []);
+        let _ = conn.execute("ALTER TABLE bot_settings ADD COLUMN rate_limit_burst INTEGER", []);
+
+        // Migration: Add per-session x402 spend budget to bot_settings (NULL = no budget)
+        let _ = conn.execute("ALTER TABLE bot_settings ADD COLUMN max_session_usdc REAL", []);
+
         // Initialize bot_settings with defaults if empty
         let bot_settings_count: i64 = conn
             .query_row("SELECT COUNT(*) FROM bot_settings", [], |row| row.get(0))
@@ -494,6 +541,11 @@ impl Database {
         let _ = conn.execute("ALTER TABLE chat_sessions ADD COLUMN safe_mode INTEGER NOT NULL DEFAULT 0", []);
         // Special role: Track which special role (if any) enriched this safe-mode session
         let _ = conn.execute("ALTER TABLE chat_sessions ADD COLUMN special_role_name TEXT", []);
+        // Failure reason: category + message recorded when a session transitions to Failed
+        let _ = conn.execute("ALTER TABLE chat_sessions ADD COLUMN failure_reason TEXT", []);
+        // The unique session_key already allows one session per chat; drop the
+        // redundant per-chat index earlier versions created
+        conn.execute("DROP INDEX IF EXISTS idx_chat_sessions_active_chat", [])?;
 
         // Session messages table - conversation transcripts
         conn.execute(
@@ -511,6 +563,8 @@ impl Database {
             )",
             [],
         )?;
+        // Migration: pinned messages are kept verbatim by compaction
+        let _ = conn.execute("ALTER TABLE session_messages ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0", []);
 
         // Telegram chat messages - passive log of ALL messages in Telegram chats
         // Independent of session system, used by telegram_read readHistory
@@ -597,6 +651,8 @@ impl Database {
         let _ = conn.execute("ALTER TABLE memories ADD COLUMN valid_from TEXT", []);
         let _ = conn.execute("ALTER TABLE memories ADD COLUMN valid_until TEXT", []);
         let _ = conn.execute("ALTER TABLE memories ADD COLUMN temporal_type TEXT", []);
+        // Public profile: facts a user opted to share with other users (lookup_user)
+        let _ = conn.execute("ALTER TABLE memories ADD COLUMN is_public INTEGER NOT NULL DEFAULT 0", []);
 
         // FTS5 virtual table for full-text search on memories
         conn.execute(
@@ -728,6 +784,9 @@ impl Database {
         // Migration: Add subagent_type column to skills if it doesn't exist
         let _ = conn.execute("ALTER TABLE skills ADD COLUMN subagent_type TEXT", []);
 
+        // Migration: Add model_override column to skills (JSON, per-skill model/archetype override)
+        let _ = conn.execute("ALTER TABLE skills ADD COLUMN model_override TEXT", []);
+
         // Migration: Add requires_api_keys column to skills if it doesn't exist
         let _ = conn.execute("ALTER TABLE skills ADD COLUMN requires_api_keys TEXT NOT NULL DEFAULT '{}'", []);
 
@@ -824,6 +883,14 @@ impl Database {
             [],
         )?;
 
+        // Migration: Add auto-retry policy and retry state to cron jobs, and link retry runs
+        let _ = conn.execute("ALTER TABLE cron_jobs ADD COLUMN max_retries INTEGER", []);
+        let _ = conn.execute("ALTER TABLE cron_jobs ADD COLUMN retry_delay_seconds INTEGER", []);
+        let _ = conn.execute("ALTER TABLE cron_jobs ADD COLUMN retry_attempt INTEGER NOT NULL DEFAULT 0", []);
+        let _ = conn.execute("ALTER TABLE cron_jobs ADD COLUMN retry_of_run_id INTEGER", []);
+        let _ = conn.execute("ALTER TABLE cron_job_runs ADD COLUMN retry_of INTEGER", []);
+        let _ = conn.execute("ALTER TABLE cron_job_runs ADD COLUMN attempt INTEGER NOT NULL DEFAULT 0", []);
+
         // Heartbeat configuration table
         conn.execute(
             "CREATE TABLE IF NOT EXISTS heartbeat_configs (
@@ -1206,6 +1273,26 @@ impl Database {
             [],
         );
 
+        // Migration: Add plan approval columns to agent_contexts if they don't exist
+        let _ = conn.execute(
+            "ALTER TABLE agent_contexts ADD COLUMN plan_approval INTEGER NOT NULL DEFAULT 0",
+            [],
+        );
+        let _ = conn.execute(
+            "ALTER TABLE agent_contexts ADD COLUMN awaiting_plan_approval INTEGER NOT NULL DEFAULT 0",
+            [],
+        );
+        // Migration: Add loop_checkpoint column (set by mid-loop context checkpoints)
+        let _ = conn.execute(
+            "ALTER TABLE agent_contexts ADD COLUMN loop_checkpoint INTEGER NOT NULL DEFAULT 0",
+            [],
+        );
+        // Migration: Add waiting_for_user_context column (pending question for the next turn)
+        let _ = conn.execute(
+            "ALTER TABLE agent_contexts ADD COLUMN waiting_for_user_context TEXT",
+            [],
+        );
+
         // Broadcasted transactions table - persistent history of all crypto tx broadcasts
         conn.execute(
             "CREATE TABLE IF NOT EXISTS broadcasted_transactions (
@@ -1423,6 +1510,16 @@ impl Database {
             [],
         );
 
+        // Migration: add max_tasks column (cap on define_tasks queue size for this subtype)
+        let _ = conn.execute(
+            "ALTER TABLE agent_subtypes ADD COLUMN max_tasks INTEGER NOT NULL DEFAULT 20",
+            [],
+        );
+
+        // Migration: per-subtype sampling overrides (NULL = use agent settings)
+        let _ = conn.execute("ALTER TABLE agent_subtypes ADD COLUMN temperature REAL", []);
+        let _ = conn.execute("ALTER TABLE agent_subtypes ADD COLUMN top_p REAL", []);
+
         // Keystore state - track backup/retrieval status per wallet
         conn.execute(
             "CREATE TABLE IF NOT EXISTS keystore_state (
@@ -1555,6 +1652,12 @@ impl Database {
             [],
         )?;
 
+        // Tool history of a failed attempt, kept for replay when snapshotting is enabled
+        let _ = conn.execute("ALTER TABLE attempts ADD COLUMN tool_history TEXT", []);
+
+        // Model that served an attempt (differs from the primary when a fallback was engaged)
+        let _ = conn.execute("ALTER TABLE attempts ADD COLUMN model TEXT", []);
+
         // resource_versions - versioned prompts, model configs, tool configs
         conn.execute(
             "CREATE TABLE IF NOT EXISTS resource_versions (
@@ -1605,6 +1708,103 @@ impl Database {
             [],
         );
 
+        // Polls (create_poll tool) and the votes cast on them
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS polls (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                poll_id TEXT UNIQUE NOT NULL,
+                channel_id INTEGER NOT NULL,
+                channel_type TEXT NOT NULL,
+                chat_id TEXT NOT NULL,
+                session_id INTEGER,
+                question TEXT NOT NULL,
+                options TEXT NOT NULL DEFAULT '[]',
+                mode TEXT NOT NULL,
+                platform_message_id TEXT,
+                status TEXT NOT NULL DEFAULT 'open',
+                closes_at TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_polls_due ON polls(status, closes_at);
+
+            CREATE TABLE IF NOT EXISTS poll_votes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                poll_id TEXT NOT NULL,
+                voter_id TEXT NOT NULL,
+                voter_name TEXT,
+                option_index INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                UNIQUE(poll_id, voter_id)
+            );",
+        )?;
+
+        // Proactive messages held back during a channel's quiet hours
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS deferred_messages (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                channel_id INTEGER NOT NULL,
+                chat_id TEXT,
+                source TEXT NOT NULL,
+                text TEXT NOT NULL,
+                deliver_after TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_deferred_messages_due ON deferred_messages(deliver_after);",
+        )?;
+        // Migration: failed sends are retried a limited number of times
+        let _ = conn.execute("ALTER TABLE deferred_messages ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0", []);
+
+        // Messages the agent scheduled for later delivery (schedule_message tool)
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS scheduled_messages (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                channel_id INTEGER NOT NULL,
+                channel_type TEXT NOT NULL,
+                chat_id TEXT NOT NULL,
+                identity_id TEXT NOT NULL,
+                text TEXT NOT NULL,
+                send_at TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending',
+                error TEXT,
+                created_at TEXT NOT NULL,
+                sent_at TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_scheduled_messages_due ON scheduled_messages(status, send_at);
+            CREATE INDEX IF NOT EXISTS idx_scheduled_messages_identity ON scheduled_messages(identity_id, status);",
+        )?;
+
+        // Platform message ids already dispatched (ignores redeliveries)
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS processed_messages (
+                channel_type TEXT NOT NULL,
+                channel_id INTEGER NOT NULL,
+                chat_id TEXT NOT NULL,
+                message_id TEXT NOT NULL,
+                processed_at TEXT NOT NULL,
+                PRIMARY KEY (channel_type, channel_id, chat_id, message_id)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_processed_messages_at ON processed_messages(processed_at);",
+        )?;
+
+        // Context-bank items (addresses, token symbols) remembered per identity across sessions
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS context_bank_items (
+                identity_id TEXT NOT NULL,
+                item_type TEXT NOT NULL,
+                value TEXT NOT NULL,
+                label TEXT,
+                last_seen_at TEXT NOT NULL,
+                PRIMARY KEY (identity_id, item_type, value)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_context_bank_items_seen ON context_bank_items(identity_id, last_seen_at);",
+        )?;
+
         Ok(())
     }
 
@@ -1760,24 +1960,61 @@ impl Database {
     pub fn record_x402_payment(
         &self,
         channel_id: Option<i64>,
+        session_id: Option<i64>,
         tool_name: Option<&str>,
         resource: Option<&str>,
         amount: &str,
         amount_formatted: &str,
         asset: &str,
         pay_to: &str,
+        from_address: Option<&str>,
         tx_hash: Option<&str>,
         status: &str,
     ) -> Result<i64, rusqlite::Error> {
         let conn = self.conn();
         conn.execute(
-            "INSERT INTO x402_payments (channel_id, tool_name, resource, amount, amount_formatted, asset, pay_to, tx_hash, status)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-            rusqlite::params![channel_id, tool_name, resource, amount, amount_formatted, asset, pay_to, tx_hash, status],
+            "INSERT INTO x402_payments (channel_id, session_id, tool_name, resource, amount, amount_formatted, asset, pay_to, from_address, tx_hash, status)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            rusqlite::params![channel_id, session_id, tool_name, resource, amount, amount_formatted, asset, pay_to, from_address, tx_hash, status],
         )?;
         Ok(conn.last_insert_rowid())
     }
 
+    /// x402 spend recorded for a session: (total, largest single payment), in formatted units
+    pub fn x402_session_spend(&self, session_id: i64) -> Result<(f64, f64), rusqlite::Error> {
+        let conn = self.conn();
+        conn.query_row(
+            "SELECT COALESCE(SUM(CAST(amount_formatted AS REAL)), 0), COALESCE(MAX(CAST(amount_formatted AS REAL)), 0)
+             FROM x402_payments WHERE session_id = ?1 AND status != 'failed'",
+            rusqlite::params![session_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+    }
+
+    /// Highest x402 payment row ID (0 if none). Used to bracket the payments made during a run.
+    pub fn latest_x402_payment_id(&self) -> Result<i64, rusqlite::Error> {
+        let conn = self.conn();
+        conn.query_row("SELECT COALESCE(MAX(id), 0) FROM x402_payments", [], |row| row.get(0))
+    }
+
+    /// Total x402 spend per asset for a channel, counting payments recorded after `after_id`
+    pub fn sum_x402_payments_since(
+        &self,
+        channel_id: i64,
+        after_id: i64,
+    ) -> Result<Vec<(String, f64)>, rusqlite::Error> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT asset, SUM(CAST(amount_formatted AS REAL)) FROM x402_payments
+             WHERE channel_id = ?1 AND id > ?2
+             GROUP BY asset ORDER BY asset",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![channel_id, after_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<f64>>(1)?.unwrap_or(0.0)))
+        })?;
+        rows.collect()
+    }
+
     /// Update payment status and tx_hash
     pub fn update_x402_payment_status(
         &self,
@@ -15,7 +15,9 @@ impl Database {
 
         let mut stmt = conn.prepare(
             "SELECT original_request, mode, mode_iterations, total_iterations,
-                    exploration_notes, scratchpad, subtype, active_skill_json
+                    exploration_notes, scratchpad, subtype, active_skill_json,
+                    plan_approval, awaiting_plan_approval, tasks_json, loop_checkpoint,
+                    waiting_for_user_context
              FROM agent_contexts
              WHERE session_id = ?",
         )?;
@@ -29,6 +31,11 @@ impl Database {
             let scratchpad: String = row.get(5)?;
             let subtype_str: Option<String> = row.get(6).ok();
             let active_skill_json: Option<String> = row.get(7).ok().flatten();
+            let plan_approval: bool = row.get(8).unwrap_or(false);
+            let awaiting_plan_approval: bool = row.get(9).unwrap_or(false);
+            let tasks_json: Option<String> = row.get(10).ok().flatten();
+            let loop_checkpoint: bool = row.get(11).unwrap_or(false);
+            let waiting_for_user_context: Option<String> = row.get(12).ok().flatten();
 
             // Parse mode (defaults to Assistant)
             let mode = AgentMode::from_str(&mode_str).unwrap_or_default();
@@ -45,6 +52,16 @@ impl Database {
             let active_skill: Option<ActiveSkill> = active_skill_json
                 .and_then(|json| serde_json::from_str(&json).ok());
 
+            // A plan awaiting approval keeps its task queue until the user answers, and
+            // an unfinished loop's checkpoint keeps it so execution can resume
+            let task_queue: TaskQueue = if awaiting_plan_approval || loop_checkpoint {
+                tasks_json
+                    .and_then(|json| serde_json::from_str(&json).ok())
+                    .unwrap_or_default()
+            } else {
+                TaskQueue::default()
+            };
+
             Ok(AgentContext {
                 original_request,
                 exploration_notes,
@@ -54,12 +71,19 @@ impl Database {
                 total_iterations,
                 scratchpad,
                 active_skill,
+                cleared_skill: None,       // Set by the orchestrator on resume
                 actual_tool_calls: 0,      // Reset on load
                 no_tool_warnings: 0,       // Reset on load
-                waiting_for_user_context: None, // Reset on load
-                task_queue: TaskQueue::default(), // Reset on load
-                planner_completed: false,  // Reset on load
+                // Kept until the next turn's first prompt has consumed it
+                waiting_for_user_context,
+                // Reset on load unless a plan is pending or a checkpointed plan is resuming
+                planner_completed: awaiting_plan_approval || (loop_checkpoint && !task_queue.tasks.is_empty()),
+                task_queue,
                 selected_network: None,    // Reset on load
+                plan_approval,
+                awaiting_plan_approval,
+                untrusted_content_flagged: false, // Reset on load
+                loop_checkpoint,
             })
         });
 
@@ -84,6 +108,12 @@ impl Database {
             .unwrap_or_else(|_| "[]".to_string());
         let active_skill_json: Option<String> = context.active_skill.as_ref()
             .and_then(|s| serde_json::to_string(s).ok());
+        // The task queue is only carried over while a plan awaits approval or in a mid-loop checkpoint
+        let tasks_json = if context.awaiting_plan_approval || context.loop_checkpoint {
+            serde_json::to_string(&context.task_queue).unwrap_or_else(|_| "{\"tasks\":[]}".to_string())
+        } else {
+            "{\"tasks\":[]}".to_string()
+        };
 
         // Use INSERT OR REPLACE for upsert behavior
         // Note: Using simplified schema - old columns will be NULL/defaults
@@ -92,10 +122,12 @@ impl Database {
                 session_id, original_request, mode, mode_iterations, total_iterations,
                 exploration_notes, scratchpad, subtype, active_skill_json,
                 context_sufficient, plan_ready, findings, plan_summary, tasks_json,
-                created_at, updated_at
+                plan_approval, awaiting_plan_approval, loop_checkpoint,
+                waiting_for_user_context, created_at, updated_at
             ) VALUES (
                 ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9,
-                0, 0, '[]', NULL, '{\"tasks\":[]}',
+                0, 0, '[]', NULL, ?11,
+                ?12, ?13, ?14, ?15,
                 COALESCE((SELECT created_at FROM agent_contexts WHERE session_id = ?1), ?10),
                 ?10
             )",
@@ -110,6 +142,11 @@ impl Database {
                 context.subtype.as_deref().unwrap_or(""),
                 active_skill_json,
                 now,
+                tasks_json,
+                context.plan_approval,
+                context.awaiting_plan_approval,
+                context.loop_checkpoint,
+                context.waiting_for_user_context,
             ],
         )?;
 
@@ -3,7 +3,7 @@
 use chrono::{DateTime, Utc};
 use rusqlite::Result as SqliteResult;
 
-use crate::models::{AgentSettings, MIN_CONTEXT_TOKENS, DEFAULT_CONTEXT_TOKENS};
+use crate::models::{AgentSettings, FallbackModel, MIN_CONTEXT_TOKENS, DEFAULT_CONTEXT_TOKENS};
 use super::super::Database;
 
 impl Database {
@@ -16,7 +16,7 @@ impl Database {
         let conn = self.conn();
 
         let mut stmt = conn.prepare(
-            "SELECT id, endpoint, model_archetype, model, max_response_tokens, max_context_tokens, enabled, secret_key, created_at, updated_at
+            "SELECT id, endpoint, model_archetype, model, max_response_tokens, max_context_tokens, enabled, secret_key, created_at, updated_at, temperature, top_p, fallback_models
              FROM agent_settings WHERE enabled = 1 LIMIT 1",
         )?;
 
@@ -33,7 +33,7 @@ impl Database {
         let conn = self.conn();
 
         let mut stmt = conn.prepare(
-            "SELECT id, endpoint, model_archetype, model, max_response_tokens, max_context_tokens, enabled, secret_key, created_at, updated_at
+            "SELECT id, endpoint, model_archetype, model, max_response_tokens, max_context_tokens, enabled, secret_key, created_at, updated_at, temperature, top_p, fallback_models
              FROM agent_settings WHERE endpoint = ?1 AND (model = ?2 OR (?2 IS NULL AND model IS NULL))",
         )?;
 
@@ -49,7 +49,7 @@ impl Database {
         let conn = self.conn();
 
         let mut stmt = conn.prepare(
-            "SELECT id, endpoint, model_archetype, model, max_response_tokens, max_context_tokens, enabled, secret_key, created_at, updated_at
+            "SELECT id, endpoint, model_archetype, model, max_response_tokens, max_context_tokens, enabled, secret_key, created_at, updated_at, temperature, top_p, fallback_models
              FROM agent_settings WHERE endpoint = ?1",
         )?;
 
@@ -65,7 +65,7 @@ impl Database {
         let conn = self.conn();
 
         let mut stmt = conn.prepare(
-            "SELECT id, endpoint, model_archetype, model, max_response_tokens, max_context_tokens, enabled, secret_key, created_at, updated_at
+            "SELECT id, endpoint, model_archetype, model, max_response_tokens, max_context_tokens, enabled, secret_key, created_at, updated_at, temperature, top_p, fallback_models
              FROM agent_settings ORDER BY id",
         )?;
 
@@ -128,6 +128,46 @@ impl Database {
             .map(|opt| opt.unwrap())
     }
 
+    /// Set sampling parameters (temperature / top_p) for an agent settings row
+    pub fn update_agent_settings_sampling(
+        &self,
+        id: i64,
+        temperature: Option<f32>,
+        top_p: Option<f32>,
+    ) -> SqliteResult<()> {
+        let conn = self.conn();
+        let now = Utc::now().to_rfc3339();
+        conn.execute(
+            "UPDATE agent_settings SET temperature = ?1, top_p = ?2, updated_at = ?3 WHERE id = ?4",
+            rusqlite::params![temperature.map(|v| v as f64), top_p.map(|v| v as f64), &now, id],
+        )?;
+        drop(conn);
+        self.cache.invalidate_agent_settings();
+        Ok(())
+    }
+
+    /// Replace the fallback model chain for an agent settings row
+    pub fn update_agent_settings_fallbacks(
+        &self,
+        id: i64,
+        fallback_models: &[FallbackModel],
+    ) -> SqliteResult<()> {
+        let conn = self.conn();
+        let now = Utc::now().to_rfc3339();
+        let json = if fallback_models.is_empty() {
+            None
+        } else {
+            serde_json::to_string(fallback_models).ok()
+        };
+        conn.execute(
+            "UPDATE agent_settings SET fallback_models = ?1, updated_at = ?2 WHERE id = ?3",
+            rusqlite::params![json, &now, id],
+        )?;
+        drop(conn);
+        self.cache.invalidate_agent_settings();
+        Ok(())
+    }
+
     /// Disable all agent settings (no AI provider active)
     pub fn disable_agent_settings(&self) -> SqliteResult<()> {
         let conn = self.conn();
@@ -150,6 +190,12 @@ impl Database {
             max_context_tokens: row.get::<_, Option<i32>>(5)?.unwrap_or(DEFAULT_CONTEXT_TOKENS),
             enabled: row.get::<_, i32>(6)? != 0,
             secret_key: row.get(7)?,
+            temperature: row.get::<_, Option<f64>>(10)?.map(|v| v as f32),
+            top_p: row.get::<_, Option<f64>>(11)?.map(|v| v as f32),
+            fallback_models: row
+                .get::<_, Option<String>>(12)?
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default(),
             created_at: DateTime::parse_from_rfc3339(&created_at_str)
                 .unwrap()
                 .with_timezone(&Utc),
@@ -110,6 +110,20 @@ impl Database {
         Ok(rows_affected > 0)
     }
 
+    /// List configured API key service names (values are not read)
+    pub fn list_api_key_names(&self) -> SqliteResult<Vec<String>> {
+        let conn = self.conn();
+
+        let mut stmt = conn.prepare("SELECT service_name FROM external_api_keys ORDER BY service_name")?;
+
+        let names = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(names)
+    }
+
     /// List all API keys with their full values (for export/backup)
     pub fn list_api_keys_with_values(&self) -> SqliteResult<Vec<(String, String)>> {
         let conn = self.conn();
@@ -1,4 +1,4 @@
-//! Auth sessions and auth challenges database operations
+//! Auth sessions, challenges, nonces and allowlist database operations
 
 use chrono::{DateTime, Duration, Utc};
 use rusqlite::Result as SqliteResult;
@@ -88,6 +88,20 @@ impl Database {
         Ok(session)
     }
 
+    /// Wallet address a live session was signed in with (None for sessions
+    /// created without one, or unknown/expired tokens)
+    pub fn get_session_address(&self, token: &str) -> SqliteResult<Option<String>> {
+        let conn = self.conn();
+        let address: Option<Option<String>> = conn
+            .query_row(
+                "SELECT public_address FROM auth_sessions WHERE token = ?1 AND expires_at > ?2",
+                [token, &Utc::now().to_rfc3339()],
+                |row| row.get(0),
+            )
+            .ok();
+        Ok(address.flatten())
+    }
+
     pub fn delete_session(&self, token: &str) -> SqliteResult<bool> {
         let conn = self.conn();
         let rows_affected = conn.execute("DELETE FROM auth_sessions WHERE token = ?1", [token])?;
@@ -147,4 +161,92 @@ impl Database {
         )?;
         Ok(rows_affected > 0)
     }
+
+    // ============================================
+    // Auth Nonce methods (for sign-in-with-Ethereum)
+    // ============================================
+
+    /// Issue a single-use nonce for a sign-in message. Nonces older than `ttl`
+    /// are dropped first; returns None when `client_ip` already holds
+    /// `max_per_client` unused nonces, so one caller can't grow the table
+    /// without bound or starve everyone else out of signing in.
+    pub fn create_auth_nonce(&self, client_ip: &str, ttl: Duration, max_per_client: usize) -> SqliteResult<Option<String>> {
+        let conn = self.conn();
+        let now = Utc::now();
+
+        let cutoff = (now - ttl).to_rfc3339();
+        conn.execute("DELETE FROM auth_nonces WHERE created_at < ?1", [&cutoff])?;
+
+        let outstanding: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM auth_nonces WHERE client_ip = ?1",
+            [client_ip],
+            |row| row.get(0),
+        )?;
+        if outstanding as usize >= max_per_client {
+            return Ok(None);
+        }
+
+        let nonce = Self::generate_session_token();
+        conn.execute(
+            "INSERT INTO auth_nonces (nonce, client_ip, created_at) VALUES (?1, ?2, ?3)",
+            [&nonce, client_ip, &now.to_rfc3339()],
+        )?;
+        Ok(Some(nonce))
+    }
+
+    /// Consume a nonce. Returns true only if it existed and was issued within
+    /// `max_age`; the check and the delete are one statement, so two concurrent
+    /// sign-ins can't both redeem the same nonce.
+    pub fn consume_auth_nonce(&self, nonce: &str, max_age: Duration) -> SqliteResult<bool> {
+        let conn = self.conn();
+        let cutoff = (Utc::now() - max_age).to_rfc3339();
+        let rows_affected = conn.execute(
+            "DELETE FROM auth_nonces WHERE nonce = ?1 AND created_at >= ?2",
+            [nonce, &cutoff],
+        )?;
+        Ok(rows_affected == 1)
+    }
+
+    // ============================================
+    // Auth Allowlist methods
+    // ============================================
+
+    pub fn add_auth_allowlist_address(&self, public_address: &str, label: Option<&str>) -> SqliteResult<()> {
+        let conn = self.conn();
+        conn.execute(
+            "INSERT INTO auth_allowlist (public_address, label, created_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(public_address) DO UPDATE SET label = excluded.label",
+            rusqlite::params![public_address.to_lowercase(), label, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_auth_allowlist_address(&self, public_address: &str) -> SqliteResult<bool> {
+        let conn = self.conn();
+        let rows_affected = conn.execute(
+            "DELETE FROM auth_allowlist WHERE public_address = ?1",
+            [public_address.to_lowercase()],
+        )?;
+        Ok(rows_affected > 0)
+    }
+
+    /// List allowlisted addresses as (address, label)
+    pub fn list_auth_allowlist(&self) -> SqliteResult<Vec<(String, Option<String>)>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT public_address, label FROM auth_allowlist ORDER BY created_at",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    pub fn is_address_allowlisted(&self, public_address: &str) -> SqliteResult<bool> {
+        let conn = self.conn();
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM auth_allowlist WHERE public_address = ?1",
+            [public_address.to_lowercase()],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
 }
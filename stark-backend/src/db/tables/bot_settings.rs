@@ -4,7 +4,11 @@ use chrono::{DateTime, Utc};
 use rusqlite::Result as SqliteResult;
 use std::collections::HashMap;
 
-use crate::models::{BotSettings, DEFAULT_MAX_TOOL_ITERATIONS, DEFAULT_SAFE_MODE_MAX_QUERIES_PER_10MIN};
+use crate::models::{
+    BotSettings, ToolTimeoutSettings, DEFAULT_LOOP_MAX_REPEATED_CALLS, DEFAULT_LOOP_SIGNATURE_HISTORY,
+    DEFAULT_MAX_SESSION_USDC, DEFAULT_MAX_TOOL_ITERATIONS, DEFAULT_RATE_LIMIT_BURST, DEFAULT_RATE_LIMIT_PER_MINUTE,
+    DEFAULT_SAFE_MODE_MAX_QUERIES_PER_10MIN,
+};
 use super::super::Database;
 
 impl Database {
@@ -17,7 +21,7 @@ impl Database {
         let conn = self.conn();
 
         let result = conn.query_row(
-            "SELECT id, bot_name, bot_email, web3_tx_requires_confirmation, rpc_provider, custom_rpc_endpoints, max_tool_iterations, rogue_mode_enabled, safe_mode_max_queries_per_10min, keystore_url, chat_session_memory_generation, guest_dashboard_enabled, theme_accent, proxy_url, kanban_auto_execute, created_at, updated_at FROM bot_settings LIMIT 1",
+            "SELECT id, bot_name, bot_email, web3_tx_requires_confirmation, rpc_provider, custom_rpc_endpoints, max_tool_iterations, rogue_mode_enabled, safe_mode_max_queries_per_10min, keystore_url, chat_session_memory_generation, guest_dashboard_enabled, theme_accent, proxy_url, kanban_auto_execute, loop_max_repeated_calls, loop_signature_history, tool_timeouts, created_at, updated_at, rate_limit_per_minute, rate_limit_burst, max_session_usdc FROM bot_settings LIMIT 1",
             [],
             |row| {
                 let web3_tx_confirmation: i64 = row.get(3)?;
@@ -32,11 +36,19 @@ impl Database {
                 let theme_accent: Option<String> = row.get(12)?;
                 let proxy_url: Option<String> = row.get(13)?;
                 let kanban_auto_execute: i64 = row.get::<_, Option<i64>>(14)?.unwrap_or(1);
-                let created_at_str: String = row.get(15)?;
-                let updated_at_str: String = row.get(16)?;
+                let loop_max_repeated_calls: i32 = row.get::<_, Option<i32>>(15)?.unwrap_or(DEFAULT_LOOP_MAX_REPEATED_CALLS);
+                let loop_signature_history: i32 = row.get::<_, Option<i32>>(16)?.unwrap_or(DEFAULT_LOOP_SIGNATURE_HISTORY);
+                let tool_timeouts_json: Option<String> = row.get(17)?;
+                let created_at_str: String = row.get(18)?;
+                let updated_at_str: String = row.get(19)?;
+                let rate_limit_per_minute: i32 = row.get::<_, Option<i32>>(20)?.unwrap_or(DEFAULT_RATE_LIMIT_PER_MINUTE);
+                let rate_limit_burst: i32 = row.get::<_, Option<i32>>(21)?.unwrap_or(DEFAULT_RATE_LIMIT_BURST);
+                let max_session_usdc: f64 = row.get::<_, Option<f64>>(22)?.unwrap_or(DEFAULT_MAX_SESSION_USDC);
 
                 let custom_rpc_endpoints: Option<HashMap<String, String>> = custom_rpc_endpoints_json
                     .and_then(|json| serde_json::from_str(&json).ok());
+                let tool_timeouts: Option<ToolTimeoutSettings> = tool_timeouts_json
+                    .and_then(|json| serde_json::from_str(&json).ok());
 
                 Ok(BotSettings {
                     id: row.get(0)?,
@@ -54,6 +66,12 @@ impl Database {
                     theme_accent,
                     proxy_url,
                     kanban_auto_execute: kanban_auto_execute != 0,
+                    loop_max_repeated_calls,
+                    loop_signature_history,
+                    tool_timeouts,
+                    rate_limit_per_minute,
+                    rate_limit_burst,
+                    max_session_usdc,
                     created_at: DateTime::parse_from_rfc3339(&created_at_str)
                         .unwrap()
                         .with_timezone(&Utc),
@@ -79,7 +97,7 @@ impl Database {
         bot_email: Option<&str>,
         web3_tx_requires_confirmation: Option<bool>,
     ) -> SqliteResult<BotSettings> {
-        self.update_bot_settings_full(bot_name, bot_email, web3_tx_requires_confirmation, None, None, None, None, None, None, None, None, None, None, None)
+        self.update_bot_settings_full(bot_name, bot_email, web3_tx_requires_confirmation, None, None, None, None, None, None, None, None, None, None, None, None, None, None, None, None, None)
     }
 
     /// Update bot settings with all fields including RPC config and keystore URL
@@ -99,6 +117,12 @@ impl Database {
         theme_accent: Option<&str>,
         proxy_url: Option<&str>,
         kanban_auto_execute: Option<bool>,
+        loop_max_repeated_calls: Option<i32>,
+        loop_signature_history: Option<i32>,
+        tool_timeouts: Option<&ToolTimeoutSettings>,
+        rate_limit_per_minute: Option<i32>,
+        rate_limit_burst: Option<i32>,
+        max_session_usdc: Option<f64>,
     ) -> SqliteResult<BotSettings> {
         let conn = self.conn();
         let now = Utc::now().to_rfc3339();
@@ -202,6 +226,55 @@ impl Database {
                     rusqlite::params![if enabled { 1 } else { 0 }, &now],
                 )?;
             }
+            if let Some(max_repeated) = loop_max_repeated_calls {
+                // 0 means reset to default (NULL)
+                let value: Option<i32> = if max_repeated > 0 { Some(max_repeated) } else { None };
+                conn.execute(
+                    "UPDATE bot_settings SET loop_max_repeated_calls = ?1, updated_at = ?2",
+                    rusqlite::params![value, &now],
+                )?;
+            }
+            if let Some(history) = loop_signature_history {
+                let value: Option<i32> = if history > 0 { Some(history) } else { None };
+                conn.execute(
+                    "UPDATE bot_settings SET loop_signature_history = ?1, updated_at = ?2",
+                    rusqlite::params![value, &now],
+                )?;
+            }
+            if let Some(timeouts) = tool_timeouts {
+                // An empty object means reset to the built-in timeouts (NULL)
+                let timeouts_json: Option<String> = if timeouts.is_empty() {
+                    None
+                } else {
+                    serde_json::to_string(timeouts).ok()
+                };
+                conn.execute(
+                    "UPDATE bot_settings SET tool_timeouts = ?1, updated_at = ?2",
+                    rusqlite::params![timeouts_json, &now],
+                )?;
+            }
+            if let Some(per_minute) = rate_limit_per_minute {
+                // 0 disables rate limiting
+                conn.execute(
+                    "UPDATE bot_settings SET rate_limit_per_minute = ?1, updated_at = ?2",
+                    rusqlite::params![per_minute.max(0), &now],
+                )?;
+            }
+            if let Some(burst) = rate_limit_burst {
+                let value: Option<i32> = if burst > 0 { Some(burst) } else { None };
+                conn.execute(
+                    "UPDATE bot_settings SET rate_limit_burst = ?1, updated_at = ?2",
+                    rusqlite::params![value, &now],
+                )?;
+            }
+            if let Some(max_usdc) = max_session_usdc {
+                // 0 (or negative) removes the budget
+                let value: Option<f64> = if max_usdc > 0.0 { Some(max_usdc) } else { None };
+                conn.execute(
+                    "UPDATE bot_settings SET max_session_usdc = ?1, updated_at = ?2",
+                    rusqlite::params![value, &now],
+                )?;
+            }
         } else {
             // Insert new
             let name = bot_name.unwrap_or("StarkBot");
@@ -220,9 +293,17 @@ impl Database {
             let theme_accent_value: Option<&str> = theme_accent.filter(|u| !u.is_empty());
             let proxy_url_value: Option<&str> = proxy_url.filter(|u| !u.is_empty());
             let kanban_auto = kanban_auto_execute.unwrap_or(true);
+            let loop_max_repeated_value: Option<i32> = loop_max_repeated_calls.filter(|v| *v > 0);
+            let loop_history_value: Option<i32> = loop_signature_history.filter(|v| *v > 0);
+            let tool_timeouts_json: Option<String> = tool_timeouts
+                .filter(|t| !t.is_empty())
+                .and_then(|t| serde_json::to_string(t).ok());
+            let rate_limit_per_minute_value: Option<i32> = rate_limit_per_minute.map(|v| v.max(0));
+            let rate_limit_burst_value: Option<i32> = rate_limit_burst.filter(|v| *v > 0);
+            let max_session_usdc_value: Option<f64> = max_session_usdc.filter(|v| *v > 0.0);
             conn.execute(
-                "INSERT INTO bot_settings (bot_name, bot_email, web3_tx_requires_confirmation, rpc_provider, custom_rpc_endpoints, max_tool_iterations, rogue_mode_enabled, safe_mode_max_queries_per_10min, keystore_url, chat_session_memory_generation, guest_dashboard_enabled, theme_accent, proxy_url, kanban_auto_execute, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
-                rusqlite::params![name, email, if confirmation { 1 } else { 0 }, provider, endpoints_json, max_iterations, if rogue_mode { 1 } else { 0 }, safe_mode_queries, keystore_url_value, if session_memory { 1 } else { 0 }, if guest_dashboard { 1 } else { 0 }, theme_accent_value, proxy_url_value, if kanban_auto { 1 } else { 0 }, &now, &now],
+                "INSERT INTO bot_settings (bot_name, bot_email, web3_tx_requires_confirmation, rpc_provider, custom_rpc_endpoints, max_tool_iterations, rogue_mode_enabled, safe_mode_max_queries_per_10min, keystore_url, chat_session_memory_generation, guest_dashboard_enabled, theme_accent, proxy_url, kanban_auto_execute, loop_max_repeated_calls, loop_signature_history, tool_timeouts, rate_limit_per_minute, rate_limit_burst, max_session_usdc, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22)",
+                rusqlite::params![name, email, if confirmation { 1 } else { 0 }, provider, endpoints_json, max_iterations, if rogue_mode { 1 } else { 0 }, safe_mode_queries, keystore_url_value, if session_memory { 1 } else { 0 }, if guest_dashboard { 1 } else { 0 }, theme_accent_value, proxy_url_value, if kanban_auto { 1 } else { 0 }, loop_max_repeated_value, loop_history_value, tool_timeouts_json, rate_limit_per_minute_value, rate_limit_burst_value, max_session_usdc_value, &now, &now],
             )?;
         }
 
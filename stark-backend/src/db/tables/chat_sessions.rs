@@ -1,9 +1,12 @@
 //! Chat session and session message database operations
 
 use chrono::{DateTime, Timelike, Utc};
-use rusqlite::Result as SqliteResult;
+use rusqlite::{OptionalExtension, Result as SqliteResult};
 
-use crate::models::{ChatSession, CompletionStatus, MessageRole, ResetPolicy, SessionMessage, SessionScope};
+use crate::models::{
+    ChatSession, CompletionStatus, FailureCategory, MessageRole, ResetPolicy, SessionFailureReason, SessionMessage,
+    SessionScope,
+};
 use super::super::Database;
 
 impl Database {
@@ -26,11 +29,58 @@ impl Database {
         agent_id: Option<&str>,
     ) -> SqliteResult<ChatSession> {
         let session_key = Self::generate_session_key(channel_type, channel_id, platform_chat_id);
+        self.get_or_create_chat_session_by_key(&session_key, channel_type, channel_id, platform_chat_id, scope, agent_id)
+    }
+
+    /// Get or create the safe-mode session for a shared chat such as a Discord
+    /// thread. It has its own session key but the same platform chat ID, so
+    /// replies still reach the chat while its history and safe-mode flag stay
+    /// apart from the session trusted users share.
+    pub fn get_or_create_safe_mode_chat_session(
+        &self,
+        channel_type: &str,
+        channel_id: i64,
+        platform_chat_id: &str,
+        scope: SessionScope,
+        agent_id: Option<&str>,
+    ) -> SqliteResult<ChatSession> {
+        let session_key = format!("{}:safe-mode", Self::generate_session_key(channel_type, channel_id, platform_chat_id));
+        self.get_or_create_chat_session_by_key(&session_key, channel_type, channel_id, platform_chat_id, scope, agent_id)
+    }
+
+    /// Session key of the throwaway session a planning dry run (/plan) uses.
+    /// Same channel and chat as the real session, so identity and channel
+    /// settings apply, but its history never mixes with the real one.
+    pub fn dry_run_session_key(channel_type: &str, channel_id: i64, platform_chat_id: &str) -> String {
+        format!("{}:dry-run", Self::generate_session_key(channel_type, channel_id, platform_chat_id))
+    }
+
+    /// Get or create the session for a planning dry run in a chat
+    pub fn get_or_create_dry_run_chat_session(
+        &self,
+        channel_type: &str,
+        channel_id: i64,
+        platform_chat_id: &str,
+        scope: SessionScope,
+    ) -> SqliteResult<ChatSession> {
+        let session_key = Self::dry_run_session_key(channel_type, channel_id, platform_chat_id);
+        self.get_or_create_chat_session_by_key(&session_key, channel_type, channel_id, platform_chat_id, scope, None)
+    }
+
+    fn get_or_create_chat_session_by_key(
+        &self,
+        session_key: &str,
+        channel_type: &str,
+        channel_id: i64,
+        platform_chat_id: &str,
+        scope: SessionScope,
+        agent_id: Option<&str>,
+    ) -> SqliteResult<ChatSession> {
         let now = Utc::now();
         let now_str = now.to_rfc3339();
 
         // Try to get existing active session
-        if let Some(mut session) = self.get_chat_session_by_key(&session_key)? {
+        if let Some(mut session) = self.get_chat_session_by_key(session_key)? {
             // Check if session needs reset based on policy
             let should_reset = match session.reset_policy {
                 ResetPolicy::Daily => {
@@ -75,31 +125,22 @@ impl Database {
             return Ok(session);
         }
 
-        // No active session found - check if there's an inactive one we can reactivate
+        // No active session found: create one, or reactivate the inactive one with
+        // this key. A single upsert, so concurrent messages for the same chat can't
+        // race between the lookup above and the insert; the loser gets the same row.
         let conn = self.conn();
-        let inactive_session_id: Option<i64> = conn.query_row(
-            "SELECT id FROM chat_sessions WHERE session_key = ?1 AND is_active = 0 ORDER BY updated_at DESC LIMIT 1",
-            [&session_key],
-            |row| row.get(0),
-        ).ok();
-
-        if let Some(inactive_id) = inactive_session_id {
-            // Reactivate the existing inactive session
-            conn.execute(
-                "UPDATE chat_sessions SET is_active = 1, last_activity_at = ?1, updated_at = ?1, completion_status = 'active' WHERE id = ?2",
-                rusqlite::params![&now_str, inactive_id],
-            )?;
-            drop(conn);
-            return self.get_chat_session(inactive_id).map(|opt| opt.unwrap());
-        }
-
-        // Create new session
-        conn.execute(
+        let id: i64 = conn.query_row(
             "INSERT INTO chat_sessions (session_key, agent_id, scope, channel_type, channel_id, platform_chat_id,
              is_active, reset_policy, idle_timeout_minutes, daily_reset_hour, created_at, updated_at, last_activity_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, 1, ?7, ?8, ?9, ?10, ?10, ?10)",
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, 1, ?7, ?8, ?9, ?10, ?10, ?10)
+             ON CONFLICT(session_key) DO UPDATE SET
+                completion_status = CASE WHEN is_active = 0 THEN 'active' ELSE completion_status END,
+                is_active = 1,
+                last_activity_at = excluded.last_activity_at,
+                updated_at = excluded.updated_at
+             RETURNING id",
             rusqlite::params![
-                &session_key,
+                session_key,
                 agent_id,
                 scope.as_str(),
                 channel_type,
@@ -110,9 +151,8 @@ impl Database {
                 Some(0i32),
                 &now_str,
             ],
+            |row| row.get(0),
         )?;
-
-        let id = conn.last_insert_rowid();
         drop(conn);
 
         self.get_chat_session(id).map(|opt| opt.unwrap())
@@ -155,6 +195,31 @@ impl Database {
         Ok(sessions)
     }
 
+    /// List a channel's sessions that were active since `since`, most recently active first
+    pub fn list_channel_sessions_since(
+        &self,
+        channel_id: i64,
+        since: &DateTime<Utc>,
+        limit: i32,
+    ) -> SqliteResult<Vec<ChatSession>> {
+        let conn = self.conn();
+
+        let mut stmt = conn.prepare(
+            "SELECT id, session_key, agent_id, scope, channel_type, channel_id, platform_chat_id,
+             is_active, reset_policy, idle_timeout_minutes, daily_reset_hour,
+             created_at, updated_at, last_activity_at, expires_at, context_tokens, max_context_tokens, compaction_id, completion_status, safe_mode, special_role_name
+             FROM chat_sessions WHERE channel_id = ?1 AND last_activity_at >= ?2
+             ORDER BY last_activity_at DESC LIMIT ?3",
+        )?;
+
+        let sessions = stmt
+            .query_map(rusqlite::params![channel_id, since.to_rfc3339(), limit], Self::row_to_chat_session)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(sessions)
+    }
+
     /// Get a chat session by session key
     pub fn get_chat_session_by_key(&self, session_key: &str) -> SqliteResult<Option<ChatSession>> {
         let conn = self.conn();
@@ -198,8 +263,36 @@ impl Database {
         Ok(session)
     }
 
+    /// Get the latest active per-message gateway session for a channel (without creating).
+    /// Thread-scoped sessions (keyed by thread ID, not `gateway-*`) are left out.
+    pub fn get_latest_gateway_session(
+        &self,
+        channel_type: &str,
+        channel_id: i64,
+    ) -> SqliteResult<Option<ChatSession>> {
+        let conn = self.conn();
+
+        let mut stmt = conn.prepare(
+            "SELECT id, session_key, agent_id, scope, channel_type, channel_id, platform_chat_id,
+             is_active, reset_policy, idle_timeout_minutes, daily_reset_hour,
+             created_at, updated_at, last_activity_at, expires_at, context_tokens, max_context_tokens, compaction_id, completion_status, safe_mode, special_role_name
+             FROM chat_sessions
+             WHERE channel_type = ?1 AND channel_id = ?2 AND is_active = 1 AND platform_chat_id LIKE 'gateway-%'
+             ORDER BY last_activity_at DESC LIMIT 1",
+        )?;
+
+        let session = stmt
+            .query_row(rusqlite::params![channel_type, channel_id], Self::row_to_chat_session)
+            .ok();
+
+        Ok(session)
+    }
+
     /// Create a new session for gateway channels (Discord, Telegram)
-    /// Always creates a fresh session with a unique key
+    /// Always creates a fresh session with a unique key. Every other active
+    /// gateway session for the channel is deactivated in the same transaction, so
+    /// sessions left active by an earlier failed `deactivate_session` don't linger.
+    /// Thread-scoped sessions are not touched.
     pub fn create_gateway_session(
         &self,
         channel_type: &str,
@@ -216,8 +309,21 @@ impl Database {
         let platform_chat_id = format!("gateway-{}", timestamp);
         let session_key = Self::generate_session_key(channel_type, channel_id, &platform_chat_id);
 
+        let tx = conn.unchecked_transaction()?;
+        let stale = tx.execute(
+            "UPDATE chat_sessions SET is_active = 0, updated_at = ?1
+             WHERE channel_type = ?2 AND channel_id = ?3 AND is_active = 1 AND platform_chat_id LIKE 'gateway-%'",
+            rusqlite::params![&now_str, channel_type, channel_id],
+        )?;
+        if stale > 1 {
+            log::warn!(
+                "[SESSION] Deactivated {} lingering active {} sessions for channel {}",
+                stale, channel_type, channel_id
+            );
+        }
+
         // Create new session
-        conn.execute(
+        tx.execute(
             "INSERT INTO chat_sessions (session_key, agent_id, scope, channel_type, channel_id, platform_chat_id,
              is_active, reset_policy, idle_timeout_minutes, daily_reset_hour, created_at, updated_at, last_activity_at)
              VALUES (?1, ?2, ?3, ?4, ?5, ?6, 1, ?7, ?8, ?9, ?10, ?10, ?10)",
@@ -235,12 +341,36 @@ impl Database {
             ],
         )?;
 
-        let id = conn.last_insert_rowid();
+        let id = tx.last_insert_rowid();
+        tx.commit()?;
         drop(conn);
 
         self.get_chat_session(id).map(|opt| opt.unwrap())
     }
 
+    /// Repair gateway channels (Discord, Telegram) that have more than one active
+    /// per-message session: all but the most recently active one are deactivated.
+    /// Thread-scoped sessions are not touched. Returns the number of sessions deactivated.
+    pub fn repair_duplicate_gateway_sessions(&self) -> SqliteResult<usize> {
+        let conn = self.conn();
+        let now = Utc::now().to_rfc3339();
+        let repaired = conn.execute(
+            "UPDATE chat_sessions SET is_active = 0, updated_at = ?1
+             WHERE is_active = 1 AND LOWER(channel_type) IN ('discord', 'telegram')
+               AND platform_chat_id LIKE 'gateway-%'
+               AND id NOT IN (
+                 SELECT (SELECT newest.id FROM chat_sessions newest
+                         WHERE newest.channel_type = c.channel_type AND newest.channel_id = c.channel_id
+                           AND newest.is_active = 1 AND newest.platform_chat_id LIKE 'gateway-%'
+                         ORDER BY newest.last_activity_at DESC, newest.id DESC LIMIT 1)
+                 FROM (SELECT DISTINCT channel_type, channel_id FROM chat_sessions
+                       WHERE is_active = 1 AND platform_chat_id LIKE 'gateway-%') c
+               )",
+            [&now],
+        )?;
+        Ok(repaired)
+    }
+
     /// Mark a session as inactive (used when creating a new gateway session)
     pub fn deactivate_session(&self, session_id: i64) -> SqliteResult<()> {
         let conn = self.conn();
@@ -252,6 +382,17 @@ impl Database {
         Ok(())
     }
 
+    /// Record activity on a session (used when a gateway session is reused)
+    pub fn touch_chat_session(&self, session_id: i64) -> SqliteResult<()> {
+        let conn = self.conn();
+        let now = Utc::now().to_rfc3339();
+        conn.execute(
+            "UPDATE chat_sessions SET last_activity_at = ?1, updated_at = ?1 WHERE id = ?2",
+            rusqlite::params![&now, session_id],
+        )?;
+        Ok(())
+    }
+
     /// Reset a chat session (mark old as inactive, create new)
     pub fn reset_chat_session(&self, id: i64) -> SqliteResult<ChatSession> {
         let conn = self.conn();
@@ -497,6 +638,7 @@ impl Database {
             platform_message_id: platform_message_id.map(|s| s.to_string()),
             tokens_used,
             created_at: now,
+            pinned: false,
         })
     }
 
@@ -537,7 +679,7 @@ impl Database {
         let conn = self.conn();
 
         let mut stmt = conn.prepare(
-            "SELECT id, session_id, role, content, user_id, user_name, platform_message_id, tokens_used, created_at
+            "SELECT id, session_id, role, content, user_id, user_name, platform_message_id, tokens_used, created_at, pinned
              FROM session_messages WHERE session_id = ?1 ORDER BY created_at ASC",
         )?;
 
@@ -554,7 +696,7 @@ impl Database {
         let conn = self.conn();
 
         let mut stmt = conn.prepare(
-            "SELECT id, session_id, role, content, user_id, user_name, platform_message_id, tokens_used, created_at
+            "SELECT id, session_id, role, content, user_id, user_name, platform_message_id, tokens_used, created_at, pinned
              FROM session_messages WHERE session_id = ?1 ORDER BY created_at DESC LIMIT ?2",
         )?;
 
@@ -568,6 +710,41 @@ impl Database {
         Ok(messages)
     }
 
+    /// Get the most recent user/assistant messages across all sessions of an identity, newest first.
+    ///
+    /// A session belongs to the identity if one of its linked platform users wrote in it.
+    /// Safe-mode, cron and heartbeat sessions are skipped. In group sessions only the
+    /// identity's own messages are returned, so other members' conversations don't leak.
+    pub fn get_recent_identity_messages(&self, identity_id: &str, limit: i32) -> SqliteResult<Vec<SessionMessage>> {
+        let conn = self.conn();
+
+        let mut stmt = conn.prepare(
+            "SELECT sm.id, sm.session_id, sm.role, sm.content, sm.user_id, sm.user_name,
+                    sm.platform_message_id, sm.tokens_used, sm.created_at, sm.pinned
+             FROM session_messages sm
+             JOIN chat_sessions cs ON cs.id = sm.session_id
+             WHERE cs.safe_mode = 0
+               AND cs.scope != 'cron'
+               AND cs.channel_type NOT IN ('cron', 'heartbeat')
+               AND sm.role IN ('user', 'assistant')
+               AND (
+                   sm.user_id IN (SELECT platform_user_id FROM identity_links WHERE identity_id = ?1)
+                   OR (sm.role = 'assistant' AND cs.scope = 'dm' AND sm.session_id IN (
+                       SELECT DISTINCT session_id FROM session_messages
+                       WHERE user_id IN (SELECT platform_user_id FROM identity_links WHERE identity_id = ?1)
+                   ))
+               )
+             ORDER BY sm.created_at DESC, sm.id DESC
+             LIMIT ?2",
+        )?;
+
+        let messages = stmt
+            .query_map(rusqlite::params![identity_id, limit], Self::row_to_session_message)?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(messages)
+    }
+
     /// Count messages in a session
     pub fn count_session_messages(&self, session_id: i64) -> SqliteResult<i64> {
         let conn = self.conn();
@@ -670,9 +847,48 @@ impl Database {
             created_at: DateTime::parse_from_rfc3339(&created_at_str)
                 .unwrap()
                 .with_timezone(&Utc),
+            pinned: row.get(9)?,
         })
     }
 
+    /// Pin or unpin a message so compaction keeps it verbatim.
+    /// Returns false if the message doesn't belong to the session.
+    pub fn set_message_pinned(&self, session_id: i64, message_id: i64, pinned: bool) -> SqliteResult<bool> {
+        let conn = self.conn();
+        let updated = conn.execute(
+            "UPDATE session_messages SET pinned = ?1 WHERE session_id = ?2 AND id = ?3",
+            rusqlite::params![pinned, session_id, message_id],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Pin a message: compaction never summarizes or deletes it
+    pub fn pin_message(&self, session_id: i64, message_id: i64) -> SqliteResult<bool> {
+        self.set_message_pinned(session_id, message_id, true)
+    }
+
+    /// Unpin a message so it can be compacted again
+    pub fn unpin_message(&self, session_id: i64, message_id: i64) -> SqliteResult<bool> {
+        self.set_message_pinned(session_id, message_id, false)
+    }
+
+    /// Get the pinned messages of a session, oldest first
+    pub fn get_pinned_messages(&self, session_id: i64) -> SqliteResult<Vec<SessionMessage>> {
+        let conn = self.conn();
+
+        let mut stmt = conn.prepare(
+            "SELECT id, session_id, role, content, user_id, user_name, platform_message_id, tokens_used, created_at, pinned
+             FROM session_messages WHERE session_id = ?1 AND pinned = 1 ORDER BY created_at ASC",
+        )?;
+
+        let messages = stmt
+            .query_map([session_id], Self::row_to_session_message)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(messages)
+    }
+
     // ============================================
     // Context Management methods (compaction)
     // ============================================
@@ -699,59 +915,34 @@ impl Database {
         Ok(())
     }
 
-    /// Get oldest messages for compaction (excludes most recent messages)
+    /// Get oldest messages for compaction (excludes the most recent messages
+    /// and pinned messages, which compaction keeps verbatim)
     pub fn get_messages_for_compaction(&self, session_id: i64, keep_recent: i32) -> SqliteResult<Vec<SessionMessage>> {
         let conn = self.conn();
 
-        // Get total count first
-        let total: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM session_messages WHERE session_id = ?1",
-            [session_id],
-            |row| row.get(0),
-        )?;
-
-        let to_compact = (total as i32).saturating_sub(keep_recent);
-        if to_compact <= 0 {
-            return Ok(vec![]);
-        }
-
         let mut stmt = conn.prepare(
-            "SELECT id, session_id, role, content, user_id, user_name, platform_message_id, tokens_used, created_at
-             FROM session_messages WHERE session_id = ?1 ORDER BY created_at ASC LIMIT ?2",
+            "SELECT id, session_id, role, content, user_id, user_name, platform_message_id, tokens_used, created_at, pinned
+             FROM session_messages WHERE session_id = ?1 AND pinned = 0 AND id NOT IN (
+                SELECT id FROM session_messages WHERE session_id = ?1 ORDER BY created_at DESC LIMIT ?2
+             )
+             ORDER BY created_at ASC",
         )?;
 
         let messages = stmt
-            .query_map(rusqlite::params![session_id, to_compact], |row| {
-                let created_at_str: String = row.get(8)?;
-                let role_str: String = row.get(2)?;
-
-                Ok(SessionMessage {
-                    id: row.get(0)?,
-                    session_id: row.get(1)?,
-                    role: MessageRole::from_str(&role_str).unwrap_or(MessageRole::User),
-                    content: row.get(3)?,
-                    user_id: row.get(4)?,
-                    user_name: row.get(5)?,
-                    platform_message_id: row.get(6)?,
-                    tokens_used: row.get(7)?,
-                    created_at: chrono::DateTime::parse_from_rfc3339(&created_at_str)
-                        .unwrap()
-                        .with_timezone(&Utc),
-                })
-            })?
+            .query_map(rusqlite::params![session_id, keep_recent], Self::row_to_session_message)?
             .filter_map(|r| r.ok())
             .collect();
 
         Ok(messages)
     }
 
-    /// Delete old messages after compaction (keeps the most recent messages)
+    /// Delete old messages after compaction (keeps the most recent and pinned messages)
     pub fn delete_compacted_messages(&self, session_id: i64, keep_recent: i32) -> SqliteResult<i32> {
         let conn = self.conn();
 
         // Get IDs of messages to delete (all except the most recent)
         let deleted = conn.execute(
-            "DELETE FROM session_messages WHERE session_id = ?1 AND id NOT IN (
+            "DELETE FROM session_messages WHERE session_id = ?1 AND pinned = 0 AND id NOT IN (
                 SELECT id FROM session_messages WHERE session_id = ?1 ORDER BY created_at DESC LIMIT ?2
             )",
             rusqlite::params![session_id, keep_recent],
@@ -819,7 +1010,7 @@ impl Database {
         let conn = self.conn();
 
         let mut stmt = conn.prepare(
-            "SELECT id, session_id, role, content, user_id, user_name, platform_message_id, tokens_used, created_at
+            "SELECT id, session_id, role, content, user_id, user_name, platform_message_id, tokens_used, created_at, pinned
              FROM session_messages WHERE session_id = ?1 ORDER BY created_at ASC LIMIT ?2",
         )?;
 
@@ -831,14 +1022,14 @@ impl Database {
         Ok(messages)
     }
 
-    /// Delete the oldest N messages from a session
+    /// Delete the oldest N unpinned messages from a session
     pub fn delete_oldest_messages(&self, session_id: i64, count: i32) -> SqliteResult<i32> {
         let conn = self.conn();
 
         // Delete oldest N messages by ID
         let deleted = conn.execute(
             "DELETE FROM session_messages WHERE id IN (
-                SELECT id FROM session_messages WHERE session_id = ?1 ORDER BY created_at ASC LIMIT ?2
+                SELECT id FROM session_messages WHERE session_id = ?1 AND pinned = 0 ORDER BY created_at ASC LIMIT ?2
             )",
             rusqlite::params![session_id, count],
         )?;
@@ -846,6 +1037,19 @@ impl Database {
         Ok(deleted as i32)
     }
 
+    /// Delete specific messages of a session by ID
+    pub fn delete_session_messages_by_ids(&self, session_id: i64, message_ids: &[i64]) -> SqliteResult<i32> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("DELETE FROM session_messages WHERE session_id = ?1 AND id = ?2")?;
+
+        let mut deleted = 0;
+        for id in message_ids {
+            deleted += stmt.execute(rusqlite::params![session_id, id])?;
+        }
+
+        Ok(deleted as i32)
+    }
+
     /// Increment the compaction generation counter for a session
     pub fn increment_compaction_generation(&self, session_id: i64) -> SqliteResult<()> {
         let conn = self.conn();
@@ -871,17 +1075,51 @@ impl Database {
     // Completion Status methods (Task Planner)
     // ============================================
 
-    /// Update the completion status of a session
+    /// Update the completion status of a session. Leaving `Failed` clears the
+    /// recorded failure reason.
     pub fn update_session_completion_status(&self, session_id: i64, status: CompletionStatus) -> SqliteResult<()> {
         let conn = self.conn();
         let now = Utc::now().to_rfc3339();
         conn.execute(
-            "UPDATE chat_sessions SET completion_status = ?1, updated_at = ?2 WHERE id = ?3",
+            "UPDATE chat_sessions SET completion_status = ?1, updated_at = ?2,
+                failure_reason = CASE WHEN ?1 = 'failed' THEN failure_reason ELSE NULL END
+             WHERE id = ?3",
             rusqlite::params![status.as_str(), &now, session_id],
         )?;
         Ok(())
     }
 
+    /// Mark a session as Failed and record why. The first reason recorded for a
+    /// failure is kept, so a specific cause (e.g. max iterations) isn't replaced
+    /// by the generic error a caller further up reports for the same run.
+    pub fn mark_session_failed(&self, session_id: i64, reason: &SessionFailureReason) -> SqliteResult<()> {
+        let conn = self.conn();
+        let now = Utc::now().to_rfc3339();
+        let reason_json = serde_json::to_string(reason).unwrap_or_default();
+        conn.execute(
+            "UPDATE chat_sessions SET completion_status = 'failed', updated_at = ?1,
+                failure_reason = CASE WHEN completion_status = 'failed' AND failure_reason IS NOT NULL
+                                      THEN failure_reason ELSE ?2 END
+             WHERE id = ?3",
+            rusqlite::params![&now, &reason_json, session_id],
+        )?;
+        Ok(())
+    }
+
+    /// Get the recorded failure reason of a session, if it failed with one
+    pub fn get_session_failure_reason(&self, session_id: i64) -> SqliteResult<Option<SessionFailureReason>> {
+        let conn = self.conn();
+        let json: Option<String> = conn
+            .query_row(
+                "SELECT failure_reason FROM chat_sessions WHERE id = ?1 AND completion_status = 'failed'",
+                [session_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+        Ok(json.and_then(|j| serde_json::from_str(&j).ok()))
+    }
+
     /// Get the completion status of a session
     pub fn get_session_completion_status(&self, session_id: i64) -> SqliteResult<Option<CompletionStatus>> {
         let conn = self.conn();
@@ -911,12 +1149,20 @@ impl Database {
     pub fn cleanup_stale_active_sessions(&self, stale_minutes: i64) -> SqliteResult<usize> {
         let conn = self.conn();
         let cutoff = (Utc::now() - chrono::Duration::minutes(stale_minutes)).to_rfc3339();
+        let reason = SessionFailureReason::new(
+            FailureCategory::Stale,
+            format!("No progress for over {} minutes", stale_minutes),
+        );
         let count = conn.execute(
             "UPDATE chat_sessions
-             SET completion_status = 'failed', updated_at = ?1
+             SET completion_status = 'failed', updated_at = ?1, failure_reason = ?3
              WHERE completion_status = 'active'
                AND updated_at < ?2",
-            rusqlite::params![&Utc::now().to_rfc3339(), &cutoff],
+            rusqlite::params![
+                &Utc::now().to_rfc3339(),
+                &cutoff,
+                serde_json::to_string(&reason).unwrap_or_default()
+            ],
         )?;
         Ok(count)
     }
@@ -0,0 +1,63 @@
+//! Persisted context-bank items (cross-session memory of detected terms)
+//!
+//! Addresses and token symbols the context bank detects are stored per
+//! identity so a later session can be reminded of them without the user
+//! re-pasting. Each identity keeps a bounded set; the least recently seen
+//! items are evicted first.
+
+use chrono::Utc;
+use rusqlite::Result as SqliteResult;
+
+use super::super::Database;
+use crate::tools::ContextBankItem;
+
+/// Most context-bank items stored per identity
+pub const MAX_STORED_CONTEXT_BANK_ITEMS: usize = 50;
+
+impl Database {
+    /// Store (or refresh) a detected item for an identity, evicting the least
+    /// recently seen items beyond `MAX_STORED_CONTEXT_BANK_ITEMS`
+    pub fn upsert_context_bank_item(&self, identity_id: &str, item: &ContextBankItem) -> SqliteResult<()> {
+        let conn = self.conn();
+        conn.execute(
+            "INSERT INTO context_bank_items (identity_id, item_type, value, label, last_seen_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(identity_id, item_type, value)
+             DO UPDATE SET label = COALESCE(excluded.label, label), last_seen_at = excluded.last_seen_at",
+            rusqlite::params![
+                identity_id,
+                item.item_type,
+                item.value,
+                item.label,
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        conn.execute(
+            "DELETE FROM context_bank_items WHERE identity_id = ?1 AND rowid NOT IN (
+                 SELECT rowid FROM context_bank_items WHERE identity_id = ?1
+                 ORDER BY last_seen_at DESC, rowid DESC LIMIT ?2
+             )",
+            rusqlite::params![identity_id, MAX_STORED_CONTEXT_BANK_ITEMS as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Stored context-bank items for an identity, most recently seen first
+    pub fn get_context_bank_items(&self, identity_id: &str) -> SqliteResult<Vec<ContextBankItem>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT value, item_type, label FROM context_bank_items
+             WHERE identity_id = ?1 ORDER BY last_seen_at DESC, rowid DESC",
+        )?;
+        let items = stmt
+            .query_map([identity_id], |row| {
+                Ok(ContextBankItem {
+                    value: row.get(0)?,
+                    item_type: row.get(1)?,
+                    label: row.get(2)?,
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        Ok(items)
+    }
+}
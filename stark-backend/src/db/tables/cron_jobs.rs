@@ -56,7 +56,8 @@ impl Database {
                     session_mode, message, system_event, channel_id, deliver_to, deliver,
                     model_override, thinking_level, timeout_seconds, delete_after_run,
                     status, last_run_at, next_run_at, run_count, error_count, last_error,
-                    created_at, updated_at
+                    created_at, updated_at, max_retries, retry_delay_seconds, retry_attempt,
+                    retry_of_run_id
              FROM cron_jobs WHERE id = ?1",
             [id],
             |row| self.map_cron_job_row(row),
@@ -90,6 +91,10 @@ impl Database {
             last_error: row.get(22)?,
             created_at: row.get(23)?,
             updated_at: row.get(24)?,
+            max_retries: row.get(25)?,
+            retry_delay_seconds: row.get(26)?,
+            retry_attempt: row.get(27)?,
+            retry_of_run_id: row.get(28)?,
         })
     }
 
@@ -111,7 +116,8 @@ impl Database {
                     session_mode, message, system_event, channel_id, deliver_to, deliver,
                     model_override, thinking_level, timeout_seconds, delete_after_run,
                     status, last_run_at, next_run_at, run_count, error_count, last_error,
-                    created_at, updated_at
+                    created_at, updated_at, max_retries, retry_delay_seconds, retry_attempt,
+                    retry_of_run_id
              FROM cron_jobs WHERE job_id = ?1",
             [job_id],
             |row| self.map_cron_job_row(row),
@@ -130,7 +136,8 @@ impl Database {
                     session_mode, message, system_event, channel_id, deliver_to, deliver,
                     model_override, thinking_level, timeout_seconds, delete_after_run,
                     status, last_run_at, next_run_at, run_count, error_count, last_error,
-                    created_at, updated_at
+                    created_at, updated_at, max_retries, retry_delay_seconds, retry_attempt,
+                    retry_of_run_id
              FROM cron_jobs ORDER BY created_at DESC"
         )?;
 
@@ -152,7 +159,8 @@ impl Database {
                     session_mode, message, system_event, channel_id, deliver_to, deliver,
                     model_override, thinking_level, timeout_seconds, delete_after_run,
                     status, last_run_at, next_run_at, run_count, error_count, last_error,
-                    created_at, updated_at
+                    created_at, updated_at, max_retries, retry_delay_seconds, retry_attempt,
+                    retry_of_run_id
              FROM cron_jobs
              WHERE status = 'active' AND (next_run_at IS NULL OR next_run_at <= ?1)
              ORDER BY next_run_at ASC"
@@ -291,6 +299,44 @@ impl Database {
         Ok(())
     }
 
+    /// Set a job's auto-retry policy. `None` leaves a field unchanged; a
+    /// value of 0 turns retries off (or restores the default delay).
+    pub fn set_cron_job_retry_policy(
+        &self,
+        id: i64,
+        max_retries: Option<i32>,
+        retry_delay_seconds: Option<i32>,
+    ) -> SqliteResult<CronJob> {
+        let conn = self.conn();
+        let now = Utc::now().to_rfc3339();
+
+        if let Some(v) = max_retries {
+            conn.execute(
+                "UPDATE cron_jobs SET max_retries = ?1, updated_at = ?2 WHERE id = ?3",
+                rusqlite::params![(v > 0).then_some(v), now, id],
+            )?;
+        }
+        if let Some(v) = retry_delay_seconds {
+            conn.execute(
+                "UPDATE cron_jobs SET retry_delay_seconds = ?1, updated_at = ?2 WHERE id = ?3",
+                rusqlite::params![(v > 0).then_some(v), now, id],
+            )?;
+        }
+
+        self.get_cron_job_by_id_internal(&conn, id)
+    }
+
+    /// Record where a job is in its retry chain: the number of retries made
+    /// so far and the failed run they retry. (0, None) ends the chain.
+    pub fn set_cron_job_retry_state(&self, id: i64, retry_attempt: i32, retry_of_run_id: Option<i64>) -> SqliteResult<()> {
+        let conn = self.conn();
+        conn.execute(
+            "UPDATE cron_jobs SET retry_attempt = ?1, retry_of_run_id = ?2 WHERE id = ?3",
+            rusqlite::params![retry_attempt, retry_of_run_id, id],
+        )?;
+        Ok(())
+    }
+
     /// Delete a cron job
     pub fn delete_cron_job(&self, id: i64) -> SqliteResult<bool> {
         let conn = self.conn();
@@ -306,7 +352,9 @@ impl Database {
         Ok(rows_deleted)
     }
 
-    /// Log a cron job run
+    /// Log a cron job run. Retries pass the failed run they retry and their
+    /// attempt number; regular runs pass `None` and 0.
+    #[allow(clippy::too_many_arguments)]
     pub fn log_cron_job_run(
         &self,
         job_id: i64,
@@ -316,13 +364,15 @@ impl Database {
         result: Option<&str>,
         error: Option<&str>,
         duration_ms: Option<i64>,
+        retry_of: Option<i64>,
+        attempt: i32,
     ) -> SqliteResult<CronJobRun> {
         let conn = self.conn();
 
         conn.execute(
-            "INSERT INTO cron_job_runs (job_id, started_at, completed_at, success, result, error, duration_ms)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            rusqlite::params![job_id, started_at, completed_at, success as i32, result, error, duration_ms],
+            "INSERT INTO cron_job_runs (job_id, started_at, completed_at, success, result, error, duration_ms, retry_of, attempt)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            rusqlite::params![job_id, started_at, completed_at, success as i32, result, error, duration_ms, retry_of, attempt],
         )?;
 
         let id = conn.last_insert_rowid();
@@ -336,6 +386,8 @@ impl Database {
             result: result.map(|s| s.to_string()),
             error: error.map(|s| s.to_string()),
             duration_ms,
+            retry_of,
+            attempt,
         })
     }
 
@@ -343,8 +395,8 @@ impl Database {
     pub fn get_cron_job_runs(&self, job_id: i64, limit: i32) -> SqliteResult<Vec<CronJobRun>> {
         let conn = self.conn();
         let mut stmt = conn.prepare(
-            "SELECT id, job_id, started_at, completed_at, success, result, error, duration_ms
-             FROM cron_job_runs WHERE job_id = ?1 ORDER BY started_at DESC LIMIT ?2"
+            "SELECT id, job_id, started_at, completed_at, success, result, error, duration_ms, retry_of, attempt
+             FROM cron_job_runs WHERE job_id = ?1 ORDER BY started_at DESC, id DESC LIMIT ?2"
         )?;
 
         let runs: Vec<CronJobRun> = stmt
@@ -358,6 +410,8 @@ impl Database {
                     result: row.get(5)?,
                     error: row.get(6)?,
                     duration_ms: row.get(7)?,
+                    retry_of: row.get(8)?,
+                    attempt: row.get(9)?,
                 })
             })?
             .filter_map(|r| r.ok())
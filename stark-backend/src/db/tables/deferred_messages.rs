@@ -0,0 +1,94 @@
+//! Deferred message database operations (channel quiet hours)
+//!
+//! Proactive messages produced while a channel is in its quiet hours are
+//! parked here with the time the window ends, and sent by the scheduler
+//! once that time has passed.
+
+use chrono::{DateTime, Utc};
+use rusqlite::Result as SqliteResult;
+use serde::Serialize;
+
+use super::super::Database;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeferredMessage {
+    pub id: i64,
+    pub channel_id: i64,
+    /// Platform chat to deliver to, if the producer named one
+    pub chat_id: Option<String>,
+    /// What produced the message, e.g. "cron:<job_id>"
+    pub source: String,
+    pub text: String,
+    pub deliver_after: String,
+    pub created_at: String,
+    /// Failed send attempts so far
+    pub attempts: i64,
+}
+
+impl Database {
+    /// Queue a message for delivery once `deliver_after` has passed
+    pub fn defer_message(
+        &self,
+        channel_id: i64,
+        chat_id: Option<&str>,
+        source: &str,
+        text: &str,
+        deliver_after: DateTime<Utc>,
+    ) -> SqliteResult<i64> {
+        let conn = self.conn();
+        conn.execute(
+            "INSERT INTO deferred_messages (channel_id, chat_id, source, text, deliver_after, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![
+                channel_id,
+                chat_id,
+                source,
+                text,
+                deliver_after.to_rfc3339(),
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Deferred messages due at `now`, oldest first
+    pub fn list_due_deferred_messages(&self, now: DateTime<Utc>) -> SqliteResult<Vec<DeferredMessage>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, channel_id, chat_id, source, text, deliver_after, created_at, attempts
+             FROM deferred_messages WHERE deliver_after <= ?1 ORDER BY id",
+        )?;
+        let due = stmt
+            .query_map([now.to_rfc3339()], |row| {
+                Ok(DeferredMessage {
+                    id: row.get(0)?,
+                    channel_id: row.get(1)?,
+                    chat_id: row.get(2)?,
+                    source: row.get(3)?,
+                    text: row.get(4)?,
+                    deliver_after: row.get(5)?,
+                    created_at: row.get(6)?,
+                    attempts: row.get(7)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(due)
+    }
+
+    /// Remove a deferred message once it has been sent (or given up on)
+    pub fn delete_deferred_message(&self, id: i64) -> SqliteResult<()> {
+        let conn = self.conn();
+        conn.execute("DELETE FROM deferred_messages WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    /// Record a failed send and try again after `retry_at`
+    pub fn retry_deferred_message(&self, id: i64, retry_at: DateTime<Utc>) -> SqliteResult<()> {
+        let conn = self.conn();
+        conn.execute(
+            "UPDATE deferred_messages SET attempts = attempts + 1, deliver_after = ?1 WHERE id = ?2",
+            rusqlite::params![retry_at.to_rfc3339(), id],
+        )?;
+        Ok(())
+    }
+}
@@ -112,6 +112,26 @@ impl Database {
         Ok(link)
     }
 
+    /// Find identity links by identity ID, platform user ID or user name
+    /// (case-insensitive, leading '@' ignored)
+    pub fn find_identities_by_handle(&self, handle: &str) -> SqliteResult<Vec<IdentityLink>> {
+        let conn = self.conn();
+        let handle = handle.trim().trim_start_matches('@');
+
+        let mut stmt = conn.prepare(
+            "SELECT id, identity_id, channel_type, platform_user_id, platform_user_name, is_verified, verified_at, created_at, updated_at
+             FROM identity_links
+             WHERE identity_id = ?1 OR platform_user_id = ?1 OR LOWER(platform_user_name) = LOWER(?1)",
+        )?;
+
+        let links = stmt
+            .query_map([handle], Self::row_to_identity_link)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(links)
+    }
+
     /// Get all linked identities for an identity_id
     pub fn get_linked_identities(&self, identity_id: &str) -> SqliteResult<Vec<IdentityLink>> {
         let conn = self.conn();
@@ -0,0 +1,254 @@
+//! Structured memories database operations (memories table)
+
+use std::collections::HashSet;
+
+use chrono::Utc;
+use rusqlite::Result as SqliteResult;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::super::Database;
+
+/// A structured memory row as carried in an export bundle. Local references
+/// (row id, session, superseding memory) are left out so rows can be moved
+/// between deployments.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Memory {
+    pub memory_type: String,
+    pub content: String,
+    pub category: Option<String>,
+    pub tags: Option<String>,
+    pub importance: i64,
+    pub identity_id: Option<String>,
+    pub source_channel_type: Option<String>,
+    pub log_date: Option<String>,
+    pub entity_type: Option<String>,
+    pub entity_name: Option<String>,
+    pub confidence: Option<f64>,
+    pub source_type: Option<String>,
+    #[serde(default)]
+    pub is_public: bool,
+    pub valid_from: Option<String>,
+    pub valid_until: Option<String>,
+    pub temporal_type: Option<String>,
+    pub expires_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl Memory {
+    /// Identity of a memory for duplicate detection: type, owner and content
+    pub fn content_hash(&self) -> String {
+        memory_content_hash(&self.memory_type, self.identity_id.as_deref(), &self.content)
+    }
+}
+
+fn memory_content_hash(memory_type: &str, identity_id: Option<&str>, content: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(memory_type.as_bytes());
+    hasher.update([0]);
+    hasher.update(identity_id.unwrap_or("").as_bytes());
+    hasher.update([0]);
+    hasher.update(content.trim().as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+const MEMORY_EXPORT_COLUMNS: &str = "memory_type, content, category, tags, importance, identity_id,
+                                     source_channel_type, log_date, entity_type, entity_name, confidence,
+                                     source_type, is_public, valid_from, valid_until, temporal_type,
+                                     expires_at, created_at, updated_at";
+
+impl Database {
+    /// Get the latest active preference memory for an identity in a category.
+    pub fn get_identity_preference(
+        &self,
+        identity_id: &str,
+        category: &str,
+    ) -> SqliteResult<Option<String>> {
+        let conn = self.conn();
+        let result = conn.query_row(
+            "SELECT content FROM memories
+             WHERE memory_type = 'preference' AND identity_id = ?1 AND category = ?2
+               AND superseded_by IS NULL
+             ORDER BY updated_at DESC, id DESC LIMIT 1",
+            rusqlite::params![identity_id, category],
+            |row| row.get(0),
+        );
+        match result {
+            Ok(content) => Ok(Some(content)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Upsert a preference memory for an identity in a category.
+    /// Only one active preference is kept per (identity, category).
+    pub fn set_identity_preference(
+        &self,
+        identity_id: &str,
+        category: &str,
+        content: &str,
+    ) -> SqliteResult<()> {
+        let conn = self.conn();
+        let now = Utc::now().to_rfc3339();
+        let updated = conn.execute(
+            "UPDATE memories SET content = ?1, updated_at = ?2
+             WHERE memory_type = 'preference' AND identity_id = ?3 AND category = ?4
+               AND superseded_by IS NULL",
+            rusqlite::params![content, &now, identity_id, category],
+        )?;
+        if updated == 0 {
+            conn.execute(
+                "INSERT INTO memories (memory_type, content, category, importance, identity_id,
+                    source_type, created_at, updated_at)
+                 VALUES ('preference', ?1, ?2, 5, ?3, 'inferred', ?4, ?4)",
+                rusqlite::params![content, category, identity_id, &now],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Add a fact to an identity's public profile. Only facts added this way
+    /// (explicitly, by the user) are ever visible to other users.
+    pub fn add_public_profile_fact(&self, identity_id: &str, fact: &str) -> SqliteResult<i64> {
+        let conn = self.conn();
+        let now = Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO memories (memory_type, content, importance, identity_id, source_type,
+                is_public, created_at, updated_at)
+             VALUES ('profile_fact', ?1, 5, ?2, 'explicit', 1, ?3, ?3)",
+            rusqlite::params![fact, identity_id, &now],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// The public-flagged facts of an identity as (memory id, fact), oldest first
+    pub fn get_public_profile_facts(&self, identity_id: &str) -> SqliteResult<Vec<(i64, String)>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, content FROM memories
+             WHERE identity_id = ?1 AND is_public = 1 AND superseded_by IS NULL
+             ORDER BY id ASC",
+        )?;
+        let facts = stmt
+            .query_map([identity_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(facts)
+    }
+
+    /// Remove a fact from an identity's public profile (the memory itself is kept,
+    /// private). Returns false if the identity has no such public fact.
+    pub fn unshare_profile_fact(&self, identity_id: &str, memory_id: i64) -> SqliteResult<bool> {
+        let conn = self.conn();
+        let now = Utc::now().to_rfc3339();
+        let updated = conn.execute(
+            "UPDATE memories SET is_public = 0, updated_at = ?1
+             WHERE id = ?2 AND identity_id = ?3 AND is_public = 1",
+            rusqlite::params![&now, memory_id, identity_id],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Active (not superseded) memories of one identity, or of everyone when
+    /// `identity_id` is None, oldest first
+    pub fn export_memories(&self, identity_id: Option<&str>) -> SqliteResult<Vec<Memory>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM memories
+             WHERE superseded_by IS NULL AND (?1 IS NULL OR identity_id = ?1)
+             ORDER BY created_at ASC, id ASC",
+            MEMORY_EXPORT_COLUMNS
+        ))?;
+        let memories = stmt
+            .query_map([identity_id], |row| {
+                Ok(Memory {
+                    memory_type: row.get(0)?,
+                    content: row.get(1)?,
+                    category: row.get(2)?,
+                    tags: row.get(3)?,
+                    importance: row.get(4)?,
+                    identity_id: row.get(5)?,
+                    source_channel_type: row.get(6)?,
+                    log_date: row.get(7)?,
+                    entity_type: row.get(8)?,
+                    entity_name: row.get(9)?,
+                    confidence: row.get(10)?,
+                    source_type: row.get(11)?,
+                    is_public: row.get::<_, i64>(12)? != 0,
+                    valid_from: row.get(13)?,
+                    valid_until: row.get(14)?,
+                    temporal_type: row.get(15)?,
+                    expires_at: row.get(16)?,
+                    created_at: row.get(17)?,
+                    updated_at: row.get(18)?,
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        Ok(memories)
+    }
+
+    /// Insert exported memories, keeping their importance, type and timestamps.
+    /// Memories whose content hash matches an existing active memory (or an
+    /// earlier one in the same batch) are skipped. Returns (inserted, skipped).
+    pub fn import_memories(&self, memories: &[Memory]) -> SqliteResult<(usize, usize)> {
+        let conn = self.conn();
+        let tx = conn.unchecked_transaction()?;
+
+        let mut known: HashSet<String> = HashSet::new();
+        {
+            let mut stmt = tx.prepare(
+                "SELECT memory_type, identity_id, content FROM memories WHERE superseded_by IS NULL",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                let memory_type: String = row.get(0)?;
+                let identity_id: Option<String> = row.get(1)?;
+                let content: String = row.get(2)?;
+                Ok(memory_content_hash(&memory_type, identity_id.as_deref(), &content))
+            })?;
+            for hash in rows {
+                known.insert(hash?);
+            }
+        }
+
+        let (mut inserted, mut skipped) = (0, 0);
+        for memory in memories {
+            if !known.insert(memory.content_hash()) {
+                skipped += 1;
+                continue;
+            }
+            tx.execute(
+                &format!(
+                    "INSERT INTO memories ({})
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
+                    MEMORY_EXPORT_COLUMNS
+                ),
+                rusqlite::params![
+                    memory.memory_type,
+                    memory.content,
+                    memory.category,
+                    memory.tags,
+                    memory.importance,
+                    memory.identity_id,
+                    memory.source_channel_type,
+                    memory.log_date,
+                    memory.entity_type,
+                    memory.entity_name,
+                    memory.confidence,
+                    memory.source_type,
+                    memory.is_public as i64,
+                    memory.valid_from,
+                    memory.valid_until,
+                    memory.temporal_type,
+                    memory.expires_at,
+                    memory.created_at,
+                    memory.updated_at,
+                ],
+            )?;
+            inserted += 1;
+        }
+
+        tx.commit()?;
+        Ok((inserted, skipped))
+    }
+}
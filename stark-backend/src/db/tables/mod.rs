@@ -11,6 +11,7 @@ mod agent_settings; // agent_settings
 mod bot_settings;   // bot_settings
 mod chat_sessions;  // chat_sessions, session_messages (+ compaction)
 mod identities;     // identity_links
+pub mod memories;   // memories (structured preferences, export/import)
 mod tool_configs;   // tool_configs, tool_executions
 mod skills;         // skills, skill_scripts
 mod cron_jobs;      // cron_jobs, cron_job_runs
@@ -26,3 +27,8 @@ pub mod kanban;          // kanban_items (kanban board task management)
 pub mod modules;         // installed_modules (plugin system registry)
 pub mod telemetry;       // execution_spans, rollouts, attempts, resource_versions
 pub mod special_roles;   // special_roles, special_role_assignments (enriched safe mode)
+pub mod polls;           // polls, poll_votes (create_poll tool)
+pub mod deferred_messages; // deferred_messages (channel quiet hours)
+pub mod scheduled_messages; // scheduled_messages (schedule_message tool)
+pub mod processed_messages; // processed_messages (dispatch idempotency)
+pub mod context_bank;    // context_bank_items (per-identity persisted context bank)
@@ -0,0 +1,257 @@
+//! Poll database operations (create_poll tool)
+//!
+//! A poll is keyed by its poll_id, which is embedded in the interactive
+//! components posted to the platform so votes can be matched back to it.
+//! Each voter holds at most one vote per poll; voting again changes it.
+
+use chrono::{DateTime, Utc};
+use rusqlite::Result as SqliteResult;
+use serde::Serialize;
+
+use super::super::Database;
+
+/// Poll is open and accepting votes
+pub const POLL_STATUS_OPEN: &str = "open";
+/// Poll window has ended and results were reported
+pub const POLL_STATUS_CLOSED: &str = "closed";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Poll {
+    pub poll_id: String,
+    pub channel_id: i64,
+    pub channel_type: String,
+    /// Platform chat the poll was posted to (Discord channel, Telegram chat, Slack channel)
+    pub chat_id: String,
+    pub session_id: Option<i64>,
+    pub question: String,
+    pub options: Vec<String>,
+    /// "buttons" (interactive components) or "reactions" (emoji tally)
+    pub mode: String,
+    pub platform_message_id: Option<String>,
+    pub status: String,
+    pub closes_at: DateTime<Utc>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PollOptionTally {
+    pub option: String,
+    pub votes: i64,
+}
+
+/// Aggregated votes for a poll
+#[derive(Debug, Clone, Serialize)]
+pub struct PollTally {
+    pub poll_id: String,
+    pub question: String,
+    pub options: Vec<PollOptionTally>,
+    pub total_votes: i64,
+}
+
+impl PollTally {
+    /// Human-readable results, one line per option
+    pub fn summary(&self) -> String {
+        let mut out = format!("Poll: {}\nTotal votes: {}", self.question, self.total_votes);
+        for (i, option) in self.options.iter().enumerate() {
+            let pct = if self.total_votes > 0 {
+                option.votes as f64 * 100.0 / self.total_votes as f64
+            } else {
+                0.0
+            };
+            out.push_str(&format!("\n{}. {} — {} ({:.0}%)", i + 1, option.option, option.votes, pct));
+        }
+        out
+    }
+}
+
+const POLL_COLUMNS: &str = "poll_id, channel_id, channel_type, chat_id, session_id, question, options,
+                            mode, platform_message_id, status, closes_at, created_at";
+
+fn map_poll_row(row: &rusqlite::Row) -> rusqlite::Result<Poll> {
+    let options_str: String = row.get(6)?;
+    let closes_at_str: String = row.get(10)?;
+    let closes_at = DateTime::parse_from_rfc3339(&closes_at_str)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(10, rusqlite::types::Type::Text, Box::new(e)))?
+        .with_timezone(&Utc);
+    Ok(Poll {
+        poll_id: row.get(0)?,
+        channel_id: row.get(1)?,
+        channel_type: row.get(2)?,
+        chat_id: row.get(3)?,
+        session_id: row.get(4)?,
+        question: row.get(5)?,
+        options: serde_json::from_str(&options_str).unwrap_or_default(),
+        mode: row.get(7)?,
+        platform_message_id: row.get(8)?,
+        status: row.get(9)?,
+        closes_at,
+        created_at: row.get(11)?,
+    })
+}
+
+impl Database {
+    /// Store a new poll
+    pub fn create_poll(&self, poll: &Poll) -> SqliteResult<()> {
+        let conn = self.conn();
+        let options_json = serde_json::to_string(&poll.options).unwrap_or_else(|_| "[]".to_string());
+        conn.execute(
+            "INSERT INTO polls (poll_id, channel_id, channel_type, chat_id, session_id, question, options,
+                                mode, platform_message_id, status, closes_at, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            rusqlite::params![
+                poll.poll_id,
+                poll.channel_id,
+                poll.channel_type,
+                poll.chat_id,
+                poll.session_id,
+                poll.question,
+                options_json,
+                poll.mode,
+                poll.platform_message_id,
+                poll.status,
+                poll.closes_at.to_rfc3339(),
+                poll.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Record the platform message the poll was posted as
+    pub fn set_poll_message_id(&self, poll_id: &str, message_id: &str) -> SqliteResult<()> {
+        let conn = self.conn();
+        conn.execute(
+            "UPDATE polls SET platform_message_id = ?1 WHERE poll_id = ?2",
+            rusqlite::params![message_id, poll_id],
+        )?;
+        Ok(())
+    }
+
+    /// Get a poll by ID
+    pub fn get_poll(&self, poll_id: &str) -> SqliteResult<Option<Poll>> {
+        let conn = self.conn();
+        let result = conn.query_row(
+            &format!("SELECT {} FROM polls WHERE poll_id = ?1", POLL_COLUMNS),
+            [poll_id],
+            map_poll_row,
+        );
+        match result {
+            Ok(poll) => Ok(Some(poll)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Delete a poll and its votes (e.g. when posting it to the platform failed)
+    pub fn delete_poll(&self, poll_id: &str) -> SqliteResult<()> {
+        let conn = self.conn();
+        conn.execute("DELETE FROM poll_votes WHERE poll_id = ?1", [poll_id])?;
+        conn.execute("DELETE FROM polls WHERE poll_id = ?1", [poll_id])?;
+        Ok(())
+    }
+
+    /// Record (or change) a voter's choice.
+    /// Returns false if the poll is unknown, closed, past its window, or the option is out of range.
+    pub fn record_poll_vote(
+        &self,
+        poll_id: &str,
+        voter_id: &str,
+        voter_name: Option<&str>,
+        option_index: usize,
+    ) -> SqliteResult<bool> {
+        let poll = match self.get_poll(poll_id)? {
+            Some(p) => p,
+            None => return Ok(false),
+        };
+        if poll.status != POLL_STATUS_OPEN || poll.closes_at <= Utc::now() {
+            return Ok(false);
+        }
+        self.upsert_poll_vote(&poll, voter_id, voter_name, option_index)
+    }
+
+    /// Store a vote without checking the voting window.
+    /// Used when reactions are tallied as the poll closes.
+    pub fn upsert_poll_vote(
+        &self,
+        poll: &Poll,
+        voter_id: &str,
+        voter_name: Option<&str>,
+        option_index: usize,
+    ) -> SqliteResult<bool> {
+        if option_index >= poll.options.len() {
+            return Ok(false);
+        }
+        let conn = self.conn();
+        conn.execute(
+            "INSERT INTO poll_votes (poll_id, voter_id, voter_name, option_index, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(poll_id, voter_id) DO UPDATE SET
+                voter_name = excluded.voter_name,
+                option_index = excluded.option_index,
+                created_at = excluded.created_at",
+            rusqlite::params![poll.poll_id, voter_id, voter_name, option_index as i64, Utc::now().to_rfc3339()],
+        )?;
+        Ok(true)
+    }
+
+    /// Aggregate the votes recorded for a poll
+    pub fn tally_poll(&self, poll_id: &str) -> SqliteResult<Option<PollTally>> {
+        let poll = match self.get_poll(poll_id)? {
+            Some(p) => p,
+            None => return Ok(None),
+        };
+
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT option_index, COUNT(*) FROM poll_votes WHERE poll_id = ?1 GROUP BY option_index",
+        )?;
+        let counts: Vec<(i64, i64)> = stmt
+            .query_map([poll_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        let mut options: Vec<PollOptionTally> = poll
+            .options
+            .iter()
+            .map(|o| PollOptionTally { option: o.clone(), votes: 0 })
+            .collect();
+        let mut total_votes = 0;
+        for (index, count) in counts {
+            if let Some(option) = options.get_mut(index as usize) {
+                option.votes = count;
+                total_votes += count;
+            }
+        }
+
+        Ok(Some(PollTally {
+            poll_id: poll.poll_id,
+            question: poll.question,
+            options,
+            total_votes,
+        }))
+    }
+
+    /// List open polls whose voting window ended by `now`, soonest first.
+    /// Windows are compared as parsed timestamps, not as stored text.
+    pub fn list_due_polls(&self, now: DateTime<Utc>) -> SqliteResult<Vec<Poll>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM polls WHERE status = ?1", POLL_COLUMNS))?;
+        let mut polls: Vec<Poll> = stmt
+            .query_map([POLL_STATUS_OPEN], map_poll_row)?
+            .filter_map(|r| r.ok())
+            .filter(|poll| poll.closes_at <= now)
+            .collect();
+        polls.sort_by_key(|poll| poll.closes_at);
+        Ok(polls)
+    }
+
+    /// Mark a poll closed. Returns false if it was already closed
+    /// (so only one caller reports the results).
+    pub fn close_poll(&self, poll_id: &str) -> SqliteResult<bool> {
+        let conn = self.conn();
+        let changed = conn.execute(
+            "UPDATE polls SET status = ?1 WHERE poll_id = ?2 AND status = ?3",
+            rusqlite::params![POLL_STATUS_CLOSED, poll_id, POLL_STATUS_OPEN],
+        )?;
+        Ok(changed > 0)
+    }
+}
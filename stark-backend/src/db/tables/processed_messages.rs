@@ -0,0 +1,67 @@
+//! Processed message ids (dispatch idempotency)
+//!
+//! Chat platforms sometimes redeliver a message (webhook retries, gateway
+//! reconnects). Each dispatched platform message id is recorded here so a
+//! redelivery within the TTL is recognized and not processed twice.
+
+use chrono::{DateTime, Utc};
+use rusqlite::Result as SqliteResult;
+
+use super::super::Database;
+
+impl Database {
+    /// Claim a platform message for dispatch. Returns false if the same message
+    /// was already claimed after `expired_before` (a redelivery); an older claim
+    /// has expired and is taken over.
+    pub fn claim_processed_message(
+        &self,
+        channel_type: &str,
+        channel_id: i64,
+        chat_id: &str,
+        message_id: &str,
+        expired_before: DateTime<Utc>,
+    ) -> SqliteResult<bool> {
+        let conn = self.conn();
+        let changed = conn.execute(
+            "INSERT INTO processed_messages (channel_type, channel_id, chat_id, message_id, processed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(channel_type, channel_id, chat_id, message_id)
+             DO UPDATE SET processed_at = excluded.processed_at WHERE processed_at < ?6",
+            rusqlite::params![
+                channel_type,
+                channel_id,
+                chat_id,
+                message_id,
+                Utc::now().to_rfc3339(),
+                expired_before.to_rfc3339(),
+            ],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// Give up a claim so a redelivery of the message is dispatched again
+    pub fn release_processed_message(
+        &self,
+        channel_type: &str,
+        channel_id: i64,
+        chat_id: &str,
+        message_id: &str,
+    ) -> SqliteResult<()> {
+        let conn = self.conn();
+        conn.execute(
+            "DELETE FROM processed_messages
+             WHERE channel_type = ?1 AND channel_id = ?2 AND chat_id = ?3 AND message_id = ?4",
+            rusqlite::params![channel_type, channel_id, chat_id, message_id],
+        )?;
+        Ok(())
+    }
+
+    /// Delete processed message ids claimed before `cutoff`
+    pub fn cleanup_processed_messages(&self, cutoff: DateTime<Utc>) -> SqliteResult<usize> {
+        let conn = self.conn();
+        conn.execute(
+            "DELETE FROM processed_messages WHERE processed_at < ?1",
+            [cutoff.to_rfc3339()],
+        )
+    }
+}
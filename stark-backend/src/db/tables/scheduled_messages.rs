@@ -0,0 +1,134 @@
+//! Scheduled message database operations (schedule_message tool)
+//!
+//! A scheduled message is stored as `pending` with the time it should be
+//! sent. The scheduler delivers due messages through their channel and marks
+//! them `sent` or `failed`, so pending messages survive restarts.
+
+use chrono::{DateTime, Utc};
+use rusqlite::Result as SqliteResult;
+use serde::Serialize;
+
+use super::super::Database;
+
+/// Waiting for its send time
+pub const SCHEDULED_STATUS_PENDING: &str = "pending";
+/// Delivered to the channel
+pub const SCHEDULED_STATUS_SENT: &str = "sent";
+/// Delivery was attempted and failed (see `error`)
+pub const SCHEDULED_STATUS_FAILED: &str = "failed";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledMessage {
+    pub id: i64,
+    pub channel_id: i64,
+    pub channel_type: String,
+    /// Platform chat to deliver to (Discord channel, Telegram chat, Slack channel)
+    pub chat_id: String,
+    /// Who scheduled it; pending messages are limited per owner
+    pub identity_id: String,
+    pub text: String,
+    pub send_at: String,
+    pub status: String,
+    pub error: Option<String>,
+    pub created_at: String,
+    pub sent_at: Option<String>,
+}
+
+const SCHEDULED_COLUMNS: &str = "id, channel_id, channel_type, chat_id, identity_id, text, send_at, status, error,
+                                 created_at, sent_at";
+
+fn map_scheduled_row(row: &rusqlite::Row) -> rusqlite::Result<ScheduledMessage> {
+    Ok(ScheduledMessage {
+        id: row.get(0)?,
+        channel_id: row.get(1)?,
+        channel_type: row.get(2)?,
+        chat_id: row.get(3)?,
+        identity_id: row.get(4)?,
+        text: row.get(5)?,
+        send_at: row.get(6)?,
+        status: row.get(7)?,
+        error: row.get(8)?,
+        created_at: row.get(9)?,
+        sent_at: row.get(10)?,
+    })
+}
+
+impl Database {
+    /// Store a pending message to be sent at `send_at`
+    pub fn create_scheduled_message(
+        &self,
+        channel_id: i64,
+        channel_type: &str,
+        chat_id: &str,
+        identity_id: &str,
+        text: &str,
+        send_at: DateTime<Utc>,
+    ) -> SqliteResult<i64> {
+        let conn = self.conn();
+        conn.execute(
+            "INSERT INTO scheduled_messages (channel_id, channel_type, chat_id, identity_id, text, send_at, status, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            rusqlite::params![
+                channel_id,
+                channel_type,
+                chat_id,
+                identity_id,
+                text,
+                send_at.to_rfc3339(),
+                SCHEDULED_STATUS_PENDING,
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Number of messages an owner has waiting to be sent
+    pub fn count_pending_scheduled_messages(&self, identity_id: &str) -> SqliteResult<i64> {
+        let conn = self.conn();
+        conn.query_row(
+            "SELECT COUNT(*) FROM scheduled_messages WHERE identity_id = ?1 AND status = ?2",
+            rusqlite::params![identity_id, SCHEDULED_STATUS_PENDING],
+            |row| row.get(0),
+        )
+    }
+
+    /// Pending messages whose send time is at or before `now`, oldest first
+    pub fn list_due_scheduled_messages(&self, now: DateTime<Utc>) -> SqliteResult<Vec<ScheduledMessage>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM scheduled_messages WHERE status = ?1 AND send_at <= ?2 ORDER BY send_at ASC, id ASC",
+            SCHEDULED_COLUMNS
+        ))?;
+        let messages = stmt
+            .query_map(rusqlite::params![SCHEDULED_STATUS_PENDING, now.to_rfc3339()], map_scheduled_row)?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(messages)
+    }
+
+    pub fn get_scheduled_message(&self, id: i64) -> SqliteResult<Option<ScheduledMessage>> {
+        let conn = self.conn();
+        let result = conn.query_row(
+            &format!("SELECT {} FROM scheduled_messages WHERE id = ?1", SCHEDULED_COLUMNS),
+            [id],
+            map_scheduled_row,
+        );
+        match result {
+            Ok(message) => Ok(Some(message)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Record the outcome of a delivery attempt. Only pending messages are
+    /// updated, so a message is never reported twice.
+    pub fn finish_scheduled_message(&self, id: i64, error: Option<&str>) -> SqliteResult<bool> {
+        let conn = self.conn();
+        let status = if error.is_some() { SCHEDULED_STATUS_FAILED } else { SCHEDULED_STATUS_SENT };
+        let changed = conn.execute(
+            "UPDATE scheduled_messages SET status = ?1, error = ?2, sent_at = ?3 WHERE id = ?4 AND status = ?5",
+            rusqlite::params![status, error, Utc::now().to_rfc3339(), id, SCHEDULED_STATUS_PENDING],
+        )?;
+        Ok(changed > 0)
+    }
+}
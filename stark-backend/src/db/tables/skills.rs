@@ -97,10 +97,11 @@ impl Database {
         let arguments_json = serde_json::to_string(&skill.arguments).unwrap_or_default();
         let tags_json = serde_json::to_string(&skill.tags).unwrap_or_default();
         let requires_api_keys_json = serde_json::to_string(&skill.requires_api_keys).unwrap_or_default();
+        let model_override_json = skill.model_override.as_ref().and_then(|o| serde_json::to_string(o).ok());
 
         conn.execute(
-            "INSERT INTO skills (name, description, body, version, author, homepage, metadata, enabled, requires_tools, requires_binaries, arguments, tags, subagent_type, requires_api_keys, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?15)
+            "INSERT INTO skills (name, description, body, version, author, homepage, metadata, enabled, requires_tools, requires_binaries, arguments, tags, subagent_type, requires_api_keys, created_at, updated_at, model_override)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?15, ?16)
              ON CONFLICT(name) DO UPDATE SET
                 description = excluded.description,
                 body = excluded.body,
@@ -114,7 +115,8 @@ impl Database {
                 tags = excluded.tags,
                 subagent_type = excluded.subagent_type,
                 requires_api_keys = excluded.requires_api_keys,
-                updated_at = excluded.updated_at",
+                updated_at = excluded.updated_at,
+                model_override = excluded.model_override",
             rusqlite::params![
                 skill.name,
                 skill.description,
@@ -130,7 +132,8 @@ impl Database {
                 tags_json,
                 skill.subagent_type,
                 requires_api_keys_json,
-                now
+                now,
+                model_override_json
             ],
         )?;
 
@@ -141,7 +144,7 @@ impl Database {
     pub fn get_skill(&self, name: &str) -> SqliteResult<Option<DbSkill>> {
         let conn = self.conn();
         let mut stmt = conn.prepare(
-            "SELECT id, name, description, body, version, author, homepage, metadata, enabled, requires_tools, requires_binaries, arguments, tags, subagent_type, requires_api_keys, created_at, updated_at
+            "SELECT id, name, description, body, version, author, homepage, metadata, enabled, requires_tools, requires_binaries, arguments, tags, subagent_type, requires_api_keys, created_at, updated_at, model_override
              FROM skills WHERE name = ?1"
         )?;
 
@@ -156,7 +159,7 @@ impl Database {
     pub fn get_skill_by_id(&self, id: i64) -> SqliteResult<Option<DbSkill>> {
         let conn = self.conn();
         let mut stmt = conn.prepare(
-            "SELECT id, name, description, body, version, author, homepage, metadata, enabled, requires_tools, requires_binaries, arguments, tags, subagent_type, requires_api_keys, created_at, updated_at
+            "SELECT id, name, description, body, version, author, homepage, metadata, enabled, requires_tools, requires_binaries, arguments, tags, subagent_type, requires_api_keys, created_at, updated_at, model_override
              FROM skills WHERE id = ?1"
         )?;
 
@@ -171,7 +174,7 @@ impl Database {
     pub fn get_enabled_skill_by_name(&self, name: &str) -> SqliteResult<Option<DbSkill>> {
         let conn = self.conn();
         let mut stmt = conn.prepare(
-            "SELECT id, name, description, body, version, author, homepage, metadata, enabled, requires_tools, requires_binaries, arguments, tags, subagent_type, requires_api_keys, created_at, updated_at
+            "SELECT id, name, description, body, version, author, homepage, metadata, enabled, requires_tools, requires_binaries, arguments, tags, subagent_type, requires_api_keys, created_at, updated_at, model_override
              FROM skills WHERE name = ?1 AND enabled = 1 LIMIT 1"
         )?;
 
@@ -186,7 +189,7 @@ impl Database {
     pub fn list_skills(&self) -> SqliteResult<Vec<DbSkill>> {
         let conn = self.conn();
         let mut stmt = conn.prepare(
-            "SELECT id, name, description, body, version, author, homepage, metadata, enabled, requires_tools, requires_binaries, arguments, tags, subagent_type, requires_api_keys, created_at, updated_at
+            "SELECT id, name, description, body, version, author, homepage, metadata, enabled, requires_tools, requires_binaries, arguments, tags, subagent_type, requires_api_keys, created_at, updated_at, model_override
              FROM skills ORDER BY name"
         )?;
 
@@ -202,7 +205,7 @@ impl Database {
     pub fn list_enabled_skills(&self) -> SqliteResult<Vec<DbSkill>> {
         let conn = self.conn();
         let mut stmt = conn.prepare(
-            "SELECT id, name, description, body, version, author, homepage, metadata, enabled, requires_tools, requires_binaries, arguments, tags, subagent_type, requires_api_keys, created_at, updated_at
+            "SELECT id, name, description, body, version, author, homepage, metadata, enabled, requires_tools, requires_binaries, arguments, tags, subagent_type, requires_api_keys, created_at, updated_at, model_override
              FROM skills WHERE enabled = 1 ORDER BY name"
         )?;
 
@@ -259,6 +262,9 @@ impl Database {
             tags: serde_json::from_str(&tags_str).unwrap_or_default(),
             subagent_type: row.get::<_, Option<String>>(13)?,
             requires_api_keys: serde_json::from_str(&requires_api_keys_str).unwrap_or_default(),
+            model_override: row
+                .get::<_, Option<String>>(17)?
+                .and_then(|json| serde_json::from_str(&json).ok()),
             created_at: row.get(15)?,
             updated_at: row.get(16)?,
         })
@@ -1,12 +1,13 @@
 //! Telemetry database operations - execution_spans, rollouts, attempts, resource_versions
 
 use chrono::{DateTime, Utc};
-use rusqlite::Result as SqliteResult;
+use rusqlite::{OptionalExtension, Result as SqliteResult};
 use serde_json::Value;
 
 use super::super::Database;
 use crate::telemetry::resource_version::ResourceBundle;
 use crate::telemetry::span::{Span, SpanStatus, SpanType};
+use crate::telemetry::store::{RolloutOutcome, RolloutQuery};
 
 impl Database {
     // ============================================
@@ -180,6 +181,7 @@ impl Database {
                 serde_json::to_string(&rollout.metadata).unwrap_or_default(),
             ],
         )?;
+        drop(conn);
 
         // Create the first attempt
         if let Some(attempt) = rollout.attempts.first() {
@@ -189,6 +191,96 @@ impl Database {
         Ok(())
     }
 
+    /// Record the resource version a rollout runs with.
+    pub fn set_rollout_resources_id(&self, rollout_id: &str, resources_id: Option<&str>) -> SqliteResult<()> {
+        let conn = self.conn();
+        conn.execute(
+            "UPDATE rollouts SET resources_id = ?1 WHERE rollout_id = ?2",
+            rusqlite::params![resources_id, rollout_id],
+        )?;
+        Ok(())
+    }
+
+    /// Get the resource version a rollout ran with, if recorded.
+    pub fn get_rollout_resources_id(&self, rollout_id: &str) -> SqliteResult<Option<String>> {
+        let conn = self.conn();
+        conn.query_row(
+            "SELECT resources_id FROM rollouts WHERE rollout_id = ?1",
+            [rollout_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map(|id| id.flatten())
+    }
+
+    /// Attach a rollout to its session once the session is resolved.
+    pub fn set_rollout_session(&self, rollout_id: &str, session_id: i64) -> SqliteResult<()> {
+        let conn = self.conn();
+        conn.execute(
+            "UPDATE rollouts SET session_id = ?1 WHERE rollout_id = ?2",
+            rusqlite::params![session_id, rollout_id],
+        )?;
+        Ok(())
+    }
+
+    /// Finished rollouts matching the query, newest first, with their attempt
+    /// count and session reward.
+    pub fn query_rollouts(&self, query: &RolloutQuery) -> SqliteResult<Vec<RolloutOutcome>> {
+        let conn = self.conn();
+
+        let mut sql = String::from(
+            "SELECT r.rollout_id, r.session_id, r.channel_id, r.status, r.created_at, r.duration_ms,
+                    COALESCE(r.result, r.error),
+                    (SELECT COUNT(*) FROM attempts a WHERE a.rollout_id = r.rollout_id),
+                    (SELECT json_extract(s.attributes, '$.reward_value') FROM execution_spans s
+                     WHERE s.rollout_id = r.rollout_id AND s.span_type = 'reward' AND s.name = 'session_completed'
+                     ORDER BY s.sequence_id DESC LIMIT 1)
+             FROM rollouts r WHERE r.completed_at IS NOT NULL"
+        );
+        let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
+
+        if let Some(sid) = query.session_id {
+            sql.push_str(&format!(" AND r.session_id = ?{}", params.len() + 1));
+            params.push(Box::new(sid));
+        }
+
+        if let Some(cid) = query.channel_id {
+            sql.push_str(&format!(" AND r.channel_id = ?{}", params.len() + 1));
+            params.push(Box::new(cid));
+        }
+
+        match query.succeeded {
+            Some(true) => sql.push_str(" AND r.status = 'succeeded'"),
+            Some(false) => sql.push_str(" AND r.status IN ('failed', 'cancelled')"),
+            None => {}
+        }
+
+        sql.push_str(&format!(" ORDER BY r.created_at DESC LIMIT {}", query.limit));
+
+        let mut stmt = conn.prepare(&sql)?;
+        let param_refs: Vec<&dyn rusqlite::types::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+        let rollouts = stmt
+            .query_map(param_refs.as_slice(), |row| {
+                let created_at_str: String = row.get(4)?;
+                Ok(RolloutOutcome {
+                    rollout_id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    channel_id: row.get(2)?,
+                    status: row.get(3)?,
+                    created_at: DateTime::parse_from_rfc3339(&created_at_str)
+                        .map(|dt| dt.with_timezone(&Utc))
+                        .unwrap_or_else(|_| Utc::now()),
+                    duration_ms: row.get(5)?,
+                    summary: row.get(6)?,
+                    attempts: row.get(7)?,
+                    reward: row.get(8)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(rollouts)
+    }
+
     pub fn update_rollout_status(&self, rollout_id: &str, status: &str) -> SqliteResult<()> {
         let conn = self.conn();
         conn.execute(
@@ -264,6 +356,55 @@ impl Database {
         Ok(())
     }
 
+    /// Save the tool history (JSON) recorded for an attempt.
+    pub fn set_attempt_tool_history(
+        &self,
+        rollout_id: &str,
+        attempt_idx: u32,
+        tool_history: &str,
+    ) -> SqliteResult<()> {
+        let conn = self.conn();
+        conn.execute(
+            "UPDATE attempts SET tool_history = ?1 WHERE rollout_id = ?2 AND attempt_idx = ?3",
+            rusqlite::params![tool_history, rollout_id, attempt_idx],
+        )?;
+        Ok(())
+    }
+
+    /// Get the tool history (JSON) saved for an attempt, if any.
+    pub fn get_attempt_tool_history(&self, rollout_id: &str, attempt_idx: u32) -> SqliteResult<Option<String>> {
+        let conn = self.conn();
+        conn.query_row(
+            "SELECT tool_history FROM attempts WHERE rollout_id = ?1 AND attempt_idx = ?2",
+            rusqlite::params![rollout_id, attempt_idx],
+            |row| row.get(0),
+        )
+        .optional()
+        .map(|history| history.flatten())
+    }
+
+    /// Record which model served an attempt.
+    pub fn set_attempt_model(&self, rollout_id: &str, attempt_idx: u32, model: &str) -> SqliteResult<()> {
+        let conn = self.conn();
+        conn.execute(
+            "UPDATE attempts SET model = ?1 WHERE rollout_id = ?2 AND attempt_idx = ?3",
+            rusqlite::params![model, rollout_id, attempt_idx],
+        )?;
+        Ok(())
+    }
+
+    /// Get the model recorded for an attempt, if any.
+    pub fn get_attempt_model(&self, rollout_id: &str, attempt_idx: u32) -> SqliteResult<Option<String>> {
+        let conn = self.conn();
+        conn.query_row(
+            "SELECT model FROM attempts WHERE rollout_id = ?1 AND attempt_idx = ?2",
+            rusqlite::params![rollout_id, attempt_idx],
+            |row| row.get(0),
+        )
+        .optional()
+        .map(|model| model.flatten())
+    }
+
     // ============================================
     // Resource version operations
     // ============================================
@@ -313,6 +454,21 @@ impl Database {
         }
     }
 
+    pub fn get_resource_bundle(&self, version_id: &str) -> SqliteResult<Option<ResourceBundle>> {
+        let conn = self.conn();
+        let result = conn.query_row(
+            "SELECT version_id, label, is_active, resources, description, created_at
+             FROM resource_versions WHERE version_id = ?1",
+            [version_id],
+            Self::row_to_resource_bundle,
+        );
+        match result {
+            Ok(bundle) => Ok(Some(bundle)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
     pub fn get_latest_resource_bundle(&self) -> SqliteResult<Option<ResourceBundle>> {
         let conn = self.conn();
         let result = conn.query_row(
@@ -373,6 +529,7 @@ fn parse_span_type(s: &str) -> SpanType {
         "rollout" => SpanType::Rollout,
         "watchdog" => SpanType::Watchdog,
         "resource_resolution" => SpanType::ResourceResolution,
+        "channel_send" => SpanType::ChannelSend,
         _ => SpanType::Annotation,
     }
 }
@@ -17,6 +17,7 @@ pub mod config;
 pub mod db;
 pub mod tools;
 
+use crate::text::truncate_chars;
 use rand::seq::SliceRandom;
 use serenity::all::{Context, Message, UserId};
 
@@ -101,7 +102,7 @@ pub fn is_bot_mentioned(msg: &Message, bot_id: UserId) -> bool {
     if in_content && !in_mentions {
         log::warn!(
             "Discord hooks: Bot mention found in content but NOT in mentions array! content='{}', mentions={:?}",
-            if msg.content.len() > 100 { format!("{}...", &msg.content[..100]) } else { msg.content.clone() },
+            truncate_chars(&msg.content, 100),
             msg.mentions.iter().map(|u| u.id.to_string()).collect::<Vec<_>>()
         );
     }
@@ -158,7 +159,7 @@ pub async fn process(
         "Discord hooks: Message from {} - mentions={:?}, content_preview='{}', reply_to_bot={}",
         msg.author.name,
         msg.mentions.iter().map(|u| format!("{}({})", u.name, u.id)).collect::<Vec<_>>(),
-        if msg.content.len() > 100 { format!("{}...", &msg.content[..100]) } else { msg.content.clone() },
+        truncate_chars(&msg.content, 100),
         is_reply_to_bot
     );
 
@@ -218,11 +219,7 @@ pub async fn process(
         user_id,
         is_admin,
         config.has_explicit_admins(),
-        if command_text.len() > 50 {
-            format!("{}...", &command_text[..50])
-        } else {
-            command_text.clone()
-        }
+        truncate_chars(&command_text, 50)
     );
 
     if is_admin {
@@ -291,11 +288,7 @@ pub async fn process(
         log::info!(
             "Discord hooks: Admin {} forwarding to agent: '{}'",
             user_name,
-            if command_text.len() > 50 {
-                format!("{}...", &command_text[..50])
-            } else {
-                command_text.clone()
-            }
+            truncate_chars(&command_text, 50)
         );
         Ok(ProcessResult::forward_to_agent(ForwardRequest {
             text: command_text,
@@ -329,7 +322,7 @@ pub async fn process(
                 log::info!(
                     "Discord hooks: Non-admin {} querying with safe mode: '{}'",
                     user_name,
-                    command_text.chars().take(50).collect::<String>()
+                    truncate_chars(&command_text, 50)
                 );
                 Ok(ProcessResult::forward_to_agent(ForwardRequest {
                     text: command_text,
@@ -1,3 +1,4 @@
+use crate::text::truncate_chars;
 use crate::gateway::events::EventBroadcaster;
 use crate::gateway::protocol::GatewayEvent;
 use crate::models::{ExecutionTask, TaskMetrics, TaskStatus, TaskType};
@@ -298,16 +299,8 @@ impl ExecutionTracker {
         // Create descriptive execution task based on user message
         let (description, active_form) = match user_message {
             Some(msg) => {
-                let truncated = if msg.len() > 60 {
-                    format!("{}...", &msg[..57])
-                } else {
-                    msg.to_string()
-                };
-                let short = if msg.len() > 30 {
-                    format!("{}...", &msg[..27])
-                } else {
-                    msg.to_string()
-                };
+                let truncated = truncate_chars(msg, 60);
+                let short = truncate_chars(msg, 30);
                 (truncated, short)
             }
             None => {
@@ -512,21 +505,13 @@ impl ExecutionTracker {
                             .split('/')
                             .next()
                             .unwrap_or(&url);
-                        let short_url = if url.len() > 60 {
-                            format!("{}...", &url[..57])
-                        } else {
-                            url.to_string()
-                        };
+                        let short_url = truncate_chars(&url, 60);
                         (format!("curl {}", short_url), format!("Calling {}", host))
                     } else {
                         ("Running curl".to_string(), "Running curl".to_string())
                     }
                 } else {
-                    let short_cmd = if cmd.len() > 50 {
-                        format!("{}...", &cmd[..47])
-                    } else {
-                        cmd.to_string()
-                    };
+                    let short_cmd = truncate_chars(cmd, 50);
                     (format!("Running: {}", short_cmd), format!("Running {}", first_word))
                 }
             }
@@ -541,11 +526,7 @@ impl ExecutionTracker {
                 let input = args.get("input")
                     .and_then(|v| v.as_str())
                     .unwrap_or("");
-                let short_input = if input.len() > 40 {
-                    format!("{}...", &input[..37])
-                } else {
-                    input.to_string()
-                };
+                let short_input = truncate_chars(input, 40);
                 if input.is_empty() {
                     (format!("Using skill: {}", skill), format!("Using {}", skill))
                 } else {
@@ -559,11 +540,7 @@ impl ExecutionTracker {
                     .or_else(|| args.get("description"))
                     .and_then(|v| v.as_str())
                     .unwrap_or("task");
-                let short_task = if task.len() > 40 {
-                    format!("{}...", &task[..37])
-                } else {
-                    task.to_string()
-                };
+                let short_task = truncate_chars(task, 40);
                 (format!("Agent: {}", short_task), "Running agent".to_string())
             }
 
@@ -576,11 +553,7 @@ impl ExecutionTracker {
                     .and_then(|v| v.as_str())
                     .or_else(|| args.get("queries").and_then(|v| v.as_array()).map(|_| "multiple queries"))
                     .unwrap_or("...");
-                let short = if query.len() > 30 {
-                    format!("{}...", &query[..27])
-                } else {
-                    query.to_string()
-                };
+                let short = truncate_chars(query, 30);
                 (format!("Recalling: {}", short), "Searching memory".to_string())
             }
 
@@ -596,11 +569,7 @@ impl ExecutionTracker {
             // User interaction
             "ask_user" => {
                 let question = args.get("question").and_then(|v| v.as_str()).unwrap_or("question");
-                let short_q = if question.len() > 30 {
-                    format!("{}...", &question[..30])
-                } else {
-                    question.to_string()
-                };
+                let short_q = truncate_chars(question, 30);
                 (format!("Asking: {}", short_q), "Asking user".to_string())
             }
 
@@ -6,7 +6,11 @@ use crate::channels::ChannelManager;
 use crate::db::Database;
 use crate::gateway::events::EventBroadcaster;
 use crate::gateway::methods;
-use crate::gateway::protocol::{ChannelIdParams, RpcError, RpcRequest, RpcResponse};
+use crate::gateway::protocol::{
+    ChannelIdParams, GatewayEvent, RpcError, RpcRequest, RpcResponse, SubscribeTelemetryParams,
+    UnsubscribeTelemetryParams,
+};
+use crate::telemetry;
 use crate::tx_queue::TxQueueManager;
 use crate::wallet::WalletProvider;
 use actix_web::{web, HttpRequest, HttpResponse};
@@ -20,6 +24,9 @@ use tokio::sync::mpsc;
 /// Authentication timeout - client must authenticate within this time
 const AUTH_TIMEOUT_SECS: u64 = 30;
 
+/// Max live telemetry subscriptions a single connection may hold
+const MAX_TELEMETRY_SUBSCRIPTIONS_PER_CLIENT: usize = 4;
+
 /// Parameters for the auth RPC method
 #[derive(Debug, Deserialize)]
 struct AuthParams {
@@ -179,12 +186,18 @@ async fn handle_ws_connection(
         }
     });
 
+    // Live telemetry subscriptions held by this connection (subscription_id → forwarder)
+    let mut telemetry_subs: Vec<(u64, tokio::task::JoinHandle<()>)> = Vec::new();
+
     // Process incoming messages
     while let Some(msg_result) = msg_stream.next().await {
         match msg_result {
             Ok(AggregatedMessage::Text(text)) => {
                 log::debug!("[DATAGRAM] <<< FROM AGENT (RPC request):\n{}", text);
-                let response = process_request(&text, &db, &channel_manager, &broadcaster, &tx_queue, &wallet_provider).await;
+                let response = match handle_telemetry_request(&text, &mut telemetry_subs, &tx) {
+                    Some(response) => response,
+                    None => process_request(&text, &db, &channel_manager, &broadcaster, &tx_queue, &wallet_provider).await,
+                };
                 if let Ok(json) = serde_json::to_string(&response) {
                     let _ = tx.send(json).await;
                 }
@@ -206,6 +219,10 @@ async fn handle_ws_connection(
     }
 
     // Cleanup
+    for (subscription_id, forwarder) in telemetry_subs {
+        telemetry::span_feed().unsubscribe(subscription_id);
+        forwarder.abort();
+    }
     broadcaster.unsubscribe(&client_id);
     send_task.abort();
     let _ = session.close(None).await;
@@ -329,6 +346,93 @@ async fn wait_for_auth(
     Ok(false)
 }
 
+/// Handle the connection-scoped telemetry methods. Returns `None` for any other
+/// method so it falls through to the shared dispatcher.
+fn handle_telemetry_request(
+    text: &str,
+    subscriptions: &mut Vec<(u64, tokio::task::JoinHandle<()>)>,
+    tx: &mpsc::Sender<String>,
+) -> Option<RpcResponse> {
+    let request: RpcRequest = serde_json::from_str(text).ok()?;
+    let result = match request.method.as_str() {
+        "subscribe_telemetry" => subscribe_telemetry(&request, subscriptions, tx),
+        "unsubscribe_telemetry" => unsubscribe_telemetry(&request, subscriptions),
+        _ => return None,
+    };
+    Some(match result {
+        Ok(value) => RpcResponse::success(request.id, value),
+        Err(error) => RpcResponse::error(request.id, error),
+    })
+}
+
+/// Stream spans for a session to this connection as they are recorded
+fn subscribe_telemetry(
+    request: &RpcRequest,
+    subscriptions: &mut Vec<(u64, tokio::task::JoinHandle<()>)>,
+    tx: &mpsc::Sender<String>,
+) -> Result<serde_json::Value, RpcError> {
+    let params: SubscribeTelemetryParams = serde_json::from_value(request.params.clone())
+        .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+    subscriptions.retain(|(_, forwarder)| !forwarder.is_finished());
+    if subscriptions.len() >= MAX_TELEMETRY_SUBSCRIPTIONS_PER_CLIENT {
+        return Err(RpcError::new(
+            -32003,
+            format!(
+                "Too many telemetry subscriptions on this connection (max {})",
+                MAX_TELEMETRY_SUBSCRIPTIONS_PER_CLIENT
+            ),
+        ));
+    }
+
+    let (subscription_id, mut span_rx) = telemetry::span_feed()
+        .subscribe(params.session_id)
+        .map_err(|e| RpcError::new(-32003, e))?;
+
+    let tx = tx.clone();
+    let forwarder = tokio::spawn(async move {
+        while let Some(span) = span_rx.recv().await {
+            let event = GatewayEvent::telemetry_span(subscription_id, &span);
+            let Ok(json) = serde_json::to_string(&event) else { continue };
+            if tx.send(json).await.is_err() {
+                break;
+            }
+        }
+        telemetry::span_feed().unsubscribe(subscription_id);
+    });
+    subscriptions.push((subscription_id, forwarder));
+
+    log::info!(
+        "[TELEMETRY] Live subscription {} for session {}",
+        subscription_id,
+        params.session_id
+    );
+    Ok(serde_json::json!({
+        "subscription_id": subscription_id,
+        "session_id": params.session_id,
+    }))
+}
+
+/// Stop a live telemetry stream held by this connection
+fn unsubscribe_telemetry(
+    request: &RpcRequest,
+    subscriptions: &mut Vec<(u64, tokio::task::JoinHandle<()>)>,
+) -> Result<serde_json::Value, RpcError> {
+    let params: UnsubscribeTelemetryParams = serde_json::from_value(request.params.clone())
+        .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))?;
+
+    let Some(pos) = subscriptions.iter().position(|(id, _)| *id == params.subscription_id) else {
+        return Err(RpcError::invalid_params(format!(
+            "Unknown subscription_id {}",
+            params.subscription_id
+        )));
+    };
+    let (subscription_id, forwarder) = subscriptions.remove(pos);
+    telemetry::span_feed().unsubscribe(subscription_id);
+    forwarder.abort();
+    Ok(serde_json::json!({ "unsubscribed": subscription_id }))
+}
+
 async fn process_request(
     text: &str,
     db: &Arc<Database>,
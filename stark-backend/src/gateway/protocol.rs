@@ -12,12 +12,14 @@ pub enum EventType {
     ChannelMessage,
     // Agent events
     AgentResponse,
+    AgentTokenDelta,   // Incremental chunk of a streamed response
     AgentToolCall,     // Real-time tool call notification for chat display
     AgentModeChange,   // Multi-agent mode transition (Explore/Plan/Perform)
     AgentSubtypeChange, // Agent subtype change
     AgentThinking,     // Progress update during long AI calls
     AgentError,        // Error notification (timeout, etc.)
     AgentWarning,      // Warning when agent tries to skip tool calls
+    AgentRateLimited,  // Message rejected by the per-identity rate limit
     // Tool events
     ToolExecution,
     ToolResult,
@@ -34,6 +36,9 @@ pub enum EventType {
     ExecutionStopped,
     // Payment events
     X402Payment,
+    X402CircuitBreaker,
+    X402SessionSpend,
+    X402BudgetExceeded,
     // Confirmation events
     ConfirmationRequired,
     ConfirmationApproved,
@@ -77,6 +82,7 @@ pub enum EventType {
     CronExecutionStoppedOnChannel,  // Cron job stopped on web channel
     // AI client events
     AiRetrying,  // AI API call is being retried after transient error
+    AiModelFallback,  // Primary model failed, switched to the next fallback model
     // Transaction queue confirmation events (partner mode)
     TxQueueConfirmationRequired,  // Pending tx needs user confirmation
     TxQueueConfirmed,             // User confirmed, tx broadcast
@@ -86,6 +92,8 @@ pub enum EventType {
     // Telemetry events
     SpanEmitted,        // A telemetry span was emitted (for real-time telemetry streaming)
     RolloutStatusChange, // Rollout lifecycle status changed
+    RolloutQueued,       // All rollout slots busy, message is waiting for one
+    Heartbeat,           // Periodic progress of a running dispatch (iteration, tasks, elapsed)
 }
 
 impl EventType {
@@ -96,12 +104,14 @@ impl EventType {
             Self::ChannelError => "channel.error",
             Self::ChannelMessage => "channel.message",
             Self::AgentResponse => "agent.response",
+            Self::AgentTokenDelta => "agent.token_delta",
             Self::AgentToolCall => "agent.tool_call",
             Self::AgentModeChange => "agent.mode_change",
             Self::AgentSubtypeChange => "agent.subtype_change",
             Self::AgentThinking => "agent.thinking",
             Self::AgentError => "agent.error",
             Self::AgentWarning => "agent.warning",
+            Self::AgentRateLimited => "agent.rate_limited",
             Self::ToolExecution => "tool.execution",
             Self::ToolResult => "tool.result",
             Self::ToolWaiting => "tool.waiting",
@@ -114,6 +124,9 @@ impl EventType {
             Self::ExecutionCompleted => "execution.completed",
             Self::ExecutionStopped => "execution.stopped",
             Self::X402Payment => "x402.payment",
+            Self::X402CircuitBreaker => "x402.circuit_breaker",
+            Self::X402SessionSpend => "x402.session_spend",
+            Self::X402BudgetExceeded => "x402.budget_exceeded",
             Self::ConfirmationRequired => "confirmation.required",
             Self::ConfirmationApproved => "confirmation.approved",
             Self::ConfirmationRejected => "confirmation.rejected",
@@ -146,12 +159,15 @@ impl EventType {
             Self::CronExecutionStartedOnChannel => "cron.execution_started_on_channel",
             Self::CronExecutionStoppedOnChannel => "cron.execution_stopped_on_channel",
             Self::AiRetrying => "ai.retrying",
+            Self::AiModelFallback => "ai.model_fallback",
             Self::TxQueueConfirmationRequired => "tx_queue.confirmation_required",
             Self::TxQueueConfirmed => "tx_queue.confirmed",
             Self::TxQueueDenied => "tx_queue.denied",
             Self::ContextCompacting => "context.compacting",
             Self::SpanEmitted => "telemetry.span_emitted",
             Self::RolloutStatusChange => "telemetry.rollout_status",
+            Self::RolloutQueued => "telemetry.rollout_queued",
+            Self::Heartbeat => "agent.heartbeat",
         }
     }
 }
@@ -322,6 +338,17 @@ impl GatewayEvent {
         )
     }
 
+    /// A chunk of a response that is still being generated
+    pub fn agent_token_delta(channel_id: i64, delta: &str) -> Self {
+        Self::new(
+            EventType::AgentTokenDelta,
+            serde_json::json!({
+                "channel_id": channel_id,
+                "delta": delta
+            }),
+        )
+    }
+
     /// Emit a tool call notification for real-time display in chat
     /// The `chat_id` is the platform-specific conversation ID (e.g., Discord channel snowflake)
     pub fn agent_tool_call(channel_id: i64, chat_id: Option<&str>, tool_name: &str, parameters: &Value) -> Self {
@@ -390,6 +417,19 @@ impl GatewayEvent {
         )
     }
 
+    /// Emit when a message is rejected by the per-identity rate limit
+    pub fn agent_rate_limited(channel_id: i64, user_name: &str, retry_after_secs: u64) -> Self {
+        Self::new(
+            EventType::AgentRateLimited,
+            serde_json::json!({
+                "channel_id": channel_id,
+                "user_name": user_name,
+                "retry_after_secs": retry_after_secs,
+                "timestamp": chrono::Utc::now().to_rfc3339()
+            }),
+        )
+    }
+
     /// Emit warning when agent tries to respond without calling tools
     pub fn agent_warning(channel_id: i64, warning_type: &str, message: &str, attempt: u32) -> Self {
         Self::new(
@@ -432,6 +472,14 @@ impl GatewayEvent {
         )
     }
 
+    /// Attach a tool result's warnings so the UI can style it as a degraded success
+    pub fn with_warnings(mut self, warnings: &[String]) -> Self {
+        if !warnings.is_empty() {
+            self.data["warnings"] = serde_json::json!(warnings);
+        }
+        self
+    }
+
     /// Tool is waiting for retry after transient network error (exponential backoff)
     pub fn tool_waiting(channel_id: i64, tool_name: &str, wait_seconds: u64) -> Self {
         Self::new(
@@ -783,6 +831,59 @@ impl GatewayEvent {
         )
     }
 
+    /// x402 endpoint circuit breaker changed state (opened or closed)
+    pub fn x402_circuit_breaker(channel_id: i64, status: &crate::x402::BreakerStatus) -> Self {
+        Self::new(
+            EventType::X402CircuitBreaker,
+            serde_json::json!({
+                "channel_id": channel_id,
+                "endpoint": status.endpoint,
+                "state": status.state,
+                "consecutive_failures": status.consecutive_failures,
+                "retry_in_secs": status.retry_in_secs,
+                "timestamp": chrono::Utc::now().to_rfc3339()
+            }),
+        )
+    }
+
+    /// Running x402 spend for a session, sent after each recorded payment
+    pub fn x402_session_spend(
+        channel_id: i64,
+        session_id: i64,
+        spent_usdc: f64,
+        max_session_usdc: Option<f64>,
+    ) -> Self {
+        Self::new(
+            EventType::X402SessionSpend,
+            serde_json::json!({
+                "channel_id": channel_id,
+                "session_id": session_id,
+                "spent_usdc": spent_usdc,
+                "max_session_usdc": max_session_usdc,
+                "timestamp": chrono::Utc::now().to_rfc3339()
+            }),
+        )
+    }
+
+    /// The next paid call would exceed the session's x402 budget; the tool loop is halted
+    pub fn x402_budget_exceeded(
+        channel_id: i64,
+        session_id: i64,
+        spent_usdc: f64,
+        max_session_usdc: f64,
+    ) -> Self {
+        Self::new(
+            EventType::X402BudgetExceeded,
+            serde_json::json!({
+                "channel_id": channel_id,
+                "session_id": session_id,
+                "spent_usdc": spent_usdc,
+                "max_session_usdc": max_session_usdc,
+                "timestamp": chrono::Utc::now().to_rfc3339()
+            }),
+        )
+    }
+
     /// Register updated - broadcast full registry state
     pub fn register_update(
         channel_id: i64,
@@ -1229,6 +1330,25 @@ impl GatewayEvent {
         )
     }
 
+    /// A model failed with a non-retryable error and the next fallback took over
+    pub fn ai_model_fallback(
+        channel_id: i64,
+        from_model: &str,
+        to_model: &str,
+        error: &str,
+    ) -> Self {
+        Self::new(
+            EventType::AiModelFallback,
+            serde_json::json!({
+                "channel_id": channel_id,
+                "from_model": from_model,
+                "to_model": to_model,
+                "error": error,
+                "timestamp": chrono::Utc::now().to_rfc3339()
+            }),
+        )
+    }
+
     // =====================================================
     // Context Management Events
     // =====================================================
@@ -1271,6 +1391,18 @@ impl GatewayEvent {
         )
     }
 
+    /// A span streamed to a `subscribe_telemetry` client (full record)
+    pub fn telemetry_span(subscription_id: u64, span: &crate::telemetry::Span) -> Self {
+        Self::new(
+            EventType::SpanEmitted,
+            serde_json::json!({
+                "subscription_id": subscription_id,
+                "session_id": span.session_id,
+                "span": span,
+            }),
+        )
+    }
+
     /// Rollout lifecycle status changed
     pub fn rollout_status_change(
         channel_id: i64,
@@ -1289,6 +1421,39 @@ impl GatewayEvent {
             }),
         )
     }
+
+    /// Watchdog heartbeat with the progress of a long-running dispatch
+    pub fn heartbeat(channel_id: i64, progress: &crate::telemetry::HeartbeatProgress) -> Self {
+        Self::new(
+            EventType::Heartbeat,
+            serde_json::json!({
+                "channel_id": channel_id,
+                "iteration": progress.iteration,
+                "max_iterations": progress.max_iterations,
+                "task_index": progress.task_index,
+                "task_count": progress.task_count,
+                "elapsed_secs": progress.elapsed_secs,
+                "percent": progress.percent,
+                "timestamp": chrono::Utc::now().to_rfc3339()
+            }),
+        )
+    }
+
+    /// System is busy: all rollout slots are in use and the message is queued
+    pub fn rollout_queued(channel_id: i64, chat_id: &str, running: usize, queued: usize, max_concurrent: usize) -> Self {
+        Self::new(
+            EventType::RolloutQueued,
+            serde_json::json!({
+                "channel_id": channel_id,
+                "chat_id": chat_id,
+                "message": "Busy — your message is queued and will start shortly",
+                "running": running,
+                "queued": queued,
+                "max_concurrent": max_concurrent,
+                "timestamp": chrono::Utc::now().to_rfc3339()
+            }),
+        )
+    }
 }
 
 /// Params for channel operations
@@ -1296,3 +1461,15 @@ impl GatewayEvent {
 pub struct ChannelIdParams {
     pub id: i64,
 }
+
+/// Params for `subscribe_telemetry`
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubscribeTelemetryParams {
+    pub session_id: i64,
+}
+
+/// Params for `unsubscribe_telemetry`
+#[derive(Debug, Clone, Deserialize)]
+pub struct UnsubscribeTelemetryParams {
+    pub subscription_id: u64,
+}
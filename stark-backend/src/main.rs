@@ -38,6 +38,7 @@ mod keystore_client;
 mod identity_client;
 mod modules;
 mod telemetry;
+mod text;
 
 use channels::{ChannelManager, MessageDispatcher, SafeModeChannelRateLimiter};
 use tx_queue::TxQueueManager;
@@ -371,6 +372,12 @@ async fn restore_backup_data(
             settings.theme_accent.as_deref(),
             None, // Don't restore proxy_url - it's infrastructure config
             None, // Don't restore kanban_auto_execute - keep current setting
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         ) {
             Ok(_) => log::info!("[Keystore] Restored bot settings"),
             Err(e) => log::warn!("[Keystore] Failed to restore bot settings: {}", e),
@@ -669,6 +676,7 @@ async fn restore_backup_data(
             tags: skill_entry.tags.clone(),
             subagent_type: skill_entry.subagent_type.clone(),
             requires_api_keys,
+            model_override: skill_entry.model_override.clone(),
             created_at: now.clone(),
             updated_at: now.clone(),
         };
@@ -752,6 +760,9 @@ async fn restore_backup_data(
             max_iterations: entry.max_iterations.unwrap_or(90) as u32,
             skip_task_planner: entry.skip_task_planner.unwrap_or(false),
             aliases,
+            max_tasks: entry.max_tasks.unwrap_or(ai::multi_agent::types::DEFAULT_MAX_TASKS),
+            temperature: entry.temperature,
+            top_p: entry.top_p,
         };
         match db.upsert_agent_subtype(&config) {
             Ok(_) => restored_subtypes += 1,
@@ -1254,7 +1265,8 @@ async fn main() -> std::io::Result<()> {
             Some(skill_registry.clone()),
         ).with_hook_manager(hook_manager.clone())
          .with_validator_registry(validator_registry.clone())
-         .with_tx_queue(tx_queue.clone());
+         .with_tx_queue(tx_queue.clone())
+         .with_channel_capabilities(gateway.channel_manager().capabilities());
     if let Some(ref dq) = disk_quota {
         dispatcher_builder = dispatcher_builder.with_disk_quota(dq.clone());
         // Also wire disk quota into the MemoryStore for memory append limits
@@ -1483,6 +1495,7 @@ async fn main() -> std::io::Result<()> {
             .configure(controllers::auth::config)
             .configure(controllers::dashboard::config)
             .configure(controllers::chat::config)
+            .configure(controllers::compare::config)
             .configure(controllers::api_keys::config)
             .configure(controllers::channels::config)
             .configure(controllers::agent_settings::configure)
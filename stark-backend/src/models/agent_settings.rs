@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::ai::SamplingParams;
+
 /// Agent settings stored in database (x402 endpoint configuration)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentSettings {
@@ -13,15 +15,86 @@ pub struct AgentSettings {
     pub max_context_tokens: i32,
     pub enabled: bool,
     pub secret_key: Option<String>,
+    /// Sampling temperature (None = provider default)
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// Nucleus sampling cutoff (None = provider default)
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    /// Models to try, in order, when this endpoint fails with a non-retryable error
+    #[serde(default)]
+    pub fallback_models: Vec<FallbackModel>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// A fallback model in an agent settings chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FallbackModel {
+    /// Model archetype (e.g. "kimi", "claude", "openai")
+    pub provider: String,
+    pub endpoint: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+impl FallbackModel {
+    /// Fill in API keys left out of an updated chain with the key already
+    /// stored for the same endpoint and model. The API never returns keys, so
+    /// re-saving a chain would otherwise wipe them; an empty string clears one.
+    pub fn keep_stored_keys(updated: &mut [FallbackModel], stored: &[FallbackModel]) {
+        for fallback in updated.iter_mut() {
+            match fallback.api_key.as_deref() {
+                Some("") => fallback.api_key = None,
+                Some(_) => {}
+                None => {
+                    fallback.api_key = stored
+                        .iter()
+                        .find(|s| s.endpoint == fallback.endpoint && s.model == fallback.model)
+                        .and_then(|s| s.api_key.clone());
+                }
+            }
+        }
+    }
+}
+
 /// Minimum allowed context tokens (ensures compaction has room to work)
 pub const MIN_CONTEXT_TOKENS: i32 = 80_000;
 /// Default context tokens (Claude/most models)
 pub const DEFAULT_CONTEXT_TOKENS: i32 = 100_000;
 
+impl AgentSettings {
+    /// Sampling parameters configured for this endpoint
+    pub fn sampling(&self) -> SamplingParams {
+        SamplingParams::new(self.temperature, self.top_p)
+    }
+
+    /// Display name of the configured model (falls back to the archetype)
+    pub fn model_label(&self) -> String {
+        self.model.clone().unwrap_or_else(|| self.model_archetype.clone())
+    }
+
+    /// This endpoint followed by its fallbacks, each as standalone settings.
+    /// Fallbacks inherit token limits and sampling from the primary.
+    pub fn model_chain(&self) -> Vec<AgentSettings> {
+        let mut chain = Vec::with_capacity(self.fallback_models.len() + 1);
+        chain.push(self.clone());
+        for fallback in &self.fallback_models {
+            chain.push(AgentSettings {
+                endpoint: fallback.endpoint.clone(),
+                model_archetype: fallback.provider.clone(),
+                model: fallback.model.clone(),
+                secret_key: fallback.api_key.clone(),
+                fallback_models: Vec::new(),
+                ..self.clone()
+            });
+        }
+        chain
+    }
+}
+
 impl Default for AgentSettings {
     /// Returns default kimi-turbo agent settings (used when no agent is configured)
     fn default() -> Self {
@@ -35,6 +108,9 @@ impl Default for AgentSettings {
             max_context_tokens: DEFAULT_CONTEXT_TOKENS,
             enabled: true,
             secret_key: None,
+            temperature: None,
+            top_p: None,
+            fallback_models: Vec::new(),
             created_at: now,
             updated_at: now,
         }
@@ -52,10 +128,22 @@ pub struct AgentSettingsResponse {
     pub max_context_tokens: i32,
     pub enabled: bool,
     pub has_secret_key: bool,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub fallback_models: Vec<FallbackModelResponse>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// Fallback model as returned by the API (the key itself is never exposed)
+#[derive(Debug, Clone, Serialize)]
+pub struct FallbackModelResponse {
+    pub provider: String,
+    pub endpoint: String,
+    pub model: Option<String>,
+    pub has_api_key: bool,
+}
+
 impl From<AgentSettings> for AgentSettingsResponse {
     fn from(settings: AgentSettings) -> Self {
         Self {
@@ -67,6 +155,18 @@ impl From<AgentSettings> for AgentSettingsResponse {
             max_context_tokens: settings.max_context_tokens,
             enabled: settings.enabled,
             has_secret_key: settings.secret_key.is_some(),
+            temperature: settings.temperature,
+            top_p: settings.top_p,
+            fallback_models: settings
+                .fallback_models
+                .into_iter()
+                .map(|f| FallbackModelResponse {
+                    provider: f.provider,
+                    endpoint: f.endpoint,
+                    model: f.model,
+                    has_api_key: f.api_key.is_some(),
+                })
+                .collect(),
             created_at: settings.created_at,
             updated_at: settings.updated_at,
         }
@@ -86,6 +186,13 @@ pub struct UpdateAgentSettingsRequest {
     #[serde(default = "default_max_context_tokens")]
     pub max_context_tokens: i32,
     pub secret_key: Option<String>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    /// Replaces the fallback chain when present
+    #[serde(default)]
+    pub fallback_models: Option<Vec<FallbackModel>>,
 }
 
 fn default_archetype() -> String {
@@ -99,3 +206,81 @@ fn default_max_response_tokens() -> i32 {
 fn default_max_context_tokens() -> i32 {
     DEFAULT_CONTEXT_TOKENS
 }
+
+/// Partial agent settings layered over a base config (e.g. for comparison runs).
+/// Unset fields keep the base value.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AgentSettingsOverride {
+    pub endpoint: Option<String>,
+    pub model_archetype: Option<String>,
+    pub model: Option<String>,
+    pub max_response_tokens: Option<i32>,
+    pub max_context_tokens: Option<i32>,
+    pub secret_key: Option<String>,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+}
+
+impl AgentSettingsOverride {
+    /// Apply this override on top of `base`. When the endpoint changes and no
+    /// secret key is given, the base key is dropped so it is never sent to another host.
+    pub fn apply(&self, base: &AgentSettings) -> AgentSettings {
+        let mut settings = base.clone();
+        if let Some(ref endpoint) = self.endpoint {
+            if *endpoint != base.endpoint {
+                settings.secret_key = None;
+            }
+            settings.endpoint = endpoint.clone();
+        }
+        if let Some(ref archetype) = self.model_archetype {
+            settings.model_archetype = archetype.clone();
+        }
+        if self.model.is_some() {
+            settings.model = self.model.clone();
+        }
+        if let Some(tokens) = self.max_response_tokens {
+            settings.max_response_tokens = tokens;
+        }
+        if let Some(tokens) = self.max_context_tokens {
+            settings.max_context_tokens = tokens.max(MIN_CONTEXT_TOKENS);
+        }
+        if self.secret_key.is_some() {
+            settings.secret_key = self.secret_key.clone();
+        }
+        if self.temperature.is_some() {
+            settings.temperature = self.temperature;
+        }
+        if self.top_p.is_some() {
+            settings.top_p = self.top_p;
+        }
+        settings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fallback(endpoint: &str, api_key: Option<&str>) -> FallbackModel {
+        FallbackModel {
+            provider: "openai".to_string(),
+            endpoint: endpoint.to_string(),
+            model: Some("backup".to_string()),
+            api_key: api_key.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_keep_stored_keys() {
+        let stored = vec![fallback("https://a.example", Some("sk-a")), fallback("https://b.example", Some("sk-b"))];
+        let mut updated = vec![
+            fallback("https://a.example", None),
+            fallback("https://b.example", Some("")),
+            fallback("https://c.example", None),
+            fallback("https://a.example", Some("sk-new")),
+        ];
+        FallbackModel::keep_stored_keys(&mut updated, &stored);
+        let keys: Vec<_> = updated.iter().map(|f| f.api_key.as_deref()).collect();
+        assert_eq!(keys, vec![Some("sk-a"), None, None, Some("sk-new")]);
+    }
+}
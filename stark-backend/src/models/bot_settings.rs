@@ -8,6 +8,47 @@ pub const DEFAULT_MAX_TOOL_ITERATIONS: i32 = 100;
 /// Default max safe mode queries per user per 10 minutes
 pub const DEFAULT_SAFE_MODE_MAX_QUERIES_PER_10MIN: i32 = 5;
 
+/// Default number of identical tool calls treated as a loop
+pub const DEFAULT_LOOP_MAX_REPEATED_CALLS: i32 = 3;
+
+/// Default number of recent tool call signatures kept for loop detection
+pub const DEFAULT_LOOP_SIGNATURE_HISTORY: i32 = 20;
+
+/// Default messages per minute allowed per identity (0 = no rate limit)
+pub const DEFAULT_RATE_LIMIT_PER_MINUTE: i32 = 0;
+
+/// Default number of messages an identity may send in a quick burst
+pub const DEFAULT_RATE_LIMIT_BURST: i32 = 5;
+
+/// Default x402 spend allowed per session, in USDC (0 = no budget)
+pub const DEFAULT_MAX_SESSION_USDC: f64 = 0.0;
+
+/// Tool timeout configuration, stored as JSON in bot_settings
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolTimeoutSettings {
+    /// Timeout in seconds for tools without an override (None = built-in default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_secs: Option<u64>,
+    /// Per-tool timeouts in seconds (tool_name → secs), on top of the built-in overrides
+    #[serde(default)]
+    pub overrides: HashMap<String, u64>,
+    /// Wall-clock limit in seconds for a whole agent session (None = no limit)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_secs: Option<u64>,
+    /// Per-subtype session limits in seconds (subtype key → secs)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub subtype_session_secs: HashMap<String, u64>,
+}
+
+impl ToolTimeoutSettings {
+    pub fn is_empty(&self) -> bool {
+        self.default_secs.is_none()
+            && self.overrides.is_empty()
+            && self.session_secs.is_none()
+            && self.subtype_session_secs.is_empty()
+    }
+}
+
 /// Bot settings stored in database
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BotSettings {
@@ -37,6 +78,18 @@ pub struct BotSettings {
     pub proxy_url: Option<String>,
     /// Whether kanban "ready" tasks are auto-executed by the scheduler
     pub kanban_auto_execute: bool,
+    /// Identical tool calls (same mode, tool and arguments) before the loop detector intervenes
+    pub loop_max_repeated_calls: i32,
+    /// How many recent tool call signatures the loop detector remembers
+    pub loop_signature_history: i32,
+    /// Tool timeout default and per-tool overrides (None = built-in watchdog timeouts)
+    pub tool_timeouts: Option<ToolTimeoutSettings>,
+    /// Messages per minute allowed per identity (0 = no rate limit)
+    pub rate_limit_per_minute: i32,
+    /// Messages an identity may send back to back before the per-minute rate applies
+    pub rate_limit_burst: i32,
+    /// Max x402 spend per session in USDC before the tool loop is halted (0 = no budget)
+    pub max_session_usdc: f64,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -59,6 +112,12 @@ impl Default for BotSettings {
             theme_accent: None,
             proxy_url: None,
             kanban_auto_execute: true,
+            loop_max_repeated_calls: DEFAULT_LOOP_MAX_REPEATED_CALLS,
+            loop_signature_history: DEFAULT_LOOP_SIGNATURE_HISTORY,
+            tool_timeouts: None,
+            rate_limit_per_minute: DEFAULT_RATE_LIMIT_PER_MINUTE,
+            rate_limit_burst: DEFAULT_RATE_LIMIT_BURST,
+            max_session_usdc: DEFAULT_MAX_SESSION_USDC,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
@@ -85,4 +144,16 @@ pub struct UpdateBotSettingsRequest {
     pub proxy_url: Option<String>,
     /// Whether kanban "ready" tasks are auto-executed by the scheduler
     pub kanban_auto_execute: Option<bool>,
+    /// Identical tool calls before the loop detector intervenes (0 = default)
+    pub loop_max_repeated_calls: Option<i32>,
+    /// Recent tool call signatures remembered by the loop detector (0 = default)
+    pub loop_signature_history: Option<i32>,
+    /// Tool timeout default and per-tool overrides (empty object = built-in timeouts)
+    pub tool_timeouts: Option<ToolTimeoutSettings>,
+    /// Messages per minute allowed per identity (0 = no rate limit)
+    pub rate_limit_per_minute: Option<i32>,
+    /// Messages an identity may send back to back (0 = default)
+    pub rate_limit_burst: Option<i32>,
+    /// Max x402 spend per session in USDC (0 = no budget)
+    pub max_session_usdc: Option<f64>,
 }
@@ -53,6 +53,19 @@ impl ToolOutputVerbosity {
 pub enum ChannelSettingKey {
     /// Common: Auto-start this channel when the server boots (after restore from backup)
     AutoStartOnBoot,
+    /// Common: Preferred response language (name or ISO 639-1 code, e.g. "Spanish" or "es")
+    PreferredLanguage,
+    /// Common: Name of the env var holding this channel's wallet private key (separate treasury)
+    WalletKeyEnv,
+    /// Common: Turns between cross-session memory rebuilds (empty = global default)
+    MemoryRefreshTurns,
+    /// Common: Wall-clock limit in seconds for one agent session (empty = global default)
+    SessionTimeLimitSecs,
+    /// Common: Daily window during which proactive (cron) messages are held back
+    QuietHours,
+    /// Discord/Telegram: Seconds after the last message during which a new message
+    /// continues the same session instead of starting a fresh one (empty = global default)
+    GatewaySessionGraceSecs,
     /// Discord: Bot authentication token
     DiscordBotToken,
     /// Discord: Comma-separated list of Discord user IDs with admin access
@@ -84,6 +97,10 @@ pub enum ChannelSettingKey {
     ExternalChannelApiToken,
     /// External Gateway: Enable safe mode (restricts tool access for untrusted input)
     ExternalChannelSafeMode,
+    /// Webhook: URL agent responses are POSTed to
+    WebhookUrl,
+    /// Webhook: Shared secret for HMAC-SHA256 signing of outbound and inbound requests
+    WebhookSecret,
 }
 
 impl ChannelSettingKey {
@@ -91,6 +108,12 @@ impl ChannelSettingKey {
     pub fn label(&self) -> &'static str {
         match self {
             Self::AutoStartOnBoot => "Auto-Start on Boot",
+            Self::PreferredLanguage => "Preferred Language (Optional)",
+            Self::WalletKeyEnv => "Wallet Key Env Var (Optional)",
+            Self::MemoryRefreshTurns => "Memory Refresh Turns (Optional)",
+            Self::SessionTimeLimitSecs => "Session Time Limit (seconds, Optional)",
+            Self::QuietHours => "Quiet Hours (Optional)",
+            Self::GatewaySessionGraceSecs => "Session Grace Period (seconds, Optional)",
             Self::DiscordBotToken => "Bot Token",
             Self::DiscordAdminUserIds => "Admin User IDs (Optional)",
             Self::TelegramBotToken => "Bot Token",
@@ -106,6 +129,8 @@ impl ChannelSettingKey {
             Self::SlackAdminUserIds => "Admin User IDs (Optional)",
             Self::ExternalChannelApiToken => "API Token",
             Self::ExternalChannelSafeMode => "Safe Mode",
+            Self::WebhookUrl => "Webhook URL",
+            Self::WebhookSecret => "Signing Secret",
         }
     }
 
@@ -116,6 +141,38 @@ impl ChannelSettingKey {
                 "Automatically start this channel when the server boots or restores from backup. \
                  Useful for ensuring your bot is always running after container updates."
             }
+            Self::PreferredLanguage => {
+                "Language the agent should respond in on this channel (e.g. 'Spanish' or 'es'). \
+                 Users writing in another language still get replies in their own language. \
+                 If left empty, the user's message language is detected automatically."
+            }
+            Self::WalletKeyEnv => {
+                "Name of an environment variable holding the private key of a wallet dedicated to \
+                 this channel. x402 payments and transactions from this channel use that wallet. \
+                 If left empty, the default bot wallet is used."
+            }
+            Self::MemoryRefreshTurns => {
+                "How many turns the relevant-memories block is reused before it is rebuilt. \
+                 Higher values save memory searches on long sessions at the cost of freshness. \
+                 1 rebuilds every turn. If left empty, STARK_MEMORY_REFRESH_TURNS is used."
+            }
+            Self::SessionTimeLimitSecs => {
+                "Maximum wall-clock time for one agent session on this channel. When it runs out \
+                 the agent stops and replies with the work done so far. Per-subtype limits in bot \
+                 settings still apply. If left empty, the bot-wide session limit (if any) is used."
+            }
+            Self::QuietHours => {
+                "Daily window in which the bot doesn't post proactively, as 'HH:MM-HH:MM' with an \
+                 optional time zone (e.g. '22:00-07:00 America/New_York' or a fixed offset like \
+                 '-05:00'; UTC if omitted). Cron deliveries \
+                 during the window are queued and sent when it ends. Replies to users are unaffected."
+            }
+            Self::GatewaySessionGraceSecs => {
+                "Each message normally starts a fresh session that only sees the last 10 messages. \
+                 A message arriving within this many seconds of the previous one continues that \
+                 session instead, so quick bursts stay one conversation. 0 always starts fresh. \
+                 If left empty, STARK_GATEWAY_SESSION_GRACE_SECS is used."
+            }
             Self::DiscordBotToken => {
                 "Your Discord bot token from the Discord Developer Portal. \
                  Found under Bot > Token in your application settings."
@@ -187,6 +244,16 @@ impl ChannelSettingKey {
                  tool access is restricted to a safe subset. Disable for full agent access \
                  (only if you trust the clients connecting to this channel)."
             }
+            Self::WebhookUrl => {
+                "Endpoint that receives each agent response as a JSON POST with \
+                 channel_id, user, text and session_id. 5xx responses are retried with backoff."
+            }
+            Self::WebhookSecret => {
+                "Shared secret for HMAC-SHA256 signatures in the X-Stark-Signature header \
+                 (sha256=<hex> of \"<X-Stark-Timestamp>.<body>\"). Outbound deliveries are signed \
+                 with it, and messages sent to /api/channels/{id}/webhook must be signed with it \
+                 and no more than 5 minutes old to be accepted."
+            }
         }
     }
 
@@ -194,6 +261,12 @@ impl ChannelSettingKey {
     pub fn input_type(&self) -> SettingInputType {
         match self {
             Self::AutoStartOnBoot => SettingInputType::Toggle,
+            Self::PreferredLanguage => SettingInputType::Text,
+            Self::WalletKeyEnv => SettingInputType::Text,
+            Self::MemoryRefreshTurns => SettingInputType::Number,
+            Self::SessionTimeLimitSecs => SettingInputType::Number,
+            Self::QuietHours => SettingInputType::Text,
+            Self::GatewaySessionGraceSecs => SettingInputType::Number,
             Self::DiscordBotToken => SettingInputType::Text,
             Self::DiscordAdminUserIds => SettingInputType::Text,
             Self::TelegramBotToken => SettingInputType::Text,
@@ -209,6 +282,8 @@ impl ChannelSettingKey {
             Self::SlackAdminUserIds => SettingInputType::Text,
             Self::ExternalChannelApiToken => SettingInputType::Text,
             Self::ExternalChannelSafeMode => SettingInputType::Toggle,
+            Self::WebhookUrl => SettingInputType::Text,
+            Self::WebhookSecret => SettingInputType::Text,
         }
     }
 
@@ -216,6 +291,12 @@ impl ChannelSettingKey {
     pub fn placeholder(&self) -> &'static str {
         match self {
             Self::AutoStartOnBoot => "",
+            Self::PreferredLanguage => "Spanish",
+            Self::WalletKeyEnv => "COMMUNITY_A_WALLET_PRIVATE_KEY",
+            Self::MemoryRefreshTurns => "1",
+            Self::SessionTimeLimitSecs => "3600",
+            Self::QuietHours => "22:00-07:00 America/New_York",
+            Self::GatewaySessionGraceSecs => "60",
             Self::DiscordBotToken => "MTIz...abc",
             Self::DiscordAdminUserIds => "123456789012345678, 987654321098765432",
             Self::TelegramBotToken => "123456:ABC-DEF...",
@@ -231,6 +312,8 @@ impl ChannelSettingKey {
             Self::SlackAdminUserIds => "U12345678,U87654321",
             Self::ExternalChannelApiToken => "Click dice to generate a secure token",
             Self::ExternalChannelSafeMode => "",
+            Self::WebhookUrl => "https://internal.example.com/stark-hook",
+            Self::WebhookSecret => "Click dice to generate a secure token",
         }
     }
 
@@ -253,6 +336,12 @@ impl ChannelSettingKey {
     pub fn default_value(&self) -> &'static str {
         match self {
             Self::AutoStartOnBoot => "false",
+            Self::PreferredLanguage => "",
+            Self::WalletKeyEnv => "",
+            Self::MemoryRefreshTurns => "",
+            Self::SessionTimeLimitSecs => "",
+            Self::QuietHours => "",
+            Self::GatewaySessionGraceSecs => "",
             Self::DiscordBotToken => "",
             Self::DiscordAdminUserIds => "",
             Self::TelegramBotToken => "",
@@ -268,12 +357,22 @@ impl ChannelSettingKey {
             Self::SlackAdminUserIds => "",
             Self::ExternalChannelApiToken => "",
             Self::ExternalChannelSafeMode => "false",
+            Self::WebhookUrl => "",
+            Self::WebhookSecret => "",
         }
     }
 
     /// Check if this setting applies to all channel types (common setting)
     pub fn is_common(&self) -> bool {
-        matches!(self, Self::AutoStartOnBoot)
+        matches!(
+            self,
+            Self::AutoStartOnBoot
+                | Self::PreferredLanguage
+                | Self::WalletKeyEnv
+                | Self::MemoryRefreshTurns
+                | Self::SessionTimeLimitSecs
+                | Self::QuietHours
+        )
     }
 }
 
@@ -374,6 +473,11 @@ pub struct SettingUpdate {
 fn get_common_settings() -> Vec<ChannelSettingDefinition> {
     vec![
         ChannelSettingKey::AutoStartOnBoot.into(),
+        ChannelSettingKey::PreferredLanguage.into(),
+        ChannelSettingKey::WalletKeyEnv.into(),
+        ChannelSettingKey::MemoryRefreshTurns.into(),
+        ChannelSettingKey::SessionTimeLimitSecs.into(),
+        ChannelSettingKey::QuietHours.into(),
     ]
 }
 
@@ -385,10 +489,12 @@ pub fn get_settings_for_channel_type(channel_type: ChannelType) -> Vec<ChannelSe
         ChannelType::Discord => vec![
             ChannelSettingKey::DiscordBotToken.into(),
             ChannelSettingKey::DiscordAdminUserIds.into(),
+            ChannelSettingKey::GatewaySessionGraceSecs.into(),
         ],
         ChannelType::Telegram => vec![
             ChannelSettingKey::TelegramBotToken.into(),
             ChannelSettingKey::TelegramAdminUserId.into(),
+            ChannelSettingKey::GatewaySessionGraceSecs.into(),
         ],
         ChannelType::Slack => vec![
             ChannelSettingKey::SlackBotToken.into(),
@@ -407,6 +513,10 @@ pub fn get_settings_for_channel_type(channel_type: ChannelType) -> Vec<ChannelSe
             ChannelSettingKey::ExternalChannelApiToken.into(),
             ChannelSettingKey::ExternalChannelSafeMode.into(),
         ],
+        ChannelType::Webhook => vec![
+            ChannelSettingKey::WebhookUrl.into(),
+            ChannelSettingKey::WebhookSecret.into(),
+        ],
     };
 
     settings.extend(type_specific);
@@ -426,32 +536,49 @@ mod tests {
     #[test]
     fn test_discord_settings() {
         let settings = get_settings_for_channel_type(ChannelType::Discord);
-        // 1 common + 2 Discord-specific (bot_token, admin_user_ids)
-        assert_eq!(settings.len(), 3);
+        // 6 common + 3 Discord-specific (bot_token, admin_user_ids, session grace)
+        assert_eq!(settings.len(), 9);
         assert_eq!(settings[0].key, "auto_start_on_boot");
-        assert_eq!(settings[1].key, "discord_bot_token");
-        assert_eq!(settings[2].key, "discord_admin_user_ids");
+        assert_eq!(settings[1].key, "preferred_language");
+        assert_eq!(settings[2].key, "wallet_key_env");
+        assert_eq!(settings[3].key, "memory_refresh_turns");
+        assert_eq!(settings[4].key, "session_time_limit_secs");
+        assert_eq!(settings[5].key, "quiet_hours");
+        assert_eq!(settings[6].key, "discord_bot_token");
+        assert_eq!(settings[7].key, "discord_admin_user_ids");
+        assert_eq!(settings[8].key, "gateway_session_grace_secs");
     }
 
     #[test]
     fn test_telegram_settings() {
         let settings = get_settings_for_channel_type(ChannelType::Telegram);
-        // 1 common + 2 Telegram-specific (bot_token, admin_user_id)
-        assert_eq!(settings.len(), 3);
+        // 6 common + 3 Telegram-specific (bot_token, admin_user_id, session grace)
+        assert_eq!(settings.len(), 9);
         assert_eq!(settings[0].key, "auto_start_on_boot");
-        assert_eq!(settings[1].key, "telegram_bot_token");
-        assert_eq!(settings[2].key, "telegram_admin_user_id");
+        assert_eq!(settings[1].key, "preferred_language");
+        assert_eq!(settings[2].key, "wallet_key_env");
+        assert_eq!(settings[3].key, "memory_refresh_turns");
+        assert_eq!(settings[4].key, "session_time_limit_secs");
+        assert_eq!(settings[5].key, "quiet_hours");
+        assert_eq!(settings[6].key, "telegram_bot_token");
+        assert_eq!(settings[7].key, "telegram_admin_user_id");
+        assert_eq!(settings[8].key, "gateway_session_grace_secs");
     }
 
     #[test]
     fn test_slack_settings() {
         let settings = get_settings_for_channel_type(ChannelType::Slack);
-        // 1 common + 3 Slack-specific (bot_token, app_token, admin_user_ids)
-        assert_eq!(settings.len(), 4);
+        // 6 common + 3 Slack-specific (bot_token, app_token, admin_user_ids)
+        assert_eq!(settings.len(), 9);
         assert_eq!(settings[0].key, "auto_start_on_boot");
-        assert_eq!(settings[1].key, "slack_bot_token");
-        assert_eq!(settings[2].key, "slack_app_token");
-        assert_eq!(settings[3].key, "slack_admin_user_ids");
+        assert_eq!(settings[1].key, "preferred_language");
+        assert_eq!(settings[2].key, "wallet_key_env");
+        assert_eq!(settings[3].key, "memory_refresh_turns");
+        assert_eq!(settings[4].key, "session_time_limit_secs");
+        assert_eq!(settings[5].key, "quiet_hours");
+        assert_eq!(settings[6].key, "slack_bot_token");
+        assert_eq!(settings[7].key, "slack_app_token");
+        assert_eq!(settings[8].key, "slack_admin_user_ids");
     }
 
     #[test]
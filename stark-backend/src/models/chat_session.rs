@@ -2,7 +2,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 /// Session scope determines the context type
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum SessionScope {
     Dm,
@@ -93,6 +93,44 @@ impl std::fmt::Display for CompletionStatus {
     }
 }
 
+/// Broad cause of a session ending up `Failed`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCategory {
+    /// The AI provider returned an error
+    AiError,
+    /// The tool loop ran out of iterations before completing
+    MaxIterations,
+    /// The conversation no longer fit the model's context window
+    ContextOverflow,
+    /// The AI client couldn't be created from the agent settings
+    ClientCreation,
+    /// The session hit its wall-clock limit
+    TimeLimit,
+    /// The session's x402 spend reached its budget
+    Budget,
+    /// Stuck as active and cleaned up by the stale-session sweep
+    Stale,
+    /// Internal error (e.g. database) before the agent could run
+    Internal,
+}
+
+/// Why a session failed, persisted when it transitions to `Failed`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionFailureReason {
+    pub category: FailureCategory,
+    pub message: String,
+}
+
+impl SessionFailureReason {
+    pub fn new(category: FailureCategory, message: impl Into<String>) -> Self {
+        Self {
+            category,
+            message: message.into(),
+        }
+    }
+}
+
 /// Reset policy determines when a session should be reset
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -219,6 +257,9 @@ pub struct ChatSessionResponse {
     // Special role name if this safe-mode session has enriched permissions
     #[serde(skip_serializing_if = "Option::is_none")]
     pub special_role_name: Option<String>,
+    // Why the session failed (only when completion_status is failed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<SessionFailureReason>,
 }
 
 impl From<ChatSession> for ChatSessionResponse {
@@ -246,6 +287,7 @@ impl From<ChatSession> for ChatSessionResponse {
             initial_query: None,
             safe_mode: if session.safe_mode { Some(true) } else { None },
             special_role_name: session.special_role_name,
+            failure_reason: None,
         }
     }
 }
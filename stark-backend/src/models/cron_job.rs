@@ -121,6 +121,14 @@ pub struct CronJob {
     pub timeout_seconds: Option<i32>,
     /// Delete after successful run (for one-shot jobs)
     pub delete_after_run: bool,
+    /// Automatic retries after a failed run (None or 0 = no retries)
+    pub max_retries: Option<i32>,
+    /// Delay before the first retry in seconds; doubles with each further retry
+    pub retry_delay_seconds: Option<i32>,
+    /// Retries already made for the current failure (0 when not retrying)
+    pub retry_attempt: i32,
+    /// The failed run the pending retries belong to
+    pub retry_of_run_id: Option<i64>,
     pub status: String,
     pub last_run_at: Option<String>,
     pub next_run_at: Option<String>,
@@ -161,6 +169,10 @@ pub struct CreateCronJobRequest {
     pub timeout_seconds: Option<i32>,
     #[serde(default)]
     pub delete_after_run: bool,
+    #[serde(default)]
+    pub max_retries: Option<i32>,
+    #[serde(default)]
+    pub retry_delay_seconds: Option<i32>,
 }
 
 fn default_session_mode() -> String {
@@ -201,6 +213,10 @@ pub struct UpdateCronJobRequest {
     #[serde(default)]
     pub delete_after_run: Option<bool>,
     #[serde(default)]
+    pub max_retries: Option<i32>,
+    #[serde(default)]
+    pub retry_delay_seconds: Option<i32>,
+    #[serde(default)]
     pub status: Option<String>,
 }
 
@@ -224,6 +240,10 @@ pub struct CronJobRun {
     pub result: Option<String>,
     pub error: Option<String>,
     pub duration_ms: Option<i64>,
+    /// For retries: the failed run being retried
+    pub retry_of: Option<i64>,
+    /// 0 for a regular run, 1.. for retries
+    pub attempt: i32,
 }
 
 /// Heartbeat configuration
@@ -11,8 +11,12 @@ pub mod session;
 pub mod session_message;
 pub mod special_role;
 
-pub use agent_settings::{AgentSettings, AgentSettingsResponse, UpdateAgentSettingsRequest, MIN_CONTEXT_TOKENS, DEFAULT_CONTEXT_TOKENS};
-pub use bot_settings::{BotSettings, UpdateBotSettingsRequest, DEFAULT_MAX_TOOL_ITERATIONS, DEFAULT_SAFE_MODE_MAX_QUERIES_PER_10MIN};
+pub use agent_settings::{AgentSettings, AgentSettingsOverride, AgentSettingsResponse, FallbackModel, UpdateAgentSettingsRequest, MIN_CONTEXT_TOKENS, DEFAULT_CONTEXT_TOKENS};
+pub use bot_settings::{
+    BotSettings, ToolTimeoutSettings, UpdateBotSettingsRequest, DEFAULT_LOOP_MAX_REPEATED_CALLS, DEFAULT_LOOP_SIGNATURE_HISTORY,
+    DEFAULT_MAX_SESSION_USDC, DEFAULT_MAX_TOOL_ITERATIONS, DEFAULT_RATE_LIMIT_BURST, DEFAULT_RATE_LIMIT_PER_MINUTE,
+    DEFAULT_SAFE_MODE_MAX_QUERIES_PER_10MIN,
+};
 pub use api_key::{ApiKey, ApiKeyResponse};
 pub use channel::{Channel, ChannelResponse, ChannelType, CreateChannelRequest, CreateSafeModeChannelRequest, UpdateChannelRequest};
 pub use channel_settings::{
@@ -21,8 +25,8 @@ pub use channel_settings::{
     SettingUpdate, ToolOutputVerbosity, UpdateChannelSettingsRequest,
 };
 pub use chat_session::{
-    ChatSession, ChatSessionResponse, CompletionStatus, GetOrCreateSessionRequest, ResetPolicy,
-    SessionScope, UpdateResetPolicyRequest,
+    ChatSession, ChatSessionResponse, CompletionStatus, FailureCategory, GetOrCreateSessionRequest,
+    ResetPolicy, SessionFailureReason, SessionScope, UpdateResetPolicyRequest,
 };
 pub use identity::{
     GetOrCreateIdentityRequest, IdentityLink, IdentityResponse, LinkIdentityRequest,
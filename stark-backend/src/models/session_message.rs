@@ -47,6 +47,9 @@ pub struct SessionMessage {
     pub platform_message_id: Option<String>,
     pub tokens_used: Option<i32>,
     pub created_at: DateTime<Utc>,
+    /// Pinned messages are never compacted away
+    #[serde(default)]
+    pub pinned: bool,
 }
 
 /// Request to add a message to a session
@@ -10,4 +10,4 @@
 pub mod file_ops;
 pub mod store;
 
-pub use store::MemoryStore;
+pub use store::{keyword_search_files, MemoryFileExport, MemoryStore};
@@ -9,7 +9,8 @@ use super::file_ops;
 use crate::disk_quota::DiskQuotaManager;
 use chrono::{Local, NaiveDate};
 use rusqlite::{params, Connection, Result as SqliteResult};
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 /// Search result from the memory store
@@ -23,6 +24,14 @@ pub struct SearchResult {
     pub score: f64,
 }
 
+/// A memory file and its content, as carried in an export bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryFileExport {
+    /// Relative file path (e.g., "MEMORY.md" or "user123/2024-01-15.md")
+    pub path: String,
+    pub content: String,
+}
+
 /// Memory store wrapping SQLite FTS5 for markdown file indexing
 pub struct MemoryStore {
     /// Path to the memory directory
@@ -155,6 +164,33 @@ impl MemoryStore {
         Ok(results)
     }
 
+    /// Search with graceful degradation: if the FTS index query fails, log it
+    /// and fall back to a keyword scan of the markdown files.
+    ///
+    /// Returns the results and whether they came from the degraded fallback
+    /// (and so may be incomplete or less well ranked).
+    pub fn search_with_fallback(&self, query: &str, limit: i32) -> (Vec<SearchResult>, bool) {
+        match self.search(query, limit) {
+            Ok(results) => (results, false),
+            Err(e) => {
+                log::warn!(
+                    "[QMD_MEMORY] Index search failed ({}), falling back to keyword search",
+                    e
+                );
+                let results = self.keyword_search(query, limit).unwrap_or_else(|e| {
+                    log::warn!("[QMD_MEMORY] Keyword fallback search failed: {}", e);
+                    Vec::new()
+                });
+                (results, true)
+            }
+        }
+    }
+
+    /// Keyword search directly over the memory files, bypassing the FTS index
+    pub fn keyword_search(&self, query: &str, limit: i32) -> std::io::Result<Vec<SearchResult>> {
+        keyword_search_files(&self.memory_dir, query, limit)
+    }
+
     /// Get content of a specific memory file
     pub fn get_file(&self, relative_path: &str) -> std::io::Result<String> {
         let full_path = self.memory_dir.join(relative_path);
@@ -209,6 +245,40 @@ impl MemoryStore {
         Ok(())
     }
 
+    /// Append to today's daily log unless a recent entry is a near-duplicate.
+    ///
+    /// The content is compared against the last [`DEDUP_RECENT_ENTRIES`] entries of
+    /// today's log by normalized token overlap; if any scores at or above
+    /// `threshold` the append is skipped. A threshold outside `(0, 1]` disables
+    /// deduplication. Returns whether the entry was written.
+    pub fn append_daily_log_deduped(
+        &self,
+        content: &str,
+        identity_id: Option<&str>,
+        threshold: f64,
+    ) -> std::io::Result<bool> {
+        if threshold > 0.0 && threshold <= 1.0 {
+            let log = self.get_daily_log(identity_id)?;
+            let entries = split_log_entries(&log);
+            let duplicate = entries
+                .iter()
+                .rev()
+                .take(DEDUP_RECENT_ENTRIES)
+                .map(|entry| token_similarity(entry, content))
+                .find(|score| *score >= threshold);
+            if let Some(score) = duplicate {
+                log::debug!(
+                    "[MEMORY] Skipping near-duplicate daily log entry (similarity {:.2} >= {:.2})",
+                    score, threshold
+                );
+                return Ok(false);
+            }
+        }
+
+        self.append_daily_log(content, identity_id)?;
+        Ok(true)
+    }
+
     /// Append to the long-term memory file (MEMORY.md)
     pub fn append_long_term(
         &self,
@@ -288,6 +358,81 @@ impl MemoryStore {
             .collect())
     }
 
+    /// All memory files of one identity (its subdirectory), or every memory
+    /// file when `identity_id` is None
+    pub fn export_files(&self, identity_id: Option<&str>) -> std::io::Result<Vec<MemoryFileExport>> {
+        let prefix = identity_id.map(|id| format!("{}/", id));
+        let mut files = Vec::new();
+        for path in self.list_files()? {
+            if prefix.as_ref().is_some_and(|p| !path.starts_with(p.as_str())) {
+                continue;
+            }
+            let content = self.get_file(&path)?;
+            files.push(MemoryFileExport { path, content });
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(files)
+    }
+
+    /// Check that exported memory files can be imported: every path stays in
+    /// the memory directory and the content fits the disk quota. Nothing is
+    /// written.
+    pub fn check_import_files(&self, files: &[MemoryFileExport]) -> std::io::Result<()> {
+        for file in files {
+            if !is_valid_memory_path(&file.path) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Invalid memory file path: {}", file.path),
+                ));
+            }
+        }
+
+        let total_bytes: u64 = files.iter().map(|f| f.content.len() as u64).sum();
+        if let Ok(guard) = self.disk_quota.lock() {
+            if let Some(ref dq) = *guard {
+                if let Err(e) = dq.check_quota(total_bytes) {
+                    return Err(std::io::Error::other(e.to_string()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write exported memory files into this store. The files are checked
+    /// (see `check_import_files`) before anything is written. A file whose
+    /// content is already present is skipped; otherwise the content is written,
+    /// or appended to an existing file of the same name. Returns (written, skipped).
+    pub fn import_files(&self, files: &[MemoryFileExport]) -> std::io::Result<(usize, usize)> {
+        self.check_import_files(files)?;
+
+        let (mut written, mut skipped) = (0, 0);
+        for file in files {
+            let path = self.memory_dir.join(&file.path);
+            let existing = file_ops::read_file(&path)?;
+            let content = file.content.trim();
+            if content.is_empty() || existing.contains(content) {
+                skipped += 1;
+                continue;
+            }
+            if existing.is_empty() {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&path, &file.content)?;
+            } else {
+                file_ops::append_raw(&path, content)?;
+            }
+            if let Ok(guard) = self.disk_quota.lock() {
+                if let Some(ref dq) = *guard {
+                    dq.record_write(file.content.len() as u64);
+                }
+            }
+            self.index_file(&path).ok();
+            written += 1;
+        }
+        Ok((written, skipped))
+    }
+
     /// Index or update a single file in the FTS index
     fn index_file(&self, file_path: &PathBuf) -> SqliteResult<()> {
         let conn = self.conn.lock().unwrap();
@@ -312,6 +457,62 @@ impl MemoryStore {
     }
 }
 
+/// Keyword search directly over the markdown files in `memory_dir`, bypassing the FTS index.
+/// Files are ranked by how many query-word occurrences they contain; the
+/// snippet is the first matching line. Scores are negated hit counts so they
+/// sort like BM25 (lower is better).
+pub fn keyword_search_files(memory_dir: &Path, query: &str, limit: i32) -> std::io::Result<Vec<SearchResult>> {
+    let words: Vec<String> = query
+        .split_whitespace()
+        .map(|w| w.to_lowercase())
+        .collect();
+    if words.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut results = Vec::new();
+    for file_path in file_ops::list_memory_files(memory_dir)? {
+        let Some(rel_path) = file_ops::relative_path(memory_dir, &file_path) else {
+            continue;
+        };
+        let Ok(content) = file_ops::read_file(&file_path) else {
+            continue;
+        };
+        let lower = content.to_lowercase();
+        let hits: usize = words.iter().map(|w| lower.matches(w.as_str()).count()).sum();
+        if hits == 0 {
+            continue;
+        }
+        let snippet = content
+            .lines()
+            .find(|line| {
+                let line = line.to_lowercase();
+                words.iter().any(|w| line.contains(w.as_str()))
+            })
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        results.push(SearchResult {
+            file_path: rel_path,
+            snippet,
+            score: -(hits as f64),
+        });
+    }
+
+    results.sort_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(std::cmp::Ordering::Equal));
+    results.truncate(limit.max(0) as usize);
+    Ok(results)
+}
+
+/// A relative `.md` path inside the memory directory (no `..`, no absolute paths)
+fn is_valid_memory_path(path: &str) -> bool {
+    let path = Path::new(path);
+    path.extension().is_some_and(|e| e == "md")
+        && path
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_)))
+}
+
 /// Escape special characters for FTS5 query
 fn escape_fts5_query(query: &str) -> String {
     // Split into words and join with OR for multi-word queries
@@ -341,6 +542,41 @@ fn escape_fts5_query(query: &str) -> String {
     escaped.join(" OR ")
 }
 
+/// Number of most recent daily log entries checked for near-duplicates
+const DEDUP_RECENT_ENTRIES: usize = 20;
+
+/// Split a daily log into entry bodies (the text under each `## HH:MM` header)
+fn split_log_entries(log: &str) -> Vec<&str> {
+    log.split("\n## ")
+        .filter_map(|chunk| chunk.split_once('\n').map(|(_, body)| body.trim()))
+        .filter(|body| !body.is_empty())
+        .collect()
+}
+
+/// Normalized token overlap (Jaccard index over lowercase alphanumeric words).
+/// Numbers are significant: texts whose tokens containing digits differ (an
+/// amount, a time, a transaction hash) score 0.
+fn token_similarity(a: &str, b: &str) -> f64 {
+    fn tokens(text: &str) -> std::collections::HashSet<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|t| !t.is_empty())
+            .map(|t| t.to_lowercase())
+            .collect()
+    }
+    let (a, b) = (tokens(a), tokens(b));
+    let numeric = |set: &std::collections::HashSet<String>| -> std::collections::HashSet<String> {
+        set.iter().filter(|t| t.chars().any(|c| c.is_ascii_digit())).cloned().collect()
+    };
+    if numeric(&a) != numeric(&b) {
+        return 0.0;
+    }
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let shared = a.intersection(&b).count();
+    shared as f64 / (a.len() + b.len() - shared) as f64
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -377,6 +613,29 @@ mod tests {
         assert!(results[0].file_path.contains("MEMORY.md"));
     }
 
+    #[test]
+    fn test_search_falls_back_to_keywords_when_index_fails() {
+        let dir = tempdir().unwrap();
+        let mem_dir = dir.path().join("memory");
+        let db_path = dir.path().join("test.db");
+
+        let store =
+            MemoryStore::new(mem_dir.clone(), db_path.to_str().unwrap()).expect("Failed to create store");
+        store
+            .append_long_term("User prefers dark mode in the dashboard", None)
+            .expect("Failed to append");
+
+        // Induce an index failure
+        store.conn.lock().unwrap().execute("DROP TABLE qmd_memory_fts", []).unwrap();
+        assert!(store.search("dark mode", 10).is_err());
+
+        let (results, degraded) = store.search_with_fallback("dark mode", 10);
+        assert!(degraded);
+        assert_eq!(results.len(), 1);
+        assert!(results[0].file_path.contains("MEMORY.md"));
+        assert!(results[0].snippet.contains("dark mode"));
+    }
+
     #[test]
     fn test_daily_log() {
         let dir = tempdir().unwrap();
@@ -394,6 +653,42 @@ mod tests {
         assert!(content.contains("feature X"));
     }
 
+    #[test]
+    fn test_daily_log_dedup_skips_near_duplicates() {
+        let dir = tempdir().unwrap();
+        let mem_dir = dir.path().join("memory");
+        let db_path = dir.path().join("test.db");
+
+        let store =
+            MemoryStore::new(mem_dir.clone(), db_path.to_str().unwrap()).expect("Failed to create store");
+
+        let entry = "### Session completed\n**User:** check my balance\n**Response:** Your balance is 12 USDC.";
+        assert!(store.append_daily_log_deduped(entry, None, 0.85).unwrap());
+        // Same blurb with trivial formatting differences is skipped
+        let near = "### Session Completed\n**User:** check my balance\n**Response:** your balance is 12 usdc";
+        assert!(!store.append_daily_log_deduped(near, None, 0.85).unwrap());
+        // An entry that differs only in a number is not a duplicate
+        let changed = "### Session completed\n**User:** check my balance\n**Response:** Your balance is 13 USDC.";
+        assert!(store.append_daily_log_deduped(changed, None, 0.85).unwrap());
+        // Different content is still written
+        let other = "### Session completed\n**User:** swap 5 USDC to ETH\n**Response:** Swap submitted.";
+        assert!(store.append_daily_log_deduped(other, None, 0.85).unwrap());
+        // Dedup disabled
+        assert!(store.append_daily_log_deduped(entry, None, 0.0).unwrap());
+
+        let content = store.get_daily_log(None).unwrap();
+        assert_eq!(split_log_entries(&content).len(), 4);
+    }
+
+    #[test]
+    fn test_token_similarity() {
+        assert_eq!(token_similarity("a b c", "c b a"), 1.0);
+        assert_eq!(token_similarity("a b", "c d"), 0.0);
+        assert!((token_similarity("a b c", "a b d") - 0.5).abs() < 1e-9);
+        assert_eq!(token_similarity("sent 5 USDC to alice", "sent 50 USDC to alice"), 0.0);
+        assert_eq!(token_similarity("sent 5 USDC", "SENT 5 usdc"), 1.0);
+    }
+
     #[test]
     fn test_identity_isolation() {
         let dir = tempdir().unwrap();
@@ -422,4 +717,27 @@ mod tests {
         assert!(user2_mem.contains("tea"));
         assert!(!user2_mem.contains("coffee"));
     }
+
+    #[test]
+    fn test_export_import_files_round_trip() {
+        let dir = tempdir().unwrap();
+        let source = MemoryStore::new(dir.path().join("a"), dir.path().join("a.db").to_str().unwrap()).unwrap();
+        source.append_long_term("Prefers USDC on Base", Some("alice")).unwrap();
+        source.append_long_term("Global fact", None).unwrap();
+
+        let alice = source.export_files(Some("alice")).unwrap();
+        assert_eq!(alice.len(), 1);
+        assert_eq!(alice[0].path, "alice/MEMORY.md");
+        assert_eq!(source.export_files(None).unwrap().len(), 2);
+
+        let target = MemoryStore::new(dir.path().join("b"), dir.path().join("b.db").to_str().unwrap()).unwrap();
+        assert_eq!(target.import_files(&alice).unwrap(), (1, 0));
+        assert_eq!(target.get_long_term(Some("alice")).unwrap(), alice[0].content);
+        // Importing the same bundle again changes nothing
+        assert_eq!(target.import_files(&alice).unwrap(), (0, 1));
+
+        let escape = MemoryFileExport { path: "../outside.md".to_string(), content: "x".to_string() };
+        assert!(target.check_import_files(std::slice::from_ref(&escape)).is_err());
+        assert!(target.import_files(&[escape]).is_err());
+    }
 }
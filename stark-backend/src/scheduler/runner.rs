@@ -1,4 +1,6 @@
+use crate::text::truncate_chars;
 use crate::channels::dispatcher::MessageDispatcher;
+use crate::channels::{delivery, quiet_hours, scheduled_messages};
 use crate::channels::types::NormalizedMessage;
 use crate::db::Database;
 use crate::execution::ExecutionTracker;
@@ -64,6 +66,147 @@ fn error_backoff_secs(error_count: i32) -> u64 {
     ERROR_BACKOFF_SECS[idx.min(ERROR_BACKOFF_SECS.len() - 1)]
 }
 
+/// Delay before the first retry of a failed cron job that has retries enabled
+/// but no retry delay of its own
+const DEFAULT_CRON_RETRY_DELAY_SECS: i64 = 60;
+/// Retry delays double with each attempt up to this cap
+const MAX_CRON_RETRY_DELAY_SECS: i64 = 60 * 60;
+
+/// When to retry a job whose run just failed, or None to give up.
+///
+/// Retries back off exponentially from the job's retry delay. A retry that
+/// would land at or after the job's next regular run is dropped and the
+/// regular run goes ahead instead, so retries never stack on the schedule.
+fn cron_retry_at(job: &CronJob, failed_at: DateTime<Utc>, next_run: Option<DateTime<Utc>>) -> Option<DateTime<Utc>> {
+    if job.retry_attempt >= job.max_retries.unwrap_or(0) {
+        return None;
+    }
+    let base = job
+        .retry_delay_seconds
+        .filter(|s| *s > 0)
+        .map(i64::from)
+        .unwrap_or(DEFAULT_CRON_RETRY_DELAY_SECS);
+    let delay = base
+        .saturating_mul(1i64 << job.retry_attempt.clamp(0, 16))
+        .min(MAX_CRON_RETRY_DELAY_SECS.max(base));
+    let retry_at = failed_at + Duration::seconds(delay);
+    match next_run {
+        Some(next) if next <= retry_at => None,
+        _ => Some(retry_at),
+    }
+}
+
+/// Record a finished cron job run and decide when the job runs next.
+///
+/// A run made while the job has a retry pending is logged as a retry of the
+/// failed run that started the chain. A failure with retries left schedules
+/// the next retry; otherwise the error backoff applies, and a one-shot job
+/// that has used up its retries is marked failed.
+#[allow(clippy::too_many_arguments)]
+fn record_cron_job_outcome(
+    db: &Database,
+    job: &CronJob,
+    started_at: DateTime<Utc>,
+    completed_at: DateTime<Utc>,
+    next_run: Option<DateTime<Utc>>,
+    success: bool,
+    response: &str,
+    error: Option<&str>,
+) -> Result<(), String> {
+    let (retry_of, attempt) = match job.retry_of_run_id {
+        Some(run_id) => (Some(run_id), job.retry_attempt),
+        None => (None, 0),
+    };
+    let run = db
+        .log_cron_job_run(
+            job.id,
+            &started_at.to_rfc3339(),
+            Some(&completed_at.to_rfc3339()),
+            success,
+            Some(response),
+            error,
+            Some((completed_at - started_at).num_milliseconds()),
+            retry_of,
+            attempt,
+        )
+        .map_err(|e| format!("Failed to log job run: {}", e))?;
+
+    let retry_at = if success { None } else { cron_retry_at(job, completed_at, next_run) };
+    let mut give_up = false;
+
+    let final_next_run = if success {
+        next_run
+    } else if let Some(retry_at) = retry_at {
+        log::info!(
+            "Cron job '{}' failed — retry {}/{} at {}",
+            job.name,
+            job.retry_attempt + 1,
+            job.max_retries.unwrap_or(0),
+            retry_at
+        );
+        Some(retry_at)
+    } else if next_run.is_none() && job.max_retries.unwrap_or(0) > 0 {
+        // One-shot job out of retries: nothing left to run
+        log::warn!("Cron job '{}' failed after {} retries, giving up", job.name, job.retry_attempt);
+        give_up = true;
+        None
+    } else {
+        // Apply error backoff: on failure, push next_run_at further into the future
+        // to prevent retry storms when a job keeps failing (e.g., API key expired, model down).
+        // Backoff: 30s → 1min → 5min → 15min → 60min based on consecutive error count.
+        let new_error_count = job.error_count + 1;
+        let backoff = error_backoff_secs(new_error_count);
+        let backoff_time = completed_at + Duration::seconds(backoff as i64);
+
+        // Use whichever is later: the normal next_run or the backoff time
+        let final_next = match next_run {
+            Some(normal_next) => {
+                if backoff_time > normal_next { Some(backoff_time) } else { Some(normal_next) }
+            }
+            None => Some(backoff_time), // one-shot jobs: still apply backoff
+        };
+
+        log::info!(
+            "Cron job '{}' failed (error #{}) — applying {}s backoff, next run at {:?}",
+            job.name, new_error_count, backoff, final_next
+        );
+
+        final_next
+    };
+
+    // Update job status with final result (including retry/backoff-adjusted next_run_at)
+    let final_next_run_str = final_next_run.map(|dt| dt.to_rfc3339());
+    db.update_cron_job_run_status(
+        job.id,
+        &started_at.to_rfc3339(),
+        final_next_run_str.as_deref(),
+        success,
+        error,
+    )
+    .map_err(|e| format!("Failed to update job status: {}", e))?;
+
+    // Advance the retry chain, or end it
+    let retry_state = match retry_at {
+        Some(_) => (job.retry_attempt + 1, retry_of.or(Some(run.id))),
+        None => (0, None),
+    };
+    if retry_state != (job.retry_attempt, job.retry_of_run_id) {
+        db.set_cron_job_retry_state(job.id, retry_state.0, retry_state.1)
+            .map_err(|e| format!("Failed to update job retry state: {}", e))?;
+    }
+
+    if give_up {
+        db.update_cron_job(
+            job.id,
+            None, None, None, None, None, None, None, None, None, None, None, None, None, None, None,
+            Some(JobStatus::Failed.as_str()),
+        )
+        .map_err(|e| format!("Failed to mark job as failed: {}", e))?;
+    }
+
+    Ok(())
+}
+
 /// The scheduler service that runs cron jobs and heartbeats
 pub struct Scheduler {
     db: Arc<Database>,
@@ -154,6 +297,26 @@ impl Scheduler {
             log::error!("Error processing heartbeats: {}", e);
         }
 
+        // Close polls whose voting window has ended and report results
+        if let Err(e) = self.process_polls().await {
+            log::error!("Error processing polls: {}", e);
+        }
+
+        // Send proactive messages held back by channel quiet hours
+        if let Err(e) = self.process_deferred_messages().await {
+            log::error!("Error processing deferred messages: {}", e);
+        }
+
+        // Send messages scheduled with the schedule_message tool
+        let db = &self.db;
+        if let Err(e) = scheduled_messages::deliver_due_messages(db, Utc::now(), |message| async move {
+            scheduled_messages::send_via_channel(db, &message).await
+        })
+        .await
+        {
+            log::error!("Error processing scheduled messages: {}", e);
+        }
+
         // Run periodic cleanup tasks once per hour (at minute 0, within first poll window)
         let now = Local::now();
         if now.minute() == 0 && now.second() < self.config.poll_interval_secs as u32 {
@@ -188,6 +351,29 @@ impl Scheduler {
         // Cleanup old telemetry spans (keep last 30 days)
         let telemetry_store = crate::telemetry::TelemetryStore::new(self.db.clone());
         telemetry_store.prune();
+
+        // Forget dispatched message ids past the redelivery window
+        let cutoff = Utc::now() - Duration::hours(crate::channels::dispatcher::PROCESSED_MESSAGE_TTL_HOURS);
+        match self.db.cleanup_processed_messages(cutoff) {
+            Ok(count) if count > 0 => {
+                log::info!("Scheduler: Cleaned up {} old processed message ids", count);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                log::error!("Scheduler: Failed to cleanup processed message ids: {}", e);
+            }
+        }
+
+        // Deactivate extra active sessions left behind on gateway channels
+        match self.db.repair_duplicate_gateway_sessions() {
+            Ok(count) if count > 0 => {
+                log::warn!("Scheduler: Deactivated {} stale active gateway sessions", count);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                log::error!("Scheduler: Failed to repair gateway sessions: {}", e);
+            }
+        }
     }
 
     /// Process due cron jobs
@@ -209,6 +395,65 @@ impl Scheduler {
         Ok(())
     }
 
+    /// Close due polls and send each tally back to the chat session that created the poll
+    async fn process_polls(&self) -> Result<(), String> {
+        let due_polls = self
+            .db
+            .list_due_polls(Utc::now())
+            .map_err(|e| format!("Failed to list due polls: {}", e))?;
+
+        for poll in due_polls {
+            let tally = match crate::channels::polls::close_poll(&self.db, &poll).await {
+                Ok(Some(tally)) => tally,
+                Ok(None) => continue,
+                Err(e) => {
+                    log::error!("Failed to close poll {}: {}", poll.poll_id, e);
+                    continue;
+                }
+            };
+            log::info!("Poll {} closed with {} vote(s)", poll.poll_id, tally.total_votes);
+            if let Err(e) = delivery::validate_chat_id(&poll.channel_type, &poll.chat_id) {
+                log::error!("Not reporting results of poll {}: {}", poll.poll_id, e);
+                continue;
+            }
+
+            // Same channel/chat as the poll so the follow-up lands in the originating session
+            let normalized = NormalizedMessage {
+                channel_id: poll.channel_id,
+                channel_type: poll.channel_type.clone(),
+                chat_id: poll.chat_id.clone(),
+                chat_name: None,
+                is_thread: false,
+                user_id: "system".to_string(),
+                user_name: "Poll".to_string(),
+                text: crate::channels::polls::poll_results_message(&tally),
+                attachments: Vec::new(),
+                message_id: Some(format!("poll-results-{}", poll.poll_id)),
+                session_mode: None,
+                selected_network: None,
+                // Results come back to the chat unattended; keep the follow-up to safe tools
+                force_safe_mode: true,
+                agent_settings_override: None,
+                ephemeral: false,
+                dry_run: false,
+            };
+            let scheduler = self.clone_inner();
+            tokio::spawn(async move {
+                let result = scheduler.dispatcher.dispatch_safe(normalized).await;
+                if let Some(e) = result.error {
+                    log::error!("Poll {} results follow-up failed: {}", poll.poll_id, e);
+                    return;
+                }
+                let source = format!("poll:{}", poll.poll_id);
+                if let Err(e) = scheduler.deliver_to_chat(poll.channel_id, &poll.chat_id, &source, &result.response).await {
+                    log::error!("Failed to deliver results of poll {}: {}", poll.poll_id, e);
+                }
+            });
+        }
+
+        Ok(())
+    }
+
     /// Process kanban tasks that are in "ready" status (auto-execute)
     async fn process_kanban_tasks(&self) -> Result<(), String> {
         // Check if auto-execute is enabled in bot settings
@@ -269,13 +514,18 @@ impl Scheduler {
             channel_type: "kanban".to_string(),
             chat_id: format!("kanban:task-{}", task.id),
             chat_name: None,
+            is_thread: false,
             user_id: "system".to_string(),
             user_name: "Kanban".to_string(),
             text: message_text,
+            attachments: Vec::new(),
             message_id: Some(format!("kanban-{}-{}", task.id, started_at.timestamp())),
             session_mode: Some("isolated".to_string()),
             selected_network: None,
             force_safe_mode: false,
+            agent_settings_override: None,
+            ephemeral: false,
+            dry_run: false,
         };
 
         // Execute with 10-minute timeout (same as cron default)
@@ -308,11 +558,7 @@ impl Scheduler {
             // Mark as complete with result and session_id
             let update = crate::db::tables::kanban::UpdateKanbanItemRequest {
                 status: Some("complete".to_string()),
-                result: Some(if response.len() > 2000 {
-                    format!("{}...", &response[..2000])
-                } else {
-                    response.clone()
-                }),
+                result: Some(truncate_chars(&response, 2000)),
                 session_id,
                 ..Default::default()
             };
@@ -355,7 +601,6 @@ impl Scheduler {
     /// Execute a single cron job
     async fn execute_cron_job(&self, job: &CronJob) -> Result<(), String> {
         let started_at = Utc::now();
-        let started_at_str = started_at.to_rfc3339();
 
         log::info!("Executing cron job '{}' ({})", job.name, job.job_id);
 
@@ -427,13 +672,18 @@ impl Scheduler {
             channel_type: "cron".to_string(),
             chat_id: format!("cron:{}:{}", job.job_id, started_at.timestamp()),
             chat_name: None,
+            is_thread: false,
             user_id: "system".to_string(),
             user_name: format!("Cron: {}", job.name),
             text: message_text,
+            attachments: Vec::new(),
             message_id: Some(format!("cron-run-{}", started_at.timestamp())),
             session_mode: Some(job.session_mode.clone()),
             selected_network: None,
             force_safe_mode: false,
+            agent_settings_override: None,
+            ephemeral: false,
+            dry_run: false,
         };
 
         // Execute the job with timeout
@@ -464,53 +714,17 @@ impl Scheduler {
             }
         };
 
-        // Apply error backoff: on failure, push next_run_at further into the future
-        // to prevent retry storms when a job keeps failing (e.g., API key expired, model down).
-        // Backoff: 30s → 1min → 5min → 15min → 60min based on consecutive error count.
-        let final_next_run_str = if !success {
-            let new_error_count = job.error_count + 1;
-            let backoff = error_backoff_secs(new_error_count);
-            let backoff_time = completed_at + Duration::seconds(backoff as i64);
-
-            // Use whichever is later: the normal next_run or the backoff time
-            let final_next = match next_run {
-                Some(normal_next) => {
-                    if backoff_time > normal_next { Some(backoff_time) } else { Some(normal_next) }
-                }
-                None => Some(backoff_time), // one-shot jobs: still apply backoff
-            };
-
-            log::info!(
-                "Cron job '{}' failed (error #{}) — applying {}s backoff, next run at {:?}",
-                job.name, new_error_count, backoff, final_next
-            );
-
-            final_next.map(|dt| dt.to_rfc3339())
-        } else {
-            next_run_str.clone()
-        };
-
-        // Update job status with final result (including backoff-adjusted next_run_at)
-        self.db
-            .update_cron_job_run_status(
-                job.id,
-                &started_at_str,
-                final_next_run_str.as_deref(),
-                success,
-                error_msg.as_deref(),
-            )
-            .map_err(|e| format!("Failed to update job status: {}", e))?;
-
-        // Log the run
-        let _ = self.db.log_cron_job_run(
-            job.id,
-            &started_at_str,
-            Some(&completed_at.to_rfc3339()),
+        // Log the run and schedule the next one (retry, backoff or regular schedule)
+        record_cron_job_outcome(
+            &self.db,
+            job,
+            started_at,
+            completed_at,
+            next_run,
             success,
-            Some(&response),
+            &response,
             error_msg.as_deref(),
-            Some(duration_ms),
-        );
+        )?;
 
         // Handle delete_after_run for one-shot jobs
         if success && job.delete_after_run {
@@ -576,26 +790,66 @@ impl Scheduler {
         }
     }
 
-    /// Deliver job result to the configured channel
+    /// Deliver job result to the configured channel, or queue it if the channel is in quiet hours
     async fn deliver_result(&self, job: &CronJob, response: &str) -> Result<(), String> {
-        // For now, we just log that we would deliver
-        // In a full implementation, this would send to the channel
-        log::info!(
-            "Would deliver cron job '{}' result to channel {} (to: {:?}): {}",
-            job.name,
-            job.channel_id.unwrap_or(0),
-            job.deliver_to,
-            if response.len() > 100 {
-                format!("{}...", &response[..100])
-            } else {
-                response.to_string()
-            }
-        );
+        let channel_id = job.channel_id.unwrap_or(0);
+        let source = format!("cron:{}", job.job_id);
+        if quiet_hours::defer_if_quiet(&self.db, channel_id, job.deliver_to.as_deref(), &source, response, Utc::now())? {
+            return Ok(());
+        }
+        self.send_to_channel(channel_id, job.deliver_to.as_deref(), &source, response).await
+    }
 
-        // TODO: Implement actual channel delivery
-        // This would involve looking up the channel and sending a message
+    /// Post an agent response to a chat, or queue it if the channel is in quiet hours
+    async fn deliver_to_chat(&self, channel_id: i64, chat_id: &str, source: &str, response: &str) -> Result<(), String> {
+        if response.trim().is_empty() {
+            return Ok(());
+        }
+        if quiet_hours::defer_if_quiet(&self.db, channel_id, Some(chat_id), source, response, Utc::now())? {
+            return Ok(());
+        }
+        self.send_to_channel(channel_id, Some(chat_id), source, response).await
+    }
 
-        Ok(())
+    /// Send deferred messages whose channel's quiet hours have ended
+    async fn process_deferred_messages(&self) -> Result<(), String> {
+        quiet_hours::flush_deferred_messages(&self.db, Utc::now(), |message| async move {
+            self.send_to_channel(message.channel_id, message.chat_id.as_deref(), &message.source, &message.text)
+                .await
+        })
+        .await
+        .map(|_| ())
+    }
+
+    /// Send a proactive message to a channel's chat through its outbound queue.
+    /// A message delivered only in part is not reported as failed, so it is never resent.
+    async fn send_to_channel(&self, channel_id: i64, to: Option<&str>, source: &str, text: &str) -> Result<(), String> {
+        let channel = self
+            .db
+            .get_channel(channel_id)
+            .map_err(|e| format!("Failed to load channel {}: {}", channel_id, e))?
+            .ok_or_else(|| format!("Channel {} not found", channel_id))?;
+        let chat_id = to.ok_or_else(|| format!("No chat to deliver {} to on channel {}", source, channel_id))?;
+
+        let delivery = delivery::send_text(&self.db, channel_id, &channel.channel_type, chat_id, text).await;
+        match delivery.error {
+            None => {
+                log::info!("Delivered {} to {} chat {}", source, channel.channel_type, chat_id);
+                Ok(())
+            }
+            Some(e) if delivery.sent_parts.is_empty() => Err(e),
+            Some(e) => {
+                log::error!(
+                    "Delivered only {} part(s) of {} to {} chat {}: {}",
+                    delivery.sent_parts.len(),
+                    source,
+                    channel.channel_type,
+                    chat_id,
+                    e
+                );
+                Ok(())
+            }
+        }
     }
 
     /// Process due heartbeats
@@ -767,13 +1021,18 @@ impl Scheduler {
             channel_type: HEARTBEAT_CHANNEL_TYPE.to_string(),
             chat_id: HEARTBEAT_CHAT_ID.to_string(),
             chat_name: None,
+            is_thread: false,
             user_id: HEARTBEAT_USER_ID.to_string(),
             user_name: HEARTBEAT_USER_NAME.to_string(),
             text: message_text,
+            attachments: Vec::new(),
             message_id: Some(format!("heartbeat-{}", now.timestamp())),
             session_mode: Some("isolated".to_string()), // Isolated to prevent state corruption
             selected_network: None,
             force_safe_mode: false,
+            agent_settings_override: None,
+            ephemeral: false,
+            dry_run: false,
         };
 
         // Execute the heartbeat
@@ -1016,13 +1275,18 @@ async fn execute_heartbeat_isolated(
         channel_type: HEARTBEAT_CHANNEL_TYPE.to_string(),
         chat_id: HEARTBEAT_CHAT_ID.to_string(),
         chat_name: None,
+        is_thread: false,
         user_id: HEARTBEAT_USER_ID.to_string(),
         user_name: HEARTBEAT_USER_NAME.to_string(),
         text: message_text,
+        attachments: Vec::new(),
         message_id: Some(format!("heartbeat-{}", now.timestamp())),
         session_mode: Some("isolated".to_string()),
         selected_network: None,
         force_safe_mode: false,
+        agent_settings_override: None,
+        ephemeral: false,
+        dry_run: false,
     };
 
     // === DEFERRED AI CALL (fire and forget) ===
@@ -1083,3 +1347,92 @@ async fn execute_heartbeat_isolated(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_job(db: &Database, schedule_type: &str, schedule_value: &str, max_retries: i32) -> CronJob {
+        let job = db
+            .create_cron_job(
+                "flaky", None, schedule_type, schedule_value, None, "isolated", Some("do the thing"),
+                None, None, None, false, None, None, None, false,
+            )
+            .unwrap();
+        db.set_cron_job_retry_policy(job.id, Some(max_retries), Some(30)).unwrap()
+    }
+
+    #[test]
+    fn test_failing_job_retries_then_gives_up() {
+        let db = Database::new(":memory:").unwrap();
+        let job = create_job(&db, "at", "2026-03-10T12:00:00Z", 3);
+        let mut now = Utc::now();
+        let mut delays = Vec::new();
+
+        // Keep failing: the original run plus three retries, then no more
+        loop {
+            let job = db.get_cron_job(job.id).unwrap().unwrap();
+            if job.status != JobStatus::Active.as_str() {
+                break;
+            }
+            assert!(delays.len() <= 3, "job kept retrying past max_retries");
+            record_cron_job_outcome(&db, &job, now, now, None, false, "", Some("boom")).unwrap();
+
+            let job = db.get_cron_job(job.id).unwrap().unwrap();
+            if let Some(next) = job.next_run_at.as_deref() {
+                let next = DateTime::parse_from_rfc3339(next).unwrap().with_timezone(&Utc);
+                delays.push((next - now).num_seconds());
+                now = next;
+            }
+        }
+
+        // Retries back off from the job's 30s delay
+        assert_eq!(delays, vec![30, 60, 120]);
+
+        let job = db.get_cron_job(job.id).unwrap().unwrap();
+        assert_eq!(job.status, JobStatus::Failed.as_str());
+        assert_eq!(job.error_count, 4);
+        assert_eq!((job.retry_attempt, job.retry_of_run_id), (0, None));
+        assert!(job.next_run_at.is_none());
+
+        // Every retry run links back to the original failed run
+        let mut runs = db.get_cron_job_runs(job.id, 10).unwrap();
+        runs.reverse();
+        assert_eq!(runs.len(), 4);
+        let original = &runs[0];
+        assert_eq!((original.retry_of, original.attempt), (None, 0));
+        for (i, run) in runs[1..].iter().enumerate() {
+            assert_eq!(run.retry_of, Some(original.id));
+            assert_eq!(run.attempt, i as i32 + 1);
+        }
+    }
+
+    #[test]
+    fn test_retry_success_ends_chain_and_schedule_wins_over_late_retry() {
+        let db = Database::new(":memory:").unwrap();
+        let now = Utc::now();
+
+        // A retry that succeeds ends the chain and returns to the schedule
+        let job = create_job(&db, "every", "3600000", 2);
+        let next_run = now + Duration::hours(1);
+        record_cron_job_outcome(&db, &job, now, now, Some(next_run), false, "", Some("boom")).unwrap();
+        let job = db.get_cron_job(job.id).unwrap().unwrap();
+        assert_eq!(job.retry_attempt, 1);
+        record_cron_job_outcome(&db, &job, now, now, Some(next_run), true, "ok", None).unwrap();
+        let job = db.get_cron_job(job.id).unwrap().unwrap();
+        assert_eq!((job.retry_attempt, job.retry_of_run_id), (0, None));
+        assert_eq!(job.next_run_at, Some(next_run.to_rfc3339()));
+        let runs = db.get_cron_job_runs(job.id, 10).unwrap();
+        assert_eq!(runs[0].attempt, 1);
+        assert!(runs[0].success);
+
+        // When the next regular run comes before the retry would, no retry is queued
+        let job = create_job(&db, "every", "10000", 2);
+        let next_run = now + Duration::seconds(10);
+        assert!(cron_retry_at(&job, now, Some(next_run)).is_none());
+        record_cron_job_outcome(&db, &job, now, now, Some(next_run), false, "", Some("boom")).unwrap();
+        let job = db.get_cron_job(job.id).unwrap().unwrap();
+        assert_eq!(job.retry_attempt, 0);
+        assert_eq!(job.status, JobStatus::Active.as_str());
+    }
+}
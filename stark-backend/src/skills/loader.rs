@@ -137,6 +137,7 @@ fn serde_yaml_parse(yaml: &str) -> Result<SkillMetadata, String> {
     let mut current_key = String::new();
     let mut in_arguments = false;
     let mut in_api_keys = false;
+    let mut in_model_override = false;
     let mut current_arg_name = String::new();
     let mut current_arg = crate::skills::types::SkillArgument {
         description: String::new(),
@@ -176,6 +177,7 @@ fn serde_yaml_parse(yaml: &str) -> Result<SkillMetadata, String> {
                 current_key = key.to_string();
                 in_arguments = key == "arguments";
                 in_api_keys = key == "requires_api_keys";
+                in_model_override = key == "model_override";
 
                 match key {
                     "name" => metadata.name = unquote(value),
@@ -233,6 +235,13 @@ fn serde_yaml_parse(yaml: &str) -> Result<SkillMetadata, String> {
                         default: None,
                     };
                 }
+            } else if in_model_override {
+                if let Some((key, value)) = trimmed.split_once(':') {
+                    metadata
+                        .model_override
+                        .get_or_insert_with(Default::default)
+                        .set_field(key.trim(), unquote(value));
+                }
             } else if in_api_keys {
                 // API key name
                 if let Some((key_name, _)) = trimmed.split_once(':') {
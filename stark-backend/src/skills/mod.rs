@@ -5,5 +5,5 @@ pub mod zip_parser;
 
 pub use loader::{load_skill_from_file, load_skills_from_directory, parse_skill_file};
 pub use registry::{create_default_registry, SkillRegistry};
-pub use types::{DbSkill, DbSkillScript, InstalledSkill, Skill, SkillArgument, SkillMetadata, SkillSource};
+pub use types::{DbSkill, DbSkillScript, Skill, SkillModelOverride};
 pub use zip_parser::{parse_skill_md, parse_skill_zip, ParsedScript, ParsedSkill};
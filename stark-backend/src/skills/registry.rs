@@ -119,6 +119,7 @@ impl SkillRegistry {
             tags: metadata.tags,
             subagent_type: metadata.subagent_type,
             requires_api_keys: metadata.requires_api_keys,
+            model_override: metadata.model_override,
             scripts: Vec::new(),
         };
 
@@ -143,6 +144,7 @@ impl SkillRegistry {
             tags: metadata.tags,
             subagent_type: metadata.subagent_type,
             requires_api_keys: metadata.requires_api_keys,
+            model_override: metadata.model_override,
             scripts: Vec::new(), // No scripts for plain markdown
         };
 
@@ -169,6 +171,7 @@ impl SkillRegistry {
             tags: parsed.tags,
             subagent_type: parsed.subagent_type,
             requires_api_keys: parsed.requires_api_keys,
+            model_override: parsed.model_override,
             created_at: now.clone(),
             updated_at: now.clone(),
         };
@@ -217,6 +220,7 @@ impl SkillRegistry {
             tags: parsed.tags,
             subagent_type: parsed.subagent_type,
             requires_api_keys: parsed.requires_api_keys,
+            model_override: parsed.model_override,
             created_at: now.clone(),
             updated_at: now.clone(),
         };
@@ -341,6 +345,7 @@ impl SkillRegistry {
             tags: skill.metadata.tags.clone(),
             subagent_type: skill.metadata.subagent_type.clone(),
             requires_api_keys: skill.metadata.requires_api_keys.clone(),
+            model_override: skill.metadata.model_override.clone(),
             created_at: now.clone(),
             updated_at: now.clone(),
         };
@@ -54,6 +54,48 @@ fn default_secret() -> bool {
     true
 }
 
+/// Model a skill runs on instead of the channel's agent, from the
+/// `model_override` frontmatter block. Unset fields keep the agent's value.
+/// Credentials are never stored on the skill: they come from the agent
+/// settings saved for the override's endpoint.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SkillModelOverride {
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    #[serde(default)]
+    pub model_archetype: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+impl SkillModelOverride {
+    pub fn is_empty(&self) -> bool {
+        self.endpoint.is_none() && self.model_archetype.is_none() && self.model.is_none()
+    }
+
+    /// Set a field from a `key: value` frontmatter line
+    pub(crate) fn set_field(&mut self, key: &str, value: String) {
+        if value.is_empty() {
+            return;
+        }
+        match key {
+            "endpoint" => self.endpoint = Some(value),
+            "model_archetype" | "archetype" => self.model_archetype = Some(value),
+            "model" => self.model = Some(value),
+            _ => {}
+        }
+    }
+
+    pub fn to_settings_override(&self) -> crate::models::AgentSettingsOverride {
+        crate::models::AgentSettingsOverride {
+            endpoint: self.endpoint.clone(),
+            model_archetype: self.model_archetype.clone(),
+            model: self.model.clone(),
+            ..Default::default()
+        }
+    }
+}
+
 /// Argument definition for a skill
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SkillArgument {
@@ -89,6 +131,8 @@ pub struct SkillMetadata {
     pub subagent_type: Option<String>,
     #[serde(default)]
     pub requires_api_keys: HashMap<String, SkillApiKey>,
+    #[serde(default)]
+    pub model_override: Option<SkillModelOverride>,
 }
 
 fn default_version() -> String {
@@ -110,6 +154,7 @@ impl Default for SkillMetadata {
             metadata: None,
             subagent_type: None,
             requires_api_keys: HashMap::new(),
+            model_override: None,
         }
     }
 }
@@ -206,6 +251,7 @@ pub struct DbSkill {
     pub tags: Vec<String>,
     pub subagent_type: Option<String>,
     pub requires_api_keys: HashMap<String, SkillApiKey>,
+    pub model_override: Option<SkillModelOverride>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -227,6 +273,7 @@ impl DbSkill {
                 metadata: self.metadata,
                 subagent_type: self.subagent_type,
                 requires_api_keys: self.requires_api_keys,
+                model_override: self.model_override,
             },
             prompt_template: self.body,
             source: SkillSource::Managed, // All DB skills are "managed"
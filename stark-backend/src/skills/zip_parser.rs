@@ -1,4 +1,4 @@
-use crate::skills::types::{SkillApiKey, SkillArgument, SkillMetadata};
+use crate::skills::types::{SkillApiKey, SkillArgument, SkillMetadata, SkillModelOverride};
 use std::collections::HashMap;
 use std::io::{Cursor, Read};
 use zip::ZipArchive;
@@ -19,6 +19,7 @@ pub struct ParsedSkill {
     pub tags: Vec<String>,
     pub subagent_type: Option<String>,
     pub requires_api_keys: HashMap<String, SkillApiKey>,
+    pub model_override: Option<SkillModelOverride>,
     pub scripts: Vec<ParsedScript>,
 }
 
@@ -167,6 +168,7 @@ pub fn parse_skill_zip(data: &[u8]) -> Result<ParsedSkill, String> {
         tags: metadata.tags,
         subagent_type: metadata.subagent_type,
         requires_api_keys: metadata.requires_api_keys,
+        model_override: metadata.model_override,
         scripts,
     })
 }
@@ -212,6 +214,7 @@ fn parse_yaml_frontmatter(yaml: &str) -> Result<SkillMetadata, String> {
     let mut current_key = String::new();
     let mut in_arguments = false;
     let mut in_api_keys = false;
+    let mut in_model_override = false;
     let mut current_arg_name = String::new();
     let mut current_arg = SkillArgument {
         description: String::new(),
@@ -251,6 +254,7 @@ fn parse_yaml_frontmatter(yaml: &str) -> Result<SkillMetadata, String> {
                 current_key = key.to_string();
                 in_arguments = key == "arguments";
                 in_api_keys = key == "requires_api_keys";
+                in_model_override = key == "model_override";
 
                 match key {
                     "name" => metadata.name = unquote(value),
@@ -307,6 +311,13 @@ fn parse_yaml_frontmatter(yaml: &str) -> Result<SkillMetadata, String> {
                         default: None,
                     };
                 }
+            } else if in_model_override {
+                if let Some((key, value)) = trimmed.split_once(':') {
+                    metadata
+                        .model_override
+                        .get_or_insert_with(Default::default)
+                        .set_field(key.trim(), unquote(value));
+                }
             } else if in_api_keys {
                 // API key name
                 if let Some((key_name, _)) = trimmed.split_once(':') {
@@ -65,6 +65,7 @@ impl Adapter<Span, Timeline> for SpansToTimeline {
                     SpanType::Watchdog => format!("Watchdog: {}", span.name),
                     SpanType::Rollout => format!("Rollout: {}", span.name),
                     SpanType::ResourceResolution => format!("Resource: {}", span.name),
+                    SpanType::ChannelSend => format!("Send: {}", span.name),
                 };
 
                 TimelineEntry {
@@ -326,6 +327,169 @@ impl Adapter<Span, Vec<Triplet>> for SpansToTriplets {
     }
 }
 
+// ─── Trace tree ────────────────────────────────────────────────────
+
+/// A span with its nested child spans.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceNode {
+    pub span_id: String,
+    pub sequence_id: u64,
+    pub span_type: SpanType,
+    pub name: String,
+    pub status: SpanStatus,
+    pub success: bool,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub duration_ms: Option<u64>,
+    pub error: Option<String>,
+    /// Reward value, for reward spans
+    pub reward: Option<f64>,
+    pub attributes: Value,
+    pub children: Vec<TraceNode>,
+}
+
+/// One attempt of a rollout with its span tree.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttemptTrace {
+    pub attempt_idx: u32,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub duration_ms: u64,
+    /// No span in the attempt failed or timed out
+    pub success: bool,
+    pub total_reward: f64,
+    pub spans: Vec<TraceNode>,
+}
+
+/// One rollout (a dispatched message) and its attempts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RolloutTrace {
+    pub rollout_id: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub duration_ms: u64,
+    /// The last attempt succeeded
+    pub success: bool,
+    pub total_reward: f64,
+    pub attempts: Vec<AttemptTrace>,
+}
+
+/// Every persisted span of a session as a rollout → attempt → span tree.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTrace {
+    pub session_id: i64,
+    pub total_spans: usize,
+    pub total_reward: f64,
+    pub rollouts: Vec<RolloutTrace>,
+}
+
+/// Transforms a session's spans into a rollout → attempt → span tree.
+/// Spans nest under their parent span; spans whose parent isn't in the
+/// attempt become top-level nodes.
+pub struct SpansToTrace;
+
+impl Adapter<Span, SessionTrace> for SpansToTrace {
+    fn transform(&self, spans: &[Span]) -> SessionTrace {
+        let mut sorted: Vec<&Span> = spans.iter().collect();
+        sorted.sort_by_key(|s| (s.started_at, s.sequence_id));
+
+        // Group by rollout (in order of first appearance), then by attempt
+        let mut rollouts: Vec<(String, std::collections::BTreeMap<u32, Vec<&Span>>)> = Vec::new();
+        for span in sorted {
+            let idx = match rollouts.iter().position(|(id, _)| *id == span.rollout_id) {
+                Some(idx) => idx,
+                None => {
+                    rollouts.push((span.rollout_id.clone(), Default::default()));
+                    rollouts.len() - 1
+                }
+            };
+            rollouts[idx].1.entry(span.attempt_idx).or_default().push(span);
+        }
+
+        let rollouts: Vec<RolloutTrace> = rollouts
+            .into_iter()
+            .map(|(rollout_id, attempts)| {
+                let attempts: Vec<AttemptTrace> = attempts
+                    .into_iter()
+                    .map(|(attempt_idx, spans)| attempt_trace(attempt_idx, &spans))
+                    .collect();
+                let started_at = attempts.iter().map(|a| a.started_at).min().unwrap_or_else(Utc::now);
+                let ended_at = attempts.iter().filter_map(|a| a.ended_at).max();
+                RolloutTrace {
+                    rollout_id,
+                    started_at,
+                    ended_at,
+                    duration_ms: elapsed_ms(started_at, ended_at),
+                    success: attempts.last().map(|a| a.success).unwrap_or(false),
+                    total_reward: attempts.iter().map(|a| a.total_reward).sum(),
+                    attempts,
+                }
+            })
+            .collect();
+
+        SessionTrace {
+            session_id: spans.first().map(|s| s.session_id).unwrap_or(0),
+            total_spans: spans.len(),
+            total_reward: rollouts.iter().map(|r| r.total_reward).sum(),
+            rollouts,
+        }
+    }
+}
+
+fn attempt_trace(attempt_idx: u32, spans: &[&Span]) -> AttemptTrace {
+    let started_at = spans.iter().map(|s| s.started_at).min().unwrap_or_else(Utc::now);
+    let ended_at = spans.iter().filter_map(|s| s.completed_at).max();
+    let ids: std::collections::HashSet<&str> = spans.iter().map(|s| s.span_id.as_str()).collect();
+    let roots: Vec<TraceNode> = spans
+        .iter()
+        .filter(|s| !s.parent_span_id.as_deref().is_some_and(|p| ids.contains(p)))
+        .map(|s| trace_node(s, spans))
+        .collect();
+
+    AttemptTrace {
+        attempt_idx,
+        started_at,
+        ended_at,
+        duration_ms: elapsed_ms(started_at, ended_at),
+        success: !spans.iter().any(|s| matches!(s.status, SpanStatus::Failed | SpanStatus::TimedOut)),
+        total_reward: spans.iter().filter_map(|s| reward_value(s)).sum(),
+        spans: roots,
+    }
+}
+
+fn trace_node(span: &Span, spans: &[&Span]) -> TraceNode {
+    TraceNode {
+        span_id: span.span_id.clone(),
+        sequence_id: span.sequence_id,
+        span_type: span.span_type,
+        name: span.name.clone(),
+        status: span.status,
+        success: span.status == SpanStatus::Succeeded,
+        started_at: span.started_at,
+        completed_at: span.completed_at,
+        duration_ms: span.duration_ms,
+        error: span.error.clone(),
+        reward: reward_value(span),
+        attributes: span.attributes.clone(),
+        children: spans
+            .iter()
+            .filter(|s| s.parent_span_id.as_deref() == Some(span.span_id.as_str()))
+            .map(|s| trace_node(s, spans))
+            .collect(),
+    }
+}
+
+fn reward_value(span: &Span) -> Option<f64> {
+    if span.span_type != SpanType::Reward {
+        return None;
+    }
+    Some(span.attributes.get("reward_value").and_then(|v| v.as_f64()).unwrap_or(0.0))
+}
+
+fn elapsed_ms(start: DateTime<Utc>, end: Option<DateTime<Utc>>) -> u64 {
+    end.map(|end| (end - start).num_milliseconds().max(0) as u64).unwrap_or(0)
+}
+
 // ─── Helpers ───────────────────────────────────────────────────────
 
 fn status_label(status: SpanStatus) -> &'static str {
@@ -338,3 +502,48 @@ fn status_label(status: SpanStatus) -> &'static str {
         SpanStatus::Cancelled => "cancelled",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(seq: u64, rollout: &str, attempt: u32, span_type: SpanType, name: &str) -> Span {
+        Span::new(seq, rollout.to_string(), 7, attempt, span_type, name.to_string())
+    }
+
+    #[test]
+    fn test_trace_groups_rollouts_attempts_and_nests_children() {
+        let mut llm = span(1, "r1", 0, SpanType::LlmCall, "generate_with_tools");
+        llm.succeed();
+        let mut tool = span(2, "r1", 0, SpanType::ToolCall, "web_fetch").with_parent(llm.span_id.clone());
+        tool.fail("HTTP 500".to_string());
+        let mut retry_tool = span(3, "r1", 1, SpanType::ToolCall, "web_fetch");
+        retry_tool.succeed();
+        let reward = span(4, "r1", 1, SpanType::Reward, "tool_completed")
+            .with_attributes(serde_json::json!({"reward_value": 0.5}));
+        let mut other = span(5, "r2", 0, SpanType::ToolCall, "say_to_user");
+        other.succeed();
+
+        let trace = SpansToTrace.transform(&[llm.clone(), tool, retry_tool, reward, other]);
+
+        assert_eq!(trace.session_id, 7);
+        assert_eq!(trace.total_spans, 5);
+        assert_eq!(trace.rollouts.len(), 2);
+        let r1 = &trace.rollouts[0];
+        assert_eq!(r1.rollout_id, "r1");
+        assert_eq!(r1.attempts.len(), 2);
+        assert!(r1.success, "last attempt succeeded");
+        assert_eq!(r1.total_reward, 0.5);
+
+        let first = &r1.attempts[0];
+        assert!(!first.success);
+        assert_eq!(first.spans.len(), 1, "tool span nests under its LLM call");
+        assert_eq!(first.spans[0].span_id, llm.span_id);
+        assert_eq!(first.spans[0].children[0].error.as_deref(), Some("HTTP 500"));
+
+        let second = &r1.attempts[1];
+        assert_eq!(second.spans.len(), 2);
+        assert_eq!(second.spans[1].reward, Some(0.5));
+        assert_eq!(trace.total_reward, 0.5);
+    }
+}
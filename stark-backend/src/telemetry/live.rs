@@ -0,0 +1,127 @@
+//! Live span feed for real-time trace views.
+//!
+//! Spans are persisted by `TelemetryStore` when a rollout finishes; this feed
+//! pushes each span to subscribers the moment `SpanCollector::record` is
+//! called, so the dashboard can watch a session as it runs.
+
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use tokio::sync::mpsc;
+
+use super::span::Span;
+
+/// Per-subscriber buffer. Spans are dropped for a subscriber that falls this far behind.
+const LIVE_SPAN_BUFFER: usize = 256;
+
+/// Upper bound on concurrent subscriptions across all clients.
+pub const MAX_LIVE_SUBSCRIPTIONS: usize = 64;
+
+struct Subscriber {
+    session_id: i64,
+    sender: mpsc::Sender<Span>,
+}
+
+/// Fan-out of recorded spans to live subscribers, filtered by session.
+pub struct SpanFeed {
+    next_id: AtomicU64,
+    subscribers: DashMap<u64, Subscriber>,
+}
+
+impl SpanFeed {
+    pub fn new() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            subscribers: DashMap::new(),
+        }
+    }
+
+    /// Subscribe to spans for a session. Returns (subscription_id, receiver).
+    pub fn subscribe(&self, session_id: i64) -> Result<(u64, mpsc::Receiver<Span>), String> {
+        if self.subscribers.len() >= MAX_LIVE_SUBSCRIPTIONS {
+            return Err(format!(
+                "Too many live telemetry subscriptions (max {})",
+                MAX_LIVE_SUBSCRIPTIONS
+            ));
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, rx) = mpsc::channel(LIVE_SPAN_BUFFER);
+        self.subscribers.insert(id, Subscriber { session_id, sender });
+        Ok((id, rx))
+    }
+
+    /// Remove a subscription.
+    pub fn unsubscribe(&self, subscription_id: u64) {
+        self.subscribers.remove(&subscription_id);
+    }
+
+    /// Push a span to every subscriber watching its session. Never blocks;
+    /// closed subscriptions are removed.
+    pub fn publish(&self, span: &Span) {
+        if self.subscribers.is_empty() {
+            return;
+        }
+        let mut closed = Vec::new();
+        for entry in self.subscribers.iter() {
+            if entry.session_id != span.session_id {
+                continue;
+            }
+            match entry.sender.try_send(span.clone()) {
+                Ok(()) => {}
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    log::warn!(
+                        "[TELEMETRY] Live subscriber {} is lagging, dropping span '{}'",
+                        entry.key(),
+                        span.name
+                    );
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => closed.push(*entry.key()),
+            }
+        }
+        for id in closed {
+            self.subscribers.remove(&id);
+        }
+    }
+}
+
+impl Default for SpanFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Process-wide live span feed.
+pub fn span_feed() -> &'static SpanFeed {
+    static FEED: OnceLock<SpanFeed> = OnceLock::new();
+    FEED.get_or_init(SpanFeed::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::{SpanCollector, SpanType};
+
+    #[tokio::test]
+    async fn test_recorded_span_pushed_to_subscriber() {
+        // Unique session id so parallel tests recording spans don't interfere
+        let session_id = 9_200_227;
+        let (sub_id, mut rx) = span_feed().subscribe(session_id).unwrap();
+
+        let collector = SpanCollector::new("rollout-live".to_string(), session_id);
+        let mut span = collector.start_span(SpanType::ToolCall, "web_fetch");
+        span.succeed();
+        collector.record(span);
+
+        let other = SpanCollector::new("rollout-other".to_string(), session_id + 1);
+        other.record(other.start_span(SpanType::ToolCall, "ignored"));
+
+        let received = rx.try_recv().expect("span should be pushed on record");
+        assert_eq!(received.name, "web_fetch");
+        assert_eq!(received.rollout_id, "rollout-live");
+        assert!(rx.try_recv().is_err(), "spans from other sessions are filtered out");
+
+        span_feed().unsubscribe(sub_id);
+        collector.record(collector.start_span(SpanType::Reward, "after"));
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -13,13 +13,14 @@ pub mod watchdog;
 pub mod resource_version;
 pub mod adapter;
 pub mod store;
+pub mod live;
 
 // Re-export key types for convenience
 pub use span::{Span, SpanCollector, SpanGuard, SpanStatus, SpanType};
 pub use rollout::{Attempt, FailureReason, Rollout, RolloutConfig, RolloutManager, RolloutStatus};
 pub use emitter::{clear_active_collector, emit_annotation, set_active_collector};
-pub use reward::RewardEmitter;
-pub use watchdog::{Watchdog, WatchdogConfig, WatchdogError};
+pub use reward::{DefaultRewardFunction, RewardEmitter, RewardFunction};
+pub use watchdog::{HeartbeatProgress, Watchdog, WatchdogConfig};
 pub use resource_version::{Resource, ResourceBundle, ResourceManager, ResourceType};
-pub use adapter::{Adapter, ExecutionSummary, SpansToSummary, SpansToTimeline, SpansToTriplets, Timeline, Triplet};
-pub use store::{RetentionPolicy, RewardStats, TelemetryStore};
+pub use store::{RolloutQuery, TelemetryStore};
+pub use live::span_feed;
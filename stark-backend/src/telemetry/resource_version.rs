@@ -6,8 +6,12 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::sync::Arc;
 
+/// Channel setting key holding a channel's pinned resource version
+pub const CHANNEL_PIN_SETTING: &str = "resource_version_id";
+
 /// A single versioned resource.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Resource {
@@ -100,6 +104,8 @@ pub struct ResourceManager {
     db: Arc<crate::db::Database>,
     /// Cache of the current active bundle
     active_cache: parking_lot::RwLock<Option<ResourceBundle>>,
+    /// Bundles loaded by version id (bundles are immutable once created)
+    version_cache: parking_lot::RwLock<HashMap<String, ResourceBundle>>,
 }
 
 impl ResourceManager {
@@ -107,6 +113,7 @@ impl ResourceManager {
         Self {
             db,
             active_cache: parking_lot::RwLock::new(None),
+            version_cache: parking_lot::RwLock::new(HashMap::new()),
         }
     }
 
@@ -222,12 +229,78 @@ impl ResourceManager {
         }
     }
 
+    /// Get a specific version by its version_id.
+    pub fn get_version(&self, version_id: &str) -> Option<ResourceBundle> {
+        if let Some(cached) = self.version_cache.read().get(version_id) {
+            return Some(cached.clone());
+        }
+
+        match self.db.get_resource_bundle(version_id) {
+            Ok(Some(bundle)) => {
+                self.version_cache.write().insert(version_id.to_string(), bundle.clone());
+                Some(bundle)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                log::error!("[RESOURCES] Failed to load resource bundle {}: {}", version_id, e);
+                None
+            }
+        }
+    }
+
+    /// Pin a channel to a specific version, or clear its pin with `None`.
+    pub fn pin_channel(&self, channel_id: i64, version_id: Option<&str>) -> Result<(), String> {
+        match version_id {
+            Some(version_id) => {
+                if self.get_version(version_id).is_none() {
+                    return Err(format!("Resource version '{}' not found", version_id));
+                }
+                self.db.set_channel_setting(channel_id, CHANNEL_PIN_SETTING, version_id)
+                    .map_err(|e| format!("Failed to pin resource version: {}", e))
+            }
+            None => self.db.delete_channel_setting(channel_id, CHANNEL_PIN_SETTING)
+                .map(|_| ())
+                .map_err(|e| format!("Failed to clear resource version pin: {}", e)),
+        }
+    }
+
+    /// Get the version pinned to a channel, if any.
+    pub fn pinned_version_id(&self, channel_id: i64) -> Option<String> {
+        self.db.get_channel_setting(channel_id, CHANNEL_PIN_SETTING)
+            .ok()
+            .flatten()
+            .filter(|v| !v.is_empty())
+    }
+
+    /// Version a channel should use: its pin if set (and still present),
+    /// otherwise the global active version.
+    pub fn effective_version_id(&self, channel_id: i64) -> Option<String> {
+        if let Some(pinned) = self.pinned_version_id(channel_id) {
+            if self.get_version(&pinned).is_some() {
+                return Some(pinned);
+            }
+            log::warn!(
+                "[RESOURCES] Channel {} is pinned to missing version {}, using active version",
+                channel_id, pinned
+            );
+        }
+        self.active_version_id()
+    }
+
     /// Resolve a prompt by name, falling back to compile-time default.
     pub fn resolve_prompt(&self, name: &str) -> String {
-        if let Some(bundle) = self.get_active() {
-            if let Some(content) = bundle.get_prompt(name) {
-                return content.to_string();
-            }
+        self.resolve_prompt_in(name, None)
+    }
+
+    /// Resolve a prompt by name from a specific version (`None` = the active
+    /// version), falling back to compile-time default.
+    pub fn resolve_prompt_in(&self, name: &str, version_id: Option<&str>) -> String {
+        let bundle = match version_id {
+            Some(version_id) => self.get_version(version_id),
+            None => self.get_active(),
+        };
+        if let Some(content) = bundle.as_ref().and_then(|b| b.get_prompt(name)) {
+            return content.to_string();
         }
 
         // Fallback to compiled-in defaults
@@ -1,19 +1,128 @@
 //! RewardEmitter with auto-scoring for tool success/failure,
 //! session efficiency, and loop detection.
+//!
+//! Session rewards are computed by a pluggable [`RewardFunction`] (set via
+//! `RolloutConfig::reward_function`). The component breakdown it returns is
+//! stored in the reward span's attributes for offline analysis.
 
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::BTreeMap;
+use std::fmt;
 use std::sync::Arc;
 
 use super::span::{SpanCollector, SpanType};
 
+/// Outcome of a single tool call, as seen by the reward function.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolDuration {
+    pub tool_name: String,
+    pub duration_ms: u64,
+    pub success: bool,
+}
+
+/// Raw signals available when scoring a completed session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionSignals {
+    pub success: bool,
+    pub iterations: u32,
+    pub tool_calls: u32,
+    pub max_iterations: u32,
+    /// Every tool call reported to the emitter during this rollout, in order
+    pub tool_durations: Vec<ToolDuration>,
+}
+
+/// A scalar reward together with the components that produced it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RewardBreakdown {
+    pub value: f64,
+    pub components: BTreeMap<String, f64>,
+}
+
+impl RewardBreakdown {
+    /// Build a breakdown whose value is the sum of its components.
+    pub fn from_components<K: Into<String>>(components: impl IntoIterator<Item = (K, f64)>) -> Self {
+        let components: BTreeMap<String, f64> =
+            components.into_iter().map(|(k, v)| (k.into(), v)).collect();
+        Self { value: components.values().sum(), components }
+    }
+}
+
+/// Scores a completed session.
+pub trait RewardFunction: Send + Sync {
+    /// Name recorded with every reward span this function produces.
+    fn name(&self) -> &str;
+
+    /// Compute the session reward from its raw signals.
+    fn session_reward(&self, signals: &SessionSignals) -> RewardBreakdown;
+}
+
+impl fmt::Debug for dyn RewardFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RewardFunction({})", self.name())
+    }
+}
+
+/// The built-in session scoring.
+///
+/// - Base: +2.0 for successful completion, -1.0 otherwise
+/// - Efficiency bonus: scales inversely with iteration count
+/// - Penalty for excessive iterations: -0.1 per iteration over a third of the max
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultRewardFunction;
+
+impl RewardFunction for DefaultRewardFunction {
+    fn name(&self) -> &str {
+        "default"
+    }
+
+    fn session_reward(&self, signals: &SessionSignals) -> RewardBreakdown {
+        let base = if signals.success { 2.0 } else { -1.0 };
+        let mut efficiency_bonus = 0.0;
+        let mut iteration_penalty = 0.0;
+
+        if signals.success {
+            // Efficiency bonus: fewer iterations = higher reward
+            let efficiency_ratio = 1.0 - (signals.iterations as f64 / signals.max_iterations as f64);
+            efficiency_bonus = efficiency_ratio.max(0.0) * 1.0;
+
+            // Penalty for high iteration counts
+            let threshold = signals.max_iterations / 3;
+            if signals.iterations > threshold {
+                iteration_penalty = -((signals.iterations - threshold) as f64 * 0.1);
+            }
+        }
+
+        RewardBreakdown::from_components([
+            ("base", base),
+            ("efficiency_bonus", efficiency_bonus),
+            ("iteration_penalty", iteration_penalty),
+        ])
+    }
+}
+
 /// Emits structured reward signals based on execution outcomes.
 pub struct RewardEmitter {
     collector: Arc<SpanCollector>,
+    reward_function: Arc<dyn RewardFunction>,
+    /// Tool outcomes seen so far, handed to the reward function at session end
+    tool_durations: Mutex<Vec<ToolDuration>>,
 }
 
 impl RewardEmitter {
     pub fn new(collector: Arc<SpanCollector>) -> Self {
-        Self { collector }
+        Self {
+            collector,
+            reward_function: Arc::new(DefaultRewardFunction),
+            tool_durations: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Score sessions with a custom reward function instead of the default.
+    pub fn with_reward_function(mut self, reward_function: Arc<dyn RewardFunction>) -> Self {
+        self.reward_function = reward_function;
+        self
     }
 
     /// Emit a reward for a completed tool call.
@@ -23,6 +132,12 @@ impl RewardEmitter {
     /// - Failure: -0.5
     /// - Bonus for fast execution (< 1s): +0.2
     pub fn tool_completed(&self, tool_name: &str, success: bool, duration_ms: u64) {
+        self.tool_durations.lock().push(ToolDuration {
+            tool_name: tool_name.to_string(),
+            duration_ms,
+            success,
+        });
+
         let mut value = if success { 1.0 } else { -0.5 };
 
         // Bonus for fast successful tools
@@ -42,12 +157,8 @@ impl RewardEmitter {
         self.collector.record(span);
     }
 
-    /// Emit a reward for session completion.
-    ///
-    /// Scoring:
-    /// - Base: +2.0 for successful completion
-    /// - Efficiency bonus: scales inversely with iteration count
-    /// - Penalty for excessive iterations: -0.1 per iteration over threshold
+    /// Emit a reward for session completion, scored by the configured
+    /// [`RewardFunction`]. The component breakdown is kept in the span attributes.
     pub fn session_completed(
         &self,
         success: bool,
@@ -55,24 +166,21 @@ impl RewardEmitter {
         tool_calls: u32,
         max_iterations: u32,
     ) {
-        let mut value = if success { 2.0 } else { -1.0 };
-
-        if success {
-            // Efficiency bonus: fewer iterations = higher reward
-            let efficiency_ratio = 1.0 - (iterations as f64 / max_iterations as f64);
-            value += efficiency_ratio.max(0.0) * 1.0;
-
-            // Penalty for high iteration counts
-            let threshold = max_iterations / 3;
-            if iterations > threshold {
-                value -= (iterations - threshold) as f64 * 0.1;
-            }
-        }
+        let signals = SessionSignals {
+            success,
+            iterations,
+            tool_calls,
+            max_iterations,
+            tool_durations: self.tool_durations.lock().clone(),
+        };
+        let breakdown = self.reward_function.session_reward(&signals);
 
         let mut span = self.collector.start_span(SpanType::Reward, "session_completed");
         span.attributes = json!({
-            "reward_value": value,
+            "reward_value": breakdown.value,
             "reward_type": "session_completed",
+            "reward_function": self.reward_function.name(),
+            "components": breakdown.components,
             "success": success,
             "iterations": iterations,
             "tool_calls": tool_calls,
@@ -135,3 +243,73 @@ impl RewardEmitter {
         self.collector.record(span);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn emitter() -> (RewardEmitter, Arc<SpanCollector>) {
+        let collector = Arc::new(SpanCollector::new("rollout-1".to_string(), 1));
+        (RewardEmitter::new(Arc::clone(&collector)), collector)
+    }
+
+    fn session_span(collector: &SpanCollector) -> serde_json::Value {
+        collector
+            .drain()
+            .into_iter()
+            .find(|s| s.name == "session_completed")
+            .expect("session_completed span")
+            .attributes
+    }
+
+    #[test]
+    fn test_default_reward_function_keeps_original_scoring() {
+        let (emitter, collector) = emitter();
+        emitter.session_completed(true, 6, 4, 9);
+
+        // 2.0 base + (1 - 6/9) efficiency - (6 - 3) * 0.1 penalty
+        let attrs = session_span(&collector);
+        let expected = 2.0 + (1.0 - 6.0 / 9.0) - 0.3;
+        assert!((attrs["reward_value"].as_f64().unwrap() - expected).abs() < 1e-9);
+        assert_eq!(attrs["reward_function"], "default");
+        assert_eq!(attrs["components"]["base"], 2.0);
+        assert!((attrs["components"]["iteration_penalty"].as_f64().unwrap() + 0.3).abs() < 1e-9);
+
+        emitter.session_completed(false, 1, 0, 1);
+        let attrs = session_span(&collector);
+        assert_eq!(attrs["reward_value"], -1.0);
+        assert_eq!(attrs["components"]["efficiency_bonus"], 0.0);
+    }
+
+    struct SlowToolPenalty;
+
+    impl RewardFunction for SlowToolPenalty {
+        fn name(&self) -> &str {
+            "slow_tool_penalty"
+        }
+
+        fn session_reward(&self, signals: &SessionSignals) -> RewardBreakdown {
+            let slow = signals.tool_durations.iter().filter(|t| t.duration_ms >= 1000).count();
+            RewardBreakdown::from_components([
+                ("outcome", if signals.success { 1.0 } else { 0.0 }),
+                ("slow_tools", -(slow as f64)),
+            ])
+        }
+    }
+
+    #[test]
+    fn test_custom_reward_function_sees_tool_durations_and_breakdown_is_recorded() {
+        let (emitter, collector) = emitter();
+        let emitter = emitter.with_reward_function(Arc::new(SlowToolPenalty));
+        emitter.tool_completed("web_fetch", true, 2500);
+        emitter.tool_completed("read_file", true, 20);
+        emitter.tool_completed("exec", false, 1200);
+        emitter.session_completed(true, 3, 3, 10);
+
+        let attrs = session_span(&collector);
+        assert_eq!(attrs["reward_function"], "slow_tool_penalty");
+        assert_eq!(attrs["reward_value"], -1.0);
+        assert_eq!(attrs["components"]["outcome"], 1.0);
+        assert_eq!(attrs["components"]["slow_tools"], -2.0);
+    }
+}
@@ -7,9 +7,15 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
+use super::reward::{DefaultRewardFunction, RewardFunction};
 use super::span::SpanCollector;
+use super::store::TelemetryStore;
+use crate::ai::ToolHistoryEntry;
 
 /// The lifecycle status of a rollout.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -66,6 +72,16 @@ pub struct RolloutConfig {
     pub exponential_backoff: bool,
     /// Maximum retry delay when using exponential backoff (ms)
     pub max_retry_delay_ms: u64,
+    /// Save each failed attempt's tool history so it can be replayed (adds DB writes)
+    #[serde(default)]
+    pub snapshot_tool_history: bool,
+    /// Scores the session reward. Not serialized: reward spans record its name
+    #[serde(skip, default = "default_reward_function")]
+    pub reward_function: Arc<dyn RewardFunction>,
+}
+
+fn default_reward_function() -> Arc<dyn RewardFunction> {
+    Arc::new(DefaultRewardFunction)
 }
 
 impl Default for RolloutConfig {
@@ -74,13 +90,16 @@ impl Default for RolloutConfig {
             timeout_secs: 300,     // 5 minutes
             max_attempts: 3,
             retry_conditions: vec![
+                // Transient provider errors (429/5xx) are already retried inside
+                // each AI client per its RetryPolicy; don't retry them again here
                 RetryCondition::OnTimeout,
-                RetryCondition::OnLlmError,
                 RetryCondition::OnContextOverflow,
             ],
             retry_delay_ms: 1000,
             exponential_backoff: true,
             max_retry_delay_ms: 30_000,
+            snapshot_tool_history: false,
+            reward_function: default_reward_function(),
         }
     }
 }
@@ -137,12 +156,23 @@ impl FailureReason {
             FailureReason::LoopDetected
         } else if lower.contains("cancelled") || lower.contains("canceled") {
             FailureReason::Cancelled
-        } else if lower.contains("rate limit") || lower.contains("429") || lower.contains("500") || lower.contains("503") {
+        } else if lower.contains("rate limit")
+            || lower.contains("overloaded")
+            || lower.contains("[http ")
+            || ["429", "500", "502", "503", "504"].iter().any(|code| lower.contains(code))
+        {
             FailureReason::LlmError(error.to_string())
         } else {
             FailureReason::Unknown(error.to_string())
         }
     }
+
+    /// Whether the failure came from the model provider (as opposed to the
+    /// agent loop itself), so switching to a different model could help.
+    /// Unclassified errors don't count: they are as likely to be ours.
+    pub fn is_provider_error(&self) -> bool {
+        matches!(self, FailureReason::Timeout | FailureReason::LlmError(_))
+    }
 }
 
 /// A single attempt within a rollout.
@@ -168,6 +198,9 @@ pub struct Attempt {
     pub llm_calls: u32,
     /// Total tokens consumed in this attempt
     pub tokens_used: u64,
+    /// Model that served this attempt (set once the AI client is created)
+    #[serde(default)]
+    pub model: Option<String>,
 }
 
 impl Attempt {
@@ -183,6 +216,7 @@ impl Attempt {
             tool_calls: 0,
             llm_calls: 0,
             tokens_used: 0,
+            model: None,
         }
     }
 
@@ -278,14 +312,110 @@ impl Rollout {
     }
 }
 
+/// Snapshot of system-wide rollout concurrency (exposed in health).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RolloutConcurrency {
+    pub running: usize,
+    pub queued: usize,
+    pub max_concurrent: usize,
+}
+
+/// A running-rollout slot. The slot is released when this is dropped.
+pub struct RolloutSlot {
+    _permit: OwnedSemaphorePermit,
+}
+
+/// Decrements the queued counter when a waiter leaves the queue (acquired, timed out or cancelled).
+struct QueuedGuard<'a>(&'a AtomicUsize);
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 /// Manages the lifecycle of rollouts and attempts.
+///
+/// Also bounds how many rollouts run at once system-wide: callers hold a
+/// `RolloutSlot` for the duration of a dispatch, and excess dispatches wait in
+/// a bounded queue with a timeout.
 pub struct RolloutManager {
     db: Arc<crate::db::Database>,
+    store: TelemetryStore,
+    slots: Arc<Semaphore>,
+    max_concurrent: usize,
+    max_queued: usize,
+    queue_timeout: Duration,
+    queued: AtomicUsize,
 }
 
 impl RolloutManager {
     pub fn new(db: Arc<crate::db::Database>) -> Self {
-        Self { db }
+        Self::with_concurrency_limit(
+            db,
+            crate::config::max_concurrent_rollouts(),
+            crate::config::rollout_queue_max(),
+            crate::config::rollout_queue_timeout(),
+        )
+    }
+
+    /// Create a manager allowing `max_concurrent` running rollouts, with up to
+    /// `max_queued` more waiting at most `queue_timeout` for a slot.
+    pub fn with_concurrency_limit(
+        db: Arc<crate::db::Database>,
+        max_concurrent: usize,
+        max_queued: usize,
+        queue_timeout: Duration,
+    ) -> Self {
+        let max_concurrent = max_concurrent.max(1);
+        Self {
+            store: TelemetryStore::new(Arc::clone(&db)),
+            db,
+            slots: Arc::new(Semaphore::new(max_concurrent)),
+            max_concurrent,
+            max_queued,
+            queue_timeout,
+            queued: AtomicUsize::new(0),
+        }
+    }
+
+    /// Take a rollout slot if one is free right now.
+    pub fn try_acquire_slot(&self) -> Option<RolloutSlot> {
+        self.slots
+            .clone()
+            .try_acquire_owned()
+            .ok()
+            .map(|permit| RolloutSlot { _permit: permit })
+    }
+
+    /// Wait in the queue for a rollout slot. Fails immediately when the queue
+    /// is full, or once `queue_timeout` elapses without a slot freeing up.
+    pub async fn wait_for_slot(&self) -> Result<RolloutSlot, String> {
+        if self.queued.fetch_add(1, Ordering::SeqCst) >= self.max_queued {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            return Err(format!(
+                "System busy: {} rollouts running and {} queued. Please try again shortly.",
+                self.max_concurrent, self.max_queued
+            ));
+        }
+        let _queued = QueuedGuard(&self.queued);
+        match tokio::time::timeout(self.queue_timeout, self.slots.clone().acquire_owned()).await {
+            Ok(Ok(permit)) => Ok(RolloutSlot { _permit: permit }),
+            Ok(Err(_)) => Err("Rollout limiter closed".to_string()),
+            Err(_) => Err(format!(
+                "System busy: no rollout slot freed up within {}s. Please try again shortly.",
+                self.queue_timeout.as_secs()
+            )),
+        }
+    }
+
+    /// Current running/queued rollout counts.
+    pub fn concurrency(&self) -> RolloutConcurrency {
+        RolloutConcurrency {
+            running: self.max_concurrent - self.slots.available_permits(),
+            queued: self.queued.load(Ordering::SeqCst),
+            max_concurrent: self.max_concurrent,
+        }
     }
 
     /// Create a new rollout and its first attempt.
@@ -311,6 +441,22 @@ impl RolloutManager {
         (rollout, collector)
     }
 
+    /// Attach the rollout to its session once the session is resolved.
+    pub fn set_session(&self, rollout: &mut Rollout, session_id: i64) {
+        if let Err(e) = self.db.set_rollout_session(&rollout.rollout_id, session_id) {
+            log::error!("[ROLLOUT] Failed to persist rollout session: {}", e);
+        }
+        rollout.session_id = session_id;
+    }
+
+    /// Record which resource version the rollout uses.
+    pub fn set_resources(&self, rollout: &mut Rollout, resources_id: Option<String>) {
+        if let Err(e) = self.db.set_rollout_resources_id(&rollout.rollout_id, resources_id.as_deref()) {
+            log::error!("[ROLLOUT] Failed to persist rollout resources: {}", e);
+        }
+        rollout.resources_id = resources_id;
+    }
+
     /// Transition the rollout to running status.
     pub fn mark_running(&self, rollout: &mut Rollout) {
         rollout.status = RolloutStatus::Running;
@@ -343,28 +489,11 @@ impl RolloutManager {
         error: &str,
         collector: &SpanCollector,
     ) -> bool {
-        let reason = FailureReason::classify(error);
-
-        if let Some(attempt) = rollout.current_attempt_mut() {
-            attempt.fail(reason.clone(), error.to_string());
-        }
-
-        // Persist the failed attempt
-        if let Err(e) = self.db.update_attempt(
-            &rollout.rollout_id,
-            rollout.attempt_count().saturating_sub(1),
-            false,
-            Some(error),
-        ) {
-            log::error!("[ROLLOUT] Failed to persist attempt failure: {}", e);
-        }
+        let reason = self.record_attempt_failure(rollout, error, collector);
 
         // Check retry policy
         if rollout.config.should_retry(&reason, rollout.attempt_count()) {
-            let new_idx = rollout.attempt_count();
-            let new_attempt = Attempt::new(new_idx);
-            rollout.attempts.push(new_attempt);
-            collector.set_attempt(new_idx);
+            let new_idx = self.push_attempt(rollout, collector);
 
             log::info!(
                 "[ROLLOUT] Retrying rollout {} (attempt {}/{}), reason: {:?}",
@@ -374,11 +503,6 @@ impl RolloutManager {
                 reason
             );
 
-            // Persist the new attempt
-            if let Err(e) = self.db.create_attempt(&rollout.rollout_id, new_idx) {
-                log::error!("[ROLLOUT] Failed to persist new attempt: {}", e);
-            }
-
             true
         } else {
             // No more retries, fail the rollout
@@ -393,6 +517,94 @@ impl RolloutManager {
         }
     }
 
+    /// Fail the current attempt and start a new one on a fallback model.
+    ///
+    /// Unlike `fail_attempt` this ignores the retry policy: the fallback chain
+    /// itself bounds how many extra attempts can happen.
+    pub fn fall_back_attempt(
+        &self,
+        rollout: &mut Rollout,
+        error: &str,
+        collector: &SpanCollector,
+        model: &str,
+    ) {
+        let reason = self.record_attempt_failure(rollout, error, collector);
+        let new_idx = self.push_attempt(rollout, collector);
+        self.set_attempt_model(rollout, model);
+
+        log::info!(
+            "[ROLLOUT] Falling back to model '{}' for rollout {} (attempt {}), reason: {:?}",
+            model,
+            rollout.rollout_id,
+            new_idx + 1,
+            reason
+        );
+    }
+
+    /// Record which model serves the current attempt.
+    pub fn set_attempt_model(&self, rollout: &mut Rollout, model: &str) {
+        let attempt_idx = rollout.attempt_count().saturating_sub(1);
+        if let Some(attempt) = rollout.current_attempt_mut() {
+            attempt.model = Some(model.to_string());
+        }
+        if let Err(e) = self.db.set_attempt_model(&rollout.rollout_id, attempt_idx, model) {
+            log::error!("[ROLLOUT] Failed to persist attempt model: {}", e);
+        }
+    }
+
+    /// Mark the current attempt failed and persist it (and its tool history).
+    fn record_attempt_failure(
+        &self,
+        rollout: &mut Rollout,
+        error: &str,
+        collector: &SpanCollector,
+    ) -> FailureReason {
+        let reason = FailureReason::classify(error);
+
+        if let Some(attempt) = rollout.current_attempt_mut() {
+            attempt.fail(reason.clone(), error.to_string());
+        }
+
+        // Persist the failed attempt
+        let attempt_idx = rollout.attempt_count().saturating_sub(1);
+        if let Err(e) = self.db.update_attempt(
+            &rollout.rollout_id,
+            attempt_idx,
+            false,
+            Some(error),
+        ) {
+            log::error!("[ROLLOUT] Failed to persist attempt failure: {}", e);
+        }
+        // The loop's history is only meaningful for the attempt that produced it
+        if let Some(history) = collector.take_tool_history() {
+            if rollout.config.snapshot_tool_history {
+                self.store.save_attempt_history(&rollout.rollout_id, attempt_idx, &history);
+            }
+        }
+
+        reason
+    }
+
+    /// Append and persist a fresh attempt, returning its index.
+    fn push_attempt(&self, rollout: &mut Rollout, collector: &SpanCollector) -> u32 {
+        let new_idx = rollout.attempt_count();
+        rollout.attempts.push(Attempt::new(new_idx));
+        collector.set_attempt(new_idx);
+
+        // Persist the new attempt
+        if let Err(e) = self.db.create_attempt(&rollout.rollout_id, new_idx) {
+            log::error!("[ROLLOUT] Failed to persist new attempt: {}", e);
+        }
+
+        new_idx
+    }
+
+    /// Load the tool history saved for a failed attempt (see
+    /// `RolloutConfig::snapshot_tool_history`).
+    pub fn load_attempt_history(&self, rollout_id: &str, attempt: u32) -> Option<Vec<ToolHistoryEntry>> {
+        self.store.load_attempt_history(rollout_id, attempt)
+    }
+
     /// Cancel the rollout.
     pub fn cancel_rollout(&self, rollout: &mut Rollout) {
         if let Some(attempt) = rollout.current_attempt_mut() {
@@ -432,3 +644,114 @@ impl RolloutManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limited_manager(max_concurrent: usize, max_queued: usize, timeout_ms: u64) -> Arc<RolloutManager> {
+        let db = Arc::new(crate::db::Database::new(":memory:").unwrap());
+        Arc::new(RolloutManager::with_concurrency_limit(
+            db,
+            max_concurrent,
+            max_queued,
+            Duration::from_millis(timeout_ms),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_rollouts_beyond_limit_wait_for_a_slot() {
+        let manager = limited_manager(2, 4, 5_000);
+        let first = manager.try_acquire_slot().expect("slot 1");
+        let _second = manager.try_acquire_slot().expect("slot 2");
+        assert!(manager.try_acquire_slot().is_none(), "limit reached");
+
+        let waiter = {
+            let manager = Arc::clone(&manager);
+            tokio::spawn(async move { manager.wait_for_slot().await.map(|_slot| ()) })
+        };
+        while manager.concurrency().queued == 0 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(
+            manager.concurrency(),
+            RolloutConcurrency { running: 2, queued: 1, max_concurrent: 2 }
+        );
+        assert!(!waiter.is_finished(), "queued rollout must not start while slots are busy");
+
+        drop(first);
+        waiter.await.unwrap().expect("queued rollout gets the freed slot");
+        assert_eq!(manager.concurrency().queued, 0);
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_and_timeout_are_rejected() {
+        let manager = limited_manager(1, 0, 5_000);
+        let _slot = manager.try_acquire_slot().unwrap();
+        assert!(manager.wait_for_slot().await.is_err(), "queue of 0 rejects immediately");
+
+        let manager = limited_manager(1, 1, 20);
+        let _slot = manager.try_acquire_slot().unwrap();
+        let err = manager.wait_for_slot().await.err().expect("times out");
+        assert!(err.contains("System busy"));
+        assert_eq!(manager.concurrency().queued, 0);
+    }
+
+    /// File-backed: saving an attempt spans more than one pooled connection
+    fn file_backed_manager() -> RolloutManager {
+        let path = std::env::temp_dir().join(format!("stark-rollout-{}.db", uuid::Uuid::new_v4()));
+        let db = Arc::new(crate::db::Database::new(path.to_str().unwrap()).unwrap());
+        RolloutManager::with_concurrency_limit(db, 1, 0, Duration::from_secs(1))
+    }
+
+    fn sample_history() -> Vec<ToolHistoryEntry> {
+        vec![ToolHistoryEntry::new(
+            vec![crate::ai::ToolCall {
+                id: "call-1".to_string(),
+                name: "web_fetch".to_string(),
+                arguments: serde_json::json!({"url": "https://example.com"}),
+            }],
+            vec![crate::ai::ToolResponse::error("call-1".to_string(), "connection reset".to_string())],
+        )]
+    }
+
+    #[test]
+    fn test_failed_attempt_history_is_snapshotted_when_enabled() {
+        let manager = file_backed_manager();
+        let config = RolloutConfig { snapshot_tool_history: true, ..RolloutConfig::default() };
+        let (mut rollout, collector) = manager.start_rollout(1, 1, config);
+
+        collector.record_tool_history(sample_history());
+        assert!(manager.fail_attempt(&mut rollout, "Tool loop timed out", &collector));
+        // The retry leaves no history of its own
+        manager.fail_attempt(&mut rollout, "Tool loop timed out", &collector);
+
+        let history = manager.load_attempt_history(&rollout.rollout_id, 0).expect("attempt 0 history");
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].tool_calls[0].name, "web_fetch");
+        assert_eq!(history[0].tool_responses[0].content, "connection reset");
+        assert!(history[0].tool_responses[0].is_error);
+        assert!(manager.load_attempt_history(&rollout.rollout_id, 1).is_none());
+    }
+
+    #[test]
+    fn test_only_provider_failures_count_as_provider_errors() {
+        assert!(FailureReason::classify("[HTTP 404] model is not available").is_provider_error());
+        assert!(FailureReason::classify("upstream overloaded, try again").is_provider_error());
+        assert!(FailureReason::classify("request timed out").is_provider_error());
+        assert!(!FailureReason::classify("failed to parse tool arguments").is_provider_error());
+        assert!(!FailureReason::classify("tool loop detected").is_provider_error());
+    }
+
+    #[test]
+    fn test_attempt_history_not_saved_by_default() {
+        let manager = file_backed_manager();
+        let (mut rollout, collector) = manager.start_rollout(1, 1, RolloutConfig::default());
+
+        collector.record_tool_history(sample_history());
+        manager.fail_attempt(&mut rollout, "LLM error: 503 Service Unavailable", &collector);
+
+        assert!(manager.load_attempt_history(&rollout.rollout_id, 0).is_none());
+        assert!(collector.take_tool_history().is_none(), "stale history must not leak into the next attempt");
+    }
+}
@@ -10,6 +10,8 @@ use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
 use std::sync::Arc;
 use parking_lot::Mutex;
 
+use crate::ai::ToolHistoryEntry;
+
 /// The kind of operation a span represents.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -30,6 +32,8 @@ pub enum SpanType {
     Watchdog,
     /// A resource version resolution
     ResourceResolution,
+    /// Outbound message delivery to a channel platform
+    ChannelSend,
 }
 
 /// The completion status of a span.
@@ -162,6 +166,25 @@ impl Span {
         self.completed_at = Some(now);
         self.duration_ms = Some((now - self.started_at).num_milliseconds().max(0) as u64);
     }
+
+    /// Flat record for structured log shipping (one JSON object per span).
+    pub fn to_log_record(&self) -> Value {
+        serde_json::json!({
+            "timestamp": self.completed_at.unwrap_or(self.started_at).to_rfc3339(),
+            "span_id": self.span_id,
+            "parent_span_id": self.parent_span_id,
+            "span_type": self.span_type,
+            "name": self.name,
+            "status": self.status,
+            "success": self.status == SpanStatus::Succeeded,
+            "duration_ms": self.duration_ms,
+            "error": self.error,
+            "attributes": self.attributes,
+            "rollout_id": self.rollout_id,
+            "session_id": self.session_id,
+            "attempt_idx": self.attempt_idx,
+        })
+    }
 }
 
 /// Thread-safe accumulator for spans within a rollout.
@@ -180,6 +203,8 @@ pub struct SpanCollector {
     attempt_idx: AtomicU64,
     /// Collected spans (thread-safe)
     spans: Mutex<Vec<Span>>,
+    /// Tool history left by the current attempt's tool loop
+    tool_history: Mutex<Option<Vec<ToolHistoryEntry>>>,
 }
 
 impl SpanCollector {
@@ -191,6 +216,7 @@ impl SpanCollector {
             session_id: AtomicI64::new(session_id),
             attempt_idx: AtomicU64::new(0),
             spans: Mutex::new(Vec::new()),
+            tool_history: Mutex::new(None),
         }
     }
 
@@ -228,8 +254,9 @@ impl SpanCollector {
         )
     }
 
-    /// Record a completed span.
+    /// Record a completed span and push it to any live subscribers.
     pub fn record(&self, span: Span) {
+        super::live::span_feed().publish(&span);
         self.spans.lock().push(span);
     }
 
@@ -246,6 +273,16 @@ impl SpanCollector {
         }
     }
 
+    /// Hand over the tool history the current attempt's tool loop ended with.
+    pub fn record_tool_history(&self, history: Vec<ToolHistoryEntry>) {
+        *self.tool_history.lock() = Some(history);
+    }
+
+    /// Take the tool history recorded for the current attempt, if any.
+    pub fn take_tool_history(&self) -> Option<Vec<ToolHistoryEntry>> {
+        self.tool_history.lock().take()
+    }
+
     /// Drain all collected spans, returning them and clearing the internal buffer.
     pub fn drain(&self) -> Vec<Span> {
         let mut spans = self.spans.lock();
@@ -352,3 +389,28 @@ impl Drop for SpanGuard {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_record_is_single_line_json() {
+        let mut span = Span::new(3, "rollout-1".to_string(), 42, 0, SpanType::ToolCall, "web_fetch".to_string())
+            .with_attributes(serde_json::json!({"url": "https://example.com\nsecond line"}));
+        span.fail("HTTP 500".to_string());
+
+        let line = span.to_log_record().to_string();
+        assert!(!line.contains('\n'), "must be one line: {}", line);
+
+        let parsed: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed["span_type"], "tool_call");
+        assert_eq!(parsed["name"], "web_fetch");
+        assert_eq!(parsed["success"], false);
+        assert_eq!(parsed["error"], "HTTP 500");
+        assert_eq!(parsed["rollout_id"], "rollout-1");
+        assert_eq!(parsed["session_id"], 42);
+        assert_eq!(parsed["attributes"]["url"], "https://example.com\nsecond line");
+        assert!(parsed["duration_ms"].is_u64());
+    }
+}
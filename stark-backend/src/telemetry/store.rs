@@ -7,8 +7,12 @@ use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-use super::adapter::{Adapter, ExecutionSummary, SpansToSummary, SpansToTimeline, SpansToTriplets, Timeline, Triplet};
+use super::adapter::{
+    Adapter, ExecutionSummary, SessionTrace, SpansToSummary, SpansToTimeline, SpansToTrace, SpansToTriplets, Timeline,
+    Triplet,
+};
 use super::span::{Span, SpanCollector, SpanType};
+use crate::ai::ToolHistoryEntry;
 
 /// Retention policy for telemetry data.
 #[derive(Debug, Clone)]
@@ -49,10 +53,42 @@ pub struct RewardTypeStat {
     pub avg_value: f64,
 }
 
+/// A finished rollout and how it went, for agents reflecting on earlier runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RolloutOutcome {
+    pub rollout_id: String,
+    pub session_id: i64,
+    pub channel_id: i64,
+    pub status: String,
+    pub attempts: u32,
+    pub created_at: DateTime<Utc>,
+    pub duration_ms: Option<u64>,
+    /// Final response if it succeeded, error otherwise
+    pub summary: Option<String>,
+    /// Session reward, if one was recorded
+    pub reward: Option<f64>,
+}
+
+/// Filters for `TelemetryStore::recent_rollouts`.
+#[derive(Debug, Clone, Default)]
+pub struct RolloutQuery {
+    pub session_id: Option<i64>,
+    pub channel_id: Option<i64>,
+    /// Some(true) = succeeded only, Some(false) = failed or cancelled only
+    pub succeeded: Option<bool>,
+    pub limit: usize,
+}
+
+/// Log target for the per-span JSON lines, so they can be routed separately
+/// (e.g. `RUST_LOG=info,telemetry_json=info`).
+pub const JSON_LOG_TARGET: &str = "telemetry_json";
+
 /// The telemetry store provides high-level persistence and query operations.
 pub struct TelemetryStore {
     db: Arc<crate::db::Database>,
     retention: RetentionPolicy,
+    /// Also emit each persisted span as a single-line JSON log record
+    json_logs: bool,
 }
 
 impl TelemetryStore {
@@ -60,6 +96,7 @@ impl TelemetryStore {
         Self {
             db,
             retention: RetentionPolicy::default(),
+            json_logs: crate::config::telemetry_json_logs(),
         }
     }
 
@@ -85,9 +122,40 @@ impl TelemetryStore {
             if let Err(e) = self.db.insert_span(span) {
                 log::error!("[TELEMETRY] Failed to persist span {}: {}", span.span_id, e);
             }
+            if self.json_logs {
+                log::info!(target: JSON_LOG_TARGET, "{}", span.to_log_record());
+            }
         }
     }
 
+    /// Save the tool history an attempt ended with.
+    pub fn save_attempt_history(&self, rollout_id: &str, attempt_idx: u32, history: &[ToolHistoryEntry]) {
+        let json = match serde_json::to_string(history) {
+            Ok(json) => json,
+            Err(e) => {
+                log::error!("[TELEMETRY] Failed to serialize tool history: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = self.db.set_attempt_tool_history(rollout_id, attempt_idx, &json) {
+            log::error!("[TELEMETRY] Failed to save tool history for {}#{}: {}", rollout_id, attempt_idx, e);
+        }
+    }
+
+    /// Load the tool history saved for an attempt, if any.
+    pub fn load_attempt_history(&self, rollout_id: &str, attempt_idx: u32) -> Option<Vec<ToolHistoryEntry>> {
+        let json = match self.db.get_attempt_tool_history(rollout_id, attempt_idx) {
+            Ok(json) => json?,
+            Err(e) => {
+                log::error!("[TELEMETRY] Failed to load tool history: {}", e);
+                return None;
+            }
+        };
+        serde_json::from_str(&json)
+            .map_err(|e| log::error!("[TELEMETRY] Corrupt tool history for {}#{}: {}", rollout_id, attempt_idx, e))
+            .ok()
+    }
+
     /// Get all spans for a rollout.
     pub fn get_rollout_spans(&self, rollout_id: &str) -> Vec<Span> {
         match self.db.get_spans_by_rollout(rollout_id) {
@@ -127,12 +195,29 @@ impl TelemetryStore {
         }
     }
 
+    /// Get the most recent finished rollouts matching the query, newest first.
+    pub fn recent_rollouts(&self, query: &RolloutQuery) -> Vec<RolloutOutcome> {
+        match self.db.query_rollouts(query) {
+            Ok(rollouts) => rollouts,
+            Err(e) => {
+                log::error!("[TELEMETRY] Failed to query rollouts: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
     /// Get a timeline view for a session.
     pub fn get_session_timeline(&self, session_id: i64) -> Timeline {
         let spans = self.get_session_spans(session_id);
         SpansToTimeline.transform(&spans)
     }
 
+    /// Get a rollout → attempt → span tree of everything persisted for a session.
+    pub fn get_session_trace(&self, session_id: i64) -> SessionTrace {
+        let spans = self.get_session_spans(session_id);
+        SessionTrace { session_id, ..SpansToTrace.transform(&spans) }
+    }
+
     /// Get an execution summary for a rollout.
     pub fn get_execution_summary(&self, rollout_id: &str) -> ExecutionSummary {
         let spans = self.get_rollout_spans(rollout_id);
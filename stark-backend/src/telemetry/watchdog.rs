@@ -1,11 +1,17 @@
-//! Timeout guards on individual tool calls and LLM calls.
+//! Timeout guards on individual tool calls and LLM calls, plus a wall-clock
+//! limit on the whole session so many just-under-timeout calls can't run on
+//! indefinitely.
 //!
-//! Heartbeat monitoring detects unresponsive executions.
+//! Heartbeat monitoring detects unresponsive executions and reports progress
+//! (iteration, task position, elapsed time) from counters the tool loop updates.
 //! Integrates with rollout retry on timeout.
 
+use std::collections::HashMap;
 use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::time::Instant;
 
 use chrono::Utc;
 use parking_lot::Mutex;
@@ -15,51 +21,95 @@ use tokio::time::timeout;
 
 use super::reward::RewardEmitter;
 use super::span::{SpanCollector, SpanType};
+use crate::models::ToolTimeoutSettings;
 
 /// Configuration for the watchdog.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WatchdogConfig {
-    /// Default timeout for tool executions in seconds
-    pub tool_timeout_secs: u64,
+    /// Default timeout for tool executions without an override
+    pub tool_timeout: Duration,
     /// Default timeout for LLM calls in seconds
     pub llm_timeout_secs: u64,
     /// Heartbeat interval for long-running operations in seconds
     pub heartbeat_interval_secs: u64,
     /// Maximum time without a heartbeat before marking as unresponsive (seconds)
     pub heartbeat_max_silence_secs: u64,
-    /// Per-tool timeout overrides (tool_name → timeout_secs)
-    pub tool_overrides: std::collections::HashMap<String, u64>,
+    /// Per-tool timeout overrides (tool_name → timeout)
+    pub tool_overrides: HashMap<String, Duration>,
+    /// Wall-clock limit for a whole session (None = unlimited)
+    pub session_timeout: Option<Duration>,
+    /// Per-subtype session limits (subtype key → limit), taking precedence over `session_timeout`
+    pub subtype_session_timeouts: HashMap<String, Duration>,
 }
 
 impl Default for WatchdogConfig {
     fn default() -> Self {
-        let mut tool_overrides = std::collections::HashMap::new();
+        let mut tool_overrides = HashMap::new();
         // web_fetch and exec can be slow
-        tool_overrides.insert("web_fetch".to_string(), 120);
-        tool_overrides.insert("exec".to_string(), 300);
-        tool_overrides.insert("x402_preset_fetch".to_string(), 120);
-        tool_overrides.insert("deploy".to_string(), 600);
-        tool_overrides.insert("spawn_subagents".to_string(), 3600);
+        tool_overrides.insert("web_fetch".to_string(), Duration::from_secs(120));
+        tool_overrides.insert("exec".to_string(), Duration::from_secs(300));
+        tool_overrides.insert("x402_preset_fetch".to_string(), Duration::from_secs(120));
+        tool_overrides.insert("deploy".to_string(), Duration::from_secs(600));
+        tool_overrides.insert("spawn_subagents".to_string(), Duration::from_secs(3600));
+        tool_overrides.insert("wait_for_subagent".to_string(), Duration::from_secs(3600));
 
         Self {
-            tool_timeout_secs: 60,
+            tool_timeout: Duration::from_secs(60),
             llm_timeout_secs: 180,
             heartbeat_interval_secs: 30,
             heartbeat_max_silence_secs: 120,
             tool_overrides,
+            // No session limit unless configured in bot or channel settings
+            session_timeout: None,
+            subtype_session_timeouts: HashMap::new(),
         }
     }
 }
 
 impl WatchdogConfig {
+    /// Apply tool timeouts configured in bot settings on top of the built-in ones.
+    /// Zero values are ignored.
+    pub fn with_tool_timeouts(mut self, settings: &ToolTimeoutSettings) -> Self {
+        if let Some(secs) = settings.default_secs.filter(|s| *s > 0) {
+            self.tool_timeout = Duration::from_secs(secs);
+        }
+        for (tool_name, secs) in &settings.overrides {
+            if *secs > 0 {
+                self.tool_overrides.insert(tool_name.clone(), Duration::from_secs(*secs));
+            }
+        }
+        if let Some(secs) = settings.session_secs.filter(|s| *s > 0) {
+            self.session_timeout = Some(Duration::from_secs(secs));
+        }
+        for (subtype, secs) in &settings.subtype_session_secs {
+            if *secs > 0 {
+                self.subtype_session_timeouts.insert(subtype.clone(), Duration::from_secs(*secs));
+            }
+        }
+        self
+    }
+
+    /// Replace the default session limit (e.g. from a channel setting).
+    /// Subtype limits still take precedence.
+    pub fn with_session_timeout(mut self, session_timeout: Duration) -> Self {
+        self.session_timeout = Some(session_timeout);
+        self
+    }
+
+    /// Get the session wall-clock limit for a subtype, if any.
+    pub fn session_timeout_for(&self, subtype_key: &str) -> Option<Duration> {
+        self.subtype_session_timeouts
+            .get(subtype_key)
+            .copied()
+            .or(self.session_timeout)
+    }
+
     /// Get the timeout for a specific tool, with override support.
     pub fn timeout_for_tool(&self, tool_name: &str) -> Duration {
-        let secs = self
-            .tool_overrides
+        self.tool_overrides
             .get(tool_name)
             .copied()
-            .unwrap_or(self.tool_timeout_secs);
-        Duration::from_secs(secs)
+            .unwrap_or(self.tool_timeout)
     }
 
     /// Get the timeout for LLM calls.
@@ -91,6 +141,44 @@ impl<E: std::fmt::Display> std::fmt::Display for WatchdogError<E> {
     }
 }
 
+/// Progress of a running dispatch, updated by the tool loop each iteration
+/// and read by the heartbeat monitor.
+#[derive(Debug, Default)]
+pub struct ProgressCounters {
+    iteration: AtomicUsize,
+    max_iterations: AtomicUsize,
+    /// Tasks completed so far (the 0-based index of the task in progress)
+    task_index: AtomicUsize,
+    task_count: AtomicUsize,
+}
+
+impl ProgressCounters {
+    /// Record the tool-loop iteration about to run.
+    pub fn set_iteration(&self, iteration: usize, max_iterations: usize) {
+        self.iteration.store(iteration, Ordering::Relaxed);
+        self.max_iterations.store(max_iterations, Ordering::Relaxed);
+    }
+
+    /// Record the position in the planner's task queue.
+    pub fn set_tasks(&self, task_index: usize, task_count: usize) {
+        self.task_index.store(task_index, Ordering::Relaxed);
+        self.task_count.store(task_count, Ordering::Relaxed);
+    }
+}
+
+/// Snapshot of dispatch progress, as sent in heartbeat events.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HeartbeatProgress {
+    pub iteration: usize,
+    pub max_iterations: usize,
+    pub task_index: usize,
+    pub task_count: usize,
+    pub elapsed_secs: u64,
+    /// Estimated completion (0-99 while running): by tasks once a plan
+    /// exists, otherwise by iterations used out of the maximum
+    pub percent: u8,
+}
+
 /// Watchdog enforces timeouts on tool and LLM calls.
 pub struct Watchdog {
     config: WatchdogConfig,
@@ -98,6 +186,10 @@ pub struct Watchdog {
     reward_emitter: Arc<RewardEmitter>,
     /// Tracks the last heartbeat time for the current execution
     last_heartbeat: Arc<Mutex<chrono::DateTime<Utc>>>,
+    /// When the session being guarded started
+    started_at: Instant,
+    /// Progress counters shared with the tool loop
+    progress: Arc<ProgressCounters>,
 }
 
 impl Watchdog {
@@ -111,6 +203,8 @@ impl Watchdog {
             collector,
             reward_emitter,
             last_heartbeat: Arc::new(Mutex::new(Utc::now())),
+            started_at: Instant::now(),
+            progress: Arc::new(ProgressCounters::default()),
         }
     }
 
@@ -124,6 +218,36 @@ impl Watchdog {
         &self.reward_emitter
     }
 
+    /// Get the span collector of the rollout being guarded.
+    pub fn collector(&self) -> &SpanCollector {
+        &self.collector
+    }
+
+    /// Get the progress counters the tool loop updates.
+    pub fn progress(&self) -> &ProgressCounters {
+        &self.progress
+    }
+
+    /// Snapshot the current progress for a heartbeat event.
+    pub fn progress_snapshot(&self) -> HeartbeatProgress {
+        let iteration = self.progress.iteration.load(Ordering::Relaxed);
+        let max_iterations = self.progress.max_iterations.load(Ordering::Relaxed);
+        let task_index = self.progress.task_index.load(Ordering::Relaxed);
+        let task_count = self.progress.task_count.load(Ordering::Relaxed);
+        let percent = (task_index * 100)
+            .checked_div(task_count)
+            .or_else(|| (iteration * 100).checked_div(max_iterations))
+            .unwrap_or(0);
+        HeartbeatProgress {
+            iteration,
+            max_iterations,
+            task_index,
+            task_count,
+            elapsed_secs: self.started_at.elapsed().as_secs(),
+            percent: percent.min(99) as u8,
+        }
+    }
+
     /// Record a heartbeat indicating the execution is still alive.
     pub fn heartbeat(&self) {
         *self.last_heartbeat.lock() = Utc::now();
@@ -136,6 +260,51 @@ impl Watchdog {
         silence > self.config.heartbeat_max_silence_secs as i64
     }
 
+    /// Check the session wall-clock limit for the current subtype. Returns the
+    /// limit once the session has run past it, recording a
+    /// `watchdog_session_timeout` annotation.
+    pub fn session_time_limit_exceeded(&self, subtype_key: &str) -> Option<Duration> {
+        let limit = self.config.session_timeout_for(subtype_key)?;
+        let elapsed = self.started_at.elapsed();
+        if elapsed < limit {
+            return None;
+        }
+        let mut span = self.collector.start_span(SpanType::Annotation, "watchdog_session_timeout");
+        span.attributes = json!({
+            "annotation_key": "watchdog_session_timeout",
+            "annotation_value": {
+                "subtype": subtype_key,
+                "limit_secs": limit.as_secs(),
+                "elapsed_ms": elapsed.as_millis() as u64,
+            },
+        });
+        span.succeed();
+        self.collector.record(span);
+        log::warn!(
+            "[WATCHDOG] Session ran {}s, past its {}s limit (subtype: '{}')",
+            elapsed.as_secs(),
+            limit.as_secs(),
+            subtype_key
+        );
+        Some(limit)
+    }
+
+    /// Record a `watchdog_tool_timeout` annotation with the configured timeout,
+    /// so the tools that hit their limits most often show up in telemetry.
+    fn record_tool_timeout(&self, tool_name: &str, tool_timeout: Duration) {
+        let mut span = self.collector.start_span(SpanType::Annotation, "watchdog_tool_timeout");
+        span.attributes = json!({
+            "annotation_key": "watchdog_tool_timeout",
+            "annotation_value": {
+                "tool_name": tool_name,
+                "timeout_secs": tool_timeout.as_secs(),
+                "has_override": self.config.tool_overrides.contains_key(tool_name),
+            },
+        });
+        span.succeed();
+        self.collector.record(span);
+    }
+
     /// Guard a tool execution with a timeout.
     ///
     /// Works with infallible futures (e.g., `tool_registry.execute()` which returns
@@ -171,6 +340,7 @@ impl Watchdog {
                 span.timeout();
                 self.collector.record(span);
                 self.reward_emitter.watchdog_timeout(tool_name, timeout_ms);
+                self.record_tool_timeout(tool_name, tool_timeout);
                 log::warn!(
                     "[WATCHDOG] Tool '{}' timed out after {}ms",
                     tool_name,
@@ -218,6 +388,7 @@ impl Watchdog {
                 span.timeout();
                 self.collector.record(span);
                 self.reward_emitter.watchdog_timeout(tool_name, timeout_ms);
+                self.record_tool_timeout(tool_name, tool_timeout);
                 log::warn!(
                     "[WATCHDOG] Tool '{}' timed out after {}ms",
                     tool_name,
@@ -283,8 +454,9 @@ impl Watchdog {
 
     /// Start a background heartbeat monitor task.
     ///
-    /// The monitor only observes — it does NOT reset the heartbeat. Only actual
-    /// execution (guard_tool_call, guard_tool, guard_llm) registers heartbeats.
+    /// Each tick emits a `heartbeat` event with the current progress. The monitor
+    /// only observes — it does NOT reset the heartbeat. Only actual execution
+    /// (guard_tool_call, guard_tool, guard_llm) registers heartbeats.
     /// Returns a JoinHandle that should be aborted when the dispatch completes.
     pub fn start_heartbeat_monitor(
         self: &Arc<Self>,
@@ -301,6 +473,11 @@ impl Watchdog {
             loop {
                 ticker.tick().await;
 
+                broadcaster.broadcast(crate::gateway::protocol::GatewayEvent::heartbeat(
+                    channel_id,
+                    &watchdog.progress_snapshot(),
+                ));
+
                 if watchdog.is_unresponsive() {
                     log::warn!(
                         "[WATCHDOG] Channel {} execution appears unresponsive (no heartbeat for >{}s)",
@@ -318,3 +495,81 @@ impl Watchdog {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watchdog(config: WatchdogConfig) -> (Watchdog, Arc<SpanCollector>) {
+        let collector = Arc::new(SpanCollector::new("rollout-test".to_string(), 1));
+        let reward_emitter = Arc::new(RewardEmitter::new(Arc::clone(&collector)));
+        (Watchdog::new(config, Arc::clone(&collector), reward_emitter), collector)
+    }
+
+    #[test]
+    fn test_tool_timeouts_from_settings_override_builtin_ones() {
+        let settings: ToolTimeoutSettings =
+            serde_json::from_str(r#"{"default_secs": 90, "overrides": {"git_clone": 900, "exec": 0}}"#).unwrap();
+        let config = WatchdogConfig::default().with_tool_timeouts(&settings);
+
+        assert_eq!(config.timeout_for_tool("git_clone"), Duration::from_secs(900));
+        assert_eq!(config.timeout_for_tool("say_to_user"), Duration::from_secs(90));
+        // Built-in overrides survive, and zero values don't disable them
+        assert_eq!(config.timeout_for_tool("exec"), Duration::from_secs(300));
+        assert_eq!(config.timeout_for_tool("spawn_subagents"), Duration::from_secs(3600));
+    }
+
+    #[tokio::test]
+    async fn test_tool_timeout_records_configured_timeout() {
+        let mut config = WatchdogConfig::default();
+        config.tool_overrides.insert("slow_tool".to_string(), Duration::from_millis(10));
+        let (watchdog, collector) = watchdog(config);
+
+        let result = watchdog
+            .guard_tool_call("slow_tool", tokio::time::sleep(Duration::from_secs(5)))
+            .await;
+        assert!(result.is_none());
+
+        let annotation = collector
+            .snapshot()
+            .into_iter()
+            .find(|s| s.attributes["annotation_key"] == "watchdog_tool_timeout")
+            .expect("timeout annotation recorded");
+        assert_eq!(annotation.attributes["annotation_value"]["tool_name"], "slow_tool");
+        assert_eq!(annotation.attributes["annotation_value"]["has_override"], true);
+    }
+
+    #[test]
+    fn test_session_limit_prefers_subtype_over_default() {
+        assert_eq!(WatchdogConfig::default().session_timeout_for("finance"), None);
+
+        let settings: ToolTimeoutSettings =
+            serde_json::from_str(r#"{"session_secs": 600, "subtype_session_secs": {"code_engineer": 1800}}"#).unwrap();
+        let config = WatchdogConfig::default().with_tool_timeouts(&settings);
+        assert_eq!(config.session_timeout_for("finance"), Some(Duration::from_secs(600)));
+        assert_eq!(config.session_timeout_for("code_engineer"), Some(Duration::from_secs(1800)));
+
+        // A channel limit replaces the default but not subtype limits
+        let config = config.with_session_timeout(Duration::from_secs(120));
+        assert_eq!(config.session_timeout_for("finance"), Some(Duration::from_secs(120)));
+        assert_eq!(config.session_timeout_for("code_engineer"), Some(Duration::from_secs(1800)));
+    }
+
+    #[test]
+    fn test_progress_percent_prefers_tasks_over_iterations() {
+        let (watchdog, _) = watchdog(WatchdogConfig::default());
+        assert_eq!(watchdog.progress_snapshot().percent, 0);
+
+        watchdog.progress().set_iteration(10, 40);
+        assert_eq!(watchdog.progress_snapshot().percent, 25);
+
+        watchdog.progress().set_tasks(3, 4);
+        let progress = watchdog.progress_snapshot();
+        assert_eq!(progress.percent, 75);
+        assert_eq!((progress.iteration, progress.max_iterations), (10, 40));
+
+        // Never reports done while still running
+        watchdog.progress().set_tasks(4, 4);
+        assert_eq!(watchdog.progress_snapshot().percent, 99);
+    }
+}
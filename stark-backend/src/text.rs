@@ -0,0 +1,151 @@
+//! Text clipping shared by summary, logging, memory and context builders,
+//! plus the formatting normalization applied to stored assistant responses.
+//!
+//! Content is always clipped by characters, never by byte index, so multi-byte
+//! text (emoji, CJK, accented Latin) can't split a char boundary and panic.
+
+/// Marker appended to clipped text
+pub const ELLIPSIS: &str = "...";
+
+/// Clip `s` to at most `max_chars` characters. When anything is cut, the
+/// result ends with [`ELLIPSIS`], which counts toward the limit.
+pub fn truncate_chars(s: &str, max_chars: usize) -> String {
+    if s.char_indices().nth(max_chars).is_none() {
+        return s.to_string();
+    }
+    let marker_chars = ELLIPSIS.chars().count();
+    if max_chars < marker_chars {
+        return s.chars().take(max_chars).collect();
+    }
+    let end = s
+        .char_indices()
+        .nth(max_chars - marker_chars)
+        .map(|(i, _)| i)
+        .unwrap_or(s.len());
+    format!("{}{}", &s[..end], ELLIPSIS)
+}
+
+/// Normalize the formatting of an assistant response before it is stored and
+/// replayed into later turns.
+///
+/// Outside fenced code blocks this converts CRLF to LF, trims trailing
+/// whitespace, collapses runs of blank lines to one, rewrites `*`, `+` and `•`
+/// bullets as `-`, and trims blank lines at both ends. Fenced code (``` or ~~~)
+/// is kept verbatim. Applying it twice gives the same result as applying it once.
+pub fn normalize_assistant_text(s: &str) -> String {
+    let s = s.replace("\r\n", "\n");
+    let mut out: Vec<String> = Vec::new();
+    // Open fence marker (e.g. "```"), if inside a code block
+    let mut fence: Option<String> = None;
+    let mut blank_run = false;
+
+    for line in s.split('\n') {
+        let trimmed_start = line.trim_start();
+        if let Some(ref marker) = fence {
+            out.push(line.to_string());
+            // A fence closes on a bare run of the same character, at least as long
+            if fence_marker(trimmed_start).is_some_and(|m| m.starts_with(marker.as_str()) && m.len() == trimmed_start.trim_end().len()) {
+                fence = None;
+            }
+            continue;
+        }
+        if let Some(marker) = fence_marker(trimmed_start) {
+            fence = Some(marker);
+            blank_run = false;
+            out.push(line.trim_end().to_string());
+            continue;
+        }
+
+        let line = line.trim_end();
+        if line.is_empty() {
+            if !blank_run && !out.is_empty() {
+                out.push(String::new());
+            }
+            blank_run = true;
+            continue;
+        }
+        blank_run = false;
+        out.push(normalize_bullet(line));
+    }
+
+    while out.last().is_some_and(|l| l.is_empty()) {
+        out.pop();
+    }
+    out.join("\n")
+}
+
+/// The opening fence of a code block (three or more backticks or tildes)
+fn fence_marker(line: &str) -> Option<String> {
+    let c = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = line.chars().take_while(|x| *x == c).count();
+    (len >= 3).then(|| c.to_string().repeat(len))
+}
+
+/// Rewrite a `*`, `+` or `•` list bullet as `-`, keeping its indentation
+fn normalize_bullet(line: &str) -> String {
+    let indent = line.len() - line.trim_start().len();
+    let rest = &line[indent..];
+    // "* * *" is a horizontal rule, not a list
+    if rest.chars().all(|c| c == '*' || c == ' ') {
+        return line.to_string();
+    }
+    for bullet in ["* ", "+ ", "• "] {
+        if let Some(item) = rest.strip_prefix(bullet) {
+            return format!("{}- {}", &line[..indent], item);
+        }
+    }
+    line.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_truncate_chars_basic() {
+        assert_eq!(truncate_chars("hello", 10), "hello");
+        assert_eq!(truncate_chars("hello", 5), "hello");
+        assert_eq!(truncate_chars("hello world", 8), "hello...");
+        assert_eq!(truncate_chars("héllo wörld", 8), "héllo...");
+        assert_eq!(truncate_chars("🚀🚀🚀🚀🚀", 4), "🚀...");
+        assert_eq!(truncate_chars("abcdef", 2), "ab");
+        assert_eq!(truncate_chars("abcdef", 0), "");
+    }
+
+    #[test]
+    fn test_truncate_chars_random_multibyte() {
+        // Mix of 1-, 2-, 3- and 4-byte chars
+        const ALPHABET: &[char] = &['a', 'Z', ' ', '\n', 'é', 'ß', 'Ж', '中', '日', '€', '🚀', '👍', '\u{301}'];
+        let mut rng = rand::thread_rng();
+        for _ in 0..2000 {
+            let len = rng.gen_range(0..200);
+            let s: String = (0..len).map(|_| ALPHABET[rng.gen_range(0..ALPHABET.len())]).collect();
+            let max = rng.gen_range(0..220);
+
+            let out = truncate_chars(&s, max);
+            let out_chars = out.chars().count();
+            assert!(out_chars <= max, "{} chars exceeds limit {}", out_chars, max);
+            if s.chars().count() <= max {
+                assert_eq!(out, s);
+            } else if max >= ELLIPSIS.len() {
+                assert!(out.ends_with(ELLIPSIS));
+                assert!(s.starts_with(out.trim_end_matches(ELLIPSIS)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_normalize_assistant_text_keeps_code_fences() {
+        let raw = "\n\nHere you go:   \r\n\n\n\n* first\n+ second\n  • nested\n\n\n\n```rust\nfn main() {   \n\n\n\n    * not a bullet\n}\n```\n\n\n\nDone.  \n\n";
+        let normalized = normalize_assistant_text(raw);
+        assert_eq!(
+            normalized,
+            "Here you go:\n\n- first\n- second\n  - nested\n\n```rust\nfn main() {   \n\n\n\n    * not a bullet\n}\n```\n\nDone."
+        );
+        assert_eq!(normalize_assistant_text(&normalized), normalized, "normalization is idempotent");
+
+        // Unclosed fences stay verbatim apart from trailing blank lines; horizontal rules aren't bullets
+        assert_eq!(normalize_assistant_text("a\n* * *\n~~~\nx  \n\n\n"), "a\n* * *\n~~~\nx  ");
+    }
+}
@@ -1,6 +1,7 @@
 //! Registry for managing tool validators
 
 use std::sync::Arc;
+use futures_util::stream::{FuturesUnordered, StreamExt};
 use super::traits::ToolValidator;
 use super::types::{ValidationContext, ValidationResult};
 
@@ -40,34 +41,46 @@ impl ValidatorRegistry {
 
     /// Run all applicable validators against a tool call
     ///
-    /// Validators are run in priority order. The first validator to return
-    /// a Block result will stop execution and return that result.
+    /// Validators run concurrently, but the outcome is the same as running
+    /// them in priority order: the highest-priority Block wins, regardless of
+    /// which validator finishes first. As soon as that Block is known (every
+    /// higher-priority validator has allowed), the remaining checks are dropped.
     pub async fn validate(&self, ctx: &ValidationContext) -> ValidationResult {
-        for validator in &self.validators {
-            // Skip disabled validators
-            if !validator.enabled() {
-                continue;
-            }
-
-            // Skip if validator doesn't apply to this tool
-            if let Some(tools) = validator.applies_to() {
-                if !tools.contains(&ctx.tool_name.as_str()) {
-                    continue;
+        let applicable: Vec<&Arc<dyn ToolValidator>> = self.validators.iter()
+            .filter(|v| v.enabled())
+            .filter(|v| match v.applies_to() {
+                Some(tools) => tools.contains(&ctx.tool_name.as_str()),
+                None => true,
+            })
+            .collect();
+
+        let mut pending: FuturesUnordered<_> = applicable.iter()
+            .enumerate()
+            .map(|(index, validator)| async move { (index, validator.validate(ctx).await) })
+            .collect();
+
+        // Results indexed by priority order; None = still running
+        let mut results: Vec<Option<ValidationResult>> = vec![None; applicable.len()];
+        while let Some((index, result)) = pending.next().await {
+            results[index] = Some(result);
+
+            // Walk in priority order until a validator that is still running
+            for (index, result) in results.iter().enumerate() {
+                match result {
+                    None => break,
+                    Some(result) if result.is_blocked() => {
+                        log::info!(
+                            "[VALIDATOR] '{}' blocked tool '{}': {}",
+                            applicable[index].id(),
+                            ctx.tool_name,
+                            result.block_reason().unwrap_or("unknown reason")
+                        );
+                        // Dropping `pending` cancels the validators still running
+                        return result.clone();
+                    }
+                    Some(_) => {}
                 }
             }
-
-            // Run the validation
-            let result = validator.validate(ctx).await;
-
-            if result.is_blocked() {
-                log::info!(
-                    "[VALIDATOR] '{}' blocked tool '{}': {}",
-                    validator.id(),
-                    ctx.tool_name,
-                    result.block_reason().unwrap_or("unknown reason")
-                );
-                return result;
-            }
         }
 
         ValidationResult::Allow
@@ -103,6 +116,7 @@ impl Default for ValidatorRegistry {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tool_validators::ValidatorPriority;
     use crate::tools::types::ToolContext;
     use async_trait::async_trait;
     use serde_json::json;
@@ -162,6 +176,61 @@ mod tests {
         assert_eq!(result.block_reason(), Some("Test block"));
     }
 
+    /// Blocks `test_tool` after a delay
+    struct SlowBlockValidator {
+        id: &'static str,
+        priority: ValidatorPriority,
+        delay_ms: u64,
+    }
+
+    #[async_trait]
+    impl ToolValidator for SlowBlockValidator {
+        fn id(&self) -> &str { self.id }
+        fn name(&self) -> &str { self.id }
+        fn applies_to(&self) -> Option<Vec<&str>> { Some(vec!["test_tool"]) }
+        fn priority(&self) -> ValidatorPriority { self.priority }
+
+        async fn validate(&self, _ctx: &ValidationContext) -> ValidationResult {
+            tokio::time::sleep(std::time::Duration::from_millis(self.delay_ms)).await;
+            ValidationResult::Block(format!("{} block", self.id))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_higher_priority_block_wins_even_when_it_finishes_second() {
+        let mut registry = ValidatorRegistry::new();
+        registry.register(Arc::new(SlowBlockValidator { id: "balance", priority: ValidatorPriority::Normal, delay_ms: 0 }));
+        registry.register(Arc::new(SlowBlockValidator { id: "safety", priority: ValidatorPriority::Critical, delay_ms: 50 }));
+
+        let ctx = ValidationContext::new(
+            "test_tool".into(),
+            json!({}),
+            Arc::new(ToolContext::new()),
+        );
+
+        let result = registry.validate(&ctx).await;
+        assert_eq!(result.block_reason(), Some("safety block"));
+    }
+
+    #[tokio::test]
+    async fn test_validators_run_concurrently() {
+        let mut registry = ValidatorRegistry::new();
+        registry.register(Arc::new(AlwaysAllowValidator));
+        registry.register(Arc::new(SlowBlockValidator { id: "first", priority: ValidatorPriority::High, delay_ms: 100 }));
+        registry.register(Arc::new(SlowBlockValidator { id: "second", priority: ValidatorPriority::Low, delay_ms: 100 }));
+
+        let ctx = ValidationContext::new(
+            "test_tool".into(),
+            json!({}),
+            Arc::new(ToolContext::new()),
+        );
+
+        let started = std::time::Instant::now();
+        let result = registry.validate(&ctx).await;
+        assert_eq!(result.block_reason(), Some("first block"));
+        assert!(started.elapsed() < std::time::Duration::from_millis(190), "validators ran sequentially");
+    }
+
     #[tokio::test]
     async fn test_validator_only_applies_to_specified_tools() {
         let mut registry = ValidatorRegistry::new();
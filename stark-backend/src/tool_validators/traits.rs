@@ -48,7 +48,8 @@ pub trait ToolValidator: Send + Sync {
     /// Return `Some(vec!["tool1", "tool2"])` to only apply to specific tools.
     fn applies_to(&self) -> Option<Vec<&str>>;
 
-    /// Priority for execution order (lower = earlier)
+    /// Priority (lower = higher priority). Validators run concurrently; when
+    /// several block, the rejection from the highest-priority one is reported.
     fn priority(&self) -> ValidatorPriority {
         ValidatorPriority::Normal
     }
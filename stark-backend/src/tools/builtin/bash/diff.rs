@@ -0,0 +1,190 @@
+//! Unified diff rendering for file-edit tool output
+//!
+//! Edit tools can report a change as a unified diff instead of echoing file
+//! content, so iterative edits stay small in session history. Full content is
+//! always available through `read_file`.
+
+/// Default cap on rendered diff lines before falling back to a summary
+pub const DEFAULT_MAX_DIFF_LINES: usize = 200;
+
+/// Lines of unchanged context shown around each hunk
+const CONTEXT_LINES: usize = 3;
+
+/// Upper bound on the LCS table size (old × new lines of the changed region).
+/// Larger regions are rendered as a full replacement instead.
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Render a unified diff between `old` and `new` for `path`.
+///
+/// If the rendered diff exceeds `max_diff_lines`, a one-line
+/// "N lines changed" summary is returned instead.
+pub fn unified_diff(path: &str, old: &str, new: &str, max_diff_lines: usize) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let ops = diff_ops(&old_lines, &new_lines);
+
+    let added = ops.iter().filter(|(op, _, _)| *op == Op::Insert).count();
+    let removed = ops.iter().filter(|(op, _, _)| *op == Op::Delete).count();
+    if added == 0 && removed == 0 {
+        return format!("No changes to '{}'", path);
+    }
+
+    let mut body: Vec<String> = Vec::new();
+    for (start, end) in hunk_ranges(&ops) {
+        let (old_start, new_start) = (ops[start].1, ops[start].2);
+        let old_count = ops[start..end].iter().filter(|(op, _, _)| *op != Op::Insert).count();
+        let new_count = ops[start..end].iter().filter(|(op, _, _)| *op != Op::Delete).count();
+        body.push(format!(
+            "@@ -{},{} +{},{} @@",
+            hunk_start(old_start, old_count),
+            old_count,
+            hunk_start(new_start, new_count),
+            new_count
+        ));
+        for (op, oi, ni) in &ops[start..end] {
+            match op {
+                Op::Equal => body.push(format!(" {}", old_lines[*oi])),
+                Op::Delete => body.push(format!("-{}", old_lines[*oi])),
+                Op::Insert => body.push(format!("+{}", new_lines[*ni])),
+            }
+        }
+    }
+
+    if body.len() > max_diff_lines {
+        return format!(
+            "{} lines changed in '{}' (+{} -{}); diff exceeds {} lines. Use read_file to view the result.",
+            added + removed,
+            path,
+            added,
+            removed,
+            max_diff_lines
+        );
+    }
+
+    let mut out = format!("--- a/{}\n+++ b/{}\n", path, path);
+    for line in body {
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Unified diff hunk headers use 1-based starts, and 0 for empty ranges
+fn hunk_start(index: usize, count: usize) -> usize {
+    if count == 0 { index } else { index + 1 }
+}
+
+/// Compute the edit script as (op, old_index, new_index) triples.
+/// Indices point at the next line on each side, so inserts and deletes carry
+/// the position of the opposite side too.
+fn diff_ops(old: &[&str], new: &[&str]) -> Vec<(Op, usize, usize)> {
+    // Trim the common prefix/suffix so the LCS only covers the changed region
+    let prefix = old.iter().zip(new.iter()).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut ops: Vec<(Op, usize, usize)> = (0..prefix).map(|i| (Op::Equal, i, i)).collect();
+
+    if old_mid.len().saturating_mul(new_mid.len()) > MAX_LCS_CELLS {
+        for i in 0..old_mid.len() {
+            ops.push((Op::Delete, prefix + i, prefix));
+        }
+        for j in 0..new_mid.len() {
+            ops.push((Op::Insert, prefix + old_mid.len(), prefix + j));
+        }
+    } else {
+        let (n, m) = (old_mid.len(), new_mid.len());
+        let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i][j] = if old_mid[i] == new_mid[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && old_mid[i] == new_mid[j] {
+                ops.push((Op::Equal, prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+                ops.push((Op::Delete, prefix + i, prefix + j));
+                i += 1;
+            } else {
+                ops.push((Op::Insert, prefix + i, prefix + j));
+                j += 1;
+            }
+        }
+    }
+
+    let old_tail = old.len() - suffix;
+    let new_tail = new.len() - suffix;
+    for k in 0..suffix {
+        ops.push((Op::Equal, old_tail + k, new_tail + k));
+    }
+    ops
+}
+
+/// Group changed ops into hunks with surrounding context, merging hunks
+/// whose context windows overlap. Returns half-open index ranges into `ops`.
+fn hunk_ranges(ops: &[(Op, usize, usize)]) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (idx, (op, _, _)) in ops.iter().enumerate() {
+        if *op == Op::Equal {
+            continue;
+        }
+        let start = idx.saturating_sub(CONTEXT_LINES);
+        let end = (idx + 1 + CONTEXT_LINES).min(ops.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => ranges.push((start, end)),
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff_single_change() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let new = "a\nb\nc\nd\nE\nf\ng\nh\n";
+        let diff = unified_diff("x.txt", old, new, DEFAULT_MAX_DIFF_LINES);
+        assert!(diff.starts_with("--- a/x.txt\n+++ b/x.txt\n"));
+        assert!(diff.contains("@@ -2,7 +2,7 @@"));
+        assert!(diff.contains("-e\n+E\n"));
+        assert!(!diff.contains(" a\n"), "lines outside context should be omitted");
+    }
+
+    #[test]
+    fn test_unified_diff_falls_back_to_summary() {
+        let old: String = (0..50).map(|i| format!("line {}\n", i)).collect();
+        let new: String = (0..50).map(|i| format!("changed {}\n", i)).collect();
+        let diff = unified_diff("big.txt", &old, &new, 20);
+        assert!(diff.starts_with("100 lines changed in 'big.txt'"));
+    }
+
+    #[test]
+    fn test_unified_diff_no_changes() {
+        assert_eq!(unified_diff("x", "same\n", "same\n", 10), "No changes to 'x'");
+    }
+}
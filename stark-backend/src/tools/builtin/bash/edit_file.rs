@@ -1,3 +1,5 @@
+use super::diff::{unified_diff, DEFAULT_MAX_DIFF_LINES};
+use crate::text::truncate_chars;
 use crate::tools::registry::Tool;
 use crate::tools::types::{
     PropertySchema, ToolContext, ToolDefinition, ToolGroup, ToolInputSchema, ToolResult,
@@ -67,6 +69,31 @@ impl EditFileTool {
             },
         );
 
+        properties.insert(
+            "output".to_string(),
+            PropertySchema {
+                schema_type: "string".to_string(),
+                description: "How to report the edit: 'context' shows the edited section with surrounding lines, 'diff' returns only a unified diff of the file (default: context). Use 'diff' for iterative edits; read_file returns the full content.".to_string(),
+                default: Some(json!("context")),
+                items: None,
+                enum_values: Some(vec!["context".to_string(), "diff".to_string()]),
+            },
+        );
+
+        properties.insert(
+            "max_diff_lines".to_string(),
+            PropertySchema {
+                schema_type: "integer".to_string(),
+                description: format!(
+                    "In diff mode, maximum diff lines to return before falling back to a 'N lines changed' summary (default: {})",
+                    DEFAULT_MAX_DIFF_LINES
+                ),
+                default: Some(json!(DEFAULT_MAX_DIFF_LINES)),
+                items: None,
+                enum_values: None,
+            },
+        );
+
         EditFileTool {
             definition: ToolDefinition {
                 name: "edit_file".to_string(),
@@ -156,11 +183,7 @@ impl EditFileTool {
         if percentage >= 40 {
             let matched = content_lines[best_start..best_start + best_len].join("\n");
             // Truncate if too long
-            let display = if matched.len() > 500 {
-                format!("{}...", &matched[..500])
-            } else {
-                matched
-            };
+            let display = truncate_chars(&matched, 500);
             Some((display, best_start + 1, percentage))
         } else {
             None
@@ -222,6 +245,8 @@ struct EditFileParams {
     old_text: String,
     new_text: String,
     occurrence: Option<String>,
+    output: Option<String>,
+    max_diff_lines: Option<usize>,
 }
 
 #[async_trait]
@@ -381,6 +406,24 @@ impl Tool for EditFileTool {
             context.record_disk_write(size_increase);
         }
 
+        let metadata = json!({
+            "path": params.path,
+            "occurrences_found": count,
+            "occurrences_replaced": replaced_count,
+            "mode": occurrence
+        });
+
+        // Diff mode: report only the unified diff so session history stays small
+        if params.output.as_deref() == Some("diff") {
+            let max_diff_lines = params.max_diff_lines.unwrap_or(DEFAULT_MAX_DIFF_LINES);
+            let diff = unified_diff(&params.path, &content, &new_content, max_diff_lines);
+            return ToolResult::success(format!(
+                "Replaced {} of {} occurrence(s).\n\n{}",
+                replaced_count, count, diff
+            ))
+            .with_metadata(metadata);
+        }
+
         // Generate output
         let diff = Self::generate_diff(&params.old_text, &params.new_text, 3);
         let context_view = Self::show_context(&new_content, edit_position, &params.new_text, 3);
@@ -397,12 +440,7 @@ impl Tool for EditFileTool {
             )
         };
 
-        ToolResult::success(message).with_metadata(json!({
-            "path": params.path,
-            "occurrences_found": count,
-            "occurrences_replaced": replaced_count,
-            "mode": occurrence
-        }))
+        ToolResult::success(message).with_metadata(metadata)
     }
 }
 
@@ -492,6 +530,56 @@ mod tests {
         assert_eq!(content, "qux bar qux baz qux");
     }
 
+    #[tokio::test]
+    async fn test_edit_file_diff_mode_returns_diff() {
+        let tool = EditFileTool::new();
+        let temp_dir = TempDir::new().unwrap();
+
+        let original: String = (1..=40).map(|i| format!("line {}\n", i)).collect();
+        let test_file = temp_dir.path().join("test.txt");
+        std::fs::write(&test_file, &original).unwrap();
+
+        let context =
+            ToolContext::new().with_workspace(temp_dir.path().to_string_lossy().to_string());
+
+        let result = tool
+            .execute(
+                json!({
+                    "path": "test.txt",
+                    "old_text": "line 20\n",
+                    "new_text": "line twenty\n",
+                    "output": "diff"
+                }),
+                &context,
+            )
+            .await;
+
+        assert!(result.success);
+        assert!(result.content.contains("--- a/test.txt"));
+        assert!(result.content.contains("-line 20\n+line twenty"));
+        // Only the hunk context is included, not the full file
+        assert!(!result.content.contains("line 1\n"));
+        assert!(!result.content.contains("line 40"));
+        assert!(!result.content.contains("Context after edit"));
+
+        let result = tool
+            .execute(
+                json!({
+                    "path": "test.txt",
+                    "old_text": "line",
+                    "new_text": "row",
+                    "occurrence": "all",
+                    "output": "diff",
+                    "max_diff_lines": 10
+                }),
+                &context,
+            )
+            .await;
+
+        assert!(result.success);
+        assert!(result.content.contains("80 lines changed"));
+    }
+
     #[tokio::test]
     async fn test_edit_file_outside_workspace() {
         let tool = EditFileTool::new();
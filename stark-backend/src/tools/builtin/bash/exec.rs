@@ -679,10 +679,10 @@ impl Tool for ExecTool {
 
         // Truncate if too long (keep small to avoid context bloat for smaller models)
         const MAX_OUTPUT: usize = 15000;
-        if result_text.len() > MAX_OUTPUT {
+        if result_text.chars().count() > MAX_OUTPUT {
             result_text = format!(
                 "{}\n\n[Output truncated at {} characters]",
-                &result_text[..MAX_OUTPUT],
+                crate::text::truncate_chars(&result_text, MAX_OUTPUT),
                 MAX_OUTPUT
             );
         }
@@ -868,4 +868,24 @@ mod tests {
         assert!(result.success);
         assert!(result.content.contains("HELLO WORLD"));
     }
+
+    #[tokio::test]
+    async fn test_exec_truncates_multibyte_output() {
+        let tool = ExecTool::new();
+        let context = ToolContext::new();
+
+        // 16001 chars; the leading ASCII byte puts byte 15000 inside a 3-byte char
+        let result = tool
+            .execute(
+                json!({
+                    "command": "printf a; yes 日 | head -n 16000 | tr -d '\\n'"
+                }),
+                &context,
+            )
+            .await;
+
+        assert!(result.success);
+        assert!(result.content.contains("[Output truncated at 15000 characters]"));
+        assert!(result.content.starts_with("a日日日"));
+    }
 }
@@ -1,3 +1,4 @@
+use crate::text::truncate_chars;
 use crate::tools::registry::Tool;
 use crate::tools::types::{
     PropertySchema, ToolContext, ToolDefinition, ToolGroup, ToolInputSchema, ToolResult,
@@ -361,11 +362,12 @@ impl Tool for GitTool {
                         } else {
                             // Truncate if too long
                             let max_output = 30000;
-                            if output.len() > max_output {
+                            let output_chars = output.chars().count();
+                            if output_chars > max_output {
                                 ToolResult::success(format!(
                                     "{}\n\n[Output truncated. {} more characters not shown.]",
-                                    &output[..max_output],
-                                    output.len() - max_output
+                                    truncate_chars(&output, max_output),
+                                    output_chars - max_output
                                 ))
                             } else {
                                 ToolResult::success(output)
@@ -1,3 +1,4 @@
+use crate::text::truncate_chars;
 use crate::tools::registry::Tool;
 use crate::tools::types::{
     PropertySchema, ToolContext, ToolDefinition, ToolGroup, ToolInputSchema, ToolResult,
@@ -402,12 +403,12 @@ impl Tool for GrepTool {
             Ok(output) => {
                 // Truncate if too long (keep small to avoid context bloat)
                 let max_output = 12000;
-                if output.len() > max_output {
-                    let truncated = &output[..max_output];
+                let output_chars = output.chars().count();
+                if output_chars > max_output {
                     ToolResult::success(format!(
                         "{}\n\n[Output truncated. {} more characters not shown. Use more specific patterns.]",
-                        truncated,
-                        output.len() - max_output
+                        truncate_chars(&output, max_output),
+                        output_chars - max_output
                     ))
                 } else {
                     ToolResult::success(output)
@@ -445,6 +446,21 @@ mod tests {
         assert!(result.content.contains("main"));
     }
 
+    #[tokio::test]
+    async fn test_grep_truncates_multibyte_output_on_char_boundary() {
+        let tool = GrepTool::new();
+        let temp_dir = TempDir::new().unwrap();
+        let line = format!("price {}\n", "€".repeat(150));
+        std::fs::write(temp_dir.path().join("prices.txt"), line.repeat(400)).unwrap();
+        let context =
+            ToolContext::new().with_workspace(temp_dir.path().to_string_lossy().to_string());
+
+        let result = tool.execute(json!({ "pattern": "price" }), &context).await;
+
+        assert!(result.success);
+        assert!(result.content.contains("[Output truncated."));
+    }
+
     #[tokio::test]
     async fn test_grep_outside_workspace() {
         let tool = GrepTool::new();
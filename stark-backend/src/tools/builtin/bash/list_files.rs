@@ -428,6 +428,10 @@ impl Tool for ListFilesTool {
     fn safety_level(&self) -> ToolSafetyLevel {
         ToolSafetyLevel::ReadOnly
     }
+
+    fn cache_ttl(&self) -> Option<u64> {
+        Some(60)
+    }
 }
 
 /// Simple glob pattern matching
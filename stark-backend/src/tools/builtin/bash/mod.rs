@@ -5,6 +5,7 @@
 mod apply_patch;
 mod claude_code_remote;
 mod delete_file;
+mod diff;
 mod edit_file;
 mod exec;
 mod git;
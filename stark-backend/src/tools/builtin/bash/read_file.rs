@@ -236,6 +236,10 @@ impl Tool for ReadFileTool {
     fn safety_level(&self) -> ToolSafetyLevel {
         ToolSafetyLevel::ReadOnly
     }
+
+    fn cache_ttl(&self) -> Option<u64> {
+        Some(60) // Any tool with side effects in the session invalidates it
+    }
 }
 
 #[cfg(test)]
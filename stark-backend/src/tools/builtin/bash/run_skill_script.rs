@@ -403,10 +403,10 @@ impl Tool for RunSkillScriptTool {
 
         // Truncate output
         const MAX_OUTPUT: usize = 15000;
-        if result_text.len() > MAX_OUTPUT {
+        if result_text.chars().count() > MAX_OUTPUT {
             result_text = format!(
                 "{}\n\n[Output truncated at {} characters]",
-                &result_text[..MAX_OUTPUT],
+                crate::text::truncate_chars(&result_text, MAX_OUTPUT),
                 MAX_OUTPUT
             );
         }
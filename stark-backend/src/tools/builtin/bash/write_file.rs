@@ -1,3 +1,4 @@
+use super::diff::{unified_diff, DEFAULT_MAX_DIFF_LINES};
 use crate::config::journal_dir;
 use crate::tools::registry::Tool;
 use crate::tools::types::{
@@ -60,6 +61,30 @@ impl WriteFileTool {
             },
         );
 
+        properties.insert(
+            "output".to_string(),
+            PropertySchema {
+                schema_type: "string".to_string(),
+                description: "How to report an overwrite of an existing file: 'summary' (default) or 'diff' to return a unified diff against the previous content".to_string(),
+                default: Some(json!("summary")),
+                items: None,
+                enum_values: Some(vec!["summary".to_string(), "diff".to_string()]),
+            },
+        );
+        properties.insert(
+            "max_diff_lines".to_string(),
+            PropertySchema {
+                schema_type: "integer".to_string(),
+                description: format!(
+                    "In diff mode, maximum diff lines to return before falling back to a 'N lines changed' summary (default: {})",
+                    DEFAULT_MAX_DIFF_LINES
+                ),
+                default: Some(json!(DEFAULT_MAX_DIFF_LINES)),
+                items: None,
+                enum_values: None,
+            },
+        );
+
         WriteFileTool {
             definition: ToolDefinition {
                 name: "write_file".to_string(),
@@ -88,6 +113,8 @@ struct WriteFileParams {
     content: String,
     append: Option<bool>,
     create_dirs: Option<bool>,
+    output: Option<String>,
+    max_diff_lines: Option<usize>,
 }
 
 #[async_trait]
@@ -209,6 +236,13 @@ impl Tool for WriteFileTool {
             }
         }
 
+        // Capture previous content for diff output (overwrites of existing files only)
+        let previous = if !append && params.output.as_deref() == Some("diff") && final_path.is_file() {
+            tokio::fs::read_to_string(&final_path).await.ok()
+        } else {
+            None
+        };
+
         // Write the file
         let result = if append {
             use tokio::io::AsyncWriteExt;
@@ -233,11 +267,17 @@ impl Tool for WriteFileTool {
                 let lines_written = params.content.lines().count();
                 let mode = if append { "appended to" } else { "written to" };
 
-                ToolResult::success(format!(
+                let mut message = format!(
                     "Successfully {} '{}' ({} bytes, {} lines)",
                     mode, params.path, bytes_written, lines_written
-                ))
-                .with_metadata(json!({
+                );
+                if let Some(previous) = previous {
+                    let max_diff_lines = params.max_diff_lines.unwrap_or(DEFAULT_MAX_DIFF_LINES);
+                    message.push_str("\n\n");
+                    message.push_str(&unified_diff(&params.path, &previous, &params.content, max_diff_lines));
+                }
+
+                ToolResult::success(message).with_metadata(json!({
                     "path": params.path,
                     "bytes_written": bytes_written,
                     "lines_written": lines_written,
@@ -3,6 +3,7 @@
 //! This is the core of the "agentic coding loop" — after writing code,
 //! the agent can verify it compiles and passes tests in one call.
 
+use crate::text::truncate_chars;
 use crate::tools::registry::Tool;
 use crate::tools::types::{
     PropertySchema, ToolContext, ToolDefinition, ToolGroup, ToolInputSchema, ToolResult,
@@ -216,11 +217,7 @@ impl VerifyChangesTool {
             else if (trimmed.contains(": error ") || trimmed.contains(": warning "))
                 && (trimmed.contains(".ts") || trimmed.contains(".js") || trimmed.contains(".tsx") || trimmed.contains(".jsx"))
             {
-                let short = if trimmed.len() > 120 {
-                    format!("{}...", &trimmed[..120])
-                } else {
-                    trimmed.to_string()
-                };
+                let short = truncate_chars(trimmed, 120);
                 locations.push(format!("  - {}", short));
             }
             // Python: File "path.py", line 42
@@ -229,11 +226,7 @@ impl VerifyChangesTool {
             }
             // Go: ./main.go:42:5: ...
             else if trimmed.contains(".go:") && trimmed.contains(": ") {
-                let short = if trimmed.len() > 120 {
-                    format!("{}...", &trimmed[..120])
-                } else {
-                    trimmed.to_string()
-                };
+                let short = truncate_chars(trimmed, 120);
                 locations.push(format!("  - {}", short));
             }
         }
@@ -269,6 +269,27 @@ mod tests {
         assert_eq!(stored.unwrap().api_key, "allium_secret");
     }
 
+    #[test]
+    fn test_lazy_api_keys_fetch_on_demand() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        db.upsert_api_key("ALLIUM_API_KEY", "allium_secret").unwrap();
+
+        // Without lazy loading the database is never consulted
+        let eager = ToolContext::new().with_database(db.clone());
+        assert_eq!(eager.get_api_key("ALLIUM_API_KEY"), None);
+        assert!(eager.list_api_key_names().is_empty());
+
+        let context = ToolContext::new().with_database(db.clone()).with_lazy_api_keys();
+        assert!(context.api_keys.read().unwrap().is_empty(), "nothing loaded up front");
+        assert_eq!(context.list_api_key_names(), vec!["ALLIUM_API_KEY".to_string()]);
+        assert_eq!(context.get_api_key("ALLIUM_API_KEY"), Some("allium_secret".to_string()));
+        assert_eq!(context.get_api_key("MISSING_KEY"), None);
+
+        // Memoized: a later database change doesn't affect this context
+        db.delete_api_key("ALLIUM_API_KEY").unwrap();
+        assert_eq!(context.get_api_key("ALLIUM_API_KEY"), Some("allium_secret".to_string()));
+    }
+
     #[tokio::test]
     async fn test_empty_api_key_rejected() {
         let tool = InstallApiKeyTool::new();
@@ -0,0 +1,129 @@
+use crate::ai::multi_agent::types;
+use crate::tools::registry::Tool;
+use crate::tools::types::{
+    ToolContext, ToolDefinition, ToolGroup, ToolInputSchema, ToolResult,
+};
+use crate::tools::ToolSafetyLevel;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Tool for discovering the agent subtypes (toolboxes) that `set_agent_subtype` accepts.
+/// Lets skills pick a valid subtype key at runtime instead of hardcoding one.
+pub struct ListSubtypesTool {
+    definition: ToolDefinition,
+}
+
+impl ListSubtypesTool {
+    pub fn new() -> Self {
+        ListSubtypesTool {
+            definition: ToolDefinition {
+                name: "list_subtypes".to_string(),
+                description: "List the available agent subtypes (toolboxes): each key with its label, \
+                    allowed tool groups, extra tools and aliases. Pass a key (or alias) to set_agent_subtype \
+                    to switch. Subtypes with skip_task_planner=true go straight to the assistant \
+                    instead of first planning a task list."
+                    .to_string(),
+                input_schema: ToolInputSchema {
+                    schema_type: "object".to_string(),
+                    properties: HashMap::new(),
+                    required: vec![],
+                },
+                group: ToolGroup::System,
+                hidden: false,
+            },
+        }
+    }
+}
+
+impl Default for ListSubtypesTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for ListSubtypesTool {
+    fn definition(&self) -> ToolDefinition {
+        self.definition.clone()
+    }
+
+    async fn execute(&self, _params: Value, _context: &ToolContext) -> ToolResult {
+        let configs = types::all_subtype_configs();
+        if configs.is_empty() {
+            return ToolResult::error("No agent subtypes are configured");
+        }
+
+        let lines: Vec<String> = configs
+            .iter()
+            .map(|c| {
+                let mut line = format!(
+                    "• '{}' — {} {}: {}\n  tool groups: {}",
+                    c.key,
+                    c.emoji,
+                    c.label,
+                    c.description,
+                    if c.tool_groups.is_empty() { "none".to_string() } else { c.tool_groups.join(", ") }
+                );
+                if !c.additional_tools.is_empty() {
+                    line.push_str(&format!("\n  extra tools: {}", c.additional_tools.join(", ")));
+                }
+                if !c.aliases.is_empty() {
+                    line.push_str(&format!("\n  aliases: {}", c.aliases.join(", ")));
+                }
+                if c.skip_task_planner {
+                    line.push_str("\n  skip_task_planner: true (no task planning step)");
+                }
+                line
+            })
+            .collect();
+
+        let subtypes: Vec<Value> = configs
+            .iter()
+            .map(|c| {
+                json!({
+                    "key": c.key,
+                    "label": c.label,
+                    "emoji": c.emoji,
+                    "description": c.description,
+                    "tool_groups": c.tool_groups,
+                    "additional_tools": c.additional_tools,
+                    "aliases": c.aliases,
+                    "skip_task_planner": c.skip_task_planner,
+                })
+            })
+            .collect();
+
+        ToolResult::success(format!(
+            "Available subtypes ({}). Switch with set_agent_subtype(subtype=\"<key>\").\n\n{}",
+            configs.len(),
+            lines.join("\n")
+        ))
+        .with_metadata(json!({ "subtypes": subtypes }))
+    }
+
+    fn safety_level(&self) -> ToolSafetyLevel {
+        ToolSafetyLevel::SafeMode
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_lists_enabled_subtypes_with_groups() {
+        types::load_subtype_registry(types::load_test_subtypes());
+
+        let result = ListSubtypesTool::new().execute(json!({}), &ToolContext::new()).await;
+        assert!(result.success);
+        assert!(result.content.contains("'finance'"));
+
+        let subtypes = result.metadata.unwrap()["subtypes"].as_array().unwrap().clone();
+        let keys: Vec<&str> = subtypes.iter().map(|s| s["key"].as_str().unwrap()).collect();
+        assert_eq!(keys, types::all_subtype_keys());
+        let finance = subtypes.iter().find(|s| s["key"] == "finance").unwrap();
+        assert!(finance["tool_groups"].as_array().unwrap().iter().any(|g| g == "finance"));
+        assert_eq!(finance["skip_task_planner"], false);
+    }
+}
@@ -57,6 +57,7 @@ impl ManageGatewayChannelsTool {
                     "discord".to_string(),
                     "twitter".to_string(),
                     "external_channel".to_string(),
+                    "webhook".to_string(),
                 ]),
             },
         );
@@ -165,6 +166,7 @@ const VALID_CHANNEL_TYPES: &[&str] = &[
     "discord",
     "twitter",
     "external_channel",
+    "webhook",
 ];
 
 #[async_trait]
@@ -9,6 +9,7 @@
 //! - connect: Create a connection between two nodes
 //! - disconnect: Remove a connection between two nodes
 
+use crate::text::truncate_chars;
 use crate::db::tables::mind_nodes::{CreateMindNodeRequest, UpdateMindNodeRequest};
 use crate::tools::registry::Tool;
 use crate::tools::types::{
@@ -125,10 +126,8 @@ fn format_node(node: &crate::db::tables::mind_nodes::MindNode) -> String {
     let trunk_label = if node.is_trunk { " [TRUNK]" } else { "" };
     let body_preview = if node.body.is_empty() {
         "(empty)".to_string()
-    } else if node.body.len() > 100 {
-        format!("{}...", &node.body[..100])
     } else {
-        node.body.clone()
+        truncate_chars(&node.body, 100)
     };
 
     format!("#{}{} — {}", node.id, trunk_label, body_preview)
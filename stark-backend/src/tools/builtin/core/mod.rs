@@ -10,6 +10,7 @@ mod ask_user;
 mod heartbeat_config;
 mod import_identity;
 mod install_api_key;
+mod list_subtypes;
 mod manage_modules;
 mod manage_skills;
 mod mindmap_manage;
@@ -19,6 +20,7 @@ mod modify_kanban;
 mod modify_soul;
 mod modify_special_role;
 mod say_to_user;
+mod schedule_message;
 mod set_agent_subtype;
 mod subagent;
 mod use_skill;
@@ -39,6 +41,7 @@ pub use ask_user::AskUserTool;
 pub use heartbeat_config::HeartbeatConfigTool;
 pub use import_identity::ImportIdentityTool;
 pub use install_api_key::InstallApiKeyTool;
+pub use list_subtypes::ListSubtypesTool;
 pub use manage_modules::ManageModulesTool;
 pub use manage_skills::ManageSkillsTool;
 pub use mindmap_manage::MindmapManageTool;
@@ -48,8 +51,9 @@ pub use modify_kanban::WorkstreamTool;
 pub use modify_soul::ModifySoulTool;
 pub use modify_special_role::ModifySpecialRoleTool;
 pub use say_to_user::SayToUserTool;
+pub use schedule_message::ScheduleMessageTool;
 pub use set_agent_subtype::SetAgentSubtypeTool;
-pub use subagent::{SubagentStatusTool, SpawnSubagentsTool};
+pub use subagent::{SubagentStatusTool, SpawnSubagentsTool, WaitForSubagentTool};
 pub use use_skill::UseSkillTool;
 pub use task_complete::TaskFullyCompletedTool;
 
@@ -0,0 +1,168 @@
+//! Schedule message tool - sends a message to the current chat later
+//!
+//! For one-off reminders ("remind the channel in 2 hours") that don't need a
+//! cron job. The message is stored and posted as-is by the scheduler at the
+//! requested time; the agent does not run again when it is sent.
+
+use crate::channels::scheduled_messages::{self, MAX_PENDING_PER_IDENTITY, MAX_SCHEDULE_DAYS};
+use crate::tools::registry::Tool;
+use crate::tools::types::{
+    PropertySchema, ToolContext, ToolDefinition, ToolGroup, ToolInputSchema, ToolResult,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+pub struct ScheduleMessageTool {
+    definition: ToolDefinition,
+}
+
+impl ScheduleMessageTool {
+    pub fn new() -> Self {
+        let mut properties = HashMap::new();
+
+        properties.insert(
+            "message".to_string(),
+            PropertySchema {
+                schema_type: "string".to_string(),
+                description: "The exact message to post when the time comes".to_string(),
+                default: None,
+                items: None,
+                enum_values: None,
+            },
+        );
+
+        properties.insert(
+            "delay_minutes".to_string(),
+            PropertySchema {
+                schema_type: "integer".to_string(),
+                description: "Send the message this many minutes from now. Use this or 'send_at'.".to_string(),
+                default: None,
+                items: None,
+                enum_values: None,
+            },
+        );
+
+        properties.insert(
+            "send_at".to_string(),
+            PropertySchema {
+                schema_type: "string".to_string(),
+                description: "Send the message at this time (RFC 3339, e.g. 2026-03-10T14:00:00Z). Use this or 'delay_minutes'.".to_string(),
+                default: None,
+                items: None,
+                enum_values: None,
+            },
+        );
+
+        properties.insert(
+            "chat_id".to_string(),
+            PropertySchema {
+                schema_type: "string".to_string(),
+                description: "Chat to post in (Discord channel ID, Telegram chat ID, Slack channel ID). Defaults to the current chat.".to_string(),
+                default: None,
+                items: None,
+                enum_values: None,
+            },
+        );
+
+        ScheduleMessageTool {
+            definition: ToolDefinition {
+                name: "schedule_message".to_string(),
+                description: format!(
+                    "Post a message to the current Discord, Telegram or Slack chat at a later time, e.g. a reminder. The message is sent as-is; you are not run again. Up to {} days ahead, at most {} pending messages per user. For recurring messages use a cron job instead.",
+                    MAX_SCHEDULE_DAYS, MAX_PENDING_PER_IDENTITY
+                ),
+                input_schema: ToolInputSchema {
+                    schema_type: "object".to_string(),
+                    properties,
+                    required: vec!["message".to_string()],
+                },
+                group: ToolGroup::Messaging,
+                hidden: false,
+            },
+        }
+    }
+}
+
+impl Default for ScheduleMessageTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ScheduleMessageParams {
+    message: String,
+    delay_minutes: Option<i64>,
+    send_at: Option<String>,
+    chat_id: Option<String>,
+}
+
+#[async_trait]
+impl Tool for ScheduleMessageTool {
+    fn definition(&self) -> ToolDefinition {
+        self.definition.clone()
+    }
+
+    async fn execute(&self, params: Value, context: &ToolContext) -> ToolResult {
+        let params: ScheduleMessageParams = match serde_json::from_value(params) {
+            Ok(p) => p,
+            Err(e) => return ToolResult::error(format!("Invalid parameters: {}", e)),
+        };
+
+        let now = Utc::now();
+        let send_at = match (params.delay_minutes, params.send_at.as_deref()) {
+            (Some(minutes), None) if minutes >= 0 => now + Duration::minutes(minutes),
+            (Some(_), None) => return ToolResult::error("'delay_minutes' must not be negative"),
+            (None, Some(at)) => match DateTime::parse_from_rfc3339(at) {
+                Ok(at) => at.with_timezone(&Utc),
+                Err(_) => return ToolResult::error(format!("Invalid 'send_at' time '{}' (use RFC 3339)", at)),
+            },
+            (Some(_), Some(_)) => return ToolResult::error("Provide either 'delay_minutes' or 'send_at', not both"),
+            (None, None) => return ToolResult::error("Provide 'delay_minutes' or 'send_at'"),
+        };
+
+        let db = match &context.database {
+            Some(db) => db.clone(),
+            None => return ToolResult::error("Database not available"),
+        };
+        let (channel_id, channel_type) = match (context.channel_id, context.channel_type.as_deref()) {
+            (Some(id), Some(ct)) => (id, ct.to_string()),
+            _ => return ToolResult::error("schedule_message must be run from a channel"),
+        };
+        let chat_id = match params.chat_id.or_else(|| context.platform_chat_id.clone()) {
+            Some(c) => c,
+            None => return ToolResult::error("No chat to post the message in. Provide 'chat_id'."),
+        };
+        // Limits apply per identity, or per platform user when there is none
+        let owner = match (&context.identity_id, &context.user_id) {
+            (Some(identity), _) => identity.clone(),
+            (None, Some(user)) => format!("{}:{}", channel_type, user),
+            (None, None) => return ToolResult::error("Cannot tell who is scheduling this message"),
+        };
+
+        let id = match scheduled_messages::schedule_message(
+            &db, channel_id, &channel_type, &chat_id, &owner, &params.message, send_at, now,
+        ) {
+            Ok(id) => id,
+            Err(e) => return ToolResult::error(e),
+        };
+
+        log::info!(
+            "[SCHEDULED_MESSAGE] Scheduled message {} for {} chat {} at {}",
+            id, channel_type, chat_id, send_at.to_rfc3339()
+        );
+        ToolResult::success(format!(
+            "Message scheduled (id {}) for {}.",
+            id,
+            send_at.format("%Y-%m-%d %H:%M UTC")
+        ))
+        .with_metadata(json!({
+            "scheduled_message_id": id,
+            "send_at": send_at.to_rfc3339(),
+            "chat_id": chat_id,
+        }))
+    }
+}
@@ -1,3 +1,4 @@
+use crate::text::truncate_chars;
 use crate::ai::multi_agent::types;
 use crate::gateway::protocol::GatewayEvent;
 use crate::tools::registry::Tool;
@@ -175,11 +176,7 @@ impl SetAgentSubtypeTool {
     fn truncate_description(desc: &str, max_len: usize) -> String {
         // Take first line only (some descriptions are multi-line)
         let first_line = desc.lines().next().unwrap_or(desc);
-        if first_line.len() > max_len {
-            format!("{}...", &first_line[..max_len])
-        } else {
-            first_line.to_string()
-        }
+        truncate_chars(first_line, max_len)
     }
 }
 
@@ -1,9 +1,11 @@
 //! Sub-agent tools for spawning and monitoring background agent instances
 //!
-//! This module provides two tools:
+//! This module provides three tools:
 //! - `spawn_subagents`: Spawn multiple sub-agents in parallel and wait for all results
 //! - `subagent_status`: Check the status of sub-agents or cancel them
+//! - `wait_for_subagent`: Join sub-agents that are still running
 
+use crate::text::truncate_chars;
 use crate::ai::archetypes::minimax::strip_think_blocks;
 use crate::ai::multi_agent::{SubAgentContext, SubAgentManager, SubAgentStatus};
 use crate::gateway::protocol::GatewayEvent;
@@ -265,6 +267,19 @@ impl SpawnSubagentsTool {
         let session_id = context.session_id.unwrap();
         let channel_id = context.channel_id.unwrap();
 
+        // Children of this context sit one level deeper (top-level sub-agents are depth 0)
+        let child_depth = context.current_subagent_depth.map_or(0, |d| d + 1);
+        if let Err(e) = manager.check_spawn_depth(child_depth) {
+            log::warn!(
+                "[SUBAGENTS] Spawn rejected for {:?} at depth {}: {}",
+                context.current_subagent_id, child_depth, e
+            );
+            return ToolResult::error(e).with_metadata(json!({
+                "depth": child_depth,
+                "max_depth": manager.max_spawn_depth(),
+            }));
+        }
+
         // Assign labels upfront for dependency resolution
         let labeled_agents: Vec<(String, &AgentSpec)> = agents
             .iter()
@@ -613,8 +628,8 @@ impl SpawnSubagentsTool {
 
                     if let Some(ref result) = status.result {
                         let cleaned = strip_think_blocks(result);
-                        let truncated = if cleaned.len() > 2000 {
-                            format!("{}...\n[truncated, {} chars total]", &cleaned[..2000], cleaned.len())
+                        let truncated = if cleaned.chars().count() > 2000 {
+                            format!("{}\n[truncated, {} chars total]", truncate_chars(&cleaned, 2000), cleaned.chars().count())
                         } else {
                             cleaned
                         };
@@ -863,11 +878,7 @@ impl Tool for SubagentStatusTool {
                             status.id,
                             status.label,
                             status.status,
-                            if status.task.len() > 50 {
-                                format!("{}...", &status.task[..50])
-                            } else {
-                                status.task.clone()
-                            }
+                            truncate_chars(&status.task, 50)
                         ));
                     }
 
@@ -895,6 +906,193 @@ impl Tool for SubagentStatusTool {
     }
 }
 
+// ---------------------------------------------------------------------------
+// WaitForSubagentTool — joins sub-agents that are still running
+// ---------------------------------------------------------------------------
+
+/// Default seconds to wait for sub-agents
+const WAIT_DEFAULT_SECS: u64 = 300;
+/// Upper bound on a single wait, matching the watchdog's default limit for the tool
+const WAIT_MAX_SECS: u64 = 3600;
+/// Headroom left under the watchdog limit so the tool can report partial results itself
+const WAIT_WATCHDOG_MARGIN_SECS: u64 = 5;
+
+/// Tool for waiting on sub-agents and collecting their results
+pub struct WaitForSubagentTool {
+    definition: ToolDefinition,
+}
+
+impl WaitForSubagentTool {
+    pub fn new() -> Self {
+        let mut properties = HashMap::new();
+
+        properties.insert(
+            "ids".to_string(),
+            PropertySchema {
+                schema_type: "array".to_string(),
+                description: "IDs of the sub-agents to wait for.".to_string(),
+                default: None,
+                items: Some(Box::new(PropertySchema {
+                    schema_type: "string".to_string(),
+                    description: "Sub-agent ID".to_string(),
+                    default: None,
+                    items: None,
+                    enum_values: None,
+                })),
+                enum_values: None,
+            },
+        );
+
+        properties.insert(
+            "timeout".to_string(),
+            PropertySchema {
+                schema_type: "integer".to_string(),
+                description: format!(
+                    "Seconds to wait for all of them (default: {}, max: {}). Sub-agents still running afterwards keep running and can be waited on again.",
+                    WAIT_DEFAULT_SECS, WAIT_MAX_SECS
+                ),
+                default: Some(json!(WAIT_DEFAULT_SECS)),
+                items: None,
+                enum_values: None,
+            },
+        );
+
+        WaitForSubagentTool {
+            definition: ToolDefinition {
+                name: "wait_for_subagent".to_string(),
+                description: "Wait for running sub-agents to finish and collect their final response, tool-call count and success flag. \
+                    Use this to join sub-agents that spawn_subagents reported as still running."
+                    .to_string(),
+                input_schema: ToolInputSchema {
+                    schema_type: "object".to_string(),
+                    properties,
+                    required: vec!["ids".to_string()],
+                },
+                group: ToolGroup::SubAgent,
+                hidden: false,
+            },
+        }
+    }
+}
+
+impl Default for WaitForSubagentTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct WaitForSubagentParams {
+    ids: Vec<String>,
+    timeout: Option<u64>,
+}
+
+#[async_trait]
+impl Tool for WaitForSubagentTool {
+    fn definition(&self) -> ToolDefinition {
+        self.definition.clone()
+    }
+
+    async fn execute(&self, params: Value, context: &ToolContext) -> ToolResult {
+        let params: WaitForSubagentParams = match serde_json::from_value(params) {
+            Ok(p) => p,
+            Err(e) => return ToolResult::error(format!("Invalid parameters: {}", e)),
+        };
+        if params.ids.is_empty() {
+            return ToolResult::error("'ids' must list at least one sub-agent ID");
+        }
+
+        let manager = match &context.subagent_manager {
+            Some(m) => m,
+            None => {
+                return ToolResult::error(
+                    "SubAgentManager not available. Waiting on sub-agents requires an active session with a configured SubAgentManager."
+                );
+            }
+        };
+
+        // Stay inside the watchdog's limit for this tool so we return partial
+        // results instead of being cut off
+        let mut wait_secs = params.timeout.unwrap_or(WAIT_DEFAULT_SECS).min(WAIT_MAX_SECS);
+        if let Some(limit) = context.tool_timeout(&self.definition.name) {
+            wait_secs = wait_secs.min(limit.as_secs().saturating_sub(WAIT_WATCHDOG_MARGIN_SECS));
+        }
+        let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(wait_secs);
+
+        let wait_all = async {
+            let mut outcomes = Vec::with_capacity(params.ids.len());
+            for id in &params.ids {
+                let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+                outcomes.push((id.clone(), manager.await_result(id, remaining).await));
+            }
+            outcomes
+        };
+
+        let outcomes = match &context.cancellation_token {
+            Some(token) => tokio::select! {
+                outcomes = wait_all => outcomes,
+                _ = token.cancelled() => {
+                    return ToolResult::error(
+                        "Execution was cancelled while waiting for sub-agents. They may still be finishing in the background."
+                    );
+                }
+            },
+            None => wait_all.await,
+        };
+
+        let mut output = String::new();
+        let mut entries = Vec::with_capacity(outcomes.len());
+        let mut pending = 0;
+        for (id, outcome) in outcomes {
+            match outcome {
+                Ok(result) => {
+                    output.push_str(&format!(
+                        "## {} ({}) — {}\nTool calls: {}\n\n{}\n\n",
+                        result.label,
+                        result.subagent_id,
+                        result.status,
+                        result.tool_calls,
+                        result
+                            .response
+                            .clone()
+                            .or_else(|| result.error.clone())
+                            .unwrap_or_else(|| "(no output)".to_string())
+                    ));
+                    entries.push(json!({
+                        "id": result.subagent_id,
+                        "label": result.label,
+                        "status": result.status.to_string(),
+                        "success": result.success,
+                        "tool_calls": result.tool_calls,
+                    }));
+                }
+                Err(e) => {
+                    pending += 1;
+                    output.push_str(&format!("## {}\n{}\n\n", id, e));
+                    entries.push(json!({ "id": id, "error": e }));
+                }
+            }
+        }
+
+        let result = ToolResult::success(output.trim_end().to_string()).with_metadata(json!({
+            "subagents": entries,
+            "pending": pending,
+        }));
+        if pending > 0 {
+            result.with_warning(format!(
+                "{} sub-agent(s) did not finish within {}s",
+                pending, wait_secs
+            ))
+        } else {
+            result
+        }
+    }
+
+    fn safety_level(&self) -> ToolSafetyLevel {
+        ToolSafetyLevel::ReadOnly
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -919,6 +1117,14 @@ mod tests {
         assert!(def.input_schema.required.is_empty());
     }
 
+    #[test]
+    fn test_wait_for_subagent_definition() {
+        let def = WaitForSubagentTool::new().definition();
+        assert_eq!(def.name, "wait_for_subagent");
+        assert_eq!(def.group, ToolGroup::SubAgent);
+        assert_eq!(def.input_schema.required, vec!["ids".to_string()]);
+    }
+
     #[tokio::test]
     async fn test_spawn_subagents_empty() {
         let tool = SpawnSubagentsTool::new();
@@ -957,4 +1163,113 @@ mod tests {
         assert!(!result.success);
         assert!(result.content.contains("SubAgentManager not available"));
     }
+
+    /// Manager with the given max depth, plus a parent session for spawned agents
+    fn manager_with_max_depth(max_spawn_depth: u32) -> (Arc<SubAgentManager>, i64) {
+        let (manager, _, session_id) = manager_and_db(max_spawn_depth);
+        (manager, session_id)
+    }
+
+    fn manager_and_db(max_spawn_depth: u32) -> (Arc<SubAgentManager>, Arc<crate::db::Database>, i64) {
+        use crate::ai::multi_agent::types::SubAgentConfig;
+        let db = Arc::new(crate::db::Database::new(":memory:").unwrap());
+        let session = db
+            .get_or_create_chat_session("web", 1, "test-chat", crate::models::SessionScope::Dm, None)
+            .unwrap();
+        let manager = Arc::new(SubAgentManager::new_with_config(
+            db.clone(),
+            Arc::new(crate::gateway::events::EventBroadcaster::new()),
+            Arc::new(crate::tools::ToolRegistry::new()),
+            SubAgentConfig { max_spawn_depth, ..Default::default() },
+            None,
+        ));
+        (manager, db, session.id)
+    }
+
+    /// Record a sub-agent row directly, as the spawn task would have persisted it
+    fn insert_subagent(db: &crate::db::Database, session_id: i64, id: &str, label: &str, status: &str, result: Option<&str>) {
+        db.conn()
+            .execute(
+                "INSERT INTO sub_agents (subagent_id, parent_session_id, parent_channel_id, label, task, status, timeout_secs, result, started_at)
+                 VALUES (?1, ?2, 1, ?3, 'Do it', ?4, 60, ?5, ?6)",
+                rusqlite::params![id, session_id, label, status, result, chrono::Utc::now().to_rfc3339()],
+            )
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_spawn_beyond_max_depth_is_rejected() {
+        let (manager, session_id) = manager_with_max_depth(2);
+        // Running inside a depth-1 sub-agent: children would be depth 2
+        let context = ToolContext::new()
+            .with_channel(1, "web".to_string())
+            .with_session(session_id)
+            .with_subagent_manager(manager.clone())
+            .with_subagent_identity("parent-agent".to_string(), 1);
+
+        let result = SpawnSubagentsTool::new()
+            .execute(json!({ "agents": [{ "task": "Research more", "label": "nested" }] }), &context)
+            .await;
+        assert!(!result.success);
+        assert!(result.content.contains("Maximum sub-agent depth (2) reached"), "got: {}", result.content);
+        assert!(result.content.contains("Do the work directly"));
+        assert!(manager.list_by_channel(1).unwrap().is_empty(), "nothing spawned");
+    }
+
+    #[tokio::test]
+    async fn test_spawn_within_max_depth_succeeds() {
+        let (manager, session_id) = manager_with_max_depth(2);
+        let child = SubAgentContext::new("child-1".to_string(), session_id, 1, "child".to_string(), "Do it".to_string(), 30)
+            .with_parent_subagent("parent-agent".to_string(), 0);
+        assert_eq!(child.depth, 1);
+        assert_eq!(manager.spawn(child).await.unwrap(), "child-1");
+
+        let grandchild = SubAgentContext::new("grandchild-1".to_string(), session_id, 1, "grandchild".to_string(), "Do it".to_string(), 30)
+            .with_parent_subagent("child-1".to_string(), 1);
+        let err = manager.spawn(grandchild).await.unwrap_err();
+        assert!(err.contains("Maximum sub-agent depth"));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_subagent_reports_finished_and_running() {
+        let (manager, db, session_id) = manager_and_db(2);
+        insert_subagent(&db, session_id, "done-1", "research", "completed", Some("Found it"));
+        insert_subagent(&db, session_id, "busy-1", "writer", "running", None);
+        let context = ToolContext::new().with_channel(1, "web".to_string()).with_subagent_manager(manager.clone());
+
+        let result = WaitForSubagentTool::new()
+            .execute(json!({ "ids": ["done-1", "busy-1"], "timeout": 0 }), &context)
+            .await;
+        assert!(result.success, "{}", result.content);
+        assert!(result.content.contains("research (done-1) — completed"), "got: {}", result.content);
+        assert!(result.content.contains("Found it"));
+        assert!(result.content.contains("still running"), "got: {}", result.content);
+        let metadata = result.metadata.unwrap();
+        assert_eq!(metadata["pending"], 1);
+        assert_eq!(metadata["subagents"][0]["success"], true);
+        assert_eq!(result.warnings.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_subagent_stops_on_cancellation() {
+        let (manager, db, session_id) = manager_and_db(2);
+        insert_subagent(&db, session_id, "busy-1", "writer", "running", None);
+        let token = tokio_util::sync::CancellationToken::new();
+        let context = ToolContext::new()
+            .with_subagent_manager(manager.clone())
+            .with_cancellation_token(token.clone());
+
+        let canceller = tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            token.cancel();
+        });
+        let started = std::time::Instant::now();
+        let result = WaitForSubagentTool::new()
+            .execute(json!({ "ids": ["busy-1"], "timeout": 600 }), &context)
+            .await;
+        canceller.await.unwrap();
+        assert!(!result.success);
+        assert!(result.content.contains("cancelled"));
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
 }
@@ -139,12 +139,7 @@ impl Tool for UseSkillTool {
 
         // Pre-flight: check required API keys are configured
         if !skill.requires_api_keys.is_empty() {
-            let configured_keys: Vec<String> = db
-                .list_api_keys()
-                .unwrap_or_default()
-                .into_iter()
-                .map(|k| k.service_name)
-                .collect();
+            let configured_keys: Vec<String> = db.list_api_key_names().unwrap_or_default();
             let missing_keys: Vec<&String> = skill
                 .requires_api_keys
                 .keys()
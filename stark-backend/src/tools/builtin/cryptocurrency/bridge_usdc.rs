@@ -24,7 +24,7 @@ use crate::tools::rpc_config::{resolve_rpc_from_context, ResolvedRpcConfig};
 use crate::tools::types::{
     PropertySchema, ToolContext, ToolDefinition, ToolGroup, ToolInputSchema, ToolResult,
 };
-use crate::tx_queue::QueuedTransaction;
+use crate::tx_queue::{QueuedTransaction, TxQueueManager};
 use crate::wallet::WalletProvider;
 use crate::x402::X402EvmRpc;
 use async_trait::async_trait;
@@ -223,6 +223,7 @@ impl BridgeUsdcTool {
     }
 
     /// Sign a transaction for queueing using WalletProvider (works in both Standard and Flash mode)
+    #[allow(clippy::too_many_arguments)]
     async fn sign_transaction_for_queue(
         chain_id: u64,
         network: &str,
@@ -231,6 +232,7 @@ impl BridgeUsdcTool {
         data: Vec<u8>,
         rpc_config: &ResolvedRpcConfig,
         wallet_provider: &Arc<dyn WalletProvider>,
+        tx_queue: &TxQueueManager,
     ) -> Result<SignedTxForQueue, String> {
         let rpc = X402EvmRpc::new_with_wallet_provider(
             wallet_provider.clone(),
@@ -244,9 +246,6 @@ impl BridgeUsdcTool {
         let from_address: Address = from_str.parse()
             .map_err(|_| format!("Invalid wallet address: {}", from_str))?;
 
-        // Get nonce
-        let nonce = rpc.get_transaction_count(from_address).await?;
-
         // Estimate gas
         let gas: U256 = rpc
             .estimate_gas(from_address, to, &data, value)
@@ -257,6 +256,11 @@ impl BridgeUsdcTool {
         // Get gas prices
         let (max_fee, priority_fee) = rpc.estimate_eip1559_fees().await?;
 
+        // Get nonce (serialized per address, so approval + bridge get consecutive nonces)
+        let nonce = tx_queue
+            .allocate_nonce(network, &from_str, || rpc.get_transaction_count(from_address))
+            .await?;
+
         log::info!(
             "[bridge_usdc] Signing tx: to={:?}, value={}, data_len={}, gas={}, nonce={} on {}",
             to,
@@ -281,10 +285,13 @@ impl BridgeUsdcTool {
 
         // Sign using WalletProvider (works in both Standard and Flash mode)
         let typed_tx: TypedTransaction = tx.into();
-        let signature = wallet_provider
-            .sign_transaction(&typed_tx)
-            .await
-            .map_err(|e| format!("Failed to sign transaction: {}", e))?;
+        let signature = match wallet_provider.sign_transaction(&typed_tx).await {
+            Ok(signature) => signature,
+            Err(e) => {
+                tx_queue.release_nonce(network, &from_str, nonce.as_u64()).await;
+                return Err(format!("Failed to sign transaction: {}", e));
+            }
+        };
 
         let signed_tx = typed_tx.rlp_signed(&signature);
         let signed_tx_hex = format!("0x{}", hex::encode(&signed_tx));
@@ -554,7 +561,6 @@ impl Tool for BridgeUsdcTool {
         let rpc_config = resolve_rpc_from_context(&context.extra, network);
 
         let mut queued_uuids = Vec::new();
-        let mut current_nonce_offset = 0u64;
 
         // Queue approval transactions if needed (usually just one for USDC)
         for approval in &across_response.approval_txns {
@@ -585,6 +591,7 @@ impl Tool for BridgeUsdcTool {
                 approval_data,
                 &rpc_config,
                 wallet_provider,
+                tx_queue,
             )
             .await
             {
@@ -610,7 +617,6 @@ impl Tool for BridgeUsdcTool {
 
             tx_queue.queue(queued_approval);
             queued_uuids.push(("approval".to_string(), approval_uuid));
-            current_nonce_offset = 1;
 
             log::info!(
                 "[bridge_usdc] Approval tx queued, nonce={}",
@@ -635,97 +641,21 @@ impl Tool for BridgeUsdcTool {
         // Bridge transactions for USDC don't require ETH value (USDC is ERC20)
         let bridge_value = U256::zero();
 
-        // For bridge tx, we need to account for approval nonce if it was queued
-        let signed_bridge = if current_nonce_offset > 0 {
-            // Re-sign with incremented nonce using WalletProvider
-            let rpc = match X402EvmRpc::new_with_wallet_provider(
-                wallet_provider.clone(),
-                network,
-                Some(rpc_config.url.clone()),
-                rpc_config.use_x402,
-            ) {
-                Ok(r) => r,
-                Err(e) => return ToolResult::error(format!("Failed to create RPC: {}", e)),
-            };
-
-            // Get wallet address from WalletProvider
-            let from_str = wallet_provider.get_address();
-            let from_address: Address = match from_str.parse() {
-                Ok(a) => a,
-                Err(_) => return ToolResult::error(format!("Invalid wallet address: {}", from_str)),
-            };
-
-            let base_nonce = match rpc.get_transaction_count(from_address).await {
-                Ok(n) => n,
-                Err(e) => return ToolResult::error(format!("Failed to get nonce: {}", e)),
-            };
-            let nonce = base_nonce + U256::from(current_nonce_offset);
-
-            let gas: U256 = match rpc
-                .estimate_gas(from_address, bridge_to, &bridge_data, bridge_value)
-                .await
-            {
-                Ok(g) => g,
-                Err(e) => {
-                    return ToolResult::error(format!("Gas estimation failed for bridge: {}", e))
-                }
-            };
-            let gas = gas * U256::from(130) / U256::from(100);
-
-            let (max_fee, priority_fee) = match rpc.estimate_eip1559_fees().await {
-                Ok(fees) => fees,
-                Err(e) => return ToolResult::error(format!("Failed to estimate fees: {}", e)),
-            };
-
-            let tx = Eip1559TransactionRequest::new()
-                .from(from_address)
-                .to(bridge_to)
-                .value(bridge_value)
-                .data(bridge_data.clone())
-                .nonce(nonce)
-                .gas(gas)
-                .max_fee_per_gas(max_fee)
-                .max_priority_fee_per_gas(priority_fee)
-                .chain_id(from_chain_id);
-
-            // Sign using WalletProvider (works in both Standard and Flash mode)
-            let typed_tx: TypedTransaction = tx.into();
-            let signature = match wallet_provider.sign_transaction(&typed_tx).await {
-                Ok(s) => s,
-                Err(e) => {
-                    return ToolResult::error(format!("Failed to sign bridge transaction: {}", e))
-                }
-            };
-
-            let signed_tx = typed_tx.rlp_signed(&signature);
-
-            SignedTxForQueue {
-                from: from_str,
-                to: format!("{:?}", bridge_to),
-                value: bridge_value.to_string(),
-                data: format!("0x{}", hex::encode(&bridge_data)),
-                gas_limit: gas.to_string(),
-                max_fee_per_gas: max_fee.to_string(),
-                max_priority_fee_per_gas: priority_fee.to_string(),
-                nonce: nonce.as_u64(),
-                signed_tx_hex: format!("0x{}", hex::encode(&signed_tx)),
-                network: network.to_string(),
-            }
-        } else {
-            match Self::sign_transaction_for_queue(
-                from_chain_id,
-                network,
-                bridge_to,
-                bridge_value,
-                bridge_data,
-                &rpc_config,
-                wallet_provider,
-            )
-            .await
-            {
-                Ok(s) => s,
-                Err(e) => return ToolResult::error(format!("Failed to sign bridge tx: {}", e)),
-            }
+        // The nonce manager hands out the nonce after any queued approval
+        let signed_bridge = match Self::sign_transaction_for_queue(
+            from_chain_id,
+            network,
+            bridge_to,
+            bridge_value,
+            bridge_data,
+            &rpc_config,
+            wallet_provider,
+            tx_queue,
+        )
+        .await
+        {
+            Ok(s) => s,
+            Err(e) => return ToolResult::error(format!("Failed to sign bridge tx: {}", e)),
         };
 
         let bridge_uuid = Uuid::new_v4().to_string();
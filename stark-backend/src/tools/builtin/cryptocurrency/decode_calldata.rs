@@ -7,6 +7,7 @@
 //! Primary use case: Decoding 0x swap quotes so they can be executed via
 //! web3_function_call with proper ABI encoding.
 
+use crate::text::truncate_chars;
 use crate::tools::registry::Tool;
 use crate::tools::types::{
     PropertySchema, ToolContext, ToolDefinition, ToolGroup, ToolInputSchema, ToolResult,
@@ -369,11 +370,7 @@ impl Tool for DecodeCalldataTool {
         let params_display: Vec<String> = decoded_params.iter()
             .map(|p| {
                 let s = p.to_string();
-                if s.len() > 50 {
-                    format!("{}...", &s[..50])
-                } else {
-                    s
-                }
+                truncate_chars(&s, 50)
             })
             .collect();
 
@@ -475,4 +475,8 @@ impl Tool for DexScreenerTool {
     fn safety_level(&self) -> ToolSafetyLevel {
         ToolSafetyLevel::ReadOnly
     }
+
+    fn cache_ttl(&self) -> Option<u64> {
+        Some(60) // Prices move; keep reuse short
+    }
 }
@@ -3,6 +3,7 @@
 //! Attaches RFC 9421 HTTP Message Signatures (signed via ERC-191) so that
 //! the receiving server can verify the agent's Ethereum address via `ecrecover`.
 
+use crate::text::truncate_chars;
 use crate::erc8128::Erc8128Signer;
 use crate::tools::registry::Tool;
 use crate::tools::types::{
@@ -324,17 +325,12 @@ impl Tool for Erc8128FetchTool {
                         "url": params.url,
                         "wallet": signer.address(),
                         "chain_id": params.chain_id,
-                        "x402_payment": payment_info.map(|p| json!({
-                            "amount": p.amount_formatted,
-                            "asset": p.asset,
-                            "pay_to": p.pay_to,
-                            "tx_hash": p.tx_hash,
-                        })),
+                        "x402_payment": payment_info,
                     });
 
                     if retry_status.is_success() {
-                        let display_body = if retry_body.len() > 8000 {
-                            format!("{}...\n\n(truncated, {} bytes total)", &retry_body[..8000], retry_body.len())
+                        let display_body = if retry_body.chars().count() > 8000 {
+                            format!("{}\n\n(truncated, {} bytes total)", truncate_chars(&retry_body, 8000), retry_body.len())
                         } else {
                             retry_body
                         };
@@ -344,7 +340,7 @@ impl Tool for Erc8128FetchTool {
                             "HTTP {} {} (after x402 payment): {}",
                             retry_status.as_u16(),
                             retry_status.canonical_reason().unwrap_or(""),
-                            if retry_body.len() > 2000 { format!("{}...", &retry_body[..2000]) } else { retry_body }
+                            truncate_chars(&retry_body, 2000)
                         )).with_metadata(metadata);
                     }
                 }
@@ -384,8 +380,8 @@ impl Tool for Erc8128FetchTool {
 
         if status.is_success() {
             // Truncate very large responses for the tool output
-            let display_body = if body_text.len() > 8000 {
-                format!("{}...\n\n(truncated, {} bytes total)", &body_text[..8000], body_text.len())
+            let display_body = if body_text.chars().count() > 8000 {
+                format!("{}\n\n(truncated, {} bytes total)", truncate_chars(&body_text, 8000), body_text.len())
             } else {
                 body_text
             };
@@ -396,11 +392,7 @@ impl Tool for Erc8128FetchTool {
                 "HTTP {} {}: {}",
                 status_code,
                 status.canonical_reason().unwrap_or(""),
-                if body_text.len() > 2000 {
-                    format!("{}...", &body_text[..2000])
-                } else {
-                    body_text
-                }
+                truncate_chars(&body_text, 2000)
             ))
             .with_metadata(metadata)
         }
@@ -256,7 +256,7 @@ impl Tool for GeckoTerminalTool {
 
         // Use explicit network param, or fall back to the globally selected network
         let effective_network = params.network.as_deref()
-            .or(context.selected_network.as_deref());
+            .or(context.selected_network.as_ref().map(|n| n.as_str()));
 
         if let Some(net) = effective_network {
             let normalized = normalize_network(net.trim());
@@ -2,6 +2,7 @@
 //!
 //! Shows transactions that have been signed but not yet broadcast.
 
+use crate::text::truncate_chars;
 use crate::gateway::protocol::GatewayEvent;
 use super::web3_tx::SendEthTool;
 use crate::tools::registry::Tool;
@@ -258,11 +259,7 @@ impl Tool for ListQueuedWeb3TxTool {
             }
 
             if let Some(ref error) = tx.error {
-                let short_error = if error.len() > 50 {
-                    format!("{}...", &error[..50])
-                } else {
-                    error.clone()
-                };
+                let short_error = truncate_chars(error, 50);
                 msg.push_str(&format!("  Error: {}\n", short_error));
             }
 
@@ -3,6 +3,7 @@
 //! Performs the SIWA nonce→sign→verify handshake against a target server,
 //! then stores the auth receipt in a register for use by `erc8128_fetch`.
 
+use crate::text::truncate_chars;
 use crate::siwa::{build_siwa_message, SiwaMessageFields};
 use crate::tools::registry::Tool;
 use crate::tools::types::{
@@ -405,11 +406,7 @@ impl Tool for SiwaAuthTool {
             address = address,
             agent_line = agent_line,
             register = params.cache_as,
-            response = if verify_text.len() > 2000 {
-                format!("{}...", &verify_text[..2000])
-            } else {
-                verify_text
-            },
+            response = truncate_chars(&verify_text, 2000),
         ))
         .with_metadata(metadata)
     }
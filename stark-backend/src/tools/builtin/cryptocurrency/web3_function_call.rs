@@ -161,7 +161,7 @@ impl Tool for Web3FunctionCallTool {
 
         let network = match resolve_network(
             params.network.as_deref(),
-            context.selected_network.as_deref()
+            context.selected_network.as_ref()
         ) {
             Ok(n) => n,
             Err(e) => return ToolResult::error(e),
@@ -101,7 +101,7 @@ impl Tool for Web3PresetFunctionCallTool {
 
         let network = match resolve_network(
             params.network.as_deref(),
-            context.selected_network.as_deref(),
+            context.selected_network.as_ref(),
         ) {
             Ok(n) => n,
             Err(e) => return ToolResult::error(e),
@@ -250,6 +250,7 @@ impl Tool for Web3PresetFunctionCallTool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tools::rpc_config::Network;
     use crate::tools::types::ToolContext;
 
     #[tokio::test]
@@ -257,7 +258,7 @@ mod tests {
         let tool = Web3PresetFunctionCallTool::new();
         let context = ToolContext::new()
             .with_channel(1, "discord".to_string())
-            .with_selected_network(Some("base".to_string()));
+            .with_selected_network(Some(Network::Base));
 
         // Set recipient_address via set_address (wrong source for Discord)
         context.set_register(
@@ -280,7 +281,7 @@ mod tests {
         let tool = Web3PresetFunctionCallTool::new();
         let context = ToolContext::new()
             .with_channel(1, "discord".to_string())
-            .with_selected_network(Some("base".to_string()));
+            .with_selected_network(Some(Network::Base));
 
         // Don't set recipient_address at all
         let result = tool
@@ -297,7 +298,7 @@ mod tests {
         let tool = Web3PresetFunctionCallTool::new();
         let context = ToolContext::new()
             .with_channel(1, "discord".to_string())
-            .with_selected_network(Some("base".to_string()));
+            .with_selected_network(Some(Network::Base));
 
         // Set recipient_address via discord_resolve_user (correct source)
         context.set_register(
@@ -325,7 +326,7 @@ mod tests {
         let tool = Web3PresetFunctionCallTool::new();
         let context = ToolContext::new()
             .with_channel(1, "web".to_string())
-            .with_selected_network(Some("base".to_string()));
+            .with_selected_network(Some(Network::Base));
 
         // Set recipient_address via set_address — should be fine for web
         context.set_register(
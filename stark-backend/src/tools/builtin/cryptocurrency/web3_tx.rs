@@ -13,11 +13,12 @@
 
 use super::verify_intent::{self, TransactionIntent};
 use crate::tools::registry::Tool;
-use crate::tools::rpc_config::{resolve_rpc_from_context, Network, ResolvedRpcConfig};
+use crate::tools::rpc_config::{resolve_rpc_from_context, ResolvedRpcConfig};
 use crate::tools::types::{
     PropertySchema, ToolContext, ToolDefinition, ToolGroup, ToolInputSchema, ToolResult,
 };
-use crate::tx_queue::QueuedTransaction;
+use crate::tx_queue::{QueuedTransaction, TxQueueManager};
+use crate::web3::{get_chain_id, resolve_network};
 use crate::wallet::WalletProvider;
 use crate::x402::X402EvmRpc;
 use async_trait::async_trait;
@@ -27,7 +28,6 @@ use ethers::types::transaction::eip2718::TypedTransaction;
 use serde::Deserialize;
 use serde_json::{json, Value};
 use std::collections::HashMap;
-use std::str::FromStr;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -81,17 +81,6 @@ impl SendEthTool {
         }
     }
 
-    /// Get chain ID for a network
-    fn get_chain_id(network: &str) -> u64 {
-        match network {
-            "mainnet" => 1,
-            "polygon" => 137,
-            "arbitrum" => 42161,
-            "optimism" => 10,
-            _ => 8453, // Base
-        }
-    }
-
     /// Sign an ETH transfer using WalletProvider (works in both Standard and Flash mode)
     async fn sign_eth_transfer(
         network: &str,
@@ -99,6 +88,7 @@ impl SendEthTool {
         value: &str,
         rpc_config: &ResolvedRpcConfig,
         wallet_provider: &Arc<dyn WalletProvider>,
+        tx_queue: &TxQueueManager,
     ) -> Result<SignedTxResult, String> {
         // Create RPC client using WalletProvider for x402 payments
         let rpc = X402EvmRpc::new_with_wallet_provider(
@@ -107,7 +97,7 @@ impl SendEthTool {
             Some(rpc_config.url.clone()),
             rpc_config.use_x402,
         )?;
-        let chain_id = Self::get_chain_id(network);
+        let chain_id = get_chain_id(network);
 
         // Get wallet address from WalletProvider
         let from_str = wallet_provider.get_address();
@@ -121,15 +111,17 @@ impl SendEthTool {
         // Parse value
         let tx_value: U256 = parse_u256(value)?;
 
-        // Get nonce
-        let nonce = rpc.get_transaction_count(from_address).await?;
-
         // Simple ETH transfer is always 21000 gas
         let gas = U256::from(21000u64);
 
         // Auto-estimate gas prices
         let (max_fee, priority_fee) = rpc.estimate_eip1559_fees().await?;
 
+        // Get nonce (serialized per address through the queue)
+        let nonce = tx_queue
+            .allocate_nonce(network, &from_str, || rpc.get_transaction_count(from_address))
+            .await?;
+
         log::info!(
             "[send_eth] Signing ETH transfer: to={}, value={}, gas={}, nonce={} on {}",
             to, value, gas, nonce, network
@@ -148,10 +140,13 @@ impl SendEthTool {
 
         // Sign the transaction using WalletProvider (works in both Standard and Flash mode)
         let typed_tx: TypedTransaction = tx.into();
-        let signature = wallet_provider
-            .sign_transaction(&typed_tx)
-            .await
-            .map_err(|e| format!("Failed to sign transaction: {}", e))?;
+        let signature = match wallet_provider.sign_transaction(&typed_tx).await {
+            Ok(signature) => signature,
+            Err(e) => {
+                tx_queue.release_nonce(network, &from_str, nonce.as_u64()).await;
+                return Err(format!("Failed to sign transaction: {}", e));
+            }
+        };
 
         let signed_tx = typed_tx.rlp_signed(&signature);
         let signed_tx_hex = format!("0x{}", hex::encode(&signed_tx));
@@ -311,17 +306,6 @@ struct ResolvedTxData {
     source: String,
 }
 
-/// Resolve the network from params, context, or default
-fn resolve_network(param_network: Option<&str>, context_network: Option<&str>) -> Result<Network, String> {
-    // Priority: explicit param > context selected > default (Base)
-    let network_str = param_network
-        .or(context_network)
-        .unwrap_or("base");
-
-    Network::from_str(network_str)
-        .map_err(|_| format!("Invalid network '{}'. Must be one of: base, mainnet, polygon", network_str))
-}
-
 #[async_trait]
 impl Tool for SendEthTool {
     fn definition(&self) -> ToolDefinition {
@@ -339,7 +323,7 @@ impl Tool for SendEthTool {
         // Resolve network: use provided network, or context's selected_network, or default to Base
         let network = match resolve_network(
             params.network.as_deref(),
-            context.selected_network.as_deref()
+            context.selected_network.as_ref()
         ) {
             Ok(n) => n,
             Err(e) => return ToolResult::error(e),
@@ -401,6 +385,7 @@ impl Tool for SendEthTool {
             &tx_data.value,
             &rpc_config,
             wallet_provider,
+            tx_queue,
         ).await {
             Ok(signed) => {
                 // Verify intent before queueing
@@ -423,6 +408,7 @@ impl Tool for SendEthTool {
                     ),
                 };
                 if let Err(reason) = verify_intent::verify_intent(&intent, context, None).await {
+                    tx_queue.release_nonce(&signed.network, &signed.from, signed.nonce).await;
                     return ToolResult::error(reason);
                 }
 
@@ -288,12 +288,20 @@ impl Tool for X402RpcTool {
         }
 
         // Return the result
-        match rpc_response.result {
+        let result = match rpc_response.result {
             Some(result) => ToolResult::success(
                 serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string()),
             )
             .with_metadata(metadata),
             None => ToolResult::success("null").with_metadata(metadata),
+        };
+        if rpc_config.fallback {
+            result.with_warning(format!(
+                "No RPC endpoint is configured for {}; the default endpoint {} was used",
+                params.network, rpc_config.url
+            ))
+        } else {
+            result
         }
     }
 }
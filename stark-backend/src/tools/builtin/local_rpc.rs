@@ -6,6 +6,7 @@
 //! (e.g. wallet-monitor-service on port 9100) without requiring
 //! dedicated tool structs for each endpoint.
 
+use crate::text::truncate_chars;
 use crate::tools::registry::Tool;
 use crate::tools::types::{
     PropertySchema, ToolContext, ToolDefinition, ToolGroup, ToolInputSchema, ToolResult,
@@ -174,11 +175,7 @@ impl Tool for LocalRpcTool {
         let body = response.text().await.unwrap_or_default();
 
         if !status.is_success() {
-            let truncated = if body.len() > 2000 {
-                format!("{}...", &body[..2000])
-            } else {
-                body
-            };
+            let truncated = truncate_chars(&body, 2000);
             return ToolResult::error(format!(
                 "HTTP {} from {}\n{}",
                 status, params.url, truncated
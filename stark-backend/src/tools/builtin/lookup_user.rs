@@ -0,0 +1,154 @@
+//! Lookup User Tool
+//!
+//! Returns the public profile of another user: the facts that user explicitly
+//! chose to share with `/profile share`. Memories are private by default and
+//! this tool only ever reads the public-flagged subset (see
+//! `Database::get_public_profile_facts`), so it can't be used to pry into what
+//! the agent remembers about someone.
+
+use crate::tools::registry::Tool;
+use crate::tools::types::{
+    PropertySchema, ToolContext, ToolDefinition, ToolGroup, ToolInputSchema, ToolResult,
+};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+
+/// Tool for reading another user's opted-in public profile
+pub struct LookupUserTool {
+    definition: ToolDefinition,
+}
+
+impl LookupUserTool {
+    pub fn new() -> Self {
+        let mut properties = HashMap::new();
+
+        properties.insert(
+            "user".to_string(),
+            PropertySchema {
+                schema_type: "string".to_string(),
+                description: "The user to look up: their @handle, platform user ID, or identity ID.".to_string(),
+                default: None,
+                items: None,
+                enum_values: None,
+            },
+        );
+
+        Self {
+            definition: ToolDefinition {
+                name: "lookup_user".to_string(),
+                description: "Look up another user's public profile: the facts they chose to share with others (via /profile share). Use this when a mentioned user's background matters for the answer. Returns nothing about users who haven't shared a profile; private memories are never available.".to_string(),
+                input_schema: ToolInputSchema {
+                    schema_type: "object".to_string(),
+                    properties,
+                    required: vec!["user".to_string()],
+                },
+                group: ToolGroup::Memory,
+                hidden: false,
+            },
+        }
+    }
+}
+
+impl Default for LookupUserTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LookupUserParams {
+    user: String,
+}
+
+#[async_trait]
+impl Tool for LookupUserTool {
+    fn definition(&self) -> ToolDefinition {
+        self.definition.clone()
+    }
+
+    async fn execute(&self, params: Value, context: &ToolContext) -> ToolResult {
+        let params: LookupUserParams = match serde_json::from_value(params) {
+            Ok(p) => p,
+            Err(e) => return ToolResult::error(format!("Invalid parameters: {}", e)),
+        };
+        let handle = params.user.trim();
+        if handle.trim_start_matches('@').is_empty() {
+            return ToolResult::error("'user' must not be empty");
+        }
+
+        let db = match &context.database {
+            Some(db) => db,
+            None => return ToolResult::error("Database not available"),
+        };
+
+        let links = match db.find_identities_by_handle(handle) {
+            Ok(links) => links,
+            Err(e) => return ToolResult::error(format!("Failed to look up user: {}", e)),
+        };
+        let identity_ids: BTreeSet<&str> = links.iter().map(|l| l.identity_id.as_str()).collect();
+        let identity_id = match identity_ids.len() {
+            0 => return ToolResult::success(format!("No known user matches '{}'.", handle))
+                .with_metadata(json!({ "found": false })),
+            1 => identity_ids.into_iter().next().unwrap(),
+            n => return ToolResult::error(format!(
+                "'{}' matches {} different users. Use their platform user ID instead.",
+                handle, n
+            )),
+        };
+
+        let facts = match db.get_public_profile_facts(identity_id) {
+            Ok(facts) => facts,
+            Err(e) => return ToolResult::error(format!("Failed to read public profile: {}", e)),
+        };
+        if facts.is_empty() {
+            return ToolResult::success(format!("{} hasn't shared a public profile.", handle))
+                .with_metadata(json!({ "found": true, "fact_count": 0 }));
+        }
+
+        let lines: Vec<String> = facts.iter().map(|(_, fact)| format!("- {}", fact)).collect();
+        ToolResult::success(format!(
+            "Public profile of {} (shared by the user themself):\n{}",
+            handle,
+            lines.join("\n")
+        ))
+        .with_metadata(json!({ "found": true, "fact_count": facts.len() }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_returns_only_public_facts() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let alice = db.get_or_create_identity("discord", "u-1", Some("Alice")).unwrap();
+        db.set_identity_preference(&alice.identity_id, "wallet", "keeps savings in a hardware wallet")
+            .unwrap();
+        db.add_public_profile_fact(&alice.identity_id, "Runs the Base builders meetup").unwrap();
+        let retracted = db.add_public_profile_fact(&alice.identity_id, "Lives in Lisbon").unwrap();
+        assert!(db.unshare_profile_fact(&alice.identity_id, retracted).unwrap());
+
+        let tool = LookupUserTool::new();
+        let context = ToolContext::new().with_database(db.clone());
+        let result = tool.execute(json!({"user": "@alice"}), &context).await;
+        assert!(result.success, "{}", result.content);
+        assert!(result.content.contains("Runs the Base builders meetup"));
+        assert!(!result.content.contains("hardware wallet"), "private memory leaked: {}", result.content);
+        assert!(!result.content.contains("Lisbon"), "unshared fact leaked: {}", result.content);
+
+        // Someone who never opted in
+        db.get_or_create_identity("discord", "u-2", Some("bob")).unwrap();
+        let result = tool.execute(json!({"user": "bob"}), &context).await;
+        assert!(result.content.contains("hasn't shared a public profile"));
+
+        // Only the owner can retract a fact
+        let bob = db.get_or_create_identity("discord", "u-2", None).unwrap();
+        let shared = db.get_public_profile_facts(&alice.identity_id).unwrap();
+        assert!(!db.unshare_profile_fact(&bob.identity_id, shared[0].0).unwrap());
+    }
+}
@@ -16,9 +16,12 @@ pub mod social_media;
 
 // Individual tools (remaining uncategorized)
 mod local_rpc;
+mod lookup_user;
 mod process_status;
 mod qmd_memory_read;
 mod qmd_memory_search;
+mod query_rollouts;
+mod recall_recent_messages;
 mod web_fetch;
 
 // Re-exports from submodules
@@ -30,9 +33,9 @@ pub use bash::{
 pub use code::{CommitterTool, DeployTool, IndexProjectTool, PrQualityTool, VerifyChangesTool};
 pub use core::{
     AddTaskTool, DefineTasksTool, AgentSendTool, ApiKeysCheckTool, AskUserTool, HeartbeatConfigTool,
-    ImportIdentityTool, InstallApiKeyTool, ManageModulesTool, ManageSkillsTool, MindmapManageTool,
+    ImportIdentityTool, InstallApiKeyTool, ListSubtypesTool, ManageModulesTool, ManageSkillsTool, MindmapManageTool,
     ReadSkillTool, RegisterNewIdentityTool, WorkstreamTool, ModifySoulTool, ModifySpecialRoleTool, SayToUserTool,
-    SetAgentSubtypeTool, SubagentStatusTool, SpawnSubagentsTool, TaskFullyCompletedTool, UseSkillTool,
+    ScheduleMessageTool, SetAgentSubtypeTool, SubagentStatusTool, SpawnSubagentsTool, WaitForSubagentTool, TaskFullyCompletedTool, UseSkillTool,
     // Meta tools (self-management)
     CloudBackupTool, ManageGatewayChannelsTool, ReadOperatingModeTool, ReadRecentTransactionsTool,
     SetThemeAccentTool,
@@ -44,11 +47,14 @@ pub use cryptocurrency::{
     VerifyTxBroadcastTool, Web3PresetFunctionCallTool, X402AgentInvokeTool, X402FetchTool,
     X402PostTool, X402RpcTool,
 };
-pub use social_media::{DiscordLookupTool, DiscordReadTool, DiscordWriteTool, FigmaTool, GithubUserTool, TelegramReadTool, TwitterPostTool};
+pub use social_media::{CreatePollTool, DiscordLookupTool, DiscordReadTool, DiscordWriteTool, FigmaTool, GithubUserTool, TelegramReadTool, TwitterPostTool};
 
 // Re-exports from individual tools
 pub use local_rpc::LocalRpcTool;
+pub use lookup_user::LookupUserTool;
 pub use process_status::ProcessStatusTool;
 pub use qmd_memory_read::QmdMemoryReadTool;
 pub use qmd_memory_search::QmdMemorySearchTool;
+pub use query_rollouts::QueryRolloutsTool;
+pub use recall_recent_messages::RecallRecentMessagesTool;
 pub use web_fetch::WebFetchTool;
@@ -6,6 +6,7 @@
 //! - Kill a running process
 //! - List all processes for the current channel
 
+use crate::text::truncate_chars;
 use crate::tools::registry::Tool;
 use crate::tools::types::{
     PropertySchema, ToolContext, ToolDefinition, ToolGroup, ToolInputSchema, ToolResult,
@@ -225,11 +226,7 @@ impl Tool for ProcessStatusTool {
                         proc.id,
                         proc.pid.map(|p| format!("PID {}", p)).unwrap_or_else(|| "no PID".to_string()),
                         proc.status,
-                        if proc.command.len() > 50 {
-                            format!("{}...", &proc.command[..47])
-                        } else {
-                            proc.command.clone()
-                        },
+                        truncate_chars(&proc.command, 50),
                         proc.duration_ms
                     ));
                 }
@@ -1,6 +1,8 @@
 //! QMD Memory Search Tool
 //!
 //! Full-text search across memory markdown files using FTS5 BM25 ranking.
+//! If the index is unavailable, falls back to a keyword scan of the files so
+//! the agent still gets best-effort results instead of an error.
 //! In safe mode, results are sandboxed to the safemode/ memory directory only.
 
 use crate::tools::registry::Tool;
@@ -96,16 +98,6 @@ impl Tool for QmdMemorySearchTool {
             return ToolResult::error("Query cannot be empty");
         }
 
-        // Get memory store from context
-        let memory_store = match &context.memory_store {
-            Some(store) => store,
-            None => {
-                return ToolResult::error(
-                    "Memory store not available. Memory search requires the memory system to be initialized.",
-                );
-            }
-        };
-
         let safe_mode = is_safe_mode(context);
         // In safe mode, request more results so we have enough after filtering
         let search_limit = if safe_mode {
@@ -115,50 +107,63 @@ impl Tool for QmdMemorySearchTool {
         };
         let result_limit = params.limit.unwrap_or(10).min(50).max(1);
 
-        // Perform search
-        match memory_store.search(&params.query, search_limit) {
-            Ok(results) => {
-                // In safe mode, filter to only safemode/ directory files
-                let results: Vec<_> = if safe_mode {
-                    results.into_iter()
-                        .filter(|r| r.file_path.starts_with("safemode/"))
-                        .take(result_limit as usize)
-                        .collect()
-                } else {
-                    results.into_iter().take(result_limit as usize).collect()
-                };
-
-                if results.is_empty() {
-                    return ToolResult::success(format!(
-                        "No memories found matching: \"{}\"",
-                        params.query
-                    ));
-                }
-
-                let mut output = format!(
-                    "## Memory Search Results\n**Query:** \"{}\"\n**Found:** {} result(s)\n\n",
-                    params.query,
-                    results.len()
-                );
-
-                for (i, result) in results.iter().enumerate() {
-                    output.push_str(&format!(
-                        "### {}. {}\n**Score:** {:.2}\n{}\n\n",
-                        i + 1,
-                        result.file_path,
-                        -result.score, // Negate because BM25 returns negative scores
-                        result.snippet.replace(">>>", "**").replace("<<<", "**")
-                    ));
-                }
-
-                ToolResult::success(output).with_metadata(json!({
-                    "query": params.query,
-                    "result_count": results.len(),
-                    "files": results.iter().map(|r| r.file_path.clone()).collect::<Vec<_>>()
-                }))
+        // Perform search (falls back to keyword matching if the index fails)
+        let (results, degraded) = match &context.memory_store {
+            Some(store) => store.search_with_fallback(&params.query, search_limit),
+            None => {
+                log::warn!("[memory_search] Memory store not available, using keyword search over memory files");
+                let memory_dir = std::path::PathBuf::from(crate::config::memory_config().memory_dir);
+                let results = crate::qmd_memory::keyword_search_files(&memory_dir, &params.query, search_limit)
+                    .unwrap_or_default();
+                (results, true)
             }
-            Err(e) => ToolResult::error(format!("Search failed: {}", e)),
+        };
+        let degraded_note = if degraded {
+            "_Note: the memory index is unavailable, so these are keyword matches and may be incomplete._\n\n"
+        } else {
+            ""
+        };
+
+        // In safe mode, filter to only safemode/ directory files
+        let results: Vec<_> = if safe_mode {
+            results.into_iter()
+                .filter(|r| r.file_path.starts_with("safemode/"))
+                .take(result_limit as usize)
+                .collect()
+        } else {
+            results.into_iter().take(result_limit as usize).collect()
+        };
+
+        if results.is_empty() {
+            return ToolResult::success(format!(
+                "{}No memories found matching: \"{}\"",
+                degraded_note, params.query
+            ));
+        }
+
+        let mut output = format!(
+            "## Memory Search Results\n**Query:** \"{}\"\n**Found:** {} result(s)\n\n{}",
+            params.query,
+            results.len(),
+            degraded_note
+        );
+
+        for (i, result) in results.iter().enumerate() {
+            output.push_str(&format!(
+                "### {}. {}\n**Score:** {:.2}\n{}\n\n",
+                i + 1,
+                result.file_path,
+                -result.score, // Negate because BM25 returns negative scores
+                result.snippet.replace(">>>", "**").replace("<<<", "**")
+            ));
         }
+
+        ToolResult::success(output).with_metadata(json!({
+            "query": params.query,
+            "result_count": results.len(),
+            "degraded": degraded,
+            "files": results.iter().map(|r| r.file_path.clone()).collect::<Vec<_>>()
+        }))
     }
 
     fn safety_level(&self) -> ToolSafetyLevel {
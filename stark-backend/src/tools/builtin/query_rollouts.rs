@@ -0,0 +1,242 @@
+//! Query Rollouts Tool
+//!
+//! Lists recent finished rollouts (one per dispatched request) for the current
+//! session or channel with their status, attempt count, final response or
+//! error, and session reward, so an agent can look at how earlier runs went
+//! before planning. Not available in safe mode.
+
+use crate::telemetry::{RolloutQuery, TelemetryStore};
+use crate::text::truncate_chars;
+use crate::tools::registry::Tool;
+use crate::tools::types::{
+    PropertySchema, ToolContext, ToolDefinition, ToolGroup, ToolInputSchema, ToolResult,
+};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Default and maximum number of rollouts returned
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 25;
+
+/// Longest final response/error excerpt shown per rollout
+const MAX_SUMMARY_CHARS: usize = 300;
+
+/// Tool for reviewing past rollouts and their outcomes
+pub struct QueryRolloutsTool {
+    definition: ToolDefinition,
+}
+
+impl QueryRolloutsTool {
+    pub fn new() -> Self {
+        let mut properties = HashMap::new();
+
+        properties.insert(
+            "scope".to_string(),
+            PropertySchema {
+                schema_type: "string".to_string(),
+                description: "'session' for runs in this session (default), 'channel' for runs across the whole channel.".to_string(),
+                default: Some(json!("session")),
+                items: None,
+                enum_values: Some(vec!["session".to_string(), "channel".to_string()]),
+            },
+        );
+        properties.insert(
+            "outcome".to_string(),
+            PropertySchema {
+                schema_type: "string".to_string(),
+                description: "Only return runs that 'succeeded' or 'failed' (failed includes cancelled). Omit for both.".to_string(),
+                default: None,
+                items: None,
+                enum_values: Some(vec!["succeeded".to_string(), "failed".to_string()]),
+            },
+        );
+        properties.insert(
+            "limit".to_string(),
+            PropertySchema {
+                schema_type: "integer".to_string(),
+                description: format!("Maximum number of runs to return (default: {}, max: {}).", DEFAULT_LIMIT, MAX_LIMIT),
+                default: Some(json!(DEFAULT_LIMIT)),
+                items: None,
+                enum_values: None,
+            },
+        );
+
+        Self {
+            definition: ToolDefinition {
+                name: "query_rollouts".to_string(),
+                description: "Review your recent runs (one per request you handled), newest first: status, number of attempts, final response or error, and reward. Use this before planning to learn from what went wrong (or right) last time.".to_string(),
+                input_schema: ToolInputSchema {
+                    schema_type: "object".to_string(),
+                    properties,
+                    required: vec![],
+                },
+                group: ToolGroup::Memory,
+                hidden: false,
+            },
+        }
+    }
+}
+
+impl Default for QueryRolloutsTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct QueryRolloutsParams {
+    scope: Option<String>,
+    outcome: Option<String>,
+    limit: Option<usize>,
+}
+
+#[async_trait]
+impl Tool for QueryRolloutsTool {
+    fn definition(&self) -> ToolDefinition {
+        self.definition.clone()
+    }
+
+    async fn execute(&self, params: Value, context: &ToolContext) -> ToolResult {
+        let params: QueryRolloutsParams = match serde_json::from_value(params) {
+            Ok(p) => p,
+            Err(e) => return ToolResult::error(format!("Invalid parameters: {}", e)),
+        };
+
+        let db = match &context.database {
+            Some(db) => db,
+            None => return ToolResult::error("Database not available"),
+        };
+
+        let mut query = RolloutQuery {
+            limit: params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+            ..RolloutQuery::default()
+        };
+        match params.scope.as_deref().unwrap_or("session") {
+            "session" => match context.session_id {
+                Some(id) => query.session_id = Some(id),
+                None => return ToolResult::error("No session for this conversation; use scope 'channel'"),
+            },
+            "channel" => match context.channel_id {
+                Some(id) => query.channel_id = Some(id),
+                None => return ToolResult::error("No channel for this conversation"),
+            },
+            other => return ToolResult::error(format!("Invalid scope '{}': use 'session' or 'channel'", other)),
+        }
+        query.succeeded = match params.outcome.as_deref() {
+            None => None,
+            Some("succeeded") => Some(true),
+            Some("failed") => Some(false),
+            Some(other) => return ToolResult::error(format!("Invalid outcome '{}': use 'succeeded' or 'failed'", other)),
+        };
+
+        let mut rollouts = TelemetryStore::new(Arc::clone(db)).recent_rollouts(&query);
+        if rollouts.is_empty() {
+            return ToolResult::success("No finished runs match.").with_metadata(json!({ "count": 0 }));
+        }
+        // Metadata is returned too, so it gets the same excerpts as the text
+        for rollout in &mut rollouts {
+            rollout.summary = rollout.summary.as_deref().map(|s| truncate_chars(s, MAX_SUMMARY_CHARS));
+        }
+
+        let lines: Vec<String> = rollouts
+            .iter()
+            .map(|r| {
+                let reward = r.reward.map(|v| format!(", reward {:.2}", v)).unwrap_or_default();
+                let duration = r.duration_ms.map(|ms| format!(", {:.1}s", ms as f64 / 1000.0)).unwrap_or_default();
+                let summary = r.summary.as_deref().unwrap_or("(no result recorded)");
+                format!(
+                    "[{} | session {}] {} after {} attempt{}{}{}\n  {}",
+                    r.created_at.format("%Y-%m-%d %H:%M UTC"),
+                    r.session_id,
+                    r.status,
+                    r.attempts,
+                    if r.attempts == 1 { "" } else { "s" },
+                    duration,
+                    reward,
+                    summary
+                )
+            })
+            .collect();
+
+        ToolResult::success(format!(
+            "Recent runs ({}, newest first):\n\n{}",
+            rollouts.len(),
+            lines.join("\n\n")
+        ))
+        .with_metadata(json!({ "count": rollouts.len(), "rollouts": rollouts }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::telemetry::{RewardEmitter, RolloutConfig, RolloutManager};
+
+    fn run(manager: &RolloutManager, store: &TelemetryStore, session_id: i64, outcome: Result<&str, &str>) {
+        let config = RolloutConfig { max_attempts: 1, ..RolloutConfig::default() };
+        let (mut rollout, collector) = manager.start_rollout(0, 7, config);
+        manager.set_session(&mut rollout, session_id);
+        collector.set_session(session_id);
+        let collector = Arc::new(collector);
+        match outcome {
+            Ok(result) => {
+                RewardEmitter::new(Arc::clone(&collector)).session_completed(true, 2, 1, 10);
+                manager.succeed_rollout(&mut rollout, result.to_string());
+            }
+            Err(error) => {
+                manager.fail_attempt(&mut rollout, error, &collector);
+            }
+        }
+        store.persist_spans(&collector);
+    }
+
+    #[tokio::test]
+    async fn test_lists_finished_rollouts_with_filters() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let manager = RolloutManager::new(db.clone());
+        let store = TelemetryStore::new(db.clone());
+        run(&manager, &store, 1, Ok("Swapped 10 USDC for ETH."));
+        run(&manager, &store, 1, Err("RPC timeout while fetching quote"));
+        run(&manager, &store, 2, Ok("Other session"));
+        // Still running: not a past run
+        manager.start_rollout(1, 7, RolloutConfig::default());
+
+        let tool = QueryRolloutsTool::new();
+        let context = ToolContext::new().with_database(db.clone()).with_session(1).with_channel(7, "web".to_string());
+
+        let result = tool.execute(json!({}), &context).await;
+        assert!(result.success, "{}", result.content);
+        assert_eq!(result.metadata.as_ref().unwrap()["count"], 2);
+        assert!(result.content.contains("failed after 1 attempt"));
+        assert!(result.content.contains("RPC timeout"));
+        assert!(result.content.contains("succeeded after 1 attempt"));
+        assert!(result.content.contains("reward 2.80"));
+
+        let result = tool.execute(json!({"outcome": "failed"}), &context).await;
+        assert_eq!(result.metadata.as_ref().unwrap()["count"], 1);
+        assert!(!result.content.contains("Swapped"));
+
+        let result = tool.execute(json!({"scope": "channel", "limit": 100}), &context).await;
+        assert_eq!(result.metadata.as_ref().unwrap()["count"], 3);
+
+        // Long results are excerpted in the metadata as well as the text
+        run(&manager, &store, 1, Ok(&"x".repeat(5_000)));
+        let result = tool.execute(json!({}), &context).await;
+        let rollouts = result.metadata.as_ref().unwrap()["rollouts"].as_array().unwrap().clone();
+        assert_eq!(rollouts.len(), 3);
+        assert!(rollouts.iter().all(|r| r["summary"].as_str().unwrap().chars().count() <= MAX_SUMMARY_CHARS));
+
+        let result = tool.execute(json!({"outcome": "maybe"}), &context).await;
+        assert!(!result.success);
+    }
+
+    #[test]
+    fn test_not_allowed_in_safe_mode() {
+        assert!(!crate::tools::types::SAFE_MODE_ALLOW_LIST.contains(&"query_rollouts"));
+        assert_ne!(QueryRolloutsTool::new().definition().group, ToolGroup::Web);
+    }
+}
@@ -0,0 +1,175 @@
+//! Recall Recent Messages Tool
+//!
+//! Reads the latest messages the current user exchanged with the agent across
+//! all of their sessions and channels, for "earlier you told me..." recall
+//! that the per-session history can't answer. Safe-mode, cron and heartbeat
+//! sessions are never included (see `Database::get_recent_identity_messages`).
+
+use crate::models::session_message::MessageRole;
+use crate::text::truncate_chars;
+use crate::tools::registry::Tool;
+use crate::tools::types::{
+    PropertySchema, ToolContext, ToolDefinition, ToolGroup, ToolInputSchema, ToolResult,
+};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Longest message excerpt shown per line
+const MAX_MESSAGE_CHARS: usize = 400;
+
+/// Tool for reading recent messages across the user's sessions
+pub struct RecallRecentMessagesTool {
+    definition: ToolDefinition,
+}
+
+impl RecallRecentMessagesTool {
+    pub fn new() -> Self {
+        let mut properties = HashMap::new();
+
+        properties.insert(
+            "limit".to_string(),
+            PropertySchema {
+                schema_type: "integer".to_string(),
+                description: "Maximum number of messages to return (default: 20, max: 100).".to_string(),
+                default: Some(json!(20)),
+                items: None,
+                enum_values: None,
+            },
+        );
+
+        Self {
+            definition: ToolDefinition {
+                name: "recall_recent_messages".to_string(),
+                description: "Read the most recent messages between you and the current user across ALL of their sessions and channels, newest first. Use this when the user refers to an earlier conversation (\"earlier you told me...\", \"what did we discuss yesterday?\") that is not in the current session.".to_string(),
+                input_schema: ToolInputSchema {
+                    schema_type: "object".to_string(),
+                    properties,
+                    required: vec![],
+                },
+                group: ToolGroup::Memory,
+                hidden: false,
+            },
+        }
+    }
+}
+
+impl Default for RecallRecentMessagesTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RecallParams {
+    limit: Option<i32>,
+}
+
+#[async_trait]
+impl Tool for RecallRecentMessagesTool {
+    fn definition(&self) -> ToolDefinition {
+        self.definition.clone()
+    }
+
+    async fn execute(&self, params: Value, context: &ToolContext) -> ToolResult {
+        let params: RecallParams = match serde_json::from_value(params) {
+            Ok(p) => p,
+            Err(e) => return ToolResult::error(format!("Invalid parameters: {}", e)),
+        };
+
+        let db = match &context.database {
+            Some(db) => db,
+            None => return ToolResult::error("Database not available"),
+        };
+        let identity_id = match &context.identity_id {
+            Some(id) => id,
+            None => return ToolResult::error("No user identity for this conversation, so there is no history to recall"),
+        };
+
+        let limit = params.limit.unwrap_or(20).clamp(1, 100);
+        let messages = match db.get_recent_identity_messages(identity_id, limit) {
+            Ok(m) => m,
+            Err(e) => return ToolResult::error(format!("Failed to read recent messages: {}", e)),
+        };
+        if messages.is_empty() {
+            return ToolResult::success("No earlier messages found for this user.")
+                .with_metadata(json!({ "count": 0 }));
+        }
+
+        let lines: Vec<String> = messages
+            .iter()
+            .map(|m| {
+                let speaker = match m.role {
+                    MessageRole::Assistant => "You".to_string(),
+                    _ => m.user_name.clone().unwrap_or_else(|| "User".to_string()),
+                };
+                let here = if Some(m.session_id) == context.session_id { ", this session" } else { "" };
+                format!(
+                    "[{} | session {}{}] {}: {}",
+                    m.created_at.format("%Y-%m-%d %H:%M UTC"),
+                    m.session_id,
+                    here,
+                    speaker,
+                    truncate_chars(&m.content, MAX_MESSAGE_CHARS)
+                )
+            })
+            .collect();
+
+        ToolResult::success(format!(
+            "Recent messages with this user ({}, newest first):\n\n{}",
+            messages.len(),
+            lines.join("\n")
+        ))
+        .with_metadata(json!({ "count": messages.len() }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::models::SessionScope;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_messages_from_two_sessions_returned_newest_first() {
+        let db = Database::new(":memory:").unwrap();
+        let identity = db.get_or_create_identity("discord", "u-1", Some("alice")).unwrap();
+        db.link_identity(&identity.identity_id, "telegram", "t-1", Some("alice")).unwrap();
+
+        let discord = db.get_or_create_chat_session("discord", 1, "dm-1", SessionScope::Dm, None).unwrap();
+        let telegram = db.get_or_create_chat_session("telegram", 2, "dm-2", SessionScope::Dm, None).unwrap();
+        let stranger = db.get_or_create_chat_session("discord", 1, "dm-3", SessionScope::Dm, None).unwrap();
+        let safe = db.get_or_create_chat_session("telegram", 2, "dm-4", SessionScope::Dm, None).unwrap();
+        db.set_session_safe_mode(safe.id).unwrap();
+
+        let add = |session: i64, role: MessageRole, text: &str, user: Option<&str>| {
+            db.add_session_message(session, role, text, user, None, None, None).unwrap();
+        };
+        add(discord.id, MessageRole::User, "my favourite chain is Base", Some("u-1"));
+        add(discord.id, MessageRole::Assistant, "Noted, Base it is.", None);
+        add(stranger.id, MessageRole::User, "someone else's secret", Some("u-9"));
+        add(safe.id, MessageRole::User, "said in safe mode", Some("t-1"));
+        add(telegram.id, MessageRole::User, "what did I say my favourite chain was?", Some("t-1"));
+
+        let messages = db.get_recent_identity_messages(&identity.identity_id, 10).unwrap();
+        let contents: Vec<&str> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            vec!["what did I say my favourite chain was?", "Noted, Base it is.", "my favourite chain is Base"]
+        );
+        assert_eq!(messages[0].session_id, telegram.id);
+        assert_eq!(messages[2].session_id, discord.id);
+        assert_eq!(db.get_recent_identity_messages(&identity.identity_id, 1).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tool_requires_identity() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let tool = RecallRecentMessagesTool::new();
+        let context = ToolContext::new().with_database(db);
+        let result = tool.execute(json!({}), &context).await;
+        assert!(!result.success);
+    }
+}
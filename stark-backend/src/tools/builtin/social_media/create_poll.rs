@@ -0,0 +1,320 @@
+//! Poll tool - posts an interactive poll to the current chat
+//!
+//! Discord and Telegram get one button per option; Slack gets a numbered list
+//! voted on with reactions. Votes are collected for `duration_minutes`, then
+//! the scheduler closes the poll and sends the aggregated results back to this
+//! session as a follow-up message.
+
+use crate::channels::delivery;
+use crate::channels::polls::{self, MAX_POLL_OPTIONS, POLL_MODE_BUTTONS};
+use crate::db::tables::polls::{Poll, POLL_STATUS_OPEN};
+use crate::tools::registry::Tool;
+use crate::tools::types::{
+    PropertySchema, ToolContext, ToolDefinition, ToolGroup, ToolInputSchema, ToolResult,
+};
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+const DEFAULT_POLL_MINUTES: i64 = 60;
+/// Longest allowed voting window (one week)
+const MAX_POLL_MINUTES: i64 = 7 * 24 * 60;
+
+pub struct CreatePollTool {
+    definition: ToolDefinition,
+}
+
+impl CreatePollTool {
+    pub fn new() -> Self {
+        let mut properties = HashMap::new();
+
+        properties.insert(
+            "question".to_string(),
+            PropertySchema {
+                schema_type: "string".to_string(),
+                description: "The poll question".to_string(),
+                default: None,
+                items: None,
+                enum_values: None,
+            },
+        );
+
+        properties.insert(
+            "options".to_string(),
+            PropertySchema {
+                schema_type: "array".to_string(),
+                description: format!("Answer options (2-{})", MAX_POLL_OPTIONS),
+                default: None,
+                items: Some(Box::new(PropertySchema {
+                    schema_type: "string".to_string(),
+                    description: "Answer option".to_string(),
+                    default: None,
+                    items: None,
+                    enum_values: None,
+                })),
+                enum_values: None,
+            },
+        );
+
+        properties.insert(
+            "duration_minutes".to_string(),
+            PropertySchema {
+                schema_type: "integer".to_string(),
+                description: format!(
+                    "How long to collect votes, in minutes (default {}, max {})",
+                    DEFAULT_POLL_MINUTES, MAX_POLL_MINUTES
+                ),
+                default: Some(json!(DEFAULT_POLL_MINUTES)),
+                items: None,
+                enum_values: None,
+            },
+        );
+
+        properties.insert(
+            "chat_id".to_string(),
+            PropertySchema {
+                schema_type: "string".to_string(),
+                description: "Chat to post in (Discord channel ID, Telegram chat ID, Slack channel ID). Defaults to the current chat.".to_string(),
+                default: None,
+                items: None,
+                enum_values: None,
+            },
+        );
+
+        CreatePollTool {
+            definition: ToolDefinition {
+                name: "create_poll".to_string(),
+                description: "Post a poll to the current Discord, Telegram or Slack chat. Votes are collected for the given duration; when the poll closes you receive a [Poll Results] follow-up with the tally. Returns the poll_id.".to_string(),
+                input_schema: ToolInputSchema {
+                    schema_type: "object".to_string(),
+                    properties,
+                    required: vec!["question".to_string(), "options".to_string()],
+                },
+                group: ToolGroup::Messaging,
+                hidden: false,
+            },
+        }
+    }
+}
+
+impl Default for CreatePollTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CreatePollParams {
+    question: String,
+    options: Vec<String>,
+    duration_minutes: Option<i64>,
+    chat_id: Option<String>,
+}
+
+#[async_trait]
+impl Tool for CreatePollTool {
+    fn definition(&self) -> ToolDefinition {
+        self.definition.clone()
+    }
+
+    async fn execute(&self, params: Value, context: &ToolContext) -> ToolResult {
+        let params: CreatePollParams = match serde_json::from_value(params) {
+            Ok(p) => p,
+            Err(e) => return ToolResult::error(format!("Invalid parameters: {}", e)),
+        };
+
+        let question = params.question.trim().to_string();
+        let options: Vec<String> = params
+            .options
+            .iter()
+            .map(|o| o.trim().to_string())
+            .filter(|o| !o.is_empty())
+            .collect();
+        if question.is_empty() {
+            return ToolResult::error("'question' is required");
+        }
+        if options.len() < 2 || options.len() > MAX_POLL_OPTIONS {
+            return ToolResult::error(format!("A poll needs between 2 and {} options", MAX_POLL_OPTIONS));
+        }
+
+        let db = match &context.database {
+            Some(db) => db.clone(),
+            None => return ToolResult::error("Database not available"),
+        };
+        let (channel_id, channel_type) = match (context.channel_id, context.channel_type.as_deref()) {
+            (Some(id), Some(ct)) => (id, ct.to_string()),
+            _ => return ToolResult::error("create_poll must be run from a channel"),
+        };
+        let mode = match polls::poll_mode_for_channel(&channel_type) {
+            Some(mode) => mode,
+            None => {
+                return ToolResult::error(format!(
+                    "Polls are not supported on {} channels. Supported: discord, telegram, slack",
+                    channel_type
+                ))
+            }
+        };
+        let chat_id = match params.chat_id.or_else(|| context.platform_chat_id.clone()) {
+            Some(c) => c,
+            None => return ToolResult::error("No chat to post the poll in. Provide 'chat_id'."),
+        };
+        if let Err(e) = delivery::validate_chat_id(&channel_type, &chat_id) {
+            return ToolResult::error(e);
+        }
+
+        let minutes = params
+            .duration_minutes
+            .unwrap_or(DEFAULT_POLL_MINUTES)
+            .clamp(1, MAX_POLL_MINUTES);
+        let now = Utc::now();
+        let poll = Poll {
+            poll_id: uuid::Uuid::new_v4().simple().to_string(),
+            channel_id,
+            channel_type: channel_type.clone(),
+            chat_id: chat_id.clone(),
+            session_id: context.session_id,
+            question,
+            options,
+            mode: mode.to_string(),
+            platform_message_id: None,
+            status: POLL_STATUS_OPEN.to_string(),
+            closes_at: now + Duration::minutes(minutes),
+            created_at: now.to_rfc3339(),
+        };
+
+        // Store first: button payloads reference the poll_id, and votes may
+        // arrive as soon as the message is visible
+        if let Err(e) = db.create_poll(&poll) {
+            return ToolResult::error(format!("Failed to store poll: {}", e));
+        }
+
+        let posted = match channel_type.as_str() {
+            "discord" => post_discord(&poll, context).await,
+            "telegram" => post_telegram(&poll, context).await,
+            _ => post_slack(&poll, context).await,
+        };
+        let message_id = match posted {
+            Ok(id) => id,
+            Err(e) => {
+                let _ = db.delete_poll(&poll.poll_id);
+                return ToolResult::error(e);
+            }
+        };
+        if let Err(e) = db.set_poll_message_id(&poll.poll_id, &message_id) {
+            log::warn!("[POLL] Failed to record message id for poll {}: {}", poll.poll_id, e);
+        }
+
+        log::info!(
+            "[POLL] Created poll {} in {} chat {} ({} options, closes {})",
+            poll.poll_id, channel_type, chat_id, poll.options.len(), poll.closes_at
+        );
+
+        let how = if mode == POLL_MODE_BUTTONS { "buttons" } else { "reactions" };
+        ToolResult::success(format!(
+            "Poll {} posted with {} options (voting by {}). It closes in {} minute(s); the results will arrive as a [Poll Results] follow-up.",
+            poll.poll_id, poll.options.len(), how, minutes
+        ))
+        .with_metadata(json!({
+            "poll_id": poll.poll_id,
+            "mode": mode,
+            "closes_at": poll.closes_at,
+            "message_id": message_id,
+        }))
+    }
+}
+
+/// Post a poll with one button per option. Returns the Discord message ID.
+async fn post_discord(poll: &Poll, context: &ToolContext) -> Result<String, String> {
+    let bot_token = context
+        .find_channel_bot_token("discord", "discord_bot_token")
+        .ok_or("Discord bot token not available. Configure it in your Discord channel settings.")?;
+    let url = format!("https://discord.com/api/v10/channels/{}/messages", poll.chat_id);
+    let body = json!({
+        "content": polls::poll_text(poll),
+        "components": polls::discord_components(poll),
+    });
+
+    let response = context
+        .http_client()
+        .post(&url)
+        .header("Authorization", format!("Bot {}", bot_token))
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to post Discord poll: {}", e))?;
+    let status = response.status();
+    let body_text = response.text().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(format!("Discord API error ({}): {}", status, body_text));
+    }
+    serde_json::from_str::<Value>(&body_text)
+        .ok()
+        .and_then(|v| v["id"].as_str().map(String::from))
+        .ok_or_else(|| "Discord response missing message id".to_string())
+}
+
+/// Post a poll with an inline keyboard. Returns the Telegram message ID.
+async fn post_telegram(poll: &Poll, context: &ToolContext) -> Result<String, String> {
+    let bot_token = context
+        .find_channel_bot_token("telegram", "telegram_bot_token")
+        .ok_or("Telegram bot token not available. Configure it in your Telegram channel settings.")?;
+    let url = format!("https://api.telegram.org/bot{}/sendMessage", bot_token);
+    let body = json!({
+        "chat_id": poll.chat_id,
+        "text": polls::poll_text(poll),
+        "reply_markup": polls::telegram_inline_keyboard(poll),
+    });
+
+    let response = context
+        .http_client()
+        .post(&url)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to post Telegram poll: {}", e))?;
+    let status = response.status();
+    let body_text = response.text().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(format!("Telegram API error ({}): {}", status, body_text));
+    }
+    serde_json::from_str::<Value>(&body_text)
+        .ok()
+        .and_then(|v| v["result"]["message_id"].as_i64())
+        .map(|id| id.to_string())
+        .ok_or_else(|| "Telegram response missing message_id".to_string())
+}
+
+/// Post a numbered poll voted on with reactions. Returns the Slack message timestamp.
+async fn post_slack(poll: &Poll, context: &ToolContext) -> Result<String, String> {
+    let bot_token = context
+        .find_channel_bot_token("slack", "slack_bot_token")
+        .ok_or("Slack bot token not available. Configure it in your Slack channel settings.")?;
+    let body = json!({
+        "channel": poll.chat_id,
+        "text": polls::poll_text(poll),
+    });
+
+    let response = context
+        .http_client()
+        .post("https://slack.com/api/chat.postMessage")
+        .header("Authorization", format!("Bearer {}", bot_token))
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to post Slack poll: {}", e))?;
+    let json: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid Slack response: {}", e))?;
+    // Slack returns 200 even on errors, check the response body
+    if !json.get("ok").and_then(|v| v.as_bool()).unwrap_or(false) {
+        let error = json.get("error").and_then(|v| v.as_str()).unwrap_or("unknown error");
+        return Err(format!("Slack API error: {}", error));
+    }
+    json["ts"]
+        .as_str()
+        .map(String::from)
+        .ok_or_else(|| "Slack response missing ts".to_string())
+}
@@ -1,3 +1,4 @@
+use crate::text::truncate_chars;
 use crate::tools::registry::Tool;
 use crate::tools::types::{
     PropertySchema, ToolContext, ToolDefinition, ToolGroup, ToolInputSchema, ToolResult,
@@ -373,11 +374,7 @@ impl DiscordReadTool {
         let summary: Vec<String> = messages.iter().map(|m| {
             let author_name = m.author.global_name.as_ref().unwrap_or(&m.author.username);
             let bot_tag = if m.author.bot { " [BOT]" } else { "" };
-            let content_preview = if m.content.len() > 100 {
-                format!("{}...", &m.content[..100])
-            } else {
-                m.content.clone()
-            };
+            let content_preview = truncate_chars(&m.content, 100);
             let attachments = if !m.attachments.is_empty() {
                 format!(" [+{} attachment(s)]", m.attachments.len())
             } else {
@@ -465,7 +462,7 @@ impl DiscordReadTool {
                         .unwrap_or("?");
                     let ch_id = m.get("channel_id").and_then(|v| v.as_str()).unwrap_or("?");
                     format!("[{}] #{} - {}: {}", id, ch_id, author,
-                        if msg_content.len() > 80 { format!("{}...", &msg_content[..80]) } else { msg_content.to_string() })
+                        truncate_chars(msg_content, 80))
                 })
                 .collect::<Vec<_>>()
                 .join("\n")
@@ -8,6 +8,7 @@
 //! - Getting design variables and variable collections
 //! - Listing team projects and project files
 
+use crate::text::truncate_chars;
 use crate::tools::registry::Tool;
 use crate::tools::types::{
     PropertySchema, ToolContext, ToolDefinition, ToolGroup, ToolInputSchema, ToolResult,
@@ -190,7 +191,7 @@ EXAMPLES:
         let status = resp.status();
         if !status.is_success() {
             let body = resp.text().await.unwrap_or_default();
-            return Err(format!("Figma API error {}: {}", status, truncate(&body, 500)));
+            return Err(format!("Figma API error {}: {}", status, truncate_chars(&body, 500)));
         }
 
         resp.json::<Value>()
@@ -217,14 +218,6 @@ struct Params {
     depth: Option<String>,
 }
 
-fn truncate(s: &str, max: usize) -> String {
-    if s.len() > max {
-        format!("{}...", &s[..max])
-    } else {
-        s.to_string()
-    }
-}
-
 /// Recursively summarize a Figma document tree to avoid overwhelming output
 fn summarize_node(node: &Value, depth: usize, max_depth: usize) -> String {
     let name = node["name"].as_str().unwrap_or("(unnamed)");
@@ -261,7 +254,7 @@ fn summarize_node(node: &Value, depth: usize, max_depth: usize) -> String {
 
     if let Some(chars) = node.get("characters") {
         if let Some(text) = chars.as_str() {
-            let preview = truncate(text, 60);
+            let preview = truncate_chars(text, 60);
             line.push_str(&format!(" text=\"{}\"", preview));
         }
     }
@@ -347,7 +340,7 @@ impl Tool for FigmaTool {
                             out.push_str(&summarize_node(document, 0, depth + 1));
                         }
 
-                        ToolResult::success(truncate(&out, 30000)).with_metadata(json!({
+                        ToolResult::success(truncate_chars(&out, 30000)).with_metadata(json!({
                             "file_key": file_key,
                             "name": file_name,
                             "last_modified": last_modified,
@@ -373,7 +366,7 @@ impl Tool for FigmaTool {
                     Ok(data) => {
                         let nodes = data.get("nodes").cloned().unwrap_or(json!({}));
                         let pretty = serde_json::to_string_pretty(&nodes).unwrap_or_default();
-                        ToolResult::success(truncate(&pretty, 30000)).with_metadata(json!({
+                        ToolResult::success(truncate_chars(&pretty, 30000)).with_metadata(json!({
                             "file_key": file_key,
                             "node_ids": node_ids,
                         }))
@@ -577,7 +570,7 @@ impl Tool for FigmaTool {
                             out = "No variables found in this file.".to_string();
                         }
 
-                        ToolResult::success(truncate(&out, 30000)).with_metadata(json!({"file_key": file_key}))
+                        ToolResult::success(truncate_chars(&out, 30000)).with_metadata(json!({"file_key": file_key}))
                     }
                     Err(e) => ToolResult::error(e),
                 }
@@ -2,6 +2,7 @@
 //!
 //! Tools for interacting with Twitter, Discord, GitHub, and other platforms.
 
+mod create_poll;
 mod discord_lookup;
 mod discord_read;
 mod discord_write;
@@ -12,6 +13,7 @@ mod telegram_read;
 mod twitter_post;
 pub mod twitter_oauth;
 
+pub use create_poll::CreatePollTool;
 pub use discord_lookup::DiscordLookupTool;
 pub use figma::FigmaTool;
 pub use discord_read::DiscordReadTool;
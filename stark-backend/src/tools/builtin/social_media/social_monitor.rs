@@ -3,6 +3,7 @@
 //! These tools are only registered when the social_monitor module is installed.
 //! All operations go through the social-monitor-service via RPC.
 
+use crate::text::truncate_chars;
 use crate::integrations::social_monitor_client::SocialMonitorClient;
 use crate::tools::registry::Tool;
 use crate::tools::types::{
@@ -541,11 +542,7 @@ fn format_tweet_list(
 
     let mut output = format!("**{}** ({} entries)\n\n", title, entries.len());
     for t in entries {
-        let text_short = if t.text.len() > 120 {
-            format!("{}...", &t.text[..120])
-        } else {
-            t.text.clone()
-        };
+        let text_short = truncate_chars(&t.text, 120);
         let engagement = t.like_count + t.retweet_count * 2 + t.reply_count;
         output.push_str(&format!(
             "[{}] {} | {} | eng:{} | {}\n",
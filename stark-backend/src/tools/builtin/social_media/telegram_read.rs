@@ -1,3 +1,4 @@
+use crate::text::truncate_chars;
 use crate::tools::registry::Tool;
 use crate::tools::types::{
     PropertySchema, ToolContext, ToolDefinition, ToolGroup, ToolInputSchema, ToolResult,
@@ -229,7 +230,7 @@ impl TelegramReadTool {
             chat_type,
             username.unwrap_or("N/A"),
             description,
-            if pinned.len() > 100 { format!("{}...", &pinned[..100]) } else { pinned.to_string() }
+            truncate_chars(pinned, 100)
         );
 
         context.set_register("telegram_chat_id", json!(chat_id), "telegram_read");
@@ -455,11 +456,7 @@ impl TelegramReadTool {
             let who = m.user_name.as_deref()
                 .unwrap_or(m.user_id.as_deref().unwrap_or("unknown"));
             let tag = if m.is_bot_response { " [bot]" } else { "" };
-            let content_preview = if m.content.len() > 200 {
-                format!("{}...", &m.content[..200])
-            } else {
-                m.content.clone()
-            };
+            let content_preview = truncate_chars(&m.content, 200);
             format!("[{}] {}{}: {}", m.created_at, who, tag, content_preview)
         }).collect();
 
@@ -1,3 +1,4 @@
+use crate::text::truncate_chars;
 use crate::tools::http_retry::{is_reqwest_error_retryable, HttpRetryManager};
 use crate::tools::registry::Tool;
 use crate::tools::types::{
@@ -429,7 +430,7 @@ impl Tool for WebFetchTool {
                 return ToolResult::error(format!(
                     "HTTP 402 Payment Required for {} (x402 auto-payment disabled in safe mode)\n\nResponse:\n{}",
                     params.url,
-                    if body.len() > 2000 { format!("{}...", &body[..2000]) } else { body }
+                    truncate_chars(&body, 2000)
                 ));
             }
             if let Some(ref wallet_provider) = context.wallet_provider {
@@ -488,11 +489,13 @@ impl Tool for WebFetchTool {
                         let retry_status = result.response.status();
                         if !retry_status.is_success() {
                             let retry_body = result.response.text().await.unwrap_or_default();
+                            // The payment went through even though the request failed
                             return ToolResult::error(format!(
                                 "HTTP {} (after x402 payment): {}",
                                 retry_status,
-                                if retry_body.len() > 2000 { format!("{}...", &retry_body[..2000]) } else { retry_body }
-                            ));
+                                truncate_chars(&retry_body, 2000)
+                            ))
+                            .with_metadata(serde_json::json!({ "x402_payment": payment_info }));
                         }
                         retry_manager.record_success(&retry_key);
                         let content_type = result.response
@@ -513,25 +516,29 @@ impl Tool for WebFetchTool {
                             "markdown" if is_html => extract_markdown_from_html(&body),
                             _ => body,
                         };
-                        let truncated = content.len() > max_chars;
+                        let content_len = content.chars().count();
+                        let truncated = content_len > max_chars;
                         let final_content = if truncated {
-                            format!("{}...\n\n(truncated, {} chars total)", &content[..max_chars], content.len())
+                            format!("{}\n\n(truncated, {} chars total)", truncate_chars(&content, max_chars), content_len)
                         } else {
                             content
                         };
-                        return ToolResult::success(final_content).with_metadata(serde_json::json!({
+                        let mut result = ToolResult::success(final_content);
+                        if truncated {
+                            result = result.with_warning(format!(
+                                "Content truncated to {} of {} characters",
+                                max_chars,
+                                content_len
+                            ));
+                        }
+                        return result.with_metadata(serde_json::json!({
                             "url": params.url,
                             "final_url": final_url,
                             "content_type": content_type,
                             "extract_mode": extract_mode,
                             "truncated": truncated,
                             "original_length": original_length,
-                            "x402_payment": payment_info.map(|p| serde_json::json!({
-                                "amount": p.amount_formatted,
-                                "asset": p.asset,
-                                "pay_to": p.pay_to,
-                                "tx_hash": p.tx_hash,
-                            })),
+                            "x402_payment": payment_info,
                         }));
                     }
                     Err(e) => {
@@ -547,7 +554,7 @@ impl Tool for WebFetchTool {
                 return ToolResult::error(format!(
                     "HTTP 402 Payment Required for {} (no wallet available for x402 payment)\n\nResponse:\n{}",
                     params.url,
-                    if body.len() > 2000 { format!("{}...", &body[..2000]) } else { body }
+                    truncate_chars(&body, 2000)
                 ));
             }
         }
@@ -555,8 +562,8 @@ impl Tool for WebFetchTool {
         if !status.is_success() {
             // Extract the response body to include in the error message (truncate to avoid huge HTML pages)
             let body = response.text().await.unwrap_or_default();
-            let truncated_body = if body.len() > 2000 {
-                format!("{}...\n[truncated, {} total bytes]", &body[..2000], body.len())
+            let truncated_body = if body.chars().count() > 2000 {
+                format!("{}\n[truncated, {} total bytes]", truncate_chars(&body, 2000), body.len())
             } else {
                 body
             };
@@ -598,19 +605,27 @@ impl Tool for WebFetchTool {
         };
 
         // Truncate if necessary
-        let truncated = content.len() > max_chars;
+        let content_len = content.chars().count();
+        let truncated = content_len > max_chars;
         let final_content = if truncated {
             format!(
                 "{}\n\n[Content truncated at {} characters. Original length: {} characters]",
-                &content[..max_chars],
+                truncate_chars(&content, max_chars),
                 max_chars,
-                content.len()
+                content_len
             )
         } else {
             content
         };
 
-        let result = ToolResult::success(final_content).with_metadata(json!({
+        let mut result = ToolResult::success(final_content);
+        if truncated {
+            result = result.with_warning(format!(
+                "Content truncated to {} of {} characters",
+                max_chars, content_len
+            ));
+        }
+        let result = result.with_metadata(json!({
             "url": params.url,
             "final_url": final_url,
             "content_type": content_type,
@@ -631,6 +646,10 @@ impl Tool for WebFetchTool {
     fn safety_level(&self) -> ToolSafetyLevel {
         ToolSafetyLevel::ReadOnly
     }
+
+    fn cache_ttl(&self) -> Option<u64> {
+        Some(300)
+    }
 }
 
 /// Validate that a URL points to a public host (not private/internal)
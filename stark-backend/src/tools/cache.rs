@@ -0,0 +1,197 @@
+//! Per-session cache of tool results.
+//!
+//! Tools that declare a `Tool::cache_ttl` have their successful results
+//! reused when the same session calls them again with identical arguments before
+//! the TTL runs out. Entries are keyed by tool name + a hash of the canonicalized
+//! JSON arguments, so key order in the arguments doesn't matter.
+
+use crate::tools::types::ToolResult;
+use parking_lot::Mutex;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Maximum cached results kept per session (least recently used are evicted)
+const MAX_ENTRIES_PER_SESSION: usize = 64;
+
+/// Maximum sessions with cached results (the least recently used session is dropped)
+const MAX_SESSIONS: usize = 256;
+
+struct CachedResult {
+    result: ToolResult,
+    stored_at: Instant,
+    ttl: Duration,
+}
+
+struct SessionCache {
+    entries: HashMap<String, CachedResult>,
+    /// Keys from least to most recently used
+    order: VecDeque<String>,
+    /// `ToolResultCache::clock` value at the session's last read or write
+    last_used: u64,
+}
+
+impl SessionCache {
+    fn new() -> Self {
+        Self { entries: HashMap::new(), order: VecDeque::new(), last_used: 0 }
+    }
+
+    fn touch(&mut self, key: &str) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            self.order.remove(pos);
+        }
+        self.order.push_back(key.to_string());
+    }
+
+    fn remove(&mut self, key: &str) {
+        self.entries.remove(key);
+        self.order.retain(|k| k != key);
+    }
+}
+
+/// A cache hit: the stored result and how old it is
+pub struct CacheHit {
+    pub result: ToolResult,
+    pub age: Duration,
+}
+
+/// LRU cache of tool results, partitioned by session
+#[derive(Default)]
+pub struct ToolResultCache {
+    sessions: Mutex<HashMap<i64, SessionCache>>,
+    /// Logical clock ordering session use, for evicting the least recently used session
+    clock: AtomicU64,
+}
+
+impl ToolResultCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Cache key for a call: tool name + SHA-256 of the canonical JSON arguments
+    pub fn key(tool_name: &str, args: &Value) -> String {
+        let digest = Sha256::digest(canonicalize(args).to_string().as_bytes());
+        format!("{}:{}", tool_name, hex::encode(digest))
+    }
+
+    /// Look up a fresh result, dropping it if it has expired
+    pub fn get(&self, session_id: i64, key: &str) -> Option<CacheHit> {
+        let mut sessions = self.sessions.lock();
+        let session = sessions.get_mut(&session_id)?;
+        let entry = session.entries.get(key)?;
+        let age = entry.stored_at.elapsed();
+        if age >= entry.ttl {
+            session.remove(key);
+            return None;
+        }
+        let result = entry.result.clone();
+        session.touch(key);
+        session.last_used = self.tick();
+        Some(CacheHit { result, age })
+    }
+
+    /// Store a result for `ttl`, evicting the least recently used entry if full
+    pub fn insert(&self, session_id: i64, key: String, ttl: Duration, result: ToolResult) {
+        let mut sessions = self.sessions.lock();
+        if !sessions.contains_key(&session_id) && sessions.len() >= MAX_SESSIONS {
+            let oldest = sessions.iter().min_by_key(|(_, s)| s.last_used).map(|(id, _)| *id);
+            if let Some(oldest) = oldest {
+                sessions.remove(&oldest);
+            }
+        }
+        let session = sessions.entry(session_id).or_insert_with(SessionCache::new);
+        session.touch(&key);
+        session.last_used = self.tick();
+        session.entries.insert(key, CachedResult { result, stored_at: Instant::now(), ttl });
+        while session.entries.len() > MAX_ENTRIES_PER_SESSION {
+            match session.order.pop_front() {
+                Some(oldest) => {
+                    session.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Drop everything cached for a session (on /new, or after a tool with side effects ran)
+    pub fn clear_session(&self, session_id: i64) {
+        self.sessions.lock().remove(&session_id);
+    }
+}
+
+/// Recursively sort object keys so equivalent arguments serialize identically
+fn canonicalize(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let mut sorted = serde_json::Map::new();
+            for key in keys {
+                sorted.insert(key.clone(), canonicalize(&map[key]));
+            }
+            Value::Object(sorted)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonicalize).collect()),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_key_ignores_argument_order() {
+        let a = ToolResultCache::key("read_file", &json!({"path": "a.txt", "opts": {"x": 1, "y": 2}}));
+        let b = ToolResultCache::key("read_file", &json!({"opts": {"y": 2, "x": 1}, "path": "a.txt"}));
+        assert_eq!(a, b);
+        assert_ne!(a, ToolResultCache::key("read_file", &json!({"path": "b.txt"})));
+        assert_ne!(a, ToolResultCache::key("web_fetch", &json!({"path": "a.txt", "opts": {"x": 1, "y": 2}})));
+    }
+
+    #[test]
+    fn test_expiry_and_eviction() {
+        let cache = ToolResultCache::new();
+        let ttl = Duration::from_secs(60);
+        cache.insert(1, "fs".into(), ttl, ToolResult::success("file"));
+        cache.insert(1, "web".into(), ttl, ToolResult::success("page"));
+        cache.insert(1, "gone".into(), Duration::ZERO, ToolResult::success("stale"));
+        assert!(cache.get(1, "gone").is_none(), "expired entry returned");
+        assert!(cache.get(2, "fs").is_none(), "sessions must not share entries");
+        assert_eq!(cache.get(1, "web").unwrap().result.content, "page");
+
+        // LRU: "web" was used most recently, so the first new entry past the cap evicts something else
+        for i in 0..MAX_ENTRIES_PER_SESSION {
+            cache.insert(1, format!("k{}", i), ttl, ToolResult::success("x"));
+            cache.get(1, "web");
+        }
+        assert!(cache.get(1, "web").is_some());
+        assert!(cache.get(1, "k0").is_none());
+
+        cache.clear_session(1);
+        assert!(cache.get(1, "web").is_none());
+    }
+
+    #[test]
+    fn test_session_count_is_bounded() {
+        let cache = ToolResultCache::new();
+        let ttl = Duration::from_secs(60);
+        for session_id in 0..MAX_SESSIONS as i64 {
+            cache.insert(session_id, "k".into(), ttl, ToolResult::success("x"));
+        }
+        // Reading session 0 leaves session 1 as the least recently used
+        cache.get(0, "k");
+        cache.insert(MAX_SESSIONS as i64, "k".into(), ttl, ToolResult::success("x"));
+
+        assert_eq!(cache.sessions.lock().len(), MAX_SESSIONS);
+        assert!(cache.get(0, "k").is_some(), "recently used session was dropped");
+        assert!(cache.get(1, "k").is_none(), "least recently used session should be dropped");
+    }
+}
@@ -18,6 +18,12 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::sync::{Arc, RwLock};
 
+/// Most context-bank items injected into the prompt for one message
+pub const MAX_CONTEXT_BANK_ITEMS: usize = 30;
+
+/// Most remembered items added on top of the ones found in the message
+pub const MAX_RECALLED_CONTEXT_BANK_ITEMS: usize = 10;
+
 /// A detected item in the context bank
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct ContextBankItem {
@@ -181,6 +187,49 @@ impl ContextBank {
     }
 }
 
+/// Injection priority of an item type (lower = more relevant)
+fn item_priority(item_type: &str) -> u8 {
+    match item_type {
+        // URLs are often the primary focus of the request
+        "url" | "github_url" => 0,
+        "eth_address" => 1,
+        "token_symbol" => 2,
+        "network" => 3,
+        _ => 4,
+    }
+}
+
+/// Whether an item is worth remembering across sessions (addresses and token
+/// symbols; numbers and URLs are specific to the message they came from)
+pub fn is_persistable(item: &ContextBankItem) -> bool {
+    matches!(item.item_type.as_str(), "eth_address" | "token_symbol")
+}
+
+/// Fresh items first, then up to `max_stored` remembered items not already
+/// among them (in the order given, most recently seen first)
+pub fn merge_items(fresh: Vec<ContextBankItem>, stored: Vec<ContextBankItem>, max_stored: usize) -> Vec<ContextBankItem> {
+    let mut seen: HashSet<(String, String)> = fresh
+        .iter()
+        .map(|i| (i.item_type.clone(), i.value.to_lowercase()))
+        .collect();
+    let mut merged = fresh;
+    merged.extend(
+        stored
+            .into_iter()
+            .filter(|item| seen.insert((item.item_type.clone(), item.value.to_lowercase())))
+            .take(max_stored),
+    );
+    merged
+}
+
+/// Keep at most `max` items, most relevant first: URLs, then addresses,
+/// tokens, networks and numbers, each in the order they were found.
+pub fn cap_items(mut items: Vec<ContextBankItem>, max: usize) -> Vec<ContextBankItem> {
+    items.sort_by_key(|item| item_priority(&item.item_type));
+    items.truncate(max);
+    items
+}
+
 // Pre-compiled regexes for scan_input — compiled once, used on every dispatch
 static ETH_ADDR_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"0x[a-fA-F0-9]{40}").unwrap());
 static URL_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"https?://[^\s<>\[\]()]+[^\s<>\[\]().,;:!?]").unwrap());
@@ -467,4 +516,39 @@ mod tests {
         assert!(formatted.is_some());
         assert!(formatted.unwrap().contains("0x123"));
     }
+
+    #[test]
+    fn test_cap_items_keeps_most_relevant_first() {
+        let text = format!(
+            "send 5 ETH on base to {} {} {}, details at https://example.com/tx",
+            "0x742d35Cc6634C0532925a3b844Bc9e7595f8FdF0",
+            "0x0000000000000000000000000000000000000001",
+            "0x0000000000000000000000000000000000000002"
+        );
+        let items = cap_items(scan_input(&text), 3);
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].item_type, "url");
+        assert_eq!(items[1].value, "0x742d35cc6634c0532925a3b844bc9e7595f8fdf0");
+        assert_eq!(items[2].value, "0x0000000000000000000000000000000000000001");
+    }
+
+    #[test]
+    fn test_merge_items_prefers_fresh_and_dedupes() {
+        let item = |value: &str, label: Option<&str>| ContextBankItem {
+            value: value.to_string(),
+            item_type: "token_symbol".to_string(),
+            label: label.map(String::from),
+        };
+        let merged = merge_items(
+            vec![item("USDC", Some("USD Coin"))],
+            vec![item("usdc", None), item("WETH", None), item("DAI", None)],
+            1,
+        );
+        assert_eq!(merged, vec![item("USDC", Some("USD Coin")), item("WETH", None)]);
+        assert!(!is_persistable(&ContextBankItem {
+            value: "5".to_string(),
+            item_type: "number".to_string(),
+            label: None,
+        }));
+    }
 }
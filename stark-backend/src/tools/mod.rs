@@ -1,13 +1,18 @@
 pub mod builtin;
+pub mod cache;
 pub mod context_bank;
 pub mod http_retry;
+pub mod injection_guard;
 pub mod presets;
 pub mod register;
 pub mod registry;
 pub mod rpc_config;
 pub mod types;
 
-pub use context_bank::{scan_input, ContextBank, ContextBankItem};
+pub use context_bank::{
+    cap_items, is_persistable, merge_items, scan_input, ContextBank, ContextBankItem, MAX_CONTEXT_BANK_ITEMS,
+    MAX_RECALLED_CONTEXT_BANK_ITEMS,
+};
 pub use register::{PresetOrCustom, RegisterStore};
 pub use registry::{Tool, ToolRegistry};
 pub use types::{
@@ -22,13 +27,18 @@ fn register_all_tools(registry: &mut ToolRegistry) {
     // System tools (always available)
     registry.register(Arc::new(builtin::SpawnSubagentsTool::new()));
     registry.register(Arc::new(builtin::SubagentStatusTool::new()));
+    registry.register(Arc::new(builtin::WaitForSubagentTool::new()));
     registry.register(Arc::new(builtin::SetAgentSubtypeTool::new()));
     registry.register(Arc::new(builtin::UseSkillTool::new()));
     registry.register(Arc::new(builtin::AskUserTool::new()));
     registry.register(Arc::new(builtin::SayToUserTool::new()));
+    registry.register(Arc::new(builtin::ScheduleMessageTool::new()));
     // QMD Memory tools (file-based markdown memory system)
     registry.register(Arc::new(builtin::QmdMemorySearchTool::new()));
     registry.register(Arc::new(builtin::QmdMemoryReadTool::new()));
+    registry.register(Arc::new(builtin::RecallRecentMessagesTool::new()));
+    registry.register(Arc::new(builtin::QueryRolloutsTool::new()));
+    registry.register(Arc::new(builtin::LookupUserTool::new()));
     registry.register(Arc::new(builtin::ModifySoulTool::new()));
     registry.register(Arc::new(builtin::RegisterNewIdentityTool::new()));
     registry.register(Arc::new(builtin::ImportIdentityTool::new()));
@@ -51,6 +61,7 @@ fn register_all_tools(registry: &mut ToolRegistry) {
     registry.register(Arc::new(builtin::CloudBackupTool::new()));
     registry.register(Arc::new(builtin::SetThemeAccentTool::new()));
     registry.register(Arc::new(builtin::ReadOperatingModeTool::new()));
+    registry.register(Arc::new(builtin::ListSubtypesTool::new()));
     registry.register(Arc::new(builtin::ReadRecentTransactionsTool::new()));
     registry.register(Arc::new(builtin::ManageGatewayChannelsTool::new()));
 
@@ -126,6 +137,7 @@ fn register_all_tools(registry: &mut ToolRegistry) {
     registry.register(Arc::new(builtin::DiscordLookupTool::new()));
     registry.register(Arc::new(builtin::TwitterPostTool::new()));
     registry.register(Arc::new(builtin::TelegramReadTool::new()));
+    registry.register(Arc::new(builtin::CreatePollTool::new()));
 
     // Design tools
     registry.register(Arc::new(builtin::FigmaTool::new()));
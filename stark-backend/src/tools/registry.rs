@@ -1,10 +1,12 @@
 use crate::ai::multi_agent::types;
+use crate::tools::cache::ToolResultCache;
 use crate::tools::types::{ToolConfig, ToolContext, ToolDefinition, ToolGroup, ToolProfile, ToolResult, ToolSafetyLevel};
 use async_trait::async_trait;
 use parking_lot::RwLock;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Trait that all tools must implement
 #[async_trait]
@@ -32,6 +34,12 @@ pub trait Tool: Send + Sync {
     fn safety_level(&self) -> ToolSafetyLevel {
         ToolSafetyLevel::Standard
     }
+
+    /// Seconds a successful result may be reused for identical arguments within
+    /// the same session (None = never cached). Only override for read-only tools.
+    fn cache_ttl(&self) -> Option<u64> {
+        None
+    }
 }
 
 /// Registry that holds all available tools.
@@ -40,6 +48,8 @@ pub trait Tool: Send + Sync {
 pub struct ToolRegistry {
     tools: RwLock<HashMap<String, Arc<dyn Tool>>>,
     default_config: ToolConfig,
+    /// Per-session results of cacheable tools
+    result_cache: ToolResultCache,
 }
 
 impl ToolRegistry {
@@ -47,6 +57,7 @@ impl ToolRegistry {
         ToolRegistry {
             tools: RwLock::new(HashMap::new()),
             default_config: ToolConfig::default(),
+            result_cache: ToolResultCache::new(),
         }
     }
 
@@ -54,6 +65,7 @@ impl ToolRegistry {
         ToolRegistry {
             tools: RwLock::new(HashMap::new()),
             default_config: config,
+            result_cache: ToolResultCache::new(),
         }
     }
 
@@ -259,8 +271,50 @@ impl ToolRegistry {
             return ToolResult::error(format!("Tool '{}' is not allowed", name));
         }
 
+        // Cacheable tools reuse a fresh result for identical arguments in the same session
+        let cache_slot = match (tool.cache_ttl(), context.session_id) {
+            (Some(ttl), Some(session_id)) if ttl > 0 => {
+                Some((session_id, ToolResultCache::key(name, &params), Duration::from_secs(ttl)))
+            }
+            _ => None,
+        };
+        let hit = cache_slot
+            .as_ref()
+            .and_then(|(session_id, key, _)| self.result_cache.get(*session_id, key));
+        if let Some(hit) = hit {
+            log::debug!("[REGISTRY] Cache hit for '{}' (age {}s)", name, hit.age.as_secs());
+            crate::telemetry::emit_annotation(
+                "tool_cache_hit",
+                serde_json::json!({
+                    "tool_name": name,
+                    "age_secs": hit.age.as_secs(),
+                }),
+            );
+            return hit.result;
+        }
+
         // Execute the tool
-        tool.execute(params, context).await
+        let result = tool.execute(params, context).await;
+
+        // A tool with side effects (file edits, shell commands, transfers...) may have
+        // changed what any cached read saw, even if it failed part-way through
+        if tool.safety_level() == ToolSafetyLevel::Standard {
+            if let Some(session_id) = context.session_id {
+                self.result_cache.clear_session(session_id);
+            }
+        }
+        if let Some((session_id, key, ttl)) = cache_slot {
+            if result.success {
+                self.result_cache.insert(session_id, key, ttl, result.clone());
+            }
+        }
+
+        result
+    }
+
+    /// Forget all cached tool results for a session (e.g. when it is reset)
+    pub fn clear_session_cache(&self, session_id: i64) {
+        self.result_cache.clear_session(session_id);
     }
 
     /// Get default configuration
@@ -395,6 +449,7 @@ mod tests {
         // Also register the safe-mode allowed tools (from non-Web groups)
         registry.register(Arc::new(MockTool::new("say_to_user", ToolGroup::System)));
         registry.register(Arc::new(MockTool::new("set_agent_subtype", ToolGroup::System)));
+        registry.register(Arc::new(MockTool::new("list_subtypes", ToolGroup::System)));
         registry.register(Arc::new(MockTool::new("task_fully_completed", ToolGroup::System)));
         registry.register(Arc::new(MockTool::new("token_lookup", ToolGroup::Finance)));
         registry.register(Arc::new(MockTool::new("memory_read", ToolGroup::Memory)));
@@ -830,4 +885,113 @@ mod tests {
         // Allowed groups must be only "web"
         assert_eq!(config.allowed_groups, vec!["web".to_string()]);
     }
+
+    // =========================================================================
+    // RESULT CACHE
+    // =========================================================================
+
+    /// Tool that counts how often it really runs
+    struct CountingTool {
+        definition: ToolDefinition,
+        safety: ToolSafetyLevel,
+        cache_ttl: Option<u64>,
+        fails: bool,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    impl CountingTool {
+        fn new(name: &str, group: ToolGroup, cache_ttl: Option<u64>, safety: ToolSafetyLevel) -> Self {
+            let definition = MockTool::new(name, group).definition;
+            CountingTool { definition, safety, cache_ttl, fails: false, calls: Default::default() }
+        }
+
+        fn failing(mut self) -> Self {
+            self.fails = true;
+            self
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(std::sync::atomic::Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl Tool for CountingTool {
+        fn definition(&self) -> ToolDefinition {
+            self.definition.clone()
+        }
+
+        async fn execute(&self, _params: Value, _context: &ToolContext) -> ToolResult {
+            let n = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            if self.fails {
+                return ToolResult::error(format!("run {} failed", n));
+            }
+            ToolResult::success(format!("run {}", n))
+        }
+
+        fn safety_level(&self) -> ToolSafetyLevel {
+            self.safety
+        }
+
+        fn cache_ttl(&self) -> Option<u64> {
+            self.cache_ttl
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cacheable_tool_results_are_reused_per_session() {
+        let registry = ToolRegistry::new();
+        let reader = Arc::new(CountingTool::new("read_file", ToolGroup::Filesystem, Some(60), ToolSafetyLevel::ReadOnly));
+        // File writes live outside the Filesystem group
+        let writer = Arc::new(CountingTool::new("edit_file", ToolGroup::Development, None, ToolSafetyLevel::Standard));
+        let shell = Arc::new(CountingTool::new("exec", ToolGroup::Exec, None, ToolSafetyLevel::Standard).failing());
+        let searcher = Arc::new(CountingTool::new("grep", ToolGroup::Filesystem, None, ToolSafetyLevel::ReadOnly));
+        registry.register(reader.clone());
+        registry.register(writer.clone());
+        registry.register(shell.clone());
+        registry.register(searcher.clone());
+        let config = ToolConfig { profile: ToolProfile::Full, ..Default::default() };
+
+        let collector = Arc::new(crate::telemetry::SpanCollector::new("rollout-cache".to_string(), 1));
+        crate::telemetry::set_active_collector(collector.clone());
+
+        let session = ToolContext::new().with_session(1);
+        let first = registry.execute("read_file", serde_json::json!({"path": "a", "limit": 10}), &session, Some(&config)).await;
+        let second = registry.execute("read_file", serde_json::json!({"limit": 10, "path": "a"}), &session, Some(&config)).await;
+        assert_eq!(reader.calls(), 1, "identical arguments should hit the cache");
+        assert_eq!(second.content, first.content);
+        assert!(collector
+            .snapshot()
+            .iter()
+            .any(|s| s.attributes["annotation_key"] == "tool_cache_hit"));
+        crate::telemetry::clear_active_collector();
+
+        // Other sessions and other arguments miss
+        let other_session = ToolContext::new().with_session(2);
+        registry.execute("read_file", serde_json::json!({"path": "a", "limit": 10}), &other_session, Some(&config)).await;
+        registry.execute("read_file", serde_json::json!({"path": "b"}), &session, Some(&config)).await;
+        assert_eq!(reader.calls(), 3);
+
+        // Read-only tools leave the cache alone
+        registry.execute("grep", serde_json::json!({"pattern": "x"}), &session, Some(&config)).await;
+        registry.execute("read_file", serde_json::json!({"path": "a", "limit": 10}), &session, Some(&config)).await;
+        assert_eq!(reader.calls(), 3);
+
+        // Any tool with side effects invalidates the session's entries, whatever its group
+        registry.execute("edit_file", serde_json::json!({"path": "a"}), &session, Some(&config)).await;
+        let after_write = registry.execute("read_file", serde_json::json!({"path": "a", "limit": 10}), &session, Some(&config)).await;
+        assert_eq!(reader.calls(), 4);
+        assert_eq!(after_write.content, "run 4");
+
+        // ...even when it fails (it may have written part of its changes)
+        let failed = registry.execute("exec", serde_json::json!({"command": "sed -i s/a/b/ a"}), &session, Some(&config)).await;
+        assert!(!failed.success);
+        registry.execute("read_file", serde_json::json!({"path": "a", "limit": 10}), &session, Some(&config)).await;
+        assert_eq!(reader.calls(), 5);
+
+        // Resetting the session (/new) drops the rest
+        registry.clear_session_cache(1);
+        registry.execute("read_file", serde_json::json!({"path": "a", "limit": 10}), &session, Some(&config)).await;
+        assert_eq!(reader.calls(), 6);
+    }
 }
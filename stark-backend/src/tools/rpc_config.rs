@@ -7,79 +7,172 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
 use std::sync::OnceLock;
-use strum::{Display, EnumString, AsRefStr};
 
-/// Supported blockchain networks
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Display, EnumString, AsRefStr)]
-#[strum(serialize_all = "lowercase")]
-#[serde(rename_all = "lowercase")]
+/// Blockchain network identifier.
+///
+/// Network names arrive as free-form strings (UI selection, tool params). They
+/// are parsed once at the boundary so aliases like "ethereum"/"eth" resolve to
+/// the same variant. Well-formed names that aren't known here parse to `Custom`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub enum Network {
+    #[default]
     Base,
     Mainnet,
     Polygon,
+    Arbitrum,
+    Optimism,
+    /// A network without built-in support (lowercase name)
+    Custom(String),
 }
 
 impl Network {
-    /// Get the chain ID for this network
-    pub fn chain_id(&self) -> u64 {
+    /// Canonical name (also the key used in RPC provider endpoint maps)
+    pub fn as_str(&self) -> &str {
         match self {
-            Network::Base => 8453,
-            Network::Mainnet => 1,
-            Network::Polygon => 137,
+            Network::Base => "base",
+            Network::Mainnet => "mainnet",
+            Network::Polygon => "polygon",
+            Network::Arbitrum => "arbitrum",
+            Network::Optimism => "optimism",
+            Network::Custom(name) => name,
         }
     }
 
+    /// Get the chain ID for this network (None for custom networks)
+    pub fn chain_id(&self) -> Option<u64> {
+        match self {
+            Network::Base => Some(8453),
+            Network::Mainnet => Some(1),
+            Network::Polygon => Some(137),
+            Network::Arbitrum => Some(42161),
+            Network::Optimism => Some(10),
+            Network::Custom(_) => None,
+        }
+    }
+
+    /// Look up a network by chain ID
+    pub fn from_chain_id(chain_id: u64) -> Option<Network> {
+        Self::known().iter().find(|n| n.chain_id() == Some(chain_id)).cloned()
+    }
+
     /// Get the native currency symbol
     pub fn native_currency(&self) -> &'static str {
         match self {
-            Network::Base => "ETH",
-            Network::Mainnet => "ETH",
             Network::Polygon => "MATIC",
+            _ => "ETH",
         }
     }
 
     /// Get the block explorer URL
-    pub fn explorer_url(&self) -> &'static str {
+    pub fn explorer_url(&self) -> Option<&'static str> {
         match self {
-            Network::Base => "https://basescan.org",
-            Network::Mainnet => "https://etherscan.io",
-            Network::Polygon => "https://polygonscan.com",
+            Network::Base => Some("https://basescan.org"),
+            Network::Mainnet => Some("https://etherscan.io"),
+            Network::Polygon => Some("https://polygonscan.com"),
+            Network::Arbitrum => Some("https://arbiscan.io"),
+            Network::Optimism => Some("https://optimistic.etherscan.io"),
+            Network::Custom(_) => None,
         }
     }
 
     /// Get the USDC contract address for this network
-    pub fn usdc_address(&self) -> &'static str {
+    pub fn usdc_address(&self) -> Option<&'static str> {
         match self {
-            Network::Base => "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
-            Network::Mainnet => "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
-            Network::Polygon => "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359",
+            Network::Base => Some("0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913"),
+            Network::Mainnet => Some("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
+            Network::Polygon => Some("0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359"),
+            Network::Arbitrum => Some("0xaf88d065e77c8cC2239327C5EDb3A432268e5831"),
+            Network::Optimism => Some("0x0b2C639c533813f4Aa9D7837cAf62653d097Ff85"),
+            Network::Custom(_) => None,
         }
     }
 
-    /// All supported networks
+    /// Networks the RPC tools support (default provider endpoints)
     pub fn all() -> &'static [Network] {
         &[Network::Base, Network::Mainnet, Network::Polygon]
     }
 
+    /// Every network with built-in support
+    pub fn known() -> &'static [Network] {
+        &[Network::Base, Network::Mainnet, Network::Polygon, Network::Arbitrum, Network::Optimism]
+    }
+
+    /// Whether the RPC tools can target this network
+    pub fn is_rpc_supported(&self) -> bool {
+        Self::all().contains(self)
+    }
+
     /// Try to detect network from a known contract address
     pub fn from_contract_address(address: &str) -> Option<Network> {
-        let addr_lower = address.to_lowercase();
-        match addr_lower.as_str() {
-            // USDC addresses
-            "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913" => Some(Network::Base),
-            "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48" => Some(Network::Mainnet),
-            "0x3c499c542cef5e3811e1192ce70d8cc03d5c3359" => Some(Network::Polygon),
-            _ => None,
-        }
+        Self::known()
+            .iter()
+            .find(|n| n.usdc_address().is_some_and(|a| a.eq_ignore_ascii_case(address)))
+            .cloned()
     }
 }
 
-impl Default for Network {
-    fn default() -> Self {
-        Network::Base
+impl std::str::FromStr for Network {
+    type Err = String;
+
+    /// Parse a network name, chain ID or common alias (case-insensitive).
+    /// Unknown but well-formed names become `Custom`; anything else is rejected.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        let network = match name.as_str() {
+            "base" | "base-mainnet" | "8453" => Network::Base,
+            "mainnet" | "ethereum" | "eth" | "ethereum-mainnet" | "homestead" | "1" => Network::Mainnet,
+            "polygon" | "matic" | "polygon-pos" | "137" => Network::Polygon,
+            "arbitrum" | "arbitrum-one" | "arb" | "42161" => Network::Arbitrum,
+            "optimism" | "op" | "op-mainnet" | "10" => Network::Optimism,
+            _ => {
+                let well_formed = !name.is_empty()
+                    && name.len() <= 32
+                    && name.starts_with(|c: char| c.is_ascii_lowercase())
+                    && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+                if !well_formed {
+                    return Err(format!("Invalid network '{}'", s.trim()));
+                }
+                Network::Custom(name)
+            }
+        };
+        Ok(network)
+    }
+}
+
+impl std::fmt::Display for Network {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
     }
 }
 
+impl AsRef<str> for Network {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Serialize for Network {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Network {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Deserialize an optional network, dropping values that don't parse.
+/// For persisted state written before networks were validated.
+pub fn deserialize_network_lenient<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Network>, D::Error> {
+    let s: Option<String> = Option::deserialize(deserializer)?;
+    Ok(s.and_then(|s| s.parse().ok()))
+}
+
 /// Global storage for RPC providers
 static RPC_PROVIDERS: OnceLock<HashMap<String, RpcProvider>> = OnceLock::new();
 
@@ -229,6 +322,8 @@ pub fn resolve_rpc_config(
 pub struct ResolvedRpcConfig {
     pub url: String,
     pub use_x402: bool,
+    /// The provider had no endpoint for the network and the default one is used
+    pub fallback: bool,
 }
 
 /// Resolve RPC configuration using default provider
@@ -242,7 +337,7 @@ pub fn resolve_rpc_from_network(network: &str) -> ResolvedRpcConfig {
                 url,
                 use_x402
             );
-            ResolvedRpcConfig { url, use_x402 }
+            ResolvedRpcConfig { url, use_x402, fallback: false }
         }
         None => {
             let url = format!("https://rpc.defirelay.com/rpc/light/{}", network);
@@ -251,7 +346,7 @@ pub fn resolve_rpc_from_network(network: &str) -> ResolvedRpcConfig {
                 network,
                 url
             );
-            ResolvedRpcConfig { url, use_x402: true }
+            ResolvedRpcConfig { url, use_x402: true, fallback: true }
         }
     }
 }
@@ -279,7 +374,7 @@ pub fn resolve_rpc_from_context(
                 url,
                 use_x402
             );
-            ResolvedRpcConfig { url, use_x402 }
+            ResolvedRpcConfig { url, use_x402, fallback: false }
         }
         None => {
             // Fallback to default defirelay URL
@@ -289,7 +384,51 @@ pub fn resolve_rpc_from_context(
                 network,
                 url
             );
-            ResolvedRpcConfig { url, use_x402: true }
+            ResolvedRpcConfig { url, use_x402: true, fallback: true }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network_aliases() {
+        for alias in ["base", "Base", " BASE ", "8453"] {
+            assert_eq!(alias.parse::<Network>().unwrap(), Network::Base, "{}", alias);
+        }
+        for alias in ["mainnet", "ethereum", "eth", "ETH", "homestead", "1"] {
+            assert_eq!(alias.parse::<Network>().unwrap(), Network::Mainnet, "{}", alias);
+        }
+        for alias in ["polygon", "matic", "137"] {
+            assert_eq!(alias.parse::<Network>().unwrap(), Network::Polygon, "{}", alias);
+        }
+        assert_eq!("arb".parse::<Network>().unwrap(), Network::Arbitrum);
+        assert_eq!("op".parse::<Network>().unwrap(), Network::Optimism);
+        assert_eq!("Zora".parse::<Network>().unwrap(), Network::Custom("zora".to_string()));
+    }
+
+    #[test]
+    fn test_network_rejects_nonsense() {
+        for bad in ["", "   ", "base mainnet", "../etc", "0xdeadbeef!", "42", "🚀", &"a".repeat(40)] {
+            assert!(bad.parse::<Network>().is_err(), "{:?} should not parse", bad);
         }
     }
+
+    #[test]
+    fn test_network_display_and_chain_id() {
+        let network: Network = "ethereum".parse().unwrap();
+        assert_eq!(network.to_string(), "mainnet");
+        assert_eq!(network.chain_id(), Some(1));
+        assert_eq!(Network::from_chain_id(137), Some(Network::Polygon));
+        assert_eq!(Network::Custom("zora".to_string()).chain_id(), None);
+        assert!(Network::Mainnet.is_rpc_supported());
+        assert!(!Network::Arbitrum.is_rpc_supported());
+
+        let json = serde_json::to_string(&Network::Polygon).unwrap();
+        assert_eq!(json, "\"polygon\"");
+        assert_eq!(serde_json::from_str::<Network>("\"matic\"").unwrap(), Network::Polygon);
+        assert!(serde_json::from_str::<Network>("\"not a network\"").is_err());
+    }
 }
@@ -7,7 +7,9 @@ use crate::gateway::events::EventBroadcaster;
 use crate::gateway::protocol::GatewayEvent;
 use crate::qmd_memory::MemoryStore;
 use crate::skills::SkillRegistry;
+use crate::telemetry::WatchdogConfig;
 use crate::tools::register::RegisterStore;
+use crate::tools::rpc_config::Network;
 use crate::tx_queue::TxQueueManager;
 use crate::wallet::WalletProvider;
 use serde::{Deserialize, Serialize};
@@ -15,6 +17,7 @@ use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 use strum::{EnumIter, IntoEnumIterator};
+use tokio_util::sync::CancellationToken;
 
 /// Describes what kind of rich content a channel can render
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -55,7 +58,9 @@ impl ToolSafetyLevel {
 }
 
 /// Tool groups for access control
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default, EnumIter)]
+///
+/// Variant order defines the order groups appear in tool lists sent to the model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Default, EnumIter)]
 #[serde(rename_all = "lowercase")]
 pub enum ToolGroup {
     /// System tools - always available (set_agent_subtype, ask_user, etc.)
@@ -315,6 +320,10 @@ pub struct ToolResult {
     /// Used for transient network errors with exponential backoff.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub retry_after_secs: Option<u64>,
+    /// Caveats on an otherwise usable result (content truncated, fallback endpoint used).
+    /// Appended to the content the model sees and broadcast to the UI separately.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
 
 impl ToolResult {
@@ -325,6 +334,7 @@ impl ToolResult {
             error: None,
             metadata: None,
             retry_after_secs: None,
+            warnings: Vec::new(),
         }
     }
 
@@ -336,6 +346,7 @@ impl ToolResult {
             error: Some(msg),
             metadata: None,
             retry_after_secs: None,
+            warnings: Vec::new(),
         }
     }
 
@@ -352,6 +363,7 @@ impl ToolResult {
             error: Some(msg),
             metadata: None,
             retry_after_secs: Some(retry_after_secs),
+            warnings: Vec::new(),
         }
     }
 
@@ -365,6 +377,20 @@ impl ToolResult {
         self
     }
 
+    pub fn with_warning(mut self, warning: impl Into<String>) -> Self {
+        self.warnings.push(warning.into());
+        self
+    }
+
+    /// The content as shown to the model, with any warnings listed after it
+    pub fn content_for_model(&self) -> String {
+        if self.warnings.is_empty() {
+            return self.content.clone();
+        }
+        let warnings = self.warnings.iter().map(|w| format!("- {}", w)).collect::<Vec<_>>().join("\n");
+        format!("{}\n\n⚠️ Warnings:\n{}", self.content, warnings)
+    }
+
     /// Check if this result indicates the tool should be retried
     pub fn should_retry(&self) -> bool {
         self.retry_after_secs.is_some()
@@ -404,7 +430,7 @@ pub struct ToolContext {
     pub tx_queue: Option<Arc<TxQueueManager>>,
     /// Currently selected network from the UI (e.g., "base", "polygon", "mainnet")
     /// Web3 tools should use this as default unless user explicitly specifies otherwise
-    pub selected_network: Option<String>,
+    pub selected_network: Option<Network>,
     /// QMD Memory store for markdown-based memory system
     pub memory_store: Option<Arc<MemoryStore>>,
     /// Wallet provider for signing transactions (Standard or Flash mode)
@@ -415,6 +441,8 @@ pub struct ToolContext {
     /// Runtime API key store (interior-mutable so install_api_key can write via &self)
     /// Keys are stored as UPPER_SNAKE_CASE names → values
     pub api_keys: Arc<RwLock<HashMap<String, String>>>,
+    /// Fetch API keys missing from `api_keys` from `database` on first use (see `get_api_key`)
+    pub lazy_api_keys: bool,
     /// Optional HTTP proxy URL for tool requests (does not affect AI model API calls)
     pub proxy_url: Option<String>,
     /// Pre-built HTTP client configured with the proxy (if proxy_url is set)
@@ -425,6 +453,10 @@ pub struct ToolContext {
     pub current_subagent_id: Option<String>,
     /// If this context is running inside a sub-agent, the sub-agent's depth (0 = top-level)
     pub current_subagent_depth: Option<u32>,
+    /// Cancelled when the user stops the current execution, so long waits can bail out early
+    pub cancellation_token: Option<CancellationToken>,
+    /// Watchdog limits in force for this session (lets tools stay inside their timeout)
+    pub watchdog_config: Option<Arc<WatchdogConfig>>,
 }
 
 impl std::fmt::Debug for ToolContext {
@@ -450,11 +482,14 @@ impl std::fmt::Debug for ToolContext {
             .field("wallet_provider", &self.wallet_provider.is_some())
             .field("platform_chat_id", &self.platform_chat_id)
             .field("api_keys", &self.api_keys.read().ok().map(|m| m.len()))
+            .field("lazy_api_keys", &self.lazy_api_keys)
             .field("proxy_url", &self.proxy_url)
             .field("tool_http_client", &self.tool_http_client.is_some())
             .field("disk_quota", &self.disk_quota.is_some())
             .field("current_subagent_id", &self.current_subagent_id)
             .field("current_subagent_depth", &self.current_subagent_depth)
+            .field("cancellation_token", &self.cancellation_token.is_some())
+            .field("watchdog_config", &self.watchdog_config.is_some())
             .finish()
     }
 }
@@ -483,11 +518,14 @@ impl Default for ToolContext {
             wallet_provider: None,
             platform_chat_id: None,
             api_keys: Arc::new(RwLock::new(HashMap::new())),
+            lazy_api_keys: false,
             proxy_url: None,
             tool_http_client: None,
             disk_quota: None,
             current_subagent_id: None,
             current_subagent_depth: None,
+            cancellation_token: None,
+            watchdog_config: None,
         }
     }
 }
@@ -552,9 +590,17 @@ impl ToolContext {
         self.with_api_key(key_id.as_str(), key_value)
     }
 
+    /// Load API keys from the database on demand instead of up front.
+    /// Requires `with_database`; keys are decrypted only when a tool asks for them.
+    pub fn with_lazy_api_keys(mut self) -> Self {
+        self.lazy_api_keys = true;
+        self
+    }
+
     /// Get an API key from the context by its exact string name
     /// Example: get_api_key("GITHUB_TOKEN")
-    /// Checks the api_keys store first, falls back to extra
+    /// Checks the api_keys store first, then extra, then (with lazy loading)
+    /// the database, memoizing what it finds in the store
     pub fn get_api_key(&self, key_name: &str) -> Option<String> {
         // Check api_keys store first
         if let Ok(store) = self.api_keys.read() {
@@ -565,9 +611,20 @@ impl ToolContext {
             }
         }
         // Fall back to extra
-        self.extra.get(&format!("api_key_{}", key_name))
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
+        if let Some(val) = self.extra.get(&format!("api_key_{}", key_name)).and_then(|v| v.as_str()) {
+            return Some(val.to_string());
+        }
+        if !self.lazy_api_keys {
+            return None;
+        }
+        let value = self.database.as_ref()?
+            .get_api_key(key_name)
+            .ok()
+            .flatten()
+            .map(|key| key.api_key)
+            .filter(|v| !v.is_empty())?;
+        self.install_api_key_runtime(key_name, value.clone());
+        Some(value)
     }
 
     /// Get an API key from the context using the type-safe ApiKeyId enum
@@ -587,13 +644,22 @@ impl ToolContext {
         }
     }
 
-    /// List all API key names currently in the runtime store
+    /// List all API key names in the runtime store, plus (with lazy loading)
+    /// those configured in the database but not fetched yet
     pub fn list_api_key_names(&self) -> Vec<String> {
-        self.api_keys
+        let mut names: Vec<String> = self.api_keys
             .read()
             .ok()
             .map(|store| store.keys().cloned().collect())
-            .unwrap_or_default()
+            .unwrap_or_default();
+        if let Some(db) = self.database.as_ref().filter(|_| self.lazy_api_keys) {
+            for name in db.list_api_key_names().unwrap_or_default() {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+        names
     }
 
     /// Find a bot token from channel settings for a given channel type.
@@ -681,7 +747,7 @@ impl ToolContext {
     }
 
     /// Set the selected network from the UI (for web3 tools to use as default)
-    pub fn with_selected_network(mut self, network: Option<String>) -> Self {
+    pub fn with_selected_network(mut self, network: Option<Network>) -> Self {
         self.selected_network = network;
         self
     }
@@ -699,6 +765,23 @@ impl ToolContext {
         self
     }
 
+    /// Attach the execution's cancellation token
+    pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// Attach the session's watchdog limits
+    pub fn with_watchdog_config(mut self, config: Arc<WatchdogConfig>) -> Self {
+        self.watchdog_config = Some(config);
+        self
+    }
+
+    /// The watchdog timeout for `tool_name`, if a watchdog is enforcing one
+    pub fn tool_timeout(&self, tool_name: &str) -> Option<std::time::Duration> {
+        self.watchdog_config.as_ref().map(|c| c.timeout_for_tool(tool_name))
+    }
+
     /// Add a DiskQuotaManager to the context (for enforcing disk usage limits)
     pub fn with_disk_quota(mut self, dq: Arc<DiskQuotaManager>) -> Self {
         self.disk_quota = Some(dq);
@@ -866,6 +949,7 @@ impl Default for ToolConfig {
 /// SECURITY: Adding tools here grants them to ALL untrusted users. Be extremely careful.
 pub const SAFE_MODE_ALLOW_LIST: &[&str] = &[
     "set_agent_subtype",    // Changes agent mode per-session (safe, no persistence)
+    "list_subtypes",        // Read-only list of subtype keys for set_agent_subtype (safe)
     "use_skill",            // Execute skills (safe — skill enum filtering controls which skills are available)
     "token_lookup",         // Read-only token info lookup (safe)
     "say_to_user",          // Send message to user (safe)
@@ -878,6 +962,15 @@ pub const SAFE_MODE_ALLOW_LIST: &[&str] = &[
     "telegram_read",        // Read-only Telegram operations (safe)
 ];
 
+/// Tools still allowed when a channel's tool config cannot be loaded.
+/// Just enough for the agent to tell the user something is wrong — no side effects.
+pub const TOOL_CONFIG_FALLBACK_ALLOW_LIST: &[&str] = &[
+    "say_to_user",
+    "ask_user",
+    "task_fully_completed",
+    "define_tasks",
+];
+
 /// Tools whose sessions must NEVER be written to memory files.
 /// SECURITY: Prevents API keys and secrets from persisting in memory markdown files.
 pub const MEMORY_EXCLUDE_TOOL_LIST: &[&str] = &[
@@ -906,6 +999,21 @@ impl ToolConfig {
         }
     }
 
+    /// Deny-by-default config used when the stored tool config can't be loaded.
+    /// Falling back to the permissive default would silently grant every tool.
+    pub fn load_failure_fallback() -> Self {
+        ToolConfig {
+            id: None,
+            channel_id: None,
+            profile: ToolProfile::None,
+            allow_list: TOOL_CONFIG_FALLBACK_ALLOW_LIST.iter().map(|s| s.to_string()).collect(),
+            deny_list: vec![],
+            allowed_groups: vec![],
+            denied_groups: vec![],
+            extra_skill_names: vec![],
+        }
+    }
+
     /// Check if a tool is allowed by this configuration
     pub fn is_tool_allowed(&self, tool_name: &str, tool_group: ToolGroup) -> bool {
         // Explicit deny takes precedence
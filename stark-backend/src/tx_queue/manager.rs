@@ -4,6 +4,8 @@
 
 use chrono::Utc;
 use dashmap::DashMap;
+use ethers::types::U256;
+use std::future::Future;
 use std::sync::Arc;
 
 use super::types::{QueuedTransaction, QueuedTxStatus, QueuedTxSummary};
@@ -11,6 +13,7 @@ use crate::db::tables::broadcasted_transactions::{
     BroadcastMode, BroadcastedTxStatus, RecordBroadcastRequest,
 };
 use crate::db::Database;
+use crate::wallet::NonceManager;
 
 /// Manager for the transaction queue
 /// Uses DashMap for thread-safe concurrent access
@@ -19,6 +22,8 @@ pub struct TxQueueManager {
     transactions: DashMap<String, QueuedTransaction>,
     /// Optional database for persistent broadcast history
     db: Option<Arc<Database>>,
+    /// Nonce allocation for transactions signed into this queue
+    nonces: Arc<NonceManager>,
 }
 
 impl TxQueueManager {
@@ -27,6 +32,7 @@ impl TxQueueManager {
         Self {
             transactions: DashMap::new(),
             db: None,
+            nonces: NonceManager::shared(),
         }
     }
 
@@ -35,17 +41,65 @@ impl TxQueueManager {
         Self {
             transactions: DashMap::new(),
             db: Some(db),
+            nonces: NonceManager::shared(),
         }
     }
 
+    /// Use a dedicated nonce manager instead of the process-wide one
+    pub fn with_nonce_manager(mut self, nonces: Arc<NonceManager>) -> Self {
+        self.nonces = nonces;
+        self
+    }
+
     /// Queue a new transaction
     pub fn queue(&self, tx: QueuedTransaction) -> String {
         let uuid = tx.uuid.clone();
         log::info!("[TxQueue] Queuing transaction {} to {}", uuid, tx.to);
+        let (network, from, nonce) = (tx.network.clone(), tx.from.clone(), tx.nonce);
         self.transactions.insert(uuid.clone(), tx);
+        self.nonces.mark_queued(&network, &from, U256::from(nonce));
         uuid
     }
 
+    /// Allocate the nonce for a transaction from `from` on `network`.
+    ///
+    /// Allocation is serialized per address, and never reuses a nonce held by
+    /// a transaction still waiting in the queue. `chain_nonce` fetches the
+    /// pending nonce from RPC.
+    pub async fn allocate_nonce<F, Fut>(&self, network: &str, from: &str, chain_nonce: F) -> Result<U256, String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<U256, String>>,
+    {
+        let queued_floor = self.next_unqueued_nonce(network, from);
+        self.nonces
+            .next_nonce(network, from, || async move {
+                let from_chain = chain_nonce().await?;
+                Ok(queued_floor.map_or(from_chain, |floor| from_chain.max(floor)))
+            })
+            .await
+    }
+
+    /// Return a nonce from `allocate_nonce` whose transaction was never queued
+    pub async fn release_nonce(&self, network: &str, from: &str, nonce: u64) {
+        self.nonces.release(network, from, U256::from(nonce)).await;
+    }
+
+    /// One past the highest nonce held by a not-yet-broadcast transaction
+    fn next_unqueued_nonce(&self, network: &str, from: &str) -> Option<U256> {
+        self.transactions
+            .iter()
+            .filter(|r| {
+                let tx = r.value();
+                matches!(tx.status, QueuedTxStatus::Pending | QueuedTxStatus::Broadcasting)
+                    && tx.network.eq_ignore_ascii_case(network)
+                    && tx.from.eq_ignore_ascii_case(from)
+            })
+            .map(|r| r.value().nonce)
+            .max()
+            .map(|nonce| U256::from(nonce) + 1)
+    }
+
     /// Get a transaction by UUID
     pub fn get(&self, uuid: &str) -> Option<QueuedTransaction> {
         self.transactions.get(uuid).map(|r| r.clone())
@@ -173,6 +227,9 @@ impl TxQueueManager {
             tx.status = QueuedTxStatus::Failed;
             tx.error = Some(error.to_string());
 
+            // The nonce may be unused (rejected, dropped, replaced): start over from the chain
+            self.nonces.resync(&tx.network, &tx.from);
+
             // Update database status if available
             if let Some(ref db) = self.db {
                 if let Err(e) = db.update_broadcast_status(uuid, BroadcastedTxStatus::Failed, Some(error)) {
@@ -212,7 +269,12 @@ impl TxQueueManager {
 
     /// Remove a transaction by UUID (for cleanup)
     pub fn remove(&self, uuid: &str) -> Option<QueuedTransaction> {
-        self.transactions.remove(uuid).map(|(_, tx)| tx)
+        let tx = self.transactions.remove(uuid).map(|(_, tx)| tx)?;
+        // A transaction removed before broadcast leaves its nonce unused
+        if tx.status == QueuedTxStatus::Pending {
+            self.nonces.resync(&tx.network, &tx.from);
+        }
+        Some(tx)
     }
 
     /// Clean up old transactions (older than duration)
@@ -312,6 +374,22 @@ mod tests {
         assert_eq!(tx.status, QueuedTxStatus::Confirmed);
     }
 
+    #[tokio::test]
+    async fn test_allocate_nonce_skips_queued_and_resyncs_after_failure() {
+        let manager = TxQueueManager::new().with_nonce_manager(Arc::new(NonceManager::new()));
+        let chain = || async { Ok(U256::zero()) };
+
+        // A transaction signed elsewhere already holds nonce 0 in the queue
+        manager.queue(create_test_tx("queued-0"));
+        let first = manager.allocate_nonce("base", "0x1234", chain).await.unwrap();
+        let second = manager.allocate_nonce("base", "0x1234", chain).await.unwrap();
+        assert_eq!((first.as_u64(), second.as_u64()), (1, 2));
+
+        // Broadcast of the queued tx fails: its nonce is free again once nothing holds it
+        manager.mark_failed("queued-0", "nonce too low");
+        assert_eq!(manager.allocate_nonce("base", "0x1234", chain).await.unwrap(), U256::zero());
+    }
+
     #[test]
     fn test_list_pending() {
         let manager = TxQueueManager::new();
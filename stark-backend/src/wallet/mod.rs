@@ -14,9 +14,13 @@
 
 mod env_provider;
 mod flash_provider;
+mod nonce_manager;
+mod registry;
 
 pub use env_provider::EnvWalletProvider;
 pub use flash_provider::FlashWalletProvider;
+pub use nonce_manager::NonceManager;
+pub use registry::WalletRegistry;
 
 use async_trait::async_trait;
 use ethers::types::{Signature, H256, transaction::eip2718::TypedTransaction};
@@ -31,7 +35,8 @@ pub trait WalletProvider: Send + Sync {
     /// Sign a message (EIP-191 personal_sign)
     async fn sign_message(&self, message: &[u8]) -> Result<Signature, String>;
 
-    /// Sign a typed transaction
+    /// Sign a typed transaction. The nonce is signed as given: allocate it
+    /// with `TxQueueManager::allocate_nonce` so concurrent signers don't collide.
     async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, String>;
 
     /// Sign a raw 32-byte hash (for EIP-712 when hash is pre-computed)
@@ -0,0 +1,219 @@
+//! Per-address nonce allocation
+//!
+//! Transactions are signed well before they are broadcast (see `tx_queue`), so
+//! the chain's pending nonce doesn't account for transactions that are signed
+//! but still waiting in the queue. Concurrent signers (e.g. subagents) that
+//! each read the nonce from RPC would sign conflicting transactions.
+//!
+//! `NonceManager` hands out nonces one at a time per (network, address): an
+//! async mutex serializes allocation, and the next nonce is cached so that
+//! back-to-back allocations don't collide. Every allocation still reads the
+//! chain's pending nonce and uses whichever is higher, so transactions sent
+//! from outside the bot are picked up. When the chain disagrees (a dropped or
+//! replaced transaction, a failed broadcast), `resync` drops the cache and the
+//! next allocation starts from the chain again.
+//!
+//! Nonces handed out but not yet queued (or released) are tracked as in flight
+//! and skipped by later allocations, so a resync or an out-of-order release
+//! can't hand a nonce to a second signer while the first is still signing.
+//!
+//! Scope: allocation goes through `TxQueueManager::allocate_nonce`, which every
+//! transaction-signing path (`web3::sign_transaction_for_queue`, `web3_tx`, `bridge_usdc`)
+//! uses before calling `WalletProvider::sign_transaction`. The wallet providers
+//! themselves (standard and Flash) sign whatever nonce they are given and don't
+//! consult this manager, so a new signing path must allocate through the queue
+//! too or it can reuse a nonce.
+
+use dashmap::DashMap;
+use ethers::types::U256;
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::Mutex;
+
+/// Allocation state for one (network, address)
+#[derive(Default)]
+struct NonceSlot {
+    /// Next nonce to hand out (None = not yet synced from chain)
+    next: Mutex<Option<U256>>,
+    /// Set by `resync`; the next allocation ignores the cached nonce
+    stale: AtomicBool,
+    /// Allocated nonces whose transaction is neither queued nor released yet
+    in_flight: parking_lot::Mutex<BTreeSet<U256>>,
+}
+
+/// Serializes nonce allocation per (network, address)
+#[derive(Default)]
+pub struct NonceManager {
+    slots: DashMap<(String, String), Arc<NonceSlot>>,
+}
+
+static SHARED: OnceLock<Arc<NonceManager>> = OnceLock::new();
+
+impl NonceManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process-wide instance, so every queue signing for an address draws
+    /// from the same sequence
+    pub fn shared() -> Arc<NonceManager> {
+        SHARED.get_or_init(|| Arc::new(NonceManager::new())).clone()
+    }
+
+    fn slot(&self, network: &str, address: &str) -> Arc<NonceSlot> {
+        self.slots
+            .entry((network.to_lowercase(), address.to_lowercase()))
+            .or_default()
+            .clone()
+    }
+
+    /// Allocate the next nonce for `address` on `network`.
+    ///
+    /// `chain_nonce` fetches the current pending nonce (from RPC); it is called
+    /// while the address is locked, so concurrent callers are served in turn.
+    pub async fn next_nonce<F, Fut>(&self, network: &str, address: &str, chain_nonce: F) -> Result<U256, String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<U256, String>>,
+    {
+        let slot = self.slot(network, address);
+        let mut next = slot.next.lock().await;
+        if slot.stale.swap(false, Ordering::SeqCst) {
+            *next = None;
+        }
+
+        let from_chain = chain_nonce().await?;
+        let mut nonce = match *next {
+            Some(cached) if cached > from_chain => cached,
+            _ => from_chain,
+        };
+        {
+            let mut in_flight = slot.in_flight.lock();
+            // Below the chain's pending nonce they were mined or replaced already
+            in_flight.retain(|held| *held >= from_chain);
+            while in_flight.contains(&nonce) {
+                nonce += U256::one();
+            }
+            in_flight.insert(nonce);
+        }
+        *next = Some(nonce + 1);
+
+        log::debug!("[NONCE] Allocated nonce {} for {} on {} (chain: {})", nonce, address, network, from_chain);
+        Ok(nonce)
+    }
+
+    /// Give back a nonce that was allocated but never used (e.g. signing failed).
+    /// Only the most recent allocation can be rolled back; otherwise the next
+    /// allocation resyncs from chain.
+    pub async fn release(&self, network: &str, address: &str, nonce: U256) {
+        let slot = self.slot(network, address);
+        let mut next = slot.next.lock().await;
+        slot.in_flight.lock().remove(&nonce);
+        if *next == Some(nonce + 1) {
+            *next = Some(nonce);
+        } else {
+            slot.stale.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// The transaction holding `nonce` was queued; from now on the queue
+    /// accounts for it
+    pub fn mark_queued(&self, network: &str, address: &str, nonce: U256) {
+        self.slot(network, address).in_flight.lock().remove(&nonce);
+    }
+
+    /// Forget the cached nonce; the next allocation starts from the chain.
+    /// Called when a transaction was dropped/replaced or the chain rejected its nonce.
+    pub fn resync(&self, network: &str, address: &str) {
+        log::info!("[NONCE] Resyncing nonce for {} on {} from chain", address, network);
+        self.slot(network, address).stale.store(true, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const NETWORK: &str = "base";
+    const ADDRESS: &str = "0xAbC0000000000000000000000000000000000001";
+
+    #[tokio::test]
+    async fn test_concurrent_allocations_get_distinct_nonces() {
+        let manager = Arc::new(NonceManager::new());
+        let handles: Vec<_> = (0..5)
+            .map(|_| {
+                let manager = manager.clone();
+                tokio::spawn(async move {
+                    manager
+                        .next_nonce(NETWORK, ADDRESS, || async {
+                            // Slow RPC: every caller sees the same pending nonce
+                            tokio::time::sleep(Duration::from_millis(5)).await;
+                            Ok(U256::from(7))
+                        })
+                        .await
+                        .unwrap()
+                })
+            })
+            .collect();
+
+        let mut nonces = Vec::new();
+        for handle in handles {
+            nonces.push(handle.await.unwrap().as_u64());
+        }
+        nonces.sort();
+        assert_eq!(nonces, vec![7, 8, 9, 10, 11]);
+    }
+
+    #[tokio::test]
+    async fn test_chain_ahead_of_cache_wins_and_resync_drops_cache() {
+        let manager = NonceManager::new();
+        let chain = |n: u64| move || async move { Ok(U256::from(n)) };
+
+        assert_eq!(manager.next_nonce(NETWORK, ADDRESS, chain(3)).await.unwrap(), U256::from(3));
+        // Sent from elsewhere: chain moved past the cache
+        assert_eq!(manager.next_nonce(NETWORK, ADDRESS, chain(10)).await.unwrap(), U256::from(10));
+        // Address matching is case-insensitive
+        assert_eq!(manager.next_nonce(NETWORK, &ADDRESS.to_lowercase(), chain(10)).await.unwrap(), U256::from(11));
+
+        // Nonce 11 was queued, then dropped; after a resync the chain's view is used again
+        manager.mark_queued(NETWORK, ADDRESS, U256::from(11));
+        manager.resync(NETWORK, ADDRESS);
+        assert_eq!(manager.next_nonce(NETWORK, ADDRESS, chain(11)).await.unwrap(), U256::from(11));
+        // Other networks are independent
+        assert_eq!(manager.next_nonce("mainnet", ADDRESS, chain(0)).await.unwrap(), U256::zero());
+    }
+
+    #[tokio::test]
+    async fn test_release_rolls_back_latest_allocation() {
+        let manager = NonceManager::new();
+        let chain = || async { Ok(U256::from(4)) };
+
+        let first = manager.next_nonce(NETWORK, ADDRESS, chain).await.unwrap();
+        let second = manager.next_nonce(NETWORK, ADDRESS, chain).await.unwrap();
+        manager.release(NETWORK, ADDRESS, second).await;
+        assert_eq!(manager.next_nonce(NETWORK, ADDRESS, chain).await.unwrap(), second);
+
+        // Releasing an older nonce can't be rolled back in place: resync instead
+        manager.release(NETWORK, ADDRESS, first).await;
+        assert_eq!(manager.next_nonce(NETWORK, ADDRESS, chain).await.unwrap(), first);
+    }
+
+    #[tokio::test]
+    async fn test_resync_skips_nonces_still_in_flight() {
+        let manager = NonceManager::new();
+        let chain = || async { Ok(U256::from(4)) };
+
+        // Nonce 4 is still being signed when another transaction forces a resync
+        let signing = manager.next_nonce(NETWORK, ADDRESS, chain).await.unwrap();
+        manager.resync(NETWORK, ADDRESS);
+        assert_eq!(manager.next_nonce(NETWORK, ADDRESS, chain).await.unwrap(), U256::from(5));
+
+        // Once queued, the queue accounts for it and it is no longer tracked here
+        manager.mark_queued(NETWORK, ADDRESS, signing);
+        manager.resync(NETWORK, ADDRESS);
+        assert_eq!(manager.next_nonce(NETWORK, ADDRESS, chain).await.unwrap(), signing);
+    }
+}
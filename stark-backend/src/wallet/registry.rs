@@ -0,0 +1,109 @@
+//! Wallet registry for per-channel / per-identity wallet selection
+//!
+//! Operators running multiple communities can give each channel its own wallet
+//! (separate treasuries). The registry resolves the wallet for a dispatch in
+//! order: channel wallet → identity wallet → default bot wallet.
+
+use dashmap::DashMap;
+use std::sync::Arc;
+
+use super::{EnvWalletProvider, WalletProvider};
+
+pub struct WalletRegistry {
+    default: Option<Arc<dyn WalletProvider>>,
+    channels: DashMap<i64, Arc<dyn WalletProvider>>,
+    identities: DashMap<String, Arc<dyn WalletProvider>>,
+}
+
+impl WalletRegistry {
+    pub fn new(default: Option<Arc<dyn WalletProvider>>) -> Self {
+        Self {
+            default,
+            channels: DashMap::new(),
+            identities: DashMap::new(),
+        }
+    }
+
+    /// The default bot wallet (used when nothing more specific is configured)
+    pub fn default_provider(&self) -> Option<Arc<dyn WalletProvider>> {
+        self.default.clone()
+    }
+
+    /// Assign a wallet to a channel
+    pub fn set_channel_wallet(&self, channel_id: i64, provider: Arc<dyn WalletProvider>) {
+        log::info!(
+            "[WALLET_REGISTRY] Channel {} uses wallet {} ({})",
+            channel_id,
+            provider.get_address(),
+            provider.mode_name()
+        );
+        self.channels.insert(channel_id, provider);
+    }
+
+    /// Remove a channel's wallet so it falls back to the default
+    pub fn remove_channel_wallet(&self, channel_id: i64) {
+        self.channels.remove(&channel_id);
+    }
+
+    /// Whether a channel has its own wallet
+    pub fn has_channel_wallet(&self, channel_id: i64) -> bool {
+        self.channels.contains_key(&channel_id)
+    }
+
+    /// Assign a wallet to an identity
+    pub fn set_identity_wallet(&self, identity_id: &str, provider: Arc<dyn WalletProvider>) {
+        self.identities.insert(identity_id.to_string(), provider);
+    }
+
+    /// Load a channel wallet from the private key held in `env_var`.
+    /// Keys are never stored in the database; channels only reference the variable name.
+    pub fn load_channel_wallet_from_env(&self, channel_id: i64, env_var: &str) -> Result<(), String> {
+        let private_key = std::env::var(env_var)
+            .map_err(|_| format!("Wallet key env var {} not set", env_var))?;
+        let provider = EnvWalletProvider::from_private_key(&private_key)?;
+        self.set_channel_wallet(channel_id, Arc::new(provider));
+        Ok(())
+    }
+
+    /// Resolve the wallet for a dispatch: channel → identity → default
+    pub fn resolve(&self, channel_id: i64, identity_id: Option<&str>) -> Option<Arc<dyn WalletProvider>> {
+        if let Some(provider) = self.channels.get(&channel_id) {
+            return Some(provider.clone());
+        }
+        if let Some(provider) = identity_id.and_then(|id| self.identities.get(id)) {
+            return Some(provider.clone());
+        }
+        self.default.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_A: &str = "0x0000000000000000000000000000000000000000000000000000000000000001";
+    const KEY_B: &str = "0x0000000000000000000000000000000000000000000000000000000000000002";
+
+    fn wallet(key: &str) -> Arc<dyn WalletProvider> {
+        Arc::new(EnvWalletProvider::from_private_key(key).unwrap())
+    }
+
+    #[test]
+    fn test_resolve_order() {
+        let default = wallet(KEY_A);
+        let registry = WalletRegistry::new(Some(default.clone()));
+        let channel_wallet = wallet(KEY_B);
+
+        assert_eq!(registry.resolve(1, Some("alice")).unwrap().get_address(), default.get_address());
+
+        registry.set_identity_wallet("alice", channel_wallet.clone());
+        assert_eq!(registry.resolve(1, Some("alice")).unwrap().get_address(), channel_wallet.get_address());
+        assert_eq!(registry.resolve(1, Some("bob")).unwrap().get_address(), default.get_address());
+
+        registry.set_channel_wallet(2, channel_wallet.clone());
+        assert_eq!(registry.resolve(2, None).unwrap().get_address(), channel_wallet.get_address());
+
+        registry.remove_channel_wallet(2);
+        assert_eq!(registry.resolve(2, None).unwrap().get_address(), default.get_address());
+    }
+}
@@ -7,7 +7,7 @@ use crate::tools::builtin::cryptocurrency::verify_intent::{self, TransactionInte
 use crate::tools::builtin::cryptocurrency::web3_tx::parse_u256;
 use crate::tools::rpc_config::{resolve_rpc_from_context, Network, ResolvedRpcConfig};
 use crate::tools::types::{ToolContext, ToolResult};
-use crate::tx_queue::QueuedTransaction;
+use crate::tx_queue::{QueuedTransaction, TxQueueManager};
 use crate::wallet::WalletProvider;
 use crate::x402::X402EvmRpc;
 use ethers::abi::{Abi, Function, ParamType, Token};
@@ -50,14 +50,18 @@ pub struct AbiFile {
     pub address: HashMap<String, String>,
 }
 
-/// Resolve the network from params, context, or default
-pub fn resolve_network(param_network: Option<&str>, context_network: Option<&str>) -> Result<Network, String> {
-    let network_str = param_network
-        .or(context_network)
-        .unwrap_or("base");
+/// Resolve the network from params, context, or default (Base).
+/// Only networks the RPC tools support are accepted.
+pub fn resolve_network(param_network: Option<&str>, context_network: Option<&Network>) -> Result<Network, String> {
+    let network = match param_network {
+        Some(name) => Network::from_str(name)?,
+        None => context_network.cloned().unwrap_or_default(),
+    };
 
-    Network::from_str(network_str)
-        .map_err(|_| format!("Invalid network '{}'. Must be one of: base, mainnet, polygon", network_str))
+    if !network.is_rpc_supported() {
+        return Err(format!("Unsupported network '{}'. Must be one of: base, mainnet, polygon", network));
+    }
+    Ok(network)
 }
 
 /// Determine abis directory -- always relative to the repo root
@@ -272,15 +276,13 @@ pub fn decode_return(function: &Function, data: &[u8]) -> Result<Value, String>
     }
 }
 
-/// Get chain ID for a network
+/// Get chain ID for a network name, defaulting to Base for unknown networks
 pub fn get_chain_id(network: &str) -> u64 {
-    match network {
-        "mainnet" => 1,
-        "polygon" => 137,
-        "arbitrum" => 42161,
-        "optimism" => 10,
-        _ => 8453, // Base
-    }
+    Network::from_str(network)
+        .ok()
+        .and_then(|n| n.chain_id())
+        .or(Network::Base.chain_id())
+        .unwrap_or(8453)
 }
 
 /// Execute a read-only call using WalletProvider
@@ -301,7 +303,8 @@ pub async fn call_function(
     rpc.call(to, &calldata).await
 }
 
-/// Sign a transaction for queuing using WalletProvider
+/// Sign a transaction for queuing using WalletProvider.
+/// The nonce is allocated through `tx_queue` so concurrent signers never collide.
 pub async fn sign_transaction_for_queue(
     network: &str,
     to: Address,
@@ -309,6 +312,7 @@ pub async fn sign_transaction_for_queue(
     value: U256,
     rpc_config: &ResolvedRpcConfig,
     wallet_provider: &Arc<dyn WalletProvider>,
+    tx_queue: &TxQueueManager,
 ) -> Result<SignedTxForQueue, String> {
     let rpc = X402EvmRpc::new_with_wallet_provider(
         wallet_provider.clone(),
@@ -323,13 +327,15 @@ pub async fn sign_transaction_for_queue(
         .map_err(|_| format!("Invalid wallet address: {}", from_str))?;
     let to_str = format!("{:?}", to);
 
-    let nonce = rpc.get_transaction_count(from_address).await?;
-
     let gas: U256 = rpc.estimate_gas(from_address, to, &calldata, value).await?;
     let gas = gas * U256::from(120) / U256::from(100); // 20% buffer
 
     let (max_fee, priority_fee) = rpc.estimate_eip1559_fees().await?;
 
+    let nonce = tx_queue
+        .allocate_nonce(network, &from_str, || rpc.get_transaction_count(from_address))
+        .await?;
+
     log::info!(
         "[web3_function_call] Signing tx for queue: to={:?}, value={}, data_len={} bytes, gas={}, nonce={} on {}",
         to, value, calldata.len(), gas, nonce, network
@@ -347,10 +353,13 @@ pub async fn sign_transaction_for_queue(
         .chain_id(chain_id);
 
     let typed_tx: TypedTransaction = tx.into();
-    let signature = wallet_provider
-        .sign_transaction(&typed_tx)
-        .await
-        .map_err(|e| format!("Failed to sign transaction: {}", e))?;
+    let signature = match wallet_provider.sign_transaction(&typed_tx).await {
+        Ok(signature) => signature,
+        Err(e) => {
+            tx_queue.release_nonce(network, &from_str, nonce.as_u64()).await;
+            return Err(format!("Failed to sign transaction: {}", e));
+        }
+    };
 
     let signed_tx = typed_tx.rlp_signed(&signature);
     let signed_tx_hex = format!("0x{}", hex::encode(&signed_tx));
@@ -567,6 +576,7 @@ pub async fn execute_resolved_call(
             tx_value,
             &rpc_config,
             wallet_provider,
+            tx_queue,
         ).await {
             Ok(signed) => {
                 // Verify intent before queueing
@@ -606,6 +616,7 @@ pub async fn execute_resolved_call(
                     ),
                 };
                 if let Err(reason) = verify_intent::verify_intent(&intent, context, None).await {
+                    tx_queue.release_nonce(&signed.network, &signed.from, signed.nonce).await;
                     return ToolResult::error(reason);
                 }
 
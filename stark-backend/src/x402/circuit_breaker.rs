@@ -0,0 +1,297 @@
+//! Circuit breaker for x402 AI endpoints
+//!
+//! When an x402 endpoint is down or the wallet can't pay, every dispatch would
+//! otherwise run the full retry ladder before failing. The breaker counts
+//! consecutive failures per endpoint and, once it trips, fails requests
+//! immediately for a cooldown window. After the cooldown a single probe request
+//! is let through (half-open) while the rest keep failing fast: a success
+//! closes the breaker, a failure re-opens it for another window.
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Consecutive failures that trip the breaker
+const FAILURE_THRESHOLD: u32 = 5;
+
+/// How long a tripped breaker short-circuits requests
+const COOLDOWN: Duration = Duration::from_secs(60);
+
+static X402_CIRCUIT_BREAKER: Lazy<CircuitBreaker> =
+    Lazy::new(|| CircuitBreaker::new(FAILURE_THRESHOLD, COOLDOWN));
+
+/// The breaker shared by all x402 AI clients
+pub fn circuit_breaker() -> &'static CircuitBreaker {
+    &X402_CIRCUIT_BREAKER
+}
+
+/// Whether an AI error counts against the endpoint: network failures (no
+/// status), payment failures (402) and server errors. Client errors such as
+/// an oversized request say nothing about the endpoint's health.
+pub fn counts_as_failure(status_code: Option<u16>) -> bool {
+    match status_code {
+        None => true,
+        Some(code) => code == 402 || code >= 500,
+    }
+}
+
+/// State of the breaker for one endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    /// Requests flow normally
+    Closed,
+    /// Requests are refused until the cooldown ends
+    Open,
+    /// Cooldown over; one probe request decides whether to close or re-open
+    HalfOpen,
+}
+
+/// Breaker state snapshot, as broadcast to the UI
+#[derive(Debug, Clone, Serialize)]
+pub struct BreakerStatus {
+    pub endpoint: String,
+    pub state: BreakerState,
+    pub consecutive_failures: u32,
+    /// Seconds until requests are let through again (open state only)
+    pub retry_in_secs: u64,
+}
+
+#[derive(Default)]
+struct EndpointHealth {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    /// When the half-open probe was let through, until its outcome is recorded
+    probe_started: Option<Instant>,
+}
+
+/// Per-endpoint consecutive-failure breaker
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    endpoints: Mutex<HashMap<String, EndpointHealth>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            endpoints: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Fail fast if the breaker for `endpoint` is open. When it is half-open the
+    /// first caller becomes the probe and everyone else fails fast until its
+    /// outcome is recorded; a caller that gets `Ok` must record one.
+    pub fn check(&self, endpoint: &str) -> Result<(), String> {
+        self.check_at(endpoint, Instant::now(), true)
+    }
+
+    /// Whether a request to `endpoint` could go through now, without claiming
+    /// the half-open probe (e.g. when choosing a model to build a client for)
+    pub fn is_available(&self, endpoint: &str) -> Result<(), String> {
+        self.check_at(endpoint, Instant::now(), false)
+    }
+
+    /// Record a successful request. Returns the new status if this closed the breaker.
+    pub fn record_success(&self, endpoint: &str) -> Option<BreakerStatus> {
+        let mut endpoints = self.endpoints.lock().unwrap();
+        let health = endpoints.remove(endpoint)?;
+        (health.consecutive_failures >= self.failure_threshold).then(|| BreakerStatus {
+            endpoint: endpoint.to_string(),
+            state: BreakerState::Closed,
+            consecutive_failures: 0,
+            retry_in_secs: 0,
+        })
+    }
+
+    /// Record a failed request. Returns the new status if this (re-)opened the breaker.
+    pub fn record_failure(&self, endpoint: &str) -> Option<BreakerStatus> {
+        self.record_failure_at(endpoint, Instant::now())
+    }
+
+    /// Record a request whose outcome says nothing about the endpoint's health
+    /// (see `counts_as_failure`), freeing the half-open probe for the next request
+    pub fn record_inconclusive(&self, endpoint: &str) {
+        if let Some(health) = self.endpoints.lock().unwrap().get_mut(endpoint) {
+            health.probe_started = None;
+        }
+    }
+
+    fn check_at(&self, endpoint: &str, now: Instant, claim_probe: bool) -> Result<(), String> {
+        let mut endpoints = self.endpoints.lock().unwrap();
+        let Some(health) = endpoints.get_mut(endpoint) else {
+            return Ok(());
+        };
+        match health.open_until {
+            Some(until) if until > now => Err(format!(
+                "x402 endpoint {} is temporarily unavailable ({} consecutive failures). Retrying in {}s.",
+                endpoint,
+                health.consecutive_failures,
+                (until - now).as_secs_f64().ceil() as u64
+            )),
+            Some(_) => {
+                // A probe that never reported back (e.g. its request was dropped)
+                // is given up on after a cooldown
+                let probing = health.probe_started.is_some_and(|started| started + self.cooldown > now);
+                if probing {
+                    return Err(format!(
+                        "x402 endpoint {} is recovering from {} consecutive failures; a trial request is in progress.",
+                        endpoint, health.consecutive_failures
+                    ));
+                }
+                if claim_probe {
+                    health.probe_started = Some(now);
+                }
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn record_failure_at(&self, endpoint: &str, now: Instant) -> Option<BreakerStatus> {
+        let mut endpoints = self.endpoints.lock().unwrap();
+        let health = endpoints.entry(endpoint.to_string()).or_default();
+        health.consecutive_failures += 1;
+        health.probe_started = None;
+        if health.consecutive_failures < self.failure_threshold {
+            return None;
+        }
+        // Already open (a request that was in flight when it tripped): nothing changes
+        if health.open_until.is_some_and(|until| until > now) {
+            return None;
+        }
+        health.open_until = Some(now + self.cooldown);
+        log::warn!(
+            "[X402] Circuit breaker open for {} after {} consecutive failures",
+            endpoint,
+            health.consecutive_failures
+        );
+        Some(BreakerStatus {
+            endpoint: endpoint.to_string(),
+            state: BreakerState::Open,
+            consecutive_failures: health.consecutive_failures,
+            retry_in_secs: self.cooldown.as_secs(),
+        })
+    }
+
+    /// Breaker state for an endpoint as of `now` (only the tests inspect it)
+    #[cfg(test)]
+    fn status_at(&self, endpoint: &str, now: Instant) -> BreakerStatus {
+        let endpoints = self.endpoints.lock().unwrap();
+        let (state, consecutive_failures, retry_in_secs) = match endpoints.get(endpoint) {
+            Some(health) => match health.open_until {
+                Some(until) if until > now => (
+                    BreakerState::Open,
+                    health.consecutive_failures,
+                    (until - now).as_secs_f64().ceil() as u64,
+                ),
+                Some(_) => (BreakerState::HalfOpen, health.consecutive_failures, 0),
+                None => (BreakerState::Closed, health.consecutive_failures, 0),
+            },
+            None => (BreakerState::Closed, 0, 0),
+        };
+        BreakerStatus {
+            endpoint: endpoint.to_string(),
+            state,
+            consecutive_failures,
+            retry_in_secs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENDPOINT: &str = "https://kimi.defirelay.com/api/v1/chat/completions";
+
+    #[test]
+    fn test_trips_after_threshold_and_short_circuits() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+        let now = Instant::now();
+
+        assert!(breaker.record_failure_at(ENDPOINT, now).is_none());
+        assert!(breaker.record_failure_at(ENDPOINT, now).is_none());
+        assert!(breaker.check_at(ENDPOINT, now, true).is_ok());
+
+        let tripped = breaker.record_failure_at(ENDPOINT, now).expect("third failure trips");
+        assert_eq!(tripped.state, BreakerState::Open);
+        assert_eq!(tripped.retry_in_secs, 60);
+
+        let err = breaker.check_at(ENDPOINT, now + Duration::from_secs(10), true).unwrap_err();
+        assert!(err.contains("temporarily unavailable"), "got: {}", err);
+        assert!(err.contains("Retrying in 50s"), "got: {}", err);
+
+        // Other endpoints are unaffected
+        assert!(breaker.check_at("https://other.defirelay.io/v1", now, true).is_ok());
+    }
+
+    #[test]
+    fn test_half_open_after_cooldown() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(30));
+        let now = Instant::now();
+        breaker.record_failure_at(ENDPOINT, now);
+        breaker.record_failure_at(ENDPOINT, now);
+
+        // Cooldown over: a single probe is let through
+        let later = now + Duration::from_secs(31);
+        assert_eq!(breaker.status_at(ENDPOINT, later).state, BreakerState::HalfOpen);
+        assert!(breaker.check_at(ENDPOINT, later, false).is_ok());
+        assert!(breaker.check_at(ENDPOINT, later, true).is_ok());
+        let err = breaker.check_at(ENDPOINT, later, true).unwrap_err();
+        assert!(err.contains("trial request is in progress"), "got: {}", err);
+        assert!(breaker.check_at(ENDPOINT, later, false).is_err());
+
+        // A failed trial re-opens it immediately
+        let reopened = breaker.record_failure_at(ENDPOINT, later).expect("re-opened");
+        assert_eq!(reopened.state, BreakerState::Open);
+        assert!(breaker.check_at(ENDPOINT, later, true).is_err());
+
+        // A success closes it and resets the count
+        let closed = breaker.record_success(ENDPOINT).expect("closed");
+        assert_eq!(closed.state, BreakerState::Closed);
+        assert!(breaker.check_at(ENDPOINT, later, true).is_ok());
+        assert!(breaker.check_at(ENDPOINT, later, true).is_ok());
+        assert_eq!(breaker.status_at(ENDPOINT, later).consecutive_failures, 0);
+    }
+
+    #[test]
+    fn test_probe_freed_when_inconclusive_or_abandoned() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(30));
+        let now = Instant::now();
+        breaker.record_failure_at(ENDPOINT, now);
+
+        let later = now + Duration::from_secs(31);
+        assert!(breaker.check_at(ENDPOINT, later, true).is_ok());
+        // The probe hit a client error: the next request may probe instead
+        breaker.record_inconclusive(ENDPOINT);
+        assert!(breaker.check_at(ENDPOINT, later, true).is_ok());
+        assert!(breaker.check_at(ENDPOINT, later, true).is_err());
+
+        // That probe never reports back; after a cooldown another one is allowed
+        assert!(breaker.check_at(ENDPOINT, later + Duration::from_secs(31), true).is_ok());
+    }
+
+    #[test]
+    fn test_success_resets_failure_count_without_event() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+        let now = Instant::now();
+        breaker.record_failure_at(ENDPOINT, now);
+        breaker.record_failure_at(ENDPOINT, now);
+        assert!(breaker.record_success(ENDPOINT).is_none(), "breaker never opened");
+        assert!(breaker.record_failure_at(ENDPOINT, now).is_none(), "count restarted");
+    }
+
+    #[test]
+    fn test_only_endpoint_health_errors_count() {
+        assert!(counts_as_failure(None));
+        assert!(counts_as_failure(Some(402)));
+        assert!(counts_as_failure(Some(503)));
+        assert!(!counts_as_failure(Some(400)));
+        assert!(!counts_as_failure(Some(429)));
+    }
+}
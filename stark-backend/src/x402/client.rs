@@ -10,8 +10,11 @@
 
 use reqwest::{header, Client, Response};
 use serde::Serialize;
-use std::collections::HashSet;
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use super::signer::X402Signer;
 use super::types::{PaymentRequired, X402PaymentInfo};
@@ -335,7 +338,8 @@ impl X402Client {
         )?;
 
         // Create payment info before signing
-        let payment_info = X402PaymentInfo::from_requirements(requirements);
+        let payment_info = X402PaymentInfo::from_requirements(requirements)
+            .with_payer(self.wallet_address());
 
         // Sign the payment using V2 format (required by Kimi/AI relay)
         let payment_payload = self.signer.sign_payment_v2(requirements).await?;
@@ -435,9 +439,94 @@ impl X402Client {
     }
 }
 
-/// Check if a URL is a defirelay endpoint that uses x402
+/// Hosts (and their subdomains) that serve x402-paid endpoints
+const X402_HOSTS: &[&str] = &["defirelay.com", "defirelay.io"];
+
+/// Check if a URL is a defirelay endpoint that uses x402.
+/// Only the parsed host counts, so the domain appearing in a path or query doesn't match.
 pub fn is_x402_endpoint(url: &str) -> bool {
-    url.contains("defirelay.com") || url.contains("defirelay.io")
+    let parsed = match url::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "https" | "http") => parsed,
+        _ => return false,
+    };
+    let host = match parsed.host_str() {
+        Some(host) => host.trim_end_matches('.').to_ascii_lowercase(),
+        None => return false,
+    };
+    X402_HOSTS
+        .iter()
+        .any(|allowed| host == *allowed || host.ends_with(&format!(".{}", allowed)))
+}
+
+/// How long a USDC balance check result is reused before hitting the RPC again
+const BALANCE_CACHE_TTL: Duration = Duration::from_secs(5);
+
+/// Upper bound on a single balance check RPC call
+const BALANCE_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+static USDC_BALANCE_CACHE: Lazy<BalanceCheckCache> =
+    Lazy::new(|| BalanceCheckCache::new(BALANCE_CACHE_TTL, BALANCE_CHECK_TIMEOUT));
+
+/// A balance check result and when it was made
+type BalanceCheckEntry = (Instant, Result<ethers::types::U256, String>);
+
+/// Short-lived cache of balance check results, keyed by wallet address.
+///
+/// Both successes and failures (including timeouts) are cached for the TTL so
+/// a burst of failed AI calls triggers at most one RPC call per wallet.
+pub struct BalanceCheckCache {
+    ttl: Duration,
+    timeout: Duration,
+    entries: Mutex<HashMap<String, BalanceCheckEntry>>,
+}
+
+impl BalanceCheckCache {
+    pub fn new(ttl: Duration, timeout: Duration) -> Self {
+        Self {
+            ttl,
+            timeout,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Return the cached result for `wallet_address` if still fresh, otherwise
+    /// run `fetch` (bounded by the timeout) and cache its result.
+    pub async fn get_or_fetch<F, Fut>(
+        &self,
+        wallet_address: &str,
+        fetch: F,
+    ) -> Result<ethers::types::U256, String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<ethers::types::U256, String>>,
+    {
+        let key = wallet_address.to_lowercase();
+        let fresh = self.entries.lock().unwrap().get(&key)
+            .filter(|(checked_at, _)| checked_at.elapsed() < self.ttl)
+            .map(|(_, result)| result.clone());
+        if let Some(result) = fresh {
+            return result;
+        }
+
+        let result = match tokio::time::timeout(self.timeout, fetch()).await {
+            Ok(result) => result,
+            Err(_) => Err(format!("Balance check timed out after {}s", self.timeout.as_secs())),
+        };
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (checked_at, _)| checked_at.elapsed() < self.ttl);
+        entries.insert(key, (Instant::now(), result.clone()));
+        result
+    }
+}
+
+/// Check USDC balance with a short timeout, reusing results from the last few
+/// seconds. Used on the x402 failure path so repeated failures don't each wait
+/// on a fresh RPC call.
+pub async fn check_usdc_balance_cached(wallet_address: &str) -> Result<ethers::types::U256, String> {
+    USDC_BALANCE_CACHE
+        .get_or_fetch(wallet_address, || check_usdc_balance(wallet_address))
+        .await
 }
 
 /// Check USDC balance on Base for a wallet address.
@@ -527,7 +616,8 @@ pub async fn sign_402_payment(
         &requirements.max_amount_required,
     )?;
 
-    let payment_info = X402PaymentInfo::from_requirements(requirements);
+    let payment_info = X402PaymentInfo::from_requirements(requirements)
+        .with_payer(wallet_provider.get_address());
 
     // Sign the payment
     let signer = X402Signer::new(wallet_provider.clone());
@@ -661,3 +751,67 @@ fn parse_url_parts(url: &str) -> (String, String, Option<String>) {
 
     (authority, path, query)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::U256;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_is_x402_endpoint_checks_host() {
+        assert!(is_x402_endpoint("https://inference.defirelay.com/api/v1/chat/completions"));
+        assert!(is_x402_endpoint("https://defirelay.io/v1"));
+        assert!(is_x402_endpoint("https://API.DefiRelay.com./v1"));
+
+        assert!(!is_x402_endpoint("https://evil.example/defirelay.com"));
+        assert!(!is_x402_endpoint("https://evil.example/?next=defirelay.io"));
+        assert!(!is_x402_endpoint("https://defirelay.com.evil.example/v1"));
+        assert!(!is_x402_endpoint("https://notdefirelay.com/v1"));
+        assert!(!is_x402_endpoint("ftp://defirelay.com/v1"));
+        assert!(!is_x402_endpoint("defirelay.com"));
+    }
+
+    #[tokio::test]
+    async fn test_balance_cache_reuses_result_within_ttl() {
+        let cache = BalanceCheckCache::new(Duration::from_secs(5), Duration::from_secs(1));
+        let calls = AtomicUsize::new(0);
+
+        for _ in 0..3 {
+            let balance = cache
+                .get_or_fetch("0xABC", || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Ok(U256::from(42u64))
+                })
+                .await;
+            assert_eq!(balance, Ok(U256::from(42u64)));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1, "RPC should run once within the TTL");
+
+        // Lookups are case-insensitive on the address
+        let _ = cache
+            .get_or_fetch("0xabc", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(U256::zero())
+            })
+            .await;
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_balance_cache_expires_and_times_out() {
+        let cache = BalanceCheckCache::new(Duration::from_millis(0), Duration::from_millis(20));
+
+        let result = cache
+            .get_or_fetch("0xabc", || async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(U256::from(1u64))
+            })
+            .await;
+        assert!(result.unwrap_err().contains("timed out"));
+
+        // Zero TTL: the next call fetches again
+        let result = cache.get_or_fetch("0xabc", || async { Ok(U256::from(7u64)) }).await;
+        assert_eq!(result, Ok(U256::from(7u64)));
+    }
+}
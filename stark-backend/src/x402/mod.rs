@@ -15,10 +15,12 @@ mod types;
 mod client;
 mod signer;
 mod evm_rpc;
+mod circuit_breaker;
 pub mod erc20;
 pub mod payment_limits;
 
 pub use types::*;
-pub use client::{X402Client, X402Response, X402RetryResult, is_x402_endpoint, sign_402_payment, retry_with_x402_payment, check_usdc_balance};
+pub use client::{X402Client, is_x402_endpoint, retry_with_x402_payment, check_usdc_balance_cached};
 pub use signer::X402Signer;
 pub use evm_rpc::{TxLog, X402EvmRpc};
+pub use circuit_breaker::{circuit_breaker, counts_as_failure, BreakerStatus};
@@ -256,6 +256,9 @@ pub struct X402PaymentInfo {
     pub status: PaymentStatus,
     /// Timestamp of payment
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Wallet address that paid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payer: Option<String>,
 }
 
 impl X402PaymentInfo {
@@ -273,9 +276,16 @@ impl X402PaymentInfo {
             tx_hash: None,
             status: PaymentStatus::Pending,
             timestamp: chrono::Utc::now(),
+            payer: None,
         }
     }
 
+    /// Record which wallet made the payment
+    pub fn with_payer(mut self, payer: String) -> Self {
+        self.payer = Some(payer);
+        self
+    }
+
     /// Set transaction hash and mark as confirmed
     pub fn with_tx_hash(mut self, tx_hash: String) -> Self {
         self.tx_hash = Some(tx_hash);
@@ -210,6 +210,7 @@ export default function ChatMessage({ role, content, timestamp, subagentLabel }:
   // Detect success/failure for tool results
   const isToolSuccess = isToolMessage && (content.includes('✅') || content.includes('Success'));
   const isToolError = isToolMessage && (content.includes('❌') || content.includes('Failed') || content.includes('Error'));
+  const isToolWarning = isToolMessage && content.includes('Success with warnings');
   const isToolCall = role === 'tool_call' || (isToolMessage && content.includes('Tool Call'));
 
   const roleStyles: Record<MessageRole, string> = {
@@ -228,6 +229,7 @@ export default function ChatMessage({ role, content, timestamp, subagentLabel }:
   // Determine border color for tool messages
   const getToolBorderColor = () => {
     if (isToolError) return 'border-l-red-500';
+    if (isToolWarning) return 'border-l-amber-400';
     if (isToolSuccess) return 'border-l-green-500';
     if (isToolCall) return 'border-l-amber-500';
     return 'border-l-slate-500';
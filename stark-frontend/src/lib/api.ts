@@ -300,6 +300,10 @@ export async function getSession(id: number): Promise<{
   message_count?: number;
   initial_query?: string;
   safe_mode?: boolean;
+  failure_reason?: {
+    category: 'ai_error' | 'max_iterations' | 'context_overflow' | 'client_creation' | 'time_limit' | 'budget' | 'stale' | 'internal';
+    message: string;
+  };
 }> {
   return apiFetch(`/sessions/${id}`);
 }
@@ -981,6 +985,10 @@ export interface CronJobInfo {
   thinking_level?: string;
   timeout_seconds?: number;
   delete_after_run: boolean;
+  max_retries?: number;
+  retry_delay_seconds?: number;
+  retry_attempt: number;
+  retry_of_run_id?: number;
   status: string;
   last_run_at?: string;
   next_run_at?: string;
@@ -1024,6 +1032,8 @@ export async function createCronJob(data: {
   thinking_level?: string;
   timeout_seconds?: number;
   delete_after_run?: boolean;
+  max_retries?: number;
+  retry_delay_seconds?: number;
 }): Promise<CronJobInfo> {
   const response = await apiFetch<CronJobResponse>('/cron/jobs', {
     method: 'POST',
@@ -1051,6 +1061,8 @@ export async function updateCronJob(id: number, data: Partial<{
   thinking_level: string;
   timeout_seconds: number;
   delete_after_run: boolean;
+  max_retries: number;
+  retry_delay_seconds: number;
   status: string;
 }>): Promise<CronJobInfo> {
   const response = await apiFetch<CronJobResponse>(`/cron/jobs/${id}`, {
@@ -1110,6 +1122,8 @@ export interface CronJobRunInfo {
   response?: string;
   error?: string;
   duration_ms?: number;
+  retry_of?: number;
+  attempt: number;
 }
 
 export async function getCronJobRuns(id: number, limit?: number): Promise<CronJobRunInfo[]> {
@@ -1174,6 +1188,13 @@ export async function pulseHeartbeatOnce(): Promise<HeartbeatConfigInfo> {
 }
 
 // Bot Settings API
+export interface ToolTimeoutSettings {
+  default_secs?: number;
+  overrides: Record<string, number>;
+  session_secs?: number;
+  subtype_session_secs?: Record<string, number>;
+}
+
 export interface BotSettings {
   id: number;
   bot_name: string;
@@ -1190,6 +1211,12 @@ export interface BotSettings {
   theme_accent?: string;
   proxy_url?: string;
   kanban_auto_execute: boolean;
+  loop_max_repeated_calls: number;
+  loop_signature_history: number;
+  tool_timeouts?: ToolTimeoutSettings;
+  rate_limit_per_minute: number;
+  rate_limit_burst: number;
+  max_session_usdc: number;
   created_at: string;
   updated_at: string;
 }
@@ -1213,6 +1240,12 @@ export async function updateBotSettings(data: {
   theme_accent?: string;
   proxy_url?: string;
   kanban_auto_execute?: boolean;
+  loop_max_repeated_calls?: number;
+  loop_signature_history?: number;
+  tool_timeouts?: ToolTimeoutSettings;
+  rate_limit_per_minute?: number;
+  rate_limit_burst?: number;
+  max_session_usdc?: number;
 }): Promise<BotSettings> {
   return apiFetch('/bot-settings', {
     method: 'PUT',
@@ -1428,6 +1461,7 @@ export interface SessionMessage {
   role: string;
   content: string;
   created_at: string;
+  pinned?: boolean;
 }
 
 export interface SessionTranscriptResponse {
@@ -1441,6 +1475,23 @@ export async function getSessionTranscript(sessionId: number, limit?: number): P
   return apiFetch(`/sessions/${sessionId}/transcript${query}`);
 }
 
+// Pinned messages are kept verbatim when the session is compacted
+export async function pinSessionMessage(sessionId: number, messageId: number): Promise<{
+  success: boolean;
+  message_id: number;
+  pinned: boolean;
+}> {
+  return apiFetch(`/sessions/${sessionId}/messages/${messageId}/pin`, { method: 'POST' });
+}
+
+export async function unpinSessionMessage(sessionId: number, messageId: number): Promise<{
+  success: boolean;
+  message_id: number;
+  pinned: boolean;
+}> {
+  return apiFetch(`/sessions/${sessionId}/messages/${messageId}/pin`, { method: 'DELETE' });
+}
+
 // Intrinsic Files API
 export interface IntrinsicFileInfo {
   name: string;
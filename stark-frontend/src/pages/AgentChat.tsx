@@ -305,6 +305,37 @@ export default function AgentChat() {
     scrollToBottom();
   }, [messages, scrollToBottom]);
 
+  // Render streamed response text as it arrives (agent.token_delta)
+  const streamingMessageIdRef = useRef<string | null>(null);
+  useEffect(() => {
+    const handleTokenDelta = (data: unknown) => {
+      if (!isWebChannelEvent(data)) return;
+
+      const event = data as { delta: string };
+      setMessages((prev) => {
+        const streamingId = streamingMessageIdRef.current;
+        const existing = streamingId ? prev.find((m) => m.id === streamingId) : undefined;
+        if (existing) {
+          return prev.map((m) => (m.id === streamingId ? { ...m, content: m.content + event.delta } : m));
+        }
+        const id = crypto.randomUUID();
+        streamingMessageIdRef.current = id;
+        return [...prev, {
+          id,
+          role: 'assistant' as MessageRole,
+          content: event.delta,
+          timestamp: new Date(),
+          sessionId,
+        }];
+      });
+    };
+
+    on('agent.token_delta', handleTokenDelta);
+    return () => {
+      off('agent.token_delta', handleTokenDelta);
+    };
+  }, [on, off, sessionId]);
+
   // Listen for real-time tool call events from the agent
   useEffect(() => {
     console.log('[AgentChat] Registering agent.tool_call listener');
@@ -342,7 +373,7 @@ export default function AgentChat() {
       if (!isWebChannelEvent(data)) return;
 
       console.log('[AgentChat] Received tool.result event:', data);
-      const event = data as { tool_name: string; success: boolean; duration_ms: number; content: string };
+      const event = data as { tool_name: string; success: boolean; duration_ms: number; content: string; warnings?: string[] };
 
       // Show say_to_user messages immediately as assistant bubbles
       if (event.tool_name === 'say_to_user') {
@@ -359,13 +390,18 @@ export default function AgentChat() {
         return;
       }
 
-      const statusEmoji = event.success ? '✅' : '❌';
-      const statusText = event.success ? 'Success' : 'Failed';
+      const warnings = event.warnings ?? [];
+      const hasWarnings = event.success && warnings.length > 0;
+      const statusEmoji = event.success ? (hasWarnings ? '⚠️' : '✅') : '❌';
+      const statusText = event.success ? (hasWarnings ? 'Success with warnings' : 'Success') : 'Failed';
 
       // Show full content - no truncation for visibility
       let displayContent = event.content;
 
-      const content = `${statusEmoji} **Tool Result:** \`${event.tool_name}\` - ${statusText} (${event.duration_ms}ms)\n\`\`\`\n${displayContent}\n\`\`\``;
+      let content = `${statusEmoji} **Tool Result:** \`${event.tool_name}\` - ${statusText} (${event.duration_ms}ms)\n\`\`\`\n${displayContent}\n\`\`\``;
+      if (hasWarnings) {
+        content += `\n${warnings.map((w) => `> ⚠️ ${w}`).join('\n')}`;
+      }
 
       const message: ChatMessageType = {
         id: crypto.randomUUID(),
@@ -645,6 +681,29 @@ export default function AgentChat() {
       });
     };
 
+    const handleAiModelFallback = (data: unknown) => {
+      // Filter out events from other channels/sessions
+      if (!isCurrentSessionEvent(data, dbSessionId)) return;
+
+      const event = data as {
+        from_model: string;
+        to_model: string;
+        error: string;
+        timestamp: string;
+      };
+      console.warn('[AI] Falling back:', event.from_model, '->', event.to_model);
+      setMessages((prev) => [
+        ...prev,
+        {
+          id: crypto.randomUUID(),
+          role: 'system' as MessageRole,
+          content: `↪️ ${event.from_model} failed, switched to fallback model ${event.to_model}: ${event.error}`,
+          timestamp: new Date(event.timestamp),
+          sessionId,
+        },
+      ]);
+    };
+
     const handleContextCompacting = (data: unknown) => {
       // Filter out events from other sessions
       const event = data as {
@@ -678,6 +737,7 @@ export default function AgentChat() {
     on('agent.error', handleError);
     on('agent.warning', handleWarning);
     on('ai.retrying', handleAiRetrying);
+    on('ai.model_fallback', handleAiModelFallback);
     on('context.compacting', handleContextCompacting);
 
     return () => {
@@ -685,6 +745,7 @@ export default function AgentChat() {
       off('agent.error', handleError);
       off('agent.warning', handleWarning);
       off('ai.retrying', handleAiRetrying);
+      off('ai.model_fallback', handleAiModelFallback);
       off('context.compacting', handleContextCompacting);
     };
   }, [on, off, sessionId, dbSessionId]);
@@ -1483,6 +1544,7 @@ export default function AgentChat() {
     // Regular message
     addMessage('user', trimmedInput);
     setIsLoading(true);
+    streamingMessageIdRef.current = null;
 
     try {
       const response = await sendChatMessage(trimmedInput, conversationHistory.current, currentNetwork?.name);
@@ -1,5 +1,5 @@
 import { useState, useEffect } from 'react';
-import { MessageSquare, Hash, Plus, Play, Square, Trash2, Save, Pencil, Twitter, AlertTriangle, Terminal, Dices, Copy, Check } from 'lucide-react';
+import { MessageSquare, Hash, Plus, Play, Square, Trash2, Save, Pencil, Twitter, AlertTriangle, Terminal, Dices, Copy, Check, Webhook } from 'lucide-react';
 import Card, { CardContent, CardHeader, CardTitle } from '@/components/ui/Card';
 import Button from '@/components/ui/Button';
 import Input from '@/components/ui/Input';
@@ -26,6 +26,7 @@ const CHANNEL_TYPES = [
   { value: 'discord', label: 'Discord', icon: MessageSquare, color: 'indigo' },
   { value: 'twitter', label: 'Twitter / X', icon: Twitter, color: 'sky' },
   { value: 'external_channel', label: 'External Channel', icon: Terminal, color: 'emerald' },
+  { value: 'webhook', label: 'Webhook', icon: Webhook, color: 'amber' },
 ];
 
 function getChannelHints(channelType: string): string[] {
@@ -51,6 +52,11 @@ function getChannelHints(channelType: string): string[] {
         'Generate a secure API Token in settings after creation. The token authenticates external clients.',
         'Safe mode is off by default — enable it in settings to restrict tool access for untrusted input.',
       ];
+    case 'webhook':
+      return [
+        'Agent responses are POSTed as JSON to the Webhook URL. Send messages in by POSTing {"text", "user", "chat_id"} to /api/channels/{id}/webhook.',
+        'Inbound requests must carry the current Unix time in X-Stark-Timestamp and an X-Stark-Signature: sha256=&lt;hex&gt; HMAC of "&lt;timestamp&gt;.&lt;body&gt;" using the Signing Secret. Requests older than 5 minutes are rejected.',
+      ];
     default:
       return [];
   }
@@ -572,7 +578,7 @@ export default function Channels() {
                           </select>
                           <p className="mt-1 text-xs text-slate-500">{setting.description}</p>
                         </>
-                      ) : setting.key === 'external_channel_api_token' ? (
+                      ) : (setting.key === 'external_channel_api_token' || setting.key === 'webhook_secret') ? (
                         <TokenInput
                           value={newChannel.settings[setting.key] || ''}
                           onChange={(value) =>
@@ -772,7 +778,7 @@ export default function Channels() {
                                       </select>
                                       <p className="mt-1 text-xs text-slate-500">{setting.description}</p>
                                     </>
-                                  ) : setting.key === 'external_channel_api_token' ? (
+                                  ) : (setting.key === 'external_channel_api_token' || setting.key === 'webhook_secret') ? (
                                     <TokenInput
                                       value={editForm.settings[setting.key] || ''}
                                       onChange={(value) =>
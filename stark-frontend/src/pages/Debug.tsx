@@ -107,6 +107,8 @@ function summarize(event: string, data: Record<string, unknown>): string {
         return `Done — ${JSON.stringify(data.total_metrics || {})}`;
       case 'ai.retrying':
         return `Attempt ${data.attempt}/${data.max_attempts} (${data.provider || '?'}) — ${truncate(String(data.error || ''), 100)}`;
+      case 'ai.model_fallback':
+        return `${data.from_model || '?'} → ${data.to_model || '?'} — ${truncate(String(data.error || ''), 100)}`;
       case 'tx.pending':
         return `${data.network || '?'} tx ${truncate(String(data.tx_hash || ''), 20)}`;
       case 'tx.confirmed':
@@ -231,7 +233,7 @@ export default function Debug() {
   };
 
   const isError = (event: string) => event.includes('error');
-  const isWarning = (event: string) => event.includes('warning') || event === 'ai.retrying';
+  const isWarning = (event: string) => event.includes('warning') || event === 'ai.retrying' || event === 'ai.model_fallback';
 
   const filteredLogs = logs.filter(log => {
     if (!showNoise && NOISE_EVENTS.has(log.event)) return false;
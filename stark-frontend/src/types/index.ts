@@ -68,6 +68,34 @@ export interface X402PaymentEvent {
   timestamp: string;
 }
 
+// x402 endpoint circuit breaker event (open = endpoint degraded)
+export interface X402CircuitBreakerEvent {
+  channel_id: number;
+  endpoint: string;
+  state: 'closed' | 'open' | 'half_open';
+  consecutive_failures: number;
+  retry_in_secs: number;
+  timestamp: string;
+}
+
+// Running x402 spend for a session (max_session_usdc is null when there is no budget)
+export interface X402SessionSpendEvent {
+  channel_id: number;
+  session_id: number;
+  spent_usdc: number;
+  max_session_usdc: number | null;
+  timestamp: string;
+}
+
+// The session's x402 budget would be exceeded; the agent loop was halted
+export interface X402BudgetExceededEvent {
+  channel_id: number;
+  session_id: number;
+  spent_usdc: number;
+  max_session_usdc: number;
+  timestamp: string;
+}
+
 // Transaction events
 export interface TxPendingEvent {
   channel_id: number;